@@ -0,0 +1,246 @@
+//! Account secret lifecycle.
+//!
+//! The account secret is the witness behind the `hashed_account_secret` public input: the
+//! Move contract stores `Poseidon1(account_secret)` in the account object and the circuit
+//! proves knowledge of its preimage. A zero secret disables that check in the circuit, so it
+//! is never generated or accepted here.
+//!
+//! Secrets are handed to the host either as decimal strings or sealed with a host key
+//! (ChaCha20-Poly1305) for storage outside the platform keystore.
+
+use crate::field::{fr_from_bytes_le, fr_to_bytes_le, parse_fr, FieldParseMode, FR_BYTES};
+use crate::poseidon_opt::hash1;
+use crate::relayer::normalize_address;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, UniformRand, Zero};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Move function that registers a new account: `vortex_account::new(hashed_secret: u256)`
+pub const ACCOUNT_REGISTRATION_FUNCTION: &str = "vortex_account::new";
+
+/// Sealed secret format version, also the AEAD associated data
+pub const SEALED_SECRET_VERSION: u8 = 1;
+
+/// An account secret; wiped on drop
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct AccountSecret(Fr);
+
+impl std::fmt::Debug for AccountSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AccountSecret(<redacted>)")
+    }
+}
+
+impl AccountSecret {
+    /// Draws a uniformly random non-zero secret
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        loop {
+            let secret = Fr::rand(rng);
+            if !secret.is_zero() {
+                return Self(secret);
+            }
+        }
+    }
+
+    /// Parses a decimal or `0x` hex secret, rejecting zero and non-canonical values
+    pub fn parse(s: &str) -> Result<Self> {
+        Self::from_fr(parse_fr(s, FieldParseMode::Strict).context("Invalid account secret")?)
+    }
+
+    pub fn from_fr(secret: Fr) -> Result<Self> {
+        if secret.is_zero() {
+            return Err(anyhow!("Account secret must be non-zero"));
+        }
+        Ok(Self(secret))
+    }
+
+    pub fn as_fr(&self) -> &Fr {
+        &self.0
+    }
+
+    /// Decimal form, as used by `ProofInput::account_secret`
+    pub fn to_decimal(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.0.into_bigint().to_string())
+    }
+
+    /// `Poseidon1(account_secret)`, the `hashed_account_secret` public input
+    pub fn hashed(&self) -> Fr {
+        hash1(&self.0)
+    }
+
+    /// Encrypts the secret under a 32-byte host key, returning hex of
+    /// `version || nonce || ciphertext`
+    pub fn seal<R: RngCore + CryptoRng>(&self, key: &[u8], rng: &mut R) -> Result<String> {
+        let cipher = sealing_cipher(key)?;
+        let mut nonce = [0u8; 12];
+        rng.fill_bytes(&mut nonce);
+
+        let plaintext = Zeroizing::new(fr_to_bytes_le(&self.0));
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_slice(),
+                    aad: &[SEALED_SECRET_VERSION],
+                },
+            )
+            .map_err(|_| anyhow!("Failed to seal account secret"))?;
+
+        let mut sealed = vec![SEALED_SECRET_VERSION];
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(hex::encode(sealed))
+    }
+
+    /// Opens a secret produced by `seal`
+    pub fn unseal(sealed_hex: &str, key: &[u8]) -> Result<Self> {
+        let sealed = hex::decode(sealed_hex.trim()).context("Invalid sealed secret hex")?;
+        if sealed.len() < 13 {
+            return Err(anyhow!("Sealed secret is too short"));
+        }
+        if sealed[0] != SEALED_SECRET_VERSION {
+            return Err(anyhow!("Unsupported sealed secret version {}", sealed[0]));
+        }
+
+        let plaintext = Zeroizing::new(
+            sealing_cipher(key)?
+                .decrypt(
+                    Nonce::from_slice(&sealed[1..13]),
+                    Payload {
+                        msg: &sealed[13..],
+                        aad: &[sealed[0]],
+                    },
+                )
+                .map_err(|_| anyhow!("Sealed secret failed authentication"))?,
+        );
+        if plaintext.len() != FR_BYTES {
+            return Err(anyhow!("Sealed secret has the wrong length"));
+        }
+
+        let secret = fr_from_bytes_le(&plaintext)
+            .context("Sealed secret is not a canonical field element")?;
+        Self::from_fr(secret)
+    }
+}
+
+/// Move call that registers an account for a secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRegistration {
+    /// Fully qualified Move function, `<package>::vortex_account::new`
+    pub move_target: String,
+    /// Decimal `u256` argument
+    pub hashed_account_secret: String,
+}
+
+/// Result of replacing an account secret
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRotation {
+    /// New secret, decimal
+    pub account_secret: String,
+    pub previous_hashed_account_secret: String,
+    /// Registration of the account for the new secret
+    pub registration: AccountRegistration,
+}
+
+impl std::fmt::Debug for AccountRotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccountRotation")
+            .field("account_secret", &"<redacted>")
+            .field(
+                "previous_hashed_account_secret",
+                &self.previous_hashed_account_secret,
+            )
+            .field("registration", &self.registration)
+            .finish()
+    }
+}
+
+/// Builds the registration call for `secret` in the Vortex package `package_id`
+pub fn registration_payload(
+    package_id: &str,
+    secret: &AccountSecret,
+) -> Result<AccountRegistration> {
+    let package_id = normalize_address(package_id).context("Invalid package id")?;
+    Ok(AccountRegistration {
+        move_target: format!("{}::{}", package_id, ACCOUNT_REGISTRATION_FUNCTION),
+        hashed_account_secret: secret.hashed().into_bigint().to_string(),
+    })
+}
+
+/// Generates a replacement for `previous` and the registration for it.
+///
+/// Notes already sent to the previous account still need the previous secret to spend, so
+/// hosts keep it until those notes have been moved.
+pub fn rotate<R: RngCore + CryptoRng>(
+    previous: &AccountSecret,
+    package_id: &str,
+    rng: &mut R,
+) -> Result<(AccountSecret, AccountRotation)> {
+    let secret = AccountSecret::generate(rng);
+    let rotation = AccountRotation {
+        account_secret: secret.to_decimal().to_string(),
+        previous_hashed_account_secret: previous.hashed().into_bigint().to_string(),
+        registration: registration_payload(package_id, &secret)?,
+    };
+    Ok((secret, rotation))
+}
+
+fn sealing_cipher(key: &[u8]) -> Result<ChaCha20Poly1305> {
+    if key.len() != 32 {
+        return Err(anyhow!("Sealing key must be 32 bytes, got {}", key.len()));
+    }
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_secret_parsing_and_hashing() {
+        assert!(AccountSecret::parse("0").is_err());
+        let secret = AccountSecret::parse("12345").unwrap();
+        assert_eq!(secret.hashed(), hash1(&Fr::from(12345u64)));
+        assert_eq!(secret.to_decimal().as_str(), "12345");
+        assert_eq!(format!("{:?}", secret), "AccountSecret(<redacted>)");
+
+        let registration = registration_payload("0x2", &secret).unwrap();
+        assert!(registration
+            .move_target
+            .ends_with("02::vortex_account::new"));
+        assert_eq!(
+            registration.hashed_account_secret,
+            secret.hashed().into_bigint().to_string()
+        );
+    }
+
+    #[test]
+    fn test_seal_round_trip_and_rotation() {
+        let mut rng = ChaCha20Rng::from_seed([5u8; 32]);
+        let secret = AccountSecret::generate(&mut rng);
+        let key = [9u8; 32];
+
+        let sealed = secret.seal(&key, &mut rng).unwrap();
+        assert_eq!(AccountSecret::unseal(&sealed, &key).unwrap(), secret);
+        assert!(AccountSecret::unseal(&sealed, &[8u8; 32]).is_err());
+
+        let (next, rotation) = rotate(&secret, "0x2", &mut rng).unwrap();
+        assert_ne!(next, secret);
+        assert_eq!(rotation.account_secret, *next.to_decimal());
+        assert_eq!(
+            rotation.previous_hashed_account_secret,
+            secret.hashed().into_bigint().to_string()
+        );
+    }
+}
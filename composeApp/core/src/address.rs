@@ -0,0 +1,137 @@
+//! Shielded payment addresses.
+//!
+//! An address bundles what a sender needs to pay a recipient: the note public key that goes
+//! into the output commitment and the X25519 key the note is encrypted to. It is encoded as
+//! bech32m with human-readable part `vortex` over
+//! `version (1) || note_public_key (32, LE) || viewing_public_key (32)`.
+
+use crate::field::{fr_from_bytes_le, fr_to_bytes_le};
+use crate::viewing::FullViewingKey;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Bech32m, Hrp};
+
+/// Human-readable part of shielded addresses
+pub const ADDRESS_HRP: &str = "vortex";
+
+/// Payload format version
+pub const ADDRESS_VERSION: u8 = 0;
+
+const PAYLOAD_LEN: usize = 1 + 32 + 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShieldedAddress {
+    /// `Poseidon1(spending_key)`, the `out_public_key` of notes paid to this address
+    pub note_public_key: Fr,
+    /// Incoming viewing public key notes are encrypted to
+    pub viewing_public_key: [u8; 32],
+}
+
+impl ShieldedAddress {
+    pub fn from_viewing_key(keys: &FullViewingKey) -> Self {
+        Self {
+            note_public_key: keys.spend_public_key,
+            viewing_public_key: keys.incoming.public_key(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut payload = Vec::with_capacity(PAYLOAD_LEN);
+        payload.push(ADDRESS_VERSION);
+        payload.extend_from_slice(&fr_to_bytes_le(&self.note_public_key));
+        payload.extend_from_slice(&self.viewing_public_key);
+
+        bech32::encode::<Bech32m>(address_hrp(), &payload)
+            .expect("shielded address payload fits the bech32m code length")
+    }
+
+    /// Parses an address, rejecting other checksums, prefixes, versions and
+    /// non-canonical keys
+    pub fn decode(address: &str) -> Result<Self> {
+        let parsed = CheckedHrpstring::new::<Bech32m>(address.trim())
+            .map_err(|e| anyhow!("Invalid shielded address: {}", e))?;
+        if parsed.hrp() != address_hrp() {
+            return Err(anyhow!(
+                "Shielded address must start with '{}1', got '{}'",
+                ADDRESS_HRP,
+                parsed.hrp()
+            ));
+        }
+
+        let payload: Vec<u8> = parsed.byte_iter().collect();
+        if payload.len() != PAYLOAD_LEN {
+            return Err(anyhow!(
+                "Shielded address payload must be {} bytes, got {}",
+                PAYLOAD_LEN,
+                payload.len()
+            ));
+        }
+        if payload[0] != ADDRESS_VERSION {
+            return Err(anyhow!(
+                "Unsupported shielded address version {}",
+                payload[0]
+            ));
+        }
+
+        let note_public_key = fr_from_bytes_le(&payload[1..33])
+            .context("Note public key is not a canonical field element")?;
+
+        Ok(Self {
+            note_public_key,
+            viewing_public_key: payload[33..].try_into().unwrap(),
+        })
+    }
+
+    pub fn is_valid(address: &str) -> bool {
+        Self::decode(address).is_ok()
+    }
+}
+
+fn address_hrp() -> Hrp {
+    Hrp::parse_unchecked(ADDRESS_HRP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewing::SpendingKeys;
+
+    #[test]
+    fn test_address_round_trip() {
+        let keys = SpendingKeys::from_seed(&[4u8; 32]).unwrap();
+        let address = ShieldedAddress::from_viewing_key(&keys.viewing);
+        let encoded = address.encode();
+
+        assert!(encoded.starts_with("vortex1"));
+        assert_eq!(ShieldedAddress::decode(&encoded).unwrap(), address);
+        assert_eq!(
+            ShieldedAddress::decode(&encoded.to_uppercase()).unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn test_address_rejects_corruption_and_foreign_formats() {
+        let address = ShieldedAddress {
+            note_public_key: Fr::from(42u64),
+            viewing_public_key: [7u8; 32],
+        };
+        let encoded = address.encode();
+
+        let mut corrupted = encoded.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        assert!(!ShieldedAddress::is_valid(
+            std::str::from_utf8(&corrupted).unwrap()
+        ));
+
+        let other_hrp =
+            bech32::encode::<Bech32m>(Hrp::parse_unchecked("other"), &[0u8; PAYLOAD_LEN]).unwrap();
+        assert!(!ShieldedAddress::is_valid(&other_hrp));
+
+        let bech32_checksum =
+            bech32::encode::<bech32::Bech32>(address_hrp(), &[0u8; PAYLOAD_LEN]).unwrap();
+        assert!(!ShieldedAddress::is_valid(&bech32_checksum));
+    }
+}
@@ -0,0 +1,634 @@
+//! Aggregation of many Groth16 proofs into one logarithmic-size argument (SnarkPack).
+//!
+//! Relayers and batch submitters pay for every pairing the pool runs. An aggregate proves
+//! that `n` proofs under one verifying key all hold, and is checked with `O(log n)`
+//! pairings plus one multi-scalar multiplication over the public inputs. It follows
+//! SnarkPack (Gailly, Maller, Nitulescu, 2021):
+//!
+//! 1. The prover commits to the proofs' `A`, `B` and `C` with pairing-based inner product
+//!    commitments keyed by two powers-of-tau SRSs, and draws `r` from a transcript of the
+//!    commitments, the verifying key and every public input.
+//! 2. The Groth16 equations scaled by `r^i` add up to
+//!    `Z_AB = e(α, β)·Σr^i + e(Σr^i·L_i, γ) + e(Z_C, δ)`, with `Z_AB = Σ r^i·e(A_i, B_i)`
+//!    and `Z_C = Σ r^i·C_i`; the verifier checks this one equation.
+//! 3. A combined TIPP/MIPP argument (GIPA) shows that `Z_AB` and `Z_C` are those inner
+//!    products of the committed vectors, halving them in every round, and KZG openings
+//!    show the final commitment keys were folded from the SRS.
+//!
+//! Counts that are not a power of two are padded by repeating the last proof, which the
+//! verifier mirrors on the public inputs.
+//!
+//! `AggregationSrs::setup` samples a fresh SRS, so whoever runs it knows its secrets and
+//! could forge aggregates: it is for tests and private deployments. A public deployment
+//! derives the SRS from two existing powers-of-tau ceremonies, as SnarkPack does.
+
+use crate::verifier::ProofWithInputs;
+use anyhow::{anyhow, Result};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::scalar_mul::ScalarMul;
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup, VariableBaseMSM};
+use ark_ff::{Field, One, PrimeField, UniformRand, Zero};
+use ark_groth16::VerifyingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+type Gt = PairingOutput<Bn254>;
+
+const TRANSCRIPT_DOMAIN: &[u8] = b"vortex-snarkpack-v1";
+
+/// Powers of two secrets `a` and `b` in both groups, enough for `max_proofs` proofs
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationSrs {
+    /// `g^(a^i)` for `i < 2·max_proofs`
+    g_alpha_powers: Vec<G1Affine>,
+    /// `g^(b^i)` for `i < 2·max_proofs`
+    g_beta_powers: Vec<G1Affine>,
+    /// `h^(a^i)` for `i < max_proofs`
+    h_alpha_powers: Vec<G2Affine>,
+    /// `h^(b^i)` for `i < max_proofs`
+    h_beta_powers: Vec<G2Affine>,
+}
+
+/// The part of the SRS a verifier needs
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationVerifierKey {
+    pub max_proofs: u32,
+    g: G1Affine,
+    h: G2Affine,
+    g_alpha: G1Affine,
+    g_beta: G1Affine,
+    h_alpha: G2Affine,
+    h_beta: G2Affine,
+}
+
+/// Messages of one halving round
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+struct GipaRound {
+    com_ab_left: [Gt; 2],
+    com_ab_right: [Gt; 2],
+    z_ab_left: Gt,
+    z_ab_right: Gt,
+    com_c_left: [Gt; 2],
+    com_c_right: [Gt; 2],
+    z_c_left: G1Affine,
+    z_c_right: G1Affine,
+}
+
+/// An aggregate of Groth16 proofs, sent with the public inputs of every proof
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregateProof {
+    com_ab: [Gt; 2],
+    com_c: [Gt; 2],
+    z_ab: Gt,
+    z_c: G1Affine,
+    rounds: Vec<GipaRound>,
+    final_a: G1Affine,
+    final_b: G2Affine,
+    final_c: G1Affine,
+    final_v: [G2Affine; 2],
+    final_w: [G1Affine; 2],
+    v_openings: [G2Affine; 2],
+    w_openings: [G1Affine; 2],
+}
+
+impl AggregationSrs {
+    /// Samples an SRS for up to `max_proofs` proofs, rounded up to a power of two.
+    ///
+    /// Whoever holds `rng`'s output can forge aggregates; see the module docs.
+    pub fn setup<R: RngCore + CryptoRng>(max_proofs: usize, rng: &mut R) -> Self {
+        let n = max_proofs.next_power_of_two().max(2);
+        let a = Fr::rand(rng);
+        let b = Fr::rand(rng);
+        let g = G1Projective::generator();
+        let h = G2Projective::generator();
+        Self {
+            g_alpha_powers: g.batch_mul(&powers(a, 2 * n)),
+            g_beta_powers: g.batch_mul(&powers(b, 2 * n)),
+            h_alpha_powers: h.batch_mul(&powers(a, n)),
+            h_beta_powers: h.batch_mul(&powers(b, n)),
+        }
+    }
+
+    /// Most proofs one aggregate can hold
+    pub fn max_proofs(&self) -> usize {
+        self.h_alpha_powers.len()
+    }
+
+    pub fn verifier_key(&self) -> AggregationVerifierKey {
+        AggregationVerifierKey {
+            max_proofs: self.max_proofs() as u32,
+            g: self.g_alpha_powers[0],
+            h: self.h_alpha_powers[0],
+            g_alpha: self.g_alpha_powers[1],
+            g_beta: self.g_beta_powers[1],
+            h_alpha: self.h_alpha_powers[1],
+            h_beta: self.h_beta_powers[1],
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        let n = self.h_alpha_powers.len();
+        if n < 2
+            || !n.is_power_of_two()
+            || self.h_beta_powers.len() != n
+            || self.g_alpha_powers.len() != 2 * n
+            || self.g_beta_powers.len() != 2 * n
+        {
+            return Err(anyhow!("Aggregation SRS has inconsistent lengths"));
+        }
+        Ok(())
+    }
+}
+
+/// Fiat-Shamir transcript over SHA-512
+struct Transcript(Sha512);
+
+impl Transcript {
+    fn new() -> Self {
+        let mut hasher = Sha512::new();
+        hasher.update(TRANSCRIPT_DOMAIN);
+        Self(hasher)
+    }
+
+    fn append(&mut self, label: &[u8], item: &impl CanonicalSerialize) {
+        let mut bytes = Vec::new();
+        item.serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        self.0.update((label.len() as u64).to_le_bytes());
+        self.0.update(label);
+        self.0.update((bytes.len() as u64).to_le_bytes());
+        self.0.update(&bytes);
+    }
+
+    /// A non-zero challenge, which is also absorbed
+    fn challenge(&mut self, label: &[u8]) -> Fr {
+        let mut counter = 0u64;
+        loop {
+            let mut hasher = self.0.clone();
+            hasher.update(label);
+            hasher.update(counter.to_le_bytes());
+            let challenge = Fr::from_le_bytes_mod_order(&hasher.finalize());
+            if !challenge.is_zero() {
+                self.append(label, &challenge);
+                return challenge;
+            }
+            counter += 1;
+        }
+    }
+
+    /// Starts the transcript with everything the aggregate is about
+    fn start(
+        vk: &VerifyingKey<Bn254>,
+        public_inputs: &[&[Fr]],
+        com_ab: &[Gt; 2],
+        com_c: &[Gt; 2],
+    ) -> Self {
+        let mut transcript = Self::new();
+        transcript.append(b"vk", vk);
+        transcript.append(b"count", &(public_inputs.len() as u64));
+        for inputs in public_inputs {
+            transcript.append(b"inputs", &inputs.to_vec());
+        }
+        transcript.append(b"com_ab", com_ab);
+        transcript.append(b"com_c", com_c);
+        transcript
+    }
+
+    fn append_round(&mut self, round: &GipaRound) -> Fr {
+        self.append(b"round", round);
+        self.challenge(b"x")
+    }
+}
+
+/// `1, x, x^2, …` with `len` terms
+fn powers(x: Fr, len: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(len);
+    let mut power = Fr::one();
+    for _ in 0..len {
+        powers.push(power);
+        power *= x;
+    }
+    powers
+}
+
+fn pairing_sum(g1: &[G1Affine], g2: &[G2Affine]) -> Gt {
+    Bn254::multi_pairing(g1.iter().copied(), g2.iter().copied())
+}
+
+/// `Σ e(a_i, v_i) + e(w_i, b_i)`, the commitment to an `A`/`B` pair under one key
+fn commit_pair(a: &[G1Affine], v: &[G2Affine], w: &[G1Affine], b: &[G2Affine]) -> Gt {
+    Bn254::multi_pairing(a.iter().chain(w).copied(), v.iter().chain(b).copied())
+}
+
+/// `left_i + right_i·x`, halving a vector
+fn fold<G: CurveGroup<ScalarField = Fr>>(
+    left: &[G::Affine],
+    right: &[G::Affine],
+    x: Fr,
+) -> Vec<G::Affine> {
+    let folded: Vec<G> = left.iter().zip(right).map(|(l, r)| *r * x + l).collect();
+    G::normalize_batch(&folded)
+}
+
+/// Scales every `points[i]` by `scalars[i]`
+fn rescale<G: CurveGroup<ScalarField = Fr>>(points: &[G::Affine], scalars: &[Fr]) -> Vec<G::Affine> {
+    let scaled: Vec<G> = points.iter().zip(scalars).map(|(p, s)| *p * s).collect();
+    G::normalize_batch(&scaled)
+}
+
+/// Coefficients of `Π_j (1 + t_j·X^(m/2^(j+1)))` for round factors `t_j`, lowest degree first
+fn folded_key_coefficients(factors: &[Fr]) -> Vec<Fr> {
+    let mut coefficients = vec![Fr::one()];
+    // The last round has weight 1, so doubling from it keeps indices in bit order
+    for t in factors.iter().rev() {
+        let scaled: Vec<Fr> = coefficients.iter().map(|c| *c * t).collect();
+        coefficients.extend(scaled);
+    }
+    coefficients
+}
+
+/// `Π_j (1 + t_j·z^(m/2^(j+1)))`
+fn folded_key_evaluation(factors: &[Fr], z: Fr) -> Fr {
+    let mut value = Fr::one();
+    let mut z_weight = z;
+    for t in factors.iter().rev() {
+        value *= Fr::one() + *t * z_weight;
+        z_weight.square_in_place();
+    }
+    value
+}
+
+/// Quotient of `p(X) - p(z)` by `X - z`
+fn kzg_quotient(p: &[Fr], z: Fr) -> Vec<Fr> {
+    let mut quotient = vec![Fr::zero(); p.len().saturating_sub(1)];
+    let mut carry = Fr::zero();
+    for i in (1..p.len()).rev() {
+        carry = p[i] + carry * z;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+fn msm<G: VariableBaseMSM<ScalarField = Fr>>(bases: &[G::MulBase], scalars: &[Fr]) -> G::MulBase {
+    G::msm_unchecked(&bases[..scalars.len()], scalars).into()
+}
+
+/// Pads to a power of two by repeating the last element
+fn padded_len(count: usize) -> usize {
+    count.next_power_of_two().max(2)
+}
+
+/// Aggregates `proofs`, all made with `vk`.
+///
+/// # Errors
+/// Returns error if there are no proofs, more than the SRS holds, or a proof's public inputs
+/// do not match `vk`.
+pub fn aggregate_proofs(
+    srs: &AggregationSrs,
+    vk: &VerifyingKey<Bn254>,
+    proofs: &[ProofWithInputs],
+) -> Result<AggregateProof> {
+    srs.check()?;
+    let last = proofs
+        .last()
+        .ok_or_else(|| anyhow!("No proofs to aggregate"))?;
+    let m = padded_len(proofs.len());
+    if m > srs.max_proofs() {
+        return Err(anyhow!(
+            "{} proofs need an SRS for {}, this one holds {}",
+            proofs.len(),
+            m,
+            srs.max_proofs()
+        ));
+    }
+    if let Some(i) = proofs
+        .iter()
+        .position(|(_, inputs)| inputs.len() + 1 != vk.gamma_abc_g1.len())
+    {
+        return Err(anyhow!(
+            "Proof {} has {} public inputs, the verifying key takes {}",
+            i,
+            proofs[i].1.len(),
+            vk.gamma_abc_g1.len() - 1
+        ));
+    }
+    let padded: Vec<&ProofWithInputs> = proofs
+        .iter()
+        .chain(std::iter::repeat(last))
+        .take(m)
+        .collect();
+    let a: Vec<G1Affine> = padded.iter().map(|(proof, _)| proof.a).collect();
+    let b: Vec<G2Affine> = padded.iter().map(|(proof, _)| proof.b).collect();
+    let c: Vec<G1Affine> = padded.iter().map(|(proof, _)| proof.c).collect();
+    let public_inputs: Vec<&[Fr]> = padded.iter().map(|(_, inputs)| inputs.as_slice()).collect();
+
+    // A and C are committed under v, B under w; their powers do not overlap
+    let v = [&srs.h_alpha_powers[..m], &srs.h_beta_powers[..m]];
+    let w = [&srs.g_alpha_powers[m..2 * m], &srs.g_beta_powers[m..2 * m]];
+    let com_ab = [0, 1].map(|k| commit_pair(&a, v[k], w[k], &b));
+    let com_c = [0, 1].map(|k| pairing_sum(&c, v[k]));
+
+    let mut transcript = Transcript::start(vk, &public_inputs, &com_ab, &com_c);
+    let r = transcript.challenge(b"r");
+    let r_inv = r.inverse().expect("challenges are non-zero");
+
+    // A·r^i under v·r^-i has the same commitment, and its inner product with B is Z_AB
+    let mut a = rescale::<G1Projective>(&a, &powers(r, m));
+    let mut c = rescale::<G1Projective>(&c, &powers(r, m));
+    let mut b = b;
+    let r_inv_powers = powers(r_inv, m);
+    let mut v = v.map(|key| rescale::<G2Projective>(key, &r_inv_powers));
+    let mut w = w.map(|key| key.to_vec());
+    let mut s = vec![Fr::one(); m];
+    let z_ab = pairing_sum(&a, &b);
+    let z_c = c
+        .iter()
+        .map(|point| point.into_group())
+        .sum::<G1Projective>()
+        .into_affine();
+    transcript.append(b"z_ab", &z_ab);
+    transcript.append(b"z_c", &z_c);
+
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+    while a.len() > 1 {
+        let half = a.len() / 2;
+        let (a_l, a_r) = a.split_at(half);
+        let (b_l, b_r) = b.split_at(half);
+        let (c_l, c_r) = c.split_at(half);
+        let (s_l, s_r) = s.split_at(half);
+        let round = GipaRound {
+            com_ab_left: [0, 1].map(|k| commit_pair(a_r, &v[k][..half], &w[k][half..], b_l)),
+            com_ab_right: [0, 1].map(|k| commit_pair(a_l, &v[k][half..], &w[k][..half], b_r)),
+            z_ab_left: pairing_sum(a_r, b_l),
+            z_ab_right: pairing_sum(a_l, b_r),
+            com_c_left: [0, 1].map(|k| pairing_sum(c_r, &v[k][..half])),
+            com_c_right: [0, 1].map(|k| pairing_sum(c_l, &v[k][half..])),
+            z_c_left: G1Projective::msm_unchecked(c_r, s_l).into_affine(),
+            z_c_right: G1Projective::msm_unchecked(c_l, s_r).into_affine(),
+        };
+        let x = transcript.append_round(&round);
+        let x_inv = x.inverse().expect("challenges are non-zero");
+
+        a = fold::<G1Projective>(a_l, a_r, x);
+        c = fold::<G1Projective>(c_l, c_r, x);
+        b = fold::<G2Projective>(b_l, b_r, x_inv);
+        s = s_l.iter().zip(s_r).map(|(l, r)| *l + *r * x_inv).collect();
+        v = v.map(|key| fold::<G2Projective>(&key[..half], &key[half..], x_inv));
+        w = w.map(|key| fold::<G1Projective>(&key[..half], &key[half..], x));
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let final_v = [v[0][0], v[1][0]];
+    let final_w = [w[0][0], w[1][0]];
+    transcript.append(b"final_v", &final_v);
+    transcript.append(b"final_w", &final_w);
+    let z = transcript.challenge(b"z");
+
+    // v ends as h^f(a), f = Π (1 + x_j^-1·r^-m_j·X^m_j); w as g^(X^m·Π (1 + x_j·X^m_j))
+    let v_factors = v_factors(&challenges, r_inv);
+    let v_quotient = kzg_quotient(&folded_key_coefficients(&v_factors), z);
+    let mut w_polynomial = vec![Fr::zero(); m];
+    w_polynomial.extend(folded_key_coefficients(&challenges));
+    let w_quotient = kzg_quotient(&w_polynomial, z);
+
+    Ok(AggregateProof {
+        com_ab,
+        com_c,
+        z_ab,
+        z_c,
+        rounds,
+        final_a: a[0],
+        final_b: b[0],
+        final_c: c[0],
+        final_v,
+        final_w,
+        v_openings: [
+            msm::<G2Projective>(&srs.h_alpha_powers, &v_quotient),
+            msm::<G2Projective>(&srs.h_beta_powers, &v_quotient),
+        ],
+        w_openings: [
+            msm::<G1Projective>(&srs.g_alpha_powers, &w_quotient),
+            msm::<G1Projective>(&srs.g_beta_powers, &w_quotient),
+        ],
+    })
+}
+
+/// Factors `x_j^-1·r^-m_j` of the folded `v` key, `m_j` being round `j`'s half length
+fn v_factors(challenges: &[Fr], r_inv: Fr) -> Vec<Fr> {
+    let mut r_weight = r_inv;
+    let mut factors: Vec<Fr> = challenges
+        .iter()
+        .rev()
+        .map(|x| {
+            let factor = x.inverse().expect("challenges are non-zero") * r_weight;
+            r_weight.square_in_place();
+            factor
+        })
+        .collect();
+    factors.reverse();
+    factors
+}
+
+/// Checks `proof` aggregates valid proofs with `public_inputs` under `vk`, in order.
+///
+/// Malformed aggregates, such as one with the wrong number of rounds, are reported invalid.
+pub fn verify_aggregate(
+    key: &AggregationVerifierKey,
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &[Vec<Fr>],
+    proof: &AggregateProof,
+) -> bool {
+    let Some(last) = public_inputs.last() else {
+        return false;
+    };
+    let m = padded_len(public_inputs.len());
+    if m > key.max_proofs as usize
+        || proof.rounds.len() != m.trailing_zeros() as usize
+        || public_inputs
+            .iter()
+            .any(|inputs| inputs.len() + 1 != vk.gamma_abc_g1.len())
+    {
+        return false;
+    }
+    let padded: Vec<&[Fr]> = public_inputs
+        .iter()
+        .chain(std::iter::repeat(last))
+        .take(m)
+        .map(Vec::as_slice)
+        .collect();
+
+    let mut transcript = Transcript::start(vk, &padded, &proof.com_ab, &proof.com_c);
+    let r = transcript.challenge(b"r");
+    let Some(r_inv) = r.inverse() else {
+        return false;
+    };
+
+    // Σ r^i·L_i as one MSM over the verifying key's input points
+    let r_powers = powers(r, m);
+    let mut input_scalars = vec![r_powers.iter().sum::<Fr>()];
+    for j in 0..vk.gamma_abc_g1.len() - 1 {
+        input_scalars.push(
+            r_powers
+                .iter()
+                .zip(&padded)
+                .map(|(r, inputs)| *r * inputs[j])
+                .sum(),
+        );
+    }
+    let inputs_acc = G1Projective::msm_unchecked(&vk.gamma_abc_g1, &input_scalars).into_affine();
+    let groth16 = Bn254::pairing(vk.alpha_g1, vk.beta_g2) * input_scalars[0]
+        + pairing_sum(&[inputs_acc, proof.z_c], &[vk.gamma_g2, vk.delta_g2]);
+    if groth16 != proof.z_ab {
+        return false;
+    }
+
+    transcript.append(b"z_ab", &proof.z_ab);
+    transcript.append(b"z_c", &proof.z_c);
+    let (mut com_ab, mut com_c, mut z_ab) = (proof.com_ab, proof.com_c, proof.z_ab);
+    let mut z_c = proof.z_c.into_group();
+    let mut s = Fr::one();
+    let mut challenges = Vec::with_capacity(proof.rounds.len());
+    for round in &proof.rounds {
+        let x = transcript.append_round(round);
+        let Some(x_inv) = x.inverse() else {
+            return false;
+        };
+        for k in 0..2 {
+            com_ab[k] += round.com_ab_left[k] * x + round.com_ab_right[k] * x_inv;
+            com_c[k] += round.com_c_left[k] * x + round.com_c_right[k] * x_inv;
+        }
+        z_ab += round.z_ab_left * x + round.z_ab_right * x_inv;
+        z_c += round.z_c_left * x + round.z_c_right * x_inv;
+        s *= Fr::one() + x_inv;
+        challenges.push(x);
+    }
+
+    let folded_hold = (0..2).all(|k| {
+        com_ab[k]
+            == commit_pair(
+                &[proof.final_a],
+                &[proof.final_v[k]],
+                &[proof.final_w[k]],
+                &[proof.final_b],
+            )
+            && com_c[k] == Bn254::pairing(proof.final_c, proof.final_v[k])
+    }) && z_ab == Bn254::pairing(proof.final_a, proof.final_b)
+        && z_c == proof.final_c * s;
+    if !folded_hold {
+        return false;
+    }
+
+    transcript.append(b"final_v", &proof.final_v);
+    transcript.append(b"final_w", &proof.final_w);
+    let z = transcript.challenge(b"z");
+    let v_eval = folded_key_evaluation(&v_factors(&challenges, r_inv), z);
+    let w_eval = z.pow([m as u64]) * folded_key_evaluation(&challenges, z);
+    let g_z = (key.g * z).into_affine();
+    let h_z = (key.h * z).into_affine();
+    let g_secrets = [key.g_alpha, key.g_beta];
+    let h_secrets = [key.h_alpha, key.h_beta];
+    (0..2).all(|k| {
+        // e(g^(s - z), π_v) = e(g, v - h^f(z)) and e(w - g^f(z), h) = e(π_w, h^(s - z))
+        let v_holds = pairing_sum(
+            &[(g_secrets[k].into_group() - g_z).into_affine(), -key.g],
+            &[
+                proof.v_openings[k],
+                (proof.final_v[k].into_group() - key.h * v_eval).into_affine(),
+            ],
+        )
+        .is_zero();
+        let w_holds = pairing_sum(
+            &[
+                (proof.final_w[k].into_group() - key.g * w_eval).into_affine(),
+                -proof.w_openings[k],
+            ],
+            &[key.h, (h_secrets[k].into_group() - h_z).into_affine()],
+        )
+        .is_zero();
+        v_holds && w_holds
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_groth16::Groth16;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    /// Knowledge of `x` with `x * x = y`, with a second input `z = x + y`
+    #[derive(Clone)]
+    struct Square {
+        x: Fr,
+    }
+
+    impl ConstraintSynthesizer<Fr> for Square {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let y = FpVar::new_input(cs.clone(), || Ok(self.x * self.x))?;
+            let z = FpVar::new_input(cs.clone(), || Ok(self.x * self.x + self.x))?;
+            let x = FpVar::new_witness(cs, || Ok(self.x))?;
+            (&x * &x).enforce_equal(&y)?;
+            (&x + &y).enforce_equal(&z)
+        }
+    }
+
+    fn proofs(count: u64, rng: &mut ChaCha20Rng) -> (VerifyingKey<Bn254>, Vec<ProofWithInputs>) {
+        let (pk, vk) =
+            Groth16::<Bn254>::circuit_specific_setup(Square { x: Fr::zero() }, rng).unwrap();
+        let proofs = (1..=count)
+            .map(|x| {
+                let x = Fr::from(x);
+                let proof = Groth16::<Bn254>::prove(&pk, Square { x }, rng).unwrap();
+                (proof, vec![x * x, x * x + x])
+            })
+            .collect();
+        (vk, proofs)
+    }
+
+    #[test]
+    fn test_aggregate_verifies_and_binds_every_input() {
+        let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+        let srs = AggregationSrs::setup(8, &mut rng);
+        let key = srs.verifier_key();
+        let (vk, proofs) = proofs(5, &mut rng);
+        let inputs: Vec<Vec<Fr>> = proofs.iter().map(|(_, inputs)| inputs.clone()).collect();
+
+        let aggregate = aggregate_proofs(&srs, &vk, &proofs).unwrap();
+        assert_eq!(aggregate.rounds.len(), 3);
+        assert!(verify_aggregate(&key, &vk, &inputs, &aggregate));
+
+        let mut bytes = Vec::new();
+        aggregate.serialize_compressed(&mut bytes).unwrap();
+        let decoded = AggregateProof::deserialize_compressed(bytes.as_slice()).unwrap();
+        assert!(verify_aggregate(&key, &vk, &inputs, &decoded));
+
+        let mut tampered = inputs.clone();
+        tampered[3][1] += Fr::one();
+        assert!(!verify_aggregate(&key, &vk, &tampered, &aggregate));
+        assert!(!verify_aggregate(&key, &vk, &inputs[..4], &aggregate));
+        let mut swapped = inputs.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_aggregate(&key, &vk, &swapped, &aggregate));
+
+        let mut forged = aggregate.clone();
+        forged.final_c = (forged.final_c * Fr::from(2u64)).into_affine();
+        assert!(!verify_aggregate(&key, &vk, &inputs, &forged));
+
+        // An invalid proof cannot be aggregated into a valid-looking argument
+        let mut bad = proofs.clone();
+        bad[2].1[0] += Fr::one();
+        let aggregate = aggregate_proofs(&srs, &vk, &bad).unwrap();
+        let bad_inputs: Vec<Vec<Fr>> = bad.iter().map(|(_, inputs)| inputs.clone()).collect();
+        assert!(!verify_aggregate(&key, &vk, &bad_inputs, &aggregate));
+
+        let (_, too_many) = self::proofs(9, &mut rng);
+        assert!(aggregate_proofs(&srs, &vk, &too_many).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! Turning a payment intent into circuit amounts.
+//!
+//! The circuit enforces `Σinputs + public_amount = Σoutputs` over the field, so value leaving
+//! the pool is a negative `public_amount` encoded as `p - |x|`. `plan_amounts` derives that
+//! value and the change output from what the user wants to do, and rejects intents the
+//! circuit or the pool would refuse before a circuit is ever built.
+//!
+//! `amount_to_fr` and `fr_to_amount` convert between integer amounts and their field
+//! encoding directly, with range errors instead of going through decimal strings. The signed
+//! `public_amount` convention lives in `signed_to_public_amount`, `public_amount_to_signed`
+//! and `ext_data_public_amount`: deposits are positive, withdrawals negative.
+
+use crate::circuit::split_public_amount;
+use crate::constants::{MAX_AMOUNT_BITS, N_INS};
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use serde::{Deserialize, Serialize};
+
+/// What the user wants a transaction to do, in MIST
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferIntent {
+    /// Amounts of the notes being spent (at most `N_INS`)
+    pub input_amounts: Vec<u64>,
+    /// Value moved into the pool from the sender's coins
+    pub deposit: u64,
+    /// Value of the shielded output paid to the recipient
+    pub send: u64,
+    /// Value paid out of the pool to a public address
+    pub withdraw: u64,
+    /// Relayer fee, paid out of the pool next to the withdrawal
+    pub fee: u64,
+}
+
+/// Amounts to put into the `ProofInput` and ext data for an intent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct AmountPlan {
+    /// Decimal field encoding of `deposit - withdraw - fee`
+    pub public_amount: String,
+    /// `out_amount_0`: the recipient's output
+    pub send_amount: u64,
+    /// `out_amount_1`: change back to the sender
+    pub change_amount: u64,
+    /// Ext data `value`: the deposit, or withdrawal plus fee
+    pub ext_value: u64,
+    pub is_deposit: bool,
+}
+
+/// Computes `public_amount` and the change output for `intent`.
+///
+/// # Errors
+/// Returns error if there are more than `N_INS` inputs, a deposit is combined with a
+/// withdrawal or fee, the outputs exceed the inputs plus the deposit, or an amount does not
+/// fit `MAX_AMOUNT_BITS` or the ext data `u64`.
+pub fn plan_amounts(intent: &TransferIntent) -> Result<AmountPlan> {
+    if intent.input_amounts.len() > N_INS {
+        return Err(anyhow!(
+            "At most {} inputs can be spent, got {}",
+            N_INS,
+            intent.input_amounts.len()
+        ));
+    }
+    if intent.deposit > 0 && (intent.withdraw > 0 || intent.fee > 0) {
+        return Err(anyhow!(
+            "A deposit cannot be combined with a withdrawal or relayer fee"
+        ));
+    }
+
+    let available = intent
+        .input_amounts
+        .iter()
+        .map(|&a| a as u128)
+        .sum::<u128>()
+        + intent.deposit as u128;
+    let spent = intent.send as u128 + intent.withdraw as u128 + intent.fee as u128;
+    if spent > available {
+        return Err(anyhow!(
+            "Intent spends {} but only {} is available",
+            spent,
+            available
+        ));
+    }
+    let change = u64::try_from(available - spent)
+        .map_err(|_| anyhow!("Change of {} does not fit a u64", available - spent))?;
+
+    let ext_value = if intent.deposit > 0 {
+        intent.deposit
+    } else {
+        intent
+            .withdraw
+            .checked_add(intent.fee)
+            .ok_or_else(|| anyhow!("Withdrawal plus fee does not fit a u64"))?
+    };
+
+    for amount in [intent.send, change] {
+        check_amount_bits(&amount_to_fr(amount))?;
+    }
+
+    let public_amount = ext_data_public_amount(ext_value, intent.deposit > 0);
+
+    Ok(AmountPlan {
+        public_amount: public_amount.into_bigint().to_string(),
+        send_amount: intent.send,
+        change_amount: change,
+        ext_value,
+        is_deposit: intent.deposit > 0,
+    })
+}
+
+/// Fails if `amount` would not pass the circuit's `MAX_AMOUNT_BITS` range check
+pub fn check_amount_bits(amount: &Fr) -> Result<()> {
+    let bits = amount.into_bigint().num_bits() as usize;
+    if bits > MAX_AMOUNT_BITS {
+        return Err(anyhow!(
+            "Amount has {} bits, more than the {} the circuit allows",
+            bits,
+            MAX_AMOUNT_BITS
+        ));
+    }
+    Ok(())
+}
+
+// Every `u128` amount passes the circuit range check, so `amount_to_fr` cannot fail
+const _: () = assert!(u128::BITS as usize <= MAX_AMOUNT_BITS);
+
+/// Field encoding of a `u64` or `u128` amount
+pub fn amount_to_fr(amount: impl Into<u128>) -> Fr {
+    let amount: u128 = amount.into();
+    Fr::from(amount)
+}
+
+/// Amount encoded by `value`, as a `u128`.
+///
+/// # Errors
+/// Returns error if `value` exceeds `MAX_AMOUNT_BITS` (negative values wrap to the top of
+/// the field, so they do too) or does not fit a `u128`.
+pub fn fr_to_amount_u128(value: &Fr) -> Result<u128> {
+    check_amount_bits(value)?;
+    let bigint = value.into_bigint();
+    if bigint.num_bits() > u128::BITS {
+        return Err(anyhow!("Amount {} does not fit a u128", bigint));
+    }
+    Ok(bigint.0[0] as u128 | (bigint.0[1] as u128) << 64)
+}
+
+/// Amount encoded by `value`, as a `u64`.
+///
+/// # Errors
+/// Returns error if `value` exceeds `MAX_AMOUNT_BITS` or does not fit a `u64`.
+pub fn fr_to_amount(value: &Fr) -> Result<u64> {
+    let amount = fr_to_amount_u128(value)?;
+    u64::try_from(amount).map_err(|_| anyhow!("Amount {} does not fit a u64", amount))
+}
+
+/// `public_amount` of a signed change to the pool's balance: `x` for a deposit of `x`, and
+/// `p - x` for a withdrawal of `x`
+pub fn signed_to_public_amount(amount: i128) -> Fr {
+    let magnitude = amount_to_fr(amount.unsigned_abs());
+    if amount < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Signed change to the pool's balance encoded by `public_amount`; the inverse of
+/// `signed_to_public_amount`.
+///
+/// # Errors
+/// Returns error if the magnitude exceeds `MAX_AMOUNT_BITS` or does not fit an `i128`.
+pub fn public_amount_to_signed(public_amount: &Fr) -> Result<i128> {
+    let (is_withdrawal, magnitude) = split_public_amount(public_amount);
+    let magnitude = fr_to_amount_u128(&magnitude)?;
+    let signed = if is_withdrawal {
+        0i128.checked_sub_unsigned(magnitude)
+    } else {
+        i128::try_from(magnitude).ok()
+    };
+    signed.ok_or_else(|| anyhow!("Public amount magnitude {} does not fit an i128", magnitude))
+}
+
+/// `public_amount` the pool checks a proof against for ext data `value` and `is_deposit`:
+/// the value itself for deposits, its negation for withdrawals and their fee
+pub fn ext_data_public_amount(value: u64, is_deposit: bool) -> Fr {
+    let value = amount_to_fr(value);
+    if is_deposit {
+        value
+    } else {
+        -value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdrawal_with_fee_encodes_negative_public_amount() {
+        let plan = plan_amounts(&TransferIntent {
+            input_amounts: vec![700, 500],
+            send: 100,
+            withdraw: 800,
+            fee: 50,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(plan.change_amount, 250);
+        assert_eq!(plan.ext_value, 850);
+        assert!(!plan.is_deposit);
+        assert_eq!(
+            plan.public_amount,
+            (-Fr::from(850u64)).into_bigint().to_string()
+        );
+    }
+
+    #[test]
+    fn test_deposit_and_invalid_intents() {
+        let deposit = plan_amounts(&TransferIntent {
+            deposit: 1_000,
+            send: 400,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(deposit.public_amount, "1000");
+        assert_eq!(deposit.change_amount, 600);
+        assert!(deposit.is_deposit);
+
+        let overspend = TransferIntent {
+            input_amounts: vec![10],
+            send: 11,
+            ..Default::default()
+        };
+        assert!(plan_amounts(&overspend).is_err());
+
+        let relayed_deposit = TransferIntent {
+            deposit: 10,
+            fee: 1,
+            ..Default::default()
+        };
+        assert!(plan_amounts(&relayed_deposit).is_err());
+
+        let too_many_inputs = TransferIntent {
+            input_amounts: vec![1; N_INS + 1],
+            ..Default::default()
+        };
+        assert!(plan_amounts(&too_many_inputs).is_err());
+
+        assert!(check_amount_bits(&-Fr::from(1u64)).is_err());
+    }
+
+    #[test]
+    fn test_amount_field_conversions_round_trip_and_reject_out_of_range() {
+        for amount in [0u64, 1, u64::MAX] {
+            assert_eq!(fr_to_amount(&amount_to_fr(amount)).unwrap(), amount);
+        }
+        let large = u128::MAX - 5;
+        assert_eq!(fr_to_amount_u128(&amount_to_fr(large)).unwrap(), large);
+        assert!(fr_to_amount(&amount_to_fr(u64::MAX as u128 + 1)).is_err());
+
+        let over_u128 = amount_to_fr(u128::MAX) + Fr::from(1u64);
+        assert!(fr_to_amount_u128(&over_u128).is_err());
+        let negative = -Fr::from(3u64);
+        let err = fr_to_amount(&negative).unwrap_err();
+        assert!(err.to_string().contains("bits"));
+    }
+
+    #[test]
+    fn test_signed_public_amounts_match_the_pool() {
+        use ark_ff::Field;
+        use num_bigint::BigUint;
+
+        // The pool reads `public_amount` as a u256: `value` for deposits, `p - value` for
+        // withdrawals
+        let modulus = BigUint::from(Fr::MODULUS);
+        let pool_public_amount = |value: u64, is_deposit: bool| {
+            if is_deposit || value == 0 {
+                BigUint::from(value)
+            } else {
+                &modulus - value
+            }
+        };
+
+        for (value, is_deposit) in [
+            (1_000u64, true),
+            (850, false),
+            (0, false),
+            (u64::MAX, false),
+        ] {
+            let encoded = ext_data_public_amount(value, is_deposit);
+            assert_eq!(
+                BigUint::from(encoded),
+                pool_public_amount(value, is_deposit)
+            );
+
+            let signed = public_amount_to_signed(&encoded).unwrap();
+            let expected = if is_deposit {
+                value as i128
+            } else {
+                -(value as i128)
+            };
+            assert_eq!(signed, expected);
+            assert_eq!(signed_to_public_amount(signed), encoded);
+        }
+
+        for amount in [i128::MIN, i128::MIN + 1, i128::MAX] {
+            assert_eq!(
+                public_amount_to_signed(&signed_to_public_amount(amount)).unwrap(),
+                amount
+            );
+        }
+        assert!(public_amount_to_signed(&amount_to_fr(i128::MAX as u128 + 1)).is_err());
+        assert!(public_amount_to_signed(&(Fr::from(2u64).pow([MAX_AMOUNT_BITS as u64]))).is_err());
+    }
+}
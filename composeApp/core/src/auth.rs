@@ -0,0 +1,152 @@
+//! "Log in with your shielded account" signatures.
+//!
+//! A dApp sends a challenge and the wallet signs it with a key derived from its spending
+//! key, so the login identity follows the same key as the notes without exposing it. The
+//! scheme is Schnorr over BN254 G1 with Poseidon as the challenge hash:
+//! - signing key `x = Poseidon2(spending_key, AUTH_KEY_DOMAIN)`, public key `P = x·G`
+//! - nonce `k = Poseidon4(x, m_lo, m_hi, AUTH_NONCE_DOMAIN)`, deterministic per message
+//! - `R = k·G`, `e = Poseidon3(Poseidon4(R_lo, R_hi, P_lo, P_hi), m_lo, m_hi)`, `s = k + e·x`
+//!
+//! `m_lo`/`m_hi` are the halves of the BLAKE2b-256 digest of the challenge and `*_lo`/`*_hi`
+//! the 16-byte halves of a compressed point, so every hash input fits the field without
+//! reduction. A signature is `R (32, compressed) || s (32, LE)`; it verifies when
+//! `s·G = R + e·P`.
+
+use crate::field::{fr_from_bytes_le, fr_to_bytes_le};
+use crate::poseidon_opt::{hash2, hash3, hash4};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::{Fr, G1Affine, G1Projective};
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use zeroize::Zeroizing;
+
+/// Length of a signature: compressed `R` and `s`
+pub const AUTH_SIGNATURE_LEN: usize = 64;
+
+/// Separates the signing key from the other Poseidon2 derivations of the spending key
+const AUTH_KEY_DOMAIN: &[u8] = b"vortex-auth-key-v1";
+const AUTH_NONCE_DOMAIN: &[u8] = b"vortex-auth-nonce-v1";
+
+fn domain(tag: &[u8]) -> Fr {
+    Fr::from_le_bytes_mod_order(tag)
+}
+
+fn signing_key(spending_key: &Fr) -> Zeroizing<Fr> {
+    Zeroizing::new(hash2(spending_key, &domain(AUTH_KEY_DOMAIN)))
+}
+
+fn compress(point: &G1Affine) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    point
+        .serialize_compressed(&mut bytes[..])
+        .expect("a compressed G1 point is 32 bytes");
+    bytes
+}
+
+/// Splits 32 bytes into two field elements below 2^128
+fn halves(bytes: &[u8; 32]) -> (Fr, Fr) {
+    (
+        Fr::from_le_bytes_mod_order(&bytes[..16]),
+        Fr::from_le_bytes_mod_order(&bytes[16..]),
+    )
+}
+
+fn message_halves(message: &[u8]) -> (Fr, Fr) {
+    halves(&Blake2b::<U32>::digest(message).into())
+}
+
+fn challenge(r: &[u8; 32], public_key: &[u8; 32], message: &(Fr, Fr)) -> Fr {
+    let (r_lo, r_hi) = halves(r);
+    let (p_lo, p_hi) = halves(public_key);
+    hash3(&hash4(&r_lo, &r_hi, &p_lo, &p_hi), &message.0, &message.1)
+}
+
+/// Compressed public key that verifies the signatures of `spending_key`
+pub fn auth_public_key(spending_key: &Fr) -> [u8; 32] {
+    compress(&(G1Projective::generator() * *signing_key(spending_key)).into_affine())
+}
+
+/// Signs `message` with the login key of `spending_key`.
+///
+/// # Errors
+/// Returns error if the derived nonce is zero, which happens with negligible probability.
+pub fn sign_challenge(spending_key: &Fr, message: &[u8]) -> Result<[u8; AUTH_SIGNATURE_LEN]> {
+    let x = signing_key(spending_key);
+    let public_key = compress(&(G1Projective::generator() * *x).into_affine());
+    let m = message_halves(message);
+
+    let k = Zeroizing::new(hash4(&x, &m.0, &m.1, &domain(AUTH_NONCE_DOMAIN)));
+    if k.is_zero() {
+        return Err(anyhow!("Derived a zero signing nonce"));
+    }
+    let r = compress(&(G1Projective::generator() * *k).into_affine());
+    let s = *k + challenge(&r, &public_key, &m) * *x;
+
+    let mut signature = [0u8; AUTH_SIGNATURE_LEN];
+    signature[..32].copy_from_slice(&r);
+    signature[32..].copy_from_slice(&fr_to_bytes_le(&s));
+    Ok(signature)
+}
+
+/// Checks a signature produced by `sign_challenge`.
+///
+/// Returns `Ok(false)` for a well-formed signature that does not verify.
+///
+/// # Errors
+/// Returns error if the public key or signature is malformed: wrong length, not a valid
+/// point, the identity, or a non-canonical `s`.
+pub fn verify_challenge(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool> {
+    let public_key: [u8; 32] = public_key
+        .try_into()
+        .map_err(|_| anyhow!("Public key must be 32 bytes, got {}", public_key.len()))?;
+    if signature.len() != AUTH_SIGNATURE_LEN {
+        return Err(anyhow!(
+            "Signature must be {} bytes, got {}",
+            AUTH_SIGNATURE_LEN,
+            signature.len()
+        ));
+    }
+    let point = |bytes: &[u8]| -> Result<G1Affine> {
+        let point = G1Affine::deserialize_compressed(bytes).context("Invalid curve point")?;
+        if point.is_zero() {
+            return Err(anyhow!("Point is the identity"));
+        }
+        Ok(point)
+    };
+    let p = point(&public_key).context("Invalid public key")?;
+    let r = point(&signature[..32]).context("Invalid signature")?;
+    let s = fr_from_bytes_le(&signature[32..]).context("Signature scalar is not canonical")?;
+
+    let r_bytes: [u8; 32] = signature[..32].try_into().unwrap();
+    let e = challenge(&r_bytes, &public_key, &message_halves(message));
+    Ok(G1Projective::generator() * s == G1Projective::from(r) + p * e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_signatures_verify_only_for_their_key_and_message() {
+        let spending_key = Fr::from(12345u64);
+        let public_key = auth_public_key(&spending_key);
+        let message = b"login to example.app, nonce 42";
+
+        let signature = sign_challenge(&spending_key, message).unwrap();
+        assert_eq!(signature, sign_challenge(&spending_key, message).unwrap());
+        assert!(verify_challenge(&public_key, message, &signature).unwrap());
+
+        assert!(!verify_challenge(&public_key, b"login, nonce 43", &signature).unwrap());
+        let other_key = auth_public_key(&Fr::from(54321u64));
+        assert!(!verify_challenge(&other_key, message, &signature).unwrap());
+
+        let mut tampered = signature;
+        tampered[40] ^= 1;
+        assert!(!verify_challenge(&public_key, message, &tampered).unwrap());
+        assert!(verify_challenge(&public_key, message, &signature[..63]).is_err());
+        assert!(verify_challenge(&[0u8; 32], message, &signature).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Password-encrypted wallet backups for moving to a new device.
+//!
+//! A backup holds the wallet's notes and its sync checkpoint (cursors, tree leaves and the
+//! spent nullifier set), so a restored wallet resumes syncing where the old device stopped
+//! instead of rescanning the chain.
+//!
+//! The snapshot is serialized as JSON and sealed with ChaCha20-Poly1305 under a key derived
+//! from the password with Argon2id. The format is `format::BACKUP` framing followed by
+//! `m_cost LE4 || t_cost LE4 || p_cost LE4 || salt(16) || nonce(12) || ciphertext`, and
+//! everything before the ciphertext is authenticated as associated data, so the KDF
+//! parameters can be raised in later backups without breaking old ones. Version 1 backups
+//! carried a single `1` byte in place of the framing and are still imported.
+
+use crate::format;
+use crate::note::Utxo;
+use anyhow::{anyhow, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Leading byte of unframed version 1 backups
+const LEGACY_VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// KDF parameters, salt and nonce following the version prefix
+const PARAMS_LEN: usize = 12 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id cost of new backups: 19 MiB, 2 passes, 1 lane (the OWASP baseline), which
+/// stays well within the memory of low-end phones
+const BACKUP_KDF: KdfParams = KdfParams {
+    m_cost: 19 * 1024,
+    t_cost: 2,
+    p_cost: 1,
+};
+
+/// Wallet state carried by a backup
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct WalletSnapshot {
+    /// The note store, spent flags included
+    pub notes: Vec<Utxo>,
+    /// `SyncCheckpoint` JSON, if the wallet has synced
+    #[serde(default)]
+    pub sync_checkpoint: Option<String>,
+}
+
+#[cfg(feature = "chain-client")]
+impl WalletSnapshot {
+    pub fn new(notes: Vec<Utxo>, state: &crate::sync::SyncState) -> Result<Self> {
+        Ok(Self {
+            notes,
+            sync_checkpoint: Some(serde_json::to_string(&state.checkpoint())?),
+        })
+    }
+
+    /// The sync state to resume from; a fresh one if the wallet had not synced
+    pub fn sync_state(&self) -> Result<crate::sync::SyncState> {
+        match &self.sync_checkpoint {
+            Some(json) => {
+                let checkpoint =
+                    serde_json::from_str(json).context("Invalid sync checkpoint in backup")?;
+                crate::sync::SyncState::from_checkpoint(&checkpoint)
+            }
+            None => crate::sync::SyncState::new(),
+        }
+    }
+}
+
+/// Highest Argon2id cost accepted from a backup: 256 MiB, 10 passes, 4 lanes. The parameters
+/// are read before the backup is authenticated, so without a cap a crafted file could
+/// demand terabytes of memory or a KDF that never finishes.
+const MAX_KDF: KdfParams = KdfParams {
+    m_cost: 256 * 1024,
+    t_cost: 10,
+    p_cost: 4,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KdfParams {
+    /// Memory in KiB
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+/// Encrypts `snapshot` under `password`.
+///
+/// # Errors
+/// Returns error if the password is empty.
+pub fn export_backup<R: RngCore + CryptoRng>(
+    snapshot: &WalletSnapshot,
+    password: &str,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    export_backup_with(snapshot, password, BACKUP_KDF, rng)
+}
+
+fn export_backup_with<R: RngCore + CryptoRng>(
+    snapshot: &WalletSnapshot,
+    password: &str,
+    kdf: KdfParams,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    if password.is_empty() {
+        return Err(anyhow!("Backup password must not be empty"));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let mut backup = Vec::with_capacity(format::HEADER_LEN + PARAMS_LEN);
+    backup.extend_from_slice(&format::BACKUP.header());
+    for param in [kdf.m_cost, kdf.t_cost, kdf.p_cost] {
+        backup.extend_from_slice(&param.to_le_bytes());
+    }
+    backup.extend_from_slice(&salt);
+    backup.extend_from_slice(&nonce);
+
+    let plaintext = Zeroizing::new(serde_json::to_vec(snapshot)?);
+    let ciphertext = backup_cipher(password, &salt, kdf)?
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &backup,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to encrypt backup"))?;
+    backup.extend_from_slice(&ciphertext);
+    Ok(backup)
+}
+
+/// Decrypts a backup produced by `export_backup`.
+///
+/// # Errors
+/// Returns error if the backup is truncated, has an unknown version, the password is wrong
+/// or the contents were modified.
+pub fn import_backup(backup: &[u8], password: &str) -> Result<WalletSnapshot> {
+    let start = params_offset(backup)?;
+    let header_len = start + PARAMS_LEN;
+    if backup.len() < header_len {
+        return Err(anyhow!("Backup is too short"));
+    }
+    let param = |i: usize| {
+        let at = start + 4 * i;
+        u32::from_le_bytes(backup[at..at + 4].try_into().unwrap())
+    };
+    let kdf = KdfParams {
+        m_cost: param(0),
+        t_cost: param(1),
+        p_cost: param(2),
+    };
+    let salt = &backup[start + 12..start + 12 + SALT_LEN];
+    let nonce = &backup[start + 12 + SALT_LEN..header_len];
+
+    let plaintext = Zeroizing::new(
+        backup_cipher(password, salt, kdf)?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: &backup[header_len..],
+                    aad: &backup[..header_len],
+                },
+            )
+            .map_err(|_| anyhow!("Wrong password or corrupted backup"))?,
+    );
+    serde_json::from_slice(&plaintext).context("Failed to parse backup contents")
+}
+
+/// Offset of the KDF parameters: past the framing, or past the version byte of an unframed
+/// version 1 backup
+fn params_offset(backup: &[u8]) -> Result<usize> {
+    if format::BACKUP.is_framed(backup) {
+        format::BACKUP.read_header(backup)?;
+        Ok(format::HEADER_LEN)
+    } else if backup.first() == Some(&LEGACY_VERSION) {
+        Ok(1)
+    } else {
+        Err(anyhow!("Not a Vortex backup"))
+    }
+}
+
+fn backup_cipher(password: &str, salt: &[u8], kdf: KdfParams) -> Result<ChaCha20Poly1305> {
+    if kdf.m_cost > MAX_KDF.m_cost || kdf.t_cost > MAX_KDF.t_cost || kdf.p_cost > MAX_KDF.p_cost {
+        return Err(anyhow!(
+            "Backup KDF parameters exceed the supported maximum: {:?}",
+            kdf
+        ));
+    }
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| anyhow!("Invalid backup KDF parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|e| anyhow!("Failed to derive backup key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+    use ark_bn254::Fr;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    /// Cheap parameters so the test does not spend seconds in Argon2
+    const TEST_KDF: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_backup_round_trips_and_rejects_tampering() {
+        let mut rng = ChaCha20Rng::from_seed([6u8; 32]);
+        let note = Note {
+            amount: 42,
+            public_key: "7".to_string(),
+            blinding: "9".to_string(),
+            legacy_commitment: false,
+        };
+        let snapshot = WalletSnapshot {
+            notes: vec![Utxo::new(note, 5, &Fr::from(5u64)).unwrap()],
+            sync_checkpoint: Some(r#"{"leaves":["1","2"]}"#.to_string()),
+        };
+
+        let backup = export_backup_with(&snapshot, "hunter2", TEST_KDF, &mut rng).unwrap();
+        assert_eq!(import_backup(&backup, "hunter2").unwrap(), snapshot);
+        assert!(import_backup(&backup, "hunter3").is_err());
+
+        let mut weakened = backup.clone();
+        weakened[format::HEADER_LEN + 1] ^= 1;
+        assert!(import_backup(&weakened, "hunter2").is_err());
+        let header_len = format::HEADER_LEN + PARAMS_LEN;
+        assert!(import_backup(&backup[..header_len - 1], "hunter2").is_err());
+        assert!(export_backup(&snapshot, "", &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_excessive_kdf_parameters_are_rejected_before_deriving() {
+        let mut rng = ChaCha20Rng::from_seed([8u8; 32]);
+        let backup =
+            export_backup_with(&WalletSnapshot::default(), "hunter2", TEST_KDF, &mut rng).unwrap();
+
+        for i in 0..3 {
+            let mut crafted = backup.clone();
+            let at = format::HEADER_LEN + 4 * i;
+            crafted[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            let start = std::time::Instant::now();
+            let err = import_backup(&crafted, "hunter2").unwrap_err();
+            assert!(err.to_string().contains("exceed"), "{}", err);
+            assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        }
+        assert!(export_backup_with(
+            &WalletSnapshot::default(),
+            "hunter2",
+            KdfParams {
+                m_cost: MAX_KDF.m_cost + 1,
+                ..TEST_KDF
+            },
+            &mut rng
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_unframed_version_1_backup_still_imports() {
+        let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+        let snapshot = WalletSnapshot::default();
+        let framed = export_backup_with(&snapshot, "hunter2", TEST_KDF, &mut rng).unwrap();
+
+        // Same parameters, salt and nonce behind the old single version byte
+        let mut legacy = vec![LEGACY_VERSION];
+        legacy.extend_from_slice(&framed[format::HEADER_LEN..format::HEADER_LEN + PARAMS_LEN]);
+        let salt = &legacy[13..13 + SALT_LEN];
+        let nonce = &legacy[13 + SALT_LEN..];
+        let ciphertext = backup_cipher("hunter2", salt, TEST_KDF)
+            .unwrap()
+            .encrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: &serde_json::to_vec(&snapshot).unwrap(),
+                    aad: &legacy,
+                },
+            )
+            .unwrap();
+        legacy.extend_from_slice(&ciphertext);
+
+        assert_eq!(import_backup(&legacy, "hunter2").unwrap(), snapshot);
+        assert!(import_backup(&[2u8; 64], "hunter2").is_err());
+    }
+}
@@ -0,0 +1,444 @@
+//! Sui full-node JSON-RPC client for pool state (`chain` feature).
+//!
+//! Reads the events and the pool object published by the Move package and converts them into
+//! the crate's own types: commitment events rebuild the `SparseMerkleTree`, nullifier events
+//! form the spent set, and the pool object yields the current root and the root history.
+
+use crate::constants::{MERKLE_TREE_LEVEL, ZERO_VALUE};
+use crate::field::{parse_fr, FieldParseMode};
+use crate::merkle_tree::SparseMerkleTree;
+use crate::poseidon_opt::PoseidonOptimized;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Move event emitted for every output commitment appended to the tree
+pub const COMMITMENT_EVENT: &str = "vortex_events::NewCommitment";
+
+/// Move event emitted for every nullifier consumed by `transact`
+pub const NULLIFIER_EVENT: &str = "vortex_events::NullifierSpent";
+
+/// Coin type of the default SUI pool
+pub const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+
+/// Events requested per `suix_queryEvents` call (the full-node maximum is 50)
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Position in an event stream, as returned by `suix_queryEvents`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventCursor {
+    pub tx_digest: String,
+    pub event_seq: String,
+}
+
+/// One page of decoded events
+#[derive(Debug, Clone)]
+pub struct EventPage<T> {
+    pub events: Vec<T>,
+    pub next_cursor: Option<EventCursor>,
+    pub has_next_page: bool,
+}
+
+/// A commitment appended to the pool's Merkle tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitmentEvent {
+    /// Leaf index in the tree
+    pub index: u64,
+    pub commitment: Fr,
+    /// Note ciphertext for the recipient
+    pub encrypted_output: Vec<u8>,
+}
+
+/// A nullifier marked as spent by the pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullifierEvent {
+    pub nullifier: Fr,
+}
+
+/// Root-related state of the pool object
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolState {
+    /// Root the contract currently reports
+    pub root: Fr,
+    /// Recent roots still accepted by `transact`, oldest slot first
+    pub root_history: Vec<Fr>,
+    /// Number of leaves inserted so far
+    pub next_index: u64,
+}
+
+impl PoolState {
+    /// Whether `root` is still accepted by the contract
+    pub fn is_known_root(&self, root: &Fr) -> bool {
+        self.root_history.contains(root)
+    }
+}
+
+/// Blocking JSON-RPC client bound to one Vortex pool
+pub struct ChainClient {
+    http: reqwest::blocking::Client,
+    rpc_url: String,
+    package_id: String,
+    pool_id: String,
+    coin_type: String,
+}
+
+impl ChainClient {
+    /// Creates a client for the SUI pool `pool_id` of `package_id`, served by `rpc_url`
+    pub fn new(rpc_url: &str, package_id: &str, pool_id: &str) -> Result<Self> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            http,
+            rpc_url: rpc_url.to_string(),
+            package_id: package_id.to_string(),
+            pool_id: pool_id.to_string(),
+            coin_type: SUI_COIN_TYPE.to_string(),
+        })
+    }
+
+    /// Targets a pool for another coin type
+    pub fn with_coin_type(mut self, coin_type: &str) -> Self {
+        self.coin_type = coin_type.to_string();
+        self
+    }
+
+    /// Fetches one page of commitment events after `cursor`
+    pub fn commitment_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<CommitmentEvent>> {
+        self.query_events(COMMITMENT_EVENT, cursor, limit, parse_commitment_event)
+    }
+
+    /// Fetches one page of nullifier events after `cursor`
+    pub fn nullifier_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<NullifierEvent>> {
+        self.query_events(NULLIFIER_EVENT, cursor, limit, parse_nullifier_event)
+    }
+
+    /// Fetches every commitment event since the pool was created
+    pub fn commitment_events(&self) -> Result<Vec<CommitmentEvent>> {
+        collect_pages(|cursor| self.commitment_events_page(cursor, DEFAULT_PAGE_SIZE))
+    }
+
+    /// Fetches every nullifier event since the pool was created
+    pub fn nullifier_events(&self) -> Result<Vec<NullifierEvent>> {
+        collect_pages(|cursor| self.nullifier_events_page(cursor, DEFAULT_PAGE_SIZE))
+    }
+
+    /// Reads the current root, root history and next leaf index from the pool object
+    pub fn pool_state(&self) -> Result<PoolState> {
+        let object: Value = self.call(
+            "sui_getObject",
+            json!([self.pool_id, { "showContent": true }]),
+        )?;
+        let fields = object
+            .pointer("/data/content/fields")
+            .ok_or_else(|| anyhow!("Pool object {} has no Move content", self.pool_id))?;
+
+        parse_pool_state(fields)
+    }
+
+    fn query_events<T>(
+        &self,
+        event: &str,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+        parse: fn(&Value) -> Result<T>,
+    ) -> Result<EventPage<T>> {
+        let event_type = format!("{}::{}<{}>", self.package_id, event, self.coin_type);
+        let page: RawEventPage = self.call(
+            "suix_queryEvents",
+            json!([{ "MoveEventType": event_type }, cursor, limit, false]),
+        )?;
+
+        let events = page
+            .data
+            .iter()
+            .map(|event| {
+                parse(&event.parsed_json).with_context(|| {
+                    format!("Malformed {} event in {}", event_type, event.id.tx_digest)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(EventPage {
+            events,
+            next_cursor: page.next_cursor,
+            has_next_page: page.has_next_page,
+        })
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: RpcResponse<T> = self
+            .http
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .with_context(|| format!("{} request to {} failed", method, self.rpc_url))?
+            .error_for_status()
+            .with_context(|| format!("{} returned an HTTP error", method))?
+            .json()
+            .with_context(|| format!("Failed to decode {} response", method))?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(anyhow!(
+                "{} failed ({}): {}",
+                method,
+                error.code,
+                error.message
+            )),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(anyhow!("{} returned neither result nor error", method)),
+        }
+    }
+}
+
+/// Rebuilds the pool tree from commitment events, which may arrive in any order.
+///
+/// # Errors
+/// Returns error if the indices are not exactly `0..events.len()`.
+pub fn build_tree(events: &[CommitmentEvent]) -> Result<SparseMerkleTree<MERKLE_TREE_LEVEL>> {
+    let mut sorted: Vec<&CommitmentEvent> = events.iter().collect();
+    sorted.sort_by_key(|event| event.index);
+
+    for (expected, event) in sorted.iter().enumerate() {
+        if event.index != expected as u64 {
+            return Err(anyhow!(
+                "Commitment events are not contiguous: expected index {}, got {}",
+                expected,
+                event.index
+            ));
+        }
+    }
+
+    let hasher = PoseidonOptimized::new_t3();
+    let empty_leaf = parse_fr(ZERO_VALUE, FieldParseMode::Strict)?;
+    let mut tree = SparseMerkleTree::new_empty(&hasher, &empty_leaf);
+    let leaves: Vec<Fr> = sorted.iter().map(|event| event.commitment).collect();
+    tree.bulk_insert(&leaves, &hasher)?;
+
+    Ok(tree)
+}
+
+/// Collects nullifier events into the set of spent nullifiers
+pub fn nullifier_set(events: &[NullifierEvent]) -> HashSet<Fr> {
+    events.iter().map(|event| event.nullifier).collect()
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEventPage {
+    data: Vec<RawEvent>,
+    next_cursor: Option<EventCursor>,
+    has_next_page: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEvent {
+    id: EventCursor,
+    parsed_json: Value,
+}
+
+fn collect_pages<T>(
+    mut fetch: impl FnMut(Option<&EventCursor>) -> Result<EventPage<T>>,
+) -> Result<Vec<T>> {
+    let mut all = Vec::new();
+    let mut cursor: Option<EventCursor> = None;
+
+    loop {
+        let page = fetch(cursor.as_ref())?;
+        all.extend(page.events);
+        match page.next_cursor {
+            Some(next) if page.has_next_page => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(all)
+}
+
+fn parse_commitment_event(json: &Value) -> Result<CommitmentEvent> {
+    Ok(CommitmentEvent {
+        index: parse_u64(field(json, "index")?)?,
+        commitment: parse_u256(field(json, "commitment")?)?,
+        encrypted_output: parse_bytes(field(json, "encrypted_output")?)?,
+    })
+}
+
+fn parse_nullifier_event(json: &Value) -> Result<NullifierEvent> {
+    Ok(NullifierEvent {
+        nullifier: parse_u256(field(json, "nullifier")?)?,
+    })
+}
+
+/// The history is a ring buffer: `current_root_index` points at the latest root
+fn parse_pool_state(fields: &Value) -> Result<PoolState> {
+    let root_history = find_field(fields, "root_history")
+        .ok_or_else(|| anyhow!("Pool object has no root_history field"))?
+        .as_array()
+        .ok_or_else(|| anyhow!("root_history is not a vector"))?
+        .iter()
+        .map(parse_u256)
+        .collect::<Result<Vec<Fr>>>()?;
+    let current = find_field(fields, "current_root_index")
+        .ok_or_else(|| anyhow!("Pool object has no current_root_index field"))
+        .and_then(parse_u64)?;
+    let next_index = find_field(fields, "next_index")
+        .ok_or_else(|| anyhow!("Pool object has no next_index field"))
+        .and_then(parse_u64)?;
+
+    let root = *root_history.get(current as usize).ok_or_else(|| {
+        anyhow!(
+            "current_root_index {} is outside a root history of {}",
+            current,
+            root_history.len()
+        )
+    })?;
+
+    Ok(PoolState {
+        root,
+        root_history,
+        next_index,
+    })
+}
+
+fn field<'a>(json: &'a Value, name: &str) -> Result<&'a Value> {
+    json.get(name)
+        .ok_or_else(|| anyhow!("Missing field '{}'", name))
+}
+
+/// Finds `name` in the object or any nested struct (e.g. a `merkle_tree` sub-object)
+fn find_field<'a>(json: &'a Value, name: &str) -> Option<&'a Value> {
+    match json {
+        Value::Object(map) => map
+            .get(name)
+            .or_else(|| map.values().find_map(|nested| find_field(nested, name))),
+        _ => None,
+    }
+}
+
+/// Move u64 values are rendered as JSON strings
+fn parse_u64(value: &Value) -> Result<u64> {
+    match value {
+        Value::String(s) => s.parse().with_context(|| format!("Invalid u64 '{}'", s)),
+        Value::Number(n) => n.as_u64().ok_or_else(|| anyhow!("Invalid u64 {}", n)),
+        other => Err(anyhow!("Expected u64, got {}", other)),
+    }
+}
+
+fn parse_u256(value: &Value) -> Result<Fr> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| anyhow!("Expected u256 string, got {}", value))?;
+    parse_fr(s, FieldParseMode::Strict)
+}
+
+/// `vector<u8>` is a number array over JSON-RPC and base64 over GraphQL-backed indexers
+fn parse_bytes(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_u64()
+                    .and_then(|b| u8::try_from(b).ok())
+                    .ok_or_else(|| anyhow!("Invalid byte {}", item))
+            })
+            .collect(),
+        Value::String(s) => base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .context("Invalid base64 bytes"),
+        other => Err(anyhow!("Expected bytes, got {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commitment_event_formats() {
+        let from_rpc = json!({ "index": "3", "commitment": "42", "encrypted_output": [1, 2, 255] });
+        let from_indexer = json!({ "index": "3", "commitment": "42", "encrypted_output": "AQL/" });
+
+        let expected = CommitmentEvent {
+            index: 3,
+            commitment: Fr::from(42u64),
+            encrypted_output: vec![1, 2, 255],
+        };
+        assert_eq!(parse_commitment_event(&from_rpc).unwrap(), expected);
+        assert_eq!(parse_commitment_event(&from_indexer).unwrap(), expected);
+        assert!(parse_commitment_event(&json!({ "index": "3" })).is_err());
+    }
+
+    #[test]
+    fn test_build_tree_requires_contiguous_indices() {
+        let event = |index: u64| CommitmentEvent {
+            index,
+            commitment: Fr::from(index + 100),
+            encrypted_output: Vec::new(),
+        };
+
+        let tree = build_tree(&[event(1), event(0), event(3), event(2)]).unwrap();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.leaves[0], Fr::from(100u64));
+
+        assert!(build_tree(&[event(0), event(2)]).is_err());
+    }
+
+    #[test]
+    fn test_parse_pool_state_from_nested_fields() {
+        let fields = json!({
+            "id": { "id": "0x1" },
+            "merkle_tree": {
+                "type": "0x2::vortex_merkle_tree::MerkleTree",
+                "fields": {
+                    "root_history": ["7", "8", "9"],
+                    "current_root_index": "1",
+                    "next_index": "6"
+                }
+            }
+        });
+
+        let state = parse_pool_state(&fields).unwrap();
+        assert_eq!(state.root, Fr::from(8u64));
+        assert_eq!(state.next_index, 6);
+        assert!(state.is_known_root(&Fr::from(9u64)));
+        assert!(!state.is_known_root(&Fr::from(10u64)));
+    }
+}
@@ -0,0 +1,1851 @@
+use crate::{
+    constants::{AUTHORITY_TREE_LEVEL, MAX_AMOUNT_BITS, MERKLE_TREE_LEVEL, N_INS, N_OUTS},
+    debug,
+    merkle_tree::{Path, PathVar},
+    poseidon_opt::{hash1, hash2, hash3, hash4, PoseidonOptimized, PoseidonOptimizedVar},
+    rotation,
+};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean, EqGadget, FieldVar},
+};
+
+use alloc::{format, vec, vec::Vec};
+use ark_relations::{
+    ns,
+    r1cs::{self, ConstraintSynthesizer, ConstraintSystemRef},
+};
+use ark_serialize::CanonicalSerialize;
+use core::ops::Not;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Transaction circuit for privacy-preserving value transfers on Sui.
+///
+/// This circuit implements a 2-input, 2-output transaction model where:
+/// - Users can spend up to 2 input UTXOs (zero amounts allowed)
+/// - Create up to 2 output UTXOs (zero amounts allowed)
+/// - Add/remove value from the pool via `public_amount`
+///
+/// # Privacy Guarantees
+///
+/// - Input amounts, recipients, and senders are hidden
+/// - Only nullifiers and output commitments are public
+/// - Links between inputs and outputs are obfuscated
+///
+/// # Security Properties
+///
+/// 1. **No double-spending**: Each nullifier can only be used once
+/// 2. **Amount conservation**: Σinputs + public_amount = Σoutputs  
+/// 3. **Valid proofs**: All non-zero inputs have valid Merkle proofs, and each path index
+///    matches the left/right structure of its path
+/// 4. **No overflow**: All amounts fit in 248 bits
+/// 5. **Unique nullifiers**: No duplicate nullifiers in same transaction
+/// 6. **Bound ext data**: `ext_data_hash` is a constrained public input
+/// 7. **Signed public amount**: `public_amount` is a deposit `x` or a withdrawal `p - x`
+///    with `x < 2^248`, so it cannot wrap around the field
+///
+/// # Commitment Scheme
+///
+/// - Input commitment: `Poseidon4(amount, pubkey, blinding, vortex)`, or with mixed-era
+///   inputs the legacy `Poseidon3(amount, pubkey, blinding)` when the input's
+///   `in_legacy_commitments` flag is set
+/// - Output commitment: `Poseidon4(amount, pubkey, blinding, vortex)`, with `blinding`
+///   replaced by `Poseidon2(blinding, ciphertext_hash)` when ciphertexts are bound
+/// - Nullifier: `Poseidon3(commitment, path_index, signature)`
+/// - Signature: `Poseidon3(privkey, commitment, path_index)`
+/// - Public key: `Poseidon1(privkey)`
+///
+/// # Mixed-Era Inputs
+///
+/// Notes deposited before the commitment scheme was bound to the pool address are still
+/// spendable through `with_mixed_era_inputs`. Each input then carries a private boolean
+/// selecting which formula its commitment is recomputed with, so the choice is hidden and old
+/// and new notes can be spent together. Outputs always use the current formula, so spending
+/// a legacy note migrates its value. The selectors and the extra Poseidon3 per input are only
+/// synthesized in this shape, which has its own key pair; the default circuit accepts current
+/// commitments only.
+///
+/// # Dummy Input Slots
+///
+/// Slots flagged in `dummy_inputs` are fixed as zero-amount dummies when the circuit is
+/// synthesized: their Merkle membership gadget is left out entirely and the amount is pinned
+/// to zero instead. This changes the constraint system, so each dummy-slot layout needs its
+/// own proving/verifying key pair (see `with_dummy_inputs`).
+///
+/// The verifying key therefore names the dummy slots. The pool contract registers a single
+/// verifying key, so a layout is a whole-pool choice: every proof in that pool has the same
+/// public layout, which reveals nothing per transaction but caps how many notes it can
+/// spend. Mixing layouts in one pool, by registering several verifying keys, would tell
+/// observers how many real notes each transaction spends and undo the padding of the
+/// `padding` module. Wallets that pad must use the default shape.
+///
+/// # Ciphertext Binding
+///
+/// `with_ciphertext_binding` adds one public input per output holding the hash of its
+/// published encrypted note (see `viewing::ciphertext_hash`) and folds it into the output's
+/// blinding. The contract hashes the ciphertexts it emits and passes them in, so a proof only
+/// verifies if the recipient is sent the exact ciphertext their commitment was built for,
+/// ruling out payments nobody can decrypt. The recipient spends the note with the bound
+/// blinding. This is a separate shape with its own key pair.
+///
+/// # Key Rotation
+///
+/// `with_key_rotation` adds an `authority_root` public input, the root of the pool's tree of
+/// registered authorities (see the `rotation` module). Each input carries a private selector:
+/// unset, its authority is the tag of the note's own key; set, it is the link from that tag to
+/// a rotated-in key the prover must also know. The authority is proven to be a leaf of the
+/// tree along a private path, so no per-key value is published and spends stay unlinkable.
+/// The note key still derives the nullifier, so a note has one nullifier whichever way it is
+/// authorized. Zero-amount inputs are exempt so padding stays free. This is another shape
+/// with its own key pair.
+///
+/// # Account Secret Check
+///
+/// Deployments without account registration build the circuit with
+/// `without_account_secret`, which removes the `hashed_account_secret` public input and its
+/// Poseidon check. This is also a different shape with its own key pair.
+///
+/// # Zeroization
+///
+/// Every field is wiped when the circuit is dropped, so the private keys, blindings and
+/// account secret do not outlive the proof that consumed them. For the same reason `Debug`
+/// prints only the public inputs and the shape flags.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct TransactionCircuit {
+    // Public inputs (must match order expected by Move contract verification)
+    // Individual fields to match how they're allocated in generate_constraints()
+    pub vortex: Fr,
+    pub root: Fr,
+    pub public_amount: Fr,
+    pub input_nullifier_0: Fr,
+    pub input_nullifier_1: Fr,
+    pub output_commitment_0: Fr,
+    pub output_commitment_1: Fr,
+    pub hashed_account_secret: Fr,
+    pub output_ciphertext_hashes: [Fr; N_OUTS],
+    pub authority_root: Fr,
+    pub ext_data_hash: Fr,
+
+    // Private inputs - Input UTXOs
+    pub account_secret: Fr,
+    pub in_private_keys: [Fr; N_INS],
+    pub in_amounts: [Fr; N_INS],
+    pub in_blindings: [Fr; N_INS],
+    pub in_path_indices: [Fr; N_INS],
+    pub merkle_paths: [Path<MERKLE_TREE_LEVEL>; N_INS],
+    // Per-input selector for the legacy `Poseidon3` commitment
+    pub in_legacy_commitments: [bool; N_INS],
+    // Per-input selector for authorizing through a rotation, the rotated-in keys, and where
+    // each input's authority sits in the authority tree
+    pub in_key_rotated: [bool; N_INS],
+    pub in_rotated_private_keys: [Fr; N_INS],
+    pub in_authority_indices: [Fr; N_INS],
+    pub in_authority_paths: [Path<AUTHORITY_TREE_LEVEL>; N_INS],
+
+    // Private inputs - Output UTXOs
+    pub out_public_keys: [Fr; N_OUTS],
+    pub out_amounts: [Fr; N_OUTS],
+    pub out_blindings: [Fr; N_OUTS],
+
+    // Circuit shape - input slots synthesized without a Merkle membership check
+    pub dummy_inputs: [bool; N_INS],
+    // Circuit shape - whether `hashed_account_secret` is a public input checked against
+    // `account_secret`
+    pub account_secret_check: bool,
+    // Circuit shape - whether output blindings are bound to `output_ciphertext_hashes`
+    pub ciphertext_binding: bool,
+    // Circuit shape - whether every input proves its authority under `authority_root`
+    pub key_rotation: bool,
+    // Circuit shape - whether inputs may select the legacy commitment formula
+    pub mixed_era_inputs: bool,
+}
+
+/// The shape flags of a `TransactionCircuit`. Circuits with equal shapes synthesize the same
+/// constraint matrices and are proven with the same keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CircuitShape {
+    pub dummy_inputs: [bool; N_INS],
+    pub account_secret_check: bool,
+    pub ciphertext_binding: bool,
+    pub key_rotation: bool,
+    pub mixed_era_inputs: bool,
+}
+
+impl core::fmt::Debug for TransactionCircuit {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TransactionCircuit")
+            .field("vortex", &self.vortex)
+            .field("root", &self.root)
+            .field("public_amount", &self.public_amount)
+            .field("input_nullifier_0", &self.input_nullifier_0)
+            .field("input_nullifier_1", &self.input_nullifier_1)
+            .field("output_commitment_0", &self.output_commitment_0)
+            .field("output_commitment_1", &self.output_commitment_1)
+            .field("hashed_account_secret", &self.hashed_account_secret)
+            .field("output_ciphertext_hashes", &self.output_ciphertext_hashes)
+            .field("authority_root", &self.authority_root)
+            .field("ext_data_hash", &self.ext_data_hash)
+            .field("account_secret", &"<redacted>")
+            .field("in_private_keys", &"<redacted>")
+            .field("in_blindings", &"<redacted>")
+            .field("in_rotated_private_keys", &"<redacted>")
+            .field("in_authority_paths", &"<redacted>")
+            .field("out_blindings", &"<redacted>")
+            .field("dummy_inputs", &self.dummy_inputs)
+            .field("account_secret_check", &self.account_secret_check)
+            .field("ciphertext_binding", &self.ciphertext_binding)
+            .field("key_rotation", &self.key_rotation)
+            .field("mixed_era_inputs", &self.mixed_era_inputs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TransactionCircuit {
+    /// Creates an empty circuit with all values set to zero.
+    /// Used for setup phase and testing.
+    pub fn empty() -> Self {
+        Self {
+            vortex: Fr::ZERO,
+            root: Fr::ZERO,
+            public_amount: Fr::ZERO,
+            input_nullifier_0: Fr::ZERO,
+            input_nullifier_1: Fr::ZERO,
+            output_commitment_0: Fr::ZERO,
+            output_commitment_1: Fr::ZERO,
+            hashed_account_secret: Fr::ZERO,
+            output_ciphertext_hashes: [Fr::ZERO; N_OUTS],
+            authority_root: Fr::ZERO,
+            ext_data_hash: Fr::ZERO,
+
+            account_secret: Fr::ZERO,
+            in_private_keys: [Fr::ZERO; N_INS],
+            in_amounts: [Fr::ZERO; N_INS],
+            in_blindings: [Fr::ZERO; N_INS],
+            in_path_indices: [Fr::ZERO; N_INS],
+            merkle_paths: [Path::empty(); N_INS],
+            in_legacy_commitments: [false; N_INS],
+            in_key_rotated: [false; N_INS],
+            in_rotated_private_keys: [Fr::ZERO; N_INS],
+            in_authority_indices: [Fr::ZERO; N_INS],
+            in_authority_paths: [Path::empty(); N_INS],
+
+            out_public_keys: [Fr::ZERO; N_OUTS],
+            out_amounts: [Fr::ZERO; N_OUTS],
+            out_blindings: [Fr::ZERO; N_OUTS],
+
+            dummy_inputs: [false; N_INS],
+            account_secret_check: true,
+            ciphertext_binding: false,
+            key_rotation: false,
+            mixed_era_inputs: false,
+        }
+    }
+
+    /// Marks input slots as dummies, dropping their Merkle membership gadget.
+    ///
+    /// The resulting circuit has a different shape than the default one, so it must be
+    /// proven with keys generated from `TransactionCircuit::empty().with_dummy_inputs(..)`
+    /// using the same flags. Those keys reveal the layout, so only use them for a pool that
+    /// registers them as its single verifying key (see "Dummy Input Slots" above).
+    ///
+    /// # Errors
+    /// Returns error if a slot flagged as dummy carries a non-zero amount.
+    pub fn with_dummy_inputs(mut self, dummy_inputs: [bool; N_INS]) -> anyhow::Result<Self> {
+        for (i, (is_dummy, amount)) in dummy_inputs.iter().zip(self.in_amounts.iter()).enumerate() {
+            if *is_dummy && *amount != Fr::ZERO {
+                return Err(anyhow::anyhow!(
+                    "Input {} is marked as dummy but has a non-zero amount",
+                    i
+                ));
+            }
+        }
+
+        self.dummy_inputs = dummy_inputs;
+        Ok(self)
+    }
+
+    /// Drops the `hashed_account_secret` public input and its Poseidon check, for deployments
+    /// without account registration.
+    ///
+    /// Saves one t=2 permutation and leaves eight public inputs. Like dummy slots, this changes
+    /// the circuit shape and needs keys generated from
+    /// `TransactionCircuit::empty().without_account_secret()`.
+    pub fn without_account_secret(mut self) -> Self {
+        self.account_secret_check = false;
+        self.hashed_account_secret = Fr::ZERO;
+        self.account_secret = Fr::ZERO;
+        self
+    }
+
+    /// Binds each output to the hash of its encrypted note.
+    ///
+    /// `out_blindings` stay the blindings sent to the recipient; the circuit commits to
+    /// `Poseidon2(blinding, ciphertext_hash)` instead. Needs keys generated from
+    /// `TransactionCircuit::empty().with_ciphertext_binding(..)`.
+    pub fn with_ciphertext_binding(mut self, output_ciphertext_hashes: [Fr; N_OUTS]) -> Self {
+        self.ciphertext_binding = true;
+        self.output_ciphertext_hashes = output_ciphertext_hashes;
+        self
+    }
+
+    /// Exposes `authority_root` and checks that each non-zero input's authority is a leaf of
+    /// the authority tree with that root.
+    ///
+    /// Inputs with a rotated-in key are authorized through the rotation link from their note
+    /// key; the rest by their note key's tag. Input `i`'s authority sits at
+    /// `in_authority_indices[i]` along `in_authority_paths[i]`. Needs keys generated from
+    /// `TransactionCircuit::empty().with_key_rotation(..)`.
+    pub fn with_key_rotation(
+        mut self,
+        authority_root: Fr,
+        in_rotated_private_keys: [Option<Fr>; N_INS],
+        in_authority_indices: [Fr; N_INS],
+        in_authority_paths: [Path<AUTHORITY_TREE_LEVEL>; N_INS],
+    ) -> Self {
+        self.key_rotation = true;
+        self.authority_root = authority_root;
+        for (i, rotated_key) in in_rotated_private_keys.iter().enumerate() {
+            self.in_key_rotated[i] = rotated_key.is_some();
+            self.in_rotated_private_keys[i] = rotated_key.unwrap_or(Fr::ZERO);
+        }
+        self.in_authority_indices = in_authority_indices;
+        self.in_authority_paths = in_authority_paths;
+        self
+    }
+
+    /// Flags inputs whose notes were committed with the legacy
+    /// `Poseidon3(amount, pubkey, blinding)` formula.
+    ///
+    /// The flags are private witnesses of the mixed-era shape: setting any of them requires
+    /// `with_mixed_era_inputs`, or `check_public_inputs` rejects the circuit.
+    pub fn with_legacy_commitments(mut self, in_legacy_commitments: [bool; N_INS]) -> Self {
+        self.in_legacy_commitments = in_legacy_commitments;
+        self
+    }
+
+    /// Lets each input select the legacy commitment formula with a private flag.
+    ///
+    /// Adds a boolean witness and a Poseidon3 per input, so this changes the circuit shape and
+    /// needs keys generated from `TransactionCircuit::empty().with_mixed_era_inputs()`.
+    pub fn with_mixed_era_inputs(mut self) -> Self {
+        self.mixed_era_inputs = true;
+        self
+    }
+
+    /// Binds a hash of application data (relayer, fee, deadline, chain id, ...) to the proof.
+    ///
+    /// The value is a public input that the circuit squares, so a proof cannot be replayed
+    /// with different ext data. Defaults to zero.
+    pub fn with_ext_data_hash(mut self, ext_data_hash: Fr) -> Self {
+        self.ext_data_hash = ext_data_hash;
+        self
+    }
+
+    /// The flags that determine this circuit's constraint layout
+    pub fn shape(&self) -> CircuitShape {
+        CircuitShape {
+            dummy_inputs: self.dummy_inputs,
+            account_secret_check: self.account_secret_check,
+            ciphertext_binding: self.ciphertext_binding,
+            key_rotation: self.key_rotation,
+            mixed_era_inputs: self.mixed_era_inputs,
+        }
+    }
+
+    /// Creates a new circuit with validation.
+    ///
+    /// # Errors
+    /// Returns error if:
+    /// - Path indices exceed tree capacity (>= 2^LEVEL)
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        vortex: Fr,
+        root: Fr,
+        public_amount: Fr,
+        input_nullifier_0: Fr,
+        input_nullifier_1: Fr,
+        output_commitment_0: Fr,
+        output_commitment_1: Fr,
+        hashed_account_secret: Fr,
+        account_secret: Fr,
+        in_private_keys: [Fr; N_INS],
+        in_amounts: [Fr; N_INS],
+        in_blindings: [Fr; N_INS],
+        in_path_indices: [Fr; N_INS],
+        merkle_paths: [Path<MERKLE_TREE_LEVEL>; N_INS],
+        out_public_keys: [Fr; N_OUTS],
+        out_amounts: [Fr; N_OUTS],
+        out_blindings: [Fr; N_OUTS],
+    ) -> anyhow::Result<Self> {
+        // Validate path indices fit in tree
+        let max_index = Fr::from(1u128 << MERKLE_TREE_LEVEL);
+        for (i, idx) in in_path_indices.iter().enumerate() {
+            if *idx >= max_index {
+                return Err(anyhow::anyhow!(
+                    "Input {} path index exceeds tree capacity (>= 2^{})",
+                    i,
+                    MERKLE_TREE_LEVEL
+                ));
+            }
+        }
+
+        Ok(Self {
+            vortex,
+            root,
+            public_amount,
+            input_nullifier_0,
+            input_nullifier_1,
+            output_commitment_0,
+            output_commitment_1,
+            hashed_account_secret,
+            output_ciphertext_hashes: [Fr::ZERO; N_OUTS],
+            authority_root: Fr::ZERO,
+            ext_data_hash: Fr::ZERO,
+            account_secret,
+            in_private_keys,
+            in_amounts,
+            in_blindings,
+            in_path_indices,
+            merkle_paths,
+            in_legacy_commitments: [false; N_INS],
+            in_key_rotated: [false; N_INS],
+            in_rotated_private_keys: [Fr::ZERO; N_INS],
+            in_authority_indices: [Fr::ZERO; N_INS],
+            in_authority_paths: [Path::empty(); N_INS],
+            out_public_keys,
+            out_amounts,
+            out_blindings,
+            dummy_inputs: [false; N_INS],
+            account_secret_check: true,
+            ciphertext_binding: false,
+            key_rotation: false,
+            mixed_era_inputs: false,
+        })
+    }
+
+    /// Returns public inputs in the order they are allocated in `generate_constraints()`.
+    ///
+    /// This order MUST match the order in which `FpVar::new_input()` is called in
+    /// `generate_constraints()` to ensure correct proof generation and verification.
+    ///
+    /// # Order
+    /// 1. vortex
+    /// 2. root
+    /// 3. public_amount
+    /// 4. input_nullifier_0
+    /// 5. input_nullifier_1
+    /// 6. output_commitment_0
+    /// 7. output_commitment_1
+    /// 8. hashed_account_secret (left out without the account secret check)
+    /// 9. output_ciphertext_hash_0 and output_ciphertext_hash_1 (only with ciphertext binding)
+    /// 10. authority_root (only with key rotation)
+    /// 11. ext_data_hash
+    ///
+    /// # Note
+    /// This method extracts public inputs from the circuit struct. Groth16's `prove()` function
+    /// extracts them from the constraint system in the same order. The values should match exactly.
+    pub fn get_public_inputs(&self) -> Vec<Fr> {
+        let mut inputs = vec![
+            self.vortex,
+            self.root,
+            self.public_amount,
+            self.input_nullifier_0,
+            self.input_nullifier_1,
+            self.output_commitment_0,
+            self.output_commitment_1,
+        ];
+        if self.account_secret_check {
+            inputs.push(self.hashed_account_secret);
+        }
+        if self.ciphertext_binding {
+            inputs.extend_from_slice(&self.output_ciphertext_hashes);
+        }
+        if self.key_rotation {
+            inputs.push(self.authority_root);
+        }
+        inputs.push(self.ext_data_hash);
+        inputs
+    }
+
+    /// Names of the public inputs in the order of `get_public_inputs()`, spelled as the
+    /// `ProofInput` JSON keys
+    pub fn public_input_names(&self) -> Vec<&'static str> {
+        let mut names = vec![
+            "vortex",
+            "root",
+            "publicAmount",
+            "inputNullifier0",
+            "inputNullifier1",
+            "outputCommitment0",
+            "outputCommitment1",
+        ];
+        if self.account_secret_check {
+            names.push("hashedAccountSecret");
+        }
+        if self.ciphertext_binding {
+            names.extend_from_slice(&["outputCiphertextHash0", "outputCiphertextHash1"]);
+        }
+        if self.key_rotation {
+            names.push("authorityRoot");
+        }
+        names.push("extDataHash");
+        names
+    }
+
+    /// Returns serialized public inputs in compressed format.
+    ///
+    /// This serializes each public input field element using `serialize_compressed()` and
+    /// concatenates them into a single byte vector. The order matches `get_public_inputs()`.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` containing the serialized public inputs, or an error if serialization fails.
+    pub fn get_public_inputs_serialized(&self) -> anyhow::Result<Vec<u8>> {
+        let public_inputs = self.get_public_inputs();
+        let mut serialized = Vec::new();
+        for input in &public_inputs {
+            input
+                .serialize_compressed(&mut serialized)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize public input: {}", e))?;
+        }
+        Ok(serialized)
+    }
+
+    /// Recomputes the derived public inputs natively from the private fields.
+    ///
+    /// Checks, in order, `hashed_account_secret` (only when non-zero, as in the circuit),
+    /// the magnitude of `public_amount`, each `input_nullifier_i`, the `root` and
+    /// `in_path_index_i` for every non-zero, non-dummy input, `authority_root` for every
+    /// non-zero input when key rotation is on, and each `output_commitment_i`.
+    /// This catches inconsistent inputs before the expensive proving step, which would
+    /// otherwise fail or yield an unverifiable proof.
+    ///
+    /// # Errors
+    /// Returns error naming the first public input that does not match its recomputed value.
+    pub fn check_public_inputs(&self) -> anyhow::Result<()> {
+        if self.account_secret_check
+            && self.hashed_account_secret != Fr::ZERO
+            && hash1(&self.account_secret) != self.hashed_account_secret
+        {
+            return Err(anyhow::anyhow!(
+                "hashed_account_secret does not match Poseidon1(account_secret)"
+            ));
+        }
+
+        let (_, magnitude) = split_public_amount(&self.public_amount);
+        if magnitude.into_bigint().num_bits() as usize > MAX_AMOUNT_BITS {
+            return Err(anyhow::anyhow!(
+                "public_amount is neither a deposit nor a withdrawal below 2^{}",
+                MAX_AMOUNT_BITS
+            ));
+        }
+
+        let tree_hasher = PoseidonOptimized::new_t3();
+        for i in 0..N_INS {
+            if self.in_legacy_commitments[i] && !self.mixed_era_inputs {
+                return Err(anyhow::anyhow!(
+                    "Input {} uses a legacy commitment, which needs the mixed-era circuit shape",
+                    i
+                ));
+            }
+
+            let public_key = hash1(&self.in_private_keys[i]);
+            let commitment = if self.in_legacy_commitments[i] {
+                compute_legacy_commitment(&self.in_amounts[i], &public_key, &self.in_blindings[i])
+            } else {
+                compute_commitment(
+                    &self.in_amounts[i],
+                    &public_key,
+                    &self.in_blindings[i],
+                    &self.vortex,
+                )
+            };
+            let nullifier = compute_nullifier(
+                &commitment,
+                &self.in_path_indices[i],
+                &self.in_private_keys[i],
+            );
+
+            let expected_nullifier = [self.input_nullifier_0, self.input_nullifier_1][i];
+            if nullifier != expected_nullifier {
+                return Err(anyhow::anyhow!(
+                    "input_nullifier_{} does not match the nullifier derived from input {}",
+                    i,
+                    i
+                ));
+            }
+
+            if !self.dummy_inputs[i]
+                && self.in_amounts[i] != Fr::ZERO
+                && !self.merkle_paths[i].check_membership_at(
+                    &self.root,
+                    &commitment,
+                    &self.in_path_indices[i],
+                    &tree_hasher,
+                )?
+            {
+                return Err(anyhow::anyhow!(
+                    "root does not match the root computed from merkle_path_{} at in_path_index_{}",
+                    i,
+                    i
+                ));
+            }
+
+            if self.key_rotation && self.in_amounts[i] != Fr::ZERO {
+                let rotated_key =
+                    self.in_key_rotated[i].then_some(&self.in_rotated_private_keys[i]);
+                let authority = rotation::authority_leaf(&self.in_private_keys[i], rotated_key);
+                if !self.in_authority_paths[i].check_membership_at(
+                    &self.authority_root,
+                    &authority,
+                    &self.in_authority_indices[i],
+                    &tree_hasher,
+                )? {
+                    return Err(anyhow::anyhow!(
+                        "authority_root does not match the root computed from the authority of input {} along authority_path_{} at in_authority_index_{}",
+                        i,
+                        i,
+                        i
+                    ));
+                }
+            }
+        }
+
+        for i in 0..N_OUTS {
+            let blinding = if self.ciphertext_binding {
+                bind_ciphertext(&self.out_blindings[i], &self.output_ciphertext_hashes[i])
+            } else {
+                self.out_blindings[i]
+            };
+            let commitment = compute_commitment(
+                &self.out_amounts[i],
+                &self.out_public_keys[i],
+                &blinding,
+                &self.vortex,
+            );
+
+            let expected_commitment = [self.output_commitment_0, self.output_commitment_1][i];
+            if commitment != expected_commitment {
+                return Err(anyhow::anyhow!(
+                    "output_commitment_{} does not match the commitment derived from output {}",
+                    i,
+                    i
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Native commitment: `Poseidon4(amount, pubkey, blinding, vortex)`
+pub fn compute_commitment(amount: &Fr, public_key: &Fr, blinding: &Fr, vortex: &Fr) -> Fr {
+    hash4(amount, public_key, blinding, vortex)
+}
+
+/// Native legacy commitment: `Poseidon3(amount, pubkey, blinding)`, used by notes created
+/// before commitments were bound to the pool
+pub fn compute_legacy_commitment(amount: &Fr, public_key: &Fr, blinding: &Fr) -> Fr {
+    hash3(amount, public_key, blinding)
+}
+
+/// Blinding committed to by an output bound to its ciphertext:
+/// `Poseidon2(blinding, ciphertext_hash)`
+pub fn bind_ciphertext(blinding: &Fr, ciphertext_hash: &Fr) -> Fr {
+    hash2(blinding, ciphertext_hash)
+}
+
+/// Splits `public_amount` into `(is_withdrawal, magnitude)`.
+///
+/// Values wider than `MAX_AMOUNT_BITS` are read as withdrawals `p - magnitude`, matching the
+/// sign witness the circuit picks. The magnitude is not range checked here.
+pub fn split_public_amount(public_amount: &Fr) -> (bool, Fr) {
+    if public_amount.into_bigint().num_bits() as usize > MAX_AMOUNT_BITS {
+        (true, -*public_amount)
+    } else {
+        (false, *public_amount)
+    }
+}
+
+/// Native nullifier: `Poseidon3(commitment, path_index, Poseidon3(privkey, commitment, path_index))`
+pub fn compute_nullifier(commitment: &Fr, path_index: &Fr, private_key: &Fr) -> Fr {
+    let signature = hash3(private_key, commitment, path_index);
+    hash3(commitment, path_index, &signature)
+}
+
+impl ConstraintSynthesizer<Fr> for TransactionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> r1cs::Result<()> {
+        // ============================================
+        // ALLOCATE PUBLIC INPUTS
+        // Order must match Move contract's verification expectations
+        // Note: In Move, these are serialized as individual elements, not vectors
+        // ============================================
+        let vortex = FpVar::new_input(ns!(cs, "vortex"), || Ok(self.vortex))?;
+
+        let root = FpVar::new_input(ns!(cs, "root"), || Ok(self.root))?;
+
+        let public_amount = FpVar::new_input(ns!(cs, "public_amount"), || Ok(self.public_amount))?;
+
+        let input_nullifier_0 =
+            FpVar::new_input(ns!(cs, "input_nullifier_0"), || Ok(self.input_nullifier_0))?;
+
+        let input_nullifier_1 =
+            FpVar::new_input(ns!(cs, "input_nullifier_1"), || Ok(self.input_nullifier_1))?;
+
+        let output_commitment_0 = FpVar::new_input(ns!(cs, "output_commitment_0"), || {
+            Ok(self.output_commitment_0)
+        })?;
+
+        let output_commitment_1 = FpVar::new_input(ns!(cs, "output_commitment_1"), || {
+            Ok(self.output_commitment_1)
+        })?;
+
+        let hashed_account_secret = self
+            .account_secret_check
+            .then(|| {
+                FpVar::new_input(ns!(cs, "hashed_account_secret"), || {
+                    Ok(self.hashed_account_secret)
+                })
+            })
+            .transpose()?;
+
+        let output_ciphertext_hashes = if self.ciphertext_binding {
+            Some([
+                FpVar::new_input(ns!(cs, "output_ciphertext_hash_0"), || {
+                    Ok(self.output_ciphertext_hashes[0])
+                })?,
+                FpVar::new_input(ns!(cs, "output_ciphertext_hash_1"), || {
+                    Ok(self.output_ciphertext_hashes[1])
+                })?,
+            ])
+        } else {
+            None
+        };
+
+        let authority_root = self
+            .key_rotation
+            .then(|| FpVar::new_input(ns!(cs, "authority_root"), || Ok(self.authority_root)))
+            .transpose()?;
+
+        let ext_data_hash = FpVar::new_input(ns!(cs, "ext_data_hash"), || Ok(self.ext_data_hash))?;
+
+        // Create arrays from individual variables for use in loops
+        let input_nullifiers = [input_nullifier_0, input_nullifier_1];
+        let output_commitment = [output_commitment_0, output_commitment_1];
+
+        // ============================================
+        // ALLOCATE PRIVATE WITNESS INPUTS
+        // ============================================
+        let account_secret = self
+            .account_secret_check
+            .then(|| FpVar::new_witness(ns!(cs, "account_secret"), || Ok(self.account_secret)))
+            .transpose()?;
+
+        let in_private_key = [
+            FpVar::new_witness(ns!(cs, "in_private_key_0"), || Ok(self.in_private_keys[0]))?,
+            FpVar::new_witness(ns!(cs, "in_private_key_1"), || Ok(self.in_private_keys[1]))?,
+        ];
+
+        let in_amounts = [
+            FpVar::new_witness(ns!(cs, "in_amount_0"), || Ok(self.in_amounts[0]))?,
+            FpVar::new_witness(ns!(cs, "in_amount_1"), || Ok(self.in_amounts[1]))?,
+        ];
+
+        let in_blindings = [
+            FpVar::new_witness(ns!(cs, "in_blinding_0"), || Ok(self.in_blindings[0]))?,
+            FpVar::new_witness(ns!(cs, "in_blinding_1"), || Ok(self.in_blindings[1]))?,
+        ];
+
+        let in_path_indices = [
+            FpVar::new_witness(ns!(cs, "in_path_index_0"), || Ok(self.in_path_indices[0]))?,
+            FpVar::new_witness(ns!(cs, "in_path_index_1"), || Ok(self.in_path_indices[1]))?,
+        ];
+
+        // Legacy commitment selectors only exist in the mixed-era shape
+        let in_legacy_commitments = if self.mixed_era_inputs {
+            Some([
+                Boolean::new_witness(ns!(cs, "in_legacy_commitment_0"), || {
+                    Ok(self.in_legacy_commitments[0])
+                })?,
+                Boolean::new_witness(ns!(cs, "in_legacy_commitment_1"), || {
+                    Ok(self.in_legacy_commitments[1])
+                })?,
+            ])
+        } else {
+            None
+        };
+
+        // Rotation witnesses only exist in the key rotation shape
+        let in_key_rotations = if self.key_rotation {
+            Some([
+                (
+                    Boolean::new_witness(ns!(cs, "in_key_rotated_0"), || {
+                        Ok(self.in_key_rotated[0])
+                    })?,
+                    FpVar::new_witness(ns!(cs, "in_rotated_private_key_0"), || {
+                        Ok(self.in_rotated_private_keys[0])
+                    })?,
+                    FpVar::new_witness(ns!(cs, "in_authority_index_0"), || {
+                        Ok(self.in_authority_indices[0])
+                    })?,
+                    PathVar::new_witness(ns!(cs, "authority_path_0"), || {
+                        Ok(self.in_authority_paths[0])
+                    })?,
+                ),
+                (
+                    Boolean::new_witness(ns!(cs, "in_key_rotated_1"), || {
+                        Ok(self.in_key_rotated[1])
+                    })?,
+                    FpVar::new_witness(ns!(cs, "in_rotated_private_key_1"), || {
+                        Ok(self.in_rotated_private_keys[1])
+                    })?,
+                    FpVar::new_witness(ns!(cs, "in_authority_index_1"), || {
+                        Ok(self.in_authority_indices[1])
+                    })?,
+                    PathVar::new_witness(ns!(cs, "authority_path_1"), || {
+                        Ok(self.in_authority_paths[1])
+                    })?,
+                ),
+            ])
+        } else {
+            None
+        };
+
+        // Dummy slots never reach the Merkle gadget, so their paths are not allocated
+        let merkle_paths = [
+            (!self.dummy_inputs[0])
+                .then(|| {
+                    PathVar::new_witness(ns!(cs, "merkle_path_0"), || Ok(self.merkle_paths[0]))
+                })
+                .transpose()?,
+            (!self.dummy_inputs[1])
+                .then(|| {
+                    PathVar::new_witness(ns!(cs, "merkle_path_1"), || Ok(self.merkle_paths[1]))
+                })
+                .transpose()?,
+        ];
+
+        // Allocate output witnesses early (before input processing)
+        // This improves constraint ordering and can help with optimization
+        let out_public_key = [
+            FpVar::new_witness(ns!(cs, "out_public_key_0"), || Ok(self.out_public_keys[0]))?,
+            FpVar::new_witness(ns!(cs, "out_public_key_1"), || Ok(self.out_public_keys[1]))?,
+        ];
+
+        let out_amounts = [
+            FpVar::new_witness(ns!(cs, "out_amount_0"), || Ok(self.out_amounts[0]))?,
+            FpVar::new_witness(ns!(cs, "out_amount_1"), || Ok(self.out_amounts[1]))?,
+        ];
+
+        let out_blindings = [
+            FpVar::new_witness(ns!(cs, "out_blinding_0"), || Ok(self.out_blindings[0]))?,
+            FpVar::new_witness(ns!(cs, "out_blinding_1"), || Ok(self.out_blindings[1]))?,
+        ];
+
+        // ============================================
+        // CREATE HASHERS (constants, no allocation needed)
+        // ============================================
+        let hasher_t2 = PoseidonOptimizedVar::new_t2();
+        let hasher_t3 = PoseidonOptimizedVar::new_t3();
+        let hasher_t4 = PoseidonOptimizedVar::new_t4();
+        let hasher_t5 = PoseidonOptimizedVar::new_t5();
+
+        // ============================================
+        // CREATE ZERO VARIABLE
+        // ============================================
+        let zero = FpVar::<Fr>::zero();
+
+        // ============================================
+        // Verify account secret
+        // ============================================
+        if let (Some(hashed_account_secret), Some(account_secret)) =
+            (&hashed_account_secret, &account_secret)
+        {
+            let _account_secret_region = debug::region(&cs, || "account_secret".into());
+            let expected_hashed_account_secret = hasher_t2.hash1(account_secret)?;
+            // Only enforce equality if account_secret is non-zero (more efficient)
+            let hashed_account_secret_is_non_zero = hashed_account_secret.is_eq(&zero)?.not();
+            expected_hashed_account_secret.conditional_enforce_equal(
+                hashed_account_secret,
+                &hashed_account_secret_is_non_zero,
+            )?;
+        }
+
+        // ============================================
+        // BIND EXTERNAL DATA
+        // ============================================
+        // SECURITY: Square ext_data_hash so it takes part in a constraint and the proof
+        // cannot be reused with different ext data
+        let ext_data_region = debug::region(&cs, || "ext_data".into());
+        let _ext_data_square = ext_data_hash.square()?;
+        drop(ext_data_region);
+
+        // ============================================
+        // VERIFY INPUT UTXOs
+        // ============================================
+        let mut sum_ins = FpVar::<Fr>::zero();
+
+        for i in 0..N_INS {
+            let _input_region = debug::region(&cs, || format!("input_{}", i));
+
+            let nullifier_region = debug::region(&cs, || "nullifier".into());
+            // Derive public key from private key: pubkey = Poseidon1(privkey)
+            let public_key = hasher_t2.hash1(&in_private_key[i])?;
+
+            // Calculate commitment under the formula the note was created with:
+            // current = Poseidon4(amount, pubkey, blinding, vortex)
+            // legacy = Poseidon3(amount, pubkey, blinding), mixed-era shape only
+            let current_commitment =
+                hasher_t5.hash4(&in_amounts[i], &public_key, &in_blindings[i], &vortex)?;
+            let commitment = match &in_legacy_commitments {
+                Some(is_legacy) => {
+                    let legacy_commitment =
+                        hasher_t4.hash3(&in_amounts[i], &public_key, &in_blindings[i])?;
+                    is_legacy[i].select(&legacy_commitment, &current_commitment)?
+                }
+                None => current_commitment,
+            };
+
+            // Calculate signature: sig = Poseidon3(privkey, commitment, path_index)
+            let signature =
+                hasher_t4.hash3(&in_private_key[i], &commitment, &in_path_indices[i])?;
+
+            // Calculate nullifier: nullifier = Poseidon3(commitment, path_index, signature)
+            let nullifier = hasher_t4.hash3(&commitment, &in_path_indices[i], &signature)?;
+
+            // Enforce computed nullifier matches public input
+            nullifier.enforce_equal(&input_nullifiers[i])?;
+            drop(nullifier_region);
+
+            if let (Some(authority_root), Some(rotations)) = (&authority_root, &in_key_rotations) {
+                // SECURITY: The selector picks whether the note key alone or its rotation to
+                // the rotated-in key authorizes the spend, and the result must be a leaf of
+                // the authority tree. Only the root is public, so spends by one key are not
+                // linkable, and the pool revokes a rotated-out key by clearing its leaf.
+                let _authority_region = debug::region(&cs, || "authority".into());
+                let (is_rotated, rotated_private_key, authority_index, authority_path) =
+                    &rotations[i];
+                let key_tag = hasher_t3.hash2(&in_private_key[i], &zero)?;
+                let rotated_key_tag = hasher_t3.hash2(rotated_private_key, &zero)?;
+                let rotation_link = hasher_t3.hash2(&key_tag, &rotated_key_tag)?;
+                let authority = is_rotated.select(&rotation_link, &key_tag)?;
+                let authority_membership = authority_path.check_membership(
+                    authority_root,
+                    &authority,
+                    authority_index,
+                    &hasher_t3,
+                )?;
+
+                // Zero-amount inputs carry no value, so padding needs no authority
+                let amount_is_non_zero = in_amounts[i].is_eq(&zero)?.not();
+                authority_membership
+                    .conditional_enforce_equal(&Boolean::constant(true), &amount_is_non_zero)?;
+            }
+
+            match &merkle_paths[i] {
+                Some(merkle_path) => {
+                    // SECURITY: Check if amount is zero (for conditional Merkle proof check)
+                    let amount_is_zero = in_amounts[i].is_eq(&zero)?;
+
+                    // SECURITY: Range check - ensure input amount fits in MAX_AMOUNT_BITS
+                    // This prevents overflow attacks
+                    let range_region = debug::region(&cs, || "range_check".into());
+                    enforce_range_check(&in_amounts[i], &amount_is_zero)?;
+                    drop(range_region);
+
+                    // SECURITY: Verify Merkle proof only if amount is non-zero. The path is
+                    // walked along the bits of the path index fed into the nullifier, so
+                    // each note has exactly one nullifier
+                    let _merkle_region = debug::region(&cs, || "merkle".into());
+                    let merkle_path_membership = merkle_path.check_membership(
+                        &root,
+                        &commitment,
+                        &in_path_indices[i],
+                        &hasher_t3,
+                    )?;
+
+                    // Only enforce Merkle membership when amount is non-zero
+                    let amount_is_non_zero = amount_is_zero.not();
+                    merkle_path_membership
+                        .conditional_enforce_equal(&Boolean::constant(true), &amount_is_non_zero)?;
+                }
+                None => {
+                    // SECURITY: Dummy slot has no membership check, so it must not carry value.
+                    // Pinning the amount to zero also makes the range check redundant.
+                    let _dummy_region = debug::region(&cs, || "dummy_amount".into());
+                    in_amounts[i].enforce_equal(&zero)?;
+                }
+            }
+
+            sum_ins += &in_amounts[i];
+        }
+
+        // ============================================
+        // VERIFY OUTPUT UTXOs
+        // ============================================
+        let mut sum_outs = FpVar::<Fr>::zero();
+
+        for i in 0..N_OUTS {
+            let _output_region = debug::region(&cs, || format!("output_{}", i));
+
+            let commitment_region = debug::region(&cs, || "commitment".into());
+            // SECURITY: With ciphertext binding the committed blinding depends on the
+            // published ciphertext, so the proof fails for any other ciphertext
+            let blinding = match &output_ciphertext_hashes {
+                Some(hashes) => hasher_t3.hash2(&out_blindings[i], &hashes[i])?,
+                None => out_blindings[i].clone(),
+            };
+
+            // Calculate output commitment: commitment = Poseidon4(amount, pubkey, blinding, vortex)
+            let expected_commitment =
+                hasher_t5.hash4(&out_amounts[i], &out_public_key[i], &blinding, &vortex)?;
+
+            // Enforce computed commitment matches public input
+            expected_commitment.enforce_equal(&output_commitment[i])?;
+            drop(commitment_region);
+
+            // SECURITY: Range check - ensure output amount fits in MAX_AMOUNT_BITS
+            let _range_region = debug::region(&cs, || "range_check".into());
+            let amount_is_zero = out_amounts[i].is_eq(&zero)?;
+            enforce_range_check(&out_amounts[i], &amount_is_zero)?;
+
+            sum_outs += &out_amounts[i];
+        }
+
+        // ============================================
+        // VERIFY NO DUPLICATE NULLIFIERS
+        // ============================================
+        // SECURITY: Prevent using same nullifier twice in one transaction
+        //
+        // Optimization: For N_INS=2, we only need 1 comparison (nullifiers[0] != nullifiers[1])
+        // This is the minimal constraint set - exactly 1 enforce_not_equal constraint.
+        //
+        // Alternative approaches considered:
+        // - Loop over all pairs: Same constraint count for N_INS=2, but adds loop overhead
+        // - Product of differences: More expensive (requires multiplications)
+        // - Direct check: Optimal for fixed N_INS=2, explicit and clear
+        //
+        // If N_INS changes in the future, generalize to: for i in 0..N_INS { for j in (i+1)..N_INS { ... } }
+        let uniqueness_region = debug::region(&cs, || "nullifier_uniqueness".into());
+        input_nullifiers[0].enforce_not_equal(&input_nullifiers[1])?;
+        drop(uniqueness_region);
+
+        // ============================================
+        // VERIFY PUBLIC AMOUNT SIGN AND MAGNITUDE
+        // ============================================
+        // SECURITY: public_amount must be a deposit x or a withdrawal p - x with
+        // x < 2^MAX_AMOUNT_BITS. Both readings cannot be in range at once, so the sign
+        // witness is forced; the value can no longer wrap around the field to mint value.
+        let public_amount_region = debug::region(&cs, || "public_amount".into());
+        let public_amount_is_withdrawal =
+            Boolean::new_witness(ns!(cs, "public_amount_is_withdrawal"), || {
+                Ok(split_public_amount(&self.public_amount).0)
+            })?;
+        let public_amount_magnitude =
+            public_amount_is_withdrawal.select(&public_amount.negate()?, &public_amount)?;
+        enforce_range_check(&public_amount_magnitude, &Boolean::constant(false))?;
+        drop(public_amount_region);
+
+        // ============================================
+        // VERIFY AMOUNT CONSERVATION
+        // ============================================
+        // SECURITY: Ensure no value is created or destroyed
+        // sum(inputs) + public_amount = sum(outputs)
+        let _conservation_region = debug::region(&cs, || "conservation".into());
+        (sum_ins + public_amount).enforce_equal(&sum_outs)?;
+
+        Ok(())
+    }
+}
+
+/// Optimized range check: ensures `value` < 2^MAX_AMOUNT_BITS
+///
+/// More efficient than Circom's Num2Bits approach: instead of reconstructing from 248 bits,
+/// we only check that the upper 6 bits [248..254) are zero when value is non-zero.
+/// This achieves the same security guarantee with far fewer constraints.
+///
+/// # Arguments
+/// * `value` - The field element to range check
+/// * `value_is_zero` - Boolean indicating if value is zero (skip check if true)
+///
+/// # Constraints
+/// - Always: ~254 constraints for bit decomposition (unavoidable with ark_r1cs_std)
+/// - When value_is_zero = true: Only bit decomposition, no range check constraints
+/// - When value_is_zero = false: Bit decomposition + 6 conditional equality checks
+///
+/// # Note on Optimization
+/// Unfortunately, ark_r1cs_std's `to_bits_le()` always performs full bit decomposition
+/// (~254 constraints) regardless of whether we conditionally use the bits. The optimization
+/// here is that we only enforce the 6 upper-bit checks when the value is non-zero, saving
+/// 6 constraints for zero values. A more efficient implementation would require custom
+/// bit decomposition that can be conditionally skipped entirely.
+fn enforce_range_check(value: &FpVar<Fr>, value_is_zero: &Boolean<Fr>) -> r1cs::Result<()> {
+    use ark_r1cs_std::prelude::ToBitsGadget;
+
+    // Decompose value into bits (all 254 bits for BN254 field)
+    // Note: This always creates ~254 constraints, even for zero values
+    let value_bits = value.to_bits_le()?;
+    let value_is_non_zero = value_is_zero.not();
+
+    // Efficient approach: Check that bits [MAX_AMOUNT_BITS..254) are all zero when value is non-zero
+    // For MAX_AMOUNT_BITS = 248, we check bits [248..254) = 6 bits
+    // This is equivalent to Circom's Num2Bits(248) but more efficient:
+    // - Circom: 248 multiplications + 248 additions + 1 equality check
+    // - This: 6 conditional equality checks (only enforced when value is non-zero)
+    for bit in value_bits
+        .iter()
+        .skip(MAX_AMOUNT_BITS)
+        .take(254 - MAX_AMOUNT_BITS)
+    {
+        // Constraint: if value is non-zero, then bit must be zero
+        // This is: NOT(value_is_zero) IMPLIES (bit == false)
+        bit.conditional_enforce_equal(&Boolean::constant(false), &value_is_non_zero)?;
+    }
+
+    Ok(())
+}
+
+/// Valid zero-value transfer (two zero-amount inputs, two zero-amount outputs), used by
+/// tests and as the canned witness of the key self-test
+#[cfg(any(feature = "std", test))]
+pub(crate) fn sample_zero_transfer() -> TransactionCircuit {
+    let vortex = Fr::ZERO;
+    let private_keys = [Fr::from(12345u64), Fr::from(67890u64)];
+    let blindings = [Fr::from(999u64), Fr::from(888u64)];
+    let path_indices = [Fr::from(0u64), Fr::from(1u64)];
+    let nullifiers: Vec<Fr> = (0..N_INS)
+        .map(|i| {
+            let commitment =
+                compute_commitment(&Fr::ZERO, &hash1(&private_keys[i]), &blindings[i], &vortex);
+            compute_nullifier(&commitment, &path_indices[i], &private_keys[i])
+        })
+        .collect();
+    let out_public_key = hash1(&private_keys[0]);
+    let out_blindings = [Fr::from(777u64), Fr::from(666u64)];
+
+    TransactionCircuit::new(
+        vortex,
+        Fr::ZERO,
+        Fr::ZERO,
+        nullifiers[0],
+        nullifiers[1],
+        compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[0], &vortex),
+        compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[1], &vortex),
+        Fr::ZERO,
+        Fr::ZERO,
+        private_keys,
+        [Fr::ZERO; N_INS],
+        blindings,
+        path_indices,
+        [Path::empty(), Path::empty()],
+        [out_public_key, out_public_key],
+        [Fr::ZERO; N_OUTS],
+        out_blindings,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_circuit_with_valid_inputs() {
+    use crate::poseidon_opt::{hash1, hash3, hash4};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let vortex = Fr::from(0u64);
+
+    // Input 0: zero amount (Merkle check skipped)
+    let private_key_0 = Fr::from(12345u64);
+    let public_key_0 = hash1(&private_key_0);
+    let amount_0 = Fr::from(0u64);
+    let blinding_0 = Fr::from(999u64);
+    let path_index_0 = Fr::from(0u64);
+
+    let commitment_0 = hash4(&amount_0, &public_key_0, &blinding_0, &vortex);
+    let signature_0 = hash3(&private_key_0, &commitment_0, &path_index_0);
+    let nullifier_0 = hash3(&commitment_0, &path_index_0, &signature_0);
+
+    // Input 1: zero amount (Merkle check skipped)
+    let private_key_1 = Fr::from(67890u64);
+    let public_key_1 = hash1(&private_key_1);
+    let amount_1 = Fr::from(0u64);
+    let blinding_1 = Fr::from(888u64);
+    let path_index_1 = Fr::from(1u64);
+
+    let commitment_1 = hash4(&amount_1, &public_key_1, &blinding_1, &vortex);
+    let signature_1 = hash3(&private_key_1, &commitment_1, &path_index_1);
+    let nullifier_1 = hash3(&commitment_1, &path_index_1, &signature_1);
+
+    // Output 0: zero amount
+    let out_public_key_0 = public_key_0;
+    let out_amount_0 = Fr::from(0u64);
+    let out_blinding_0 = Fr::from(777u64);
+    let out_commitment_0 = hash4(&out_amount_0, &out_public_key_0, &out_blinding_0, &vortex);
+
+    // Output 1: zero amount
+    let out_public_key_1 = public_key_1;
+    let out_amount_1 = Fr::from(0u64);
+    let out_blinding_1 = Fr::from(666u64);
+    let out_commitment_1 = hash4(&out_amount_1, &out_public_key_1, &out_blinding_1, &vortex);
+
+    // Empty merkle paths
+    let merkle_paths = [Path::empty(), Path::empty()];
+
+    let circuit = TransactionCircuit::new(
+        vortex,
+        Fr::from(0u64), // root
+        Fr::from(0u64), // public_amount
+        nullifier_0,
+        nullifier_1,
+        out_commitment_0,
+        out_commitment_1,
+        Fr::from(0u64), // hashed_account_secret
+        Fr::from(0u64), // account_secret
+        [private_key_0, private_key_1],
+        [amount_0, amount_1],
+        [blinding_0, blinding_1],
+        [path_index_0, path_index_1],
+        merkle_paths,
+        [out_public_key_0, out_public_key_1],
+        [out_amount_0, out_amount_1],
+        [out_blinding_0, out_blinding_1],
+    )
+    .unwrap();
+
+    circuit.generate_constraints(cs.clone()).unwrap();
+
+    println!("Constraints: {}", cs.num_constraints());
+    let is_satisfied = cs.is_satisfied().unwrap();
+    println!("Satisfied: {}", is_satisfied);
+
+    if !is_satisfied {
+        println!("Which failed: {:?}", cs.which_is_unsatisfied());
+    }
+
+    assert!(is_satisfied);
+}
+
+#[test]
+fn test_account_secret_verification() {
+    use crate::poseidon_opt::{hash1, hash3, hash4};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let vortex = Fr::from(0u64);
+
+    // Setup minimal valid circuit inputs
+    let private_key_0 = Fr::from(12345u64);
+    let public_key_0 = hash1(&private_key_0);
+    let amount_0 = Fr::from(0u64);
+    let blinding_0 = Fr::from(999u64);
+    let path_index_0 = Fr::from(0u64);
+    let commitment_0 = hash4(&amount_0, &public_key_0, &blinding_0, &vortex);
+    let signature_0 = hash3(&private_key_0, &commitment_0, &path_index_0);
+    let nullifier_0 = hash3(&commitment_0, &path_index_0, &signature_0);
+
+    let private_key_1 = Fr::from(67890u64);
+    let public_key_1 = hash1(&private_key_1);
+    let amount_1 = Fr::from(0u64);
+    let blinding_1 = Fr::from(888u64);
+    let path_index_1 = Fr::from(1u64);
+    let commitment_1 = hash4(&amount_1, &public_key_1, &blinding_1, &vortex);
+    let signature_1 = hash3(&private_key_1, &commitment_1, &path_index_1);
+    let nullifier_1 = hash3(&commitment_1, &path_index_1, &signature_1);
+
+    let out_public_key_0 = public_key_0;
+    let out_amount_0 = Fr::from(0u64);
+    let out_blinding_0 = Fr::from(777u64);
+    let out_commitment_0 = hash4(&out_amount_0, &out_public_key_0, &out_blinding_0, &vortex);
+
+    let out_public_key_1 = public_key_1;
+    let out_amount_1 = Fr::from(0u64);
+    let out_blinding_1 = Fr::from(666u64);
+    let out_commitment_1 = hash4(&out_amount_1, &out_public_key_1, &out_blinding_1, &vortex);
+
+    let merkle_paths = [Path::empty(), Path::empty()];
+
+    // Test 1: correct secret with non-zero hashed_account_secret (should pass)
+    {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let account_secret = Fr::from(42u64);
+        let hashed_account_secret = hash1(&account_secret);
+
+        let circuit = TransactionCircuit::new(
+            vortex,
+            Fr::from(0u64), // root
+            Fr::from(0u64), // public_amount
+            nullifier_0,
+            nullifier_1,
+            out_commitment_0,
+            out_commitment_1,
+            hashed_account_secret,
+            account_secret,
+            [private_key_0, private_key_1],
+            [amount_0, amount_1],
+            [blinding_0, blinding_1],
+            [path_index_0, path_index_1],
+            merkle_paths,
+            [out_public_key_0, out_public_key_1],
+            [out_amount_0, out_amount_1],
+            [out_blinding_0, out_blinding_1],
+        )
+        .unwrap();
+
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(
+            cs.is_satisfied().unwrap(),
+            "Circuit should be satisfied when hashed_account_secret is non-zero and secret is correct"
+        );
+    }
+
+    // Test 2: incorrect secret with non-zero hashed_account_secret (should fail)
+    {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let account_secret = Fr::from(42u64);
+        let wrong_hashed_account_secret = hash1(&Fr::from(99u64)); // Wrong hash
+
+        let circuit = TransactionCircuit::new(
+            vortex,
+            Fr::from(0u64), // root
+            Fr::from(0u64), // public_amount
+            nullifier_0,
+            nullifier_1,
+            out_commitment_0,
+            out_commitment_1,
+            wrong_hashed_account_secret,
+            account_secret,
+            [private_key_0, private_key_1],
+            [amount_0, amount_1],
+            [blinding_0, blinding_1],
+            [path_index_0, path_index_1],
+            merkle_paths,
+            [out_public_key_0, out_public_key_1],
+            [out_amount_0, out_amount_1],
+            [out_blinding_0, out_blinding_1],
+        )
+        .unwrap();
+
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(
+            !cs.is_satisfied().unwrap(),
+            "Circuit should NOT be satisfied when hashed_account_secret is non-zero and secret is incorrect"
+        );
+    }
+
+    // Test 3: hashed_account_secret is 0, secret doesn't matter (should pass)
+    {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let account_secret = Fr::from(44u64);
+        let hashed_account_secret = Fr::ZERO; // Zero hash, check is skipped
+
+        let circuit = TransactionCircuit::new(
+            vortex,
+            Fr::from(0u64), // root
+            Fr::from(0u64), // public_amount
+            nullifier_0,
+            nullifier_1,
+            out_commitment_0,
+            out_commitment_1,
+            hashed_account_secret,
+            account_secret,
+            [private_key_0, private_key_1],
+            [amount_0, amount_1],
+            [blinding_0, blinding_1],
+            [path_index_0, path_index_1],
+            merkle_paths,
+            [out_public_key_0, out_public_key_1],
+            [out_amount_0, out_amount_1],
+            [out_blinding_0, out_blinding_1],
+        )
+        .unwrap();
+
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(
+            cs.is_satisfied().unwrap(),
+            "Circuit should be satisfied when hashed_account_secret is 0 (check is skipped)"
+        );
+    }
+}
+
+#[test]
+fn test_dummy_input_slots_skip_merkle_gadget() {
+    use crate::poseidon_opt::{hash1, hash3, hash4};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let vortex = Fr::from(0u64);
+
+    let private_keys = [Fr::from(12345u64), Fr::from(67890u64)];
+    let blindings = [Fr::from(999u64), Fr::from(888u64)];
+    let path_indices = [Fr::from(0u64), Fr::from(1u64)];
+    let nullifiers: Vec<Fr> = (0..N_INS)
+        .map(|i| {
+            let public_key = hash1(&private_keys[i]);
+            let commitment = hash4(&Fr::ZERO, &public_key, &blindings[i], &vortex);
+            let signature = hash3(&private_keys[i], &commitment, &path_indices[i]);
+            hash3(&commitment, &path_indices[i], &signature)
+        })
+        .collect();
+
+    let out_public_key = hash1(&private_keys[0]);
+    let out_blindings = [Fr::from(777u64), Fr::from(666u64)];
+    let out_commitments: Vec<Fr> = out_blindings
+        .iter()
+        .map(|b| hash4(&Fr::ZERO, &out_public_key, b, &vortex))
+        .collect();
+
+    let build = |in_amounts: [Fr; N_INS]| {
+        TransactionCircuit::new(
+            vortex,
+            Fr::ZERO,
+            Fr::ZERO,
+            nullifiers[0],
+            nullifiers[1],
+            out_commitments[0],
+            out_commitments[1],
+            Fr::ZERO,
+            Fr::ZERO,
+            private_keys,
+            in_amounts,
+            blindings,
+            path_indices,
+            [Path::empty(), Path::empty()],
+            [out_public_key, out_public_key],
+            [Fr::ZERO, Fr::ZERO],
+            out_blindings,
+        )
+        .unwrap()
+    };
+
+    let full_cs = ConstraintSystem::<Fr>::new_ref();
+    build([Fr::ZERO; N_INS])
+        .generate_constraints(full_cs.clone())
+        .unwrap();
+
+    let dummy_cs = ConstraintSystem::<Fr>::new_ref();
+    build([Fr::ZERO; N_INS])
+        .with_dummy_inputs([true, true])
+        .unwrap()
+        .generate_constraints(dummy_cs.clone())
+        .unwrap();
+
+    assert!(dummy_cs.is_satisfied().unwrap());
+    assert!(
+        dummy_cs.num_constraints() + 2 * MERKLE_TREE_LEVEL * 200 < full_cs.num_constraints(),
+        "Dummy slots should drop the Merkle hash chain ({} vs {} constraints)",
+        dummy_cs.num_constraints(),
+        full_cs.num_constraints()
+    );
+
+    // A dummy slot cannot carry value
+    assert!(build([Fr::from(5u64), Fr::ZERO])
+        .with_dummy_inputs([true, false])
+        .is_err());
+}
+
+#[test]
+fn test_check_public_inputs_names_mismatch() {
+    let vortex = Fr::from(0u64);
+    let private_keys = [Fr::from(12345u64), Fr::from(67890u64)];
+    let blindings = [Fr::from(999u64), Fr::from(888u64)];
+    let path_indices = [Fr::from(0u64), Fr::from(1u64)];
+    let nullifiers: Vec<Fr> = (0..N_INS)
+        .map(|i| {
+            let commitment =
+                compute_commitment(&Fr::ZERO, &hash1(&private_keys[i]), &blindings[i], &vortex);
+            compute_nullifier(&commitment, &path_indices[i], &private_keys[i])
+        })
+        .collect();
+    let out_public_key = hash1(&private_keys[0]);
+    let out_blindings = [Fr::from(777u64), Fr::from(666u64)];
+    let out_commitments: Vec<Fr> = out_blindings
+        .iter()
+        .map(|b| compute_commitment(&Fr::ZERO, &out_public_key, b, &vortex))
+        .collect();
+
+    let build = |nullifier_1: Fr, output_commitment_0: Fr, hashed_account_secret: Fr| {
+        TransactionCircuit::new(
+            vortex,
+            Fr::ZERO,
+            Fr::ZERO,
+            nullifiers[0],
+            nullifier_1,
+            output_commitment_0,
+            out_commitments[1],
+            hashed_account_secret,
+            Fr::from(42u64),
+            private_keys,
+            [Fr::ZERO; N_INS],
+            blindings,
+            path_indices,
+            [Path::empty(), Path::empty()],
+            [out_public_key, out_public_key],
+            [Fr::ZERO, Fr::ZERO],
+            out_blindings,
+        )
+        .unwrap()
+    };
+
+    let valid = build(nullifiers[1], out_commitments[0], hash1(&Fr::from(42u64)));
+    assert!(valid.check_public_inputs().is_ok());
+
+    let err = build(Fr::from(1u64), out_commitments[0], Fr::ZERO)
+        .check_public_inputs()
+        .unwrap_err();
+    assert!(err.to_string().contains("input_nullifier_1"));
+
+    let err = build(nullifiers[1], Fr::from(1u64), Fr::ZERO)
+        .check_public_inputs()
+        .unwrap_err();
+    assert!(err.to_string().contains("output_commitment_0"));
+
+    let err = build(nullifiers[1], out_commitments[0], Fr::from(1u64))
+        .check_public_inputs()
+        .unwrap_err();
+    assert!(err.to_string().contains("hashed_account_secret"));
+}
+
+#[test]
+fn test_zeroize_wipes_private_inputs() {
+    let mut circuit = sample_zero_transfer();
+    circuit.account_secret = Fr::from(42u64);
+    circuit.merkle_paths[0].path[3] = (Fr::from(5u64), Fr::from(6u64));
+    assert_ne!(circuit.in_private_keys, [Fr::ZERO; N_INS]);
+
+    circuit.zeroize();
+
+    assert_eq!(circuit.account_secret, Fr::ZERO);
+    assert_eq!(circuit.in_private_keys, [Fr::ZERO; N_INS]);
+    assert_eq!(circuit.in_blindings, [Fr::ZERO; N_INS]);
+    assert_eq!(circuit.out_blindings, [Fr::ZERO; N_OUTS]);
+    assert_eq!(circuit.merkle_paths[0], Path::empty());
+}
+
+#[test]
+fn test_debug_redacts_private_inputs() {
+    let mut circuit = sample_zero_transfer();
+    circuit.account_secret = Fr::from(987_654_321u64);
+    circuit.in_blindings[0] = Fr::from(123_456_789u64);
+
+    let printed = format!("{:?}", circuit);
+    assert!(!printed.contains("987654321"));
+    assert!(!printed.contains("123456789"));
+    assert!(printed.contains("account_secret: \"<redacted>\""));
+    assert!(printed.contains(&format!("{:?}", circuit.root)));
+}
+
+#[test]
+fn test_ext_data_hash_is_a_constrained_public_input() {
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let circuit = sample_zero_transfer().with_ext_data_hash(Fr::from(99u64));
+    assert_eq!(circuit.get_public_inputs().last(), Some(&Fr::from(99u64)));
+
+    let with_hash = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(with_hash.clone()).unwrap();
+    assert!(with_hash.is_satisfied().unwrap());
+    // One for the constant, nine public inputs
+    assert_eq!(with_hash.num_instance_variables(), 10);
+}
+
+#[test]
+fn test_circuit_without_account_secret_drops_input_and_check() {
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let default_cs = ConstraintSystem::<Fr>::new_ref();
+    sample_zero_transfer()
+        .generate_constraints(default_cs.clone())
+        .unwrap();
+
+    let mut circuit = sample_zero_transfer();
+    circuit.hashed_account_secret = Fr::from(1u64);
+    let circuit = circuit.without_account_secret();
+    assert_eq!(circuit.get_public_inputs().len(), 8);
+    assert!(circuit.check_public_inputs().is_ok());
+
+    let variant_cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(variant_cs.clone()).unwrap();
+    assert!(variant_cs.is_satisfied().unwrap());
+    assert_eq!(
+        variant_cs.num_instance_variables() + 1,
+        default_cs.num_instance_variables()
+    );
+    assert!(variant_cs.num_constraints() < default_cs.num_constraints());
+}
+
+#[test]
+fn test_legacy_commitment_input_is_spendable() {
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let mut circuit = sample_zero_transfer();
+    let public_key = hash1(&circuit.in_private_keys[0]);
+    let legacy_commitment =
+        compute_legacy_commitment(&Fr::ZERO, &public_key, &circuit.in_blindings[0]);
+    circuit.input_nullifier_0 = compute_nullifier(
+        &legacy_commitment,
+        &circuit.in_path_indices[0],
+        &circuit.in_private_keys[0],
+    );
+
+    // Without the selector the nullifier is derived from the current commitment
+    assert!(circuit.check_public_inputs().is_err());
+    let current_cs = ConstraintSystem::<Fr>::new_ref();
+    circuit
+        .clone()
+        .generate_constraints(current_cs.clone())
+        .unwrap();
+    assert!(!current_cs.is_satisfied().unwrap());
+
+    // The selector is a witness of the mixed-era shape only
+    let circuit = circuit.with_legacy_commitments([true, false]);
+    assert!(circuit.check_public_inputs().is_err());
+    let circuit = circuit.with_mixed_era_inputs();
+    assert!(circuit.check_public_inputs().is_ok());
+    let mixed_cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(mixed_cs.clone()).unwrap();
+    assert!(mixed_cs.is_satisfied().unwrap());
+    assert!(mixed_cs.num_constraints() > current_cs.num_constraints());
+}
+
+#[test]
+fn test_public_amount_sign_and_magnitude_are_constrained() {
+    use ark_relations::r1cs::ConstraintSystem;
+
+    // Deposit of `amount` into two equal outputs, with no inputs
+    let deposit = |amount: Fr| {
+        let mut circuit = sample_zero_transfer();
+        circuit.public_amount = amount + amount;
+        circuit.out_amounts = [amount; N_OUTS];
+        circuit.output_commitment_0 = compute_commitment(
+            &amount,
+            &circuit.out_public_keys[0],
+            &circuit.out_blindings[0],
+            &circuit.vortex,
+        );
+        circuit.output_commitment_1 = compute_commitment(
+            &amount,
+            &circuit.out_public_keys[1],
+            &circuit.out_blindings[1],
+            &circuit.vortex,
+        );
+        circuit
+    };
+
+    let valid = deposit(Fr::from(500u64));
+    assert!(valid.check_public_inputs().is_ok());
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    valid.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    // Each output is in range, but their sum is neither a deposit nor a withdrawal
+    let max_amount = ark_ff::Field::pow(&Fr::from(2u64), [MAX_AMOUNT_BITS as u64]) - Fr::from(1u64);
+    let wide = deposit(max_amount);
+    assert!(wide.check_public_inputs().is_err());
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    wide.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+
+    assert_eq!(
+        split_public_amount(&-Fr::from(7u64)),
+        (true, Fr::from(7u64))
+    );
+    assert_eq!(
+        split_public_amount(&Fr::from(7u64)),
+        (false, Fr::from(7u64))
+    );
+}
+
+#[test]
+fn test_ciphertext_binding_commits_to_published_hash() {
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let hashes = [Fr::from(31u64), Fr::from(32u64)];
+    let mut circuit = sample_zero_transfer();
+    circuit.output_commitment_0 = compute_commitment(
+        &Fr::ZERO,
+        &circuit.out_public_keys[0],
+        &bind_ciphertext(&circuit.out_blindings[0], &hashes[0]),
+        &circuit.vortex,
+    );
+    circuit.output_commitment_1 = compute_commitment(
+        &Fr::ZERO,
+        &circuit.out_public_keys[1],
+        &bind_ciphertext(&circuit.out_blindings[1], &hashes[1]),
+        &circuit.vortex,
+    );
+
+    let bound = circuit.clone().with_ciphertext_binding(hashes);
+    assert!(bound.check_public_inputs().is_ok());
+    let public_inputs = bound.get_public_inputs();
+    assert_eq!(public_inputs.len(), 11);
+    assert_eq!(&public_inputs[8..10], &hashes);
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    bound.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    // Any other ciphertext breaks the commitment
+    let swapped = circuit.with_ciphertext_binding([hashes[1], hashes[0]]);
+    assert!(swapped.check_public_inputs().is_err());
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    swapped.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_path_index_must_match_merkle_path_position() {
+    use crate::merkle_tree::SparseMerkleTree;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let tree_hasher = PoseidonOptimized::new_t3();
+    let vortex = Fr::ZERO;
+    let amount = Fr::from(10u64);
+    let private_key = Fr::from(12345u64);
+    let blinding = Fr::from(999u64);
+    let commitment = compute_commitment(&amount, &hash1(&private_key), &blinding, &vortex);
+
+    // The note is the right leaf of the first pair, so its index is 1
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&tree_hasher, &Fr::ZERO);
+    tree.insert_pair(Fr::from(3u64), commitment, &tree_hasher)
+        .unwrap();
+    let path = tree.generate_membership_proof(1).unwrap();
+
+    let build = |path_index: Fr| {
+        let mut circuit = sample_zero_transfer();
+        circuit.root = tree.root();
+        circuit.in_private_keys[0] = private_key;
+        circuit.in_amounts[0] = amount;
+        circuit.in_blindings[0] = blinding;
+        circuit.in_path_indices[0] = path_index;
+        circuit.merkle_paths[0] = path;
+        circuit.input_nullifier_0 = compute_nullifier(&commitment, &path_index, &private_key);
+        circuit.out_amounts[0] = amount;
+        circuit.output_commitment_0 = compute_commitment(
+            &amount,
+            &circuit.out_public_keys[0],
+            &circuit.out_blindings[0],
+            &vortex,
+        );
+        circuit
+    };
+
+    let valid = build(Fr::from(1u64));
+    assert!(valid.check_public_inputs().is_ok());
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    valid.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    // Same note and path under another index would yield a second nullifier
+    let other_index = build(Fr::from(5u64));
+    let err = other_index.check_public_inputs().unwrap_err();
+    assert!(err.to_string().contains("in_path_index_0"));
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    other_index.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_key_rotation_authorizes_with_either_key() {
+    use crate::merkle_tree::SparseMerkleTree;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let tree_hasher = PoseidonOptimized::new_t3();
+    let vortex = Fr::ZERO;
+    let amounts = [Fr::from(10u64), Fr::from(4u64)];
+    let previous_key = Fr::from(12345u64);
+    let new_key = Fr::from(54321u64);
+    let blindings = [Fr::from(999u64), Fr::from(998u64)];
+    // One note still under the previous key, one already migrated to the new key
+    let keys = [previous_key, new_key];
+    let commitments: [Fr; N_INS] = core::array::from_fn(|i| {
+        compute_commitment(&amounts[i], &hash1(&keys[i]), &blindings[i], &vortex)
+    });
+
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&tree_hasher, &Fr::ZERO);
+    tree.insert_pair(commitments[0], commitments[1], &tree_hasher)
+        .unwrap();
+
+    let mut circuit = sample_zero_transfer();
+    circuit.root = tree.root();
+    for i in 0..N_INS {
+        circuit.in_private_keys[i] = keys[i];
+        circuit.in_amounts[i] = amounts[i];
+        circuit.in_blindings[i] = blindings[i];
+        circuit.in_path_indices[i] = Fr::from(i as u64);
+        circuit.merkle_paths[i] = tree.generate_membership_proof(i).unwrap();
+    }
+    circuit.input_nullifier_0 = compute_nullifier(&commitments[0], &Fr::ZERO, &previous_key);
+    circuit.input_nullifier_1 = compute_nullifier(&commitments[1], &Fr::from(1u64), &new_key);
+    circuit.out_amounts[0] = amounts[0] + amounts[1];
+    circuit.output_commitment_0 = compute_commitment(
+        &circuit.out_amounts[0],
+        &circuit.out_public_keys[0],
+        &circuit.out_blindings[0],
+        &vortex,
+    );
+
+    let check = |circuit: TransactionCircuit| {
+        let natively_valid = circuit.check_public_inputs().is_ok();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), natively_valid);
+        natively_valid
+    };
+    // Authorizes input 0 with `rotated_key` and input 1 with the new key's own tag
+    let authorize = |leaves: &[Fr], rotated_key: Option<Fr>| {
+        let authorities = [
+            rotation::authority_leaf(&previous_key, rotated_key.as_ref()),
+            rotation::key_tag(&new_key),
+        ];
+        let paths = authorities.map(|leaf| {
+            rotation::authority_path(leaves, &leaf).unwrap_or((Fr::ZERO, 0, Path::empty()))
+        });
+        circuit.clone().with_key_rotation(
+            paths[0].0,
+            [rotated_key, None],
+            paths.map(|(_, index, _)| Fr::from(index)),
+            paths.map(|(_, _, path)| path),
+        )
+    };
+
+    let previous_tag = rotation::key_tag(&previous_key);
+    let new_tag = rotation::key_tag(&new_key);
+    let link = rotation::rotation_link(&previous_tag, &new_tag);
+    let in_grace_period = [previous_tag, Fr::from(77u64), new_tag, link];
+    let after_grace_period = [Fr::ZERO, Fr::from(77u64), new_tag, link];
+
+    // During the grace period the old key alone and the rotation to the new key both
+    // authorize the same nullifier, next to a note spent under the new key
+    let by_previous_key = authorize(&in_grace_period, None);
+    let by_rotation = authorize(&in_grace_period, Some(new_key));
+    let authority_root = by_previous_key.get_public_inputs()[8];
+    assert_eq!(by_rotation.get_public_inputs()[8], authority_root);
+    assert_ne!(authority_root, previous_tag);
+    assert!(check(by_previous_key));
+    assert!(check(by_rotation));
+
+    // Once the old tag is cleared only the rotation authorizes the old note
+    assert!(!check(authorize(&after_grace_period, None)));
+    assert!(check(authorize(&after_grace_period, Some(new_key))));
+
+    // A key that was not rotated in has no leaf in the tree
+    assert!(!check(authorize(&in_grace_period, Some(Fr::from(7u64)))));
+
+    // A path into another tree does not lead to the published root
+    let mut stale = authorize(&in_grace_period, None);
+    stale.authority_root = authorize(&after_grace_period, Some(new_key)).authority_root;
+    let err = stale.check_public_inputs().unwrap_err();
+    assert!(err.to_string().contains("authority_path_0"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_public_input_names_match_public_inputs() {
+    use crate::estimate::CircuitVariant;
+
+    for variant in CircuitVariant::ALL {
+        let circuit = variant.empty_circuit();
+        let names = circuit.public_input_names();
+        assert_eq!(names.len(), circuit.get_public_inputs().len());
+        assert_eq!(names.first(), Some(&"vortex"));
+        assert_eq!(names.last(), Some(&"extDataHash"));
+    }
+}
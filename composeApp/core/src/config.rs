@@ -0,0 +1,101 @@
+//! Prover resource profiles.
+//!
+//! The same binary runs on 3 GB phones and on desktop browsers, so how much the prover may
+//! hold and do at once is a process-wide setting chosen by the host:
+//! - how many proofs `prove_batch` and the proof queue run concurrently, which is also the
+//!   chunk size batches are proven in
+//! - how many proving keys stay deserialized in the cache (uniffi), loading another one
+//!   evicts the least recently loaded
+//! - whether a key passed by value is kept for the next call (wasm)
+//!
+//! `Balanced` is the default.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static MEMORY_PROFILE: AtomicU8 = AtomicU8::new(MemoryProfile::Balanced as u8);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryProfile {
+    /// One proof and one resident key at a time, for devices with ~3 GB of RAM
+    Low,
+    #[default]
+    Balanced,
+    /// One proof per core and every loaded key kept resident
+    Fast,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProverConfig {
+    pub memory_profile: MemoryProfile,
+}
+
+impl ProverConfig {
+    /// Proofs run at the same time, each holding its own witness and R1CS matrices
+    pub fn max_parallel_proofs(&self) -> usize {
+        match self.memory_profile {
+            MemoryProfile::Low => 1,
+            MemoryProfile::Balanced => 2,
+            MemoryProfile::Fast => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Proving keys kept in the cache
+    pub fn max_resident_keys(&self) -> usize {
+        match self.memory_profile {
+            MemoryProfile::Low => 1,
+            MemoryProfile::Balanced => 2,
+            MemoryProfile::Fast => usize::MAX,
+        }
+    }
+
+    /// Whether a key passed by value is kept deserialized for the next call with the same
+    /// bytes, at the cost of holding it between proofs
+    pub fn keeps_passed_key(&self) -> bool {
+        self.memory_profile != MemoryProfile::Low
+    }
+}
+
+/// Selects the profile used from the next prover call on
+pub fn set_prover_config(config: ProverConfig) {
+    MEMORY_PROFILE.store(config.memory_profile as u8, Ordering::Relaxed);
+}
+
+pub fn prover_config() -> ProverConfig {
+    let memory_profile = match MEMORY_PROFILE.load(Ordering::Relaxed) {
+        0 => MemoryProfile::Low,
+        2 => MemoryProfile::Fast,
+        _ => MemoryProfile::Balanced,
+    };
+    ProverConfig { memory_profile }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_order_resource_limits() {
+        let limits = |memory_profile| {
+            let config = ProverConfig { memory_profile };
+            (config.max_parallel_proofs(), config.max_resident_keys())
+        };
+        let (low_parallel, low_keys) = limits(MemoryProfile::Low);
+        let (balanced_parallel, balanced_keys) = limits(MemoryProfile::Balanced);
+        let (_, fast_keys) = limits(MemoryProfile::Fast);
+
+        assert_eq!((low_parallel, low_keys), (1, 1));
+        assert!(balanced_parallel > low_parallel && balanced_keys > low_keys);
+        assert!(fast_keys > balanced_keys);
+        assert!(!ProverConfig {
+            memory_profile: MemoryProfile::Low
+        }
+        .keeps_passed_key());
+    }
+}
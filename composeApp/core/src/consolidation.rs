@@ -0,0 +1,339 @@
+//! Merging dust notes into one.
+//!
+//! Every payment received is a note, and a transaction spends at most `N_INS` of them, so a
+//! wallet fed many small payments needs ever longer chains to spend its balance. The
+//! planner finds the unspent notes below a dust threshold and merges them the way
+//! `multi_send` merges notes: the first step spends `N_INS` dust notes into one, and every
+//! later step spends the previous step's note with `N_INS - 1` more.
+//!
+//! Each step pays `fee_per_transaction` to a relayer, so the largest dust goes first and the
+//! chain stops at the first step whose fee exceeds `max_fee_bps` of the dust it absorbs;
+//! notes too small to pay for their own merge are left alone. `max_transactions` bounds the
+//! proving work, so a background job can consolidate a little on every run.
+//!
+//! Planning reads only the notes and is cheap enough to run on every sync. The inputs from
+//! `build_consolidation` hold only while no other transaction lands between the steps, like
+//! those of `build_multi_send`; when the tree has moved on, plan again from the synced notes.
+
+use crate::{
+    account::AccountSecret,
+    constants::{MERKLE_TREE_LEVEL, N_INS},
+    merkle_tree::SparseMerkleTree,
+    multi_send::{self, PlannedTransaction, SendStep},
+    note::Utxo,
+    relayer::RelayerFeeQuote,
+    viewing::SpendingKeys,
+};
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// When and how far to consolidate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidationPolicy {
+    /// Notes holding less than this many MIST are dust
+    pub dust_threshold: u64,
+    /// Consolidate only once the wallet holds at least this many dust notes
+    pub min_dust_notes: u32,
+    /// Relayer fee each transaction pays, 0 when the wallet submits them itself
+    pub fee_per_transaction: u64,
+    /// Highest fee a step may pay, in basis points of the dust it merges
+    pub max_fee_bps: u32,
+    /// Most transactions in one plan
+    pub max_transactions: u32,
+}
+
+/// Chained transactions merging dust notes, in submission order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidationPlan {
+    /// Steps paying nobody; each step's `change` is the merged note
+    pub steps: Vec<SendStep>,
+    /// Dust notes spent
+    pub merged_notes: u32,
+    /// Sum of the dust notes spent
+    pub merged_amount: u64,
+    /// Relayer fee of every step
+    pub fee_per_transaction: u64,
+    /// Fees of all steps
+    pub total_fee: u64,
+    /// Amount of the note the last step leaves
+    pub consolidated_amount: u64,
+}
+
+/// Plans the merge of the unspent dust in `notes`; `None` when there is too little dust or
+/// no step is worth its fee.
+///
+/// # Errors
+/// Returns error if the merged amount does not fit a `u64`.
+pub fn plan_consolidation(
+    notes: &[Utxo],
+    policy: &ConsolidationPolicy,
+) -> Result<Option<ConsolidationPlan>> {
+    let mut dust: Vec<u32> = (0..notes.len() as u32)
+        .filter(|&i| {
+            let utxo = &notes[i as usize];
+            !utxo.metadata.spent && utxo.note.amount > 0 && utxo.note.amount < policy.dust_threshold
+        })
+        .collect();
+    if dust.len() < (policy.min_dust_notes as usize).max(N_INS) {
+        return Ok(None);
+    }
+    dust.sort_by_key(|&i| std::cmp::Reverse(notes[i as usize].note.amount));
+
+    let fee = policy.fee_per_transaction as u128;
+    let mut dust = dust.into_iter().peekable();
+    let mut steps: Vec<SendStep> = Vec::new();
+    let mut carry = 0u128;
+    let mut merged = 0u128;
+    while steps.len() < policy.max_transactions as usize {
+        let spends_previous_change = !steps.is_empty();
+        let mut step_notes = Vec::new();
+        let mut absorbed = 0u128;
+        while step_notes.len() + (spends_previous_change as usize) < N_INS {
+            let Some(i) = dust.next() else {
+                break;
+            };
+            absorbed += notes[i as usize].note.amount as u128;
+            step_notes.push(i);
+        }
+        // A first step needs two notes to merge, later ones one
+        let merges = step_notes.len() + (spends_previous_change as usize) >= 2;
+        let worth_fee = fee * 10_000 <= absorbed * policy.max_fee_bps as u128;
+        if !merges || !worth_fee || carry + absorbed <= fee {
+            break;
+        }
+
+        carry = carry + absorbed - fee;
+        merged += absorbed;
+        steps.push(SendStep {
+            notes: step_notes,
+            spends_previous_change,
+            recipients: Vec::new(),
+            change: u64::try_from(carry).map_err(|_| anyhow!("Merged amount exceeds u64"))?,
+        });
+        if dust.peek().is_none() {
+            break;
+        }
+    }
+    if steps.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ConsolidationPlan {
+        merged_notes: steps.iter().map(|step| step.notes.len() as u32).sum(),
+        merged_amount: u64::try_from(merged).map_err(|_| anyhow!("Merged amount exceeds u64"))?,
+        fee_per_transaction: policy.fee_per_transaction,
+        total_fee: policy.fee_per_transaction * steps.len() as u64,
+        consolidated_amount: carry as u64,
+        steps,
+    }))
+}
+
+/// Builds the proof inputs of every step of `plan`, spending with `keys` and paying each
+/// step's fee to `relayer`.
+///
+/// `tree` is the synced commitment tree holding every note the plan spends. The second
+/// output of every step is a zero-amount note to the sender.
+///
+/// # Errors
+/// Returns error if the relayer's fee differs from the planned fee (a plan without fee takes
+/// no relayer), the plan does not match `notes`, or a note is not in `tree`.
+#[allow(clippy::too_many_arguments)]
+pub fn build_consolidation<R: RngCore + CryptoRng>(
+    plan: &ConsolidationPlan,
+    notes: &[Utxo],
+    keys: &SpendingKeys,
+    tree: &SparseMerkleTree<MERKLE_TREE_LEVEL>,
+    vortex: &Fr,
+    relayer: Option<&RelayerFeeQuote>,
+    account_secret: Option<&AccountSecret>,
+    rng: &mut R,
+) -> Result<Vec<PlannedTransaction>> {
+    let quoted = relayer.map_or(0, |quote| quote.fee);
+    if quoted != plan.fee_per_transaction {
+        return Err(anyhow!(
+            "Relayer fee {} differs from the planned fee {}; plan again with the quoted fee",
+            quoted,
+            plan.fee_per_transaction
+        ));
+    }
+    if let Some(step) = plan.steps.iter().find(|step| !step.recipients.is_empty()) {
+        return Err(anyhow!(
+            "A consolidation step pays recipients {:?}",
+            step.recipients
+        ));
+    }
+    multi_send::build_steps(
+        &plan.steps,
+        notes,
+        &[],
+        keys,
+        tree,
+        vortex,
+        relayer,
+        account_secret,
+        rng,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::ext_data_public_amount;
+    use crate::constants::ZERO_VALUE;
+    use crate::debug::find_unsatisfied;
+    use crate::field::{parse_fr, FieldParseMode};
+    use crate::note::Note;
+    use crate::poseidon_opt::{fr_from_str, PoseidonOptimized};
+    use crate::replay::circuit_from_input;
+    use ark_ff::PrimeField;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn wallet(amounts: &[u64], vortex: &Fr) -> (SpendingKeys, Vec<Utxo>) {
+        let keys = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+        let notes = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| {
+                let note = Note {
+                    amount,
+                    public_key: keys.viewing.spend_public_key.into_bigint().to_string(),
+                    blinding: (100 + i).to_string(),
+                    legacy_commitment: false,
+                };
+                Utxo::new(note, i as u64, vortex).unwrap()
+            })
+            .collect();
+        (keys, notes)
+    }
+
+    fn policy(fee_per_transaction: u64) -> ConsolidationPolicy {
+        ConsolidationPolicy {
+            dust_threshold: 1_000,
+            min_dust_notes: 3,
+            fee_per_transaction,
+            max_fee_bps: 1_000,
+            max_transactions: 8,
+        }
+    }
+
+    #[test]
+    fn test_plan_merges_largest_dust_while_worth_the_fee() {
+        let vortex = Fr::from(5u64);
+        let (_, notes) = wallet(&[500, 5_000, 300, 0, 200, 40, 900], &vortex);
+
+        let plan = plan_consolidation(&notes, &policy(20)).unwrap().unwrap();
+        // 40 cannot pay a fee of 20 within 10%, so it stays; 5_000 and 0 are not dust
+        assert_eq!(
+            plan.steps,
+            [
+                SendStep {
+                    notes: vec![6, 0],
+                    spends_previous_change: false,
+                    recipients: vec![],
+                    change: 1_380,
+                },
+                SendStep {
+                    notes: vec![2],
+                    spends_previous_change: true,
+                    recipients: vec![],
+                    change: 1_660,
+                },
+                SendStep {
+                    notes: vec![4],
+                    spends_previous_change: true,
+                    recipients: vec![],
+                    change: 1_840,
+                },
+            ]
+        );
+        assert_eq!(
+            (plan.merged_notes, plan.merged_amount, plan.total_fee),
+            (4, 1_900, 60)
+        );
+        assert_eq!(plan.consolidated_amount, 1_840);
+
+        let without_fee = plan_consolidation(&notes, &policy(0)).unwrap().unwrap();
+        assert_eq!(without_fee.merged_notes, 5);
+        let capped = ConsolidationPolicy {
+            max_transactions: 1,
+            ..policy(0)
+        };
+        assert_eq!(
+            plan_consolidation(&notes, &capped)
+                .unwrap()
+                .unwrap()
+                .steps
+                .len(),
+            1
+        );
+
+        let too_little_dust = ConsolidationPolicy {
+            min_dust_notes: 6,
+            ..policy(0)
+        };
+        assert!(plan_consolidation(&notes, &too_little_dust)
+            .unwrap()
+            .is_none());
+        assert!(plan_consolidation(&notes, &policy(200)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_built_steps_pay_the_relayer_and_satisfy_the_circuit() {
+        let mut rng = ChaCha20Rng::from_seed([6u8; 32]);
+        let vortex = Fr::from(5u64);
+        let (keys, notes) = wallet(&[500, 300, 200, 5_000], &vortex);
+        let plan = plan_consolidation(&notes, &policy(20)).unwrap().unwrap();
+        let quote = RelayerFeeQuote {
+            quote_id: "q1".into(),
+            relayer_address: "0xabc".into(),
+            fee: 20,
+            expires_at_ms: 0,
+        };
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+        let leaves: Vec<Fr> = notes
+            .iter()
+            .map(|utxo| parse_fr(&utxo.commitment, FieldParseMode::Strict).unwrap())
+            .collect();
+        tree.bulk_insert(&leaves, &hasher).unwrap();
+
+        let transactions = build_consolidation(
+            &plan,
+            &notes,
+            &keys,
+            &tree,
+            &vortex,
+            Some(&quote),
+            None,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[1].change.as_ref().unwrap().note.amount, 960);
+        for transaction in &transactions {
+            assert_eq!(transaction.ext_data.relayer, "0xabc");
+            assert_eq!(transaction.ext_data.relayer_fee, 20);
+            let circuit = circuit_from_input(&transaction.proof_input).unwrap();
+            circuit.check_public_inputs().unwrap();
+            assert!(find_unsatisfied(&circuit).unwrap().is_none());
+            assert_eq!(
+                transaction.proof_input.public_amount,
+                ext_data_public_amount(20, false).into_bigint().to_string()
+            );
+        }
+
+        assert!(
+            build_consolidation(&plan, &notes, &keys, &tree, &vortex, None, None, &mut rng)
+                .is_err()
+        );
+    }
+}
@@ -4,6 +4,16 @@
 /// Changing this requires redeploying contracts and regenerating files.
 pub const MERKLE_TREE_LEVEL: usize = 26;
 
+/// Depth of the 4-ary tree with the same capacity (4^13 = 2^26 commitments)
+///
+/// See `quad_merkle_tree`. No circuit variant or bundled key uses the quaternary tree yet.
+pub const QUAD_MERKLE_TREE_LEVEL: usize = MERKLE_TREE_LEVEL / 2;
+
+/// Height of the authority tree of registered note keys and rotation links (2^20 leaves)
+///
+/// See `rotation`. Only used by the key rotation circuit shape.
+pub const AUTHORITY_TREE_LEVEL: usize = 20;
+
 /// Number of input UTXOs per transaction
 ///
 /// Fixed at 2 for Vortex v1. Each input requires:
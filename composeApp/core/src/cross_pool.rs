@@ -0,0 +1,509 @@
+//! Moving funds from one pool into another as one resumable operation.
+//!
+//! A cross-pool transfer withdraws from the source pool to an intermediate Sui address,
+//! converts the coins when the pools hold different assets, and deposits into the target
+//! pool. Each leg is proven, submitted and confirmed separately and the app may be killed in
+//! between, so `CrossPoolTransfer` is a plain record the host persists after every change:
+//! `next_action` says what to do from the stored stage and `apply` records the outcome.
+//!
+//! Transitions are chosen so a retry never spends twice:
+//! - a withdrawal is only proven again after the submitted one is known to have failed, i.e.
+//!   its nullifiers are still unspent on chain
+//! - once the withdrawal is confirmed the funds sit at the intermediate address and every
+//!   later failure retries the deposit; nothing withdraws again
+//! - `Abandon` before anything is submitted cancels the transfer; after the withdrawal it
+//!   deposits the funds back into the source pool instead of converting them
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// A pool and the coin it holds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct PoolRef {
+    pub pool_id: String,
+    pub coin_type: String,
+}
+
+/// Where a transfer stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(
+    tag = "stage",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CrossPoolStage {
+    /// The withdrawal still has to be proven and submitted
+    WithdrawPending,
+    /// The withdrawal was submitted and spends `nullifiers`
+    WithdrawSubmitted {
+        tx_digest: String,
+        nullifiers: Vec<String>,
+    },
+    /// `amount` of the source coin waits at the intermediate address to be converted
+    ConversionPending { amount: u64 },
+    /// `amount` waits at the intermediate address to be deposited into `pool`
+    DepositPending { pool: PoolRef, amount: u64 },
+    /// The deposit was submitted and creates `commitments`
+    DepositSubmitted {
+        pool: PoolRef,
+        amount: u64,
+        tx_digest: String,
+        commitments: Vec<String>,
+    },
+    /// The funds landed in the target pool, or back in the source pool when `refunded`
+    Completed { tx_digest: String, refunded: bool },
+    /// Abandoned before anything was withdrawn
+    Cancelled,
+}
+
+/// What the host does next
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CrossPoolAction {
+    /// Prove and submit a withdrawal of `amount` from `pool` to `recipient`, then report
+    /// `WithdrawalSubmitted`
+    ProveWithdrawal {
+        pool: PoolRef,
+        amount: u64,
+        recipient: String,
+    },
+    /// Wait for the withdrawal; report `WithdrawalConfirmed` once `nullifiers` are spent, or
+    /// `WithdrawalFailed` once the transaction failed with them unspent
+    AwaitWithdrawal {
+        tx_digest: String,
+        nullifiers: Vec<String>,
+    },
+    /// Swap `amount` of `from_coin_type` at the intermediate address, then report `Converted`
+    Convert {
+        from_coin_type: String,
+        to_coin_type: String,
+        amount: u64,
+    },
+    /// Prove and submit a deposit of up to `amount` into `pool`, then report
+    /// `DepositSubmitted`
+    ProveDeposit { pool: PoolRef, amount: u64 },
+    /// Wait for the deposit; report `DepositConfirmed` or `DepositFailed`
+    AwaitDeposit {
+        tx_digest: String,
+        commitments: Vec<String>,
+    },
+    /// Nothing left to do
+    Done,
+}
+
+/// An outcome reported by the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(
+    tag = "event",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CrossPoolEvent {
+    WithdrawalSubmitted {
+        tx_digest: String,
+        nullifiers: Vec<String>,
+    },
+    /// `received` is what reached the intermediate address after relayer fees
+    WithdrawalConfirmed {
+        received: u64,
+    },
+    WithdrawalFailed {
+        error: String,
+    },
+    /// `received` of the target coin came out of the swap
+    Converted {
+        received: u64,
+    },
+    ConversionFailed {
+        error: String,
+    },
+    DepositSubmitted {
+        amount: u64,
+        tx_digest: String,
+        commitments: Vec<String>,
+    },
+    DepositConfirmed,
+    DepositFailed {
+        error: String,
+    },
+    /// Give up on reaching the target pool
+    Abandon,
+}
+
+/// A cross-pool transfer; persist it after every `apply`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct CrossPoolTransfer {
+    pub id: String,
+    pub source: PoolRef,
+    pub target: PoolRef,
+    /// Withdrawn from the source pool
+    pub amount: u64,
+    /// Sui address between the legs; it pays the gas of the deposit
+    pub intermediate_address: String,
+    pub stage: CrossPoolStage,
+    /// Failures reported in the current stage
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl CrossPoolTransfer {
+    /// # Errors
+    /// Returns error if `amount` is zero or the pools are the same.
+    pub fn new(
+        id: String,
+        source: PoolRef,
+        target: PoolRef,
+        amount: u64,
+        intermediate_address: String,
+    ) -> Result<Self> {
+        if amount == 0 {
+            return Err(anyhow!("Cross-pool transfer amount must be positive"));
+        }
+        if source.pool_id == target.pool_id {
+            return Err(anyhow!("Source and target pool are the same"));
+        }
+        Ok(Self {
+            id,
+            source,
+            target,
+            amount,
+            intermediate_address,
+            stage: CrossPoolStage::WithdrawPending,
+            attempts: 0,
+            last_error: None,
+        })
+    }
+
+    pub fn next_action(&self) -> CrossPoolAction {
+        match &self.stage {
+            CrossPoolStage::WithdrawPending => CrossPoolAction::ProveWithdrawal {
+                pool: self.source.clone(),
+                amount: self.amount,
+                recipient: self.intermediate_address.clone(),
+            },
+            CrossPoolStage::WithdrawSubmitted {
+                tx_digest,
+                nullifiers,
+            } => CrossPoolAction::AwaitWithdrawal {
+                tx_digest: tx_digest.clone(),
+                nullifiers: nullifiers.clone(),
+            },
+            CrossPoolStage::ConversionPending { amount } => CrossPoolAction::Convert {
+                from_coin_type: self.source.coin_type.clone(),
+                to_coin_type: self.target.coin_type.clone(),
+                amount: *amount,
+            },
+            CrossPoolStage::DepositPending { pool, amount } => CrossPoolAction::ProveDeposit {
+                pool: pool.clone(),
+                amount: *amount,
+            },
+            CrossPoolStage::DepositSubmitted {
+                tx_digest,
+                commitments,
+                ..
+            } => CrossPoolAction::AwaitDeposit {
+                tx_digest: tx_digest.clone(),
+                commitments: commitments.clone(),
+            },
+            CrossPoolStage::Completed { .. } | CrossPoolStage::Cancelled => CrossPoolAction::Done,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.stage,
+            CrossPoolStage::Completed { .. } | CrossPoolStage::Cancelled
+        )
+    }
+
+    /// Records `event`, leaving the transfer unchanged when it is not valid in the current
+    /// stage.
+    ///
+    /// # Errors
+    /// Returns error if the event does not belong to the current stage, or reports a deposit
+    /// larger than the funds waiting for it.
+    pub fn apply(&mut self, event: CrossPoolEvent) -> Result<()> {
+        use CrossPoolEvent as E;
+        use CrossPoolStage as S;
+
+        let next = match (&self.stage, event) {
+            (
+                S::WithdrawPending,
+                E::WithdrawalSubmitted {
+                    tx_digest,
+                    nullifiers,
+                },
+            ) => {
+                if nullifiers.is_empty() {
+                    return Err(anyhow!("A submitted withdrawal must list its nullifiers"));
+                }
+                S::WithdrawSubmitted {
+                    tx_digest,
+                    nullifiers,
+                }
+            }
+            // Proving or swapping failed before anything reached the chain: retry in place
+            (S::WithdrawPending, E::WithdrawalFailed { error })
+            | (S::ConversionPending { .. }, E::ConversionFailed { error })
+            | (S::DepositPending { .. }, E::DepositFailed { error }) => {
+                self.attempts += 1;
+                self.last_error = Some(error);
+                return Ok(());
+            }
+            (S::WithdrawSubmitted { .. }, E::WithdrawalFailed { error }) => {
+                self.attempts = 0;
+                self.last_error = Some(error);
+                self.stage = S::WithdrawPending;
+                return Ok(());
+            }
+            (S::WithdrawSubmitted { .. }, E::WithdrawalConfirmed { received }) => {
+                if self.source.coin_type == self.target.coin_type {
+                    S::DepositPending {
+                        pool: self.target.clone(),
+                        amount: received,
+                    }
+                } else {
+                    S::ConversionPending { amount: received }
+                }
+            }
+            (S::ConversionPending { .. }, E::Converted { received }) => S::DepositPending {
+                pool: self.target.clone(),
+                amount: received,
+            },
+            (
+                S::DepositPending { pool, amount },
+                E::DepositSubmitted {
+                    amount: deposited,
+                    tx_digest,
+                    commitments,
+                },
+            ) => {
+                if deposited == 0 || deposited > *amount {
+                    return Err(anyhow!(
+                        "Deposit of {} does not fit the {} waiting for it",
+                        deposited,
+                        amount
+                    ));
+                }
+                S::DepositSubmitted {
+                    pool: pool.clone(),
+                    amount: *amount,
+                    tx_digest,
+                    commitments,
+                }
+            }
+            (S::DepositSubmitted { pool, amount, .. }, E::DepositFailed { error }) => {
+                let pool = pool.clone();
+                let amount = *amount;
+                self.attempts = 0;
+                self.last_error = Some(error);
+                self.stage = S::DepositPending { pool, amount };
+                return Ok(());
+            }
+            (
+                S::DepositSubmitted {
+                    pool, tx_digest, ..
+                },
+                E::DepositConfirmed,
+            ) => S::Completed {
+                tx_digest: tx_digest.clone(),
+                refunded: pool.pool_id != self.target.pool_id,
+            },
+            (S::WithdrawPending, E::Abandon) => S::Cancelled,
+            (S::ConversionPending { amount }, E::Abandon) => S::DepositPending {
+                pool: self.source.clone(),
+                amount: *amount,
+            },
+            // Converted funds no longer fit the source pool, so only unconverted ones go back
+            (S::DepositPending { pool, amount }, E::Abandon)
+                if *pool == self.target && self.source.coin_type == self.target.coin_type =>
+            {
+                S::DepositPending {
+                    pool: self.source.clone(),
+                    amount: *amount,
+                }
+            }
+            (stage, event) => {
+                return Err(anyhow!(
+                    "Event {:?} is not valid in stage {:?}",
+                    event,
+                    stage
+                ));
+            }
+        };
+        self.stage = next;
+        self.attempts = 0;
+        self.last_error = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(id: &str, coin_type: &str) -> PoolRef {
+        PoolRef {
+            pool_id: id.into(),
+            coin_type: coin_type.into(),
+        }
+    }
+
+    fn transfer(target_coin: &str) -> CrossPoolTransfer {
+        CrossPoolTransfer::new(
+            "t1".into(),
+            pool("0x1", "0x2::sui::SUI"),
+            pool("0x2", target_coin),
+            1_000,
+            "0xabc".into(),
+        )
+        .unwrap()
+    }
+
+    fn withdrawn(transfer: &mut CrossPoolTransfer) {
+        transfer
+            .apply(CrossPoolEvent::WithdrawalSubmitted {
+                tx_digest: "w1".into(),
+                nullifiers: vec!["11".into(), "12".into()],
+            })
+            .unwrap();
+        transfer
+            .apply(CrossPoolEvent::WithdrawalConfirmed { received: 990 })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_transfer_runs_through_conversion_and_survives_persistence() {
+        let mut transfer = transfer("0xdba::usdc::USDC");
+        assert_eq!(
+            transfer.next_action(),
+            CrossPoolAction::ProveWithdrawal {
+                pool: transfer.source.clone(),
+                amount: 1_000,
+                recipient: "0xabc".into(),
+            }
+        );
+        withdrawn(&mut transfer);
+
+        let json = serde_json::to_string(&transfer).unwrap();
+        assert!(json.contains(r#""stage":"conversionPending""#));
+        let mut transfer: CrossPoolTransfer = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            transfer.next_action(),
+            CrossPoolAction::Convert { amount: 990, .. }
+        ));
+
+        transfer
+            .apply(CrossPoolEvent::ConversionFailed {
+                error: "slippage".into(),
+            })
+            .unwrap();
+        assert_eq!(transfer.attempts, 1);
+        transfer
+            .apply(CrossPoolEvent::Converted { received: 3_000 })
+            .unwrap();
+        assert_eq!(transfer.attempts, 0);
+        assert!(transfer
+            .apply(CrossPoolEvent::DepositSubmitted {
+                amount: 3_001,
+                tx_digest: "d1".into(),
+                commitments: vec![],
+            })
+            .is_err());
+        transfer
+            .apply(CrossPoolEvent::DepositSubmitted {
+                amount: 2_900,
+                tx_digest: "d1".into(),
+                commitments: vec!["21".into()],
+            })
+            .unwrap();
+        transfer.apply(CrossPoolEvent::DepositConfirmed).unwrap();
+        assert_eq!(
+            transfer.stage,
+            CrossPoolStage::Completed {
+                tx_digest: "d1".into(),
+                refunded: false,
+            }
+        );
+        assert!(transfer.is_finished());
+        assert_eq!(transfer.next_action(), CrossPoolAction::Done);
+    }
+
+    #[test]
+    fn test_failures_never_withdraw_twice() {
+        let mut transfer = transfer("0x2::sui::SUI");
+        transfer
+            .apply(CrossPoolEvent::WithdrawalSubmitted {
+                tx_digest: "w1".into(),
+                nullifiers: vec!["11".into()],
+            })
+            .unwrap();
+        assert!(transfer.apply(CrossPoolEvent::Abandon).is_err());
+        transfer
+            .apply(CrossPoolEvent::WithdrawalFailed {
+                error: "stale root".into(),
+            })
+            .unwrap();
+        assert_eq!(transfer.stage, CrossPoolStage::WithdrawPending);
+        withdrawn(&mut transfer);
+
+        // Same asset, so the funds go straight to the deposit and failures only retry it
+        let deposit = CrossPoolStage::DepositPending {
+            pool: transfer.target.clone(),
+            amount: 990,
+        };
+        assert_eq!(transfer.stage, deposit);
+        transfer
+            .apply(CrossPoolEvent::DepositSubmitted {
+                amount: 990,
+                tx_digest: "d1".into(),
+                commitments: vec!["21".into()],
+            })
+            .unwrap();
+        transfer
+            .apply(CrossPoolEvent::DepositFailed {
+                error: "gas".into(),
+            })
+            .unwrap();
+        assert_eq!(transfer.stage, deposit);
+        assert!(transfer
+            .apply(CrossPoolEvent::WithdrawalSubmitted {
+                tx_digest: "w2".into(),
+                nullifiers: vec!["13".into()],
+            })
+            .is_err());
+
+        transfer.apply(CrossPoolEvent::Abandon).unwrap();
+        assert!(matches!(
+            transfer.next_action(),
+            CrossPoolAction::ProveDeposit { ref pool, amount: 990 } if pool.pool_id == "0x1"
+        ));
+        transfer
+            .apply(CrossPoolEvent::DepositSubmitted {
+                amount: 990,
+                tx_digest: "d2".into(),
+                commitments: vec!["22".into()],
+            })
+            .unwrap();
+        transfer.apply(CrossPoolEvent::DepositConfirmed).unwrap();
+        assert!(matches!(
+            transfer.stage,
+            CrossPoolStage::Completed { refunded: true, .. }
+        ));
+
+        let mut cancelled = self::transfer("0xdba::usdc::USDC");
+        cancelled.apply(CrossPoolEvent::Abandon).unwrap();
+        assert_eq!(cancelled.stage, CrossPoolStage::Cancelled);
+    }
+}
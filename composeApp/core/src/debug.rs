@@ -0,0 +1,458 @@
+//! Debug-prove support: locating the first unsatisfied constraint, and dumping the
+//! constraint system for auditors.
+//!
+//! The circuit marks labelled regions (`input_1/merkle/level_17`) through `region()`. Labels
+//! are only recorded while `find_unsatisfied` or `dump_constraint_system` is running on the
+//! current thread; otherwise `region()` is a thread-local check and nothing else, so normal
+//! proving is unaffected.
+
+use crate::circuit::TransactionCircuit;
+use crate::constants::{N_INS, N_OUTS};
+use crate::prover;
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+#[cfg(feature = "setup")]
+use ark_relations::r1cs::SynthesisMode;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use serde::Serialize;
+use std::cell::RefCell;
+use zeroize::Zeroizing;
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct Recorder {
+    stack: Vec<String>,
+    /// (first constraint index, region path) in synthesis order
+    boundaries: Vec<(usize, String)>,
+    /// (constraint index, region path) of the innermost region left with no region entered
+    /// or constraint added since. While a synthesis error propagates, this is the region
+    /// it was raised in.
+    last_exit: Option<(usize, String)>,
+}
+
+impl Recorder {
+    fn mark(&mut self, index: usize) {
+        self.boundaries.push((index, self.stack.join("/")));
+    }
+
+    fn path_of(&self, index: usize) -> String {
+        self.boundaries
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= index)
+            .map(|(_, path)| path.clone())
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| "<root>".to_string())
+    }
+
+    /// Non-empty constraint ranges of each region, in synthesis order
+    #[cfg(feature = "setup")]
+    fn spans(&self, num_constraints: usize) -> Vec<RegionSpan> {
+        let root = (0, String::new());
+        let starts = std::iter::once(&root).chain(&self.boundaries);
+        let ends = self
+            .boundaries
+            .iter()
+            .map(|(start, _)| *start)
+            .chain([num_constraints]);
+        starts
+            .zip(ends)
+            .filter(|((start, _), end)| end > start)
+            .map(|((start, path), end)| RegionSpan {
+                start: *start as u64,
+                end: end as u64,
+                path: if path.is_empty() {
+                    "<root>".to_string()
+                } else {
+                    path.clone()
+                },
+            })
+            .collect()
+    }
+}
+
+/// Leaves the labelled region when dropped
+pub struct RegionGuard {
+    cs: Option<ConstraintSystemRef<Fr>>,
+}
+
+/// Enters a labelled region for constraint reporting.
+///
+/// `label` is only evaluated when a debug run is recording on this thread.
+pub fn region(cs: &ConstraintSystemRef<Fr>, label: impl FnOnce() -> String) -> RegionGuard {
+    let recording = RECORDER.with(|r| {
+        if let Some(recorder) = r.borrow_mut().as_mut() {
+            recorder.stack.push(label());
+            recorder.mark(cs.num_constraints());
+            recorder.last_exit = None;
+            true
+        } else {
+            false
+        }
+    });
+
+    RegionGuard {
+        cs: recording.then(|| cs.clone()),
+    }
+}
+
+impl Drop for RegionGuard {
+    fn drop(&mut self) {
+        if let Some(cs) = &self.cs {
+            RECORDER.with(|r| {
+                if let Some(recorder) = r.borrow_mut().as_mut() {
+                    let index = cs.num_constraints();
+                    if !matches!(&recorder.last_exit, Some((i, _)) if *i == index) {
+                        recorder.last_exit = Some((index, recorder.stack.join("/")));
+                    }
+                    recorder.stack.pop();
+                    recorder.mark(index);
+                }
+            });
+        }
+    }
+}
+
+/// A named value attached to a constraint report. Private values are redacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ReportValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// Location of the first unsatisfied constraint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ConstraintReport {
+    /// Index of the constraint in synthesis order
+    pub constraint_index: u64,
+    /// Region path, e.g. `input_1/merkle/level_17`
+    pub path: String,
+    /// Public inputs in full, plus redacted private values of the failing input/output slot
+    pub values: Vec<ReportValue>,
+}
+
+/// Synthesizes `circuit` with region labels and reports the first unsatisfied constraint,
+/// or `None` if every constraint holds.
+///
+/// A witness that cannot be assigned at all, such as the inverse `enforce_not_equal`
+/// needs for two equal nullifiers, is reported at the constraint index and region where
+/// synthesis stopped.
+pub fn find_unsatisfied(circuit: &TransactionCircuit) -> anyhow::Result<Option<ConstraintReport>> {
+    let Some((index, path)) = unsatisfied(circuit, false)?.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let values = report_values(circuit, &path);
+
+    Ok(Some(ConstraintReport {
+        constraint_index: index as u64,
+        path,
+        values,
+    }))
+}
+
+/// Every unsatisfied constraint of `circuit` as `(index, region path)`, in synthesis order,
+/// with unassignable witnesses reported as in `find_unsatisfied`.
+///
+/// Used by the constraint mutation tests to check that a malicious witness is rejected by
+/// exactly the constraint group meant to reject it.
+pub fn unsatisfied_constraints(circuit: &TransactionCircuit) -> anyhow::Result<Vec<(u64, String)>> {
+    Ok(unsatisfied(circuit, true)?
+        .into_iter()
+        .map(|(index, path)| (index as u64, path))
+        .collect())
+}
+
+/// Unsatisfied constraints (only the first unless `all`) with their region paths
+fn unsatisfied(circuit: &TransactionCircuit, all: bool) -> anyhow::Result<Vec<(usize, String)>> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    RECORDER.with(|r| *r.borrow_mut() = Some(Recorder::default()));
+    let synthesized = circuit.clone().generate_constraints(cs.clone());
+    let recorder = RECORDER.with(|r| r.borrow_mut().take()).unwrap_or_default();
+    if let Err(SynthesisError::AssignmentMissing) = synthesized {
+        let index = cs.num_constraints();
+        prover::wipe_witness(&cs);
+        let path = match recorder.last_exit {
+            Some((i, path)) if i == index => path,
+            _ => "<root>".to_string(),
+        };
+        return Ok(vec![(index, path)]);
+    }
+    synthesized?;
+
+    if cs.is_satisfied()? {
+        prover::wipe_witness(&cs);
+        return Ok(Vec::new());
+    }
+
+    cs.finalize();
+    let matrices = cs
+        .to_matrices()
+        .ok_or_else(|| anyhow::anyhow!("Constraint matrices were not constructed"))?;
+    let assignment = {
+        let inner = cs
+            .borrow()
+            .ok_or_else(|| anyhow::anyhow!("Constraint system is not available"))?;
+        Zeroizing::new(
+            [
+                inner.instance_assignment.as_slice(),
+                inner.witness_assignment.as_slice(),
+            ]
+            .concat(),
+        )
+    };
+    prover::wipe_witness(&cs);
+
+    let eval = |row: &[(Fr, usize)]| -> Fr {
+        row.iter().fold(Fr::ZERO, |acc, (coeff, var)| {
+            acc + *coeff * assignment[*var]
+        })
+    };
+
+    let failing = (0..matrices.num_constraints)
+        .filter(|&i| eval(&matrices.a[i]) * eval(&matrices.b[i]) != eval(&matrices.c[i]));
+    let failing: Vec<(usize, String)> = failing
+        .take(if all { usize::MAX } else { 1 })
+        .map(|index| (index, recorder.path_of(index)))
+        .collect();
+    if failing.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Constraint system is unsatisfied but no failing row found"
+        ));
+    }
+
+    Ok(failing)
+}
+
+/// Constraints `start..end` were synthesized inside the region at `path`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegionSpan {
+    pub start: u64,
+    pub end: u64,
+    pub path: String,
+}
+
+/// The R1CS of a circuit shape, for independent analysis.
+///
+/// Each matrix row is a constraint `<a_i, z> * <b_i, z> = <c_i, z>`, given as
+/// `(coefficient, variable)` pairs with decimal coefficients. In `z`, variable 0 is the
+/// constant one, variables `1..num_instance_variables` are the public inputs in the order
+/// of `public_inputs`, and the witness variables follow. These are the matrices the
+/// Groth16 setup and prover reduce to a QAP, which adds one row per instance variable.
+#[cfg(feature = "setup")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConstraintSystemDump {
+    pub num_instance_variables: u64,
+    pub num_witness_variables: u64,
+    pub num_constraints: u64,
+    pub public_inputs: Vec<String>,
+    pub regions: Vec<RegionSpan>,
+    pub a: Vec<Vec<(String, u64)>>,
+    pub b: Vec<Vec<(String, u64)>>,
+    pub c: Vec<Vec<(String, u64)>>,
+}
+
+#[cfg(feature = "setup")]
+impl ConstraintSystemDump {
+    /// Writes the dump as JSON
+    pub fn write_json(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+}
+
+/// Synthesizes the shape of `circuit` without a witness and returns its constraint
+/// matrices with region labels. Only the shape matters, so `TransactionCircuit::empty()`
+/// with the deployed options gives the deployed circuit.
+#[cfg(feature = "setup")]
+pub fn dump_constraint_system(
+    circuit: &TransactionCircuit,
+) -> anyhow::Result<ConstraintSystemDump> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+
+    RECORDER.with(|r| *r.borrow_mut() = Some(Recorder::default()));
+    let synthesized = circuit.clone().generate_constraints(cs.clone());
+    let recorder = RECORDER.with(|r| r.borrow_mut().take()).unwrap_or_default();
+    synthesized?;
+
+    let num_constraints = cs.num_constraints();
+    let regions = recorder.spans(num_constraints);
+    cs.finalize();
+    let matrices = cs
+        .to_matrices()
+        .ok_or_else(|| anyhow::anyhow!("Constraint matrices were not constructed"))?;
+
+    let rows = |matrix: Vec<Vec<(Fr, usize)>>| -> Vec<Vec<(String, u64)>> {
+        matrix
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(coeff, var)| (coeff.into_bigint().to_string(), var as u64))
+                    .collect()
+            })
+            .collect()
+    };
+
+    Ok(ConstraintSystemDump {
+        num_instance_variables: matrices.num_instance_variables as u64,
+        num_witness_variables: matrices.num_witness_variables as u64,
+        num_constraints: matrices.num_constraints as u64,
+        public_inputs: circuit
+            .public_input_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        regions,
+        a: rows(matrices.a),
+        b: rows(matrices.b),
+        c: rows(matrices.c),
+    })
+}
+
+fn public(name: &str, value: &Fr) -> ReportValue {
+    ReportValue {
+        name: name.to_string(),
+        value: value.into_bigint().to_string(),
+    }
+}
+
+/// Private values only reveal whether they are zero, which is what most dummy-input and
+/// amount mistakes come down to
+fn redacted(name: String, value: &Fr) -> ReportValue {
+    let value = if *value == Fr::ZERO {
+        "0"
+    } else {
+        "<redacted>"
+    };
+    ReportValue {
+        name,
+        value: value.to_string(),
+    }
+}
+
+fn report_values(circuit: &TransactionCircuit, path: &str) -> Vec<ReportValue> {
+    let mut values = vec![
+        public("vortex", &circuit.vortex),
+        public("root", &circuit.root),
+        public("public_amount", &circuit.public_amount),
+        public("input_nullifier_0", &circuit.input_nullifier_0),
+        public("input_nullifier_1", &circuit.input_nullifier_1),
+        public("output_commitment_0", &circuit.output_commitment_0),
+        public("output_commitment_1", &circuit.output_commitment_1),
+        public("hashed_account_secret", &circuit.hashed_account_secret),
+    ];
+
+    let slot = path.split('/').next().unwrap_or_default();
+    if slot == "account_secret" {
+        values.push(redacted("account_secret".into(), &circuit.account_secret));
+    }
+    for i in 0..N_INS {
+        if slot == format!("input_{}", i) {
+            values.push(redacted(
+                format!("in_private_key_{}", i),
+                &circuit.in_private_keys[i],
+            ));
+            values.push(redacted(format!("in_amount_{}", i), &circuit.in_amounts[i]));
+            values.push(redacted(
+                format!("in_blinding_{}", i),
+                &circuit.in_blindings[i],
+            ));
+            values.push(redacted(
+                format!("in_path_index_{}", i),
+                &circuit.in_path_indices[i],
+            ));
+        }
+    }
+    for i in 0..N_OUTS {
+        if slot == format!("output_{}", i) {
+            values.push(redacted(
+                format!("out_public_key_{}", i),
+                &circuit.out_public_keys[i],
+            ));
+            values.push(redacted(
+                format!("out_amount_{}", i),
+                &circuit.out_amounts[i],
+            ));
+            values.push(redacted(
+                format!("out_blinding_{}", i),
+                &circuit.out_blindings[i],
+            ));
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{compute_commitment, compute_nullifier, sample_zero_transfer};
+    use crate::poseidon_opt::hash1;
+
+    #[cfg(feature = "setup")]
+    #[test]
+    fn test_dump_labels_every_constraint() {
+        let circuit = TransactionCircuit::empty();
+        let dump = dump_constraint_system(&circuit).unwrap();
+
+        assert_eq!(dump.a.len() as u64, dump.num_constraints);
+        assert_eq!(
+            dump.num_instance_variables as usize,
+            circuit.get_public_inputs().len() + 1
+        );
+        let covered: u64 = dump.regions.iter().map(|r| r.end - r.start).sum();
+        assert_eq!(covered, dump.num_constraints);
+        assert!(dump.regions.iter().any(|r| r.path == "input_0/merkle"));
+    }
+
+    #[test]
+    fn test_satisfied_circuit_has_no_report() {
+        assert_eq!(find_unsatisfied(&sample_zero_transfer()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_reports_region_of_first_failure() {
+        let mut circuit = sample_zero_transfer();
+        circuit.input_nullifier_1 = Fr::from(1u64);
+
+        let report = find_unsatisfied(&circuit).unwrap().unwrap();
+        assert_eq!(report.path, "input_1/nullifier");
+        assert!(report.values.contains(&redacted(
+            "in_private_key_1".into(),
+            &circuit.in_private_keys[1]
+        )));
+
+        // A spend of value without a valid path fails in the Merkle region
+        let mut circuit = sample_zero_transfer();
+        circuit.in_amounts[0] = Fr::from(5u64);
+        circuit.input_nullifier_0 = compute_nullifier(
+            &compute_commitment(
+                &circuit.in_amounts[0],
+                &hash1(&circuit.in_private_keys[0]),
+                &circuit.in_blindings[0],
+                &circuit.vortex,
+            ),
+            &circuit.in_path_indices[0],
+            &circuit.in_private_keys[0],
+        );
+
+        let report = find_unsatisfied(&circuit).unwrap().unwrap();
+        assert_eq!(report.path, "input_0/merkle");
+        assert!(report.values.contains(&ReportValue {
+            name: "in_amount_0".into(),
+            value: "<redacted>".into(),
+        }));
+    }
+}
@@ -0,0 +1,290 @@
+//! Delegated proving: offloading proof generation to a remote prover.
+//!
+//! The device seals its `ProofInput` to the prover's X25519 key (ephemeral ECDH, HKDF-SHA256,
+//! ChaCha20-Poly1305), sends the envelope, and checks the returned proof itself: the public
+//! inputs must equal the ones of the circuit built from the local input, the session nonce
+//! must be echoed back, and the Groth16 proof must verify against them. A remote prover therefore learns the witness but cannot substitute a
+//! different transaction.
+
+use crate::field::{parse_fr, FieldParseMode};
+use crate::interop::{ProofInput, ProofOutput};
+use crate::replay;
+use crate::validation::{self, PointValidation};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, VerifyingKey};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+/// Envelope format version
+pub const WITNESS_ENVELOPE_VERSION: u8 = 1;
+
+const HKDF_INFO: &[u8] = b"vortex-delegated-witness-v1";
+
+/// A `ProofInput` sealed to a remote prover
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedWitness {
+    pub version: u8,
+    /// Hex-encoded ephemeral X25519 public key of the sender
+    pub ephemeral_public_key: String,
+    /// Hex-encoded 12-byte AEAD nonce
+    pub nonce: String,
+    /// Hex-encoded ChaCha20-Poly1305 ciphertext of the `ProofInput` JSON
+    pub ciphertext: String,
+}
+
+/// Seals `input` so that only the holder of `prover_public_key` can read it
+pub fn encrypt_witness<R: RngCore + CryptoRng>(
+    input: &ProofInput,
+    prover_public_key: &[u8],
+    rng: &mut R,
+) -> Result<EncryptedWitness> {
+    let prover_public = PublicKey::from(to_key_bytes(prover_public_key, "prover public key")?);
+    let ephemeral = EphemeralSecret::random_from_rng(&mut *rng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&prover_public);
+    if !shared.was_contributory() {
+        return Err(anyhow!("Prover public key is a low-order point"));
+    }
+
+    let cipher = envelope_cipher(shared.as_bytes(), &ephemeral_public, &prover_public)?;
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut nonce);
+
+    let plaintext =
+        Zeroizing::new(serde_json::to_vec(input).context("Failed to serialize proof input")?);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &[WITNESS_ENVELOPE_VERSION],
+            },
+        )
+        .map_err(|_| anyhow!("Failed to encrypt witness"))?;
+
+    Ok(EncryptedWitness {
+        version: WITNESS_ENVELOPE_VERSION,
+        ephemeral_public_key: hex::encode(ephemeral_public.as_bytes()),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+/// Opens an envelope on the prover side
+pub fn decrypt_witness(
+    envelope: &EncryptedWitness,
+    prover_secret_key: &[u8],
+) -> Result<ProofInput> {
+    if envelope.version != WITNESS_ENVELOPE_VERSION {
+        return Err(anyhow!(
+            "Unsupported witness envelope version {}",
+            envelope.version
+        ));
+    }
+
+    let secret = StaticSecret::from(*Zeroizing::new(to_key_bytes(
+        prover_secret_key,
+        "prover secret key",
+    )?));
+    let prover_public = PublicKey::from(&secret);
+    let ephemeral_public = PublicKey::from(to_key_bytes(
+        &decode_hex(&envelope.ephemeral_public_key, "ephemeral public key")?,
+        "ephemeral public key",
+    )?);
+    let shared = secret.diffie_hellman(&ephemeral_public);
+
+    let cipher = envelope_cipher(shared.as_bytes(), &ephemeral_public, &prover_public)?;
+    let nonce = decode_hex(&envelope.nonce, "nonce")?;
+    if nonce.len() != 12 {
+        return Err(anyhow!("Nonce must be 12 bytes, got {}", nonce.len()));
+    }
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &decode_hex(&envelope.ciphertext, "ciphertext")?,
+                    aad: &[envelope.version],
+                },
+            )
+            .map_err(|_| anyhow!("Witness envelope failed authentication"))?,
+    );
+
+    serde_json::from_slice(&plaintext).context("Decrypted witness is not a valid proof input")
+}
+
+/// Public inputs the proof for `input` must commit to, from the circuit `input` describes
+pub fn expected_public_inputs(input: &ProofInput) -> Result<Vec<Fr>> {
+    Ok(replay::circuit_from_input(input)?.get_public_inputs())
+}
+
+/// Checks a proof returned by a remote prover against the locally known input.
+///
+/// # Errors
+/// Returns error if the output does not echo the input's session nonce, the reported public
+/// inputs differ from the expected ones, the proof does not deserialize with full point
+/// validation, or the Groth16 check fails.
+pub fn verify_delegated_proof(
+    output: &ProofOutput,
+    input: &ProofInput,
+    vk: &VerifyingKey<Bn254>,
+) -> Result<()> {
+    if let Some(nonce) = &input.session_nonce {
+        output.check_session_nonce(nonce)?;
+    }
+    let expected = expected_public_inputs(input)?;
+    let reported = output
+        .public_inputs
+        .iter()
+        .map(|s| parse_fr(s, FieldParseMode::Strict))
+        .collect::<Result<Vec<Fr>>>()
+        .context("Remote prover returned invalid public inputs")?;
+
+    if reported.len() != expected.len() {
+        return Err(anyhow!(
+            "Remote prover returned {} public inputs, expected {}",
+            reported.len(),
+            expected.len()
+        ));
+    }
+    if let Some(i) = (0..expected.len()).find(|&i| reported[i] != expected[i]) {
+        return Err(anyhow!(
+            "Remote prover changed public input {}: expected {}, got {}",
+            i,
+            expected[i].into_bigint(),
+            reported[i].into_bigint()
+        ));
+    }
+
+    let proof_bytes = decode_hex(&output.proof_serialized_hex, "proof")?;
+    let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)?;
+    let pvk = ark_groth16::prepare_verifying_key(vk);
+    if !Groth16::<Bn254>::verify_proof(&pvk, &proof, &expected)? {
+        return Err(anyhow!("Remote proof does not verify"));
+    }
+
+    Ok(())
+}
+
+fn envelope_cipher(
+    shared_secret: &[u8; 32],
+    ephemeral_public: &PublicKey,
+    prover_public: &PublicKey,
+) -> Result<ChaCha20Poly1305> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public.as_bytes());
+    salt[32..].copy_from_slice(prover_public.as_bytes());
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(HKDF_INFO, key.as_mut())
+        .map_err(|_| anyhow!("Failed to derive envelope key"))?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+fn to_key_bytes(bytes: &[u8], what: &str) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("{} must be 32 bytes, got {}", what, bytes.len()))
+}
+
+fn decode_hex(s: &str, what: &str) -> Result<Vec<u8>> {
+    hex::decode(s.trim_start_matches("0x")).with_context(|| format!("Invalid {} hex", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MERKLE_TREE_LEVEL;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn sample_input() -> ProofInput {
+        let path = vec![["0", "0"]; MERKLE_TREE_LEVEL];
+        let mut input: ProofInput = serde_json::from_value(serde_json::json!({
+            "vortex": "1", "root": "2", "publicAmount": "3",
+            "inputNullifier0": "4", "inputNullifier1": "5",
+            "outputCommitment0": "6", "outputCommitment1": "7",
+            "hashedAccountSecret": "8", "accountSecret": "18",
+            "inPrivateKey0": "10", "inPrivateKey1": "11",
+            "inAmount0": "0", "inAmount1": "0",
+            "inBlinding0": "12", "inBlinding1": "13",
+            "inPathIndex0": "0", "inPathIndex1": "1",
+            "merklePath0": path.clone(), "merklePath1": path,
+            "outPublicKey0": "14", "outPublicKey1": "15",
+            "outAmount0": "0", "outAmount1": "0",
+            "outBlinding0": "16", "outBlinding1": "17",
+            "extDataHash": "9"
+        }))
+        .unwrap();
+        input.session_nonce = Some("req-1".to_string());
+        input
+    }
+
+    #[test]
+    fn test_witness_round_trip_and_tamper_detection() {
+        let mut rng = ChaCha20Rng::from_seed([3u8; 32]);
+        let prover_secret = [42u8; 32];
+        let prover_public = PublicKey::from(&StaticSecret::from(prover_secret));
+
+        let input = sample_input();
+        let envelope = encrypt_witness(&input, prover_public.as_bytes(), &mut rng).unwrap();
+        let opened = decrypt_witness(&envelope, &prover_secret).unwrap();
+        assert_eq!(opened.account_secret, input.account_secret);
+        assert_eq!(opened.session_nonce, input.session_nonce);
+
+        assert!(decrypt_witness(&envelope, &[7u8; 32]).is_err());
+
+        let mut ciphertext = hex::decode(&envelope.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let tampered = EncryptedWitness {
+            ciphertext: hex::encode(ciphertext),
+            ..envelope
+        };
+        assert!(decrypt_witness(&tampered, &prover_secret).is_err());
+    }
+
+    #[test]
+    fn test_expected_public_inputs_follow_circuit_order() {
+        let expected: Vec<Fr> = (1..=9u64).map(Fr::from).collect();
+        assert_eq!(expected_public_inputs(&sample_input()).unwrap(), expected);
+
+        let mut input = sample_input();
+        input.without_account_secret = true;
+        let expected: Vec<Fr> = [1, 2, 3, 4, 5, 6, 7, 9].map(Fr::from).to_vec();
+        assert_eq!(expected_public_inputs(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_session_nonce_must_be_echoed() {
+        let input = sample_input();
+        let vk = VerifyingKey::<Bn254>::default();
+        let output = |session_nonce: Option<&str>| ProofOutput {
+            proof_a: Vec::new(),
+            proof_b: Vec::new(),
+            proof_c: Vec::new(),
+            public_inputs: Vec::new(),
+            proof_serialized_hex: String::new(),
+            public_inputs_serialized_hex: String::new(),
+            session_nonce: session_nonce.map(str::to_string),
+        };
+
+        let err = verify_delegated_proof(&output(Some("req-2")), &input, &vk).unwrap_err();
+        assert!(err.to_string().contains("Session nonce mismatch"));
+        let err = verify_delegated_proof(&output(None), &input, &vk).unwrap_err();
+        assert!(err.to_string().contains("no session nonce"));
+        // The nonce matches, so the check moves on to the public inputs
+        let err = verify_delegated_proof(&output(Some("req-1")), &input, &vk).unwrap_err();
+        assert!(err.to_string().contains("public inputs"));
+    }
+}
@@ -0,0 +1,304 @@
+//! Deposit note strings for backing up a single note.
+//!
+//! A deposit note string carries everything needed to spend one note later, so it can be
+//! written down or stored like a mixer note: `vortex-sui-v1-<asset>-<data>`, where `asset`
+//! names the pool's coin in lowercase and `data` is the hex of
+//! `private_key (32, LE) || blinding (32, LE) || amount (8, LE) || flags (1) || checksum (4)`.
+//! Bit 0 of `flags` marks a legacy `Poseidon3` commitment, and the checksum is the first four
+//! bytes of SHA-256 over the string's prefix and the preceding payload bytes, so a mistyped
+//! character or a note pasted under the wrong asset fails to decode.
+//!
+//! Anyone holding the string can spend the note; hosts must treat it like a spending key.
+
+use crate::circuit::{compute_commitment, compute_legacy_commitment};
+use crate::field::{fr_from_bytes_le, fr_to_bytes_le, parse_fr, FieldParseMode, FR_BYTES};
+use crate::note::Note;
+use crate::poseidon_opt::hash1;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Chain part of the prefix
+pub const DEPOSIT_NOTE_PREFIX: &str = "vortex-sui";
+
+/// Note string format version
+pub const DEPOSIT_NOTE_VERSION: u8 = 1;
+
+const FLAG_LEGACY_COMMITMENT: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+const PAYLOAD_LEN: usize = 2 * FR_BYTES + 8 + 1;
+const MAX_ASSET_LEN: usize = 16;
+
+/// A spendable note as carried by a deposit note string; wiped on drop
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct DepositNote {
+    /// Lowercase coin name of the pool, e.g. `sui`
+    pub asset: String,
+    pub amount: u64,
+    pub private_key: Fr,
+    pub blinding: Fr,
+    pub legacy_commitment: bool,
+}
+
+impl std::fmt::Debug for DepositNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DepositNote")
+            .field("asset", &self.asset)
+            .field("amount", &self.amount)
+            .field("private_key", &"<redacted>")
+            .field("blinding", &"<redacted>")
+            .field("legacy_commitment", &self.legacy_commitment)
+            .finish()
+    }
+}
+
+/// A `DepositNote` as passed to and from the hosts
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct DepositNoteParts {
+    pub asset: String,
+    pub note: Note,
+    /// Decimal private key spending the note
+    pub private_key: String,
+}
+
+impl std::fmt::Debug for DepositNoteParts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DepositNoteParts")
+            .field("asset", &self.asset)
+            .field("note", &self.note)
+            .field("private_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl DepositNote {
+    /// Rebuilds a deposit note from the parts a host passed in.
+    ///
+    /// # Errors
+    /// Returns error if a field element does not parse or the note's public key is not the
+    /// one of `private_key`.
+    pub fn from_parts(parts: &DepositNoteParts) -> Result<Self> {
+        let deposit = Self {
+            asset: parts.asset.clone(),
+            amount: parts.note.amount,
+            private_key: parse_fr(&parts.private_key, FieldParseMode::Strict)
+                .context("Invalid private key")?,
+            blinding: parse_fr(&parts.note.blinding, FieldParseMode::Strict)
+                .context("Invalid note blinding")?,
+            legacy_commitment: parts.note.legacy_commitment,
+        };
+        let public_key = parse_fr(&parts.note.public_key, FieldParseMode::Strict)
+            .context("Invalid note public key")?;
+        if deposit.public_key() != public_key {
+            return Err(anyhow!("Private key does not own the note"));
+        }
+        Ok(deposit)
+    }
+
+    /// Host-facing form, with the note record the wallet stores
+    pub fn to_parts(&self) -> DepositNoteParts {
+        DepositNoteParts {
+            asset: self.asset.clone(),
+            note: self.note(),
+            private_key: self.private_key.into_bigint().to_string(),
+        }
+    }
+
+    /// `Poseidon1(private_key)`, the note's public key
+    pub fn public_key(&self) -> Fr {
+        hash1(&self.private_key)
+    }
+
+    /// The note record the wallet stores
+    pub fn note(&self) -> Note {
+        Note {
+            amount: self.amount,
+            public_key: self.public_key().into_bigint().to_string(),
+            blinding: self.blinding.into_bigint().to_string(),
+            legacy_commitment: self.legacy_commitment,
+        }
+    }
+
+    /// Commitment to look the note up by in the pool's events
+    pub fn commitment(&self, vortex: &Fr) -> Fr {
+        let amount = Fr::from(self.amount);
+        if self.legacy_commitment {
+            compute_legacy_commitment(&amount, &self.public_key(), &self.blinding)
+        } else {
+            compute_commitment(&amount, &self.public_key(), &self.blinding, vortex)
+        }
+    }
+
+    /// Encodes the note as `vortex-sui-v1-<asset>-<data>`.
+    ///
+    /// # Errors
+    /// Returns error if the asset is empty, longer than 16 characters or not lowercase
+    /// alphanumeric.
+    pub fn encode(&self) -> Result<Zeroizing<String>> {
+        check_asset(&self.asset)?;
+        let prefix = prefix(&self.asset);
+
+        let mut data = Zeroizing::new(Vec::with_capacity(PAYLOAD_LEN + CHECKSUM_LEN));
+        data.extend_from_slice(&fr_to_bytes_le(&self.private_key));
+        data.extend_from_slice(&fr_to_bytes_le(&self.blinding));
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.push(if self.legacy_commitment {
+            FLAG_LEGACY_COMMITMENT
+        } else {
+            0
+        });
+        let checksum = checksum(&prefix, &data);
+        data.extend_from_slice(&checksum);
+
+        Ok(Zeroizing::new(format!("{}{}", prefix, hex::encode(&*data))))
+    }
+
+    /// Parses a note string, rejecting other versions, unknown flags, bad checksums and
+    /// non-canonical field elements.
+    pub fn decode(note: &str) -> Result<Self> {
+        let note = note.trim();
+        let rest = note
+            .strip_prefix(DEPOSIT_NOTE_PREFIX)
+            .and_then(|rest| rest.strip_prefix("-v"))
+            .ok_or_else(|| anyhow!("Not a Vortex deposit note"))?;
+        let (version, rest) = rest
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Deposit note has no asset"))?;
+        if version != DEPOSIT_NOTE_VERSION.to_string() {
+            return Err(anyhow!("Unsupported deposit note version {}", version));
+        }
+        let (asset, data_hex) = rest
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Deposit note has no data"))?;
+        check_asset(asset)?;
+
+        let data = Zeroizing::new(hex::decode(data_hex).context("Deposit note data is not hex")?);
+        if data.len() != PAYLOAD_LEN + CHECKSUM_LEN {
+            return Err(anyhow!(
+                "Deposit note data must be {} bytes, got {}",
+                PAYLOAD_LEN + CHECKSUM_LEN,
+                data.len()
+            ));
+        }
+        let (payload, expected) = data.split_at(PAYLOAD_LEN);
+        if checksum(&prefix(asset), payload) != expected {
+            return Err(anyhow!("Deposit note checksum does not match"));
+        }
+
+        let flags = payload[PAYLOAD_LEN - 1];
+        if flags & !FLAG_LEGACY_COMMITMENT != 0 {
+            return Err(anyhow!("Deposit note has unknown flags {:#04x}", flags));
+        }
+        let amount = &payload[2 * FR_BYTES..PAYLOAD_LEN - 1];
+        Ok(Self {
+            asset: asset.to_string(),
+            amount: u64::from_le_bytes(amount.try_into().unwrap()),
+            private_key: fr_from_bytes_le(&payload[..FR_BYTES])
+                .context("Deposit note private key is not a canonical field element")?,
+            blinding: fr_from_bytes_le(&payload[FR_BYTES..2 * FR_BYTES])
+                .context("Deposit note blinding is not a canonical field element")?,
+            legacy_commitment: flags & FLAG_LEGACY_COMMITMENT != 0,
+        })
+    }
+}
+
+fn prefix(asset: &str) -> String {
+    format!(
+        "{}-v{}-{}-",
+        DEPOSIT_NOTE_PREFIX, DEPOSIT_NOTE_VERSION, asset
+    )
+}
+
+fn checksum(prefix: &str, payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::new()
+        .chain_update(prefix.as_bytes())
+        .chain_update(payload)
+        .finalize();
+    digest[..CHECKSUM_LEN].try_into().unwrap()
+}
+
+fn check_asset(asset: &str) -> Result<()> {
+    if asset.is_empty()
+        || asset.len() > MAX_ASSET_LEN
+        || !asset
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    {
+        return Err(anyhow!(
+            "Deposit note asset '{}' must be 1 to {} lowercase letters or digits",
+            asset,
+            MAX_ASSET_LEN
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit() -> DepositNote {
+        DepositNote {
+            asset: "sui".into(),
+            amount: 1_000_000_000,
+            private_key: Fr::from(11u64),
+            blinding: Fr::from(22u64),
+            legacy_commitment: false,
+        }
+    }
+
+    #[test]
+    fn test_note_string_round_trips() {
+        let encoded = deposit().encode().unwrap();
+        assert!(encoded.starts_with("vortex-sui-v1-sui-"));
+        assert_eq!(encoded.len(), "vortex-sui-v1-sui-".len() + 2 * 77);
+        assert_eq!(DepositNote::decode(&encoded).unwrap(), deposit());
+
+        let mut legacy = deposit();
+        legacy.legacy_commitment = true;
+        let decoded = DepositNote::decode(&legacy.encode().unwrap()).unwrap();
+        assert!(decoded.legacy_commitment);
+        assert_eq!(
+            decoded.note().commitment(&Fr::from(5u64)).unwrap(),
+            legacy.commitment(&Fr::from(5u64))
+        );
+        assert!(!format!("{:?}", decoded).contains("22"));
+        assert_eq!(
+            DepositNote::from_parts(&decoded.to_parts()).unwrap(),
+            legacy
+        );
+
+        let mut stolen = decoded.to_parts();
+        stolen.private_key = "12".into();
+        assert!(DepositNote::from_parts(&stolen).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_and_foreign_notes_are_rejected() {
+        let encoded = deposit().encode().unwrap();
+
+        let mut typo = encoded.as_bytes().to_vec();
+        let at = typo.len() - 20;
+        typo[at] = if typo[at] == b'0' { b'1' } else { b'0' };
+        let err = DepositNote::decode(std::str::from_utf8(&typo).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+
+        let other_asset = encoded.replace("-v1-sui-", "-v1-usdc-");
+        assert!(DepositNote::decode(&other_asset).is_err());
+        let other_version = encoded.replacen("-v1-", "-v2-", 1);
+        assert!(DepositNote::decode(&other_version)
+            .unwrap_err()
+            .to_string()
+            .contains("version 2"));
+        assert!(DepositNote::decode("tornado-eth-0.1-1-0xabc").is_err());
+
+        let mut upper = deposit();
+        upper.asset = "SUI".into();
+        assert!(upper.encode().is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Transaction dry runs for confirmation screens.
+//!
+//! `dry_run` does everything a transaction needs before the proof: it selects the notes to
+//! spend, plans the amounts, and derives the nullifiers and output commitments the proof
+//! will publish. The UI can render the confirmation screen from the report straight away.
+//! Proving the same intent with the same output blindings publishes exactly these values.
+
+use crate::{
+    amount::{self, AmountPlan, TransferIntent},
+    circuit::compute_commitment,
+    constants::{N_INS, N_OUTS},
+    field::{parse_fr, FieldParseMode},
+    note::Utxo,
+    poseidon_opt::hash1,
+};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+/// A payment to dry-run against the wallet's notes, in MIST
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransactionIntent {
+    /// Notes the inputs may be selected from; spent ones are skipped
+    pub notes: Vec<Utxo>,
+    /// Value moved into the pool from the sender's coins
+    pub deposit: u64,
+    /// Value of the shielded output paid to the recipient
+    pub send: u64,
+    /// Decimal note public key of the recipient; the sender's own key when absent
+    pub recipient_public_key: Option<String>,
+    /// Value paid out of the pool to a public address
+    pub withdraw: u64,
+    /// Relayer fee, paid out of the pool next to the withdrawal
+    pub fee: u64,
+    /// Decimal blindings of the recipient and change outputs, as they will be proven
+    pub output_blindings: Vec<String>,
+}
+
+/// What a transaction will publish and move, computed without proving
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport {
+    /// Notes selected as inputs; the remaining slots are padded with dummies
+    pub selected: Vec<Utxo>,
+    /// Decimal nullifiers of the selected notes
+    pub nullifiers: Vec<String>,
+    /// Decimal `output_commitment`s of the recipient and change outputs
+    pub output_commitments: Vec<String>,
+    pub amounts: AmountPlan,
+    /// Sum of the selected notes
+    pub input_total: u64,
+    /// Relayer fee deducted from the pool
+    pub fee: u64,
+}
+
+/// Selects notes for `intent` and derives what its transaction publishes.
+///
+/// # Errors
+/// Returns error if the unspent notes cannot cover the intent within `N_INS` inputs, the
+/// amounts are invalid (see `amount::plan_amounts`), or a key, blinding or note does not
+/// parse.
+pub fn dry_run(intent: &TransactionIntent, private_key: &Fr, vortex: &Fr) -> Result<DryRunReport> {
+    if intent.output_blindings.len() != N_OUTS {
+        return Err(anyhow!(
+            "Expected {} output blindings, got {}",
+            N_OUTS,
+            intent.output_blindings.len()
+        ));
+    }
+
+    let needed = (intent.send as u128 + intent.withdraw as u128 + intent.fee as u128)
+        .saturating_sub(intent.deposit as u128);
+    let selected = select_notes(&intent.notes, needed)?;
+
+    let amounts = amount::plan_amounts(&TransferIntent {
+        input_amounts: selected.iter().map(|utxo| utxo.note.amount).collect(),
+        deposit: intent.deposit,
+        send: intent.send,
+        withdraw: intent.withdraw,
+        fee: intent.fee,
+    })?;
+
+    let nullifiers = selected
+        .iter()
+        .map(|utxo| Ok(utxo.nullifier(private_key)?.into_bigint().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let own_public_key = hash1(private_key);
+    let recipient_public_key = match &intent.recipient_public_key {
+        Some(key) => {
+            parse_fr(key, FieldParseMode::Strict).context("Invalid recipient public key")?
+        }
+        None => own_public_key,
+    };
+    let outputs = [
+        (amounts.send_amount, recipient_public_key),
+        (amounts.change_amount, own_public_key),
+    ];
+    let output_commitments = outputs
+        .iter()
+        .zip(&intent.output_blindings)
+        .map(|((amount, public_key), blinding)| {
+            let blinding =
+                parse_fr(blinding, FieldParseMode::Strict).context("Invalid output blinding")?;
+            Ok(
+                compute_commitment(&Fr::from(*amount), public_key, &blinding, vortex)
+                    .into_bigint()
+                    .to_string(),
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let input_total = selected.iter().map(|utxo| utxo.note.amount).sum();
+    Ok(DryRunReport {
+        selected,
+        nullifiers,
+        output_commitments,
+        amounts,
+        input_total,
+        fee: intent.fee,
+    })
+}
+
+/// Picks unspent notes summing to at least `needed`.
+///
+/// The smallest single note that covers it is preferred, which keeps the change small and
+/// leaves large notes for large payments. Otherwise the `N_INS` largest notes are used.
+fn select_notes(notes: &[Utxo], needed: u128) -> Result<Vec<Utxo>> {
+    if needed == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut unspent: Vec<&Utxo> = notes.iter().filter(|utxo| !utxo.metadata.spent).collect();
+    unspent.sort_by_key(|utxo| utxo.note.amount);
+
+    if let Some(single) = unspent
+        .iter()
+        .find(|utxo| utxo.note.amount as u128 >= needed)
+    {
+        return Ok(vec![(*single).clone()]);
+    }
+
+    let largest: Vec<Utxo> = unspent
+        .iter()
+        .rev()
+        .take(N_INS)
+        .map(|&utxo| utxo.clone())
+        .collect();
+    let total: u128 = largest.iter().map(|utxo| utxo.note.amount as u128).sum();
+    if total < needed {
+        return Err(anyhow!(
+            "Needs {} but the {} largest unspent notes only hold {}",
+            needed,
+            N_INS,
+            total
+        ));
+    }
+    Ok(largest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+
+    fn utxo(amount: u64, leaf_index: u64, private_key: &Fr, vortex: &Fr) -> Utxo {
+        let note = Note {
+            amount,
+            public_key: hash1(private_key).into_bigint().to_string(),
+            blinding: (leaf_index + 100).to_string(),
+            legacy_commitment: false,
+        };
+        Utxo::new(note, leaf_index, vortex).unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_selects_notes_and_derives_public_values() {
+        let private_key = Fr::from(12345u64);
+        let vortex = Fr::from(5u64);
+        let mut spent = utxo(5_000, 0, &private_key, &vortex);
+        spent.metadata.spent = true;
+        let notes = vec![
+            spent,
+            utxo(300, 1, &private_key, &vortex),
+            utxo(900, 2, &private_key, &vortex),
+            utxo(1_200, 3, &private_key, &vortex),
+        ];
+        let intent = TransactionIntent {
+            notes,
+            send: 600,
+            fee: 100,
+            output_blindings: vec!["7".to_string(), "8".to_string()],
+            ..Default::default()
+        };
+
+        let report = dry_run(&intent, &private_key, &vortex).unwrap();
+        assert_eq!(report.selected, vec![intent.notes[2].clone()]);
+        assert_eq!(report.input_total, 900);
+        assert_eq!(report.amounts.change_amount, 200);
+        assert_eq!(
+            report.nullifiers,
+            vec![intent.notes[2]
+                .nullifier(&private_key)
+                .unwrap()
+                .into_bigint()
+                .to_string()]
+        );
+        let change_commitment = compute_commitment(
+            &Fr::from(200u64),
+            &hash1(&private_key),
+            &Fr::from(8u64),
+            &vortex,
+        );
+        assert_eq!(
+            report.output_commitments[1],
+            change_commitment.into_bigint().to_string()
+        );
+
+        let two_notes = TransactionIntent {
+            send: 2_000,
+            ..intent.clone()
+        };
+        let report = dry_run(&two_notes, &private_key, &vortex).unwrap();
+        assert_eq!(report.input_total, 2_100);
+
+        let too_much = TransactionIntent {
+            send: 2_200,
+            ..intent
+        };
+        assert!(dry_run(&too_much, &private_key, &vortex).is_err());
+    }
+}
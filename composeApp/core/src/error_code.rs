@@ -0,0 +1,121 @@
+//! Stable error codes shared by the uniffi and wasm bindings.
+//!
+//! Every `BindingError` variant and every error thrown by the wasm exports carries one of
+//! these codes, so the apps can map failures to UX by category and support can triage a
+//! report from the code alone. Numeric values are part of the public API: they are never
+//! reused or renumbered, only added.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What the app should do about an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCategory {
+    /// The request is malformed or inconsistent; retrying it unchanged fails again
+    Input,
+    /// Key material is missing, corrupt or of the wrong circuit; re-fetch the keys
+    Key,
+    /// Proof generation failed on a valid request; a retry may succeed
+    Proving,
+    /// A proof was rejected
+    Verification,
+    /// A bug in the library; report it
+    Internal,
+}
+
+/// Error codes, one per `BindingError` variant of the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum VortexErrorCode {
+    /// A field element, hex string or JSON document does not parse
+    Parse,
+    /// Parsed values that are out of range or do not belong together
+    Input,
+    /// The witness does not satisfy the circuit
+    Constraint,
+    /// A proving or verifying key does not deserialize or does not match
+    Key,
+    /// The prover failed
+    Proof,
+    /// Verification failed or could not run
+    Verify,
+    /// A result could not be serialized
+    Serialization,
+    /// Anything else, including prover panics
+    Internal,
+}
+
+impl VortexErrorCode {
+    pub const ALL: [VortexErrorCode; 8] = [
+        VortexErrorCode::Parse,
+        VortexErrorCode::Input,
+        VortexErrorCode::Constraint,
+        VortexErrorCode::Key,
+        VortexErrorCode::Proof,
+        VortexErrorCode::Verify,
+        VortexErrorCode::Serialization,
+        VortexErrorCode::Internal,
+    ];
+
+    /// The stable numeric code; the thousands digit follows the category
+    pub fn code(self) -> u32 {
+        match self {
+            VortexErrorCode::Parse => 1001,
+            VortexErrorCode::Input => 1002,
+            VortexErrorCode::Constraint => 1003,
+            VortexErrorCode::Key => 2001,
+            VortexErrorCode::Proof => 3001,
+            VortexErrorCode::Verify => 4001,
+            VortexErrorCode::Internal => 9001,
+            VortexErrorCode::Serialization => 9002,
+        }
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            VortexErrorCode::Parse | VortexErrorCode::Input | VortexErrorCode::Constraint => {
+                ErrorCategory::Input
+            }
+            VortexErrorCode::Key => ErrorCategory::Key,
+            VortexErrorCode::Proof => ErrorCategory::Proving,
+            VortexErrorCode::Verify => ErrorCategory::Verification,
+            VortexErrorCode::Serialization | VortexErrorCode::Internal => ErrorCategory::Internal,
+        }
+    }
+}
+
+/// `E1001`, the form used in logs and support tickets
+impl fmt::Display for VortexErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        for code in VortexErrorCode::ALL {
+            assert_eq!(VortexErrorCode::from_code(code.code()), Some(code));
+            let category_digit = match code.category() {
+                ErrorCategory::Input => 1,
+                ErrorCategory::Key => 2,
+                ErrorCategory::Proving => 3,
+                ErrorCategory::Verification => 4,
+                ErrorCategory::Internal => 9,
+            };
+            assert_eq!(code.code() / 1000, category_digit, "{:?}", code);
+        }
+        assert_eq!(VortexErrorCode::from_code(1000), None);
+        assert_eq!(VortexErrorCode::Key.to_string(), "E2001");
+    }
+}
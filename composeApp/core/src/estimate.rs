@@ -0,0 +1,224 @@
+//! Proving-time estimates calibrated per device.
+//!
+//! Groth16 proving time is dominated by multi-scalar multiplications whose size grows
+//! linearly with the circuit, so a device is summarized by one number: nanoseconds per
+//! constraint. `calibrate` measures it with a short G1 MSM micro-benchmark; once the device
+//! has proven for real, `calibration_from_stats` derives it from that proof's `ProofStats`
+//! instead, which is more accurate. The host stores the calibration and passes it back to
+//! `estimate_proving_time`, which scales it by each circuit variant's constraint count.
+
+use crate::{
+    circuit::TransactionCircuit,
+    constants::{N_INS, N_OUTS},
+    merkle_tree::Path,
+    telemetry::ProofStats,
+};
+use anyhow::{anyhow, Result};
+use ark_bn254::{Fr, G1Affine, G1Projective};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::AdditiveGroup;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+use ark_std::UniformRand;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use web_time::Instant;
+
+/// Points in the calibration MSM
+const CALIBRATION_POINTS: usize = 256;
+/// Calibration rounds; the fastest one is kept to filter out scheduler noise
+const CALIBRATION_ROUNDS: usize = 3;
+/// G1-equivalent MSM points per constraint in a Groth16 proof: the A, B (G1), H and L
+/// queries take about one each and the B query in G2 about three
+const MSM_POINTS_PER_CONSTRAINT: u64 = 7;
+
+/// Circuit shapes a wallet may prove
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitVariant {
+    /// The default 2x2 transaction circuit
+    Transaction,
+    /// Without the `hashed_account_secret` input (`_noaccount` keys)
+    NoAccountSecret,
+    /// With output ciphertext binding (`_ciphertext` keys)
+    CiphertextBinding,
+    /// With the `authority_root` input and per-input authority paths (`_rotation` keys)
+    KeyRotation,
+    /// With per-input legacy commitment selectors (`_mixed_era` keys)
+    MixedEraInputs,
+}
+
+impl CircuitVariant {
+    pub const ALL: [CircuitVariant; 5] = [
+        CircuitVariant::Transaction,
+        CircuitVariant::NoAccountSecret,
+        CircuitVariant::CiphertextBinding,
+        CircuitVariant::KeyRotation,
+        CircuitVariant::MixedEraInputs,
+    ];
+
+    /// The empty circuit of this shape, as used for key generation
+    pub fn empty_circuit(self) -> TransactionCircuit {
+        let circuit = TransactionCircuit::empty();
+        match self {
+            CircuitVariant::Transaction => circuit,
+            CircuitVariant::NoAccountSecret => circuit.without_account_secret(),
+            CircuitVariant::CiphertextBinding => {
+                circuit.with_ciphertext_binding([Fr::ZERO; N_OUTS])
+            }
+            CircuitVariant::KeyRotation => circuit.with_key_rotation(
+                Fr::ZERO,
+                [None; N_INS],
+                [Fr::ZERO; N_INS],
+                [Path::empty(); N_INS],
+            ),
+            CircuitVariant::MixedEraInputs => circuit.with_mixed_era_inputs(),
+        }
+    }
+
+    /// R1CS constraint count of this shape, synthesized once per process
+    pub fn num_constraints(self) -> Result<u64> {
+        static COUNTS: [OnceLock<u64>; 5] = [const { OnceLock::new() }; 5];
+
+        let slot = &COUNTS[self as usize];
+        if let Some(count) = slot.get() {
+            return Ok(*count);
+        }
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        self.empty_circuit()
+            .generate_constraints(cs.clone())
+            .map_err(|e| anyhow!("Failed to synthesize {:?} circuit: {}", self, e))?;
+        Ok(*slot.get_or_init(|| cs.num_constraints() as u64))
+    }
+}
+
+/// How fast this device proves, to be stored by the host between sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCalibration {
+    /// Proving nanoseconds per R1CS constraint
+    pub ns_per_constraint: u64,
+    /// Derived from a real proof rather than the micro-benchmark
+    pub from_proof: bool,
+}
+
+/// Expected proving time of one circuit variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ProvingTimeEstimate {
+    pub variant: CircuitVariant,
+    pub num_constraints: u64,
+    pub estimated_ms: u64,
+}
+
+/// Measures this device with a short G1 MSM micro-benchmark (tens of milliseconds)
+pub fn calibrate() -> DeviceCalibration {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut point = G1Projective::from(G1Affine::generator());
+    let mut bases = Vec::with_capacity(CALIBRATION_POINTS);
+    for _ in 0..CALIBRATION_POINTS {
+        bases.push(point);
+        point += G1Affine::generator();
+    }
+    let bases = G1Projective::normalize_batch(&bases);
+    let scalars: Vec<Fr> = (0..CALIBRATION_POINTS)
+        .map(|_| Fr::rand(&mut rng))
+        .collect();
+
+    let fastest_ns = (0..CALIBRATION_ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            let _ = G1Projective::msm(&bases, &scalars);
+            start.elapsed().as_nanos()
+        })
+        .min()
+        .unwrap_or_default();
+    let ns_per_point = (fastest_ns / CALIBRATION_POINTS as u128).max(1) as u64;
+
+    DeviceCalibration {
+        ns_per_constraint: ns_per_point * MSM_POINTS_PER_CONSTRAINT,
+        from_proof: false,
+    }
+}
+
+/// Calibration measured from a real proof on this device.
+///
+/// # Errors
+/// Returns error if the stats carry no constraint count.
+pub fn calibration_from_stats(stats: &ProofStats) -> Result<DeviceCalibration> {
+    if stats.num_constraints == 0 {
+        return Err(anyhow!("Proof stats carry no constraint count"));
+    }
+    let proving_us = stats.witness_us + stats.constraints_us + stats.prove_us;
+    Ok(DeviceCalibration {
+        ns_per_constraint: (proving_us.saturating_mul(1_000) / stats.num_constraints).max(1),
+        from_proof: true,
+    })
+}
+
+/// Expected proving time of every circuit variant, calibrating first if no stored
+/// calibration is given.
+///
+/// # Errors
+/// Returns error if a variant fails to synthesize.
+pub fn estimate_proving_time(
+    calibration: Option<&DeviceCalibration>,
+) -> Result<Vec<ProvingTimeEstimate>> {
+    let calibration = calibration.copied().unwrap_or_else(calibrate);
+    CircuitVariant::ALL
+        .iter()
+        .map(|&variant| {
+            let num_constraints = variant.num_constraints()?;
+            Ok(ProvingTimeEstimate {
+                variant,
+                num_constraints,
+                estimated_ms: num_constraints.saturating_mul(calibration.ns_per_constraint)
+                    / 1_000_000,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_scale_with_calibration_and_circuit_size() {
+        let stats = ProofStats {
+            witness_us: 200_000,
+            constraints_us: 300_000,
+            prove_us: 1_500_000,
+            num_constraints: 40_000,
+            ..Default::default()
+        };
+        let calibration = calibration_from_stats(&stats).unwrap();
+        assert_eq!(calibration.ns_per_constraint, 50_000);
+        assert!(calibration_from_stats(&ProofStats::default()).is_err());
+
+        let estimates = estimate_proving_time(Some(&calibration)).unwrap();
+        let estimate = |variant| {
+            estimates
+                .iter()
+                .find(|estimate| estimate.variant == variant)
+                .unwrap()
+        };
+        let default = estimate(CircuitVariant::Transaction);
+        assert_eq!(
+            default.estimated_ms,
+            default.num_constraints * 50_000 / 1_000_000
+        );
+        assert!(
+            estimate(CircuitVariant::NoAccountSecret).num_constraints < default.num_constraints
+        );
+        assert!(estimate(CircuitVariant::KeyRotation).num_constraints > default.num_constraints);
+        assert!(estimate(CircuitVariant::MixedEraInputs).num_constraints > default.num_constraints);
+
+        assert!(calibrate().ns_per_constraint > 0);
+    }
+}
@@ -0,0 +1,212 @@
+//! Parsing of BN254 scalar field elements from host-provided strings, and their canonical
+//! 32-byte encodings.
+//!
+//! Byte encodings go through `fr_to_bytes_le`/`fr_from_bytes_le` (or the big-endian pair)
+//! everywhere: the little-endian form is also what arkworks' compressed serialization
+//! writes. Decoding rejects values `>=` the modulus instead of reducing them.
+
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::BigInt;
+use ark_ff::PrimeField;
+use core::str::FromStr;
+use num_bigint::BigUint;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+/// How to treat integers that are not canonical field elements (>= the BN254 modulus)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldParseMode {
+    /// Reject out-of-range values (default)
+    #[default]
+    Strict,
+    /// Reduce out-of-range values modulo the field order
+    Reduce,
+}
+
+impl FieldParseMode {
+    pub fn from_reduce(reduce: bool) -> Self {
+        if reduce {
+            FieldParseMode::Reduce
+        } else {
+            FieldParseMode::Strict
+        }
+    }
+}
+
+/// Parses a decimal or `0x`-prefixed hex string into a field element.
+///
+/// # Errors
+/// Returns error if the string is not a valid integer, or if it is `>=` the BN254 modulus
+/// and `mode` is `FieldParseMode::Strict`.
+pub fn parse_fr(s: &str, mode: FieldParseMode) -> Result<Fr> {
+    let s = s.trim();
+
+    let value = if let Some(hex_str) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        BigUint::parse_bytes(hex_str.as_bytes(), 16)
+            .ok_or_else(|| anyhow!("Failed to parse hex '{}': invalid hex string", s))?
+    } else {
+        BigUint::from_str(s).map_err(|e| anyhow!("Failed to parse decimal '{}': {}", s, e))?
+    };
+
+    if mode == FieldParseMode::Strict && value >= Fr::MODULUS.into() {
+        return Err(anyhow!(
+            "Value '{}' is not a canonical field element (>= BN254 modulus)",
+            s
+        ));
+    }
+
+    Ok(Fr::from(value))
+}
+
+/// Constant-time equality of two field elements.
+///
+/// Use this instead of `==` whenever either side is derived from secret material (leaves,
+/// sibling hashes of a spent note, keys), so the comparison time does not depend on where
+/// the values first differ.
+pub fn ct_eq(a: &Fr, b: &Fr) -> Choice {
+    // Both sides are reduced Montgomery representations, so limb equality is value equality
+    a.0 .0.ct_eq(&b.0 .0)
+}
+
+/// Constant-time selection: returns `a` if `choice` is 0 and `b` if it is 1
+pub fn ct_select(a: &Fr, b: &Fr, choice: Choice) -> Fr {
+    let mut limbs = [0u64; 4];
+    for (limb, (x, y)) in limbs.iter_mut().zip(a.0 .0.iter().zip(b.0 .0.iter())) {
+        *limb = u64::conditional_select(x, y, choice);
+    }
+    Fr::new_unchecked(BigInt(limbs))
+}
+
+/// Length of a field element's byte encoding
+pub const FR_BYTES: usize = 32;
+
+/// Canonical little-endian bytes of `value`
+pub fn fr_to_bytes_le(value: &Fr) -> [u8; FR_BYTES] {
+    let mut bytes = [0u8; FR_BYTES];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(value.into_bigint().0) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+/// Canonical big-endian bytes of `value`
+pub fn fr_to_bytes_be(value: &Fr) -> [u8; FR_BYTES] {
+    let mut bytes = fr_to_bytes_le(value);
+    bytes.reverse();
+    bytes
+}
+
+/// Parses the little-endian bytes written by `fr_to_bytes_le`.
+///
+/// # Errors
+/// Returns error if `bytes` is not 32 bytes long or encodes a value `>=` the BN254 modulus.
+pub fn fr_from_bytes_le(bytes: &[u8]) -> Result<Fr> {
+    if bytes.len() != FR_BYTES {
+        return Err(anyhow!(
+            "A field element is {} bytes, got {}",
+            FR_BYTES,
+            bytes.len()
+        ));
+    }
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Fr::from_bigint(BigInt(limbs))
+        .ok_or_else(|| anyhow!("Bytes are not a canonical field element (>= BN254 modulus)"))
+}
+
+/// Parses the big-endian bytes written by `fr_to_bytes_be`.
+///
+/// # Errors
+/// Returns error if `bytes` is not 32 bytes long or encodes a value `>=` the BN254 modulus.
+pub fn fr_from_bytes_be(bytes: &[u8]) -> Result<Fr> {
+    let mut le = [0u8; FR_BYTES];
+    if bytes.len() == FR_BYTES {
+        le.copy_from_slice(bytes);
+        le.reverse();
+        fr_from_bytes_le(&le)
+    } else {
+        fr_from_bytes_le(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULUS: &str =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+    #[test]
+    fn test_strict_rejects_modulus_and_above() {
+        assert!(parse_fr(MODULUS, FieldParseMode::Strict).is_err());
+
+        let max = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        assert_eq!(
+            parse_fr(max, FieldParseMode::Strict).unwrap(),
+            -Fr::from(1u64)
+        );
+    }
+
+    #[test]
+    fn test_reduce_keeps_old_behavior() {
+        let above = "21888242871839275222246405745257275088548364400416034343698204186575808495622";
+        assert_eq!(
+            parse_fr(MODULUS, FieldParseMode::Reduce).unwrap(),
+            Fr::from(0u64)
+        );
+        assert_eq!(
+            parse_fr(above, FieldParseMode::Reduce).unwrap(),
+            Fr::from(5u64)
+        );
+    }
+
+    #[test]
+    fn test_constant_time_helpers() {
+        let a = Fr::from(7u64);
+        let b = -Fr::from(7u64);
+
+        assert!(bool::from(ct_eq(&a, &Fr::from(7u64))));
+        assert!(!bool::from(ct_eq(&a, &b)));
+        assert_eq!(ct_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(ct_select(&a, &b, Choice::from(1)), b);
+    }
+
+    #[test]
+    fn test_byte_encodings_are_canonical() {
+        use ark_serialize::CanonicalSerialize;
+
+        let value = -Fr::from(2u64);
+        let le = fr_to_bytes_le(&value);
+        let mut compressed = Vec::new();
+        value.serialize_compressed(&mut compressed).unwrap();
+        assert_eq!(le.as_slice(), compressed.as_slice());
+        assert_eq!(fr_from_bytes_le(&le).unwrap(), value);
+
+        let be = fr_to_bytes_be(&Fr::from(258u64));
+        assert_eq!(be[30..], [1, 2]);
+        assert_eq!(fr_from_bytes_be(&be).unwrap(), Fr::from(258u64));
+
+        // p - 1 ends in a zero byte, so this is the modulus itself
+        let mut above = fr_to_bytes_le(&(-Fr::from(1u64)));
+        above[0] += 1;
+        assert!(fr_from_bytes_le(&above).is_err());
+        assert!(fr_from_bytes_le(&le[..31]).is_err());
+        assert!(fr_from_bytes_be(&[0u8; 33]).is_err());
+    }
+
+    #[test]
+    fn test_hex_and_decimal() {
+        assert_eq!(
+            parse_fr("0x1f", FieldParseMode::Strict).unwrap(),
+            Fr::from(31u64)
+        );
+        assert_eq!(
+            parse_fr(" 31 ", FieldParseMode::Strict).unwrap(),
+            Fr::from(31u64)
+        );
+        assert!(parse_fr("0xzz", FieldParseMode::Strict).is_err());
+        assert!(parse_fr("-1", FieldParseMode::Strict).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Invalid transaction fixtures for testing the Move verifier's rejection paths.
+//!
+//! Each fixture is a real proof output with one targeted defect, so the contract is tested
+//! against the bytes it will actually see rather than hand-written garbage. Public input
+//! positions come from `TransactionCircuit::public_input_names`, so fixtures follow the
+//! layout of whichever circuit variant produced the proof. `public_inputs_serialized_hex`
+//! and `proof_serialized_hex` are kept consistent with the mutated fields.
+
+use crate::constants::MAX_AMOUNT_BITS;
+use crate::field::{parse_fr, FieldParseMode};
+use crate::interop::ProofOutput;
+use crate::poseidon_opt::hash1;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{Field, PrimeField};
+use ark_serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NegativeCase {
+    WrongRoot,
+    DuplicateNullifier,
+    OverflowAmount,
+    TamperedProofByte,
+    ReorderedPublicInputs,
+}
+
+impl NegativeCase {
+    pub const ALL: [NegativeCase; 5] = [
+        NegativeCase::WrongRoot,
+        NegativeCase::DuplicateNullifier,
+        NegativeCase::OverflowAmount,
+        NegativeCase::TamperedProofByte,
+        NegativeCase::ReorderedPublicInputs,
+    ];
+
+    /// The check the verifier is expected to fail on
+    pub fn expected_failure(self) -> &'static str {
+        match self {
+            NegativeCase::WrongRoot => "root is not in the pool's root history",
+            NegativeCase::DuplicateNullifier => "both inputs spend the same nullifier",
+            NegativeCase::OverflowAmount => {
+                "public amount does not fit in MAX_AMOUNT_BITS in either direction"
+            }
+            NegativeCase::TamperedProofByte => "proof does not deserialize or does not verify",
+            NegativeCase::ReorderedPublicInputs => {
+                "Groth16 verification fails for public inputs in the wrong order"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegativeFixture {
+    pub case: NegativeCase,
+    pub expected_failure: String,
+    pub proof: ProofOutput,
+}
+
+/// Derives one fixture per `NegativeCase` from a valid proof.
+///
+/// `layout` names the public inputs of `valid` in order, as returned by
+/// `TransactionCircuit::public_input_names` for the variant that produced it.
+///
+/// # Errors
+/// Returns error if the proof does not match the layout or a public input does not parse.
+pub fn negative_fixtures(valid: &ProofOutput, layout: &[&str]) -> Result<Vec<NegativeFixture>> {
+    if valid.public_inputs.len() != layout.len() {
+        return Err(anyhow!(
+            "Proof has {} public inputs, layout has {}",
+            valid.public_inputs.len(),
+            layout.len()
+        ));
+    }
+    let position = |name: &str| {
+        layout
+            .iter()
+            .position(|n| *n == name)
+            .ok_or_else(|| anyhow!("Layout has no '{}' input", name))
+    };
+    let inputs = valid
+        .public_inputs
+        .iter()
+        .map(|s| parse_fr(s, FieldParseMode::Strict))
+        .collect::<Result<Vec<Fr>>>()
+        .context("Invalid public input in proof")?;
+
+    NegativeCase::ALL
+        .iter()
+        .map(|&case| {
+            let mut inputs = inputs.clone();
+            let mut proof = valid.clone();
+            match case {
+                NegativeCase::WrongRoot => {
+                    let root = position("root")?;
+                    inputs[root] = hash1(&inputs[root]);
+                }
+                NegativeCase::DuplicateNullifier => {
+                    inputs[position("inputNullifier1")?] = inputs[position("inputNullifier0")?];
+                }
+                NegativeCase::OverflowAmount => {
+                    // 2^MAX_AMOUNT_BITS: one past the largest deposit, and far from any
+                    // withdrawal (which wraps around near the modulus)
+                    inputs[position("publicAmount")?] =
+                        Fr::from(2u64).pow([MAX_AMOUNT_BITS as u64]);
+                }
+                NegativeCase::TamperedProofByte => {
+                    // The low byte of A's x coordinate; its curve point usually stops existing
+                    proof.proof_a[0] ^= 1;
+                    let mut serialized = hex::decode(&proof.proof_serialized_hex)?;
+                    serialized[0] ^= 1;
+                    proof.proof_serialized_hex = hex::encode(serialized);
+                }
+                NegativeCase::ReorderedPublicInputs => {
+                    let (a, b) = (
+                        position("outputCommitment0")?,
+                        position("outputCommitment1")?,
+                    );
+                    if inputs[a] != inputs[b] {
+                        inputs.swap(a, b);
+                    } else {
+                        // Equal commitments would make that swap a no-op
+                        let i = (1..inputs.len())
+                            .find(|&i| inputs[i] != inputs[0])
+                            .ok_or_else(|| anyhow!("All public inputs are equal"))?;
+                        inputs.swap(0, i);
+                    }
+                }
+            }
+            set_public_inputs(&mut proof, &inputs)?;
+            Ok(NegativeFixture {
+                case,
+                expected_failure: case.expected_failure().to_string(),
+                proof,
+            })
+        })
+        .collect()
+}
+
+fn set_public_inputs(proof: &mut ProofOutput, inputs: &[Fr]) -> Result<()> {
+    let mut serialized = Vec::new();
+    for input in inputs {
+        input.serialize_compressed(&mut serialized)?;
+    }
+    proof.public_inputs = inputs.iter().map(|x| x.into_bigint().to_string()).collect();
+    proof.public_inputs_serialized_hex = hex::encode(serialized);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::TransactionCircuit;
+
+    #[test]
+    fn test_each_fixture_changes_only_its_target() {
+        let layout = TransactionCircuit::empty().public_input_names();
+        let public_inputs: Vec<Fr> = (1..=layout.len() as u64).map(Fr::from).collect();
+        let mut valid = ProofOutput {
+            proof_a: vec![1; 32],
+            proof_b: vec![2; 64],
+            proof_c: vec![3; 32],
+            public_inputs: Vec::new(),
+            proof_serialized_hex: hex::encode([[1u8; 32].as_slice(), &[2; 64], &[3; 32]].concat()),
+            public_inputs_serialized_hex: String::new(),
+            session_nonce: None,
+        };
+        set_public_inputs(&mut valid, &public_inputs).unwrap();
+
+        let fixtures = negative_fixtures(&valid, &layout).unwrap();
+        assert_eq!(fixtures.len(), NegativeCase::ALL.len());
+        for fixture in &fixtures {
+            let changed: Vec<&str> = layout
+                .iter()
+                .zip(fixture.proof.public_inputs.iter().zip(&valid.public_inputs))
+                .filter(|(_, (a, b))| a != b)
+                .map(|(name, _)| *name)
+                .collect();
+            let expected: &[&str] = match fixture.case {
+                NegativeCase::WrongRoot => &["root"],
+                NegativeCase::DuplicateNullifier => &["inputNullifier1"],
+                NegativeCase::OverflowAmount => &["publicAmount"],
+                NegativeCase::TamperedProofByte => &[],
+                NegativeCase::ReorderedPublicInputs => &["outputCommitment0", "outputCommitment1"],
+            };
+            assert_eq!(changed, expected, "{:?}", fixture.case);
+
+            let mut reserialized = fixture.proof.clone();
+            set_public_inputs(&mut reserialized, &proof_inputs(&fixture.proof)).unwrap();
+            assert_eq!(
+                reserialized.public_inputs_serialized_hex,
+                fixture.proof.public_inputs_serialized_hex
+            );
+            assert_eq!(
+                fixture.case == NegativeCase::TamperedProofByte,
+                fixture.proof.proof_serialized_hex != valid.proof_serialized_hex
+            );
+        }
+        assert!(negative_fixtures(&valid, &layout[1..]).is_err());
+    }
+
+    fn proof_inputs(proof: &ProofOutput) -> Vec<Fr> {
+        proof
+            .public_inputs
+            .iter()
+            .map(|s| parse_fr(s, FieldParseMode::Strict).unwrap())
+            .collect()
+    }
+}
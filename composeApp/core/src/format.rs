@@ -0,0 +1,194 @@
+//! Magic bytes and versions framing the binary artifacts this crate persists.
+//!
+//! Tree snapshots, backups, proving keys and binary proofs start with a 4-byte magic naming
+//! the artifact and a little-endian `u16` format version. Readers reject bytes of another
+//! kind or written by a newer release instead of misparsing them, and bring bodies of an
+//! older version up to date through the artifact's migration hooks before parsing.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use anyhow::{anyhow, Result};
+
+/// Magic and version bytes in front of every framed artifact
+pub const HEADER_LEN: usize = 6;
+
+/// Upgrades a body by one version
+pub type Migration = fn(&[u8]) -> Result<Vec<u8>>;
+
+/// A framed binary format
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryFormat {
+    /// Human-readable artifact name for error messages
+    pub name: &'static str,
+    pub magic: [u8; 4],
+    /// Version written by this release
+    pub version: u16,
+    /// `(from, hook)` pairs, each upgrading a version `from` body to `from + 1`
+    pub migrations: &'static [(u16, Migration)],
+}
+
+/// `SparseMerkleTree::to_snapshot`
+pub const TREE_SNAPSHOT: BinaryFormat = BinaryFormat {
+    name: "tree snapshot",
+    magic: *b"VXTS",
+    version: 1,
+    migrations: &[],
+};
+
+/// `backup::export_backup`. Version 1 is the unframed layout that started with a single
+/// `1` byte; backups authenticate their header, so they are read per version rather than
+/// migrated.
+pub const BACKUP: BinaryFormat = BinaryFormat {
+    name: "backup",
+    magic: *b"VXBK",
+    version: 2,
+    migrations: &[],
+};
+
+/// Compressed Groth16 proving key; bare keys from earlier `keygen` runs are still accepted
+pub const PROVING_KEY: BinaryFormat = BinaryFormat {
+    name: "proving key",
+    magic: *b"VXPK",
+    version: 1,
+    migrations: &[],
+};
+
+/// A `PROVING_KEY` body compressed into one zstd frame, read by `validation::read_proving_key`
+/// without a decompressed copy
+pub const PROVING_KEY_ZSTD: BinaryFormat = BinaryFormat {
+    name: "zstd proving key",
+    magic: *b"VXPZ",
+    version: 1,
+    migrations: &[],
+};
+
+/// One file of a proving key split by `key_shards::shard_proving_key`
+pub const PROVING_KEY_SHARD: BinaryFormat = BinaryFormat {
+    name: "proving key shard",
+    magic: *b"VXKS",
+    version: 1,
+    migrations: &[],
+};
+
+/// `ProofOutput::to_bytes`
+pub const PROOF_OUTPUT: BinaryFormat = BinaryFormat {
+    name: "proof",
+    magic: *b"VXPF",
+    version: 1,
+    migrations: &[],
+};
+
+impl BinaryFormat {
+    pub fn header(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&self.magic);
+        header[4..].copy_from_slice(&self.version.to_le_bytes());
+        header
+    }
+
+    /// `body` framed with the current version
+    pub fn encode(&self, body: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(&self.header());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// Whether `bytes` start with this format's magic
+    pub fn is_framed(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(&self.magic)
+    }
+
+    /// Splits framed bytes into their version and body, without migrating.
+    ///
+    /// # Errors
+    /// Returns error if the magic does not match, or the version is zero or newer than this
+    /// release writes.
+    pub fn read_header<'a>(&self, bytes: &'a [u8]) -> Result<(u16, &'a [u8])> {
+        if bytes.len() < HEADER_LEN || !self.is_framed(bytes) {
+            return Err(anyhow!("Not a Vortex {}", self.name));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version == 0 || version > self.version {
+            return Err(anyhow!(
+                "Unsupported {} version {} (this release reads up to {})",
+                self.name,
+                version,
+                self.version
+            ));
+        }
+        Ok((version, &bytes[HEADER_LEN..]))
+    }
+
+    /// Body of framed bytes, migrated to the current version
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let (version, body) = self.read_header(bytes)?;
+        self.migrate(version, body)
+    }
+
+    /// Runs the migration hooks from `version` up to the current one
+    pub fn migrate<'a>(&self, mut version: u16, body: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let mut body = Cow::Borrowed(body);
+        while version < self.version {
+            let (_, hook) = self
+                .migrations
+                .iter()
+                .find(|(from, _)| *from == version)
+                .ok_or_else(|| anyhow!("No migration for {} version {}", self.name, version))?;
+            body = Cow::Owned(hook(&body)?);
+            version += 1;
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn append_one(body: &[u8]) -> Result<Vec<u8>> {
+        let mut body = body.to_vec();
+        body.push(1);
+        Ok(body)
+    }
+
+    const MIGRATED: BinaryFormat = BinaryFormat {
+        name: "test artifact",
+        magic: *b"TEST",
+        version: 3,
+        migrations: &[(1, append_one), (2, append_one)],
+    };
+
+    #[test]
+    fn test_round_trip_and_rejections() {
+        let bytes = TREE_SNAPSHOT.encode(b"body");
+        assert_eq!(&bytes[..6], b"VXTS\x01\x00");
+        assert_eq!(TREE_SNAPSHOT.decode(&bytes).unwrap().as_ref(), b"body");
+
+        assert!(BACKUP.decode(&bytes).is_err());
+        assert!(TREE_SNAPSHOT.decode(b"VXT").is_err());
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        let err = TREE_SNAPSHOT.decode(&newer).unwrap_err();
+        assert!(err.to_string().contains("version 2"));
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        let mut v1 = MIGRATED.encode(&[7]);
+        v1[4] = 1;
+        assert_eq!(MIGRATED.decode(&v1).unwrap().as_ref(), [7, 1, 1]);
+
+        let current = MIGRATED.encode(&[7]);
+        assert!(matches!(
+            MIGRATED.decode(&current).unwrap(),
+            Cow::Borrowed(_)
+        ));
+
+        let missing = BinaryFormat {
+            migrations: &[(2, append_one)],
+            ..MIGRATED
+        };
+        assert!(missing.decode(&v1).is_err());
+    }
+}
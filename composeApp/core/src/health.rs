@@ -0,0 +1,292 @@
+//! Startup health checks.
+//!
+//! Each check looks at one thing a proof depends on and reports instead of failing, so the
+//! app gets a full picture of a broken install in one call and can tell the user before they
+//! try to pay:
+//! - the Poseidon constants, against circomlibjs outputs and the contract's empty subtree
+//!   hashes, which catches a corrupted binary or a wrong constants table
+//! - a proving key's manifest digest and internal consistency, which catches truncated or
+//!   mixed-up key files
+//! - that a storage directory can be written and read back
+//! - the memory the OS reports available, against what a proof needs
+//!
+//! Unlike `selftest`, nothing here proves, so running every check takes milliseconds.
+
+use crate::constants::{EMPTY_SUBTREE_HASHES, ZERO_VALUE};
+use crate::poseidon_opt::{fr_from_str, hash1, hash2, hash3, hash4};
+use crate::selftest::{self, KeyManifest};
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::ProvingKey;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// circomlibjs `poseidon([1])` to `poseidon([1, 2, 3, 4])`, one per hasher width
+const POSEIDON_VECTORS: [&str; 4] = [
+    "18586133768512220936620570745912940619677854269274689475585506675881198879027",
+    "7853200120776062878684798364095072458815029376092732009249414926327459813530",
+    "6542985608222806190361240322586112750744169038454362455181422643027100751666",
+    "18821383157269793795438455681495246036402687001665670618754263018637548127333",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Ok,
+    /// Proving may still work, e.g. memory is tight or could not be measured
+    Warning,
+    /// Proving will fail or produce proofs the pool rejects
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum HealthComponent {
+    Constants,
+    ProvingKey,
+    Storage,
+    Memory,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub component: HealthComponent,
+    /// Circuit id for key checks, the directory for storage checks
+    pub subject: Option<String>,
+    pub status: HealthStatus,
+    /// What is wrong; empty when the check passed
+    pub detail: String,
+}
+
+impl HealthCheck {
+    fn new(
+        component: HealthComponent,
+        subject: Option<String>,
+        result: Result<(), String>,
+    ) -> Self {
+        let (status, detail) = match result {
+            Ok(()) => (HealthStatus::Ok, String::new()),
+            Err(detail) => (HealthStatus::Failed, detail),
+        };
+        Self {
+            component,
+            subject,
+            status,
+            detail,
+        }
+    }
+}
+
+/// Every check run, and whether none of them failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub checks: Vec<HealthCheck>,
+    /// Memory the OS reports available, where it can be read
+    pub available_memory_bytes: Option<u64>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>, available_memory_bytes: Option<u64>) -> Self {
+        Self {
+            healthy: checks
+                .iter()
+                .all(|check| check.status != HealthStatus::Failed),
+            checks,
+            available_memory_bytes,
+        }
+    }
+}
+
+/// Hashes the circomlibjs vectors with every width and rebuilds the empty subtree hashes
+pub fn check_constants() -> HealthCheck {
+    HealthCheck::new(HealthComponent::Constants, None, constants_match())
+}
+
+fn constants_match() -> Result<(), String> {
+    let inputs: Vec<Fr> = (1..=4u64).map(Fr::from).collect();
+    let outputs = [
+        hash1(&inputs[0]),
+        hash2(&inputs[0], &inputs[1]),
+        hash3(&inputs[0], &inputs[1], &inputs[2]),
+        hash4(&inputs[0], &inputs[1], &inputs[2], &inputs[3]),
+    ];
+    for (width, (output, expected)) in outputs.iter().zip(POSEIDON_VECTORS).enumerate() {
+        if *output != fr_from_str(expected) {
+            return Err(format!(
+                "Poseidon with {} inputs does not match circomlibjs",
+                width + 1
+            ));
+        }
+    }
+    if EMPTY_SUBTREE_HASHES.first() != Some(&ZERO_VALUE) {
+        return Err("Empty subtree hashes do not start at the zero leaf".to_string());
+    }
+    for (level, pair) in EMPTY_SUBTREE_HASHES.windows(2).enumerate() {
+        let child = fr_from_str(pair[0]);
+        if hash2(&child, &child) != fr_from_str(pair[1]) {
+            return Err(format!(
+                "Empty subtree hash of level {} is wrong",
+                level + 1
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks a resident proving key: its digest against `manifest` when given, and that its
+/// queries have the lengths one circuit would give them
+pub fn check_proving_key(
+    circuit_id: &str,
+    pk: &ProvingKey<Bn254>,
+    digest: &[u8; 32],
+    manifest: Option<&KeyManifest>,
+) -> HealthCheck {
+    HealthCheck::new(
+        HealthComponent::ProvingKey,
+        Some(circuit_id.to_string()),
+        proving_key_consistent(pk, digest, manifest),
+    )
+}
+
+fn proving_key_consistent(
+    pk: &ProvingKey<Bn254>,
+    digest: &[u8; 32],
+    manifest: Option<&KeyManifest>,
+) -> Result<(), String> {
+    if let Some(manifest) = manifest {
+        selftest::check_manifest(digest, manifest).map_err(|e| e.to_string())?;
+    }
+    let variables = pk.a_query.len();
+    if pk.b_g1_query.len() != variables || pk.b_g2_query.len() != variables {
+        return Err("A and B queries differ in length".to_string());
+    }
+    if pk.vk.gamma_abc_g1.len() + pk.l_query.len() != variables {
+        return Err("Verifying key inputs and L query do not add up to the variables".to_string());
+    }
+    if !(pk.h_query.len() + 1).is_power_of_two() {
+        return Err(format!(
+            "H query has {} points, not one less than a power of two",
+            pk.h_query.len()
+        ));
+    }
+    Ok(())
+}
+
+/// A manifest naming a key that is not resident
+pub fn missing_proving_key(circuit_id: &str) -> HealthCheck {
+    HealthCheck::new(
+        HealthComponent::ProvingKey,
+        Some(circuit_id.to_string()),
+        Err("No proving key is cached for this circuit".to_string()),
+    )
+}
+
+/// Writes a probe file to `dir`, reads it back and deletes it
+pub fn check_storage(dir: &Path) -> HealthCheck {
+    let result = {
+        let mut probe = [0u8; 4096];
+        OsRng.fill_bytes(&mut probe);
+        let path = dir.join(format!(".vortex-health-{}", hex::encode(&probe[..8])));
+        let round_trip = fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, probe))
+            .and_then(|_| fs::read(&path));
+        let _ = fs::remove_file(&path);
+        match round_trip {
+            Ok(read) if read == probe => Ok(()),
+            Ok(_) => Err("Read back different bytes than were written".to_string()),
+            Err(e) => Err(format!("Cannot write and read files: {}", e)),
+        }
+    };
+    HealthCheck::new(
+        HealthComponent::Storage,
+        Some(dir.display().to_string()),
+        result,
+    )
+}
+
+/// Compares the memory the OS reports available with `required_bytes`, warning below twice
+/// that and where it cannot be read
+pub fn check_memory(available_bytes: Option<u64>, required_bytes: u64) -> HealthCheck {
+    let mut check = HealthCheck::new(HealthComponent::Memory, None, Ok(()));
+    match available_bytes {
+        Some(available) if available < required_bytes => {
+            check.status = HealthStatus::Failed;
+            check.detail = format!(
+                "{} MiB available, a proof needs about {} MiB",
+                available >> 20,
+                required_bytes >> 20
+            );
+        }
+        Some(available) if available < 2 * required_bytes => {
+            check.status = HealthStatus::Warning;
+            check.detail = format!("Only {} MiB available", available >> 20);
+        }
+        Some(_) => {}
+        None => {
+            check.status = HealthStatus::Warning;
+            check.detail = "Available memory cannot be read on this platform".to_string();
+        }
+    }
+    check
+}
+
+/// `MemAvailable` from `/proc/meminfo` on Linux and Android; `None` elsewhere
+pub fn available_memory() -> Option<u64> {
+    fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| kib_field_bytes(&meminfo, "MemAvailable"))
+}
+
+/// A `<field>: <n> kB` line of a `/proc` file, in bytes
+pub(crate) fn kib_field_bytes(proc_file: &str, field: &str) -> Option<u64> {
+    let line = proc_file.lines().find_map(|line| {
+        line.strip_prefix(field)
+            .and_then(|rest| rest.strip_prefix(':'))
+    })?;
+    let kib: u64 = line.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_pass_on_a_working_install() {
+        assert_eq!(check_constants().status, HealthStatus::Ok);
+
+        let dir = std::env::temp_dir().join(format!("vortex-health-{}", std::process::id()));
+        assert_eq!(check_storage(&dir).status, HealthStatus::Ok);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let meminfo = "MemTotal:        7845560 kB\nMemAvailable:    1048576 kB\n";
+        assert_eq!(kib_field_bytes(meminfo, "MemAvailable"), Some(1 << 30));
+        let required = 256 << 20;
+        let statuses: Vec<HealthStatus> = [Some(1 << 30), Some(300 << 20), Some(100 << 20), None]
+            .into_iter()
+            .map(|available| check_memory(available, required).status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                HealthStatus::Ok,
+                HealthStatus::Warning,
+                HealthStatus::Failed,
+                HealthStatus::Warning
+            ]
+        );
+
+        let report = HealthReport::new(vec![missing_proving_key("default")], None);
+        assert!(!report.healthy);
+    }
+}
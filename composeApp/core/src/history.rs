@@ -0,0 +1,218 @@
+//! Signed transaction history exports.
+//!
+//! An export lists a wallet's shielded receives and spends, optionally limited to a time
+//! range, and is signed with an Ed25519 key derived from the spend seed. The signing key is
+//! independent of the spending and viewing keys, so an accountant or tax authority can check
+//! that the report is unmodified and comes from the wallet that published the verifying key
+//! without learning anything that decrypts or spends notes.
+
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// Export format version
+pub const HISTORY_EXPORT_VERSION: u32 = 1;
+
+const SIGNING_KEY_INFO: &[u8] = b"vortex-history-signing-key-v1";
+const SIGNATURE_DOMAIN: &[u8] = b"vortex-history-export-v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryDirection {
+    Received,
+    Sent,
+}
+
+/// One shielded receive or spend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// Base58 Sui transaction digest
+    pub tx_digest: String,
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    pub direction: HistoryDirection,
+    /// Amount in MIST
+    pub amount: u64,
+    /// Shielded or Sui address of the other party, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+    /// Decimal commitment of the note received or spent
+    pub commitment: String,
+}
+
+/// The signed part of an export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryReport {
+    pub version: u32,
+    /// Inclusive lower bound of the range, if scoped
+    pub from_ms: Option<u64>,
+    /// Exclusive upper bound of the range, if scoped
+    pub to_ms: Option<u64>,
+    /// Entries in timestamp order
+    pub entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedHistoryExport {
+    pub report: HistoryReport,
+    /// Hex Ed25519 verifying key
+    pub signer_public_key: String,
+    /// Hex Ed25519 signature over the domain tag and the report JSON
+    pub signature: String,
+}
+
+/// Derives the history signing key of a 32-byte spend seed
+pub fn signing_key_from_seed(seed: &[u8]) -> Result<SigningKey> {
+    if seed.len() != 32 {
+        return Err(anyhow!("Spend seed must be 32 bytes, got {}", seed.len()));
+    }
+    let mut secret = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, seed)
+        .expand(SIGNING_KEY_INFO, secret.as_mut())
+        .map_err(|_| anyhow!("Failed to derive history signing key"))?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+/// Builds and signs an export of the entries inside `[from_ms, to_ms)`
+pub fn export_history(
+    entries: &[HistoryEntry],
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+    signing_key: &SigningKey,
+) -> Result<SignedHistoryExport> {
+    if let (Some(from), Some(to)) = (from_ms, to_ms)
+        && from > to
+    {
+        return Err(anyhow!(
+            "History range starts at {} after it ends at {}",
+            from,
+            to
+        ));
+    }
+
+    let mut entries: Vec<HistoryEntry> = entries
+        .iter()
+        .filter(|entry| from_ms.is_none_or(|from| entry.timestamp_ms >= from))
+        .filter(|entry| to_ms.is_none_or(|to| entry.timestamp_ms < to))
+        .cloned()
+        .collect();
+    entries.sort_by(|a, b| {
+        a.timestamp_ms
+            .cmp(&b.timestamp_ms)
+            .then_with(|| a.tx_digest.cmp(&b.tx_digest))
+    });
+
+    let report = HistoryReport {
+        version: HISTORY_EXPORT_VERSION,
+        from_ms,
+        to_ms,
+        entries,
+    };
+    let signature = signing_key.sign(&signing_message(&report)?);
+
+    Ok(SignedHistoryExport {
+        report,
+        signer_public_key: hex::encode(signing_key.verifying_key().as_bytes()),
+        signature: hex::encode(signature.to_bytes()),
+    })
+}
+
+/// Checks an export's signature against its embedded verifying key.
+///
+/// Callers compare `signer_public_key` with the key the wallet published to them.
+pub fn verify_history(export: &SignedHistoryExport) -> Result<()> {
+    if export.report.version != HISTORY_EXPORT_VERSION {
+        return Err(anyhow!(
+            "Unsupported history export version {}",
+            export.report.version
+        ));
+    }
+
+    let key_bytes: [u8; 32] = hex::decode(&export.signer_public_key)
+        .context("Invalid signer public key hex")?
+        .try_into()
+        .map_err(|_| anyhow!("Signer public key must be 32 bytes"))?;
+    let key = VerifyingKey::from_bytes(&key_bytes).context("Invalid signer public key")?;
+    let signature_bytes: [u8; 64] = hex::decode(&export.signature)
+        .context("Invalid signature hex")?
+        .try_into()
+        .map_err(|_| anyhow!("Signature must be 64 bytes"))?;
+
+    key.verify(
+        &signing_message(&export.report)?,
+        &Signature::from_bytes(&signature_bytes),
+    )
+    .map_err(|_| anyhow!("History export signature is invalid"))
+}
+
+fn signing_message(report: &HistoryReport) -> Result<Vec<u8>> {
+    let mut message = SIGNATURE_DOMAIN.to_vec();
+    message.extend_from_slice(
+        &serde_json::to_vec(report).context("Failed to serialize history report")?,
+    );
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tx_digest: &str, timestamp_ms: u64, direction: HistoryDirection) -> HistoryEntry {
+        HistoryEntry {
+            tx_digest: tx_digest.to_string(),
+            timestamp_ms,
+            direction,
+            amount: 1_000,
+            counterparty: None,
+            commitment: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_export_scopes_sorts_and_verifies() {
+        let key = signing_key_from_seed(&[6u8; 32]).unwrap();
+        let entries = vec![
+            entry("c", 300, HistoryDirection::Sent),
+            entry("a", 100, HistoryDirection::Received),
+            entry("b", 200, HistoryDirection::Received),
+        ];
+
+        let export = export_history(&entries, Some(150), Some(300), &key).unwrap();
+        assert_eq!(export.report.entries, vec![entries[2].clone()]);
+        verify_history(&export).unwrap();
+
+        let all = export_history(&entries, None, None, &key).unwrap();
+        let digests: Vec<&str> = all
+            .report
+            .entries
+            .iter()
+            .map(|e| e.tx_digest.as_str())
+            .collect();
+        assert_eq!(digests, vec!["a", "b", "c"]);
+
+        assert!(export_history(&entries, Some(2), Some(1), &key).is_err());
+    }
+
+    #[test]
+    fn test_tampered_export_fails_verification() {
+        let key = signing_key_from_seed(&[6u8; 32]).unwrap();
+        let mut export = export_history(
+            &[entry("a", 100, HistoryDirection::Received)],
+            None,
+            None,
+            &key,
+        )
+        .unwrap();
+
+        export.report.entries[0].amount += 1;
+        assert!(verify_history(&export).is_err());
+    }
+}
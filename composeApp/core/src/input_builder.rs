@@ -0,0 +1,334 @@
+//! `ProofInput` builder that starts from an all-dummy transaction.
+//!
+//! A valid `ProofInput` has a dozen fields that must agree with each other: nullifiers and
+//! commitments derived from the private values, a root matching every spent note's path and
+//! a public amount balancing inputs against outputs. Clients kept getting one of them wrong,
+//! so `ProofInputBuilder` derives them all and callers set only what the transaction is
+//! about. Unused input slots are padded with dummies and shuffled (see `padding`), unused
+//! outputs are zero-amount notes under a random key, and every path has `MERKLE_TREE_LEVEL`
+//! levels.
+
+use crate::{
+    account::AccountSecret,
+    circuit::compute_commitment,
+    constants::{MERKLE_TREE_LEVEL, N_OUTS},
+    interop::{validate_session_nonce, ProofInput},
+    merkle_tree::Path,
+    padding::{pad_inputs, SpendInput},
+    poseidon_opt::{hash1, PoseidonOptimized},
+};
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+use ark_std::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// An output note as it is fed into one output slot of the circuit
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+struct OutputSpec {
+    public_key: Fr,
+    amount: u64,
+    blinding: Fr,
+}
+
+/// Builds a consistent `ProofInput`; see the module docs for what is filled in
+#[derive(Clone, Default)]
+pub struct ProofInputBuilder {
+    vortex: Fr,
+    root: Option<Fr>,
+    public_amount: Option<Fr>,
+    inputs: Vec<SpendInput>,
+    outputs: Vec<OutputSpec>,
+    account_secret: Option<AccountSecret>,
+    ext_data_hash: Fr,
+    session_nonce: Option<String>,
+}
+
+impl ProofInputBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_vortex(mut self, vortex: Fr) -> Self {
+        self.vortex = vortex;
+        self
+    }
+
+    /// Overrides the root, which is otherwise computed from the paths of the non-zero inputs
+    pub fn with_root(mut self, root: Fr) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Overrides the public amount, which is otherwise the outputs minus the inputs
+    pub fn with_public_amount(mut self, public_amount: Fr) -> Self {
+        self.public_amount = Some(public_amount);
+        self
+    }
+
+    /// Spends `input` in the next free slot; at most `N_INS` inputs can be added
+    pub fn with_input(mut self, input: SpendInput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Pays `amount` to `public_key` in the next free slot; at most `N_OUTS` outputs can be
+    /// added
+    pub fn with_output(mut self, public_key: Fr, amount: u64, blinding: Fr) -> Self {
+        self.outputs.push(OutputSpec {
+            public_key,
+            amount,
+            blinding,
+        });
+        self
+    }
+
+    /// Proves knowledge of `account_secret` and sets `hashed_account_secret` from it
+    pub fn with_account_secret(mut self, account_secret: AccountSecret) -> Self {
+        self.account_secret = Some(account_secret);
+        self
+    }
+
+    pub fn with_ext_data_hash(mut self, ext_data_hash: Fr) -> Self {
+        self.ext_data_hash = ext_data_hash;
+        self
+    }
+
+    pub fn with_session_nonce(mut self, session_nonce: impl Into<String>) -> Self {
+        self.session_nonce = Some(session_nonce.into());
+        self
+    }
+
+    /// Pads the transaction and derives its public inputs.
+    ///
+    /// # Errors
+    /// Returns error if more than `N_INS` inputs or `N_OUTS` outputs were added, two non-zero
+    /// inputs sit under different roots, an input's path index does not fit the tree, or the
+    /// session nonce is invalid.
+    pub fn build<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<ProofInput> {
+        if self.outputs.len() > N_OUTS {
+            return Err(anyhow!(
+                "At most {} outputs can be created, got {}",
+                N_OUTS,
+                self.outputs.len()
+            ));
+        }
+        if let Some(nonce) = &self.session_nonce {
+            validate_session_nonce(nonce)?;
+        }
+
+        let inputs = pad_inputs(self.inputs.clone(), rng)?;
+        let mut outputs = self.outputs.clone();
+        while outputs.len() < N_OUTS {
+            outputs.push(OutputSpec {
+                public_key: hash1(&Fr::rand(rng)),
+                amount: 0,
+                blinding: Fr::rand(rng),
+            });
+        }
+
+        let root = match self.root {
+            Some(root) => root,
+            None => derive_root(&inputs, &self.vortex)?,
+        };
+        let public_amount = self.public_amount.unwrap_or_else(|| {
+            let out_total: Fr = outputs.iter().map(|output| Fr::from(output.amount)).sum();
+            let in_total: Fr = inputs.iter().map(|input| input.amount).sum();
+            out_total - in_total
+        });
+        let output_commitments: Vec<Fr> = outputs
+            .iter()
+            .map(|output| {
+                compute_commitment(
+                    &Fr::from(output.amount),
+                    &output.public_key,
+                    &output.blinding,
+                    &self.vortex,
+                )
+            })
+            .collect();
+        let (account_secret, hashed_account_secret) = match &self.account_secret {
+            Some(secret) => (secret.to_decimal().to_string(), secret.hashed()),
+            None => ("0".to_string(), Fr::ZERO),
+        };
+
+        let s = |x: &Fr| x.into_bigint().to_string();
+        let path = |path: &Path<MERKLE_TREE_LEVEL>| -> Vec<[String; 2]> {
+            path.path
+                .iter()
+                .map(|(left, right)| [s(left), s(right)])
+                .collect()
+        };
+        Ok(ProofInput {
+            vortex: s(&self.vortex),
+            root: s(&root),
+            public_amount: s(&public_amount),
+            input_nullifier_0: s(&inputs[0].nullifier(&self.vortex)),
+            input_nullifier_1: s(&inputs[1].nullifier(&self.vortex)),
+            output_commitment_0: s(&output_commitments[0]),
+            output_commitment_1: s(&output_commitments[1]),
+            hashed_account_secret: s(&hashed_account_secret),
+            output_ciphertext_hash_0: "0".to_string(),
+            output_ciphertext_hash_1: "0".to_string(),
+            authority_root: "0".to_string(),
+            ext_data_hash: s(&self.ext_data_hash),
+            account_secret,
+            in_private_key_0: s(&inputs[0].private_key),
+            in_private_key_1: s(&inputs[1].private_key),
+            in_amount_0: s(&inputs[0].amount),
+            in_amount_1: s(&inputs[1].amount),
+            in_blinding_0: s(&inputs[0].blinding),
+            in_blinding_1: s(&inputs[1].blinding),
+            in_path_index_0: s(&inputs[0].path_index),
+            in_path_index_1: s(&inputs[1].path_index),
+            merkle_path_0: path(&inputs[0].merkle_path),
+            merkle_path_1: path(&inputs[1].merkle_path),
+            in_legacy_commitment_0: inputs[0].legacy_commitment,
+            in_legacy_commitment_1: inputs[1].legacy_commitment,
+            in_rotated_private_key_0: None,
+            in_rotated_private_key_1: None,
+            in_authority_index_0: "0".to_string(),
+            in_authority_index_1: "0".to_string(),
+            authority_path_0: Vec::new(),
+            authority_path_1: Vec::new(),
+            out_public_key_0: s(&outputs[0].public_key),
+            out_public_key_1: s(&outputs[1].public_key),
+            out_amount_0: outputs[0].amount.to_string(),
+            out_amount_1: outputs[1].amount.to_string(),
+            out_blinding_0: s(&outputs[0].blinding),
+            out_blinding_1: s(&outputs[1].blinding),
+            dummy_input_0: false,
+            dummy_input_1: false,
+            without_account_secret: false,
+            bind_output_ciphertexts: false,
+            key_rotation: false,
+            // Legacy notes are only spendable in the mixed-era shape
+            mixed_era_inputs: inputs.iter().any(|input| input.legacy_commitment),
+            reduce_field_elements: false,
+            session_nonce: self.session_nonce.clone(),
+        })
+    }
+}
+
+/// The root every non-zero input's path leads to, or zero when all inputs are zero-amount
+/// (the circuit skips the membership check for those)
+fn derive_root(inputs: &[SpendInput], vortex: &Fr) -> Result<Fr> {
+    let hasher = PoseidonOptimized::new_t3();
+    let mut root: Option<(usize, Fr)> = None;
+    for (i, input) in inputs.iter().enumerate() {
+        if input.amount == Fr::ZERO {
+            continue;
+        }
+        let input_root = input.merkle_path.calculate_root_at(
+            &input.commitment(vortex),
+            &input.path_index,
+            &hasher,
+        )?;
+        match root {
+            Some((first, root)) if root != input_root => {
+                return Err(anyhow!(
+                    "Inputs {} and {} have paths to different roots",
+                    first,
+                    i
+                ));
+            }
+            Some(_) => {}
+            None => root = Some((i, input_root)),
+        }
+    }
+    Ok(root.map_or(Fr::ZERO, |(_, root)| root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ZERO_VALUE;
+    use crate::debug::find_unsatisfied;
+    use crate::merkle_tree::SparseMerkleTree;
+    use crate::poseidon_opt::fr_from_str;
+    use crate::replay::circuit_from_input;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn spend(private_key: u64, amount: u64, blinding: u64, leaf_index: u64) -> SpendInput {
+        SpendInput {
+            private_key: Fr::from(private_key),
+            amount: Fr::from(amount),
+            blinding: Fr::from(blinding),
+            path_index: Fr::from(leaf_index),
+            merkle_path: Path::empty(),
+            legacy_commitment: false,
+        }
+    }
+
+    #[test]
+    fn test_all_dummy_input_satisfies_the_circuit() {
+        let mut rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let input = ProofInputBuilder::new()
+            .with_vortex(Fr::from(5u64))
+            .build(&mut rng)
+            .unwrap();
+        assert_eq!(input.merkle_path_0.len(), MERKLE_TREE_LEVEL);
+        assert_eq!(input.public_amount, "0");
+
+        let circuit = circuit_from_input(&input).unwrap();
+        circuit.check_public_inputs().unwrap();
+        assert!(find_unsatisfied(&circuit).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_spend_derives_root_and_public_amount() {
+        let mut rng = ChaCha20Rng::from_seed([2u8; 32]);
+        let vortex = Fr::from(5u64);
+        let mut a = spend(11, 700, 21, 2);
+        let mut b = spend(12, 300, 22, 3);
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+        tree.bulk_insert(
+            &[
+                Fr::from(98u64),
+                Fr::from(99u64),
+                a.commitment(&vortex),
+                b.commitment(&vortex),
+            ],
+            &hasher,
+        )
+        .unwrap();
+        a.merkle_path = tree.generate_membership_proof(2).unwrap();
+        b.merkle_path = tree.generate_membership_proof(3).unwrap();
+
+        // Withdraws 600 of the 1000 spent, keeping 400 as change
+        let input = ProofInputBuilder::new()
+            .with_vortex(vortex)
+            .with_input(a.clone())
+            .with_input(b)
+            .with_output(hash1(&Fr::from(11u64)), 400, Fr::from(31u64))
+            .with_account_secret(AccountSecret::generate(&mut rng))
+            .with_session_nonce("tx-1")
+            .build(&mut rng)
+            .unwrap();
+        assert_eq!(input.root, tree.root().into_bigint().to_string());
+        assert_eq!(
+            input.public_amount,
+            (-Fr::from(600u64)).into_bigint().to_string()
+        );
+        let circuit = circuit_from_input(&input).unwrap();
+        circuit.check_public_inputs().unwrap();
+        assert!(find_unsatisfied(&circuit).unwrap().is_none());
+
+        // A second note from another tree cannot be spent alongside
+        assert!(ProofInputBuilder::new()
+            .with_vortex(vortex)
+            .with_input(a)
+            .with_input(spend(13, 5, 23, 0))
+            .build(&mut rng)
+            .is_err());
+        let too_many_outputs = (0..=N_OUTS).fold(ProofInputBuilder::new(), |builder, _| {
+            builder.with_output(Fr::from(1u64), 0, Fr::from(2u64))
+        });
+        assert!(too_many_outputs.build(&mut rng).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! JSON documents exchanged with the apps and the prover.
+//!
+//! `ProofInput` is what every binding's `prove` takes and `ProofOutput` what it returns,
+//! in the camelCase shape the Kotlin, Swift and JavaScript clients serialize. They live
+//! here rather than in a binding crate so the core modules that parse, replay and relay
+//! them do not depend on either binding.
+
+use crate::field::{fr_from_bytes_le, FR_BYTES};
+use crate::format;
+use crate::validation::{self, PointValidation};
+use anyhow::{anyhow, Context};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Proof output structure that matches the expected format for Sui Move contracts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofOutput {
+    /// Proof component A (compressed: 32 bytes)
+    pub proof_a: Vec<u8>,
+    /// Proof component B (compressed: 64 bytes)
+    pub proof_b: Vec<u8>,
+    /// Proof component C (compressed: 32 bytes)
+    pub proof_c: Vec<u8>,
+    /// All public inputs in order expected by Move contract
+    pub public_inputs: Vec<String>,
+    pub proof_serialized_hex: String,
+    pub public_inputs_serialized_hex: String,
+    /// Caller-supplied nonce copied from `ProofInput::session_nonce` (not part of the proof)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_nonce: Option<String>,
+}
+
+/// Longest accepted session nonce, e.g. a hex transaction digest or a UUID
+pub const MAX_SESSION_NONCE_LEN: usize = 128;
+
+/// Compressed Groth16 proof: A (32), B (64) and C (32)
+const PROOF_LEN: usize = 128;
+
+/// Validates a caller-supplied session nonce
+pub fn validate_session_nonce(nonce: &str) -> anyhow::Result<()> {
+    if nonce.is_empty() || nonce.len() > MAX_SESSION_NONCE_LEN {
+        return Err(anyhow::anyhow!(
+            "Session nonce must be 1 to {} bytes, got {}",
+            MAX_SESSION_NONCE_LEN,
+            nonce.len()
+        ));
+    }
+    Ok(())
+}
+
+impl ProofOutput {
+    /// Checks that this proof was produced for the request identified by `expected_nonce`.
+    ///
+    /// The nonce is application-layer metadata: it lets relayer protocols correlate responses
+    /// with requests and reject replayed responses, but it is not bound by the proof itself.
+    pub fn check_session_nonce(&self, expected_nonce: &str) -> anyhow::Result<()> {
+        match &self.session_nonce {
+            Some(nonce) if nonce == expected_nonce => Ok(()),
+            Some(nonce) => Err(anyhow::anyhow!(
+                "Session nonce mismatch: expected '{}', got '{}'",
+                expected_nonce,
+                nonce
+            )),
+            None => Err(anyhow::anyhow!(
+                "Proof carries no session nonce, expected '{}'",
+                expected_nonce
+            )),
+        }
+    }
+
+    /// Binary form for persisting proofs awaiting submission, framed with
+    /// `format::PROOF_OUTPUT`: `proof(128) || input count LE4 || inputs(32 each) || nonce
+    /// length || nonce`, with a zero length for no nonce.
+    ///
+    /// # Errors
+    /// Returns error if the hex fields are malformed or disagree with `public_inputs`.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let proof = hex::decode(&self.proof_serialized_hex)?;
+        let public_inputs = hex::decode(&self.public_inputs_serialized_hex)?;
+        if proof.len() != PROOF_LEN || public_inputs.len() != self.public_inputs.len() * FR_BYTES {
+            return Err(anyhow!(
+                "Serialized proof or public inputs do not match the proof output"
+            ));
+        }
+        let nonce = self.session_nonce.as_deref().unwrap_or_default();
+        if let Some(nonce) = &self.session_nonce {
+            validate_session_nonce(nonce)?;
+        }
+
+        let mut body = Vec::with_capacity(PROOF_LEN + 5 + public_inputs.len() + nonce.len());
+        body.extend_from_slice(&proof);
+        body.extend_from_slice(&(self.public_inputs.len() as u32).to_le_bytes());
+        body.extend_from_slice(&public_inputs);
+        body.push(nonce.len() as u8);
+        body.extend_from_slice(nonce.as_bytes());
+        Ok(format::PROOF_OUTPUT.encode(&body))
+    }
+
+    /// Parses the binary form written by `to_bytes`, checking every curve point.
+    ///
+    /// # Errors
+    /// Returns error if the bytes are not a binary proof, are truncated, or hold an invalid
+    /// point, field element or nonce.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let body = format::PROOF_OUTPUT.decode(bytes)?;
+        let truncated = || anyhow!("Binary proof is truncated");
+
+        let proof_bytes = body.get(..PROOF_LEN).ok_or_else(truncated)?;
+        let proof = validation::deserialize_proof(proof_bytes, PointValidation::Checked)?;
+        let count = body.get(PROOF_LEN..PROOF_LEN + 4).ok_or_else(truncated)?;
+        let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+        let inputs_end = count
+            .checked_mul(FR_BYTES)
+            .and_then(|len| len.checked_add(PROOF_LEN + 4))
+            .ok_or_else(truncated)?;
+        let inputs_bytes = body.get(PROOF_LEN + 4..inputs_end).ok_or_else(truncated)?;
+        let public_inputs = inputs_bytes
+            .chunks(FR_BYTES)
+            .map(|chunk| fr_from_bytes_le(chunk).map(|fr| fr.into_bigint().to_string()))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Invalid public input in binary proof")?;
+
+        let (&nonce_len, nonce) = body[inputs_end..].split_first().ok_or_else(truncated)?;
+        if nonce.len() != nonce_len as usize {
+            return Err(anyhow!(
+                "Binary proof nonce length does not match its contents"
+            ));
+        }
+        let session_nonce = match nonce_len {
+            0 => None,
+            _ => {
+                let nonce = String::from_utf8(nonce.to_vec())?;
+                validate_session_nonce(&nonce)?;
+                Some(nonce)
+            }
+        };
+
+        let mut proof_a = Vec::new();
+        proof.a.serialize_compressed(&mut proof_a)?;
+        let mut proof_b = Vec::new();
+        proof.b.serialize_compressed(&mut proof_b)?;
+        let mut proof_c = Vec::new();
+        proof.c.serialize_compressed(&mut proof_c)?;
+
+        Ok(Self {
+            proof_a,
+            proof_b,
+            proof_c,
+            public_inputs,
+            proof_serialized_hex: hex::encode(proof_bytes),
+            public_inputs_serialized_hex: hex::encode(inputs_bytes),
+            session_nonce,
+        })
+    }
+}
+
+/// Input structure for proof generation
+///
+/// Wiped on drop, like the `TransactionCircuit` it is parsed into, and redacted the same way
+/// when debug-printed.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofInput {
+    // Public inputs
+    pub vortex: String,
+    pub root: String,
+    pub public_amount: String,
+    pub input_nullifier_0: String,
+    pub input_nullifier_1: String,
+    pub output_commitment_0: String,
+    pub output_commitment_1: String,
+    #[serde(default = "zero_field_element")]
+    pub hashed_account_secret: String,
+    #[serde(default = "zero_field_element")]
+    pub output_ciphertext_hash_0: String,
+    #[serde(default = "zero_field_element")]
+    pub output_ciphertext_hash_1: String,
+    #[serde(default = "zero_field_element")]
+    pub authority_root: String,
+    #[serde(default = "zero_field_element")]
+    pub ext_data_hash: String,
+
+    // Private inputs - Input UTXOs
+    #[serde(default = "zero_field_element")]
+    pub account_secret: String,
+    pub in_private_key_0: String,
+    pub in_private_key_1: String,
+    pub in_amount_0: String,
+    pub in_amount_1: String,
+    pub in_blinding_0: String,
+    pub in_blinding_1: String,
+    pub in_path_index_0: String,
+    pub in_path_index_1: String,
+
+    // Merkle paths (array of [left, right] pairs for each level)
+    pub merkle_path_0: Vec<[String; 2]>,
+    pub merkle_path_1: Vec<[String; 2]>,
+
+    // Inputs whose notes use the legacy Poseidon3 commitment
+    #[serde(default)]
+    pub in_legacy_commitment_0: bool,
+    #[serde(default)]
+    pub in_legacy_commitment_1: bool,
+
+    // Rotated-in keys authorizing inputs through a key rotation
+    #[serde(default)]
+    pub in_rotated_private_key_0: Option<String>,
+    #[serde(default)]
+    pub in_rotated_private_key_1: Option<String>,
+
+    // Where each input's authority sits in the authority tree (only with key rotation)
+    #[serde(default = "zero_field_element")]
+    pub in_authority_index_0: String,
+    #[serde(default = "zero_field_element")]
+    pub in_authority_index_1: String,
+    #[serde(default)]
+    pub authority_path_0: Vec<[String; 2]>,
+    #[serde(default)]
+    pub authority_path_1: Vec<[String; 2]>,
+
+    // Private inputs - Output UTXOs
+    pub out_public_key_0: String,
+    pub out_public_key_1: String,
+    pub out_amount_0: String,
+    pub out_amount_1: String,
+    pub out_blinding_0: String,
+    pub out_blinding_1: String,
+
+    // Circuit shape - input slots proven without a Merkle check (needs matching keys, which
+    // reveal the layout; only for pools registered with that layout's verifying key)
+    #[serde(default)]
+    pub dummy_input_0: bool,
+    #[serde(default)]
+    pub dummy_input_1: bool,
+
+    // Circuit shape - no hashed_account_secret input or check (needs matching keys)
+    #[serde(default)]
+    pub without_account_secret: bool,
+
+    // Circuit shape - output blindings bound to the ciphertext hashes (needs matching keys)
+    #[serde(default)]
+    pub bind_output_ciphertexts: bool,
+
+    // Circuit shape - authority_root input checked for every input (needs matching keys)
+    #[serde(default)]
+    pub key_rotation: bool,
+
+    // Circuit shape - inputs may use the legacy commitment (needs matching keys)
+    #[serde(default)]
+    pub mixed_era_inputs: bool,
+
+    // Reduce values >= the field modulus instead of rejecting them
+    #[serde(default)]
+    pub reduce_field_elements: bool,
+
+    // Request nonce / transaction digest echoed into ProofOutput::session_nonce
+    #[serde(default)]
+    pub session_nonce: Option<String>,
+}
+
+impl std::fmt::Debug for ProofInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProofInput")
+            .field("vortex", &self.vortex)
+            .field("root", &self.root)
+            .field("public_amount", &self.public_amount)
+            .field("input_nullifier_0", &self.input_nullifier_0)
+            .field("input_nullifier_1", &self.input_nullifier_1)
+            .field("output_commitment_0", &self.output_commitment_0)
+            .field("output_commitment_1", &self.output_commitment_1)
+            .field("hashed_account_secret", &self.hashed_account_secret)
+            .field("output_ciphertext_hash_0", &self.output_ciphertext_hash_0)
+            .field("output_ciphertext_hash_1", &self.output_ciphertext_hash_1)
+            .field("authority_root", &self.authority_root)
+            .field("ext_data_hash", &self.ext_data_hash)
+            .field("account_secret", &"<redacted>")
+            .field("in_private_keys", &"<redacted>")
+            .field("in_blindings", &"<redacted>")
+            .field("in_rotated_private_keys", &"<redacted>")
+            .field("out_blindings", &"<redacted>")
+            .field("session_nonce", &self.session_nonce)
+            .finish_non_exhaustive()
+    }
+}
+
+fn zero_field_element() -> String {
+    "0".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_groth16::Proof;
+
+    fn compressed(value: &impl CanonicalSerialize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn sample_output(session_nonce: Option<String>) -> ProofOutput {
+        let proof = Proof::<Bn254> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+        let inputs = [Fr::from(1u64), Fr::from(2u64)];
+        let mut inputs_serialized = Vec::new();
+        for input in &inputs {
+            inputs_serialized.extend(compressed(input));
+        }
+        ProofOutput {
+            proof_a: compressed(&proof.a),
+            proof_b: compressed(&proof.b),
+            proof_c: compressed(&proof.c),
+            public_inputs: inputs.iter().map(|x| x.into_bigint().to_string()).collect(),
+            proof_serialized_hex: hex::encode(compressed(&proof)),
+            public_inputs_serialized_hex: hex::encode(inputs_serialized),
+            session_nonce,
+        }
+    }
+
+    #[test]
+    fn test_binary_proof_round_trips() {
+        for nonce in [None, Some("request-1".to_string())] {
+            let output = sample_output(nonce);
+            let bytes = output.to_bytes().unwrap();
+            assert!(bytes.starts_with(b"VXPF"));
+            let parsed = ProofOutput::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                serde_json::to_string(&parsed).unwrap(),
+                serde_json::to_string(&output).unwrap()
+            );
+            assert!(ProofOutput::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        }
+        assert!(ProofOutput::from_bytes(&format::TREE_SNAPSHOT.encode(&[])).is_err());
+    }
+}
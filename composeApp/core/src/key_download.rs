@@ -0,0 +1,318 @@
+//! Proving and verifying key downloads pinned to manifest hashes (`key-download` feature).
+//!
+//! Keys are tens of megabytes and phones drop connections, so a download resumes: bytes go to
+//! `<name>.part` in the key directory, and the next attempt asks the server for the rest with
+//! an HTTP range request. Servers that ignore the range get the file fetched from the start.
+//! The file is only renamed to `<name>` once the SHA-256 of all of it equals the hash pinned
+//! by the app, so a truncated, corrupted or swapped key is never picked up by the loaders.
+//!
+//! Only HTTPS URLs are accepted; the pinned hash protects the contents, TLS the request.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Longest the server may stall before a download fails
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// A key file to fetch and the hash it must have
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDownload {
+    /// File name in the key directory, e.g. "transaction.pk"
+    pub name: String,
+    /// HTTPS URL of the key
+    pub url: String,
+    /// Hex SHA-256 of the file, from the app's manifest
+    pub sha256: String,
+}
+
+/// Downloads keys into one directory
+pub struct KeyDownloader {
+    http: reqwest::blocking::Client,
+    dir: PathBuf,
+    allow_http: bool,
+}
+
+impl KeyDownloader {
+    /// Creates a downloader storing keys in `dir`, creating the directory if needed
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create key directory {}", dir.display()))?;
+        let http = reqwest::blocking::Client::builder()
+            .timeout(READ_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            http,
+            dir,
+            allow_http: false,
+        })
+    }
+
+    /// Where `key` is stored once downloaded
+    pub fn path(&self, key: &KeyDownload) -> Result<PathBuf> {
+        let is_file_name = Path::new(&key.name).file_name() == Some(key.name.as_ref());
+        if !is_file_name || key.name.ends_with(".part") {
+            return Err(anyhow!("Invalid key file name '{}'", key.name));
+        }
+        Ok(self.dir.join(&key.name))
+    }
+
+    /// Downloads `key` unless a file with its pinned hash is already stored, and returns its
+    /// path. `progress` receives the bytes stored so far and the file size when the server
+    /// sends it; returning `false` stops the download, keeping the partial file for the next
+    /// attempt.
+    ///
+    /// # Errors
+    /// Returns error if the URL is not HTTPS, the request fails, the download is stopped, or
+    /// the file does not match its pinned hash. A mismatching file is deleted.
+    pub fn download(
+        &self,
+        key: &KeyDownload,
+        progress: &mut dyn FnMut(u64, Option<u64>) -> bool,
+    ) -> Result<PathBuf> {
+        let expected = parse_sha256(&key.sha256)?;
+        if !(key.url.starts_with("https://") || self.allow_http) {
+            return Err(anyhow!("Key URL must use HTTPS: {}", key.url));
+        }
+        let path = self.path(key)?;
+        if path.exists() && file_sha256(&path)?.finalize()[..] == expected {
+            let len = fs::metadata(&path)?.len();
+            progress(len, Some(len));
+            return Ok(path);
+        }
+
+        let part = self.dir.join(format!("{}.part", key.name));
+        let (mut hasher, mut offset) = if part.exists() {
+            (file_sha256(&part)?, fs::metadata(&part)?.len())
+        } else {
+            (Sha256::new(), 0)
+        };
+
+        let mut request = self.http.get(&key.url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .with_context(|| format!("Failed to request {}", key.url))?;
+        let status = response.status();
+        let resumed = status == StatusCode::PARTIAL_CONTENT;
+        if resumed {
+            let start = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|range| range.to_str().ok())
+                .and_then(content_range_start);
+            if offset == 0 || start != Some(offset) {
+                return Err(anyhow!("Server resumed {} at the wrong offset", key.name));
+            }
+        } else if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            // Nothing left to fetch: the partial file may already be complete
+        } else if status.is_success() {
+            hasher = Sha256::new();
+            offset = 0;
+        } else {
+            return Err(anyhow!("Failed to download {}: HTTP {}", key.url, status));
+        }
+
+        if status != StatusCode::RANGE_NOT_SATISFIABLE {
+            let total = response.content_length().map(|len| len + offset);
+            let mut file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(resumed)
+                .truncate(!resumed)
+                .open(&part)
+                .with_context(|| format!("Failed to open {}", part.display()))?;
+            let mut buf = vec![0u8; CHUNK_LEN];
+            loop {
+                let n = response
+                    .read(&mut buf)
+                    .with_context(|| format!("Download of {} was interrupted", key.name))?;
+                if n == 0 {
+                    break;
+                }
+                file.write_all(&buf[..n])?;
+                hasher.update(&buf[..n]);
+                offset += n as u64;
+                if !progress(offset, total) {
+                    file.sync_all()?;
+                    return Err(anyhow!("Download of {} was cancelled", key.name));
+                }
+            }
+            file.sync_all()?;
+        }
+
+        let digest = hasher.finalize();
+        if digest[..] != expected {
+            fs::remove_file(&part)?;
+            return Err(anyhow!(
+                "{} does not match its pinned hash: expected {}, got {}",
+                key.name,
+                key.sha256,
+                hex::encode(digest)
+            ));
+        }
+        fs::rename(&part, &path).with_context(|| format!("Failed to store {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Downloads `keys` in order with `download`, stopping at the first failure
+    pub fn download_all(
+        &self,
+        keys: &[KeyDownload],
+        progress: &mut dyn FnMut(&KeyDownload, u64, Option<u64>) -> bool,
+    ) -> Result<Vec<PathBuf>> {
+        keys.iter()
+            .map(|key| self.download(key, &mut |stored, total| progress(key, stored, total)))
+            .collect()
+    }
+}
+
+fn parse_sha256(sha256: &str) -> Result<[u8; 32]> {
+    hex::decode(sha256.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Invalid pinned SHA-256 '{}'", sha256))
+}
+
+fn file_sha256(path: &Path) -> Result<Sha256> {
+    let mut hasher = Sha256::new();
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher)
+}
+
+/// First byte of a `Content-Range: bytes <start>-<end>/<len>` header
+fn content_range_start(range: &str) -> Option<u64> {
+    range
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serves `body` over plain HTTP, honouring `Range: bytes=<n>-`, and records the ranges
+    fn serve(body: Vec<u8>) -> (String, Arc<Mutex<Vec<Option<u64>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/key.pk", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        range = value.trim_end_matches('-').parse::<u64>().ok();
+                    }
+                }
+                seen.lock().unwrap().push(range);
+                let start = range.unwrap_or(0) as usize;
+                let head = match range {
+                    Some(_) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n",
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    ),
+                    None => "HTTP/1.1 200 OK\r\n".to_string(),
+                };
+                let head = format!(
+                    "{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    head,
+                    body.len() - start
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&body[start..]).unwrap();
+            }
+        });
+        (url, ranges)
+    }
+
+    fn downloader(name: &str) -> KeyDownloader {
+        let dir = std::env::temp_dir().join(format!("vortex-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut downloader = KeyDownloader::new(dir).unwrap();
+        downloader.allow_http = true;
+        downloader
+    }
+
+    #[test]
+    fn test_download_resumes_and_checks_the_pinned_hash() {
+        let body: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let (url, ranges) = serve(body.clone());
+        let downloader = downloader("resume");
+        let key = KeyDownload {
+            name: "key.pk".into(),
+            url,
+            sha256: hex::encode(Sha256::digest(&body)),
+        };
+
+        fs::write(downloader.dir.join("key.pk.part"), &body[..70_000]).unwrap();
+        let mut last = (0, None);
+        let path = downloader
+            .download(&key, &mut |stored, total| {
+                last = (stored, total);
+                true
+            })
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+        assert_eq!(last, (200_000, Some(200_000)));
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(70_000)]);
+
+        // Already stored: nothing is fetched
+        downloader.download(&key, &mut |_, _| true).unwrap();
+        assert_eq!(ranges.lock().unwrap().len(), 1);
+
+        let tampered = KeyDownload {
+            name: "other.pk".into(),
+            sha256: hex::encode([7u8; 32]),
+            ..key.clone()
+        };
+        let err = downloader
+            .download(&tampered, &mut |_, _| true)
+            .unwrap_err();
+        assert!(err.to_string().contains("pinned hash"));
+        assert!(!downloader.dir.join("other.pk").exists());
+        assert!(!downloader.dir.join("other.pk.part").exists());
+
+        let escaping = KeyDownload {
+            name: "../key.pk".into(),
+            ..key.clone()
+        };
+        assert!(downloader.download(&escaping, &mut |_, _| true).is_err());
+        assert!(KeyDownloader::new(downloader.dir.clone())
+            .unwrap()
+            .download(&key, &mut |_, _| true)
+            .unwrap_err()
+            .to_string()
+            .contains("HTTPS"));
+        fs::remove_dir_all(&downloader.dir).unwrap();
+    }
+}
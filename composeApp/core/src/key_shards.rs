@@ -0,0 +1,504 @@
+//! Proving keys split into shards that are loaded while proving.
+//!
+//! Nearly all of a proving key is its five query vectors. Sharding stores each vector as
+//! fixed-size segments next to a small header holding the verifying key and the vector
+//! lengths. `finalize_proof_sharded` runs every MSM segment by segment: it loads a shard, adds
+//! its partial sum and drops it, so besides the witness only one segment of points is resident
+//! at a time. The partial sums add up to exactly the proof `finalize_proof` produces for the
+//! same randomness.
+//!
+//! Every shard is framed with `format::PROVING_KEY_SHARD` and starts with a kind byte, and
+//! segments also carry their index, so a shard copied into the wrong slot is rejected. The
+//! header and every segment carry the SHA-256 digest of the whole key, so a segment left
+//! over from another key is rejected too.
+
+use crate::format;
+use crate::memlock;
+use crate::prover::{PreparedWitness, ProvingDomain};
+use crate::telemetry::{Phase, PhaseTimer};
+use crate::validation::PointValidation;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{AdditiveGroup, PrimeField, Zero};
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress};
+use ark_std::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Points per segment when the caller has no better figure, about 1 MiB of G1 points
+pub const DEFAULT_POINTS_PER_SHARD: u32 = 1 << 14;
+
+/// Name of the shard holding the verifying key and vector lengths
+pub const HEADER_SHARD: &str = "header.bin";
+
+const HEADER_KIND: u8 = 0;
+
+/// SHA-256 of a proving key's compressed serialization
+pub type KeyDigest = [u8; 32];
+
+/// The query vectors of a proving key, in shard kind order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyQuery {
+    A,
+    BG1,
+    BG2,
+    H,
+    L,
+}
+
+impl KeyQuery {
+    pub const ALL: [KeyQuery; 5] = [
+        KeyQuery::A,
+        KeyQuery::BG1,
+        KeyQuery::BG2,
+        KeyQuery::H,
+        KeyQuery::L,
+    ];
+
+    fn kind(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// File name of segment `index`, e.g. `b_g1-0003.bin`
+    pub fn shard_name(self, index: u32) -> String {
+        let query = match self {
+            KeyQuery::A => "a",
+            KeyQuery::BG1 => "b_g1",
+            KeyQuery::BG2 => "b_g2",
+            KeyQuery::H => "h",
+            KeyQuery::L => "l",
+        };
+        format!("{}-{:04}.bin", query, index)
+    }
+}
+
+/// One file of a sharded proving key
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct KeyShard {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl std::fmt::Debug for KeyShard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyShard")
+            .field("name", &self.name)
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+/// Where the shards of a key are read from
+pub trait ShardSource {
+    /// Bytes of the shard called `name`
+    fn load(&self, name: &str) -> Result<Vec<u8>>;
+}
+
+/// Shards stored as files in one directory
+#[derive(Debug, Clone)]
+pub struct DirectoryShards(pub PathBuf);
+
+impl ShardSource for DirectoryShards {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        std::fs::read(self.0.join(name))
+            .with_context(|| format!("Failed to read key shard {}", name))
+    }
+}
+
+impl ShardSource for HashMap<String, Vec<u8>> {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing key shard {}", name))
+    }
+}
+
+/// Splits `pk` into its header followed by segments of at most `points_per_shard` points.
+///
+/// # Errors
+/// Returns error if `points_per_shard` is zero or serialization fails.
+pub fn shard_proving_key(pk: &ProvingKey<Bn254>, points_per_shard: u32) -> Result<Vec<KeyShard>> {
+    if points_per_shard == 0 {
+        return Err(anyhow!("A shard must hold at least one point"));
+    }
+    let mut hasher = Sha256::new();
+    pk.serialize_compressed(&mut hasher)?;
+    let digest: KeyDigest = hasher.finalize().into();
+
+    let mut header = vec![HEADER_KIND];
+    header.extend_from_slice(&points_per_shard.to_le_bytes());
+    header.extend_from_slice(&digest);
+    pk.vk.serialize_compressed(&mut header)?;
+    pk.beta_g1.serialize_compressed(&mut header)?;
+    pk.delta_g1.serialize_compressed(&mut header)?;
+    for len in [
+        pk.a_query.len(),
+        pk.b_g1_query.len(),
+        pk.b_g2_query.len(),
+        pk.h_query.len(),
+        pk.l_query.len(),
+    ] {
+        header.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+
+    let mut segments = Segments {
+        shards: vec![KeyShard {
+            name: HEADER_SHARD.to_string(),
+            bytes: format::PROVING_KEY_SHARD.encode(&header),
+        }],
+        digest,
+        points_per_shard,
+    };
+    segments.push(KeyQuery::A, &pk.a_query)?;
+    segments.push(KeyQuery::BG1, &pk.b_g1_query)?;
+    segments.push(KeyQuery::BG2, &pk.b_g2_query)?;
+    segments.push(KeyQuery::H, &pk.h_query)?;
+    segments.push(KeyQuery::L, &pk.l_query)?;
+    Ok(segments.shards)
+}
+
+/// The shards of one key as they are split off
+struct Segments {
+    shards: Vec<KeyShard>,
+    digest: KeyDigest,
+    points_per_shard: u32,
+}
+
+impl Segments {
+    fn push<G: CanonicalSerialize>(&mut self, query: KeyQuery, points: &[G]) -> Result<()> {
+        for (index, segment) in points.chunks(self.points_per_shard as usize).enumerate() {
+            let mut body = vec![query.kind()];
+            body.extend_from_slice(&(index as u32).to_le_bytes());
+            body.extend_from_slice(&self.digest);
+            segment.serialize_compressed(&mut body)?;
+            self.shards.push(KeyShard {
+                name: query.shard_name(index as u32),
+                bytes: format::PROVING_KEY_SHARD.encode(&body),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A proving key whose query vectors stay in `source` until a proof needs them
+pub struct ShardedProvingKey<S> {
+    source: S,
+    validation: PointValidation,
+    pub vk: VerifyingKey<Bn254>,
+    /// Digest of the key the shards were split from
+    pub digest: KeyDigest,
+    beta_g1: G1Affine,
+    delta_g1: G1Affine,
+    points_per_shard: usize,
+    lens: [usize; 5],
+    domain: ProvingDomain,
+}
+
+impl<S: ShardSource> ShardedProvingKey<S> {
+    /// Reads the header shard; segments are read, with `validation`, by each proof.
+    ///
+    /// # Errors
+    /// Returns error if the header is missing or malformed.
+    pub fn open(source: S, validation: PointValidation) -> Result<Self> {
+        let bytes = source.load(HEADER_SHARD)?;
+        let body = format::PROVING_KEY_SHARD.decode(&bytes)?;
+        let mut reader = match body.split_first() {
+            Some((&HEADER_KIND, rest)) => rest,
+            _ => return Err(anyhow!("{} is not a key header shard", HEADER_SHARD)),
+        };
+        let points_per_shard = take_u64(&mut reader, 4)? as usize;
+        if points_per_shard == 0 {
+            return Err(anyhow!("Key header has zero points per shard"));
+        }
+        let digest = take_digest(&mut reader)?;
+        let malformed = |e| anyhow!("Malformed key header shard: {}", e);
+        let mode = validation.mode();
+        let vk = VerifyingKey::deserialize_with_mode(&mut reader, Compress::Yes, mode)
+            .map_err(malformed)?;
+        let beta_g1 =
+            G1Affine::deserialize_with_mode(&mut reader, Compress::Yes, mode).map_err(malformed)?;
+        let delta_g1 =
+            G1Affine::deserialize_with_mode(&mut reader, Compress::Yes, mode).map_err(malformed)?;
+        let mut lens = [0; 5];
+        for len in &mut lens {
+            *len = take_u64(&mut reader, 8)? as usize;
+        }
+        if !reader.is_empty() {
+            return Err(anyhow!("Key header shard has trailing bytes"));
+        }
+        Ok(Self {
+            source,
+            validation,
+            vk,
+            digest,
+            beta_g1,
+            delta_g1,
+            points_per_shard,
+            lens,
+            domain: ProvingDomain::for_h_query_len(lens[KeyQuery::H as usize])
+                .map_err(|e| anyhow!("Invalid key header: {}", e))?,
+        })
+    }
+
+    /// Number of segment shards, not counting the header
+    pub fn num_segments(&self) -> usize {
+        self.lens
+            .iter()
+            .map(|len| len.div_ceil(self.points_per_shard))
+            .sum()
+    }
+
+    /// `sum(query[i] * scalars[i])`, one loaded segment at a time
+    fn msm<G>(&self, query: KeyQuery, scalars: &[<Fr as PrimeField>::BigInt]) -> Result<G::Group>
+    where
+        G: AffineRepr<ScalarField = Fr> + CanonicalDeserialize,
+        G::Group: VariableBaseMSM<MulBase = G>,
+    {
+        let len = self.lens[query as usize];
+        let mut acc = G::Group::ZERO;
+        for (index, start) in (0..len.min(scalars.len()))
+            .step_by(self.points_per_shard)
+            .enumerate()
+        {
+            let expected = (len - start).min(self.points_per_shard);
+            let points = self.load_segment::<G>(query, index as u32, expected)?;
+            let _points_lock = memlock::lock(&points);
+            let end = (start + points.len()).min(scalars.len());
+            acc += G::Group::msm_bigint(&points, &scalars[start..end]);
+        }
+        Ok(acc)
+    }
+
+    fn load_segment<G: CanonicalDeserialize>(
+        &self,
+        query: KeyQuery,
+        index: u32,
+        expected: usize,
+    ) -> Result<Vec<G>> {
+        let name = query.shard_name(index);
+        let bytes = self.source.load(&name)?;
+        let body = format::PROVING_KEY_SHARD.decode(&bytes)?;
+        let mut reader = &body[..];
+        let kind = take_u64(&mut reader, 1)? as u8;
+        if kind != query.kind() || take_u64(&mut reader, 4)? != u64::from(index) {
+            return Err(anyhow!("Key shard {} holds a different segment", name));
+        }
+        if take_digest(&mut reader)? != self.digest {
+            return Err(anyhow!("Key shard {} belongs to another proving key", name));
+        }
+        let points =
+            Vec::<G>::deserialize_with_mode(&mut reader, Compress::Yes, self.validation.mode())
+                .map_err(|e| anyhow!("Malformed key shard {}: {}", name, e))?;
+        if points.len() != expected || !reader.is_empty() {
+            return Err(anyhow!(
+                "Key shard {} holds {} points, expected {}",
+                name,
+                points.len(),
+                expected
+            ));
+        }
+        Ok(points)
+    }
+}
+
+/// Runs the MSMs for a prepared witness against a sharded key, like `finalize_proof`
+///
+/// # Errors
+/// Returns error if a shard is missing or malformed, or the witness does not fit the key.
+pub fn finalize_proof_sharded<S: ShardSource, R: RngCore + CryptoRng>(
+    key: &ShardedProvingKey<S>,
+    prepared: &PreparedWitness,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> Result<Proof<Bn254>> {
+    let r = Fr::rand(rng);
+    let s = Fr::rand(rng);
+    trace_span!(
+        "groth16_prove_sharded",
+        num_constraints = prepared.num_constraints
+    );
+
+    let full_assignment = &prepared.full_assignment;
+    if full_assignment.len() != key.lens[KeyQuery::A as usize] {
+        return Err(anyhow!(
+            "Witness has {} variables but the key was set up for {}",
+            full_assignment.len(),
+            key.lens[KeyQuery::A as usize]
+        ));
+    }
+    let h_acc: G1Projective = {
+        let h = key
+            .domain
+            .witness_map(prepared)
+            .map_err(|e| anyhow!("Failed to compute the QAP witness: {}", e))?;
+        let h: Vec<_> = h.iter().map(|x| x.into_bigint()).collect();
+        key.msm::<G1Affine>(KeyQuery::H, &h)?
+    };
+
+    // `full_assignment[0]` is the constant 1, so the A and B queries' constant terms are
+    // part of these MSMs
+    let assignment: Vec<_> = full_assignment.iter().map(|x| x.into_bigint()).collect();
+    let l_acc = key.msm::<G1Affine>(KeyQuery::L, &assignment[prepared.num_inputs..])?;
+    let g_a = key.delta_g1 * r + key.vk.alpha_g1 + key.msm::<G1Affine>(KeyQuery::A, &assignment)?;
+    let g1_b = if r.is_zero() {
+        G1Projective::ZERO
+    } else {
+        key.delta_g1 * s + key.beta_g1 + key.msm::<G1Affine>(KeyQuery::BG1, &assignment)?
+    };
+    let g2_b =
+        key.vk.delta_g2 * s + key.vk.beta_g2 + key.msm::<G2Affine>(KeyQuery::BG2, &assignment)?;
+    let g_c = g_a * s + g1_b * r - key.delta_g1 * (r * s) + l_acc + h_acc;
+    timer.end_phase(Phase::Prove);
+
+    Ok(Proof {
+        a: g_a.into_affine(),
+        b: g2_b.into_affine(),
+        c: g_c.into_affine(),
+    })
+}
+
+/// Reads a little-endian integer of `len` bytes
+fn take_u64(reader: &mut &[u8], len: usize) -> Result<u64> {
+    if reader.len() < len {
+        return Err(anyhow!("Key shard is truncated"));
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    let mut buf = [0u8; 8];
+    buf[..len].copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn take_digest(reader: &mut &[u8]) -> Result<KeyDigest> {
+    if reader.len() < 32 {
+        return Err(anyhow!("Key shard is truncated"));
+    }
+    let (digest, rest) = reader.split_at(32);
+    *reader = rest;
+    Ok(digest.try_into().expect("32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{sample_zero_transfer, TransactionCircuit};
+    use crate::prover::{finalize_proof, prepare_circuit};
+    use ark_groth16::Groth16;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn proving_key(seed: u8) -> ProvingKey<Bn254> {
+        let mut rng = ChaCha20Rng::from_seed([seed; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+            .unwrap()
+    }
+
+    fn setup() -> (ProvingKey<Bn254>, PreparedWitness) {
+        let pk = proving_key(3);
+        let circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let prepared = prepare_circuit(circuit, &mut PhaseTimer::start()).unwrap();
+        (pk, prepared)
+    }
+
+    fn source(shards: Vec<KeyShard>) -> HashMap<String, Vec<u8>> {
+        shards
+            .into_iter()
+            .map(|shard| (shard.name, shard.bytes))
+            .collect()
+    }
+
+    #[test]
+    fn test_sharded_proof_matches_the_unsharded_one() {
+        let (pk, prepared) = setup();
+        let shards = shard_proving_key(&pk, 1000).unwrap();
+        let key =
+            ShardedProvingKey::open(source(shards.clone()), PointValidation::Checked).unwrap();
+        assert_eq!(key.vk, pk.vk);
+        assert_eq!(key.num_segments(), shards.len() - 1);
+        assert!(key.num_segments() > KeyQuery::ALL.len());
+
+        let mut timer = PhaseTimer::start();
+        let expected = finalize_proof(
+            &pk,
+            &prepared,
+            &mut ChaCha20Rng::from_seed([4u8; 32]),
+            &mut timer,
+        )
+        .unwrap();
+        let sharded = finalize_proof_sharded(
+            &key,
+            &prepared,
+            &mut ChaCha20Rng::from_seed([4u8; 32]),
+            &mut timer,
+        )
+        .unwrap();
+        assert_eq!(sharded, expected);
+
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &sharded, prepared.public_inputs()).unwrap());
+    }
+
+    #[test]
+    fn test_missing_and_misplaced_shards_are_rejected() {
+        let (pk, prepared) = setup();
+        assert!(shard_proving_key(&pk, 0).is_err());
+        let mut shards = source(shard_proving_key(&pk, 1000).unwrap());
+        let mut rng = ChaCha20Rng::from_seed([5u8; 32]);
+
+        let swapped = shards[&KeyQuery::A.shard_name(1)].clone();
+        shards.insert(KeyQuery::A.shard_name(0), swapped);
+        let key = ShardedProvingKey::open(shards.clone(), PointValidation::Unchecked).unwrap();
+        let err = finalize_proof_sharded(&key, &prepared, &mut rng, &mut PhaseTimer::start())
+            .unwrap_err();
+        assert!(err.to_string().contains("different segment"));
+
+        shards.remove(&KeyQuery::L.shard_name(0));
+        let key = ShardedProvingKey::open(shards.clone(), PointValidation::Unchecked).unwrap();
+        assert!(
+            finalize_proof_sharded(&key, &prepared, &mut rng, &mut PhaseTimer::start()).is_err()
+        );
+
+        shards.remove(HEADER_SHARD);
+        assert!(ShardedProvingKey::open(shards, PointValidation::Unchecked).is_err());
+    }
+
+    #[test]
+    fn test_shards_of_another_key_are_rejected() {
+        let (pk, prepared) = setup();
+        let other = proving_key(6);
+        let mut shards = source(shard_proving_key(&pk, 1000).unwrap());
+        let other_shards = source(shard_proving_key(&other, 1000).unwrap());
+        let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+
+        let key = ShardedProvingKey::open(shards.clone(), PointValidation::Unchecked).unwrap();
+        let other_key =
+            ShardedProvingKey::open(other_shards.clone(), PointValidation::Unchecked).unwrap();
+        assert_ne!(key.digest, other_key.digest);
+
+        // Same circuit, so the segment has the right kind, index and length
+        let name = KeyQuery::H.shard_name(0);
+        shards.insert(name.clone(), other_shards[&name].clone());
+        let key = ShardedProvingKey::open(shards.clone(), PointValidation::Unchecked).unwrap();
+        let err = finalize_proof_sharded(&key, &prepared, &mut rng, &mut PhaseTimer::start())
+            .unwrap_err();
+        assert!(err.to_string().contains("another proving key"), "{}", err);
+
+        // And the other key's header cannot adopt this key's segments
+        let mut mixed = source(shard_proving_key(&pk, 1000).unwrap());
+        mixed.insert(HEADER_SHARD.to_string(), other_shards[HEADER_SHARD].clone());
+        let key = ShardedProvingKey::open(mixed, PointValidation::Unchecked).unwrap();
+        let err = finalize_proof_sharded(&key, &prepared, &mut rng, &mut PhaseTimer::start())
+            .unwrap_err();
+        assert!(err.to_string().contains("another proving key"), "{}", err);
+    }
+}
@@ -0,0 +1,127 @@
+//! Circuit, hashing, Merkle trees, notes and everything else the Vortex clients share.
+//!
+//! Free of binding code: `vortex-uniffi` and `vortex-wasm` wrap it for the apps and the web
+//! wallet, and server-side or CLI users depend on it directly. See `Cargo.toml` for the
+//! features selecting what gets compiled for each target.
+//!
+//! Without the default `std` feature the crate is `no_std + alloc` and keeps only hashing,
+//! commitments, Merkle paths and the circuit definition, for verifiers on embedded targets.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
+
+#[cfg(feature = "chain-client")]
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod account;
+#[cfg(feature = "std")]
+pub mod address;
+#[cfg(feature = "std")]
+pub mod aggregation;
+#[cfg(feature = "std")]
+pub mod amount;
+#[cfg(feature = "std")]
+pub mod auth;
+#[cfg(feature = "std")]
+pub mod backup;
+pub mod circuit;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod consolidation;
+pub mod constants;
+#[cfg(feature = "std")]
+pub mod cross_pool;
+#[cfg(feature = "std")]
+pub mod debug;
+#[cfg(not(feature = "std"))]
+#[path = "debug_region.rs"]
+pub mod debug;
+#[cfg(feature = "std")]
+pub mod delegate;
+#[cfg(feature = "std")]
+pub mod deposit_note;
+#[cfg(feature = "std")]
+pub mod dry_run;
+#[cfg(feature = "std")]
+pub mod error_code;
+#[cfg(feature = "std")]
+pub mod estimate;
+pub mod field;
+pub mod format;
+#[cfg(feature = "std")]
+pub mod fixtures;
+#[cfg(feature = "std")]
+pub mod health;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "std")]
+pub mod input_builder;
+#[cfg(feature = "std")]
+pub mod interop;
+#[cfg(feature = "key-download")]
+pub mod key_download;
+#[cfg(feature = "std")]
+pub mod key_shards;
+#[cfg(feature = "std")]
+pub mod memlock;
+pub mod merkle_tree;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod mul_kernel;
+#[cfg(feature = "std")]
+pub mod multi_send;
+#[cfg(feature = "std")]
+pub mod note;
+#[cfg(feature = "std")]
+pub mod onchain_vk;
+#[cfg(feature = "std")]
+pub mod padding;
+#[cfg(feature = "std")]
+pub mod payment_request;
+#[cfg(feature = "std")]
+pub mod payment_uri;
+pub mod poseidon_opt;
+#[cfg(feature = "std")]
+pub mod prover;
+#[cfg(feature = "std")]
+pub mod quad_merkle_tree;
+#[cfg(feature = "std")]
+pub mod receipt;
+#[cfg(feature = "std")]
+pub mod relayer;
+#[cfg(feature = "std")]
+pub mod relayer_fee;
+#[cfg(feature = "std")]
+pub mod replay;
+pub mod rotation;
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod sui_tx;
+#[cfg(feature = "chain-client")]
+pub mod sync;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+pub mod validation;
+#[cfg(feature = "std")]
+pub mod verifier;
+#[cfg(feature = "std")]
+pub mod viewing;
+#[cfg(feature = "std")]
+pub mod witness_cache;
+#[cfg(feature = "std")]
+pub mod zklogin;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
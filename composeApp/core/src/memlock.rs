@@ -0,0 +1,88 @@
+//! Best-effort memory locking for secrets and key material.
+//!
+//! When enabled, buffers holding the account secret and the proving key are pinned with
+//! `mlock`/`VirtualLock` so they are never written to swap. Locking is advisory: it is off by
+//! default, quietly does nothing when the OS refuses it (e.g. a low `RLIMIT_MEMLOCK` on
+//! Android) and is a no-op on wasm. Page locks do not nest, so releasing one lock also
+//! unlocks any other locked data that shares its pages.
+
+use ark_bn254::Bn254;
+use ark_groth16::ProvingKey;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static LOCKING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns memory locking on or off for buffers locked from now on
+pub fn set_enabled(enabled: bool) {
+    LOCKING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    LOCKING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Keeps a buffer resident in RAM until dropped
+pub struct MemoryLock {
+    #[cfg(not(target_arch = "wasm32"))]
+    _guard: region::LockGuard,
+}
+
+/// Locks the pages backing `data`, if locking is enabled and the platform allows it.
+///
+/// The returned lock must be dropped before `data` is freed.
+pub fn lock<T>(data: &[T]) -> Option<MemoryLock> {
+    if !is_enabled() || data.is_empty() {
+        return None;
+    }
+    lock_pages(data)
+}
+
+/// Locks the point vectors of a proving key, skipping any the OS refuses to lock
+pub fn lock_proving_key(pk: &ProvingKey<Bn254>) -> Vec<MemoryLock> {
+    [
+        lock(&pk.a_query),
+        lock(&pk.b_g1_query),
+        lock(&pk.b_g2_query),
+        lock(&pk.h_query),
+        lock(&pk.l_query),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn lock_pages<T>(data: &[T]) -> Option<MemoryLock> {
+    match region::lock(data.as_ptr(), std::mem::size_of_val(data)) {
+        Ok(guard) => Some(MemoryLock { _guard: guard }),
+        Err(e) => {
+            log::debug!("Memory locking unavailable: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn lock_pages<T>(_data: &[T]) -> Option<MemoryLock> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_respects_enabled_flag() {
+        let secret = vec![0x5au8; 64];
+
+        set_enabled(false);
+        assert!(lock(&secret).is_none());
+
+        // Whether the OS grants the lock depends on RLIMIT_MEMLOCK; it must not panic either way
+        set_enabled(true);
+        let guard = lock(&secret);
+        assert!(lock::<u8>(&[]).is_none());
+        drop(guard);
+        set_enabled(false);
+    }
+}
@@ -1,16 +1,25 @@
-use std::borrow::Borrow;
+use alloc::format;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
 
 use anyhow::{anyhow, Context};
 use ark_bn254::Fr;
-use ark_ff::AdditiveGroup;
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
 use ark_r1cs_std::{
     fields::fp::FpVar,
     prelude::{AllocVar, AllocationMode, Boolean, EqGadget},
     select::CondSelectGadget,
+    R1CSVar,
 };
 use ark_relations::r1cs::{Namespace, SynthesisError};
+use serde::{Deserialize, Serialize};
+use subtle::Choice;
+use zeroize::Zeroize;
 
-use crate::poseidon_opt::{PoseidonOptimized, PoseidonOptimizedVar};
+use crate::debug;
+use crate::field::{ct_eq, ct_select, fr_from_bytes_le, fr_to_bytes_le, FR_BYTES};
+use crate::format;
+use crate::poseidon_opt::{PoseidonOptimized, PoseidonOptimizedVar, SboxWitness};
 
 /// Merkle tree path structure
 /// Each level contains (left_hash, right_hash) pair
@@ -19,6 +28,16 @@ pub struct Path<const N: usize> {
     pub path: [(Fr, Fr); N],
 }
 
+// Paths are witness data: a spent note's siblings reveal its position in the tree
+impl<const N: usize> Zeroize for Path<N> {
+    fn zeroize(&mut self) {
+        for (left, right) in self.path.iter_mut() {
+            left.zeroize();
+            right.zeroize();
+        }
+    }
+}
+
 impl<const N: usize> Path<N> {
     /// Creates a new empty path
     pub fn empty() -> Self {
@@ -40,27 +59,65 @@ impl<const N: usize> Path<N> {
         Ok(root == *root_hash)
     }
 
-    /// Calculate Merkle root from leaf and path
+    /// Check if leaf belongs to tree with given root at position `index`, as the circuit does
+    pub fn check_membership_at(
+        &self,
+        root_hash: &Fr,
+        leaf: &Fr,
+        index: &Fr,
+        hasher: &PoseidonOptimized,
+    ) -> anyhow::Result<bool> {
+        Ok(self.calculate_root_at(leaf, index, hasher)? == *root_hash)
+    }
+
+    /// Calculate Merkle root from leaf and path, taking each level's direction from bit
+    /// `level` of `index`
     ///
     /// This matches PathVar::root_hash and Move's append_pair logic:
     /// - Level 0: path stores (leaf_left, leaf_right)
     /// - Levels 1 to N-1: path stores (left_sibling, right_sibling)
+    ///
+    /// # Errors
+    /// Returns error if `index` does not fit in `N` bits.
+    pub fn calculate_root_at(
+        &self,
+        leaf: &Fr,
+        index: &Fr,
+        hasher: &PoseidonOptimized,
+    ) -> anyhow::Result<Fr> {
+        let index = index.into_bigint();
+        if index.num_bits() as usize > N {
+            return Err(anyhow!("Leaf index exceeds tree capacity (>= 2^{})", N));
+        }
+
+        let mut previous_hash = *leaf;
+        for (level, (p_left_hash, p_right_hash)) in self.path.iter().enumerate() {
+            let previous_is_right = Choice::from(index.get_bit(level) as u8);
+
+            let left_hash = ct_select(&previous_hash, p_left_hash, previous_is_right);
+            let right_hash = ct_select(p_right_hash, &previous_hash, previous_is_right);
+
+            previous_hash = hasher.hash2(&left_hash, &right_hash);
+        }
+
+        Ok(previous_hash)
+    }
+
+    /// Calculate Merkle root from leaf and path, inferring each level's direction by
+    /// comparing the running hash with the left sibling
+    ///
+    /// Prefer `calculate_root_at` when the index is known: the inferred direction is wrong
+    /// when a node equals its left sibling, though the root is the same.
     pub fn calculate_root(&self, leaf: &Fr, hasher: &PoseidonOptimized) -> anyhow::Result<Fr> {
         let mut previous_hash = *leaf;
 
+        // The leaf and path belong to the note being spent, so direction is resolved
+        // without secret-dependent branches
         for (p_left_hash, p_right_hash) in self.path.iter() {
-            let previous_is_left = previous_hash == *p_left_hash;
+            let previous_is_right = !ct_eq(&previous_hash, p_left_hash);
 
-            let left_hash = if previous_is_left {
-                previous_hash
-            } else {
-                *p_left_hash
-            };
-            let right_hash = if previous_is_left {
-                *p_right_hash
-            } else {
-                previous_hash
-            };
+            let left_hash = ct_select(&previous_hash, p_left_hash, previous_is_right);
+            let right_hash = ct_select(p_right_hash, &previous_hash, previous_is_right);
 
             previous_hash = hasher.hash2(&left_hash, &right_hash);
         }
@@ -81,16 +138,15 @@ impl<const N: usize> Path<N> {
             ));
         }
 
-        let is_left = *leaf == self.path[0].0;
-        let mut index = if is_left { Fr::ZERO } else { Fr::from(1u64) };
+        let is_right = !ct_eq(leaf, &self.path[0].0);
+        let mut index = ct_select(&Fr::ZERO, &Fr::from(1u64), is_right);
 
         let mut prev = hasher.hash2(&self.path[0].0, &self.path[0].1);
 
         for (level, (left_hash, right_hash)) in self.path.iter().enumerate().skip(1) {
-            if &prev != left_hash {
-                let bit_value = Fr::from(1u64 << level);
-                index += bit_value;
-            }
+            let bit_value = Fr::from(1u64 << level);
+            let is_right = !ct_eq(&prev, left_hash);
+            index += ct_select(&Fr::ZERO, &bit_value, is_right);
             prev = hasher.hash2(left_hash, right_hash);
         }
 
@@ -183,7 +239,7 @@ impl<const N: usize> SparseMerkleTree<N> {
             let left: Fr;
             let right: Fr;
 
-            if current_index % 2 == 0 {
+            if current_index.is_multiple_of(2) {
                 // Current is left child
                 left = current_level_hash;
                 right = self.empty_hashes[i];
@@ -221,7 +277,7 @@ impl<const N: usize> SparseMerkleTree<N> {
 
     /// Bulk insert (must be even number of leaves)
     pub fn bulk_insert(&mut self, leaves: &[Fr], hasher: &PoseidonOptimized) -> anyhow::Result<()> {
-        if leaves.len() % 2 != 0 {
+        if !leaves.len().is_multiple_of(2) {
             return Err(anyhow!("Must insert even number of leaves (pairs)"));
         }
 
@@ -257,6 +313,60 @@ impl<const N: usize> SparseMerkleTree<N> {
         &self.leaves
     }
 
+    /// Serializes the tree under `format::TREE_SNAPSHOT`, cached subtrees and root included,
+    /// so restoring it does not rehash every leaf.
+    ///
+    /// The body is `depth || leaf count LE8` followed by the empty leaf, the leaves, the
+    /// subtrees and the root as compressed field elements.
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let elements = 1 + self.leaves.len() + self.subtrees.len() + 1;
+        let mut body = Vec::with_capacity(9 + elements * FR_BYTES);
+        body.push(N as u8);
+        body.extend_from_slice(&(self.leaves.len() as u64).to_le_bytes());
+        let all = core::iter::once(&self.empty_hashes[0])
+            .chain(&self.leaves)
+            .chain(&self.subtrees)
+            .chain(core::iter::once(&self.root));
+        for element in all {
+            body.extend_from_slice(&fr_to_bytes_le(element));
+        }
+        format::TREE_SNAPSHOT.encode(&body)
+    }
+
+    /// Restores a tree saved with `to_snapshot`.
+    ///
+    /// # Errors
+    /// Returns error if `bytes` are not a snapshot of a depth-`N` tree, are truncated, or
+    /// hold a non-canonical field element.
+    pub fn from_snapshot(bytes: &[u8], hasher: &PoseidonOptimized) -> anyhow::Result<Self> {
+        let body = format::TREE_SNAPSHOT.decode(bytes)?;
+        if body.len() < 9 || body[0] as usize != N {
+            return Err(anyhow!("Not a snapshot of a depth-{} tree", N));
+        }
+        let count = u64::from_le_bytes(body[1..9].try_into().unwrap());
+        if count > 1u64 << N || !count.is_multiple_of(2) {
+            return Err(anyhow!(
+                "Tree snapshot holds an invalid leaf count {}",
+                count
+            ));
+        }
+        let count = count as usize;
+        if body.len() != 9 + (1 + count + N + 1) * FR_BYTES {
+            return Err(anyhow!("Tree snapshot is truncated"));
+        }
+        let elements = body[9..]
+            .chunks(FR_BYTES)
+            .map(fr_from_bytes_le)
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Invalid field element in tree snapshot")?;
+
+        let mut tree = Self::new_empty(hasher, &elements[0]);
+        tree.leaves = elements[1..1 + count].to_vec();
+        tree.subtrees = elements[1 + count..1 + count + N].to_vec();
+        tree.root = elements[1 + count + N];
+        Ok(tree)
+    }
+
     /// Generate membership proof for leaf at given index
     ///
     /// Returns a Path containing siblings at each level:
@@ -356,7 +466,7 @@ impl<const N: usize> SparseMerkleTree<N> {
 
         // Extract siblings from rebuilt tree
         for (level, path_elem) in path.iter_mut().enumerate().skip(1) {
-            let is_left = current_index % 2 == 0;
+            let is_left = current_index.is_multiple_of(2);
             let level_idx = level - 1;
             let child_hashes = &level_child_hashes[level_idx];
 
@@ -404,7 +514,120 @@ impl<const N: usize> SparseMerkleTree<N> {
         let leaf = self.leaves[index];
         let hasher = PoseidonOptimized::new_t3();
 
-        path.check_membership(&self.root, &leaf, &hasher)
+        path.check_membership_at(&self.root, &leaf, &Fr::from(index as u64), &hasher)
+    }
+
+    /// Rebuilds the tree from `leaves` in insertion order and returns its root, the index of
+    /// `target` and the path to it.
+    ///
+    /// For stateless callers, e.g. web clients that fetch the full commitment list from an
+    /// indexer. `leaves` must hold whole pairs, as the pool inserts them.
+    pub fn path_from_leaves(
+        leaves: &[Fr],
+        target: &Fr,
+        empty_leaf: &Fr,
+    ) -> anyhow::Result<(Fr, usize, Path<N>)> {
+        let index = leaves
+            .iter()
+            .position(|leaf| leaf == target)
+            .ok_or_else(|| anyhow!("Target leaf is not in the leaf list"))?;
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree = Self::new_empty(&hasher, empty_leaf);
+        tree.bulk_insert(leaves, &hasher)?;
+        Ok((tree.root, index, tree.generate_membership_proof(index)?))
+    }
+}
+
+/// How a local tree relates to the pool's tree on chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum RootStatus {
+    /// Same leaves and root
+    Consistent,
+    /// The chain has more leaves and the local tree agrees with it as far as it goes; a
+    /// regular sync catches it up
+    Behind,
+    /// The local tree has leaves the chain does not, or a different root at the same size;
+    /// proofs against it would use a root the pool never had
+    Diverged,
+}
+
+/// Result of `check_root_consistency`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct RootConsistency {
+    pub status: RootStatus,
+    pub local_leaf_count: u64,
+    pub chain_leaf_count: u64,
+    /// First leaf index where the local tree departs from the chain, when the event history
+    /// or the leaf counts show it
+    pub first_mismatch: Option<u64>,
+    /// Discard the local tree and rebuild it from the chain's events
+    pub resync_recommended: bool,
+}
+
+/// Compares a local tree against the pool's current root and leaf count.
+///
+/// `chain_leaves` is the chain's commitment history from leaf 0 in index order, as far as
+/// the caller has it, and may be empty. Leaves both sides have are compared one by one to
+/// find where a forked local state departs. When the local tree is behind and the history
+/// covers the missing leaves, they are replayed onto a copy of it so the chain root is still
+/// checked; otherwise a shorter local tree whose leaves agree is reported as `Behind`.
+pub fn check_root_consistency<const N: usize>(
+    local_tree: &SparseMerkleTree<N>,
+    chain_root: &Fr,
+    chain_leaf_count: u64,
+    chain_leaves: &[Fr],
+    hasher: &PoseidonOptimized,
+) -> RootConsistency {
+    let local_leaf_count = local_tree.len() as u64;
+    let known = chain_leaves.len().min(chain_leaf_count as usize);
+    let history = &chain_leaves[..known];
+
+    let mut first_mismatch = local_tree
+        .leaves
+        .iter()
+        .zip(history)
+        .position(|(local, chain)| local != chain)
+        .map(|index| index as u64);
+    if first_mismatch.is_none() && local_leaf_count > chain_leaf_count {
+        first_mismatch = Some(chain_leaf_count);
+    }
+
+    let status = if first_mismatch.is_some() {
+        RootStatus::Diverged
+    } else if local_leaf_count == chain_leaf_count {
+        if local_tree.root() == *chain_root {
+            RootStatus::Consistent
+        } else {
+            RootStatus::Diverged
+        }
+    } else if known as u64 == chain_leaf_count {
+        let mut replayed = local_tree.clone();
+        let caught_up = history[local_tree.len()..]
+            .chunks(2)
+            .try_for_each(|pair| match *pair {
+                [left, right] => replayed.insert_pair(left, right, hasher),
+                _ => replayed.insert(pair[0], hasher),
+            });
+        if caught_up.is_ok() && replayed.root() == *chain_root {
+            RootStatus::Behind
+        } else {
+            RootStatus::Diverged
+        }
+    } else {
+        RootStatus::Behind
+    };
+
+    RootConsistency {
+        status,
+        local_leaf_count,
+        chain_leaf_count,
+        first_mismatch,
+        resync_recommended: status == RootStatus::Diverged,
     }
 }
 
@@ -415,39 +638,129 @@ pub struct PathVar<const N: usize> {
 }
 
 impl<const N: usize> PathVar<N> {
-    /// Check membership in circuit
+    /// Check membership in circuit of `leaf` at position `index`.
+    ///
+    /// The index is decomposed into `N` direction bits (see `index_bits`), so the result is
+    /// only true for the position the path was generated for.
     pub fn check_membership(
         &self,
         root: &FpVar<Fr>,
         leaf: &FpVar<Fr>,
+        index: &FpVar<Fr>,
         hasher: &PoseidonOptimizedVar,
     ) -> Result<Boolean<Fr>, SynthesisError> {
-        let computed_root = self.root_hash(leaf, hasher)?;
+        let index_bits = Self::index_bits(index)?;
+        let computed_root = self.root_hash(leaf, &index_bits, hasher)?;
         root.is_eq(&computed_root)
     }
 
-    /// Calculate root hash in circuit
+    /// Allocates the `N` little-endian direction bits of `index` and enforces that they
+    /// recompose to it.
+    ///
+    /// Bit `level` is set when the running hash is the right child at that level. Since
+    /// `2^N` is far below the field modulus, the decomposition is unique and also enforces
+    /// `index < 2^N`.
+    pub fn index_bits(index: &FpVar<Fr>) -> Result<Vec<Boolean<Fr>>, SynthesisError> {
+        let cs = index.cs();
+        let bits = (0..N)
+            .map(|level| {
+                Boolean::new_witness(ark_relations::ns!(cs, "index_bit"), || {
+                    Ok(index.value()?.into_bigint().get_bit(level))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Boolean::le_bits_to_fp(&bits)?.enforce_equal(index)?;
+        Ok(bits)
+    }
+
+    /// Calculate root hash in circuit, taking each level's direction from `index_bits`.
+    ///
+    /// Selecting by an explicit bit rather than by comparing the running hash with the left
+    /// sibling costs one booleanity constraint per level instead of an equality gadget, and
+    /// stays correct when a node equals its sibling. Fails with `Unsatisfiable` unless
+    /// `index_bits` holds one bit per level.
     pub fn root_hash(
         &self,
         leaf: &FpVar<Fr>,
+        index_bits: &[Boolean<Fr>],
         hasher: &PoseidonOptimizedVar,
     ) -> Result<FpVar<Fr>, SynthesisError> {
-        assert_eq!(self.path.len(), N);
+        if index_bits.len() != N {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(key) = self.cache_key(leaf, index_bits, hasher) {
+            if let Some(trace) = crate::witness_cache::lookup(&key) {
+                let mut witness = SboxWitness::Replay(&trace);
+                return self.hash_levels(leaf, index_bits, hasher, &mut witness);
+            }
+            let mut trace = Vec::new();
+            let root = self.hash_levels(
+                leaf,
+                index_bits,
+                hasher,
+                &mut SboxWitness::Record(&mut trace),
+            )?;
+            crate::witness_cache::insert(key, trace);
+            return Ok(root);
+        }
+        self.hash_levels(leaf, index_bits, hasher, &mut SboxWitness::Compute)
+    }
+
+    fn hash_levels(
+        &self,
+        leaf: &FpVar<Fr>,
+        index_bits: &[Boolean<Fr>],
+        hasher: &PoseidonOptimizedVar,
+        witness: &mut SboxWitness,
+    ) -> Result<FpVar<Fr>, SynthesisError> {
         let mut previous_hash = leaf.clone();
 
-        for (p_left_hash, p_right_hash) in self.path.iter() {
-            let previous_is_left = previous_hash.is_eq(p_left_hash)?;
+        let cs = leaf.cs();
+        for (level, ((p_left_hash, p_right_hash), previous_is_right)) in
+            self.path.iter().zip(index_bits).enumerate()
+        {
+            let _level_region = debug::region(&cs, || format!("level_{}", level));
 
             let left_hash =
-                FpVar::conditionally_select(&previous_is_left, &previous_hash, p_left_hash)?;
+                FpVar::conditionally_select(previous_is_right, p_left_hash, &previous_hash)?;
             let right_hash =
-                FpVar::conditionally_select(&previous_is_left, p_right_hash, &previous_hash)?;
+                FpVar::conditionally_select(previous_is_right, &previous_hash, p_right_hash)?;
 
-            previous_hash = hasher.hash2(&left_hash, &right_hash)?;
+            previous_hash = hasher.hash_with(&[left_hash, right_hash], witness)?;
         }
 
         Ok(previous_hash)
     }
+
+    /// The witness cache key of this path, if the cache is on and every value is assigned
+    #[cfg(feature = "std")]
+    fn cache_key(
+        &self,
+        leaf: &FpVar<Fr>,
+        index_bits: &[Boolean<Fr>],
+        hasher: &PoseidonOptimizedVar,
+    ) -> Option<crate::witness_cache::PathKey> {
+        if !crate::witness_cache::is_enabled() {
+            return None;
+        }
+        let bits: Vec<bool> = index_bits
+            .iter()
+            .map(|bit| bit.value().ok())
+            .collect::<Option<_>>()?;
+        let path: Vec<(Fr, Fr)> = self
+            .path
+            .iter()
+            .map(|(left, right)| Some((left.value().ok()?, right.value().ok()?)))
+            .collect::<Option<_>>()?;
+        Some(crate::witness_cache::path_key(
+            hasher.width(),
+            leaf.value().ok()?,
+            &bits,
+            &path,
+        ))
+    }
 }
 
 impl<const N: usize> AllocVar<Path<N>, Fr> for PathVar<N> {
@@ -459,27 +772,39 @@ impl<const N: usize> AllocVar<Path<N>, Fr> for PathVar<N> {
         let ns = cs.into();
         let cs = ns.cs();
 
-        let mut path = Vec::new();
-        let path_obj = f()?;
-        for (l, r) in &path_obj.borrow().path {
-            let l_hash =
-                FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "l_child"), || Ok(*l), mode)?;
-            let r_hash =
-                FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "r_child"), || Ok(*r), mode)?;
-            path.push((l_hash, r_hash));
-        }
-
+        let path = f()?;
+        let path = path.borrow();
         Ok(PathVar {
-            path: path.try_into().unwrap_or_else(
-                #[allow(clippy::type_complexity)]
-                |v: Vec<(FpVar<Fr>, FpVar<Fr>)>| {
-                    panic!("Expected path of length {}, got {}", N, v.len())
-                },
-            ),
+            path: try_array_from_fn(|level| {
+                let (l, r) = path.path[level];
+                let l_hash =
+                    FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "l_child"), || Ok(l), mode)?;
+                let r_hash =
+                    FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "r_child"), || Ok(r), mode)?;
+                Ok((l_hash, r_hash))
+            })?,
         })
     }
 }
 
+/// `core::array::from_fn` for a fallible `f`, building the array in place and stopping at
+/// the first error
+pub(crate) fn try_array_from_fn<T, E, const N: usize>(
+    mut f: impl FnMut(usize) -> Result<T, E>,
+) -> Result<[T; N], E> {
+    let mut error = None;
+    let array: [Option<T>; N] = core::array::from_fn(|i| {
+        if error.is_some() {
+            return None;
+        }
+        f(i).map_err(|e| error = Some(e)).ok()
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(array.map(|x| x.expect("every element is built when no error occurs"))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -495,6 +820,50 @@ mod tests {
     use ark_r1cs_std::R1CSVar;
     use ark_relations::r1cs::ConstraintSystem;
 
+    #[test]
+    fn test_root_consistency() {
+        let hasher = PoseidonOptimized::new_t3();
+        let chain_leaves: Vec<Fr> = (1..=8u64).map(Fr::from).collect();
+        let mut chain = SparseMerkleTree::<4>::new_empty(&hasher, &zero_value());
+        chain.bulk_insert(&chain_leaves, &hasher).unwrap();
+        let check = |local: &SparseMerkleTree<4>, history: &[Fr]| {
+            check_root_consistency(local, &chain.root(), 8, history, &hasher)
+        };
+
+        let report = check(&chain, &[]);
+        assert_eq!(report.status, RootStatus::Consistent);
+        assert!(!report.resync_recommended);
+
+        let mut behind = SparseMerkleTree::<4>::new_empty(&hasher, &zero_value());
+        behind.bulk_insert(&chain_leaves[..4], &hasher).unwrap();
+        assert_eq!(check(&behind, &[]).status, RootStatus::Behind);
+        assert_eq!(check(&behind, &chain_leaves).status, RootStatus::Behind);
+
+        // A fork at leaf 3: undetectable from the counts alone, pinpointed with history
+        let mut forked_leaves = chain_leaves[..6].to_vec();
+        forked_leaves[3] = Fr::from(99u64);
+        let mut forked = SparseMerkleTree::<4>::new_empty(&hasher, &zero_value());
+        forked.bulk_insert(&forked_leaves, &hasher).unwrap();
+        assert_eq!(check(&forked, &[]).status, RootStatus::Behind);
+        let report = check(&forked, &chain_leaves);
+        assert_eq!(report.status, RootStatus::Diverged);
+        assert_eq!(report.first_mismatch, Some(3));
+        assert!(report.resync_recommended);
+
+        // Only the replayed root can expose a fork in leaves the caller has no history for
+        let mut wrong_history = chain_leaves.clone();
+        wrong_history[6] = Fr::from(99u64);
+        assert_eq!(check(&behind, &wrong_history).status, RootStatus::Diverged);
+
+        let mut ahead = chain.clone();
+        ahead
+            .insert_pair(Fr::from(9u64), Fr::from(10u64), &hasher)
+            .unwrap();
+        let report = check(&ahead, &[]);
+        assert_eq!(report.status, RootStatus::Diverged);
+        assert_eq!(report.first_mismatch, Some(8));
+    }
+
     #[test]
     fn test_path_verification_matches_circuit() {
         let hasher = PoseidonOptimized::new_t3();
@@ -520,11 +889,19 @@ mod tests {
         let leaf_var = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
         let path_var = PathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
         let hasher_var = PoseidonOptimizedVar::new_t3();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::ZERO)).unwrap();
+        let index_bits = PathVar::<4>::index_bits(&index_var).unwrap();
 
-        let circuit_root = path_var.root_hash(&leaf_var, &hasher_var).unwrap();
+        let circuit_root = path_var
+            .root_hash(&leaf_var, &index_bits, &hasher_var)
+            .unwrap();
         circuit_root.enforce_equal(&root_var).unwrap();
 
         assert!(cs.is_satisfied().unwrap());
+        assert!(matches!(
+            path_var.root_hash(&leaf_var, &index_bits[..3], &hasher_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
         println!("✓ Path verification matches circuit");
     }
 
@@ -571,6 +948,55 @@ mod tests {
         println!("✓ Bulk insert successful");
     }
 
+    #[test]
+    fn test_path_from_leaves_matches_stateful_tree() {
+        let hasher = PoseidonOptimized::new_t3();
+        let empty_leaf = zero_value();
+        let leaves: Vec<Fr> = (1..=6u64).map(|i| Fr::from(i * 10)).collect();
+
+        let mut tree = SparseMerkleTree::<4>::new_empty(&hasher, &empty_leaf);
+        tree.bulk_insert(&leaves, &hasher).unwrap();
+
+        let (root, index, path) =
+            SparseMerkleTree::<4>::path_from_leaves(&leaves, &leaves[3], &empty_leaf).unwrap();
+        assert_eq!(root, tree.root());
+        assert_eq!(index, 3);
+        assert!(tree.verify_path(index, &path).unwrap());
+
+        assert!(
+            SparseMerkleTree::<4>::path_from_leaves(&leaves, &Fr::from(7u64), &empty_leaf).is_err()
+        );
+        assert!(
+            SparseMerkleTree::<4>::path_from_leaves(&leaves[..5], &leaves[0], &empty_leaf).is_err()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_restores_without_rehashing() {
+        let hasher = PoseidonOptimized::new_t3();
+        let leaves: Vec<Fr> = (1..=6u64).map(|i| Fr::from(i * 10)).collect();
+        let mut tree = SparseMerkleTree::<4>::new_empty(&hasher, &zero_value());
+        tree.bulk_insert(&leaves, &hasher).unwrap();
+
+        let snapshot = tree.to_snapshot();
+        let mut restored = SparseMerkleTree::<4>::from_snapshot(&snapshot, &hasher).unwrap();
+        assert_eq!(restored, tree);
+
+        // Later insertions use the restored subtrees
+        tree.insert_pair(Fr::from(70u64), Fr::from(80u64), &hasher)
+            .unwrap();
+        restored
+            .insert_pair(Fr::from(70u64), Fr::from(80u64), &hasher)
+            .unwrap();
+        assert_eq!(restored.root(), tree.root());
+
+        assert!(SparseMerkleTree::<5>::from_snapshot(&snapshot, &hasher).is_err());
+        assert!(
+            SparseMerkleTree::<4>::from_snapshot(&snapshot[..snapshot.len() - 1], &hasher).is_err()
+        );
+        assert!(SparseMerkleTree::<4>::from_snapshot(&snapshot[6..], &hasher).is_err());
+    }
+
     #[test]
     fn test_path_var_constraint_generation() {
         let cs = ConstraintSystem::<Fr>::new_ref();
@@ -588,8 +1014,9 @@ mod tests {
         let path_var = PathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
         let hasher_var = PoseidonOptimizedVar::new_t3();
 
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::ZERO)).unwrap();
         let is_member = path_var
-            .check_membership(&root_var, &leaf_var, &hasher_var)
+            .check_membership(&root_var, &leaf_var, &index_var, &hasher_var)
             .unwrap();
 
         assert!(is_member.value().unwrap());
@@ -657,9 +1084,50 @@ mod tests {
                 index
             );
             assert!(path.check_membership(&root, leaf, &hasher).unwrap());
+            assert!(path
+                .check_membership_at(&root, leaf, &Fr::from(index as u64), &hasher)
+                .unwrap());
         }
     }
 
+    #[test]
+    fn test_index_bits_handle_duplicate_siblings() {
+        let hasher = PoseidonOptimized::new_t3();
+        let empty_leaf = zero_value();
+
+        // Both pairs hash the same, so at level 1 the running hash equals its left sibling
+        let leaf_pairs = vec![(Fr::from(7u64), Fr::from(7u64)); 2];
+        let tree = SparseMerkleTree::<4>::new(&leaf_pairs, &hasher, &empty_leaf).unwrap();
+        let root = tree.root();
+        let path = tree.generate_membership_proof(3).unwrap();
+        let leaf = tree.leaves()[3];
+
+        // Value comparison reads the note as leaf 0; the index bits place it correctly
+        assert_eq!(path.get_index(&root, &leaf, &hasher).unwrap(), Fr::ZERO);
+        assert!(path
+            .check_membership_at(&root, &leaf, &Fr::from(3u64), &hasher)
+            .unwrap());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+        let leaf_var = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+        let path_var = PathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
+        let hasher_var = PoseidonOptimizedVar::new_t3();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(3u64))).unwrap();
+        path_var
+            .check_membership(&root_var, &leaf_var, &index_var, &hasher_var)
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // An index beyond the tree cannot be decomposed into its direction bits
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(16u64))).unwrap();
+        PathVar::<4>::index_bits(&index_var).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
     /// Reference Move-style implementation for testing
     fn move_style_root<const N: usize>(
         leaf_pairs: &[(Fr, Fr)],
@@ -690,7 +1158,7 @@ mod tests {
 
             for i in 1..N {
                 let subtree = &mut subtrees[i];
-                let (left, right) = if current_index % 2 == 0 {
+                let (left, right) = if current_index.is_multiple_of(2) {
                     *subtree = current_level_hash;
                     (current_level_hash, empty_subtree_hashes[i])
                 } else {
@@ -754,7 +1222,11 @@ mod tests {
         let path_var = PathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
         let hasher_var = PoseidonOptimizedVar::new_t3();
 
-        let computed_root_var = path_var.root_hash(&leaf_var, &hasher_var).unwrap();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+        let index_bits = PathVar::<4>::index_bits(&index_var).unwrap();
+        let computed_root_var = path_var
+            .root_hash(&leaf_var, &index_bits, &hasher_var)
+            .unwrap();
         computed_root_var.enforce_equal(&root_var).unwrap();
 
         assert!(cs.is_satisfied().unwrap());
@@ -0,0 +1,267 @@
+//! Optional proving metrics for fleet monitoring (`metrics` feature).
+//!
+//! With the feature, every proof bumps a counter and adds its phase durations and the
+//! process's resident memory to histograms; the host drains the accumulated values with
+//! `drain()` (`drain_metrics` over uniffi, `drainMetrics` in wasm) on its own schedule and
+//! forwards them to whatever backend it uses. Draining resets everything, so each snapshot
+//! covers the proofs since the previous one. Nothing identifying a user or a transaction is
+//! recorded.
+//!
+//! Without the feature the recording functions compile to nothing.
+
+#[cfg(feature = "metrics")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "metrics")]
+use std::collections::BTreeMap;
+#[cfg(feature = "metrics")]
+use std::sync::Mutex;
+
+use crate::telemetry::ProofStats;
+
+/// Proofs that produced an output
+pub const PROOFS_GENERATED: &str = "proofs_generated";
+/// Proofs that failed, for any reason
+pub const PROOFS_FAILED: &str = "proofs_failed";
+
+/// Buckets are powers of two, enough for any `u64`
+#[cfg(feature = "metrics")]
+const BUCKETS: usize = 65;
+
+/// A counter's increase since the last drain
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct CounterValue {
+    pub name: String,
+    pub value: u64,
+}
+
+/// Values whose bucket's upper bound is `upper_bound`, inclusive
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    pub upper_bound: u64,
+    pub count: u64,
+}
+
+/// Distribution of the values recorded since the last drain, in microseconds for `_us`
+/// names and bytes for `_bytes` names
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramValue {
+    pub name: String,
+    pub count: u64,
+    pub sum: u64,
+    pub min: u64,
+    pub max: u64,
+    /// Non-empty power-of-two buckets, in increasing order
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// Everything recorded since the last drain
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    pub counters: Vec<CounterValue>,
+    pub histograms: Vec<HistogramValue>,
+}
+
+#[cfg(feature = "metrics")]
+struct Histogram {
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+    buckets: [u64; BUCKETS],
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+            buckets: [0; BUCKETS],
+        }
+    }
+
+    fn record(&mut self, value: u64) {
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        // Bucket i holds values up to 2^i: 0 and 1 in bucket 0, 2 in 1, 3 and 4 in 2, ...
+        let bucket = (u64::BITS - value.saturating_sub(1).leading_zeros()) as usize;
+        self.buckets[bucket] += 1;
+    }
+
+    fn snapshot(&self, name: &str) -> HistogramValue {
+        HistogramValue {
+            name: name.to_string(),
+            count: self.count,
+            sum: self.sum,
+            min: self.min,
+            max: self.max,
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(|(bucket, count)| HistogramBucket {
+                    upper_bound: 1u64.checked_shl(bucket as u32).unwrap_or(u64::MAX),
+                    count: *count,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<&'static str, u64>,
+    histograms: BTreeMap<&'static str, Histogram>,
+}
+
+#[cfg(feature = "metrics")]
+impl Registry {
+    fn increment(&mut self, name: &'static str) {
+        *self.counters.entry(name).or_default() += 1;
+    }
+
+    fn record(&mut self, name: &'static str, value: u64) {
+        self.histograms
+            .entry(name)
+            .or_insert_with(Histogram::new)
+            .record(value);
+    }
+}
+
+#[cfg(feature = "metrics")]
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+#[cfg(feature = "metrics")]
+fn with_registry(f: impl FnOnce(&mut Registry)) {
+    f(REGISTRY
+        .lock()
+        .unwrap()
+        .get_or_insert_with(Registry::default));
+}
+
+/// Counts a proof and records its phase durations and the resident memory after it
+pub fn proof_finished(stats: &ProofStats) {
+    #[cfg(feature = "metrics")]
+    {
+        let resident_bytes = resident_memory();
+        with_registry(|registry| {
+            registry.increment(PROOFS_GENERATED);
+            registry.record("proof_total_us", stats.total_us);
+            registry.record("proof_parse_us", stats.parse_us);
+            registry.record("proof_witness_us", stats.witness_us);
+            registry.record("proof_constraints_us", stats.constraints_us);
+            registry.record("proof_prove_us", stats.prove_us);
+            registry.record("proof_serialize_us", stats.serialize_us);
+            if let Some(resident_bytes) = resident_bytes {
+                registry.record("proof_resident_bytes", resident_bytes);
+            }
+        });
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = stats;
+}
+
+/// Counts a failed proof
+pub fn proof_failed() {
+    #[cfg(feature = "metrics")]
+    with_registry(|registry| registry.increment(PROOFS_FAILED));
+}
+
+/// Returns everything recorded since the last drain and starts over
+#[cfg(feature = "metrics")]
+pub fn drain() -> MetricsSnapshot {
+    let Some(registry) = REGISTRY.lock().unwrap().take() else {
+        return MetricsSnapshot::default();
+    };
+    MetricsSnapshot {
+        counters: registry
+            .counters
+            .iter()
+            .map(|(name, value)| CounterValue {
+                name: name.to_string(),
+                value: *value,
+            })
+            .collect(),
+        histograms: registry
+            .histograms
+            .iter()
+            .map(|(name, histogram)| histogram.snapshot(name))
+            .collect(),
+    }
+}
+
+/// `VmRSS` of this process on Linux and Android; `None` elsewhere
+#[cfg(feature = "metrics")]
+fn resident_memory() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| crate::health::kib_field_bytes(&status, "VmRSS"))
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_powers_of_two() {
+        let mut histogram = Histogram::new();
+        for value in [0, 1, 2, 3, 1000, u64::MAX] {
+            histogram.record(value);
+        }
+        let snapshot = histogram.snapshot("h");
+        assert_eq!(
+            (snapshot.count, snapshot.min, snapshot.max),
+            (6, 0, u64::MAX)
+        );
+        let buckets: Vec<(u64, u64)> = snapshot
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.upper_bound, bucket.count))
+            .collect();
+        assert_eq!(buckets, [(1, 2), (2, 1), (4, 1), (1024, 1), (u64::MAX, 1)]);
+    }
+
+    #[test]
+    fn test_drain_reports_and_resets() {
+        // Proofs of other tests may land in the same snapshot, so only lower bounds hold
+        drain();
+        proof_finished(&ProofStats {
+            total_us: 1000,
+            ..ProofStats::default()
+        });
+        proof_failed();
+
+        let snapshot = drain();
+        let counter = |name: &str| {
+            snapshot
+                .counters
+                .iter()
+                .find(|counter| counter.name == name)
+                .map_or(0, |counter| counter.value)
+        };
+        assert!(counter(PROOFS_GENERATED) >= 1);
+        assert!(counter(PROOFS_FAILED) >= 1);
+        assert!(snapshot
+            .histograms
+            .iter()
+            .any(|histogram| histogram.name == "proof_total_us" && histogram.max >= 1000));
+    }
+}
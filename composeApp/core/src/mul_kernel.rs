@@ -0,0 +1,277 @@
+//! Interleaved kernels for the pointwise BN254 scalar multiplications of the witness map.
+//!
+//! Only the witness map in `prover` uses them: the `a * b` product and the coset shifts
+//! around its FFTs. The MSMs of `finalize_proof`, which take most of the proving time, still
+//! run on arkworks' `VariableBaseMSM`, so the kernels only shorten the witness-map part of a
+//! proof. arkworks multiplies one element at a time, and on the in-order and narrow
+//! out-of-order cores of many phones a chain of dependent `mul`/`umulh` pairs can leave the
+//! multiplier idle. The interleaved kernels here multiply two or four independent pairs in
+//! lock step so their limb products overlap. They are portable Rust over `u64` limbs, the
+//! same on every target; there is no architecture-specific code and no CPU feature detection.
+//!
+//! Which kernel wins depends on the core, so `report` times each one on this device the first
+//! time it is needed and keeps the fastest. The multiplied values are witness data, so the
+//! kernels take the same path for every input.
+
+use ark_bn254::{Fr, FrConfig};
+use ark_ff::{BigInt, Field, Fp, MontConfig, PrimeField};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use web_time::Instant;
+
+/// Elements multiplied per benchmark round
+const BENCHMARK_LEN: usize = 2048;
+/// Benchmark rounds per kernel; the fastest one is kept to filter out scheduler noise
+const BENCHMARK_ROUNDS: usize = 3;
+
+const MODULUS: [u64; 4] = <Fr as PrimeField>::MODULUS.0;
+const INV: u64 = <FrConfig as MontConfig<4>>::INV;
+
+/// Batched multiplication kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum MulKernel {
+    /// One arkworks multiplication at a time
+    Arkworks,
+    /// Two independent Montgomery multiplications interleaved
+    Interleaved2,
+    /// Four independent Montgomery multiplications interleaved
+    Interleaved4,
+}
+
+impl MulKernel {
+    pub const ALL: [MulKernel; 3] = [
+        MulKernel::Arkworks,
+        MulKernel::Interleaved2,
+        MulKernel::Interleaved4,
+    ];
+
+    fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value.checked_sub(1)? as usize).copied()
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8 + 1
+    }
+}
+
+/// Benchmark result of one kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct KernelTiming {
+    pub kernel: MulKernel,
+    /// Nanoseconds per thousand multiplications
+    pub ns_per_thousand: u64,
+}
+
+/// Which kernel the benchmark picked, and the timings it picked it by
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct MulKernelReport {
+    /// Architecture the timings were taken on
+    pub arch: String,
+    /// Fastest kernel on this device
+    pub selected: MulKernel,
+    pub timings: Vec<KernelTiming>,
+}
+
+static REPORT: OnceLock<MulKernelReport> = OnceLock::new();
+/// Kernel forced by the host, 0 for none
+static FORCED: AtomicU8 = AtomicU8::new(0);
+
+/// Benchmarks the kernels on first call and reports the fastest
+pub fn report() -> &'static MulKernelReport {
+    REPORT.get_or_init(|| {
+        let timings: Vec<KernelTiming> = MulKernel::ALL.into_iter().map(benchmark).collect();
+        let selected = timings
+            .iter()
+            .min_by_key(|timing| timing.ns_per_thousand)
+            .map_or(MulKernel::Arkworks, |timing| timing.kernel);
+        MulKernelReport {
+            arch: std::env::consts::ARCH.to_string(),
+            selected,
+            timings,
+        }
+    })
+}
+
+/// Uses `kernel` instead of the benchmarked one; `None` returns to the benchmark's choice
+pub fn force(kernel: Option<MulKernel>) {
+    FORCED.store(kernel.map_or(0, MulKernel::to_u8), Ordering::Relaxed);
+}
+
+/// Kernel in use: the forced one, else the benchmark's choice
+pub fn active() -> MulKernel {
+    MulKernel::from_u8(FORCED.load(Ordering::Relaxed)).unwrap_or_else(|| report().selected)
+}
+
+/// `a[i] *= b[i]` with the active kernel, over the shorter of the two slices
+pub fn mul_assign(a: &mut [Fr], b: &[Fr]) {
+    mul_assign_with(active(), a, b);
+}
+
+/// `a[i] *= b[i]` with `kernel`, over the shorter of the two slices
+pub fn mul_assign_with(kernel: MulKernel, a: &mut [Fr], b: &[Fr]) {
+    let len = a.len().min(b.len());
+    let (a, b) = (&mut a[..len], &b[..len]);
+    match kernel {
+        MulKernel::Arkworks => a.iter_mut().zip(b).for_each(|(x, y)| *x *= y),
+        MulKernel::Interleaved2 => interleaved::<2>(a, b),
+        MulKernel::Interleaved4 => interleaved::<4>(a, b),
+    }
+}
+
+fn interleaved<const L: usize>(a: &mut [Fr], b: &[Fr]) {
+    let mut a_chunks = a.chunks_exact_mut(L);
+    let mut b_chunks = b.chunks_exact(L);
+    for (a, b) in (&mut a_chunks).zip(&mut b_chunks) {
+        let products = montgomery_mul::<L>(
+            core::array::from_fn(|lane| a[lane].0 .0),
+            core::array::from_fn(|lane| b[lane].0 .0),
+        );
+        for (x, product) in a.iter_mut().zip(products) {
+            *x = Fp::new_unchecked(BigInt(product));
+        }
+    }
+    let tail = a_chunks.into_remainder();
+    tail.iter_mut()
+        .zip(b_chunks.remainder())
+        .for_each(|(x, y)| *x *= y);
+}
+
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: &mut u64) -> u64 {
+    let t = a as u128 + (b as u128) * (c as u128) + *carry as u128;
+    *carry = (t >> 64) as u64;
+    t as u64
+}
+
+/// CIOS Montgomery multiplication of `L` independent pairs, each loop body running across
+/// the lanes so their products are independent. The modulus leaves the top bit of its last
+/// limb clear, so the intermediate sum fits four limbs without a carry word.
+#[inline(always)]
+#[allow(clippy::needless_range_loop)]
+fn montgomery_mul<const L: usize>(a: [[u64; 4]; L], b: [[u64; 4]; L]) -> [[u64; 4]; L] {
+    let mut r = [[0u64; 4]; L];
+    for i in 0..4 {
+        let mut carry1 = [0u64; L];
+        let mut carry2 = [0u64; L];
+        let mut k = [0u64; L];
+        for lane in 0..L {
+            r[lane][0] = mac(r[lane][0], a[lane][0], b[lane][i], &mut carry1[lane]);
+            k[lane] = r[lane][0].wrapping_mul(INV);
+            mac(r[lane][0], k[lane], MODULUS[0], &mut carry2[lane]);
+        }
+        for j in 1..4 {
+            for lane in 0..L {
+                r[lane][j] = mac(r[lane][j], a[lane][j], b[lane][i], &mut carry1[lane]);
+                r[lane][j - 1] = mac(r[lane][j], k[lane], MODULUS[j], &mut carry2[lane]);
+            }
+        }
+        for lane in 0..L {
+            r[lane][3] = carry1[lane] + carry2[lane];
+        }
+    }
+    for r in r.iter_mut() {
+        subtract_modulus(r);
+    }
+    r
+}
+
+/// `r - modulus` if that does not borrow, else `r`, chosen with a mask rather than a branch
+#[inline(always)]
+fn subtract_modulus(r: &mut [u64; 4]) {
+    let mut reduced = [0u64; 4];
+    let mut borrow = 0u64;
+    for j in 0..4 {
+        let (diff, b1) = r[j].overflowing_sub(MODULUS[j]);
+        let (diff, b2) = diff.overflowing_sub(borrow);
+        reduced[j] = diff;
+        borrow = (b1 | b2) as u64;
+    }
+    // All ones when the subtraction borrowed, i.e. `r` was already below the modulus
+    let keep = borrow.wrapping_neg();
+    for j in 0..4 {
+        r[j] = (r[j] & keep) | (reduced[j] & !keep);
+    }
+}
+
+fn benchmark(kernel: MulKernel) -> KernelTiming {
+    // Any fixed non-trivial values do; the kernels take the same time for every input
+    let b: Vec<Fr> = (0..BENCHMARK_LEN as u64)
+        .filter_map(|i| Fr::from(i + 3).inverse())
+        .collect();
+    let fastest_ns = (0..BENCHMARK_ROUNDS)
+        .map(|_| {
+            let mut a = b.clone();
+            let start = Instant::now();
+            mul_assign_with(kernel, &mut a, &b);
+            let elapsed = start.elapsed().as_nanos();
+            std::hint::black_box(&a);
+            elapsed
+        })
+        .min()
+        .unwrap_or_default();
+    KernelTiming {
+        kernel,
+        ns_per_thousand: (fastest_ns * 1000 / BENCHMARK_LEN as u128) as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{AdditiveGroup, UniformRand};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_kernels_match_arkworks() {
+        let mut rng = ChaCha20Rng::from_seed([4u8; 32]);
+        let mut a: Vec<Fr> = (0..67).map(|_| Fr::rand(&mut rng)).collect();
+        let mut b: Vec<Fr> = (0..67).map(|_| Fr::rand(&mut rng)).collect();
+        // Edge values around the modulus
+        a.extend([Fr::ZERO, Fr::ONE, -Fr::ONE, -Fr::ONE]);
+        b.extend([-Fr::ONE, -Fr::ONE, -Fr::ONE, Fr::from(2u64)]);
+        let expected: Vec<Fr> = a.iter().zip(&b).map(|(x, y)| x * y).collect();
+
+        for kernel in MulKernel::ALL {
+            let mut product = a.clone();
+            mul_assign_with(kernel, &mut product, &b);
+            assert_eq!(product, expected, "{:?}", kernel);
+        }
+    }
+
+    #[test]
+    fn test_subtract_modulus_reduces_only_values_above_it() {
+        let mut below = MODULUS;
+        below[0] -= 1;
+        let mut r = below;
+        subtract_modulus(&mut r);
+        assert_eq!(r, below);
+
+        let mut above = MODULUS;
+        above[0] += 5;
+        subtract_modulus(&mut above);
+        assert_eq!(above, [5, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_report_selects_the_fastest_kernel() {
+        let report = report();
+        let fastest = report
+            .timings
+            .iter()
+            .min_by_key(|timing| timing.ns_per_thousand)
+            .unwrap();
+        assert_eq!(report.selected, fastest.kernel);
+        assert_eq!(
+            report.timings.iter().map(|t| t.kernel).collect::<Vec<_>>(),
+            MulKernel::ALL
+        );
+    }
+}
@@ -0,0 +1,465 @@
+//! Paying several recipients with 2-in-2-out transactions.
+//!
+//! A transaction spends `N_INS` notes and creates `N_OUTS`, so a payment to many recipients
+//! is a chain: each step pays what it can and sends its change back to the sender, and the
+//! next step spends that change together with a fresh note. `plan_multi_send` picks the
+//! fewest notes covering the total (largest first) and walks the recipients in order, paying
+//! up to `N_OUTS` of them per step and keeping one output for change whenever some is left;
+//! a step pays nobody when the next recipient needs more than it holds, which merges notes
+//! instead. Nothing in the planner assumes two slots, so a batched circuit only changes the
+//! constants.
+//!
+//! `build_multi_send` turns a plan into provable `ProofInput`s. Change notes of earlier steps
+//! do not exist on chain yet, so it replays every step's output pair onto a copy of the tree
+//! to find their leaf indices and paths. The inputs are valid only if the steps land in order
+//! with no other transaction in between; when the tree has moved on, plan the remaining
+//! payments again from the synced notes.
+
+use crate::{
+    account::AccountSecret,
+    address::ShieldedAddress,
+    constants::{MERKLE_TREE_LEVEL, N_INS, N_OUTS},
+    field::{parse_fr, FieldParseMode},
+    input_builder::ProofInputBuilder,
+    interop::ProofInput,
+    merkle_tree::SparseMerkleTree,
+    note::{create_output_for, PreparedOutput, Utxo},
+    poseidon_opt::PoseidonOptimized,
+    relayer::{self, ExtData, RelayerFeeQuote},
+    viewing::SpendingKeys,
+};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// One payment of a multi-recipient send
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct Recipient {
+    /// Shielded address to pay
+    pub address: String,
+    /// Amount in MIST
+    pub amount: u64,
+}
+
+/// One transaction of a send plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct SendStep {
+    /// Indices into the wallet's notes spent by this step
+    pub notes: Vec<u32>,
+    /// Whether the step also spends the change of the step before it
+    pub spends_previous_change: bool,
+    /// Indices of the recipients paid, in output order
+    pub recipients: Vec<u32>,
+    /// Change returned to the sender, spent by the next step or kept after the last
+    pub change: u64,
+}
+
+/// Ordered transactions paying every recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct SendPlan {
+    pub steps: Vec<SendStep>,
+    /// Sum paid to the recipients
+    pub total: u64,
+    /// Change the sender holds after the last step
+    pub change: u64,
+}
+
+/// A planned step ready to prove
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedTransaction {
+    pub proof_input: ProofInput,
+    pub ext_data: ExtData,
+    /// Notes created, in output slot order
+    pub outputs: Vec<PreparedOutput>,
+    /// The step's change note at the leaf it gets if the steps land in order
+    pub change: Option<Utxo>,
+}
+
+/// Plans the transactions paying `recipients` from the unspent `notes`.
+///
+/// # Errors
+/// Returns error if there are no recipients, an amount is zero, or the unspent notes do not
+/// cover the total.
+pub fn plan_multi_send(notes: &[Utxo], recipients: &[Recipient]) -> Result<SendPlan> {
+    if recipients.is_empty() {
+        return Err(anyhow!("A send needs at least one recipient"));
+    }
+    if let Some(i) = recipients.iter().position(|r| r.amount == 0) {
+        return Err(anyhow!("Recipient {} is paid a zero amount", i));
+    }
+    let total: u128 = recipients.iter().map(|r| r.amount as u128).sum();
+    let total = u64::try_from(total).map_err(|_| anyhow!("Total payment exceeds u64"))?;
+
+    let mut unspent: Vec<u32> = (0..notes.len() as u32)
+        .filter(|&i| !notes[i as usize].metadata.spent)
+        .collect();
+    unspent.sort_by_key(|&i| std::cmp::Reverse(notes[i as usize].note.amount));
+    let mut selected = VecDeque::new();
+    let mut covered = 0u128;
+    for i in unspent {
+        if covered >= total as u128 {
+            break;
+        }
+        covered += notes[i as usize].note.amount as u128;
+        selected.push_back(i);
+    }
+    if covered < total as u128 {
+        return Err(anyhow!(
+            "Unspent notes hold {} MIST, the recipients are owed {}",
+            covered,
+            total
+        ));
+    }
+
+    let mut pending: VecDeque<u32> = (0..recipients.len() as u32).collect();
+    let mut steps: Vec<SendStep> = Vec::new();
+    let mut carry = 0u128;
+    while !pending.is_empty() {
+        let spends_previous_change = carry > 0;
+        let mut step_notes = Vec::new();
+        let mut available = carry;
+        while step_notes.len() + (spends_previous_change as usize) < N_INS {
+            let Some(i) = selected.pop_front() else {
+                break;
+            };
+            available += notes[i as usize].note.amount as u128;
+            step_notes.push(i);
+        }
+
+        let mut paid = Vec::new();
+        let mut spent = 0u128;
+        while paid.len() < N_OUTS {
+            match pending.front() {
+                Some(&r) if spent + recipients[r as usize].amount as u128 <= available => {
+                    spent += recipients[r as usize].amount as u128;
+                    paid.push(pending.pop_front().unwrap());
+                }
+                _ => break,
+            }
+        }
+        // Leftover funds need an output of their own
+        if spent < available && paid.len() == N_OUTS {
+            let r = paid.pop().unwrap();
+            spent -= recipients[r as usize].amount as u128;
+            pending.push_front(r);
+        }
+        if paid.is_empty() && step_notes.is_empty() {
+            return Err(anyhow!("Send plan cannot make progress"));
+        }
+
+        carry = available - spent;
+        steps.push(SendStep {
+            notes: step_notes,
+            spends_previous_change,
+            recipients: paid,
+            change: carry as u64,
+        });
+    }
+
+    Ok(SendPlan {
+        steps,
+        total,
+        change: carry as u64,
+    })
+}
+
+/// Builds the proof inputs of every step of `plan`, spending with `keys`.
+///
+/// `tree` is the synced commitment tree holding every note the plan spends. Empty output
+/// slots get zero-amount notes to the sender so every output carries a ciphertext.
+///
+/// # Errors
+/// Returns error if the plan does not match `notes` and `recipients`, a note is not in
+/// `tree`, or an address is invalid.
+#[allow(clippy::too_many_arguments)]
+pub fn build_multi_send<R: RngCore + CryptoRng>(
+    plan: &SendPlan,
+    notes: &[Utxo],
+    recipients: &[Recipient],
+    keys: &SpendingKeys,
+    tree: &SparseMerkleTree<MERKLE_TREE_LEVEL>,
+    vortex: &Fr,
+    account_secret: Option<&AccountSecret>,
+    rng: &mut R,
+) -> Result<Vec<PlannedTransaction>> {
+    build_steps(
+        &plan.steps,
+        notes,
+        recipients,
+        keys,
+        tree,
+        vortex,
+        None,
+        account_secret,
+        rng,
+    )
+}
+
+/// Builds chained `steps`, each paying `relayer` its quoted fee out of the step's change
+/// when given
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn build_steps<R: RngCore + CryptoRng>(
+    steps: &[SendStep],
+    notes: &[Utxo],
+    recipients: &[Recipient],
+    keys: &SpendingKeys,
+    tree: &SparseMerkleTree<MERKLE_TREE_LEVEL>,
+    vortex: &Fr,
+    relayer: Option<&RelayerFeeQuote>,
+    account_secret: Option<&AccountSecret>,
+    rng: &mut R,
+) -> Result<Vec<PlannedTransaction>> {
+    let hasher = PoseidonOptimized::new_t3();
+    let mut tree = tree.clone();
+    let sender = ShieldedAddress::from_viewing_key(&keys.viewing).encode();
+    let fee = relayer.map_or(0, |quote| quote.fee);
+    let mut transactions: Vec<PlannedTransaction> = Vec::with_capacity(steps.len());
+
+    for (s, step) in steps.iter().enumerate() {
+        let mut spends: Vec<Utxo> = Vec::with_capacity(N_INS);
+        if step.spends_previous_change {
+            let change = transactions
+                .last()
+                .and_then(|previous| previous.change.clone())
+                .ok_or_else(|| anyhow!("Step {} spends change the step before lacks", s))?;
+            spends.push(change);
+        }
+        for &i in &step.notes {
+            let note = notes
+                .get(i as usize)
+                .ok_or_else(|| anyhow!("Step {} spends unknown note {}", s, i))?;
+            spends.push(note.clone());
+        }
+
+        let mut builder = ProofInputBuilder::new().with_vortex(*vortex);
+        for utxo in &spends {
+            let index = utxo.metadata.leaf_index as usize;
+            let commitment = parse_fr(&utxo.commitment, FieldParseMode::Strict)
+                .context("Invalid note commitment")?;
+            if tree.leaves.get(index) != Some(&commitment) {
+                return Err(anyhow!(
+                    "Note {} is not at leaf {} of the tree",
+                    utxo.commitment,
+                    index
+                ));
+            }
+            let path = tree.generate_membership_proof(index)?;
+            builder = builder.with_input(utxo.spend_input(keys.spending_key(), path)?);
+        }
+
+        let mut outputs = Vec::with_capacity(N_OUTS);
+        for &r in &step.recipients {
+            let recipient = recipients
+                .get(r as usize)
+                .ok_or_else(|| anyhow!("Step {} pays unknown recipient {}", s, r))?;
+            outputs.push(create_output_for(
+                &recipient.address,
+                recipient.amount,
+                vortex,
+                &keys.viewing,
+                None,
+                rng,
+            )?);
+        }
+        if outputs.len() + (step.change > 0) as usize > N_OUTS {
+            return Err(anyhow!("Step {} has more than {} outputs", s, N_OUTS));
+        }
+        let change_slot = (step.change > 0).then_some(outputs.len());
+        while outputs.len() < N_OUTS {
+            let amount = if Some(outputs.len()) == change_slot {
+                step.change
+            } else {
+                0
+            };
+            outputs.push(create_output_for(
+                &sender,
+                amount,
+                vortex,
+                &keys.viewing,
+                None,
+                rng,
+            )?);
+        }
+
+        let ext_data = ExtData {
+            value: fee,
+            is_deposit: false,
+            relayer: relayer
+                .map_or_else(|| "0x0".to_string(), |quote| quote.relayer_address.clone()),
+            relayer_fee: fee,
+            encrypted_output_0: hex::decode(&outputs[0].encrypted_output)?,
+            encrypted_output_1: hex::decode(&outputs[1].encrypted_output)?,
+        };
+        for output in &outputs {
+            builder = builder.with_output(
+                parse_fr(&output.note.public_key, FieldParseMode::Strict)?,
+                output.note.amount,
+                parse_fr(&output.note.blinding, FieldParseMode::Strict)?,
+            );
+        }
+        if let Some(secret) = account_secret {
+            builder = builder.with_account_secret(secret.clone());
+        }
+        let proof_input = builder
+            .with_ext_data_hash(relayer::ext_data_hash(&ext_data)?)
+            .build(rng)
+            .with_context(|| format!("Failed to build step {}", s))?;
+
+        let first_leaf = tree.len() as u64;
+        let commitments = outputs
+            .iter()
+            .map(|output| parse_fr(&output.commitment, FieldParseMode::Strict))
+            .collect::<Result<Vec<_>>>()?;
+        tree.insert_pair(commitments[0], commitments[1], &hasher)?;
+        let change = change_slot
+            .map(|slot| Utxo::new(outputs[slot].note.clone(), first_leaf + slot as u64, vortex))
+            .transpose()?;
+
+        transactions.push(PlannedTransaction {
+            proof_input,
+            ext_data,
+            outputs,
+            change,
+        });
+    }
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ZERO_VALUE;
+    use crate::debug::find_unsatisfied;
+    use crate::note::Note;
+    use crate::poseidon_opt::fr_from_str;
+    use crate::replay::circuit_from_input;
+    use ark_ff::PrimeField;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn recipient(seed: u8, amount: u64) -> Recipient {
+        let keys = SpendingKeys::from_seed(&[seed; 32]).unwrap();
+        Recipient {
+            address: ShieldedAddress::from_viewing_key(&keys.viewing).encode(),
+            amount,
+        }
+    }
+
+    fn wallet(amounts: &[u64], vortex: &Fr) -> (SpendingKeys, Vec<Utxo>) {
+        let keys = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+        let notes = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| {
+                let note = Note {
+                    amount,
+                    public_key: keys.viewing.spend_public_key.into_bigint().to_string(),
+                    blinding: (100 + i).to_string(),
+                    legacy_commitment: false,
+                };
+                Utxo::new(note, i as u64, vortex).unwrap()
+            })
+            .collect();
+        (keys, notes)
+    }
+
+    #[test]
+    fn test_plan_chains_change_through_the_steps() {
+        let vortex = Fr::from(5u64);
+        let (_, notes) = wallet(&[100, 40, 300, 10], &vortex);
+        let recipients = [recipient(2, 120), recipient(3, 150), recipient(4, 60)];
+
+        let plan = plan_multi_send(&notes, &recipients).unwrap();
+        assert_eq!(plan.total, 330);
+        assert_eq!(plan.change, 70);
+        assert_eq!(
+            plan.steps,
+            [
+                SendStep {
+                    notes: vec![2, 0],
+                    spends_previous_change: false,
+                    recipients: vec![0],
+                    change: 280,
+                },
+                SendStep {
+                    notes: vec![],
+                    spends_previous_change: true,
+                    recipients: vec![1],
+                    change: 130,
+                },
+                SendStep {
+                    notes: vec![],
+                    spends_previous_change: true,
+                    recipients: vec![2],
+                    change: 70,
+                },
+            ]
+        );
+
+        // Small notes are merged until the next recipient fits
+        let plan = plan_multi_send(&notes, &[recipient(2, 445)]).unwrap();
+        assert_eq!(plan.steps.len(), 3);
+        assert!(plan.steps[0].recipients.is_empty());
+        assert_eq!(plan.steps[1].notes, [1]);
+        assert_eq!(plan.change, 5);
+
+        // Spending everything leaves no change output, so one step pays two recipients
+        let plan = plan_multi_send(&notes[..2], &[recipient(2, 90), recipient(3, 50)]).unwrap();
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].recipients, [0, 1]);
+
+        assert!(plan_multi_send(&notes, &[recipient(2, 451)]).is_err());
+        assert!(plan_multi_send(&notes, &[recipient(2, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_built_steps_satisfy_the_circuit() {
+        let mut rng = ChaCha20Rng::from_seed([3u8; 32]);
+        let vortex = Fr::from(5u64);
+        let (keys, notes) = wallet(&[100, 40, 300, 10], &vortex);
+        let recipients = [recipient(2, 120), recipient(3, 150), recipient(4, 60)];
+        let plan = plan_multi_send(&notes, &recipients).unwrap();
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+        let leaves: Vec<Fr> = notes
+            .iter()
+            .map(|utxo| parse_fr(&utxo.commitment, FieldParseMode::Strict).unwrap())
+            .collect();
+        tree.bulk_insert(&leaves, &hasher).unwrap();
+
+        let transactions = build_multi_send(
+            &plan,
+            &notes,
+            &recipients,
+            &keys,
+            &tree,
+            &vortex,
+            None,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(
+            transactions[0].change.as_ref().unwrap().metadata.leaf_index,
+            5
+        );
+        assert_eq!(transactions[2].change.as_ref().unwrap().note.amount, 70);
+        for transaction in &transactions {
+            let circuit = circuit_from_input(&transaction.proof_input).unwrap();
+            circuit.check_public_inputs().unwrap();
+            assert!(find_unsatisfied(&circuit).unwrap().is_none());
+            assert_eq!(transaction.proof_input.public_amount, "0");
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Note records shared with the host apps.
+//!
+//! `Note` is what a note commits to, `NoteMetadata` is where it sits in the tree and
+//! whether it was spent, and `Utxo` pairs the two with the commitment. The note store,
+//! scanner and transaction builder on every platform pass these records instead of keeping
+//! their own copies of the fields, and `Utxo::spend_input` turns one into a circuit input.
+//!
+//! `create_output_for` goes the other way: it builds a new output paying a shielded address.
+
+use crate::{
+    address::ShieldedAddress,
+    circuit::{bind_ciphertext, compute_commitment, compute_legacy_commitment, compute_nullifier},
+    constants::MERKLE_TREE_LEVEL,
+    field::{parse_fr, FieldParseMode},
+    merkle_tree::Path,
+    padding::SpendInput,
+    viewing::{self, DecryptedNote, FullViewingKey, NotePlaintext},
+};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_std::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// The values a note commitment opens to
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    /// Amount in MIST
+    pub amount: u64,
+    /// Decimal note public key, `Poseidon1(privkey)`
+    pub public_key: String,
+    /// Decimal blinding
+    pub blinding: String,
+    /// Committed with the legacy `Poseidon3(amount, pubkey, blinding)` formula
+    #[serde(default)]
+    pub legacy_commitment: bool,
+}
+
+impl std::fmt::Debug for Note {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Note")
+            .field("amount", &self.amount)
+            .field("public_key", &self.public_key)
+            .field("blinding", &"<redacted>")
+            .field("legacy_commitment", &self.legacy_commitment)
+            .finish()
+    }
+}
+
+impl Note {
+    /// The note a decrypted output opens to under `public_key`
+    pub fn from_decrypted(decrypted: &DecryptedNote, public_key: &Fr) -> Self {
+        Self {
+            amount: decrypted.amount,
+            public_key: public_key.into_bigint().to_string(),
+            blinding: decrypted.blinding.clone(),
+            legacy_commitment: false,
+        }
+    }
+
+    /// Recomputes the commitment under the formula the note was created with
+    pub fn commitment(&self, vortex: &Fr) -> Result<Fr> {
+        let amount = Fr::from(self.amount);
+        let public_key = parse_fr(&self.public_key, FieldParseMode::Strict)
+            .context("Invalid note public key")?;
+        let blinding =
+            parse_fr(&self.blinding, FieldParseMode::Strict).context("Invalid note blinding")?;
+        Ok(if self.legacy_commitment {
+            compute_legacy_commitment(&amount, &public_key, &blinding)
+        } else {
+            compute_commitment(&amount, &public_key, &blinding, vortex)
+        })
+    }
+}
+
+/// Where a note sits in the commitment tree and whether it was spent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct NoteMetadata {
+    /// Leaf index, the circuit's `in_path_index`
+    pub leaf_index: u64,
+    #[serde(default)]
+    pub spent: bool,
+}
+
+/// A note owned by the wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct Utxo {
+    pub note: Note,
+    pub metadata: NoteMetadata,
+    /// Decimal commitment
+    pub commitment: String,
+}
+
+impl Utxo {
+    /// An unspent UTXO for `note` at `leaf_index`.
+    ///
+    /// # Errors
+    /// Returns error if the note's public key or blinding is not a field element, or the
+    /// leaf index does not fit the tree.
+    pub fn new(note: Note, leaf_index: u64, vortex: &Fr) -> Result<Self> {
+        if leaf_index >= 1u64 << MERKLE_TREE_LEVEL {
+            return Err(anyhow!(
+                "Leaf index {} exceeds tree capacity (>= 2^{})",
+                leaf_index,
+                MERKLE_TREE_LEVEL
+            ));
+        }
+        let commitment = note.commitment(vortex)?.into_bigint().to_string();
+        Ok(Self {
+            note,
+            metadata: NoteMetadata {
+                leaf_index,
+                spent: false,
+            },
+            commitment,
+        })
+    }
+
+    /// The nullifier published when the note is spent with `private_key`
+    pub fn nullifier(&self, private_key: &Fr) -> Result<Fr> {
+        let commitment =
+            parse_fr(&self.commitment, FieldParseMode::Strict).context("Invalid commitment")?;
+        Ok(compute_nullifier(
+            &commitment,
+            &Fr::from(self.metadata.leaf_index),
+            private_key,
+        ))
+    }
+
+    /// Circuit input spending this note with `private_key`.
+    ///
+    /// # Errors
+    /// Returns error if the note was already spent or its blinding is not a field element.
+    pub fn spend_input(
+        &self,
+        private_key: &Fr,
+        merkle_path: Path<MERKLE_TREE_LEVEL>,
+    ) -> Result<SpendInput> {
+        if self.metadata.spent {
+            return Err(anyhow!("Note {} is already spent", self.commitment));
+        }
+        Ok(SpendInput {
+            private_key: *private_key,
+            amount: Fr::from(self.note.amount),
+            blinding: parse_fr(&self.note.blinding, FieldParseMode::Strict)
+                .context("Invalid note blinding")?,
+            path_index: Fr::from(self.metadata.leaf_index),
+            merkle_path,
+            legacy_commitment: self.note.legacy_commitment,
+        })
+    }
+}
+
+/// A note whose `spent` flag disagrees with the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct NoteStatusChange {
+    /// Decimal commitment identifying the note
+    pub commitment: String,
+    pub leaf_index: u64,
+    /// The flag the note should have
+    pub spent: bool,
+    /// Decimal nullifier of the note
+    pub nullifier: String,
+}
+
+/// Derives the nullifier of every owned note and looks it up in `on_chain_nullifiers`,
+/// returning the notes whose `spent` flag is wrong, in the order given.
+///
+/// A note flagged spent whose nullifier is not on chain is reported as unspent, e.g. when
+/// the store marked it at submission and the transaction then failed, so
+/// `on_chain_nullifiers` must be the pool's complete set, as `SyncState` keeps it.
+///
+/// # Errors
+/// Returns error if a note's commitment is not a field element.
+pub fn mark_spent_notes(
+    owned_notes: &[Utxo],
+    on_chain_nullifiers: &HashSet<Fr>,
+    private_key: &Fr,
+) -> Result<Vec<NoteStatusChange>> {
+    let mut changes = Vec::new();
+    for utxo in owned_notes {
+        let nullifier = utxo.nullifier(private_key)?;
+        let spent = on_chain_nullifiers.contains(&nullifier);
+        if spent != utxo.metadata.spent {
+            changes.push(NoteStatusChange {
+                commitment: utxo.commitment.clone(),
+                leaf_index: utxo.metadata.leaf_index,
+                spent,
+                nullifier: nullifier.into_bigint().to_string(),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// A new output paying a shielded address, with everything the transaction needs
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedOutput {
+    /// The note the recipient receives
+    pub note: Note,
+    /// Decimal `output_commitment`
+    pub commitment: String,
+    /// Hex `encrypted_output` to publish with the transaction
+    pub encrypted_output: String,
+    /// Decimal `output_ciphertext_hash`, for circuits with ciphertext binding
+    pub ciphertext_hash: String,
+    /// Decimal `output_commitment` under ciphertext binding
+    pub bound_commitment: String,
+}
+
+impl std::fmt::Debug for PreparedOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreparedOutput")
+            .field("note", &self.note)
+            .field("commitment", &self.commitment)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builds an output of `amount` paying `address`, encrypted to its viewing key.
+///
+/// The blinding is drawn from `rng` unless one is given, e.g. from
+/// `viewing::derive_output_blinding`. A copy stays readable with `sender`'s outgoing key.
+///
+/// # Errors
+/// Returns error if the address does not decode or its viewing key cannot be encrypted to.
+pub fn create_output_for<R: RngCore + CryptoRng>(
+    address: &str,
+    amount: u64,
+    vortex: &Fr,
+    sender: &FullViewingKey,
+    blinding: Option<Fr>,
+    rng: &mut R,
+) -> Result<PreparedOutput> {
+    let address = ShieldedAddress::decode(address)?;
+    let plaintext = NotePlaintext {
+        amount,
+        blinding: blinding.unwrap_or_else(|| Fr::rand(rng)),
+    };
+    let encrypted_output =
+        viewing::encrypt_note(&plaintext, &address.viewing_public_key, sender, rng)?;
+    let ciphertext_hash = viewing::ciphertext_hash(&encrypted_output);
+
+    let commitment = plaintext.commitment(&address.note_public_key, vortex);
+    let bound_commitment = compute_commitment(
+        &Fr::from(amount),
+        &address.note_public_key,
+        &bind_ciphertext(&plaintext.blinding, &ciphertext_hash),
+        vortex,
+    );
+
+    Ok(PreparedOutput {
+        note: Note {
+            amount,
+            public_key: address.note_public_key.into_bigint().to_string(),
+            blinding: plaintext.blinding.into_bigint().to_string(),
+            legacy_commitment: false,
+        },
+        commitment: commitment.into_bigint().to_string(),
+        encrypted_output: hex::encode(&encrypted_output),
+        ciphertext_hash: ciphertext_hash.into_bigint().to_string(),
+        bound_commitment: bound_commitment.into_bigint().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon_opt::hash1;
+
+    #[test]
+    fn test_utxo_matches_its_spend_input() {
+        let private_key = Fr::from(12345u64);
+        let vortex = Fr::from(5u64);
+        let decrypted = DecryptedNote {
+            amount: 700,
+            blinding: "999".to_string(),
+            recipient_viewing_public_key: None,
+        };
+        let utxo = Utxo::new(
+            Note::from_decrypted(&decrypted, &hash1(&private_key)),
+            3,
+            &vortex,
+        )
+        .unwrap();
+
+        let input = utxo.spend_input(&private_key, Path::empty()).unwrap();
+        assert_eq!(
+            input.commitment(&vortex).into_bigint().to_string(),
+            utxo.commitment
+        );
+        assert_eq!(
+            input.nullifier(&vortex),
+            utxo.nullifier(&private_key).unwrap()
+        );
+
+        let mut spent = utxo.clone();
+        spent.metadata.spent = true;
+        assert!(spent.spend_input(&private_key, Path::empty()).is_err());
+        assert!(Utxo::new(utxo.note, 1u64 << MERKLE_TREE_LEVEL, &vortex).is_err());
+    }
+
+    #[test]
+    fn test_mark_spent_notes_reports_only_changed_flags() {
+        let private_key = Fr::from(12345u64);
+        let vortex = Fr::from(5u64);
+        let utxo = |leaf_index: u64, spent: bool| {
+            let decrypted = DecryptedNote {
+                amount: 100 + leaf_index,
+                blinding: (7 + leaf_index).to_string(),
+                recipient_viewing_public_key: None,
+            };
+            let mut utxo = Utxo::new(
+                Note::from_decrypted(&decrypted, &hash1(&private_key)),
+                leaf_index,
+                &vortex,
+            )
+            .unwrap();
+            utxo.metadata.spent = spent;
+            utxo
+        };
+        // Spent on chain and unflagged, spent and flagged, flagged but never spent, unspent
+        let owned = [utxo(0, false), utxo(1, true), utxo(2, true), utxo(3, false)];
+        let on_chain: HashSet<Fr> = [&owned[0], &owned[1]]
+            .iter()
+            .map(|utxo| utxo.nullifier(&private_key).unwrap())
+            .chain([Fr::from(42u64)])
+            .collect();
+
+        let changes = mark_spent_notes(&owned, &on_chain, &private_key).unwrap();
+        let changed: Vec<(u64, bool)> = changes
+            .iter()
+            .map(|change| (change.leaf_index, change.spent))
+            .collect();
+        assert_eq!(changed, [(0, true), (2, false)]);
+        assert_eq!(changes[0].commitment, owned[0].commitment);
+    }
+
+    #[test]
+    fn test_output_for_address_is_received_by_its_owner() {
+        use crate::viewing::SpendingKeys;
+        use rand_chacha::ChaCha20Rng;
+        use rand_core::SeedableRng;
+
+        let mut rng = ChaCha20Rng::from_seed([2u8; 32]);
+        let vortex = Fr::from(5u64);
+        let sender = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+        let recipient = SpendingKeys::from_seed(&[2u8; 32]).unwrap();
+        let address = ShieldedAddress::from_viewing_key(&recipient.viewing).encode();
+
+        let output =
+            create_output_for(&address, 250, &vortex, &sender.viewing, None, &mut rng).unwrap();
+        let encrypted = hex::decode(&output.encrypted_output).unwrap();
+        let commitment = parse_fr(&output.commitment, FieldParseMode::Strict).unwrap();
+        let received = recipient
+            .viewing
+            .decrypt_incoming(&encrypted, &commitment, &vortex)
+            .unwrap();
+        assert_eq!(received.amount, 250);
+        assert_eq!(
+            Note::from_decrypted(
+                &DecryptedNote::new(&received, None),
+                &hash1(recipient.spending_key())
+            ),
+            output.note
+        );
+        assert_eq!(output.note.commitment(&vortex).unwrap(), commitment);
+
+        let bound = parse_fr(&output.bound_commitment, FieldParseMode::Strict).unwrap();
+        assert!(recipient
+            .viewing
+            .decrypt_incoming(&encrypted, &bound, &vortex)
+            .is_some());
+
+        assert!(
+            create_output_for("vortex1bad", 1, &vortex, &sender.viewing, None, &mut rng).is_err()
+        );
+    }
+}
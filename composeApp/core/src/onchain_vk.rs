@@ -0,0 +1,306 @@
+//! Comparison of the verifying key a pool registered on chain with the one the app bundles.
+//!
+//! The Move contract either keeps the compressed key it passed to
+//! `sui::groth16::prepare_verifying_key`, or only the four byte vectors of the resulting
+//! `PreparedVerifyingKey`. Both are brought to the prepared form, which is what the contract
+//! actually verifies against, and compared part by part. A mismatch means the pool's keys
+//! were rotated or the wrong ones deployed, and every proof made with the bundled key would
+//! be rejected on chain.
+//!
+//! The fingerprint is the SHA-256 of the four prepared parts concatenated in the contract's
+//! order, so it can be recomputed from the pool object without any arkworks code.
+
+use anyhow::{anyhow, Result};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_groth16::VerifyingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::validation::{self, PointValidation};
+
+const G1_COMPRESSED_LEN: usize = 32;
+
+/// A verifying key as the Move contract stores it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(
+    tag = "format",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum OnChainVerifyingKey {
+    /// The compressed arkworks key given to `groth16::prepare_verifying_key`
+    Raw { bytes: Vec<u8> },
+    /// The parts of `groth16::PreparedVerifyingKey`
+    Prepared {
+        vk_gamma_abc_g1_bytes: Vec<u8>,
+        alpha_g1_beta_g2_bytes: Vec<u8>,
+        gamma_g2_neg_pc_bytes: Vec<u8>,
+        delta_g2_neg_pc_bytes: Vec<u8>,
+    },
+}
+
+/// A part of the prepared key that differs between the two keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "camelCase")]
+pub enum VerifyingKeyPart {
+    /// The public input points, including a different number of public inputs
+    GammaAbc,
+    AlphaBeta,
+    Gamma,
+    Delta,
+}
+
+/// Outcome of `check_verifying_key`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyingKeyCheck {
+    pub matches: bool,
+    /// Hex fingerprint of the bundled key
+    pub local_fingerprint: String,
+    /// Hex fingerprint of the registered key
+    pub onchain_fingerprint: String,
+    pub local_public_inputs: u32,
+    pub onchain_public_inputs: u32,
+    /// Parts that differ; empty when the keys match
+    pub mismatches: Vec<VerifyingKeyPart>,
+}
+
+/// The four prepared parts, in the contract's order
+#[derive(Debug, PartialEq, Eq)]
+struct PreparedParts {
+    gamma_abc_g1: Vec<u8>,
+    alpha_g1_beta_g2: Vec<u8>,
+    gamma_g2_neg: Vec<u8>,
+    delta_g2_neg: Vec<u8>,
+}
+
+impl PreparedParts {
+    fn from_key(vk: &VerifyingKey<Bn254>) -> Result<Self> {
+        let mut gamma_abc_g1 = Vec::with_capacity(vk.gamma_abc_g1.len() * G1_COMPRESSED_LEN);
+        for point in &vk.gamma_abc_g1 {
+            point.serialize_compressed(&mut gamma_abc_g1)?;
+        }
+        Ok(Self {
+            gamma_abc_g1,
+            alpha_g1_beta_g2: compressed(&Bn254::pairing(vk.alpha_g1, vk.beta_g2))?,
+            gamma_g2_neg: compressed(&-vk.gamma_g2)?,
+            delta_g2_neg: compressed(&-vk.delta_g2)?,
+        })
+    }
+
+    /// Checks every part decodes to valid points, as the contract's prepared key must
+    fn from_onchain(onchain: &OnChainVerifyingKey) -> Result<Self> {
+        let (gamma_abc_g1, alpha_g1_beta_g2, gamma_g2_neg, delta_g2_neg) = match onchain {
+            OnChainVerifyingKey::Raw { bytes } => {
+                let vk = validation::deserialize_verifying_key(bytes, PointValidation::Checked)?;
+                return Self::from_key(&vk);
+            }
+            OnChainVerifyingKey::Prepared {
+                vk_gamma_abc_g1_bytes,
+                alpha_g1_beta_g2_bytes,
+                gamma_g2_neg_pc_bytes,
+                delta_g2_neg_pc_bytes,
+            } => (
+                vk_gamma_abc_g1_bytes,
+                alpha_g1_beta_g2_bytes,
+                gamma_g2_neg_pc_bytes,
+                delta_g2_neg_pc_bytes,
+            ),
+        };
+        if gamma_abc_g1.is_empty() || gamma_abc_g1.len() % G1_COMPRESSED_LEN != 0 {
+            return Err(anyhow!(
+                "Prepared public input points are {} bytes, not a non-zero multiple of {}",
+                gamma_abc_g1.len(),
+                G1_COMPRESSED_LEN
+            ));
+        }
+        for point in gamma_abc_g1.chunks(G1_COMPRESSED_LEN) {
+            check_exact::<G1Affine>(point, "public input point")?;
+        }
+        check_exact::<PairingOutput<Bn254>>(alpha_g1_beta_g2, "alpha_g1_beta_g2")?;
+        check_exact::<G2Affine>(gamma_g2_neg, "gamma_g2_neg_pc")?;
+        check_exact::<G2Affine>(delta_g2_neg, "delta_g2_neg_pc")?;
+        Ok(Self {
+            gamma_abc_g1: gamma_abc_g1.clone(),
+            alpha_g1_beta_g2: alpha_g1_beta_g2.clone(),
+            gamma_g2_neg: gamma_g2_neg.clone(),
+            delta_g2_neg: delta_g2_neg.clone(),
+        })
+    }
+
+    fn public_inputs(&self) -> u32 {
+        (self.gamma_abc_g1.len() / G1_COMPRESSED_LEN).saturating_sub(1) as u32
+    }
+
+    fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.gamma_abc_g1);
+        hasher.update(&self.alpha_g1_beta_g2);
+        hasher.update(&self.gamma_g2_neg);
+        hasher.update(&self.delta_g2_neg);
+        hex::encode(hasher.finalize())
+    }
+}
+
+fn compressed(value: &impl CanonicalSerialize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Rejects trailing bytes, which the contract's parser would also refuse
+fn check_exact<T: CanonicalDeserialize>(bytes: &[u8], what: &str) -> Result<()> {
+    let mut reader = bytes;
+    T::deserialize_compressed(&mut reader)
+        .map_err(|e| anyhow!("Invalid prepared {}: {}", what, e))?;
+    if !reader.is_empty() {
+        return Err(anyhow!("Prepared {} has {} trailing bytes", what, reader.len()));
+    }
+    Ok(())
+}
+
+/// Fingerprint of a verifying key in its prepared on-chain form
+///
+/// # Errors
+/// Returns error only if serialization fails.
+pub fn fingerprint(vk: &VerifyingKey<Bn254>) -> Result<String> {
+    Ok(PreparedParts::from_key(vk)?.fingerprint())
+}
+
+/// Compares the key registered by the pool with the bundled `local` key.
+///
+/// # Errors
+/// Returns error if the on-chain bytes do not decode to a valid key; that is a broken
+/// deployment too, but there is nothing to fingerprint.
+pub fn check_verifying_key(
+    onchain: &OnChainVerifyingKey,
+    local: &VerifyingKey<Bn254>,
+) -> Result<VerifyingKeyCheck> {
+    let onchain = PreparedParts::from_onchain(onchain)?;
+    let local = PreparedParts::from_key(local)?;
+    let mismatches: Vec<VerifyingKeyPart> = [
+        (
+            VerifyingKeyPart::GammaAbc,
+            local.gamma_abc_g1 == onchain.gamma_abc_g1,
+        ),
+        (
+            VerifyingKeyPart::AlphaBeta,
+            local.alpha_g1_beta_g2 == onchain.alpha_g1_beta_g2,
+        ),
+        (
+            VerifyingKeyPart::Gamma,
+            local.gamma_g2_neg == onchain.gamma_g2_neg,
+        ),
+        (
+            VerifyingKeyPart::Delta,
+            local.delta_g2_neg == onchain.delta_g2_neg,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(part, equal)| (!equal).then_some(part))
+    .collect();
+
+    Ok(VerifyingKeyCheck {
+        matches: mismatches.is_empty(),
+        local_fingerprint: local.fingerprint(),
+        onchain_fingerprint: onchain.fingerprint(),
+        local_public_inputs: local.public_inputs(),
+        onchain_public_inputs: onchain.public_inputs(),
+        mismatches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::TransactionCircuit;
+    use ark_groth16::Groth16;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn key(seed: u8) -> VerifyingKey<Bn254> {
+        let circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            circuit,
+            &mut ChaCha20Rng::from_seed([seed; 32]),
+        )
+        .unwrap()
+        .vk
+    }
+
+    fn prepared(vk: &VerifyingKey<Bn254>) -> OnChainVerifyingKey {
+        let parts = PreparedParts::from_key(vk).unwrap();
+        OnChainVerifyingKey::Prepared {
+            vk_gamma_abc_g1_bytes: parts.gamma_abc_g1,
+            alpha_g1_beta_g2_bytes: parts.alpha_g1_beta_g2,
+            gamma_g2_neg_pc_bytes: parts.gamma_g2_neg,
+            delta_g2_neg_pc_bytes: parts.delta_g2_neg,
+        }
+    }
+
+    #[test]
+    fn test_both_formats_match_and_rotation_is_reported() {
+        let vk = key(1);
+        let raw = OnChainVerifyingKey::Raw {
+            bytes: compressed(&vk).unwrap(),
+        };
+        for onchain in [raw, prepared(&vk)] {
+            let check = check_verifying_key(&onchain, &vk).unwrap();
+            assert!(check.matches);
+            assert_eq!(check.local_fingerprint, check.onchain_fingerprint);
+            assert_eq!(check.local_fingerprint, fingerprint(&vk).unwrap());
+            assert_eq!(check.onchain_public_inputs, vk.gamma_abc_g1.len() as u32 - 1);
+        }
+
+        let mut rotated = vk.clone();
+        rotated.delta_g2 = key(2).delta_g2;
+        let check = check_verifying_key(&prepared(&rotated), &vk).unwrap();
+        assert!(!check.matches);
+        assert_ne!(check.local_fingerprint, check.onchain_fingerprint);
+        assert_eq!(check.mismatches, vec![VerifyingKeyPart::Delta]);
+
+        let mut fewer_inputs = vk.clone();
+        fewer_inputs.gamma_abc_g1.pop();
+        let check = check_verifying_key(&prepared(&fewer_inputs), &vk).unwrap();
+        assert_eq!(check.mismatches, vec![VerifyingKeyPart::GammaAbc]);
+        assert_eq!(check.onchain_public_inputs + 1, check.local_public_inputs);
+    }
+
+    #[test]
+    fn test_malformed_prepared_parts_are_rejected() {
+        let vk = key(1);
+        let OnChainVerifyingKey::Prepared {
+            vk_gamma_abc_g1_bytes,
+            alpha_g1_beta_g2_bytes,
+            gamma_g2_neg_pc_bytes,
+            mut delta_g2_neg_pc_bytes,
+        } = prepared(&vk)
+        else {
+            unreachable!()
+        };
+        delta_g2_neg_pc_bytes.push(0);
+        let trailing = OnChainVerifyingKey::Prepared {
+            vk_gamma_abc_g1_bytes: vk_gamma_abc_g1_bytes.clone(),
+            alpha_g1_beta_g2_bytes: alpha_g1_beta_g2_bytes.clone(),
+            gamma_g2_neg_pc_bytes: gamma_g2_neg_pc_bytes.clone(),
+            delta_g2_neg_pc_bytes,
+        };
+        assert!(check_verifying_key(&trailing, &vk).is_err());
+
+        let truncated = OnChainVerifyingKey::Prepared {
+            vk_gamma_abc_g1_bytes: vk_gamma_abc_g1_bytes[1..].to_vec(),
+            alpha_g1_beta_g2_bytes,
+            gamma_g2_neg_pc_bytes: gamma_g2_neg_pc_bytes.clone(),
+            delta_g2_neg_pc_bytes: gamma_g2_neg_pc_bytes,
+        };
+        assert!(check_verifying_key(&truncated, &vk).is_err());
+        assert!(check_verifying_key(&OnChainVerifyingKey::Raw { bytes: vec![0; 8] }, &vk).is_err());
+    }
+}
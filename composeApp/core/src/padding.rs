@@ -0,0 +1,289 @@
+//! Input padding that hides how many notes a transaction spends.
+//!
+//! Every transaction proves `N_INS` inputs. Unused slots are filled with zero-amount notes
+//! under a fresh random private key, blinding and path index, so their nullifiers are
+//! Poseidon outputs of uniformly random preimages, exactly like the nullifiers of real notes.
+//! The slots are then shuffled so a real note is not always first.
+//!
+//! Padded transactions must be proven with the default circuit shape: keys generated with
+//! `with_dummy_inputs` reveal which slots are dummies through the verifying key. Padding
+//! also shuffles the dummies into random slots, which no fixed layout matches, so the two
+//! never combine; `ProofInputBuilder` always emits the default layout.
+
+use crate::{
+    circuit::{compute_commitment, compute_legacy_commitment, compute_nullifier},
+    constants::{MERKLE_TREE_LEVEL, N_INS},
+    merkle_tree::Path,
+    poseidon_opt::{hash1, hash3},
+};
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+use ark_std::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A note being spent, as it is fed into one input slot of the circuit
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SpendInput {
+    pub private_key: Fr,
+    pub amount: Fr,
+    pub blinding: Fr,
+    pub path_index: Fr,
+    pub merkle_path: Path<MERKLE_TREE_LEVEL>,
+    pub legacy_commitment: bool,
+}
+
+impl SpendInput {
+    /// Zero-amount input whose nullifier is indistinguishable from a real one
+    pub fn dummy<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            private_key: Fr::rand(rng),
+            amount: Fr::ZERO,
+            blinding: Fr::rand(rng),
+            path_index: Fr::from(rng.next_u64() % (1u64 << MERKLE_TREE_LEVEL)),
+            merkle_path: Path::empty(),
+            legacy_commitment: false,
+        }
+    }
+
+    /// Like `dummy`, at the chosen leaf index.
+    ///
+    /// # Errors
+    /// Returns error if `index` does not fit the tree.
+    pub fn dummy_at<R: RngCore + CryptoRng>(index: u64, rng: &mut R) -> Result<Self> {
+        if index >= 1u64 << MERKLE_TREE_LEVEL {
+            return Err(anyhow!(
+                "Leaf index {} exceeds tree capacity (>= 2^{})",
+                index,
+                MERKLE_TREE_LEVEL
+            ));
+        }
+        let mut input = Self::dummy(rng);
+        input.path_index = Fr::from(index);
+        Ok(input)
+    }
+
+    pub fn commitment(&self, vortex: &Fr) -> Fr {
+        let public_key = hash1(&self.private_key);
+        if self.legacy_commitment {
+            compute_legacy_commitment(&self.amount, &public_key, &self.blinding)
+        } else {
+            compute_commitment(&self.amount, &public_key, &self.blinding, vortex)
+        }
+    }
+
+    /// `Poseidon3(privkey, commitment, path_index)`, the signature the nullifier hashes
+    pub fn signature(&self, vortex: &Fr) -> Fr {
+        hash3(
+            &self.private_key,
+            &self.commitment(vortex),
+            &self.path_index,
+        )
+    }
+
+    pub fn nullifier(&self, vortex: &Fr) -> Fr {
+        compute_nullifier(
+            &self.commitment(vortex),
+            &self.path_index,
+            &self.private_key,
+        )
+    }
+}
+
+/// A zero-amount input with every value a client fills into a `ProofInput` slot, in decimal
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct DummyInput {
+    pub private_key: String,
+    pub public_key: String,
+    pub amount: String,
+    pub blinding: String,
+    pub path_index: String,
+    pub commitment: String,
+    pub signature: String,
+    pub nullifier: String,
+    /// `[left, right]` pairs of the empty path, `MERKLE_TREE_LEVEL` of them
+    pub merkle_path: Vec<Vec<String>>,
+}
+
+impl std::fmt::Debug for DummyInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DummyInput")
+            .field("private_key", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .field("path_index", &self.path_index)
+            .field("commitment", &self.commitment)
+            .field("nullifier", &self.nullifier)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A zero-amount input at leaf `index` under a fresh random private key and blinding.
+///
+/// The circuit skips the membership check for zero-amount inputs, so the empty path and any
+/// index in the tree are accepted; the nullifier is still derived from them exactly as for a
+/// real note.
+///
+/// # Errors
+/// Returns error if `index` does not fit the tree.
+pub fn make_dummy_input<R: RngCore + CryptoRng>(
+    index: u64,
+    vortex: &Fr,
+    rng: &mut R,
+) -> Result<DummyInput> {
+    let input = SpendInput::dummy_at(index, rng)?;
+    let s = |x: &Fr| x.into_bigint().to_string();
+    Ok(DummyInput {
+        private_key: s(&input.private_key),
+        public_key: s(&hash1(&input.private_key)),
+        amount: s(&input.amount),
+        blinding: s(&input.blinding),
+        path_index: s(&input.path_index),
+        commitment: s(&input.commitment(vortex)),
+        signature: s(&input.signature(vortex)),
+        nullifier: s(&input.nullifier(vortex)),
+        merkle_path: input
+            .merkle_path
+            .path
+            .iter()
+            .map(|(left, right)| vec![s(left), s(right)])
+            .collect(),
+    })
+}
+
+/// Fills the unused slots after `real` with dummies and shuffles all `N_INS` slots.
+///
+/// # Errors
+/// Returns error if more than `N_INS` real inputs are given.
+pub fn pad_inputs<R: RngCore + CryptoRng>(
+    mut real: Vec<SpendInput>,
+    rng: &mut R,
+) -> Result<[SpendInput; N_INS]> {
+    if real.len() > N_INS {
+        return Err(anyhow!(
+            "At most {} inputs can be spent, got {}",
+            N_INS,
+            real.len()
+        ));
+    }
+
+    while real.len() < N_INS {
+        real.push(SpendInput::dummy(rng));
+    }
+    // Fisher-Yates; the modulo bias of a 64-bit draw over N_INS slots is negligible
+    for i in (1..N_INS).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        real.swap(i, j);
+    }
+
+    Ok(real
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("padded to N_INS inputs")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::TransactionCircuit;
+    use crate::constants::N_OUTS;
+    use crate::field::{parse_fr, FieldParseMode};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn real_input() -> SpendInput {
+        SpendInput {
+            private_key: Fr::from(12345u64),
+            amount: Fr::ZERO,
+            blinding: Fr::from(999u64),
+            path_index: Fr::from(3u64),
+            merkle_path: Path::empty(),
+            legacy_commitment: false,
+        }
+    }
+
+    #[test]
+    fn test_padding_shuffles_real_input_position() {
+        let real_nullifier = real_input().nullifier(&Fr::ZERO);
+        let mut positions = [0usize; N_INS];
+        for seed in 0..16u8 {
+            let mut rng = ChaCha20Rng::from_seed([seed; 32]);
+            let padded = pad_inputs(vec![real_input()], &mut rng).unwrap();
+            let position = padded
+                .iter()
+                .position(|input| input.nullifier(&Fr::ZERO) == real_nullifier)
+                .unwrap();
+            positions[position] += 1;
+        }
+        assert!(positions.iter().all(|&count| count > 0));
+
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        assert!(pad_inputs(vec![real_input(); N_INS + 1], &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_padded_inputs_satisfy_the_default_circuit() {
+        let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+        let vortex = Fr::from(5u64);
+        let inputs = pad_inputs(vec![real_input()], &mut rng).unwrap();
+
+        let out_public_key = hash1(&Fr::from(1u64));
+        let out_blindings = [Fr::from(7u64), Fr::from(8u64)];
+        let circuit = TransactionCircuit::new(
+            vortex,
+            Fr::ZERO,
+            Fr::ZERO,
+            inputs[0].nullifier(&vortex),
+            inputs[1].nullifier(&vortex),
+            compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[0], &vortex),
+            compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[1], &vortex),
+            Fr::ZERO,
+            Fr::ZERO,
+            [inputs[0].private_key, inputs[1].private_key],
+            [inputs[0].amount, inputs[1].amount],
+            [inputs[0].blinding, inputs[1].blinding],
+            [inputs[0].path_index, inputs[1].path_index],
+            [inputs[0].merkle_path, inputs[1].merkle_path],
+            [out_public_key; N_OUTS],
+            [Fr::ZERO; N_OUTS],
+            out_blindings,
+        )
+        .unwrap();
+
+        assert!(circuit.check_public_inputs().is_ok());
+    }
+
+    #[test]
+    fn test_dummy_input_values_are_consistent() {
+        let mut rng = ChaCha20Rng::from_seed([3u8; 32]);
+        let vortex = Fr::from(5u64);
+        let dummy = make_dummy_input(7, &vortex, &mut rng).unwrap();
+        let fr = |s: &str| parse_fr(s, FieldParseMode::Strict).unwrap();
+
+        let private_key = fr(&dummy.private_key);
+        assert_eq!(fr(&dummy.public_key), hash1(&private_key));
+        assert_eq!(dummy.amount, "0");
+        assert_eq!(dummy.path_index, "7");
+        let commitment = compute_commitment(
+            &Fr::ZERO,
+            &fr(&dummy.public_key),
+            &fr(&dummy.blinding),
+            &vortex,
+        );
+        assert_eq!(fr(&dummy.commitment), commitment);
+        assert_eq!(
+            fr(&dummy.signature),
+            hash3(&private_key, &commitment, &Fr::from(7u64))
+        );
+        assert_eq!(
+            fr(&dummy.nullifier),
+            compute_nullifier(&commitment, &Fr::from(7u64), &private_key)
+        );
+        assert_eq!(dummy.merkle_path.len(), MERKLE_TREE_LEVEL);
+        assert!(dummy.merkle_path.iter().all(|pair| pair == &["0", "0"]));
+
+        assert!(make_dummy_input(1u64 << MERKLE_TREE_LEVEL, &vortex, &mut rng).is_err());
+    }
+}
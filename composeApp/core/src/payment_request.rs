@@ -0,0 +1,327 @@
+//! Scan-to-pay payment requests.
+//!
+//! A request names the shielded address to pay and optionally an amount, a memo and an
+//! expiry. It is encoded as uppercase bech32m with human-readable part `vortexpay`, so the
+//! whole string fits the QR alphanumeric mode, over
+//! `version (1) || flags (1) || note_public_key (32, LE) || viewing_public_key (32)` followed
+//! by the fields whose flag bit is set, in bit order:
+//! - bit 0: `amount (8, LE)`
+//! - bit 1: `expires_at_ms (8, LE)`
+//! - bit 2: `memo_len (1) || memo (UTF-8)`
+//! - bit 3: `signer_public_key (32) || signature (64)`
+//!
+//! The signature is an `auth` login signature of the recipient over the domain tag and every
+//! payload byte before it. A signer key is not tied to the address itself, so wallets compare
+//! it with a key they already trust for the payee before showing the request as verified.
+
+use crate::address::ShieldedAddress;
+use crate::auth::{self, AUTH_SIGNATURE_LEN};
+use crate::field::{fr_from_bytes_le, fr_to_bytes_le};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Bech32m, Hrp};
+use serde::{Deserialize, Serialize};
+
+/// Human-readable part of payment requests
+pub const PAYMENT_REQUEST_HRP: &str = "vortexpay";
+
+/// Payload format version
+pub const PAYMENT_REQUEST_VERSION: u8 = 0;
+
+/// Longest memo in bytes, keeping a signed request under 420 characters
+pub const MAX_MEMO_LEN: usize = 64;
+
+const SIGNATURE_DOMAIN: &[u8] = b"vortex-payment-request-v0";
+
+const FLAG_AMOUNT: u8 = 1;
+const FLAG_EXPIRY: u8 = 1 << 1;
+const FLAG_MEMO: u8 = 1 << 2;
+const FLAG_SIGNATURE: u8 = 1 << 3;
+
+const HEADER_LEN: usize = 2 + 32 + 32;
+
+/// What the payee asks for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    /// Shielded address to pay
+    pub address: String,
+    /// Amount in the pool's base unit; the payer chooses when absent
+    pub amount: Option<u64>,
+    pub memo: Option<String>,
+    /// Unix time in milliseconds after which the request must not be paid
+    pub expires_at_ms: Option<u64>,
+}
+
+/// A decoded request and who signed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedPaymentRequest {
+    pub request: PaymentRequest,
+    /// Hex `auth` public key whose signature verified, `None` for unsigned requests
+    pub signer_public_key: Option<String>,
+}
+
+/// Encodes `request`, signed with the login key of `spending_key` when given.
+///
+/// # Errors
+/// Returns error if the address is invalid, the memo is longer than `MAX_MEMO_LEN` bytes or
+/// signing fails.
+pub fn encode_payment_request(
+    request: &PaymentRequest,
+    spending_key: Option<&Fr>,
+) -> Result<String> {
+    let address = ShieldedAddress::decode(&request.address)?;
+    let mut flags = 0;
+    let mut fields = Vec::new();
+    if let Some(amount) = request.amount {
+        flags |= FLAG_AMOUNT;
+        fields.extend_from_slice(&amount.to_le_bytes());
+    }
+    if let Some(expires_at_ms) = request.expires_at_ms {
+        flags |= FLAG_EXPIRY;
+        fields.extend_from_slice(&expires_at_ms.to_le_bytes());
+    }
+    if let Some(memo) = &request.memo {
+        check_memo(memo)?;
+        flags |= FLAG_MEMO;
+        fields.push(memo.len() as u8);
+        fields.extend_from_slice(memo.as_bytes());
+    }
+    if spending_key.is_some() {
+        flags |= FLAG_SIGNATURE;
+    }
+
+    let mut payload = Vec::with_capacity(HEADER_LEN + fields.len() + 32 + AUTH_SIGNATURE_LEN);
+    payload.push(PAYMENT_REQUEST_VERSION);
+    payload.push(flags);
+    payload.extend_from_slice(&fr_to_bytes_le(&address.note_public_key));
+    payload.extend_from_slice(&address.viewing_public_key);
+    payload.extend_from_slice(&fields);
+    if let Some(spending_key) = spending_key {
+        payload.extend_from_slice(&auth::auth_public_key(spending_key));
+        let signature = auth::sign_challenge(spending_key, &signing_message(&payload))?;
+        payload.extend_from_slice(&signature);
+    }
+
+    bech32::encode_upper::<Bech32m>(request_hrp(), &payload)
+        .context("Payment request is too long to encode")
+}
+
+/// Parses a scanned request, verifying its signature if it has one.
+///
+/// # Errors
+/// Returns error if the string is not a payment request of this version, has unknown flags,
+/// trailing bytes or a non-canonical key, a signature that does not verify, or expired at
+/// `now_ms`.
+pub fn decode_payment_request(payload: &str, now_ms: u64) -> Result<ScannedPaymentRequest> {
+    let parsed = CheckedHrpstring::new::<Bech32m>(payload.trim())
+        .map_err(|e| anyhow!("Invalid payment request: {}", e))?;
+    if parsed.hrp() != request_hrp() {
+        return Err(anyhow!(
+            "Payment request must start with '{}1', got '{}'",
+            PAYMENT_REQUEST_HRP,
+            parsed.hrp()
+        ));
+    }
+    let payload: Vec<u8> = parsed.byte_iter().collect();
+    if payload.len() < HEADER_LEN {
+        return Err(anyhow!("Payment request is truncated"));
+    }
+    if payload[0] != PAYMENT_REQUEST_VERSION {
+        return Err(anyhow!(
+            "Unsupported payment request version {}",
+            payload[0]
+        ));
+    }
+    let flags = payload[1];
+    if flags & !(FLAG_AMOUNT | FLAG_EXPIRY | FLAG_MEMO | FLAG_SIGNATURE) != 0 {
+        return Err(anyhow!("Payment request has unknown flags {:#04x}", flags));
+    }
+
+    let address = ShieldedAddress {
+        note_public_key: fr_from_bytes_le(&payload[2..34])
+            .context("Note public key is not a canonical field element")?,
+        viewing_public_key: payload[34..HEADER_LEN].try_into().unwrap(),
+    };
+    let mut reader = Reader {
+        bytes: &payload,
+        at: HEADER_LEN,
+    };
+    let amount = (flags & FLAG_AMOUNT != 0)
+        .then(|| reader.u64())
+        .transpose()?;
+    let expires_at_ms = (flags & FLAG_EXPIRY != 0)
+        .then(|| reader.u64())
+        .transpose()?;
+    let memo = (flags & FLAG_MEMO != 0)
+        .then(|| -> Result<String> {
+            let len = reader.take(1)?[0] as usize;
+            let memo = std::str::from_utf8(reader.take(len)?).context("Memo is not UTF-8")?;
+            check_memo(memo)?;
+            Ok(memo.to_string())
+        })
+        .transpose()?;
+
+    let signer_public_key = if flags & FLAG_SIGNATURE != 0 {
+        let signed_len = reader.at + 32;
+        let public_key = reader.take(32)?;
+        let signature = reader.take(AUTH_SIGNATURE_LEN)?;
+        let message = signing_message(&payload[..signed_len]);
+        if !auth::verify_challenge(public_key, &message, signature)? {
+            return Err(anyhow!("Payment request signature is invalid"));
+        }
+        Some(hex::encode(public_key))
+    } else {
+        None
+    };
+    if reader.at != payload.len() {
+        return Err(anyhow!(
+            "Payment request has {} trailing bytes",
+            payload.len() - reader.at
+        ));
+    }
+
+    if let Some(expires_at_ms) = expires_at_ms
+        && now_ms >= expires_at_ms
+    {
+        return Err(anyhow!(
+            "Payment request expired at {} (now {})",
+            expires_at_ms,
+            now_ms
+        ));
+    }
+
+    Ok(ScannedPaymentRequest {
+        request: PaymentRequest {
+            address: address.encode(),
+            amount,
+            memo,
+            expires_at_ms,
+        },
+        signer_public_key,
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.at..self.at + len)
+            .ok_or_else(|| anyhow!("Payment request is truncated"))?;
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn check_memo(memo: &str) -> Result<()> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(anyhow!(
+            "Memo is {} bytes, at most {} fit a payment request",
+            memo.len(),
+            MAX_MEMO_LEN
+        ));
+    }
+    Ok(())
+}
+
+fn signing_message(signed: &[u8]) -> Vec<u8> {
+    [SIGNATURE_DOMAIN, signed].concat()
+}
+
+fn request_hrp() -> Hrp {
+    Hrp::parse_unchecked(PAYMENT_REQUEST_HRP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewing::SpendingKeys;
+
+    fn request() -> (PaymentRequest, SpendingKeys) {
+        let keys = SpendingKeys::from_seed(&[5u8; 32]).unwrap();
+        let request = PaymentRequest {
+            address: ShieldedAddress::from_viewing_key(&keys.viewing).encode(),
+            amount: Some(2_500_000_000),
+            memo: Some("coffee ☕".into()),
+            expires_at_ms: Some(10_000),
+        };
+        (request, keys)
+    }
+
+    #[test]
+    fn test_requests_round_trip_signed_and_unsigned() {
+        let (request, keys) = request();
+
+        let unsigned = encode_payment_request(&request, None).unwrap();
+        assert!(unsigned.starts_with("VORTEXPAY1"));
+        assert!(unsigned
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()));
+        let scanned = decode_payment_request(&unsigned, 9_999).unwrap();
+        assert_eq!(scanned.request, request);
+        assert_eq!(scanned.signer_public_key, None);
+
+        let signed = encode_payment_request(&request, Some(keys.spending_key())).unwrap();
+        let scanned = decode_payment_request(&signed.to_lowercase(), 0).unwrap();
+        assert_eq!(scanned.request, request);
+        assert_eq!(
+            scanned.signer_public_key,
+            Some(hex::encode(auth::auth_public_key(keys.spending_key())))
+        );
+
+        let bare = PaymentRequest {
+            amount: None,
+            memo: None,
+            expires_at_ms: None,
+            ..request
+        };
+        let encoded = encode_payment_request(&bare, None).unwrap();
+        assert_eq!(
+            decode_payment_request(&encoded, u64::MAX).unwrap().request,
+            bare
+        );
+    }
+
+    #[test]
+    fn test_tampered_expired_and_oversized_requests_are_rejected() {
+        let (request, keys) = request();
+        let signed = encode_payment_request(&request, Some(keys.spending_key())).unwrap();
+
+        let err = decode_payment_request(&signed, 10_000).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        // Re-encode a valid bech32m string with a different amount under the old signature
+        let mut payload: Vec<u8> = CheckedHrpstring::new::<Bech32m>(&signed)
+            .unwrap()
+            .byte_iter()
+            .collect();
+        payload[HEADER_LEN] ^= 1;
+        let tampered = bech32::encode_upper::<Bech32m>(request_hrp(), &payload).unwrap();
+        let err = decode_payment_request(&tampered, 0).unwrap_err();
+        assert!(err.to_string().contains("signature"));
+
+        payload[1] |= 1 << 7;
+        let unknown = bech32::encode_upper::<Bech32m>(request_hrp(), &payload).unwrap();
+        assert!(decode_payment_request(&unknown, 0).is_err());
+
+        let long_memo = PaymentRequest {
+            memo: Some("x".repeat(MAX_MEMO_LEN + 1)),
+            ..request.clone()
+        };
+        assert!(encode_payment_request(&long_memo, None).is_err());
+        assert!(decode_payment_request(&request.address, 0).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! `vortex:` payment URIs for deep links.
+//!
+//! A URI is `vortex:<shielded address>[?<params>]`, BIP-21 style, with the parameters
+//! - `amount`: base units as a decimal integer, without sign, leading zeros or fraction
+//! - `memo`: percent-encoded UTF-8, at most `MAX_MEMO_LEN` bytes once decoded
+//! - `expires`: Unix time in milliseconds
+//!
+//! Parsing is strict so the Android and iOS link handlers accept exactly the same strings:
+//! the scheme is matched case-insensitively but `vortex://` is rejected, every value must be
+//! non-empty and appear once, `+` is a literal plus rather than a space, and malformed
+//! percent escapes, fragments and unknown parameters prefixed `req-` are errors. Other
+//! unknown parameters are ignored for forward compatibility and dropped by
+//! `canonicalize_payment_uri`, which also lowercases the address and orders the parameters as
+//! above.
+
+use crate::address::ShieldedAddress;
+use crate::payment_request::{PaymentRequest, MAX_MEMO_LEN};
+use anyhow::{anyhow, Context, Result};
+
+/// URI scheme, without the colon
+pub const PAYMENT_URI_SCHEME: &str = "vortex";
+
+/// Longest URI accepted, well above a canonical URI with a full memo
+pub const MAX_PAYMENT_URI_LEN: usize = 2048;
+
+/// Parses a payment URI and rejects it if it expired at `now_ms`.
+///
+/// # Errors
+/// Returns error if the URI is malformed (see the module docs) or expired.
+pub fn parse_payment_uri(uri: &str, now_ms: u64) -> Result<PaymentRequest> {
+    let request = parse(uri)?;
+    if let Some(expires_at_ms) = request.expires_at_ms
+        && now_ms >= expires_at_ms
+    {
+        return Err(anyhow!(
+            "Payment URI expired at {} (now {})",
+            expires_at_ms,
+            now_ms
+        ));
+    }
+    Ok(request)
+}
+
+/// Formats `request` as a canonical payment URI.
+///
+/// # Errors
+/// Returns error if the address is invalid, the amount is zero, the memo is empty or longer
+/// than `MAX_MEMO_LEN` bytes.
+pub fn format_payment_uri(request: &PaymentRequest) -> Result<String> {
+    let address = ShieldedAddress::decode(&request.address)?;
+    let mut params = Vec::new();
+    if let Some(amount) = request.amount {
+        if amount == 0 {
+            return Err(anyhow!("Payment URI amount must be positive"));
+        }
+        params.push(format!("amount={}", amount));
+    }
+    if let Some(memo) = &request.memo {
+        check_memo(memo)?;
+        params.push(format!("memo={}", percent_encode(memo)));
+    }
+    if let Some(expires_at_ms) = request.expires_at_ms {
+        params.push(format!("expires={}", expires_at_ms));
+    }
+
+    let mut uri = format!("{}:{}", PAYMENT_URI_SCHEME, address.encode());
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    Ok(uri)
+}
+
+/// The canonical form of a payment URI, without checking its expiry
+pub fn canonicalize_payment_uri(uri: &str) -> Result<String> {
+    format_payment_uri(&parse(uri)?)
+}
+
+fn parse(uri: &str) -> Result<PaymentRequest> {
+    let uri = uri.trim();
+    if uri.len() > MAX_PAYMENT_URI_LEN {
+        return Err(anyhow!(
+            "Payment URI is {} characters, at most {} are accepted",
+            uri.len(),
+            MAX_PAYMENT_URI_LEN
+        ));
+    }
+    let rest = uri
+        .split_once(':')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(PAYMENT_URI_SCHEME))
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| anyhow!("Not a '{}:' payment URI", PAYMENT_URI_SCHEME))?;
+    if rest.starts_with("//") {
+        return Err(anyhow!(
+            "Payment URI must be '{}:<address>', not '{}://'",
+            PAYMENT_URI_SCHEME,
+            PAYMENT_URI_SCHEME
+        ));
+    }
+    if rest.contains('#') {
+        return Err(anyhow!("Payment URI must not have a fragment"));
+    }
+
+    let (address, query) = match rest.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (rest, None),
+    };
+    let mut request = PaymentRequest {
+        address: ShieldedAddress::decode(address)
+            .context("Invalid payment URI address")?
+            .encode(),
+        amount: None,
+        memo: None,
+        expires_at_ms: None,
+    };
+
+    for param in query.map(|query| query.split('&')).into_iter().flatten() {
+        let (key, value) = param
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Payment URI parameter '{}' has no value", param))?;
+        if value.is_empty() {
+            return Err(anyhow!("Payment URI parameter '{}' is empty", key));
+        }
+        let duplicate = || anyhow!("Payment URI parameter '{}' appears twice", key);
+        match key {
+            "amount" => {
+                if request.amount.is_some() {
+                    return Err(duplicate());
+                }
+                let amount = parse_integer(value).context("Invalid payment URI amount")?;
+                if amount == 0 {
+                    return Err(anyhow!("Payment URI amount must be positive"));
+                }
+                request.amount = Some(amount);
+            }
+            "memo" => {
+                if request.memo.is_some() {
+                    return Err(duplicate());
+                }
+                let memo = percent_decode(value)?;
+                check_memo(&memo)?;
+                request.memo = Some(memo);
+            }
+            "expires" => {
+                if request.expires_at_ms.is_some() {
+                    return Err(duplicate());
+                }
+                request.expires_at_ms =
+                    Some(parse_integer(value).context("Invalid payment URI expiry")?);
+            }
+            key if key.starts_with("req-") => {
+                return Err(anyhow!(
+                    "Payment URI requires unsupported parameter '{}'",
+                    key
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(request)
+}
+
+/// A `u64` written without sign, leading zeros or fraction
+fn parse_integer(value: &str) -> Result<u64> {
+    if !value.bytes().all(|b| b.is_ascii_digit()) || (value.len() > 1 && value.starts_with('0')) {
+        return Err(anyhow!("'{}' is not a canonical decimal integer", value));
+    }
+    value
+        .parse()
+        .map_err(|_| anyhow!("'{}' does not fit in 64 bits", value))
+}
+
+fn check_memo(memo: &str) -> Result<()> {
+    if memo.is_empty() || memo.len() > MAX_MEMO_LEN {
+        return Err(anyhow!(
+            "Memo must be 1 to {} bytes, got {}",
+            MAX_MEMO_LEN,
+            memo.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Escapes everything but RFC 3986 unreserved characters, with uppercase hex digits
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| anyhow!("Malformed percent escape in '{}'", value))?;
+                decoded.push(byte);
+                i += 3;
+            }
+            byte if byte.is_ascii_graphic() => {
+                decoded.push(byte);
+                i += 1;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Payment URI values must percent-encode spaces and non-ASCII characters"
+                ));
+            }
+        }
+    }
+    String::from_utf8(decoded).context("Memo is not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+
+    fn address() -> String {
+        ShieldedAddress {
+            note_public_key: Fr::from(42u64),
+            viewing_public_key: [7u8; 32],
+        }
+        .encode()
+    }
+
+    #[test]
+    fn test_uris_round_trip_in_canonical_form() {
+        let request = PaymentRequest {
+            address: address(),
+            amount: Some(1_500_000_000),
+            memo: Some("rent & coffee ☕ 100%".into()),
+            expires_at_ms: Some(2_000),
+        };
+        let uri = format_payment_uri(&request).unwrap();
+        assert_eq!(
+            uri,
+            format!(
+                "vortex:{}?amount=1500000000&memo=rent%20%26%20coffee%20%E2%98%95%20100%25&expires=2000",
+                address()
+            )
+        );
+        assert_eq!(parse_payment_uri(&uri, 1_999).unwrap(), request);
+
+        // Scheme and address case, parameter order, lowercase escapes and unknown optional
+        // parameters all canonicalize away
+        let messy = format!(
+            "VORTEX:{}?expires=2000&label=x&memo=rent%20%26%20coffee%20%e2%98%95%20100%25&amount=1500000000",
+            address().to_uppercase()
+        );
+        assert_eq!(canonicalize_payment_uri(&messy).unwrap(), uri);
+
+        let bare = format!("vortex:{}", address());
+        assert_eq!(canonicalize_payment_uri(&bare).unwrap(), bare);
+    }
+
+    #[test]
+    fn test_malformed_uris_are_rejected() {
+        let base = format!("vortex:{}", address());
+        let rejected = [
+            format!("vortex://{}", address()),
+            format!("bitcoin:{}", address()),
+            format!("{}?amount=01", base),
+            format!("{}?amount=-1", base),
+            format!("{}?amount=1.5", base),
+            format!("{}?amount=0", base),
+            format!("{}?amount=18446744073709551616", base),
+            format!("{}?amount=1&amount=2", base),
+            format!("{}?amount=", base),
+            format!("{}?amount", base),
+            format!("{}?memo=%zz", base),
+            format!("{}?memo=%E2%98", base),
+            format!("{}?memo=a b", base),
+            format!("{}?memo={}", base, "x".repeat(MAX_MEMO_LEN + 1)),
+            format!("{}?req-invoice=1", base),
+            format!("{}#fragment", base),
+            format!("{}?", base),
+        ];
+        for uri in &rejected {
+            assert!(parse_payment_uri(uri, 0).is_err(), "{}", uri);
+        }
+
+        let expired = format!("{}?expires=10", base);
+        assert!(parse_payment_uri(&expired, 10)
+            .unwrap_err()
+            .to_string()
+            .contains("expired"));
+        assert!(canonicalize_payment_uri(&expired).is_ok());
+        assert_eq!(
+            parse_payment_uri(&format!("{}?memo=a+b", base), 0)
+                .unwrap()
+                .memo
+                .as_deref(),
+            Some("a+b")
+        );
+    }
+}
@@ -6,6 +6,8 @@
 // These constants include C (round constants), S (sparse matrix),
 // M (MDS matrix), and P (pre-sparse matrix) for the optimized algorithm.
 
+use alloc::vec;
+use alloc::vec::Vec;
 use ark_bn254::Fr;
 use num_bigint::BigUint;
 use num_traits::Num;
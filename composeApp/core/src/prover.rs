@@ -0,0 +1,486 @@
+//! Groth16 proving split into explicit phases.
+//!
+//! `Groth16::prove` synthesizes, builds matrices and runs the MSMs in one call. Doing the
+//! same steps here lets callers time each phase without changing the resulting proof.
+//!
+//! The steps are also exposed in two halves: `prepare_circuit` synthesizes the witness and
+//! matrices, and `finalize_proof` runs the MSMs. Apps prepare while the user is still on the
+//! confirmation screen and only pay for the MSMs once the transaction is confirmed.
+//!
+//! The constraint matrices depend only on the circuit's shape, not on the witness. The first
+//! proof of each shape builds them and keeps them for the process, so later proofs of that
+//! shape (batches, consolidation chains) only assign the witness and skip building, inlining
+//! and converting the linear combinations.
+//!
+//! Likewise the QAP evaluation domain depends only on the proving key. A `ProvingDomain` built
+//! once per key keeps the domain, the coset powers and the vanishing-polynomial inverse the
+//! witness map needs, so callers that cache a key cache its domain next to it.
+
+use crate::circuit::{CircuitShape, TransactionCircuit};
+use crate::mul_kernel;
+use crate::telemetry::{Phase, PhaseTimer};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{AdditiveGroup, FftField, Field, PrimeField, Zero};
+use ark_groth16::r1cs_to_qap::evaluate_constraint;
+use ark_groth16::{Proof, ProvingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef,
+    OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_std::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use std::sync::{Arc, Mutex};
+use zeroize::{Zeroize, Zeroizing};
+
+/// A synthesized circuit waiting for its MSMs.
+///
+/// Holds the full witness assignment, which is wiped on drop.
+pub struct PreparedWitness {
+    pub(crate) matrices: Arc<ConstraintMatrices<Fr>>,
+    pub(crate) num_inputs: usize,
+    pub(crate) num_constraints: usize,
+    pub(crate) full_assignment: Zeroizing<Vec<Fr>>,
+}
+
+impl PreparedWitness {
+    /// Public inputs in `get_public_inputs()` order
+    pub fn public_inputs(&self) -> &[Fr] {
+        &self.full_assignment[1..self.num_inputs]
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.num_constraints
+    }
+}
+
+/// Constraint matrices by circuit shape, built by the first proof of each shape
+static STRUCTURES: Mutex<Vec<(CircuitShape, Arc<ConstraintMatrices<Fr>>)>> = Mutex::new(Vec::new());
+
+fn cached_structure(shape: &CircuitShape) -> Option<Arc<ConstraintMatrices<Fr>>> {
+    let structures = STRUCTURES.lock().unwrap();
+    structures
+        .iter()
+        .find(|(cached, _)| cached == shape)
+        .map(|(_, matrices)| matrices.clone())
+}
+
+/// Drops the constraint matrices kept for every circuit shape
+pub fn clear_circuit_structures() {
+    STRUCTURES.lock().unwrap().clear();
+}
+
+/// Proves `circuit` with `pk`, attributing witness, constraint and proving time to `timer`
+pub fn prove_circuit<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    circuit: TransactionCircuit,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    let prepared = prepare_circuit(circuit, timer)?;
+    finalize_proof(pk, &prepared, rng, timer)
+}
+
+/// Synthesizes `circuit` and builds its matrices, the first half of `prove_circuit`.
+///
+/// Matrices already built for the circuit's shape are reused and only the witness is
+/// assigned.
+pub fn prepare_circuit(
+    circuit: TransactionCircuit,
+    timer: &mut PhaseTimer,
+) -> Result<PreparedWitness, SynthesisError> {
+    let shape = circuit.shape();
+    let cached = cached_structure(&shape);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    if cached.is_some() {
+        cs.set_mode(SynthesisMode::Prove {
+            construct_matrices: false,
+        });
+    }
+    {
+        trace_span!("synthesize", reused_structure = cached.is_some());
+        circuit.generate_constraints(cs.clone())?;
+    }
+    timer.end_phase(Phase::Witness);
+
+    let (matrices, num_inputs, num_constraints, full_assignment) = {
+        trace_span!("build_matrices");
+        let matrices = match cached {
+            Some(matrices) => {
+                // The shape fixes the layout, so a mismatch means the circuit depends on
+                // more than its shape flags
+                if matrices.num_instance_variables != cs.num_instance_variables()
+                    || matrices.num_witness_variables != cs.num_witness_variables()
+                    || matrices.num_constraints != cs.num_constraints()
+                {
+                    wipe_witness(&cs);
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+                matrices
+            }
+            None => {
+                cs.finalize();
+                let matrices = Arc::new(cs.to_matrices().ok_or(SynthesisError::AssignmentMissing)?);
+                let mut structures = STRUCTURES.lock().unwrap();
+                if !structures.iter().any(|(cached, _)| *cached == shape) {
+                    structures.push((shape, matrices.clone()));
+                }
+                matrices
+            }
+        };
+        let full_assignment = {
+            let prover = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+            Zeroizing::new(
+                [
+                    prover.instance_assignment.as_slice(),
+                    prover.witness_assignment.as_slice(),
+                ]
+                .concat(),
+            )
+        };
+        wipe_witness(&cs);
+        (
+            matrices,
+            cs.num_instance_variables(),
+            cs.num_constraints(),
+            full_assignment,
+        )
+    };
+    timer.set_num_constraints(num_constraints);
+    timer.end_phase(Phase::Constraints);
+
+    Ok(PreparedWitness {
+        matrices,
+        num_inputs,
+        num_constraints,
+        full_assignment,
+    })
+}
+
+/// The QAP evaluation domain of a proving key and the values derived from it that every
+/// proof with the key needs
+#[derive(Debug, Clone)]
+pub struct ProvingDomain {
+    domain: GeneralEvaluationDomain<Fr>,
+    /// `g^i` for the coset generator `g`, to move coefficients onto the coset
+    coset_powers: Vec<Fr>,
+    /// `g^-i`, to move them back
+    coset_inverse_powers: Vec<Fr>,
+    /// Inverse of the vanishing polynomial, which is constant on the coset
+    vanishing_inverse: Fr,
+}
+
+impl ProvingDomain {
+    /// The domain of `pk`
+    pub fn for_key(pk: &ProvingKey<Bn254>) -> Result<Self, SynthesisError> {
+        Self::for_h_query_len(pk.h_query.len())
+    }
+
+    /// The domain of a key with `h_query_len` H query points, one fewer than the domain has
+    /// elements
+    pub(crate) fn for_h_query_len(h_query_len: usize) -> Result<Self, SynthesisError> {
+        let domain = GeneralEvaluationDomain::<Fr>::new(h_query_len + 1)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let powers = |base: Fr| {
+            core::iter::successors(Some(Fr::ONE), move |x| Some(*x * base))
+                .take(domain.size())
+                .collect()
+        };
+        let generator_inverse = Fr::GENERATOR
+            .inverse()
+            .ok_or(SynthesisError::DivisionByZero)?;
+        let vanishing_inverse = domain
+            .evaluate_vanishing_polynomial(Fr::GENERATOR)
+            .inverse()
+            .ok_or(SynthesisError::DivisionByZero)?;
+        Ok(Self {
+            domain,
+            coset_powers: powers(Fr::GENERATOR),
+            coset_inverse_powers: powers(generator_inverse),
+            vanishing_inverse,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.domain.size()
+    }
+
+    /// Coefficients of `h(x)` for the libsnark R1CS-to-QAP reduction, as
+    /// `LibsnarkReduction::witness_map_from_matrices` computes them
+    pub(crate) fn witness_map(
+        &self,
+        prepared: &PreparedWitness,
+    ) -> Result<Zeroizing<Vec<Fr>>, SynthesisError> {
+        let (num_constraints, num_inputs) = (prepared.num_constraints, prepared.num_inputs);
+        // A witness from a circuit with another domain size cannot be proven with this key
+        if GeneralEvaluationDomain::<Fr>::compute_size_of_domain(num_constraints + num_inputs)
+            != Some(self.size())
+        {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+        let matrices = &prepared.matrices;
+        let assignment = &prepared.full_assignment[..];
+
+        let evaluate = |rows: &[Vec<(Fr, usize)>]| {
+            let mut evaluations = Zeroizing::new(vec![Fr::ZERO; self.size()]);
+            for (evaluation, row) in evaluations.iter_mut().zip(rows) {
+                *evaluation = evaluate_constraint(row, assignment);
+            }
+            evaluations
+        };
+        let mut a = evaluate(&matrices.a);
+        a[num_constraints..num_constraints + num_inputs].copy_from_slice(&assignment[..num_inputs]);
+        let mut b = evaluate(&matrices.b);
+        let mut c = evaluate(&matrices.c);
+        // Proving a witness that breaks a constraint would only yield a proof that fails to
+        // verify
+        if (0..num_constraints).any(|i| a[i] * b[i] != c[i]) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        for evaluations in [&mut a, &mut b, &mut c] {
+            self.to_coset(evaluations);
+        }
+
+        let mut h = a;
+        mul_kernel::mul_assign(&mut h, &b);
+        for (h, c) in h.iter_mut().zip(c.iter()) {
+            *h = (*h - c) * self.vanishing_inverse;
+        }
+        self.domain.ifft_in_place(&mut h);
+        mul_kernel::mul_assign(&mut h, &self.coset_inverse_powers);
+        Ok(h)
+    }
+
+    /// Turns evaluations over the domain into evaluations over its coset
+    fn to_coset(&self, evaluations: &mut Vec<Fr>) {
+        self.domain.ifft_in_place(evaluations);
+        mul_kernel::mul_assign(evaluations, &self.coset_powers);
+        self.domain.fft_in_place(evaluations);
+    }
+}
+
+/// Runs the MSMs for a prepared witness, the second half of `prove_circuit`
+pub fn finalize_proof<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    prepared: &PreparedWitness,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    finalize_proof_with_domain(pk, &ProvingDomain::for_key(pk)?, prepared, rng, timer)
+}
+
+/// `finalize_proof` with the domain of `pk` computed beforehand, for keys proven with
+/// repeatedly
+pub fn finalize_proof_with_domain<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    domain: &ProvingDomain,
+    prepared: &PreparedWitness,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    let r = Fr::rand(rng);
+    let s = Fr::rand(rng);
+    trace_span!("groth16_prove", num_constraints = prepared.num_constraints);
+
+    let h_acc = {
+        let h = domain.witness_map(prepared)?;
+        let h: Vec<_> = h.iter().map(|x| x.into_bigint()).collect();
+        G1Projective::msm_bigint(&pk.h_query, &h)
+    };
+
+    // `full_assignment[0]` is the constant 1, so the A and B queries' constant terms are
+    // part of these MSMs
+    let assignment: Vec<_> = prepared
+        .full_assignment
+        .iter()
+        .map(|x| x.into_bigint())
+        .collect();
+    let l_acc = G1Projective::msm_bigint(&pk.l_query, &assignment[prepared.num_inputs..]);
+    let g_a = pk.delta_g1 * r + pk.vk.alpha_g1 + msm::<G1Affine>(&pk.a_query, &assignment);
+    let g1_b = if r.is_zero() {
+        G1Projective::ZERO
+    } else {
+        pk.delta_g1 * s + pk.beta_g1 + msm::<G1Affine>(&pk.b_g1_query, &assignment)
+    };
+    let g2_b: G2Projective =
+        pk.vk.delta_g2 * s + pk.vk.beta_g2 + msm::<G2Affine>(&pk.b_g2_query, &assignment);
+    let g_c = g_a * s + g1_b * r - pk.delta_g1 * (r * s) + l_acc + h_acc;
+    timer.end_phase(Phase::Prove);
+
+    Ok(Proof {
+        a: g_a.into_affine(),
+        b: g2_b.into_affine(),
+        c: g_c.into_affine(),
+    })
+}
+
+fn msm<A: ark_ec::AffineRepr>(bases: &[A], scalars: &[<Fr as PrimeField>::BigInt]) -> A::Group
+where
+    A::Group: VariableBaseMSM<MulBase = A, ScalarField = Fr>,
+{
+    A::Group::msm_bigint(bases, scalars)
+}
+
+/// Wipes the private witness values held by a synthesized constraint system
+pub(crate) fn wipe_witness(cs: &ConstraintSystemRef<Fr>) {
+    if let Some(mut inner) = cs.borrow_mut() {
+        inner.witness_assignment.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::sample_zero_transfer;
+    use ark_groth16::Groth16;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_phased_proof_verifies() {
+        let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+                .unwrap();
+
+        let circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let public_inputs = circuit.get_public_inputs();
+
+        let mut timer = PhaseTimer::start();
+        let proof = prove_circuit(&pk, circuit, &mut rng, &mut timer).unwrap();
+        let stats = timer.finish();
+
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &public_inputs).unwrap());
+        assert!(stats.num_constraints > 0);
+        assert!(stats.total_us >= stats.witness_us + stats.constraints_us + stats.prove_us);
+    }
+
+    #[test]
+    fn test_prepared_witness_finalizes_to_a_valid_proof() {
+        let mut rng = ChaCha20Rng::from_seed([8u8; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+                .unwrap();
+
+        let circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let public_inputs = circuit.get_public_inputs();
+
+        let mut timer = PhaseTimer::start();
+        let prepared = prepare_circuit(circuit, &mut timer).unwrap();
+        assert_eq!(prepared.public_inputs(), public_inputs.as_slice());
+
+        let proof = finalize_proof(&pk, &prepared, &mut rng, &mut timer).unwrap();
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn test_unsatisfied_witness_is_not_proven() {
+        let mut rng = ChaCha20Rng::from_seed([12u8; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+                .unwrap();
+
+        let mut circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        circuit.input_nullifier_1 = Fr::from(1u64);
+        let prepared = prepare_circuit(circuit, &mut PhaseTimer::start()).unwrap();
+
+        assert!(matches!(
+            finalize_proof(&pk, &prepared, &mut rng, &mut PhaseTimer::start()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_cached_domain_proves_like_arkworks() {
+        let mut rng = ChaCha20Rng::from_seed([10u8; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+                .unwrap();
+        let domain = ProvingDomain::for_key(&pk).unwrap();
+        let circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let prepared = prepare_circuit(circuit, &mut PhaseTimer::start()).unwrap();
+
+        let mut proof_rng = ChaCha20Rng::from_seed([11u8; 32]);
+        let r = Fr::rand(&mut proof_rng);
+        let s = Fr::rand(&mut proof_rng);
+        let expected = Groth16::<Bn254>::create_proof_with_reduction_and_matrices(
+            &pk,
+            r,
+            s,
+            &prepared.matrices,
+            prepared.num_inputs,
+            prepared.num_constraints,
+            &prepared.full_assignment,
+        )
+        .unwrap();
+        for _ in 0..2 {
+            let proof = finalize_proof_with_domain(
+                &pk,
+                &domain,
+                &prepared,
+                &mut ChaCha20Rng::from_seed([11u8; 32]),
+                &mut PhaseTimer::start(),
+            )
+            .unwrap();
+            assert_eq!(proof, expected);
+        }
+    }
+
+    #[test]
+    fn test_later_proofs_reuse_the_shape_structure() {
+        let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, false])
+            .unwrap();
+        let shape = setup_circuit.shape();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit, &mut rng)
+                .unwrap();
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+
+        for _ in 0..2 {
+            let circuit = sample_zero_transfer()
+                .with_dummy_inputs([true, false])
+                .unwrap();
+            let public_inputs = circuit.get_public_inputs();
+            let prepared = prepare_circuit(circuit, &mut PhaseTimer::start()).unwrap();
+            let proof = finalize_proof(&pk, &prepared, &mut rng, &mut PhaseTimer::start()).unwrap();
+            assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &public_inputs).unwrap());
+        }
+
+        // Proofs after the first only assign the witness against the kept matrices
+        let circuit = sample_zero_transfer()
+            .with_dummy_inputs([true, false])
+            .unwrap();
+        let prepared = prepare_circuit(circuit, &mut PhaseTimer::start()).unwrap();
+        assert!(Arc::ptr_eq(
+            &prepared.matrices,
+            &cached_structure(&shape).unwrap()
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! Quaternary (4-ary) Merkle tree.
+//!
+//! Each node is `Poseidon4(c0, c1, c2, c3)` over its four children, using the t=5 hasher.
+//! Holding the same `2^26` leaves as the binary tree takes `QUAD_MERKLE_TREE_LEVEL = 13`
+//! levels instead of 26, so a membership proof is 13 permutations and 39 siblings instead
+//! of 26 permutations and 52 path elements.
+//!
+//! A path stores the three siblings of each level in order; the leaf index, read two bits
+//! per level (little-endian), says where the running hash goes among them.
+//!
+//! This module provides the tree and its gadget only. `TransactionCircuit`, its variants,
+//! the bundled keys and the pool contract all use the binary tree, so proving against a
+//! quaternary tree needs a circuit variant built on `QuadPathVar`, keys generated for it and
+//! a contract that maintains the same tree.
+
+use std::borrow::Borrow;
+
+use anyhow::anyhow;
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{AllocVar, AllocationMode, Boolean, EqGadget},
+    select::CondSelectGadget,
+    R1CSVar,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use zeroize::Zeroize;
+
+use crate::debug;
+use crate::merkle_tree::try_array_from_fn;
+use crate::poseidon_opt::{PoseidonOptimized, PoseidonOptimizedVar};
+
+/// Siblings of a leaf in a 4-ary tree of depth `N`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadPath<const N: usize> {
+    pub siblings: [[Fr; 3]; N],
+}
+
+// Like binary paths, siblings reveal the spent note's position
+impl<const N: usize> Zeroize for QuadPath<N> {
+    fn zeroize(&mut self) {
+        for sibling in self.siblings.iter_mut().flatten() {
+            sibling.zeroize();
+        }
+    }
+}
+
+impl<const N: usize> QuadPath<N> {
+    pub fn empty() -> Self {
+        Self {
+            siblings: [[Fr::ZERO; 3]; N],
+        }
+    }
+
+    /// Calculate the root of `leaf` at position `index`
+    ///
+    /// # Errors
+    /// Returns error if `index` does not fit in `2N` bits.
+    pub fn calculate_root(
+        &self,
+        leaf: &Fr,
+        index: u64,
+        hasher: &PoseidonOptimized,
+    ) -> anyhow::Result<Fr> {
+        if 2 * N < 64 && index >> (2 * N) != 0 {
+            return Err(anyhow!("Leaf index exceeds tree capacity (>= 4^{})", N));
+        }
+
+        let mut current = *leaf;
+        for (level, siblings) in self.siblings.iter().enumerate() {
+            let children = place_child(&current, siblings, (index >> (2 * level)) as usize & 3);
+            current = hasher.hash4(&children[0], &children[1], &children[2], &children[3]);
+        }
+        Ok(current)
+    }
+
+    /// Check if `leaf` is at position `index` of the tree with the given root
+    pub fn check_membership(
+        &self,
+        root: &Fr,
+        leaf: &Fr,
+        index: u64,
+        hasher: &PoseidonOptimized,
+    ) -> anyhow::Result<bool> {
+        Ok(self.calculate_root(leaf, index, hasher)? == *root)
+    }
+}
+
+/// Inserts `child` at `position` among the three `siblings`
+fn place_child(child: &Fr, siblings: &[Fr; 3], position: usize) -> [Fr; 4] {
+    let mut children = [Fr::ZERO; 4];
+    let mut siblings = siblings.iter();
+    for (slot, node) in children.iter_mut().enumerate() {
+        *node = if slot == position {
+            *child
+        } else {
+            *siblings.next().unwrap()
+        };
+    }
+    children
+}
+
+/// Append-only 4-ary Merkle tree of depth `N`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadMerkleTree<const N: usize> {
+    /// Nodes of every level for the occupied positions; `nodes[0]` are the leaves
+    nodes: Vec<Vec<Fr>>,
+    /// Hash of an empty subtree at each level, and of the whole empty tree at `N`
+    empty_hashes: Vec<Fr>,
+    root: Fr,
+}
+
+impl<const N: usize> QuadMerkleTree<N> {
+    pub fn new_empty(hasher: &PoseidonOptimized, empty_leaf: &Fr) -> Self {
+        let mut empty_hashes = Vec::with_capacity(N + 1);
+        empty_hashes.push(*empty_leaf);
+        for level in 0..N {
+            let empty = empty_hashes[level];
+            empty_hashes.push(hasher.hash4(&empty, &empty, &empty, &empty));
+        }
+
+        Self {
+            nodes: vec![Vec::new(); N],
+            root: empty_hashes[N],
+            empty_hashes,
+        }
+    }
+
+    /// Appends a leaf, updating the nodes above it
+    pub fn insert(&mut self, leaf: Fr, hasher: &PoseidonOptimized) -> anyhow::Result<()> {
+        if self.is_full() {
+            return Err(anyhow!("Quad Merkle tree is full (depth {})", N));
+        }
+
+        self.nodes[0].push(leaf);
+        let mut position = self.nodes[0].len() - 1;
+        for level in 0..N {
+            let group = position / 4;
+            let children: Vec<Fr> = (0..4)
+                .map(|slot| self.node(level, group * 4 + slot))
+                .collect();
+            let parent = hasher.hash4(&children[0], &children[1], &children[2], &children[3]);
+
+            if level + 1 == N {
+                self.root = parent;
+            } else if group < self.nodes[level + 1].len() {
+                self.nodes[level + 1][group] = parent;
+            } else {
+                self.nodes[level + 1].push(parent);
+            }
+            position = group;
+        }
+        Ok(())
+    }
+
+    pub fn root(&self) -> Fr {
+        self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes[0].is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        2 * N < usize::BITS as usize && self.len() >= 1 << (2 * N)
+    }
+
+    pub fn leaves(&self) -> &[Fr] {
+        &self.nodes[0]
+    }
+
+    /// Generate the membership proof of the leaf at `index`
+    pub fn generate_membership_proof(&self, index: usize) -> anyhow::Result<QuadPath<N>> {
+        if index >= self.len() {
+            return Err(anyhow!(
+                "Index {} out of bounds (tree has {} leaves)",
+                index,
+                self.len()
+            ));
+        }
+
+        let mut path = QuadPath::empty();
+        let mut position = index;
+        for (level, siblings) in path.siblings.iter_mut().enumerate() {
+            let group = position / 4;
+            let others = (0..4).filter(|slot| group * 4 + slot != position);
+            for (sibling, slot) in siblings.iter_mut().zip(others) {
+                *sibling = self.node(level, group * 4 + slot);
+            }
+            position = group;
+        }
+        Ok(path)
+    }
+
+    fn node(&self, level: usize, position: usize) -> Fr {
+        self.nodes[level]
+            .get(position)
+            .copied()
+            .unwrap_or(self.empty_hashes[level])
+    }
+}
+
+/// Circuit variable for a 4-ary Merkle path
+#[derive(Debug, Clone)]
+pub struct QuadPathVar<const N: usize> {
+    siblings: [[FpVar<Fr>; 3]; N],
+}
+
+impl<const N: usize> QuadPathVar<N> {
+    /// Check membership in circuit of `leaf` at position `index`
+    pub fn check_membership(
+        &self,
+        root: &FpVar<Fr>,
+        leaf: &FpVar<Fr>,
+        index: &FpVar<Fr>,
+        hasher: &PoseidonOptimizedVar,
+    ) -> Result<Boolean<Fr>, SynthesisError> {
+        let index_bits = Self::index_bits(index)?;
+        root.is_eq(&self.root_hash(leaf, &index_bits, hasher)?)
+    }
+
+    /// Allocates the `2N` little-endian bits of `index` and enforces that they recompose to
+    /// it, which also enforces `index < 4^N`
+    pub fn index_bits(index: &FpVar<Fr>) -> Result<Vec<Boolean<Fr>>, SynthesisError> {
+        let cs = index.cs();
+        let bits = (0..2 * N)
+            .map(|bit| {
+                Boolean::new_witness(ark_relations::ns!(cs, "index_bit"), || {
+                    Ok(index.value()?.into_bigint().get_bit(bit))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Boolean::le_bits_to_fp(&bits)?.enforce_equal(index)?;
+        Ok(bits)
+    }
+
+    /// Calculate root hash in circuit; bits `2l` and `2l + 1` of `index_bits` give the
+    /// position of the running hash at level `l`. Fails with `Unsatisfiable` unless
+    /// `index_bits` holds two bits per level.
+    pub fn root_hash(
+        &self,
+        leaf: &FpVar<Fr>,
+        index_bits: &[Boolean<Fr>],
+        hasher: &PoseidonOptimizedVar,
+    ) -> Result<FpVar<Fr>, SynthesisError> {
+        if index_bits.len() != 2 * N {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut current = leaf.clone();
+
+        let cs = leaf.cs();
+        for (level, ([s0, s1, s2], bits)) in
+            self.siblings.iter().zip(index_bits.chunks(2)).enumerate()
+        {
+            let _level_region = debug::region(&cs, || format!("level_{}", level));
+            let (low, high) = (&bits[0], &bits[1]);
+            let at_0 = !low & !high;
+            let at_1 = low & !high;
+            let at_2 = !low & high;
+            let at_3 = low & high;
+
+            // Position 0: [cur, s0, s1, s2] ... position 3: [s0, s1, s2, cur]
+            let c0 = FpVar::conditionally_select(&at_0, &current, s0)?;
+            let c1 = FpVar::conditionally_select(
+                &at_1,
+                &current,
+                &FpVar::conditionally_select(high, s1, s0)?,
+            )?;
+            let c2 = FpVar::conditionally_select(
+                &at_2,
+                &current,
+                &FpVar::conditionally_select(&at_3, s2, s1)?,
+            )?;
+            let c3 = FpVar::conditionally_select(&at_3, &current, s2)?;
+
+            current = hasher.hash4(&c0, &c1, &c2, &c3)?;
+        }
+
+        Ok(current)
+    }
+}
+
+impl<const N: usize> AllocVar<QuadPath<N>, Fr> for QuadPathVar<N> {
+    fn new_variable<T: Borrow<QuadPath<N>>>(
+        cs: impl Into<Namespace<Fr>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let path = f()?;
+        let path = path.borrow();
+        Ok(QuadPathVar {
+            siblings: try_array_from_fn(|level| {
+                try_array_from_fn(|i| {
+                    let sibling = path.siblings[level][i];
+                    FpVar::<Fr>::new_variable(
+                        ark_relations::ns!(cs, "sibling"),
+                        || Ok(sibling),
+                        mode,
+                    )
+                })
+            })?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MERKLE_TREE_LEVEL, QUAD_MERKLE_TREE_LEVEL};
+    use crate::merkle_tree::{Path, PathVar};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_quad_paths_match_native_and_gadget_roots() {
+        let hasher = PoseidonOptimized::new_t5();
+        let mut tree = QuadMerkleTree::<3>::new_empty(&hasher, &Fr::ZERO);
+        for leaf in 1..=23u64 {
+            tree.insert(Fr::from(leaf), &hasher).unwrap();
+        }
+        let root = tree.root();
+
+        for index in [0usize, 1, 2, 3, 5, 17, 22] {
+            let leaf = tree.leaves()[index];
+            let path = tree.generate_membership_proof(index).unwrap();
+            assert!(path
+                .check_membership(&root, &leaf, index as u64, &hasher)
+                .unwrap());
+            assert!(!path
+                .check_membership(&root, &leaf, index as u64 ^ 1, &hasher)
+                .unwrap());
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let leaf_var = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+            let path_var = QuadPathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
+            path_var
+                .check_membership(
+                    &root_var,
+                    &leaf_var,
+                    &index_var,
+                    &PoseidonOptimizedVar::new_t5(),
+                )
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            assert!(cs.is_satisfied().unwrap(), "leaf {}", index);
+        }
+    }
+
+    #[test]
+    fn test_root_hash_rejects_wrong_bit_count() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let leaf = FpVar::new_witness(cs.clone(), || Ok(Fr::ZERO)).unwrap();
+        let path_var =
+            QuadPathVar::<3>::new_witness(cs.clone(), || Ok(QuadPath::<3>::empty())).unwrap();
+        let bits = vec![Boolean::FALSE; 5];
+        assert!(matches!(
+            path_var.root_hash(&leaf, &bits, &PoseidonOptimizedVar::new_t5()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_quad_gadget_is_cheaper_for_the_same_capacity() {
+        let binary = ConstraintSystem::<Fr>::new_ref();
+        let leaf = FpVar::new_witness(binary.clone(), || Ok(Fr::ZERO)).unwrap();
+        let index = FpVar::new_witness(binary.clone(), || Ok(Fr::ZERO)).unwrap();
+        let _ = PathVar::<MERKLE_TREE_LEVEL>::new_witness(binary.clone(), || {
+            Ok(Path::<MERKLE_TREE_LEVEL>::empty())
+        })
+        .unwrap()
+        .check_membership(&leaf, &leaf, &index, &PoseidonOptimizedVar::new_t3())
+        .unwrap();
+
+        let quad = ConstraintSystem::<Fr>::new_ref();
+        let leaf = FpVar::new_witness(quad.clone(), || Ok(Fr::ZERO)).unwrap();
+        let index = FpVar::new_witness(quad.clone(), || Ok(Fr::ZERO)).unwrap();
+        let _ = QuadPathVar::<QUAD_MERKLE_TREE_LEVEL>::new_witness(quad.clone(), || {
+            Ok(QuadPath::<QUAD_MERKLE_TREE_LEVEL>::empty())
+        })
+        .unwrap()
+        .check_membership(&leaf, &leaf, &index, &PoseidonOptimizedVar::new_t5())
+        .unwrap();
+
+        assert!(
+            quad.num_constraints() < binary.num_constraints(),
+            "quad {} vs binary {} constraints",
+            quad.num_constraints(),
+            binary.num_constraints()
+        );
+        const { assert!(3 * QUAD_MERKLE_TREE_LEVEL < 2 * MERKLE_TREE_LEVEL) };
+    }
+}
@@ -0,0 +1,299 @@
+//! Payment receipts for merchants and exchanges.
+//!
+//! A receipt discloses a single output note: the recipient's address, the amount and the
+//! blinding, which open the output commitment, plus the commitment's Merkle path to a pool
+//! root. A verifier recomputes the commitment from the address's note public key, checks the
+//! path, and compares the root against the pool's root history and the address against its
+//! own. Nothing else about the payer is disclosed: the spent notes, change and other outputs
+//! stay hidden, and the blinding alone cannot spend or nullify the note.
+//!
+//! The payer may sign the receipt with their `auth` login key over the domain tag and the
+//! receipt's fields; the path is not signed since the root and leaf index determine it.
+
+use crate::address::ShieldedAddress;
+use crate::auth;
+use crate::circuit::compute_commitment;
+use crate::constants::MERKLE_TREE_LEVEL;
+use crate::field::{fr_to_bytes_le, parse_fr, FieldParseMode};
+use crate::merkle_tree::{Path, SparseMerkleTree};
+use crate::note::Note;
+use crate::poseidon_opt::PoseidonOptimized;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+/// Receipt format version
+pub const RECEIPT_VERSION: u8 = 1;
+
+const SIGNATURE_DOMAIN: &[u8] = b"vortex-payment-receipt-v1";
+
+/// Disclosure of one paid output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentReceipt {
+    pub version: u8,
+    /// Decimal pool id
+    pub vortex: String,
+    /// Shielded address paid
+    pub recipient: String,
+    /// Amount in MIST
+    pub amount: u64,
+    /// Decimal blinding of the output note
+    pub blinding: String,
+    /// Decimal output commitment
+    pub commitment: String,
+    pub leaf_index: u64,
+    /// Decimal root the path leads to
+    pub root: String,
+    pub merkle_path: Vec<[String; 2]>,
+    /// Sui digest of the transaction that created the output
+    pub tx_digest: Option<String>,
+    /// Hex `auth` public key of the payer, for signed receipts
+    pub signer_public_key: Option<String>,
+    /// Hex `auth` signature
+    pub signature: Option<String>,
+}
+
+/// Issues a receipt for `note`, paid to `recipient` and inserted at `leaf_index` of `tree`,
+/// signed with the login key of `spending_key` when given.
+///
+/// # Errors
+/// Returns error if the note is a legacy note or was not paid to `recipient`, or its
+/// commitment is not at `leaf_index` of `tree`.
+pub fn create_payment_receipt(
+    note: &Note,
+    recipient: &str,
+    leaf_index: u64,
+    tree: &SparseMerkleTree<MERKLE_TREE_LEVEL>,
+    vortex: &Fr,
+    tx_digest: Option<String>,
+    spending_key: Option<&Fr>,
+) -> Result<PaymentReceipt> {
+    if note.legacy_commitment {
+        return Err(anyhow!(
+            "Legacy notes do not bind the pool and cannot be receipted"
+        ));
+    }
+    let address = ShieldedAddress::decode(recipient)?;
+    let public_key =
+        parse_fr(&note.public_key, FieldParseMode::Strict).context("Invalid note public key")?;
+    if public_key != address.note_public_key {
+        return Err(anyhow!("Note was not paid to {}", recipient));
+    }
+    let commitment = note.commitment(vortex)?;
+    if tree.leaves.get(leaf_index as usize) != Some(&commitment) {
+        return Err(anyhow!("Note is not at leaf {} of the tree", leaf_index));
+    }
+
+    let s = |x: &Fr| x.into_bigint().to_string();
+    let path = tree.generate_membership_proof(leaf_index as usize)?;
+    let mut receipt = PaymentReceipt {
+        version: RECEIPT_VERSION,
+        vortex: s(vortex),
+        recipient: address.encode(),
+        amount: note.amount,
+        blinding: note.blinding.clone(),
+        commitment: s(&commitment),
+        leaf_index,
+        root: s(&tree.root()),
+        merkle_path: path.path.iter().map(|(l, r)| [s(l), s(r)]).collect(),
+        tx_digest,
+        signer_public_key: None,
+        signature: None,
+    };
+    if let Some(spending_key) = spending_key {
+        let signature = auth::sign_challenge(spending_key, &signing_message(&receipt)?)?;
+        receipt.signer_public_key = Some(hex::encode(auth::auth_public_key(spending_key)));
+        receipt.signature = Some(hex::encode(signature));
+    }
+    Ok(receipt)
+}
+
+/// Checks that a receipt's note opens its commitment under the recipient's key, that the
+/// commitment sits at `leaf_index` under `root`, and the payer's signature if present.
+///
+/// Callers still check that `root` is a root the pool has had and that `recipient` is them.
+///
+/// # Errors
+/// Returns error naming the first check that fails.
+pub fn verify_payment_receipt(receipt: &PaymentReceipt) -> Result<()> {
+    if receipt.version != RECEIPT_VERSION {
+        return Err(anyhow!("Unsupported receipt version {}", receipt.version));
+    }
+    let field = |name: &str, value: &str| {
+        parse_fr(value, FieldParseMode::Strict).with_context(|| format!("Invalid receipt {}", name))
+    };
+    let address = ShieldedAddress::decode(&receipt.recipient)?;
+    let commitment = compute_commitment(
+        &Fr::from(receipt.amount),
+        &address.note_public_key,
+        &field("blinding", &receipt.blinding)?,
+        &field("vortex", &receipt.vortex)?,
+    );
+    if commitment != field("commitment", &receipt.commitment)? {
+        return Err(anyhow!(
+            "Amount and blinding do not open the commitment for this recipient"
+        ));
+    }
+
+    if receipt.merkle_path.len() != MERKLE_TREE_LEVEL {
+        return Err(anyhow!(
+            "Invalid Merkle path length: expected {}, got {}",
+            MERKLE_TREE_LEVEL,
+            receipt.merkle_path.len()
+        ));
+    }
+    let mut path = Path::<MERKLE_TREE_LEVEL>::empty();
+    for (level, [left, right]) in receipt.merkle_path.iter().enumerate() {
+        path.path[level] = (field("path", left)?, field("path", right)?);
+    }
+    let root = path.calculate_root_at(
+        &commitment,
+        &Fr::from(receipt.leaf_index),
+        &PoseidonOptimized::new_t3(),
+    )?;
+    if root != field("root", &receipt.root)? {
+        return Err(anyhow!(
+            "Commitment is not at leaf {} under the receipt's root",
+            receipt.leaf_index
+        ));
+    }
+
+    match (&receipt.signer_public_key, &receipt.signature) {
+        (None, None) => Ok(()),
+        (Some(public_key), Some(signature)) => {
+            let public_key = hex::decode(public_key).context("Invalid signer public key hex")?;
+            let signature = hex::decode(signature).context("Invalid signature hex")?;
+            if !auth::verify_challenge(&public_key, &signing_message(receipt)?, &signature)? {
+                return Err(anyhow!("Receipt signature is invalid"));
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!(
+            "Receipt has a signer key without a signature or vice versa"
+        )),
+    }
+}
+
+fn signing_message(receipt: &PaymentReceipt) -> Result<Vec<u8>> {
+    let field = |value: &str| -> Result<[u8; 32]> {
+        Ok(fr_to_bytes_le(&parse_fr(value, FieldParseMode::Strict)?))
+    };
+    let mut message = SIGNATURE_DOMAIN.to_vec();
+    message.push(receipt.version);
+    message.extend_from_slice(&field(&receipt.vortex)?);
+    message.extend_from_slice(&field(&receipt.commitment)?);
+    message.extend_from_slice(&field(&receipt.root)?);
+    message.extend_from_slice(&receipt.leaf_index.to_le_bytes());
+    message.extend_from_slice(&receipt.amount.to_le_bytes());
+    message.extend_from_slice(&field(&receipt.blinding)?);
+    for text in [Some(&receipt.recipient), receipt.tx_digest.as_ref()] {
+        let text = text.map_or(&[][..], |text| text.as_bytes());
+        message.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        message.extend_from_slice(text);
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ZERO_VALUE;
+    use crate::note::create_output_for;
+    use crate::poseidon_opt::fr_from_str;
+    use crate::viewing::SpendingKeys;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    fn paid() -> (Note, String, SparseMerkleTree<MERKLE_TREE_LEVEL>, Fr) {
+        let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+        let vortex = Fr::from(5u64);
+        let payer = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+        let merchant = SpendingKeys::from_seed(&[2u8; 32]).unwrap();
+        let address = ShieldedAddress::from_viewing_key(&merchant.viewing).encode();
+        let output =
+            create_output_for(&address, 42_000, &vortex, &payer.viewing, None, &mut rng).unwrap();
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+        let commitment = parse_fr(&output.commitment, FieldParseMode::Strict).unwrap();
+        tree.bulk_insert(
+            &[Fr::from(7u64), Fr::from(8u64), Fr::from(9u64), commitment],
+            &hasher,
+        )
+        .unwrap();
+        (output.note, address, tree, vortex)
+    }
+
+    #[test]
+    fn test_receipts_verify_signed_and_unsigned() {
+        let (note, address, tree, vortex) = paid();
+        let payer = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+
+        let receipt =
+            create_payment_receipt(&note, &address, 3, &tree, &vortex, None, None).unwrap();
+        verify_payment_receipt(&receipt).unwrap();
+        assert_eq!(receipt.root, tree.root().into_bigint().to_string());
+
+        let signed = create_payment_receipt(
+            &note,
+            &address,
+            3,
+            &tree,
+            &vortex,
+            Some("9mX2".into()),
+            Some(payer.spending_key()),
+        )
+        .unwrap();
+        verify_payment_receipt(&signed).unwrap();
+        let json = serde_json::to_string(&signed).unwrap();
+        verify_payment_receipt(&serde_json::from_str(&json).unwrap()).unwrap();
+
+        assert!(create_payment_receipt(&note, &address, 2, &tree, &vortex, None, None).is_err());
+    }
+
+    #[test]
+    fn test_altered_receipts_are_rejected() {
+        let (note, address, tree, vortex) = paid();
+        let payer = SpendingKeys::from_seed(&[1u8; 32]).unwrap();
+        let receipt = create_payment_receipt(
+            &note,
+            &address,
+            3,
+            &tree,
+            &vortex,
+            Some("9mX2".into()),
+            Some(payer.spending_key()),
+        )
+        .unwrap();
+
+        let mut inflated = receipt.clone();
+        inflated.amount += 1;
+        assert!(verify_payment_receipt(&inflated)
+            .unwrap_err()
+            .to_string()
+            .contains("open"));
+
+        let other = SpendingKeys::from_seed(&[3u8; 32]).unwrap();
+        let mut redirected = receipt.clone();
+        redirected.recipient = ShieldedAddress::from_viewing_key(&other.viewing).encode();
+        assert!(verify_payment_receipt(&redirected).is_err());
+
+        let mut moved = receipt.clone();
+        moved.leaf_index = 2;
+        assert!(verify_payment_receipt(&moved).is_err());
+
+        let mut relabeled = receipt.clone();
+        relabeled.tx_digest = Some("other".into());
+        assert!(verify_payment_receipt(&relabeled)
+            .unwrap_err()
+            .to_string()
+            .contains("signature"));
+
+        let mut unsigned = receipt;
+        unsigned.signature = None;
+        assert!(verify_payment_receipt(&unsigned).is_err());
+    }
+}
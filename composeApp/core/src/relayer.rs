@@ -0,0 +1,360 @@
+//! Relayer protocol for gasless withdrawals and transfers.
+//!
+//! The host does the HTTP calls; this module owns the wire format so every platform sends
+//! the same request and applies the same checks:
+//! - `parse_fee_quote` validates a relayer's fee quote
+//! - `ext_data_hash` computes the `ext_data_hash` public input that binds ext data to a proof
+//! - `build_relay_request` binds a proof and its ext data to that quote
+//! - `verify_relay_response` recomputes the transaction digest the relayer reports and
+//!   checks the submitted transaction carries our proof
+
+use crate::field::{parse_fr, FieldParseMode};
+use crate::interop::ProofOutput;
+use crate::sui_tx;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use base64::Engine;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use serde::{Deserialize, Serialize};
+
+/// Domain prefix Sui hashes in front of BCS `TransactionData` to form its digest
+const TRANSACTION_DATA_PREFIX: &[u8] = b"TransactionData::";
+
+/// A relayer's offer to submit one transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct RelayerFeeQuote {
+    pub quote_id: String,
+    /// Address that receives the fee; must be the `relayer` field of the ext data
+    pub relayer_address: String,
+    /// Fee in MIST, deducted from the withdrawn value
+    pub fee: u64,
+    /// Unix time in milliseconds after which the relayer rejects the quote
+    pub expires_at_ms: u64,
+}
+
+/// Public transaction data checked by the pool next to the proof (`vortex_ext_data::new`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ExtData {
+    /// Amount moved in or out of the pool, in MIST
+    pub value: u64,
+    /// `true` for deposits, `false` for withdrawals and transfers
+    pub is_deposit: bool,
+    pub relayer: String,
+    pub relayer_fee: u64,
+    pub encrypted_output_0: Vec<u8>,
+    pub encrypted_output_1: Vec<u8>,
+}
+
+/// Body of a relay request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayRequest {
+    pub quote_id: String,
+    pub pool_id: String,
+    /// Address that receives the withdrawn coin
+    pub recipient: String,
+    pub proof_serialized_hex: String,
+    pub public_inputs: Vec<String>,
+    pub ext_data: ExtData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_nonce: Option<String>,
+}
+
+/// A relayer's response once it has submitted the transaction, after verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct RelayReceipt {
+    /// Base58 transaction digest, recomputed from the transaction bytes
+    pub digest: String,
+    pub tx_bytes: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRelayResponse {
+    digest: String,
+    /// Base64 BCS `TransactionData`
+    tx_bytes: String,
+}
+
+/// Parses and validates a fee quote returned by a relayer.
+///
+/// # Errors
+/// Returns error if the JSON is malformed, the relayer address is invalid or the quote has
+/// expired at `now_ms`.
+pub fn parse_fee_quote(quote_json: &str, now_ms: u64) -> Result<RelayerFeeQuote> {
+    let mut quote: RelayerFeeQuote =
+        serde_json::from_str(quote_json).context("Failed to parse fee quote JSON")?;
+    quote.relayer_address = normalize_address(&quote.relayer_address)
+        .context("Fee quote has an invalid relayer address")?;
+    check_not_expired(&quote, now_ms)?;
+    Ok(quote)
+}
+
+/// Assembles the relay request for a withdrawal or transfer proof.
+///
+/// # Errors
+/// Returns error if the ext data does not pay `quote`, the fee exceeds the value, the
+/// request is a deposit, an address is invalid or the quote has expired at `now_ms`.
+pub fn build_relay_request(
+    proof: &ProofOutput,
+    ext_data: &ExtData,
+    quote: &RelayerFeeQuote,
+    pool_id: &str,
+    recipient: &str,
+    now_ms: u64,
+) -> Result<RelayRequest> {
+    check_not_expired(quote, now_ms)?;
+    check_pays_quote(ext_data, quote)?;
+
+    // ext_data_hash is the last public input of every circuit variant
+    let proven_hash = proof
+        .public_inputs
+        .last()
+        .ok_or_else(|| anyhow!("Proof has no ext_data_hash public input"))?;
+    if parse_fr(proven_hash, FieldParseMode::Strict)? != ext_data_hash(ext_data)? {
+        return Err(anyhow!("Proof was generated for different ext data"));
+    }
+
+    Ok(RelayRequest {
+        quote_id: quote.quote_id.clone(),
+        pool_id: normalize_address(pool_id).context("Invalid pool id")?,
+        recipient: normalize_address(recipient).context("Invalid recipient address")?,
+        proof_serialized_hex: proof.proof_serialized_hex.clone(),
+        public_inputs: proof.public_inputs.clone(),
+        ext_data: ext_data.clone(),
+        session_nonce: proof.session_nonce.clone(),
+    })
+}
+
+/// Checks a relayer's submission response against the proof that was sent.
+///
+/// The digest is recomputed from the returned transaction bytes, so a relayer cannot report
+/// a digest for a transaction other than the one it built, and that transaction must embed
+/// the serialized proof.
+///
+/// # Errors
+/// Returns error if the response is malformed, the digest does not match the transaction
+/// bytes, or the transaction does not contain the proof.
+pub fn verify_relay_response(response_json: &str, proof: &ProofOutput) -> Result<RelayReceipt> {
+    let response: RawRelayResponse =
+        serde_json::from_str(response_json).context("Failed to parse relay response JSON")?;
+    let tx_bytes = base64::engine::general_purpose::STANDARD
+        .decode(response.tx_bytes.trim())
+        .context("Relay response has invalid base64 transaction bytes")?;
+
+    let digest = transaction_digest(&tx_bytes);
+    if digest != response.digest.trim() {
+        return Err(anyhow!(
+            "Relayer reported digest {} but the transaction bytes hash to {}",
+            response.digest,
+            digest
+        ));
+    }
+
+    let proof_bytes =
+        hex::decode(&proof.proof_serialized_hex).context("Failed to decode proof hex")?;
+    if proof_bytes.is_empty()
+        || !tx_bytes
+            .windows(proof_bytes.len())
+            .any(|window| window == proof_bytes.as_slice())
+    {
+        return Err(anyhow!(
+            "Relayed transaction does not contain the submitted proof"
+        ));
+    }
+
+    Ok(RelayReceipt { digest, tx_bytes })
+}
+
+/// The `ext_data_hash` public input: Blake2b-256 of the BCS-encoded ext data, reduced
+/// modulo the BN254 scalar field.
+///
+/// # Errors
+/// Returns error if the relayer address is invalid.
+pub fn ext_data_hash(ext_data: &ExtData) -> Result<Fr> {
+    let bcs = sui_tx::ext_data_arguments(ext_data)?.concat();
+    Ok(Fr::from_le_bytes_mod_order(&Blake2b::<U32>::digest(&bcs)))
+}
+
+/// Sui transaction digest: Base58 of Blake2b-256 over `TransactionData::` + BCS bytes
+pub fn transaction_digest(tx_bytes: &[u8]) -> String {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(TRANSACTION_DATA_PREFIX);
+    hasher.update(tx_bytes);
+    bs58::encode(hasher.finalize()).into_string()
+}
+
+/// Normalizes a Sui address to `0x` followed by 64 lowercase hex digits
+pub fn normalize_address(address: &str) -> Result<String> {
+    let hex_part = address
+        .trim()
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow!("Address '{}' must start with 0x", address))?;
+    if hex_part.is_empty()
+        || hex_part.len() > 64
+        || !hex_part.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(anyhow!("Address '{}' is not 1 to 64 hex digits", address));
+    }
+    Ok(format!("0x{:0>64}", hex_part.to_ascii_lowercase()))
+}
+
+/// Fails unless `ext_data` is a withdrawal paying `quote`'s relayer its fee out of the value
+pub(crate) fn check_pays_quote(ext_data: &ExtData, quote: &RelayerFeeQuote) -> Result<()> {
+    if ext_data.is_deposit {
+        return Err(anyhow!(
+            "Deposits spend the sender's own coins and cannot be relayed"
+        ));
+    }
+    if normalize_address(&ext_data.relayer)? != normalize_address(&quote.relayer_address)? {
+        return Err(anyhow!(
+            "Ext data pays relayer {} but the quote is from {}",
+            ext_data.relayer,
+            quote.relayer_address
+        ));
+    }
+    if ext_data.relayer_fee != quote.fee {
+        return Err(anyhow!(
+            "Ext data fee {} does not match the quoted fee {}",
+            ext_data.relayer_fee,
+            quote.fee
+        ));
+    }
+    if ext_data.relayer_fee > ext_data.value {
+        return Err(anyhow!(
+            "Relayer fee {} exceeds the withdrawn value {}",
+            ext_data.relayer_fee,
+            ext_data.value
+        ));
+    }
+    Ok(())
+}
+
+fn check_not_expired(quote: &RelayerFeeQuote, now_ms: u64) -> Result<()> {
+    if now_ms >= quote.expires_at_ms {
+        return Err(anyhow!(
+            "Fee quote {} expired at {} (now {})",
+            quote.quote_id,
+            quote.expires_at_ms,
+            now_ms
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELAYER: &str = "0xabc";
+
+    fn quote() -> RelayerFeeQuote {
+        parse_fee_quote(
+            r#"{"quoteId":"q1","relayerAddress":"0xABC","fee":5000,"expiresAtMs":2000}"#,
+            1000,
+        )
+        .unwrap()
+    }
+
+    fn proof_with_bytes(bytes: &[u8]) -> ProofOutput {
+        let mut public_inputs = vec!["0".to_string(); 8];
+        public_inputs.push(
+            ext_data_hash(&ext_data(5000))
+                .unwrap()
+                .into_bigint()
+                .to_string(),
+        );
+        ProofOutput {
+            proof_a: Vec::new(),
+            proof_b: Vec::new(),
+            proof_c: Vec::new(),
+            public_inputs,
+            proof_serialized_hex: hex::encode(bytes),
+            public_inputs_serialized_hex: String::new(),
+            session_nonce: None,
+        }
+    }
+
+    fn ext_data(relayer_fee: u64) -> ExtData {
+        ExtData {
+            value: 1_000_000,
+            is_deposit: false,
+            relayer: RELAYER.to_string(),
+            relayer_fee,
+            encrypted_output_0: vec![1],
+            encrypted_output_1: vec![2],
+        }
+    }
+
+    #[test]
+    fn test_fee_quote_validation() {
+        assert_eq!(quote().relayer_address, normalize_address(RELAYER).unwrap());
+        let expired = r#"{"quoteId":"q1","relayerAddress":"0x1","fee":1,"expiresAtMs":10}"#;
+        assert!(parse_fee_quote(expired, 10).is_err());
+        let bad_address = r#"{"quoteId":"q1","relayerAddress":"abc","fee":1,"expiresAtMs":10}"#;
+        assert!(parse_fee_quote(bad_address, 0).is_err());
+    }
+
+    #[test]
+    fn test_build_request_checks_ext_data_against_quote() {
+        let proof = proof_with_bytes(&[9, 9, 9]);
+        let request =
+            build_relay_request(&proof, &ext_data(5000), &quote(), "0x5", "0x6", 1500).unwrap();
+        assert_eq!(request.quote_id, "q1");
+        assert_eq!(request.recipient, normalize_address("0x6").unwrap());
+
+        assert!(
+            build_relay_request(&proof, &ext_data(4000), &quote(), "0x5", "0x6", 1500).is_err()
+        );
+        assert!(
+            build_relay_request(&proof, &ext_data(5000), &quote(), "0x5", "0x6", 2000).is_err()
+        );
+
+        let mut deposit = ext_data(5000);
+        deposit.is_deposit = true;
+        assert!(build_relay_request(&proof, &deposit, &quote(), "0x5", "0x6", 1500).is_err());
+
+        let mut other_outputs = ext_data(5000);
+        other_outputs.encrypted_output_1 = vec![3];
+        assert!(build_relay_request(&proof, &other_outputs, &quote(), "0x5", "0x6", 1500).is_err());
+    }
+
+    #[test]
+    fn test_ext_data_hash_matches_bcs_layout() {
+        let mut bcs = Vec::new();
+        bcs.extend_from_slice(&1_000_000u64.to_le_bytes());
+        bcs.push(0);
+        bcs.extend_from_slice(&hex::decode(&normalize_address(RELAYER).unwrap()[2..]).unwrap());
+        bcs.extend_from_slice(&5000u64.to_le_bytes());
+        bcs.extend_from_slice(&[1, 1, 1, 2]);
+        let expected = Fr::from_le_bytes_mod_order(&Blake2b::<U32>::digest(&bcs));
+
+        assert_eq!(ext_data_hash(&ext_data(5000)).unwrap(), expected);
+        assert_ne!(ext_data_hash(&ext_data(4000)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_verify_relay_response_recomputes_digest() {
+        let proof_bytes = [7u8, 8, 9, 10];
+        let tx_bytes = [0u8, 1, 7, 8, 9, 10, 2];
+        let tx_b64 = base64::engine::general_purpose::STANDARD.encode(tx_bytes);
+        let digest = transaction_digest(&tx_bytes);
+
+        let response = format!(r#"{{"digest":"{}","txBytes":"{}"}}"#, digest, tx_b64);
+        let receipt = verify_relay_response(&response, &proof_with_bytes(&proof_bytes)).unwrap();
+        assert_eq!(receipt.digest, digest);
+
+        let wrong_digest = format!(r#"{{"digest":"{}","txBytes":"{}"}}"#, "1111", tx_b64);
+        assert!(verify_relay_response(&wrong_digest, &proof_with_bytes(&proof_bytes)).is_err());
+
+        assert!(verify_relay_response(&response, &proof_with_bytes(&[1, 2, 3, 4, 5])).is_err());
+    }
+}
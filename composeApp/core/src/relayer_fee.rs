@@ -0,0 +1,190 @@
+//! Relayer fee arithmetic, checked against the circuit before proving.
+//!
+//! A relayed withdrawal takes `value = withdraw + fee` out of the pool: the recipient's
+//! address gets `value - fee`, the relayer gets `fee`, and what the spent notes hold beyond
+//! `value` is refunded to the sender as the change output. `split_relayer_fee` derives those
+//! numbers from a quote; `check_relayed_input` asserts that a filled-in `ProofInput` and its
+//! ext data agree with each other and with the quote the way the circuit and the relayer
+//! will check them, so a mismatch fails here instead of after a proof has been paid for.
+
+use crate::amount::{self, TransferIntent};
+use crate::field::{parse_fr, FieldParseMode};
+use crate::interop::ProofInput;
+use crate::relayer::{self, ExtData, RelayerFeeQuote};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use serde::{Deserialize, Serialize};
+
+/// How a relayed withdrawal is divided
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSplit {
+    /// Ext data `value`: everything leaving the pool
+    pub value: u64,
+    /// Ext data `relayer_fee`
+    pub fee: u64,
+    /// What the recipient's address receives, `value - fee`
+    pub amount_after_fee: u64,
+    /// Change returned to the sender as a shielded output (`out_amount_1`)
+    pub refund: u64,
+    /// Decimal `public_amount`, the field encoding of `-value`
+    pub public_amount: String,
+}
+
+/// Splits a withdrawal of `withdraw` from notes holding `input_amounts` under `quote`.
+///
+/// # Errors
+/// Returns error if the notes cannot cover the withdrawal plus the fee, or an amount is out
+/// of range (see `amount::plan_amounts`).
+pub fn split_relayer_fee(
+    input_amounts: &[u64],
+    withdraw: u64,
+    quote: &RelayerFeeQuote,
+) -> Result<FeeSplit> {
+    let plan = amount::plan_amounts(&TransferIntent {
+        input_amounts: input_amounts.to_vec(),
+        withdraw,
+        fee: quote.fee,
+        ..Default::default()
+    })?;
+    Ok(FeeSplit {
+        value: plan.ext_value,
+        fee: quote.fee,
+        amount_after_fee: withdraw,
+        refund: plan.change_amount,
+        public_amount: plan.public_amount,
+    })
+}
+
+/// Checks that proving `input` with `ext_data` yields a proof the relayer behind `quote`
+/// accepts.
+///
+/// # Errors
+/// Returns error naming the first violated rule: a deposit, a fee or relayer other than
+/// quoted, a fee above the value, a `public_amount` other than `-value`, amounts that do not
+/// balance, or an `ext_data_hash` computed from different ext data.
+pub fn check_relayed_input(
+    input: &ProofInput,
+    ext_data: &ExtData,
+    quote: &RelayerFeeQuote,
+) -> Result<()> {
+    relayer::check_pays_quote(ext_data, quote)?;
+
+    let parse = |name: &str, value: &str| {
+        parse_fr(value, FieldParseMode::Strict).with_context(|| format!("Invalid {}", name))
+    };
+    let public_amount = parse("public_amount", &input.public_amount)?;
+    if public_amount != amount::ext_data_public_amount(ext_data.value, false) {
+        return Err(anyhow!(
+            "public_amount does not encode a withdrawal of the ext data value {}",
+            ext_data.value
+        ));
+    }
+
+    let inputs = [
+        parse("in_amount_0", &input.in_amount_0)?,
+        parse("in_amount_1", &input.in_amount_1)?,
+    ];
+    let outputs = [
+        parse("out_amount_0", &input.out_amount_0)?,
+        parse("out_amount_1", &input.out_amount_1)?,
+    ];
+    for amount in inputs.iter().chain(&outputs) {
+        amount::check_amount_bits(amount)?;
+    }
+    if inputs.iter().sum::<Fr>() + public_amount != outputs.iter().sum::<Fr>() {
+        return Err(anyhow!(
+            "Inputs minus the withdrawn value {} do not equal the outputs",
+            ext_data.value
+        ));
+    }
+
+    if parse("ext_data_hash", &input.ext_data_hash)? != relayer::ext_data_hash(ext_data)? {
+        return Err(anyhow!(
+            "ext_data_hash was computed from different ext data"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::PrimeField;
+
+    fn quote() -> RelayerFeeQuote {
+        RelayerFeeQuote {
+            quote_id: "q1".into(),
+            relayer_address: "0xabc".into(),
+            fee: 50,
+            expires_at_ms: 2000,
+        }
+    }
+
+    fn relayed(split: &FeeSplit) -> (ProofInput, ExtData) {
+        let ext_data = ExtData {
+            value: split.value,
+            is_deposit: false,
+            relayer: "0xABC".into(),
+            relayer_fee: split.fee,
+            encrypted_output_0: vec![1],
+            encrypted_output_1: vec![2],
+        };
+        let mut input: ProofInput = serde_json::from_value(serde_json::json!({
+            "vortex": "0", "root": "0", "publicAmount": split.public_amount,
+            "inputNullifier0": "0", "inputNullifier1": "0",
+            "outputCommitment0": "0", "outputCommitment1": "0",
+            "inPrivateKey0": "0", "inPrivateKey1": "0",
+            "inAmount0": "700", "inAmount1": "500",
+            "inBlinding0": "0", "inBlinding1": "0",
+            "inPathIndex0": "0", "inPathIndex1": "0",
+            "merklePath0": [], "merklePath1": [],
+            "outPublicKey0": "0", "outPublicKey1": "0",
+            "outAmount0": "0", "outAmount1": split.refund.to_string(),
+            "outBlinding0": "0", "outBlinding1": "0",
+        }))
+        .unwrap();
+        input.ext_data_hash = relayer::ext_data_hash(&ext_data)
+            .unwrap()
+            .into_bigint()
+            .to_string();
+        (input, ext_data)
+    }
+
+    #[test]
+    fn test_split_balances_and_passes_the_checks() {
+        let split = split_relayer_fee(&[700, 500], 800, &quote()).unwrap();
+        assert_eq!(split.value, 850);
+        assert_eq!(split.amount_after_fee, 800);
+        assert_eq!(split.refund, 350);
+
+        let (input, ext_data) = relayed(&split);
+        check_relayed_input(&input, &ext_data, &quote()).unwrap();
+
+        assert!(split_relayer_fee(&[700, 500], 1_160, &quote()).is_err());
+    }
+
+    #[test]
+    fn test_mismatches_are_rejected_before_proving() {
+        let split = split_relayer_fee(&[700, 500], 800, &quote()).unwrap();
+        let (input, ext_data) = relayed(&split);
+
+        let mut unbalanced = input.clone();
+        unbalanced.out_amount_1 = "351".into();
+        let err = check_relayed_input(&unbalanced, &ext_data, &quote()).unwrap_err();
+        assert!(err.to_string().contains("do not equal"));
+
+        let mut fee_excluded = ext_data.clone();
+        fee_excluded.value = 800;
+        assert!(check_relayed_input(&input, &fee_excluded, &quote()).is_err());
+
+        let cheaper = RelayerFeeQuote { fee: 40, ..quote() };
+        assert!(check_relayed_input(&input, &ext_data, &cheaper).is_err());
+
+        let mut other_outputs = ext_data.clone();
+        other_outputs.encrypted_output_1 = vec![3];
+        let err = check_relayed_input(&input, &other_outputs, &quote()).unwrap_err();
+        assert!(err.to_string().contains("ext_data_hash"));
+    }
+}
@@ -0,0 +1,979 @@
+//! Opt-in record-and-replay of failed proofs, for bug reports.
+//!
+//! While recording is enabled, a failing `prove` keeps a `BugReport`: the `ProofInput` with
+//! its secrets replaced by placeholders, which of the public inputs derived from those
+//! secrets matched them, the error and the build environment. The app reads it with
+//! `take_bug_report()` (uniffi and wasm) and attaches it to a bug report; the `replay`
+//! binary fills the placeholders with fresh values and recomputes the derived inputs that
+//! matched, so the same check fails on a dev machine.
+//!
+//! Placeholders are `redacted:<n>`. Equal values share a placeholder and zero is kept, so
+//! two inputs spent by one key are still spent by one key after replay. Amounts, including
+//! `public_amount`, are bucketed by bit length (`bits:<n>`, `-bits:<n>` for a withdrawal) and
+//! leaf indices become `index:<n>`, since they too can identify the notes; replay draws
+//! amounts of the same width, rebalances `public_amount` if the amounts balanced, and draws
+//! indices where the paths meet at the recorded level. Hosts that need them exactly opt in
+//! with `set_full_detail`. `vortex`, the ext data and ciphertext hashes and the circuit shape
+//! flags are kept.
+
+use crate::circuit::{
+    bind_ciphertext, compute_commitment, compute_legacy_commitment, compute_nullifier,
+    split_public_amount, TransactionCircuit,
+};
+use crate::constants::{AUTHORITY_TREE_LEVEL, MERKLE_TREE_LEVEL, N_INS, N_OUTS};
+use crate::field::{parse_fr, FieldParseMode};
+use crate::interop::ProofInput;
+use crate::merkle_tree::Path;
+use crate::poseidon_opt::{hash1, hash2, PoseidonOptimized};
+use crate::rotation;
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInt, BigInteger, PrimeField, UniformRand};
+use lazy_static::lazy_static;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const PLACEHOLDER_PREFIX: &str = "redacted:";
+const AMOUNT_PREFIX: &str = "bits:";
+const WITHDRAWAL_PREFIX: &str = "-bits:";
+const INDEX_PREFIX: &str = "index:";
+
+/// Bumped when the report layout changes incompatibly
+pub const BUG_REPORT_VERSION: u32 = 1;
+
+static RECORDING_ENABLED: AtomicBool = AtomicBool::new(false);
+static FULL_DETAIL: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref LAST_REPORT: Mutex<Option<BugReport>> = Mutex::new(None);
+}
+
+/// Build and target the failing proof ran on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    pub crate_version: String,
+    pub target_arch: String,
+    pub target_os: String,
+    pub target_family: String,
+    pub debug_build: bool,
+    pub features: Vec<String>,
+}
+
+impl Environment {
+    pub fn current() -> Self {
+        let features = [
+            ("uniffi", cfg!(feature = "uniffi")),
+            ("wasm", cfg!(feature = "wasm")),
+            ("parallel", cfg!(feature = "parallel")),
+            ("chain-client", cfg!(feature = "chain-client")),
+            ("setup", cfg!(feature = "setup")),
+            ("tracing", cfg!(feature = "tracing")),
+        ];
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            target_arch: std::env::consts::ARCH.to_string(),
+            target_os: std::env::consts::OS.to_string(),
+            target_family: std::env::consts::FAMILY.to_string(),
+            debug_build: cfg!(debug_assertions),
+            features: features
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Which public inputs matched the values recomputed from the redacted secrets. Replay
+/// recomputes those and leaves the others unrelated to the fresh secrets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayHints {
+    /// `hashed_account_secret` was `Poseidon1(account_secret)`
+    pub hashed_account_secret: bool,
+    /// `input_nullifier_i` was the nullifier of input `i`
+    pub input_nullifiers: [bool; N_INS],
+    /// `root` was the root of input `i`'s commitment along `merkle_path_i`
+    pub input_roots: [bool; N_INS],
+    /// Level at which the two Merkle paths meet, when both lead to `root`
+    pub merkle_join_level: Option<u32>,
+    /// `authority_root` was the root of input `i`'s authority along `authority_path_i`
+    pub authority_roots: [bool; N_INS],
+    /// Level at which the two authority paths meet, when both lead to `authority_root`
+    pub authority_join_level: Option<u32>,
+    /// `output_commitment_i` was the commitment of output `i`
+    pub output_commitments: [bool; N_OUTS],
+    /// The input amounts plus `public_amount` equalled the output amounts
+    pub balanced: bool,
+}
+
+/// A failed proof with its secrets redacted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BugReport {
+    pub version: u32,
+    pub error: String,
+    pub environment: Environment,
+    /// Amounts and leaf indices were kept in the clear
+    pub full_detail: bool,
+    pub input: ProofInput,
+    pub hints: ReplayHints,
+}
+
+/// Turns recording of failed proofs on or off. Disabling also drops the last report.
+pub fn set_enabled(enabled: bool) {
+    RECORDING_ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        *LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+pub fn is_enabled() -> bool {
+    RECORDING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Keeps amounts and leaf indices in the clear in later reports instead of bucketing and
+/// replacing them (off by default)
+pub fn set_full_detail(enabled: bool) {
+    FULL_DETAIL.store(enabled, Ordering::Relaxed);
+}
+
+/// Keeps a report of a proof of `input_json` failing with `error`, if recording is enabled.
+///
+/// Inputs that do not parse are not recorded: their error already says what is wrong.
+/// Recording never fails, so it cannot mask the original error.
+pub fn record_failure(input_json: &str, error: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let Ok(input) = serde_json::from_str::<ProofInput>(input_json) else {
+        return;
+    };
+    if let Ok(report) = record(&input, error, FULL_DETAIL.load(Ordering::Relaxed)) {
+        *LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+    }
+}
+
+/// The report of the most recent failed proof, removing it
+pub fn take_report() -> Option<BugReport> {
+    LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// Redacts `input`, keeping amounts and leaf indices when `full_detail` is set, and records
+/// which of its derived public inputs were consistent.
+///
+/// # Errors
+/// Returns error if a field element does not parse or a Merkle path has the wrong length.
+pub fn record(input: &ProofInput, error: &str, full_detail: bool) -> Result<BugReport> {
+    let mode = FieldParseMode::from_reduce(input.reduce_field_elements);
+    let values = Values::parse(input, &mut |s| parse_fr(s, mode))?;
+
+    Ok(BugReport {
+        version: BUG_REPORT_VERSION,
+        error: error.to_string(),
+        environment: Environment::current(),
+        full_detail,
+        input: redact(input, mode, full_detail)?,
+        hints: values.hints(input),
+    })
+}
+
+/// Rebuilds a `ProofInput` from `report` with fresh secrets in place of the placeholders.
+///
+/// Substitutes are drawn from a fixed seed, so replaying a report always gives the same
+/// input.
+///
+/// # Errors
+/// Returns error if the report is from an unknown version or its input does not parse.
+pub fn replay_input(report: &BugReport) -> Result<ProofInput> {
+    if report.version != BUG_REPORT_VERSION {
+        return Err(anyhow!(
+            "Unsupported bug report version {} (expected {})",
+            report.version,
+            BUG_REPORT_VERSION
+        ));
+    }
+    let mut input = report.input.clone();
+    let mode = FieldParseMode::from_reduce(input.reduce_field_elements);
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut substitutes: HashMap<String, Fr> = HashMap::new();
+    let mut values = Values::parse(&input, &mut |s| {
+        if s.starts_with(PLACEHOLDER_PREFIX) {
+            Ok(*substitutes
+                .entry(s.to_string())
+                .or_insert_with(|| Fr::rand(&mut rng)))
+        } else if let Some(bits) = s.strip_prefix(WITHDRAWAL_PREFIX) {
+            Ok(-fresh_amount(bits, &mut rng)?)
+        } else if let Some(bits) = s.strip_prefix(AMOUNT_PREFIX) {
+            fresh_amount(bits, &mut rng)
+        } else if s.starts_with(INDEX_PREFIX) {
+            // Drawn below, once the other input's index is known
+            Ok(Fr::ZERO)
+        } else {
+            parse_fr(s, mode)
+        }
+    })?;
+
+    let hints = &report.hints;
+    fresh_indices::<MERKLE_TREE_LEVEL>(
+        [&input.in_path_index_0, &input.in_path_index_1],
+        &mut values.in_path_indices,
+        hints.merkle_join_level,
+        &mut rng,
+    )?;
+    fresh_indices::<AUTHORITY_TREE_LEVEL>(
+        [&input.in_authority_index_0, &input.in_authority_index_1],
+        &mut values.in_authority_indices,
+        hints.authority_join_level,
+        &mut rng,
+    )?;
+    if hints.balanced {
+        values.public_amount =
+            values.out_amounts.iter().sum::<Fr>() - values.in_amounts.iter().sum::<Fr>();
+    }
+    let commitments = values.in_commitments(&input);
+
+    if let Some(root) = rejoin_paths(
+        &mut values.merkle_paths,
+        &commitments,
+        &values.in_path_indices,
+        hints.input_roots,
+        hints.merkle_join_level,
+    )? {
+        values.root = root;
+    }
+
+    for (i, commitment) in commitments.iter().enumerate() {
+        if hints.input_nullifiers[i] {
+            values.input_nullifiers[i] = compute_nullifier(
+                commitment,
+                &values.in_path_indices[i],
+                &values.in_private_keys[i],
+            );
+        }
+    }
+    if hints.hashed_account_secret {
+        values.hashed_account_secret = hash1(&values.account_secret);
+    }
+    let authorities = values.authority_leaves();
+    if let Some(root) = rejoin_paths(
+        &mut values.authority_paths,
+        &authorities,
+        &values.in_authority_indices,
+        hints.authority_roots,
+        hints.authority_join_level,
+    )? {
+        values.authority_root = root;
+    }
+    let out_commitments = values.out_commitments(&input);
+    for (i, commitment) in out_commitments.into_iter().enumerate() {
+        if hints.output_commitments[i] {
+            values.output_commitments[i] = commitment;
+        }
+    }
+
+    values.write(&mut input);
+    Ok(input)
+}
+
+/// Builds the circuit for `input` without checking its public inputs, so a replayed input
+/// can be taken to `check_public_inputs` and `debug::find_unsatisfied` separately.
+///
+/// # Errors
+/// Returns error if a value does not parse or the input shape is invalid.
+pub fn circuit_from_input(input: &ProofInput) -> Result<TransactionCircuit> {
+    let mode = FieldParseMode::from_reduce(input.reduce_field_elements);
+    let values = Values::parse(input, &mut |s| parse_fr(s, mode))?;
+
+    let circuit = TransactionCircuit::new(
+        values.vortex,
+        values.root,
+        values.public_amount,
+        values.input_nullifiers[0],
+        values.input_nullifiers[1],
+        values.output_commitments[0],
+        values.output_commitments[1],
+        values.hashed_account_secret,
+        values.account_secret,
+        values.in_private_keys,
+        values.in_amounts,
+        values.in_blindings,
+        values.in_path_indices,
+        values.merkle_paths,
+        values.out_public_keys,
+        values.out_amounts,
+        values.out_blindings,
+    )?
+    .with_dummy_inputs([input.dummy_input_0, input.dummy_input_1])?
+    .with_ext_data_hash(values.ext_data_hash)
+    .with_legacy_commitments([input.in_legacy_commitment_0, input.in_legacy_commitment_1]);
+    let circuit = if input.without_account_secret {
+        circuit.without_account_secret()
+    } else {
+        circuit
+    };
+    let circuit = if input.bind_output_ciphertexts {
+        circuit.with_ciphertext_binding(values.output_ciphertext_hashes)
+    } else {
+        circuit
+    };
+    let circuit = if input.key_rotation {
+        circuit.with_key_rotation(
+            values.authority_root,
+            values.in_rotated_private_keys,
+            values.in_authority_indices,
+            values.authority_paths,
+        )
+    } else {
+        circuit
+    };
+    let circuit = if input.mixed_era_inputs {
+        circuit.with_mixed_era_inputs()
+    } else {
+        circuit
+    };
+    Ok(circuit)
+}
+
+/// Replaces every secret and every public input derived from one with a placeholder, and
+/// unless `full_detail` is set buckets the amounts and replaces the leaf indices
+fn redact(input: &ProofInput, mode: FieldParseMode, full_detail: bool) -> Result<ProofInput> {
+    let mut redacted = input.clone();
+    let mut placeholders: HashMap<Fr, usize> = HashMap::new();
+    let mut placeholder = |s: &mut String| -> Result<()> {
+        let value = parse_fr(s, mode)?;
+        let replacement = if value == Fr::ZERO {
+            "0".to_string()
+        } else {
+            let next = placeholders.len();
+            let n = *placeholders.entry(value).or_insert(next);
+            format!("{}{}", PLACEHOLDER_PREFIX, n)
+        };
+        s.zeroize();
+        *s = replacement;
+        Ok(())
+    };
+
+    for field in [
+        &mut redacted.root,
+        &mut redacted.input_nullifier_0,
+        &mut redacted.input_nullifier_1,
+        &mut redacted.output_commitment_0,
+        &mut redacted.output_commitment_1,
+        &mut redacted.hashed_account_secret,
+        &mut redacted.authority_root,
+        &mut redacted.account_secret,
+        &mut redacted.in_private_key_0,
+        &mut redacted.in_private_key_1,
+        &mut redacted.in_blinding_0,
+        &mut redacted.in_blinding_1,
+        &mut redacted.out_public_key_0,
+        &mut redacted.out_public_key_1,
+        &mut redacted.out_blinding_0,
+        &mut redacted.out_blinding_1,
+    ] {
+        placeholder(field)?;
+    }
+    for key in [
+        &mut redacted.in_rotated_private_key_0,
+        &mut redacted.in_rotated_private_key_1,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        placeholder(key)?;
+    }
+    for pair in redacted
+        .merkle_path_0
+        .iter_mut()
+        .chain(redacted.merkle_path_1.iter_mut())
+        .chain(redacted.authority_path_0.iter_mut())
+        .chain(redacted.authority_path_1.iter_mut())
+    {
+        let [left, right] = pair;
+        placeholder(left)?;
+        placeholder(right)?;
+    }
+    redacted.session_nonce = None;
+    if full_detail {
+        return Ok(redacted);
+    }
+
+    let bucket = |prefix: &str, amount: Fr| {
+        let bits = amount.into_bigint().num_bits();
+        if bits == 0 {
+            "0".to_string()
+        } else {
+            format!("{}{}", prefix, bits)
+        }
+    };
+    for amount in [
+        &mut redacted.in_amount_0,
+        &mut redacted.in_amount_1,
+        &mut redacted.out_amount_0,
+        &mut redacted.out_amount_1,
+    ] {
+        *amount = bucket(AMOUNT_PREFIX, parse_fr(amount, mode)?);
+    }
+    let (withdrawal, magnitude) = split_public_amount(&parse_fr(&redacted.public_amount, mode)?);
+    let prefix = if withdrawal {
+        WITHDRAWAL_PREFIX
+    } else {
+        AMOUNT_PREFIX
+    };
+    redacted.public_amount = bucket(prefix, magnitude);
+
+    for indices in [
+        [&mut redacted.in_path_index_0, &mut redacted.in_path_index_1],
+        [
+            &mut redacted.in_authority_index_0,
+            &mut redacted.in_authority_index_1,
+        ],
+    ] {
+        let [first, second] = indices;
+        let equal = parse_fr(first, mode)? == parse_fr(second, mode)?;
+        for (n, index) in [first, second].into_iter().enumerate() {
+            if parse_fr(index, mode)? != Fr::ZERO {
+                *index = format!("{}{}", INDEX_PREFIX, if equal { 0 } else { n });
+            }
+        }
+    }
+
+    Ok(redacted)
+}
+
+/// A fresh value of exactly the bit length `bits` was recorded with
+fn fresh_amount(bits: &str, rng: &mut ChaCha20Rng) -> Result<Fr> {
+    let bits: u32 = bits
+        .parse()
+        .map_err(|e| anyhow!("Invalid amount bucket '{}': {}", bits, e))?;
+    if bits == 0 || bits > Fr::MODULUS_BIT_SIZE {
+        return Err(anyhow!("Invalid amount bucket of {} bits", bits));
+    }
+    loop {
+        let mut limbs: [u64; 4] = std::array::from_fn(|_| rng.next_u64());
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let low = 64 * i as u32;
+            if bits <= low {
+                *limb = 0;
+            } else if bits - low < 64 {
+                *limb &= (1 << (bits - low)) - 1;
+            }
+        }
+        let top = bits - 1;
+        limbs[(top / 64) as usize] |= 1 << (top % 64);
+        // Only a 254-bit draw can land outside the field
+        if let Some(amount) = Fr::from_bigint(BigInt::new(limbs)) {
+            return Ok(amount);
+        }
+    }
+}
+
+/// Draws the leaf indices of a depth-`N` tree that were replaced by `index:<n>`, so equal
+/// ones stay equal and paths that met at `join_level` still do
+fn fresh_indices<const N: usize>(
+    recorded: [&str; N_INS],
+    indices: &mut [Fr; N_INS],
+    join_level: Option<u32>,
+    rng: &mut ChaCha20Rng,
+) -> Result<()> {
+    if let Some(level) = join_level.filter(|&level| level as usize >= N) {
+        return Err(anyhow!("Merkle join level {} is out of range", level));
+    }
+    let mask = (1u64 << N) - 1;
+    let mut drawn = [false; N_INS];
+    for i in (0..N_INS).filter(|&i| recorded[i].starts_with(INDEX_PREFIX)) {
+        let other = 1 - i;
+        let other_known = !recorded[other].starts_with(INDEX_PREFIX) || drawn[other];
+        let index = match join_level {
+            _ if other_known && recorded[other] == recorded[i] => indices[other].into_bigint().0[0],
+            // Same index above `level`, the other child at `level`, anything below
+            Some(level) if other_known => {
+                let above = (indices[other].into_bigint().0[0] >> level) ^ 1;
+                (above << level) | (rng.next_u64() & ((1 << level) - 1))
+            }
+            _ => rng.next_u64() & mask,
+        };
+        indices[i] = Fr::from(index);
+        drawn[i] = true;
+    }
+    Ok(())
+}
+
+/// Puts each matched input's `leaves[i]` back into its path, restores the sibling where the
+/// two paths meet at `join_level`, and returns the root they now lead to, if any matched
+fn rejoin_paths<const N: usize>(
+    paths: &mut [Path<N>; N_INS],
+    leaves: &[Fr; N_INS],
+    indices: &[Fr; N_INS],
+    roots: [bool; N_INS],
+    join_level: Option<u32>,
+) -> Result<Option<Fr>> {
+    for i in (0..N_INS).filter(|&i| roots[i]) {
+        *slot(&mut paths[i], 0, &indices[i], false) = leaves[i];
+    }
+    if let Some(level) = join_level {
+        let level = level as usize;
+        if level >= N {
+            return Err(anyhow!("Merkle join level {} is out of range", level));
+        }
+        let nodes: Vec<Vec<Fr>> = (0..N_INS)
+            .map(|i| path_nodes(&paths[i], &leaves[i], &indices[i]))
+            .collect();
+        for i in 0..N_INS {
+            *slot(&mut paths[i], level, &indices[i], true) = nodes[1 - i][level];
+        }
+    }
+    Ok((0..N_INS)
+        .find(|&i| roots[i])
+        .map(|i| path_nodes(&paths[i], &leaves[i], &indices[i])[N]))
+}
+
+/// Level at which two paths leading to one root meet
+fn join_level<const N: usize>(
+    paths: &[Path<N>; N_INS],
+    leaves: &[Fr; N_INS],
+    indices: &[Fr; N_INS],
+) -> Option<u32> {
+    let nodes: Vec<Vec<Fr>> = (0..N_INS)
+        .map(|i| path_nodes(&paths[i], &leaves[i], &indices[i]))
+        .collect();
+    (0..N)
+        .find(|&level| {
+            nodes[0][level] != nodes[1][level] && nodes[0][level + 1] == nodes[1][level + 1]
+        })
+        .map(|level| level as u32)
+}
+
+/// The leaf slot (`sibling == false`) or sibling slot of `path` at `level` for `index`
+fn slot<'a, const N: usize>(
+    path: &'a mut Path<N>,
+    level: usize,
+    index: &Fr,
+    sibling: bool,
+) -> &'a mut Fr {
+    let (left, right) = &mut path.path[level];
+    if index.into_bigint().get_bit(level) != sibling {
+        right
+    } else {
+        left
+    }
+}
+
+/// The node entering each level of `path` from `leaf` at `index`, followed by the root
+fn path_nodes<const N: usize>(path: &Path<N>, leaf: &Fr, index: &Fr) -> Vec<Fr> {
+    let index = index.into_bigint();
+    let mut nodes = vec![*leaf];
+    for (level, (left, right)) in path.path.iter().enumerate() {
+        let node = nodes[level];
+        nodes.push(if index.get_bit(level) {
+            hash2(left, &node)
+        } else {
+            hash2(&node, right)
+        });
+    }
+    nodes
+}
+
+/// Parses a path of exactly `N` levels of `[left, right]` pairs
+fn parse_path<const N: usize>(
+    data: &[[String; 2]],
+    value: &mut dyn FnMut(&str) -> Result<Fr>,
+) -> Result<Path<N>> {
+    if data.len() != N {
+        return Err(anyhow!(
+            "Invalid Merkle path length: expected {}, got {}",
+            N,
+            data.len()
+        ));
+    }
+    let mut path = Path::empty();
+    for (level, [left, right]) in data.iter().enumerate() {
+        path.path[level] = (value(left)?, value(right)?);
+    }
+    Ok(path)
+}
+
+/// A `ProofInput` parsed into field elements
+#[derive(Zeroize, ZeroizeOnDrop)]
+struct Values {
+    vortex: Fr,
+    root: Fr,
+    public_amount: Fr,
+    input_nullifiers: [Fr; N_INS],
+    output_commitments: [Fr; N_OUTS],
+    hashed_account_secret: Fr,
+    output_ciphertext_hashes: [Fr; N_OUTS],
+    authority_root: Fr,
+    ext_data_hash: Fr,
+    account_secret: Fr,
+    in_private_keys: [Fr; N_INS],
+    in_amounts: [Fr; N_INS],
+    in_blindings: [Fr; N_INS],
+    in_path_indices: [Fr; N_INS],
+    in_rotated_private_keys: [Option<Fr>; N_INS],
+    in_authority_indices: [Fr; N_INS],
+    merkle_paths: [Path<MERKLE_TREE_LEVEL>; N_INS],
+    authority_paths: [Path<AUTHORITY_TREE_LEVEL>; N_INS],
+    out_public_keys: [Fr; N_OUTS],
+    out_amounts: [Fr; N_OUTS],
+    out_blindings: [Fr; N_OUTS],
+}
+
+impl Values {
+    fn parse(input: &ProofInput, value: &mut dyn FnMut(&str) -> Result<Fr>) -> Result<Self> {
+        let merkle_paths = [
+            parse_path(&input.merkle_path_0, value)?,
+            parse_path(&input.merkle_path_1, value)?,
+        ];
+        // Authority paths are only given with key rotation
+        let mut authority_path = |data: &[[String; 2]]| {
+            if data.is_empty() {
+                Ok(Path::empty())
+            } else {
+                parse_path(data, value)
+            }
+        };
+        let authority_paths = [
+            authority_path(&input.authority_path_0)?,
+            authority_path(&input.authority_path_1)?,
+        ];
+
+        Ok(Self {
+            vortex: value(&input.vortex)?,
+            root: value(&input.root)?,
+            public_amount: value(&input.public_amount)?,
+            input_nullifiers: [
+                value(&input.input_nullifier_0)?,
+                value(&input.input_nullifier_1)?,
+            ],
+            output_commitments: [
+                value(&input.output_commitment_0)?,
+                value(&input.output_commitment_1)?,
+            ],
+            hashed_account_secret: value(&input.hashed_account_secret)?,
+            output_ciphertext_hashes: [
+                value(&input.output_ciphertext_hash_0)?,
+                value(&input.output_ciphertext_hash_1)?,
+            ],
+            authority_root: value(&input.authority_root)?,
+            ext_data_hash: value(&input.ext_data_hash)?,
+            account_secret: value(&input.account_secret)?,
+            in_private_keys: [
+                value(&input.in_private_key_0)?,
+                value(&input.in_private_key_1)?,
+            ],
+            in_amounts: [value(&input.in_amount_0)?, value(&input.in_amount_1)?],
+            in_blindings: [value(&input.in_blinding_0)?, value(&input.in_blinding_1)?],
+            in_path_indices: [
+                value(&input.in_path_index_0)?,
+                value(&input.in_path_index_1)?,
+            ],
+            in_rotated_private_keys: [
+                input
+                    .in_rotated_private_key_0
+                    .as_deref()
+                    .map(&mut *value)
+                    .transpose()?,
+                input
+                    .in_rotated_private_key_1
+                    .as_deref()
+                    .map(&mut *value)
+                    .transpose()?,
+            ],
+            in_authority_indices: [
+                value(&input.in_authority_index_0)?,
+                value(&input.in_authority_index_1)?,
+            ],
+            merkle_paths,
+            authority_paths,
+            out_public_keys: [
+                value(&input.out_public_key_0)?,
+                value(&input.out_public_key_1)?,
+            ],
+            out_amounts: [value(&input.out_amount_0)?, value(&input.out_amount_1)?],
+            out_blindings: [value(&input.out_blinding_0)?, value(&input.out_blinding_1)?],
+        })
+    }
+
+    fn in_commitments(&self, input: &ProofInput) -> [Fr; N_INS] {
+        let legacy = [input.in_legacy_commitment_0, input.in_legacy_commitment_1];
+        std::array::from_fn(|i| {
+            let public_key = hash1(&self.in_private_keys[i]);
+            if legacy[i] {
+                compute_legacy_commitment(&self.in_amounts[i], &public_key, &self.in_blindings[i])
+            } else {
+                compute_commitment(
+                    &self.in_amounts[i],
+                    &public_key,
+                    &self.in_blindings[i],
+                    &self.vortex,
+                )
+            }
+        })
+    }
+
+    fn authority_leaves(&self) -> [Fr; N_INS] {
+        std::array::from_fn(|i| {
+            rotation::authority_leaf(
+                &self.in_private_keys[i],
+                self.in_rotated_private_keys[i].as_ref(),
+            )
+        })
+    }
+
+    fn out_commitments(&self, input: &ProofInput) -> [Fr; N_OUTS] {
+        std::array::from_fn(|i| {
+            let blinding = if input.bind_output_ciphertexts {
+                bind_ciphertext(&self.out_blindings[i], &self.output_ciphertext_hashes[i])
+            } else {
+                self.out_blindings[i]
+            };
+            compute_commitment(
+                &self.out_amounts[i],
+                &self.out_public_keys[i],
+                &blinding,
+                &self.vortex,
+            )
+        })
+    }
+
+    fn hints(&self, input: &ProofInput) -> ReplayHints {
+        let commitments = self.in_commitments(input);
+        let out_commitments = self.out_commitments(input);
+        let hasher = PoseidonOptimized::new_t3();
+
+        let mut hints = ReplayHints {
+            hashed_account_secret: hash1(&self.account_secret) == self.hashed_account_secret,
+            ..ReplayHints::default()
+        };
+        for (i, commitment) in commitments.iter().enumerate() {
+            hints.input_nullifiers[i] = compute_nullifier(
+                commitment,
+                &self.in_path_indices[i],
+                &self.in_private_keys[i],
+            ) == self.input_nullifiers[i];
+            hints.input_roots[i] = self.merkle_paths[i]
+                .check_membership_at(&self.root, commitment, &self.in_path_indices[i], &hasher)
+                .unwrap_or(false);
+        }
+        let authorities = self.authority_leaves();
+        for (i, authority) in authorities.iter().enumerate() {
+            hints.authority_roots[i] = self.authority_paths[i]
+                .check_membership_at(
+                    &self.authority_root,
+                    authority,
+                    &self.in_authority_indices[i],
+                    &hasher,
+                )
+                .unwrap_or(false);
+        }
+        for (i, commitment) in out_commitments.iter().enumerate() {
+            hints.output_commitments[i] = *commitment == self.output_commitments[i];
+        }
+        hints.balanced = self.in_amounts.iter().sum::<Fr>() + self.public_amount
+            == self.out_amounts.iter().sum::<Fr>();
+
+        if hints.input_roots.iter().all(|matched| *matched) {
+            hints.merkle_join_level =
+                join_level(&self.merkle_paths, &commitments, &self.in_path_indices);
+        }
+        if hints.authority_roots.iter().all(|matched| *matched) {
+            hints.authority_join_level = join_level(
+                &self.authority_paths,
+                &authorities,
+                &self.in_authority_indices,
+            );
+        }
+
+        hints
+    }
+
+    /// Writes every value back into `input` in decimal
+    fn write(&self, input: &mut ProofInput) {
+        let s = |x: &Fr| x.into_bigint().to_string();
+        fn path<const N: usize>(p: &Path<N>) -> Vec<[String; 2]> {
+            let s = |x: &Fr| x.into_bigint().to_string();
+            p.path.iter().map(|(l, r)| [s(l), s(r)]).collect()
+        }
+
+        input.vortex = s(&self.vortex);
+        input.root = s(&self.root);
+        input.public_amount = s(&self.public_amount);
+        input.input_nullifier_0 = s(&self.input_nullifiers[0]);
+        input.input_nullifier_1 = s(&self.input_nullifiers[1]);
+        input.output_commitment_0 = s(&self.output_commitments[0]);
+        input.output_commitment_1 = s(&self.output_commitments[1]);
+        input.hashed_account_secret = s(&self.hashed_account_secret);
+        input.output_ciphertext_hash_0 = s(&self.output_ciphertext_hashes[0]);
+        input.output_ciphertext_hash_1 = s(&self.output_ciphertext_hashes[1]);
+        input.authority_root = s(&self.authority_root);
+        input.ext_data_hash = s(&self.ext_data_hash);
+        input.account_secret = s(&self.account_secret);
+        input.in_private_key_0 = s(&self.in_private_keys[0]);
+        input.in_private_key_1 = s(&self.in_private_keys[1]);
+        input.in_amount_0 = s(&self.in_amounts[0]);
+        input.in_amount_1 = s(&self.in_amounts[1]);
+        input.in_blinding_0 = s(&self.in_blindings[0]);
+        input.in_blinding_1 = s(&self.in_blindings[1]);
+        input.in_path_index_0 = s(&self.in_path_indices[0]);
+        input.in_path_index_1 = s(&self.in_path_indices[1]);
+        input.in_rotated_private_key_0 = self.in_rotated_private_keys[0].as_ref().map(s);
+        input.in_rotated_private_key_1 = self.in_rotated_private_keys[1].as_ref().map(s);
+        input.merkle_path_0 = path(&self.merkle_paths[0]);
+        input.merkle_path_1 = path(&self.merkle_paths[1]);
+        if input.key_rotation {
+            input.in_authority_index_0 = s(&self.in_authority_indices[0]);
+            input.in_authority_index_1 = s(&self.in_authority_indices[1]);
+            input.authority_path_0 = path(&self.authority_paths[0]);
+            input.authority_path_1 = path(&self.authority_paths[1]);
+        }
+        input.out_public_key_0 = s(&self.out_public_keys[0]);
+        input.out_public_key_1 = s(&self.out_public_keys[1]);
+        input.out_amount_0 = s(&self.out_amounts[0]);
+        input.out_amount_1 = s(&self.out_amounts[1]);
+        input.out_blinding_0 = s(&self.out_blindings[0]);
+        input.out_blinding_1 = s(&self.out_blindings[1]);
+        input.reduce_field_elements = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ZERO_VALUE;
+    use crate::merkle_tree::SparseMerkleTree;
+    use crate::poseidon_opt::fr_from_str;
+
+    /// A valid transfer spending two notes of one key from a tree with a few other leaves
+    fn valid_input() -> ProofInput {
+        let private_key = Fr::from(4242u64);
+        let public_key = hash1(&private_key);
+        let vortex = Fr::from(7u64);
+        let in_amounts = [Fr::from(30u64), Fr::from(12u64)];
+        let in_blindings = [Fr::from(501u64), Fr::from(502u64)];
+        let commitments: [Fr; N_INS] = std::array::from_fn(|i| {
+            compute_commitment(&in_amounts[i], &public_key, &in_blindings[i], &vortex)
+        });
+        let leaves = [
+            Fr::from(1u64),
+            commitments[0],
+            Fr::from(2u64),
+            Fr::from(3u64),
+            commitments[1],
+            Fr::from(4u64),
+        ];
+        let empty_leaf = fr_from_str(ZERO_VALUE);
+        let paths: Vec<(Fr, usize, Path<MERKLE_TREE_LEVEL>)> = commitments
+            .iter()
+            .map(|c| {
+                SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(&leaves, c, &empty_leaf)
+                    .unwrap()
+            })
+            .collect();
+        let nullifiers: [Fr; N_INS] = std::array::from_fn(|i| {
+            compute_nullifier(&commitments[i], &Fr::from(paths[i].1 as u64), &private_key)
+        });
+        let out_amounts = [Fr::from(40u64), Fr::from(2u64)];
+        let out_blindings = [Fr::from(601u64), Fr::from(602u64)];
+        let out_commitments: [Fr; N_OUTS] = std::array::from_fn(|i| {
+            compute_commitment(&out_amounts[i], &public_key, &out_blindings[i], &vortex)
+        });
+        let account_secret = Fr::from(99u64);
+
+        let s = |x: &Fr| x.into_bigint().to_string();
+        let path = |p: &Path<MERKLE_TREE_LEVEL>| -> Vec<[String; 2]> {
+            p.path.iter().map(|(l, r)| [s(l), s(r)]).collect()
+        };
+        serde_json::from_value(serde_json::json!({
+            "vortex": s(&vortex), "root": s(&paths[0].0), "publicAmount": "0",
+            "inputNullifier0": s(&nullifiers[0]), "inputNullifier1": s(&nullifiers[1]),
+            "outputCommitment0": s(&out_commitments[0]),
+            "outputCommitment1": s(&out_commitments[1]),
+            "hashedAccountSecret": s(&hash1(&account_secret)),
+            "accountSecret": s(&account_secret),
+            "inPrivateKey0": s(&private_key), "inPrivateKey1": s(&private_key),
+            "inAmount0": s(&in_amounts[0]), "inAmount1": s(&in_amounts[1]),
+            "inBlinding0": s(&in_blindings[0]), "inBlinding1": s(&in_blindings[1]),
+            "inPathIndex0": paths[0].1.to_string(), "inPathIndex1": paths[1].1.to_string(),
+            "merklePath0": path(&paths[0].2), "merklePath1": path(&paths[1].2),
+            "outPublicKey0": s(&public_key), "outPublicKey1": s(&public_key),
+            "outAmount0": s(&out_amounts[0]), "outAmount1": s(&out_amounts[1]),
+            "outBlinding0": s(&out_blindings[0]), "outBlinding1": s(&out_blindings[1]),
+            "sessionNonce": "req-7"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replay_reproduces_failure_without_secrets() {
+        let valid = valid_input();
+        let report = record(&valid, "", false).unwrap();
+        let json = serde_json::to_string(&report).unwrap();
+        for secret in [
+            &valid.in_private_key_0,
+            &valid.in_blinding_0,
+            &valid.account_secret,
+            &valid.root,
+            &valid.in_amount_0,
+            &valid.out_amount_0,
+            &valid.in_path_index_1,
+        ] {
+            assert!(
+                !json.contains(&format!("\"{}\"", secret)),
+                "{} leaked",
+                secret
+            );
+        }
+        assert_eq!(report.input.in_private_key_0, report.input.in_private_key_1);
+        assert_eq!(report.input.in_amount_0, "bits:5");
+        assert_eq!(report.input.in_path_index_1, "index:1");
+        assert!(report.hints.merkle_join_level.is_some());
+        assert!(report.hints.balanced);
+
+        let replayed = replay_input(&report).unwrap();
+        assert_ne!(replayed.in_private_key_0, valid.in_private_key_0);
+        let circuit = circuit_from_input(&replayed).unwrap();
+        circuit.check_public_inputs().unwrap();
+        assert!(crate::debug::find_unsatisfied(&circuit).unwrap().is_none());
+
+        let mut broken = valid.clone();
+        broken.output_commitment_1 = "12345".to_string();
+        let expected = circuit_from_input(&broken)
+            .unwrap()
+            .check_public_inputs()
+            .unwrap_err()
+            .to_string();
+        let report = record(&broken, &expected, false).unwrap();
+        let replayed = circuit_from_input(&replay_input(&report).unwrap()).unwrap();
+        assert_eq!(
+            replayed.check_public_inputs().unwrap_err().to_string(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_full_detail_keeps_amounts_and_indices() {
+        let valid = valid_input();
+        let report = record(&valid, "", true).unwrap();
+        assert!(report.full_detail);
+        assert_eq!(report.input.in_amount_0, valid.in_amount_0);
+        assert_eq!(report.input.public_amount, valid.public_amount);
+        assert_eq!(report.input.in_path_index_1, valid.in_path_index_1);
+        assert_ne!(report.input.in_private_key_0, valid.in_private_key_0);
+
+        let replayed = replay_input(&report).unwrap();
+        assert_eq!(replayed.in_amount_0, valid.in_amount_0);
+        assert_eq!(replayed.in_path_index_1, valid.in_path_index_1);
+        circuit_from_input(&replayed)
+            .unwrap()
+            .check_public_inputs()
+            .unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! Note key rotation with a grace period.
+//!
+//! A note's private key derives its nullifier, so it cannot be swapped out without
+//! respending the note. Instead, circuits built `with_key_rotation` check that every
+//! non-zero input is authorized by a leaf of the pool's authority tree: either the note
+//! key's tag, or the link from that tag to a successor key's tag. The prover picks which
+//! with a private per-input selector and proves membership along a private path, so the
+//! only public value is the `authority_root`, which is the same for every spend against the
+//! same tree. Spends by one key cannot be linked through it, and each input is authorized
+//! on its own, so one transaction can spend notes under the old key and the new one.
+//!
+//! A note key's tag is registered in the tree before its notes are spent in this shape.
+//! After a device is compromised the owner registers a rotation from the old key to a new
+//! one, which adds the new key's tag and the rotation link. During the grace period the old
+//! tag stays in the tree. Once it ends the pool clears that leaf and only accepts roots from
+//! after the clearing, so a spend must also prove knowledge of the new key and a thief
+//! holding only the old key can no longer move the notes. Notes are migrated to the new key
+//! at the owner's pace instead of all at once. Empty and cleared slots hold zero, which no
+//! key tag or link hashes to.
+
+use crate::constants::AUTHORITY_TREE_LEVEL;
+use crate::merkle_tree::{Path, SparseMerkleTree};
+use crate::poseidon_opt::{hash2, PoseidonOptimized};
+use alloc::string::{String, ToString};
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+use serde::{Deserialize, Serialize};
+
+/// A registered rotation from one note key to its successor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotation {
+    /// Decimal tag of the rotated-out key, cleared from the authority tree once the grace
+    /// period ends
+    pub previous_key_tag: String,
+    /// Decimal tag of the successor key, added to the authority tree
+    pub new_key_tag: String,
+    /// Decimal authority leaf of spends authorized through this rotation, added to the
+    /// authority tree
+    pub rotation_link: String,
+    /// Unix time in milliseconds until which the previous key alone may still spend
+    pub grace_period_ends_ms: u64,
+}
+
+impl KeyRotation {
+    /// Whether a spend authorized by the previous key alone is still accepted at `now_ms`
+    pub fn accepts_previous_key(&self, now_ms: u64) -> bool {
+        now_ms < self.grace_period_ends_ms
+    }
+}
+
+/// Public tag of a note key: `Poseidon2(privkey, 0)`.
+///
+/// Unlike the note public key it cannot be computed by senders, so it does not tell them
+/// when the notes they paid are spent.
+pub fn key_tag(private_key: &Fr) -> Fr {
+    hash2(private_key, &Fr::ZERO)
+}
+
+/// Authority leaf of a rotated spend: `Poseidon2(previous_tag, new_tag)`
+pub fn rotation_link(previous_key_tag: &Fr, new_key_tag: &Fr) -> Fr {
+    hash2(previous_key_tag, new_key_tag)
+}
+
+/// Authority leaf a note spent under `private_key` proves membership of, authorized by
+/// `rotated_key` when it is given
+pub fn authority_leaf(private_key: &Fr, rotated_key: Option<&Fr>) -> Fr {
+    let tag = key_tag(private_key);
+    match rotated_key {
+        Some(rotated_key) => rotation_link(&tag, &key_tag(rotated_key)),
+        None => tag,
+    }
+}
+
+/// Root of the authority tree holding `leaves`, with the index of `leaf` and its path.
+///
+/// `leaves` hold whole pairs, as the pool inserts them. Cleared slots are passed as zero,
+/// like the empty ones.
+///
+/// # Errors
+/// Returns error if `leaf` is not among `leaves` or they do not fit the tree.
+pub fn authority_path(leaves: &[Fr], leaf: &Fr) -> Result<(Fr, u64, Path<AUTHORITY_TREE_LEVEL>)> {
+    if *leaf == Fr::ZERO {
+        return Err(anyhow!("Zero marks an empty slot, not an authority"));
+    }
+    let index = leaves
+        .iter()
+        .position(|l| l == leaf)
+        .ok_or_else(|| anyhow!("Leaf is not in the authority tree"))?;
+    let hasher = PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<AUTHORITY_TREE_LEVEL>::new_empty(&hasher, &Fr::ZERO);
+    tree.bulk_insert(leaves, &hasher)?;
+    let path = tree.generate_membership_proof(index)?;
+    Ok((tree.root(), index as u64, path))
+}
+
+/// Builds the rotation record to register for moving from `previous_private_key` to
+/// `new_private_key`.
+///
+/// # Errors
+/// Returns error if the keys are equal or the grace period end does not fit a `u64`.
+pub fn rotate_key(
+    previous_private_key: &Fr,
+    new_private_key: &Fr,
+    now_ms: u64,
+    grace_period_ms: u64,
+) -> Result<KeyRotation> {
+    if previous_private_key == new_private_key {
+        return Err(anyhow!("The new key must differ from the rotated-out key"));
+    }
+    let grace_period_ends_ms = now_ms
+        .checked_add(grace_period_ms)
+        .ok_or_else(|| anyhow!("Grace period end does not fit a u64"))?;
+
+    let previous_key_tag = key_tag(previous_private_key);
+    let new_key_tag = key_tag(new_private_key);
+    Ok(KeyRotation {
+        previous_key_tag: previous_key_tag.into_bigint().to_string(),
+        new_key_tag: new_key_tag.into_bigint().to_string(),
+        rotation_link: rotation_link(&previous_key_tag, &new_key_tag)
+            .into_bigint()
+            .to_string(),
+        grace_period_ends_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_record_matches_authority_leaves() {
+        let previous = Fr::from(11u64);
+        let new = Fr::from(22u64);
+        let rotation = rotate_key(&previous, &new, 1_000, 500).unwrap();
+
+        assert_eq!(
+            rotation.previous_key_tag,
+            authority_leaf(&previous, None).into_bigint().to_string()
+        );
+        assert_eq!(
+            rotation.new_key_tag,
+            authority_leaf(&new, None).into_bigint().to_string()
+        );
+        assert_eq!(
+            rotation.rotation_link,
+            authority_leaf(&previous, Some(&new))
+                .into_bigint()
+                .to_string()
+        );
+        assert_ne!(rotation.new_key_tag, rotation.previous_key_tag);
+
+        assert!(rotation.accepts_previous_key(1_499));
+        assert!(!rotation.accepts_previous_key(1_500));
+    }
+
+    #[test]
+    fn test_authority_paths_lead_to_one_root() {
+        let leaves = [
+            key_tag(&Fr::from(11u64)),
+            Fr::ZERO,
+            authority_leaf(&Fr::from(11u64), Some(&Fr::from(22u64))),
+            key_tag(&Fr::from(22u64)),
+        ];
+        let (root, index, path) = authority_path(&leaves, &leaves[2]).unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(
+            path.calculate_root_at(&leaves[2], &Fr::from(index), &PoseidonOptimized::new_t3())
+                .unwrap(),
+            root
+        );
+        assert_eq!(authority_path(&leaves, &leaves[0]).unwrap().0, root);
+        assert!(authority_path(&leaves, &Fr::ZERO).is_err());
+        assert!(authority_path(&leaves, &Fr::from(5u64)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_rotations_are_rejected() {
+        assert!(rotate_key(&Fr::from(1u64), &Fr::from(1u64), 0, 10).is_err());
+        assert!(rotate_key(&Fr::from(1u64), &Fr::from(2u64), u64::MAX, 1).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Startup self-test of the bundled keys.
+//!
+//! Proves and verifies a canned zero-value transfer, so a mismatch between the shipped
+//! proving key, verifying key, circuit and binary shows up when the app starts rather than
+//! when a user first tries to pay. The proving key is also checked against the manifest the
+//! app was built with, which catches a stale or truncated key download.
+//!
+//! The canned witness has the default circuit shape, so only keys of that shape can be
+//! tested.
+
+use crate::circuit::sample_zero_transfer;
+use anyhow::{anyhow, Result};
+use ark_bn254::Bn254;
+use ark_crypto_primitives::snark::SNARK;
+use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use web_time::Instant;
+
+/// What the app expects of its bundled proving key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct KeyManifest {
+    /// Circuit id the key is cached under (uniffi), e.g. "default"
+    pub circuit_id: String,
+    /// Hex SHA-256 of the serialized proving key
+    pub proving_key_sha256: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub prove_ms: u64,
+    pub verify_ms: u64,
+}
+
+pub fn proving_key_digest(proving_key: &[u8]) -> [u8; 32] {
+    Sha256::digest(proving_key).into()
+}
+
+/// Hashes a key file as it is read, for keys streamed from disk with
+/// `validation::read_proving_key`
+pub struct DigestReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> DigestReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Reads whatever the deserializer left unread and returns the digest of the whole file,
+    /// equal to `proving_key_digest` of its bytes
+    pub fn finish(mut self) -> std::io::Result<[u8; 32]> {
+        std::io::copy(&mut self, &mut std::io::sink())?;
+        Ok(self.hasher.finalize().into())
+    }
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Checks a proving key digest against the manifest.
+///
+/// # Errors
+/// Returns error if the manifest digest is not 32 bytes of hex or does not match.
+pub fn check_manifest(digest: &[u8; 32], manifest: &KeyManifest) -> Result<()> {
+    let expected = hex::decode(manifest.proving_key_sha256.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Invalid manifest digest: {}", e))?;
+    if expected.as_slice() != digest {
+        return Err(anyhow!(
+            "Proving key for '{}' does not match its manifest: expected {}, got {}",
+            manifest.circuit_id,
+            manifest.proving_key_sha256,
+            hex::encode(digest)
+        ));
+    }
+    Ok(())
+}
+
+/// Proves and verifies the canned transfer with `pk` and `vk`.
+///
+/// # Errors
+/// Returns error if the keys do not belong together, proving fails, or the proof does not
+/// verify.
+pub fn self_test(pk: &ProvingKey<Bn254>, vk: &VerifyingKey<Bn254>) -> Result<SelfTestReport> {
+    if &pk.vk != vk {
+        return Err(anyhow!(
+            "Proving key was not generated with this verifying key"
+        ));
+    }
+
+    let circuit = sample_zero_transfer();
+    let public_inputs = circuit.get_public_inputs();
+
+    let start = Instant::now();
+    let proof = Groth16::<Bn254>::prove(pk, circuit, &mut OsRng)
+        .map_err(|e| anyhow!("Failed to prove the canned witness: {}", e))?;
+    let prove_ms = start.elapsed().as_millis() as u64;
+
+    let start = Instant::now();
+    let valid = Groth16::<Bn254>::verify(vk, &public_inputs, &proof)
+        .map_err(|e| anyhow!("Failed to verify the canned proof: {}", e))?;
+    let verify_ms = start.elapsed().as_millis() as u64;
+
+    if !valid {
+        return Err(anyhow!(
+            "Canned proof does not verify: the circuit does not match the keys"
+        ));
+    }
+    Ok(SelfTestReport {
+        prove_ms,
+        verify_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::TransactionCircuit;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_self_test_accepts_matching_keys_only() {
+        let mut rng = ChaCha20Rng::from_seed([3u8; 32]);
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            TransactionCircuit::empty(),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(self_test(&pk, &pk.vk).is_ok());
+
+        let mut other_vk = pk.vk.clone();
+        other_vk.gamma_abc_g1.pop();
+        assert!(self_test(&pk, &other_vk).is_err());
+
+        let digest = proving_key_digest(b"key bytes");
+        let manifest = KeyManifest {
+            circuit_id: "default".to_string(),
+            proving_key_sha256: hex::encode(digest),
+        };
+        assert!(check_manifest(&digest, &manifest).is_ok());
+        assert!(check_manifest(&proving_key_digest(b"other bytes"), &manifest).is_err());
+    }
+}
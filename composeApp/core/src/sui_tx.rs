@@ -0,0 +1,175 @@
+//! Arguments of the Move calls that submit a proof to the pool.
+//!
+//! A pool transaction is three calls in one programmable transaction block:
+//! 1. `vortex_ext_data::new(value, is_deposit, relayer, relayer_fee, encrypted_output_0,
+//!    encrypted_output_1)`
+//! 2. `vortex_proof::new<T>(pool, proof_points, public inputs...)`, taking every public input
+//!    after `vortex` as a `u256`, in `public_input_names()` order; the pool supplies its own
+//!    id for `vortex`
+//! 3. `vortex::transact<T>(pool, coin, proof, ext_data)`, on the pool object, the coin moved
+//!    in (a zero-value split for transfers and withdrawals) and the results of 1 and 2
+//!
+//! `build_transact_call` returns the BCS bytes of every pure argument of 1 and 2 in order, so
+//! hosts only wire them into their SDK's transaction builder.
+
+use crate::field::{fr_to_bytes_le, parse_fr, FieldParseMode};
+use crate::interop::ProofOutput;
+use crate::relayer::{normalize_address, ExtData};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Coin type of the SUI pool
+pub const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+
+/// Ordered arguments of the calls submitting one proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct TransactCall {
+    /// `<package>::vortex_ext_data::new`
+    pub ext_data_target: String,
+    /// BCS pure arguments of `ext_data_target`
+    pub ext_data_arguments: Vec<Vec<u8>>,
+    /// `<package>::vortex_proof::new`
+    pub proof_target: String,
+    /// BCS pure arguments of `proof_target`
+    pub proof_arguments: Vec<Vec<u8>>,
+    /// `<package>::vortex::transact`
+    pub transact_target: String,
+    /// Type arguments of `proof_target` and `transact_target`
+    pub type_arguments: Vec<String>,
+}
+
+/// Assembles the calls submitting `proof` with `ext_data` to the pool of `coin_type`.
+///
+/// # Errors
+/// Returns error if an address is invalid, the proof bytes are not hex, or the proof has no
+/// public input besides `vortex` or one that is not a canonical field element.
+pub fn build_transact_call(
+    proof: &ProofOutput,
+    ext_data: &ExtData,
+    package_id: &str,
+    pool_id: &str,
+    coin_type: &str,
+) -> Result<TransactCall> {
+    let package_id = normalize_address(package_id).context("Invalid package id")?;
+
+    let mut proof_arguments = vec![address(pool_id).context("Invalid pool id")?];
+    let proof_points =
+        hex::decode(&proof.proof_serialized_hex).context("Failed to decode proof hex")?;
+    proof_arguments.push(byte_vector(&proof_points));
+    if proof.public_inputs.len() < 2 {
+        return Err(anyhow!(
+            "Proof has {} public inputs, expected vortex and at least one more",
+            proof.public_inputs.len()
+        ));
+    }
+    for (i, input) in proof.public_inputs.iter().enumerate().skip(1) {
+        let value = parse_fr(input, FieldParseMode::Strict)
+            .with_context(|| format!("Invalid public input {}", i))?;
+        proof_arguments.push(fr_to_bytes_le(&value).to_vec());
+    }
+
+    Ok(TransactCall {
+        ext_data_target: format!("{}::vortex_ext_data::new", package_id),
+        ext_data_arguments: ext_data_arguments(ext_data)?,
+        proof_target: format!("{}::vortex_proof::new", package_id),
+        proof_arguments,
+        transact_target: format!("{}::vortex::transact", package_id),
+        type_arguments: vec![coin_type.to_string()],
+    })
+}
+
+/// BCS arguments of `vortex_ext_data::new`; concatenated they are the BCS `ExtData` struct
+/// hashed into `ext_data_hash`.
+///
+/// # Errors
+/// Returns error if the relayer address is invalid.
+pub fn ext_data_arguments(ext_data: &ExtData) -> Result<Vec<Vec<u8>>> {
+    Ok(vec![
+        ext_data.value.to_le_bytes().to_vec(),
+        vec![ext_data.is_deposit as u8],
+        address(&ext_data.relayer).context("Invalid relayer address")?,
+        ext_data.relayer_fee.to_le_bytes().to_vec(),
+        byte_vector(&ext_data.encrypted_output_0),
+        byte_vector(&ext_data.encrypted_output_1),
+    ])
+}
+
+/// BCS `address`: 32 bytes, no length prefix
+fn address(address: &str) -> Result<Vec<u8>> {
+    Ok(hex::decode(&normalize_address(address)?[2..])?)
+}
+
+/// BCS `vector<u8>`: ULEB128 length followed by the bytes
+fn byte_vector(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 5);
+    let mut len = bytes.len();
+    while len >= 0x80 {
+        out.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    out.push(len as u8);
+    out.extend_from_slice(bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ext_data() -> ExtData {
+        ExtData {
+            value: 1_000_000,
+            is_deposit: true,
+            relayer: "0xabc".to_string(),
+            relayer_fee: 0,
+            encrypted_output_0: vec![7; 200],
+            encrypted_output_1: vec![8],
+        }
+    }
+
+    fn proof() -> ProofOutput {
+        ProofOutput {
+            proof_a: Vec::new(),
+            proof_b: Vec::new(),
+            proof_c: Vec::new(),
+            public_inputs: vec!["11".into(), "258".into(), "3".into()],
+            proof_serialized_hex: "0102".into(),
+            public_inputs_serialized_hex: String::new(),
+            session_nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_arguments_are_ordered_bcs() {
+        let call = build_transact_call(&proof(), &ext_data(), "0x1", "0x5", SUI_COIN_TYPE).unwrap();
+        assert_eq!(
+            call.transact_target,
+            format!("0x{:0>64}::vortex::transact", "1")
+        );
+        assert_eq!(call.type_arguments, ["0x2::sui::SUI"]);
+
+        let args = &call.ext_data_arguments;
+        assert_eq!(args[0], 1_000_000u64.to_le_bytes());
+        assert_eq!(args[1], [1]);
+        assert_eq!(args[2].len(), 32);
+        assert_eq!(args[2][30..], [0x0a, 0xbc]);
+        assert_eq!(args[4][..2], [0xc8, 0x01]);
+        assert_eq!(args[4].len(), 202);
+        assert_eq!(args[5], [1, 8]);
+
+        // Pool id, proof points, then the public inputs without `vortex`
+        let args = &call.proof_arguments;
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[0][31], 5);
+        assert_eq!(args[1], [2, 1, 2]);
+        let mut u256 = [0u8; 32];
+        u256[..2].copy_from_slice(&[2, 1]);
+        assert_eq!(args[2], u256);
+
+        let mut no_inputs = proof();
+        no_inputs.public_inputs.truncate(1);
+        assert!(build_transact_call(&no_inputs, &ext_data(), "0x1", "0x5", SUI_COIN_TYPE).is_err());
+    }
+}
@@ -0,0 +1,486 @@
+//! Incremental wallet sync on top of `chain` (`chain` feature).
+//!
+//! `SyncEngine` resumes from the cursors stored in a `SyncCheckpoint`, pulls new commitment
+//! and nullifier events page by page and applies each page to the Merkle tree, the spent
+//! nullifier set and the host's `NoteStore` as one unit. A commitment page is checked for
+//! contiguous leaf indices and tree capacity before the store sees it, and the local state
+//! is only updated in place once the store has accepted it: if either rejects the page,
+//! none of the state moves and the next sync retries from the same cursor.
+
+use crate::chain::{
+    ChainClient, CommitmentEvent, EventCursor, EventPage, NullifierEvent, PoolState,
+};
+use crate::constants::{MERKLE_TREE_LEVEL, ZERO_VALUE};
+use crate::field::{parse_fr, FieldParseMode};
+use crate::merkle_tree::SparseMerkleTree;
+use crate::poseidon_opt::PoseidonOptimized;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Where the pool events come from; implemented by `ChainClient`
+pub trait EventSource {
+    fn commitment_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<CommitmentEvent>>;
+
+    fn nullifier_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<NullifierEvent>>;
+
+    fn pool_state(&self) -> Result<PoolState>;
+}
+
+impl EventSource for ChainClient {
+    fn commitment_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<CommitmentEvent>> {
+        ChainClient::commitment_events_page(self, cursor, limit)
+    }
+
+    fn nullifier_events_page(
+        &self,
+        cursor: Option<&EventCursor>,
+        limit: u32,
+    ) -> Result<EventPage<NullifierEvent>> {
+        ChainClient::nullifier_events_page(self, cursor, limit)
+    }
+
+    fn pool_state(&self) -> Result<PoolState> {
+        ChainClient::pool_state(self)
+    }
+}
+
+/// Host-side storage of decrypted notes and their spent flags.
+///
+/// Each call receives one page. Returning an error aborts the page, so an implementation
+/// should either apply the whole page or nothing.
+pub trait NoteStore {
+    fn apply_commitments(&mut self, events: &[CommitmentEvent]) -> Result<()>;
+
+    fn apply_nullifiers(&mut self, nullifiers: &[Fr]) -> Result<()>;
+}
+
+/// For callers that only need the tree and the nullifier set
+impl NoteStore for () {
+    fn apply_commitments(&mut self, _events: &[CommitmentEvent]) -> Result<()> {
+        Ok(())
+    }
+
+    fn apply_nullifiers(&mut self, _nullifiers: &[Fr]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Persistable sync position and the data needed to rebuild the local pool view
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCheckpoint {
+    pub commitment_cursor: Option<EventCursor>,
+    pub nullifier_cursor: Option<EventCursor>,
+    /// Inserted leaves in index order, as decimal strings
+    pub leaves: Vec<String>,
+    /// First leaf of a pair whose sibling has not been seen yet
+    pub pending_leaf: Option<String>,
+    pub nullifiers: Vec<String>,
+}
+
+/// Sync progress reported after every applied page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// Commitments applied so far, including a pending unpaired one
+    pub commitments: u64,
+    /// Leaf count reported by the pool when the sync started
+    pub target_commitments: u64,
+    pub nullifiers: u64,
+    /// Pages applied during this sync
+    pub pages: u32,
+}
+
+/// Local view of the pool maintained by `SyncEngine`
+#[derive(Debug, Clone)]
+pub struct SyncState {
+    tree: SparseMerkleTree<MERKLE_TREE_LEVEL>,
+    pending_leaf: Option<Fr>,
+    nullifiers: HashSet<Fr>,
+    commitment_cursor: Option<EventCursor>,
+    nullifier_cursor: Option<EventCursor>,
+}
+
+impl SyncState {
+    /// State of a wallet that has not synced yet
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            tree: SparseMerkleTree::new_empty(&PoseidonOptimized::new_t3(), &empty_leaf()?),
+            pending_leaf: None,
+            nullifiers: HashSet::new(),
+            commitment_cursor: None,
+            nullifier_cursor: None,
+        })
+    }
+
+    /// Rebuilds the state saved with `checkpoint()`
+    pub fn from_checkpoint(checkpoint: &SyncCheckpoint) -> Result<Self> {
+        let parse = |s: &String| parse_fr(s, FieldParseMode::Strict);
+        let leaves = checkpoint
+            .leaves
+            .iter()
+            .map(parse)
+            .collect::<Result<Vec<Fr>>>()
+            .context("Invalid leaf in sync checkpoint")?;
+        let nullifiers = checkpoint
+            .nullifiers
+            .iter()
+            .map(parse)
+            .collect::<Result<HashSet<Fr>>>()
+            .context("Invalid nullifier in sync checkpoint")?;
+
+        let hasher = PoseidonOptimized::new_t3();
+        let mut tree = SparseMerkleTree::new_empty(&hasher, &empty_leaf()?);
+        tree.bulk_insert(&leaves, &hasher)?;
+
+        Ok(Self {
+            tree,
+            pending_leaf: checkpoint.pending_leaf.as_ref().map(parse).transpose()?,
+            nullifiers,
+            commitment_cursor: checkpoint.commitment_cursor.clone(),
+            nullifier_cursor: checkpoint.nullifier_cursor.clone(),
+        })
+    }
+
+    pub fn checkpoint(&self) -> SyncCheckpoint {
+        let to_string = |fr: &Fr| fr.into_bigint().to_string();
+        let mut nullifiers: Vec<String> = self.nullifiers.iter().map(to_string).collect();
+        nullifiers.sort();
+
+        SyncCheckpoint {
+            commitment_cursor: self.commitment_cursor.clone(),
+            nullifier_cursor: self.nullifier_cursor.clone(),
+            leaves: self.tree.leaves.iter().map(to_string).collect(),
+            pending_leaf: self.pending_leaf.as_ref().map(to_string),
+            nullifiers,
+        }
+    }
+
+    pub fn tree(&self) -> &SparseMerkleTree<MERKLE_TREE_LEVEL> {
+        &self.tree
+    }
+
+    pub fn is_spent(&self, nullifier: &Fr) -> bool {
+        self.nullifiers.contains(nullifier)
+    }
+
+    /// Every nullifier seen so far, for `note::mark_spent_notes`
+    pub fn nullifiers(&self) -> &HashSet<Fr> {
+        &self.nullifiers
+    }
+
+    /// Commitments seen so far, including a pending unpaired one
+    pub fn num_commitments(&self) -> u64 {
+        (self.tree.len() + self.pending_leaf.is_some() as usize) as u64
+    }
+
+    /// Checks that `events` continue the tree without a gap and still fit in it
+    fn check_commitments(&self, events: &[CommitmentEvent]) -> Result<()> {
+        let first_index = self.num_commitments();
+        for (expected, event) in (first_index..).zip(events) {
+            if event.index != expected {
+                return Err(anyhow!(
+                    "Commitment event out of order: expected index {}, got {}",
+                    expected,
+                    event.index
+                ));
+            }
+        }
+
+        let capacity = 1u64 << MERKLE_TREE_LEVEL;
+        if first_index + events.len() as u64 > capacity {
+            return Err(anyhow!("Merkle tree is full (capacity: {})", capacity));
+        }
+        Ok(())
+    }
+
+    /// Inserts a page that passed `check_commitments`
+    fn apply_commitments(&mut self, events: &[CommitmentEvent]) -> Result<()> {
+        let mut leaves: Vec<Fr> = self.pending_leaf.take().into_iter().collect();
+        leaves.extend(events.iter().map(|event| event.commitment));
+
+        if !leaves.len().is_multiple_of(2) {
+            self.pending_leaf = leaves.pop();
+        }
+        self.tree.bulk_insert(&leaves, &PoseidonOptimized::new_t3())
+    }
+}
+
+/// Pulls pool events from an `EventSource` into a `SyncState`
+pub struct SyncEngine<S: EventSource> {
+    source: S,
+    state: SyncState,
+    page_size: u32,
+}
+
+impl<S: EventSource> SyncEngine<S> {
+    pub fn new(source: S, state: SyncState) -> Self {
+        Self {
+            source,
+            state,
+            page_size: crate::chain::DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn state(&self) -> &SyncState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> SyncState {
+        self.state
+    }
+
+    /// Applies every event published since the last sync.
+    ///
+    /// Commitments are synced before nullifiers. `on_progress` runs after each applied page.
+    /// Once caught up, the local root is checked against the pool's root history.
+    ///
+    /// # Errors
+    /// Returns error if fetching fails, events are out of order, the note store rejects a page
+    /// or the synced tree does not produce a root the pool knows. Pages applied before the
+    /// error are kept.
+    pub fn sync(
+        &mut self,
+        notes: &mut impl NoteStore,
+        mut on_progress: impl FnMut(SyncProgress),
+    ) -> Result<SyncProgress> {
+        let pool = self.source.pool_state()?;
+        let mut progress = SyncProgress {
+            commitments: self.state.num_commitments(),
+            target_commitments: pool.next_index,
+            nullifiers: self.state.nullifiers.len() as u64,
+            pages: 0,
+        };
+
+        loop {
+            let page = self
+                .source
+                .commitment_events_page(self.state.commitment_cursor.as_ref(), self.page_size)?;
+
+            self.state.check_commitments(&page.events)?;
+            notes.apply_commitments(&page.events)?;
+            self.state.apply_commitments(&page.events)?;
+            if let Some(cursor) = &page.next_cursor {
+                self.state.commitment_cursor = Some(cursor.clone());
+            }
+
+            progress.commitments = self.state.num_commitments();
+            progress.pages += 1;
+            on_progress(progress);
+
+            if !page.has_next_page || page.next_cursor.is_none() {
+                break;
+            }
+        }
+
+        loop {
+            let page = self
+                .source
+                .nullifier_events_page(self.state.nullifier_cursor.as_ref(), self.page_size)?;
+
+            let nullifiers: Vec<Fr> = page.events.iter().map(|e| e.nullifier).collect();
+            notes.apply_nullifiers(&nullifiers)?;
+            self.state.nullifiers.extend(nullifiers);
+            if let Some(cursor) = &page.next_cursor {
+                self.state.nullifier_cursor = Some(cursor.clone());
+            }
+
+            progress.nullifiers = self.state.nullifiers.len() as u64;
+            progress.pages += 1;
+            on_progress(progress);
+
+            if !page.has_next_page || page.next_cursor.is_none() {
+                break;
+            }
+        }
+
+        if self.state.pending_leaf.is_none()
+            && self.state.tree.len() as u64 == pool.next_index
+            && !pool.is_known_root(&self.state.tree.root())
+        {
+            return Err(anyhow!(
+                "Synced tree root is not in the pool's root history"
+            ));
+        }
+
+        Ok(progress)
+    }
+}
+
+fn empty_leaf() -> Result<Fr> {
+    parse_fr(ZERO_VALUE, FieldParseMode::Strict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::build_tree;
+
+    struct MockSource {
+        commitments: Vec<CommitmentEvent>,
+        nullifiers: Vec<NullifierEvent>,
+        pool: PoolState,
+    }
+
+    fn page<T: Clone>(all: &[T], cursor: Option<&EventCursor>, limit: u32) -> EventPage<T> {
+        let start = cursor.map_or(0, |c| c.event_seq.parse::<usize>().unwrap());
+        let end = (start + limit as usize).min(all.len());
+        EventPage {
+            events: all[start..end].to_vec(),
+            next_cursor: Some(EventCursor {
+                tx_digest: "digest".to_string(),
+                event_seq: end.to_string(),
+            }),
+            has_next_page: end < all.len(),
+        }
+    }
+
+    impl EventSource for MockSource {
+        fn commitment_events_page(
+            &self,
+            cursor: Option<&EventCursor>,
+            limit: u32,
+        ) -> Result<EventPage<CommitmentEvent>> {
+            Ok(page(&self.commitments, cursor, limit))
+        }
+
+        fn nullifier_events_page(
+            &self,
+            cursor: Option<&EventCursor>,
+            limit: u32,
+        ) -> Result<EventPage<NullifierEvent>> {
+            Ok(page(&self.nullifiers, cursor, limit))
+        }
+
+        fn pool_state(&self) -> Result<PoolState> {
+            Ok(self.pool.clone())
+        }
+    }
+
+    fn mock_source(num_commitments: u64) -> MockSource {
+        let commitments: Vec<CommitmentEvent> = (0..num_commitments)
+            .map(|index| CommitmentEvent {
+                index,
+                commitment: Fr::from(1000 + index),
+                encrypted_output: Vec::new(),
+            })
+            .collect();
+        let root = build_tree(&commitments).unwrap().root();
+        MockSource {
+            commitments,
+            nullifiers: vec![NullifierEvent {
+                nullifier: Fr::from(7u64),
+            }],
+            pool: PoolState {
+                root,
+                root_history: vec![root],
+                next_index: num_commitments,
+            },
+        }
+    }
+
+    struct RejectingStore {
+        reject_commitments: bool,
+    }
+
+    impl NoteStore for RejectingStore {
+        fn apply_commitments(&mut self, _events: &[CommitmentEvent]) -> Result<()> {
+            if self.reject_commitments {
+                return Err(anyhow!("disk full"));
+            }
+            Ok(())
+        }
+
+        fn apply_nullifiers(&mut self, _nullifiers: &[Fr]) -> Result<()> {
+            Err(anyhow!("disk full"))
+        }
+    }
+
+    #[test]
+    fn test_sync_in_odd_pages_matches_full_rebuild() {
+        let source = mock_source(6);
+        let expected_root = source.pool.root;
+
+        let mut engine = SyncEngine::new(source, SyncState::new().unwrap()).with_page_size(3);
+        let mut reports = Vec::new();
+        let progress = engine.sync(&mut (), |p| reports.push(p)).unwrap();
+
+        assert_eq!(progress.commitments, 6);
+        assert_eq!(progress.target_commitments, 6);
+        assert_eq!(engine.state().tree().root(), expected_root);
+        assert!(engine.state().is_spent(&Fr::from(7u64)));
+        assert_eq!(reports.len(), 3);
+    }
+
+    #[test]
+    fn test_sync_resumes_from_checkpoint() {
+        let mut engine = SyncEngine::new(mock_source(4), SyncState::new().unwrap());
+        engine.sync(&mut (), |_| {}).unwrap();
+        let checkpoint = engine.state().checkpoint();
+
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        let restored: SyncCheckpoint = serde_json::from_str(&json).unwrap();
+        let state = SyncState::from_checkpoint(&restored).unwrap();
+        assert_eq!(state.tree().root(), engine.state().tree().root());
+
+        let mut engine = SyncEngine::new(mock_source(8), state).with_page_size(3);
+        let progress = engine.sync(&mut (), |_| {}).unwrap();
+        assert_eq!(progress.commitments, 8);
+    }
+
+    #[test]
+    fn test_rejected_page_leaves_state_untouched() {
+        let mut engine = SyncEngine::new(mock_source(2), SyncState::new().unwrap());
+        let mut store = RejectingStore {
+            reject_commitments: true,
+        };
+        assert!(engine.sync(&mut store, |_| {}).is_err());
+        assert_eq!(engine.state().num_commitments(), 0);
+        assert_eq!(
+            engine.state().checkpoint(),
+            SyncState::new().unwrap().checkpoint()
+        );
+
+        let mut store = RejectingStore {
+            reject_commitments: false,
+        };
+        assert!(engine.sync(&mut store, |_| {}).is_err());
+        let checkpoint = engine.state().checkpoint();
+        assert_eq!(checkpoint.leaves.len(), 2);
+        assert!(checkpoint.nullifiers.is_empty());
+        assert_eq!(checkpoint.nullifier_cursor, None);
+    }
+
+    #[test]
+    fn test_out_of_order_page_keeps_earlier_pages() {
+        let mut source = mock_source(4);
+        source.commitments[3].index = 5;
+        let mut engine = SyncEngine::new(source, SyncState::new().unwrap()).with_page_size(2);
+
+        let err = engine.sync(&mut (), |_| {}).unwrap_err();
+        assert!(err.to_string().contains("out of order"));
+        let checkpoint = engine.state().checkpoint();
+        assert_eq!(checkpoint.leaves.len(), 2);
+        assert_eq!(checkpoint.pending_leaf, None);
+        assert_eq!(checkpoint.commitment_cursor.unwrap().event_seq, "2");
+    }
+}
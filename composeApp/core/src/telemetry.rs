@@ -0,0 +1,117 @@
+//! Opt-in per-phase timing for the proving pipeline.
+//!
+//! When enabled, every proof records how long each phase took and the stats of the most
+//! recent proof can be read back through `get_last_proof_stats()` (uniffi and wasm).
+//! Collection is off by default. Disabled, it still costs a handful of clock reads and an
+//! atomic load per proof, which is negligible next to proving.
+
+use crate::metrics;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use web_time::Instant;
+
+static STATS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref LAST_PROOF_STATS: Mutex<Option<ProofStats>> = Mutex::new(None);
+}
+
+/// Durations (in microseconds) of each phase of the most recent proof
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ProofStats {
+    /// JSON decoding and field-element parsing into a circuit
+    pub parse_us: u64,
+    /// Running the circuit synthesizer (witness assignment)
+    pub witness_us: u64,
+    /// Finalizing the constraint system and building the R1CS matrices
+    pub constraints_us: u64,
+    /// QAP witness map and the Groth16 MSMs
+    pub prove_us: u64,
+    /// Encoding the proof and public inputs into the output format
+    pub serialize_us: u64,
+    /// End-to-end duration, including anything between phases
+    pub total_us: u64,
+    /// Number of R1CS constraints in the synthesized circuit
+    pub num_constraints: u64,
+}
+
+/// Proving pipeline phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parse,
+    Witness,
+    Constraints,
+    Prove,
+    Serialize,
+}
+
+/// Collects phase durations for a single proof.
+///
+/// Each call to `end_phase` attributes the time since the previous mark to the given phase.
+pub struct PhaseTimer {
+    stats: ProofStats,
+    start: Instant,
+    mark: Instant,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            stats: ProofStats::default(),
+            start: now,
+            mark: now,
+        }
+    }
+
+    /// Attributes the time elapsed since the last mark to `phase`
+    pub fn end_phase(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.mark).as_micros() as u64;
+        self.mark = now;
+
+        let slot = match phase {
+            Phase::Parse => &mut self.stats.parse_us,
+            Phase::Witness => &mut self.stats.witness_us,
+            Phase::Constraints => &mut self.stats.constraints_us,
+            Phase::Prove => &mut self.stats.prove_us,
+            Phase::Serialize => &mut self.stats.serialize_us,
+        };
+        *slot += elapsed;
+    }
+
+    pub fn set_num_constraints(&mut self, num_constraints: usize) {
+        self.stats.num_constraints = num_constraints as u64;
+    }
+
+    /// Completes the measurement and publishes it if collection is enabled
+    pub fn finish(mut self) -> ProofStats {
+        self.stats.total_us = self.start.elapsed().as_micros() as u64;
+        metrics::proof_finished(&self.stats);
+        if is_enabled() {
+            *LAST_PROOF_STATS.lock().unwrap() = Some(self.stats);
+        }
+        self.stats
+    }
+}
+
+/// Turns proof stats collection on or off. Disabling also clears the last recorded stats.
+pub fn set_enabled(enabled: bool) {
+    STATS_ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        *LAST_PROOF_STATS.lock().unwrap() = None;
+    }
+}
+
+pub fn is_enabled() -> bool {
+    STATS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Stats of the most recent proof, if collection was enabled while it ran
+pub fn last_proof_stats() -> Option<ProofStats> {
+    *LAST_PROOF_STATS.lock().unwrap()
+}
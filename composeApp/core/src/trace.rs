@@ -0,0 +1,22 @@
+//! Optional `tracing` instrumentation of the proving pipeline.
+//!
+//! With the `tracing` feature, `trace_span!` enters an info-level span for the rest of the
+//! enclosing block. When the host has not installed a `tracing` subscriber, span entry and
+//! exit are forwarded to the `log` crate and so reach the logger set up by `init_logger`.
+//! Without the feature the macro expands to nothing.
+//!
+//! The macro is exported for the binding crates, and follows this crate's `tracing` feature.
+
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_span {
+    ($($args:tt)+) => {
+        let _span = $crate::__tracing::info_span!($($args)+).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_span {
+    ($($args:tt)+) => {};
+}
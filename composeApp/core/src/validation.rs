@@ -0,0 +1,206 @@
+//! Deserialization of Groth16 proofs and keys with an explicit point-validation policy.
+//!
+//! Checked deserialization verifies that every curve point is on the curve and in the
+//! prime-order subgroup. This is what arkworks' `deserialize_compressed` does and it must be
+//! used for anything received over the network (proofs, keys fetched at runtime).
+//! Unchecked deserialization skips those checks and is only appropriate for key material the
+//! app ships itself, where it noticeably shortens proving-key load time on mobile.
+//!
+//! Proving keys may also be stored zstd-compressed (`format::PROVING_KEY_ZSTD`), which mostly
+//! saves the identity points padding the A and B queries. `read_proving_key` decompresses such
+//! a key as it deserializes, so neither a temp file nor the decompressed bytes are needed.
+
+use crate::format;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+use ruzstd::decoding::StreamingDecoder;
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Point-validation policy applied when deserializing proofs and keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum PointValidation {
+    /// On-curve and subgroup checks for every point (default)
+    #[default]
+    Checked,
+    /// No point checks; only for trusted, locally bundled key material
+    Unchecked,
+}
+
+impl PointValidation {
+    pub(crate) fn mode(self) -> Validate {
+        match self {
+            PointValidation::Checked => Validate::Yes,
+            PointValidation::Unchecked => Validate::No,
+        }
+    }
+}
+
+fn describe(what: &str, e: SerializationError) -> anyhow::Error {
+    match e {
+        SerializationError::InvalidData => anyhow!(
+            "Invalid {}: malformed encoding or a point not on the curve / not in the prime-order subgroup",
+            what
+        ),
+        e => anyhow!("Failed to deserialize {}: {}", what, e),
+    }
+}
+
+/// Deserializes a compressed Groth16 proving key, framed with `format::PROVING_KEY` or
+/// `format::PROVING_KEY_ZSTD`, or bare as written by earlier `keygen` runs
+pub fn deserialize_proving_key(
+    bytes: &[u8],
+    validation: PointValidation,
+) -> Result<ProvingKey<Bn254>> {
+    if format::PROVING_KEY_ZSTD.is_framed(bytes) {
+        return read_proving_key(bytes, validation);
+    }
+    let body = if format::PROVING_KEY.is_framed(bytes) {
+        format::PROVING_KEY.decode(bytes)?
+    } else {
+        Cow::Borrowed(bytes)
+    };
+    ProvingKey::<Bn254>::deserialize_with_mode(&body[..], Compress::Yes, validation.mode())
+        .map_err(|e| describe("proving key", e))
+}
+
+/// Reads a proving key in any format `deserialize_proving_key` accepts from `reader`,
+/// decompressing zstd keys while deserializing them.
+///
+/// # Errors
+/// Returns error if reading fails or the key is truncated or malformed.
+pub fn read_proving_key<R: Read>(
+    mut reader: R,
+    validation: PointValidation,
+) -> Result<ProvingKey<Bn254>> {
+    let mut header = [0u8; format::HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .context("Proving key is truncated")?;
+    let read = |reader: &mut dyn Read| {
+        ProvingKey::<Bn254>::deserialize_with_mode(reader, Compress::Yes, validation.mode())
+            .map_err(|e| describe("proving key", e))
+    };
+
+    if format::PROVING_KEY_ZSTD.is_framed(&header) {
+        format::PROVING_KEY_ZSTD.read_header(&header)?;
+        let mut decoder = StreamingDecoder::new(reader)
+            .map_err(|e| anyhow!("Invalid zstd proving key: {}", e))?;
+        read(&mut decoder)
+    } else if format::PROVING_KEY.is_framed(&header) {
+        // Version 1 is the only one so far, so there is nothing to migrate while streaming
+        format::PROVING_KEY.read_header(&header)?;
+        read(&mut reader)
+    } else {
+        read(&mut header.as_slice().chain(reader))
+    }
+}
+
+/// Recompresses a serialized proving key with zstd and frames it with
+/// `format::PROVING_KEY_ZSTD`
+///
+/// # Errors
+/// Returns error if `proving_key` is framed as something other than a proving key.
+pub fn compress_proving_key(proving_key: &[u8]) -> Result<Vec<u8>> {
+    let body = if format::PROVING_KEY.is_framed(proving_key) {
+        format::PROVING_KEY.decode(proving_key)?
+    } else if format::PROVING_KEY_ZSTD.is_framed(proving_key) {
+        return Err(anyhow!("Proving key is already zstd-compressed"));
+    } else {
+        Cow::Borrowed(proving_key)
+    };
+    Ok(format::PROVING_KEY_ZSTD.encode(&compress_to_vec(&body[..], CompressionLevel::Fastest)))
+}
+
+/// Compresses a proving key and frames it with `format::PROVING_KEY`
+pub fn serialize_proving_key(pk: &ProvingKey<Bn254>) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    pk.serialize_compressed(&mut body)?;
+    Ok(format::PROVING_KEY.encode(&body))
+}
+
+/// Deserializes a compressed Groth16 verifying key
+pub fn deserialize_verifying_key(
+    bytes: &[u8],
+    validation: PointValidation,
+) -> Result<VerifyingKey<Bn254>> {
+    VerifyingKey::<Bn254>::deserialize_with_mode(bytes, Compress::Yes, validation.mode())
+        .map_err(|e| describe("verifying key", e))
+}
+
+/// Deserializes a compressed Groth16 proof
+pub fn deserialize_proof(bytes: &[u8], validation: PointValidation) -> Result<Proof<Bn254>> {
+    Proof::<Bn254>::deserialize_with_mode(bytes, Compress::Yes, validation.mode())
+        .map_err(|e| describe("proof", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::TransactionCircuit;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_groth16::Groth16;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_zstd_keys_stream_to_the_same_key() {
+        let setup_circuit = TransactionCircuit::empty()
+            .with_dummy_inputs([true, true])
+            .unwrap();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            setup_circuit,
+            &mut ChaCha20Rng::from_seed([6u8; 32]),
+        )
+        .unwrap();
+        let framed = serialize_proving_key(&pk).unwrap();
+        let compressed = compress_proving_key(&framed).unwrap();
+        assert!(compressed.len() < framed.len());
+        assert!(compress_proving_key(&compressed).is_err());
+
+        let bare = &framed[format::HEADER_LEN..];
+        assert_eq!(compress_proving_key(bare).unwrap(), compressed);
+        for bytes in [&framed[..], bare, &compressed[..]] {
+            let read = read_proving_key(bytes, PointValidation::Unchecked).unwrap();
+            assert_eq!(read, pk);
+        }
+        assert_eq!(
+            deserialize_proving_key(&compressed, PointValidation::Checked).unwrap(),
+            pk
+        );
+        assert!(read_proving_key(
+            &compressed[..compressed.len() / 2],
+            PointValidation::Unchecked
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_checked_rejects_point_off_curve() {
+        let proof = Proof::<Bn254> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        assert!(deserialize_proof(&bytes, PointValidation::Checked).is_ok());
+
+        // Search for an x-coordinate of `a` that is not on the curve
+        let mut tampered = bytes.clone();
+        let err = (0u8..=255)
+            .find_map(|b| {
+                tampered[0] = b;
+                deserialize_proof(&tampered, PointValidation::Checked).err()
+            })
+            .expect("some x-coordinate must be off the curve");
+        assert!(err.to_string().contains("proof"));
+    }
+}
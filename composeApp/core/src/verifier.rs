@@ -0,0 +1,127 @@
+//! Verification of many proofs against one verifying key.
+//!
+//! Explorers and auditing tools check whole sets of pool transactions, so the key is
+//! prepared once and, when randomized, the batch is checked with a single multi-pairing:
+//! each proof's Groth16 equation is scaled by a random 128-bit `r_i` and the sum
+//!
+//! `Σ e(r_i·A_i, B_i) - e(Σ r_i·L_i, γ) - e(Σ r_i·C_i, δ) = (Σ r_i)·e(α, β)`
+//!
+//! holds for all valid proofs, while a batch containing an invalid one passes only with
+//! probability ~2^-128. On failure every proof is checked on its own, so the result still
+//! says which ones are bad.
+
+use ark_bn254::{Bn254, Fr, G1Projective, G2Affine};
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::CurveGroup;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use rand_core::{CryptoRng, RngCore};
+
+/// A proof and its public inputs
+pub type ProofWithInputs = (Proof<Bn254>, Vec<Fr>);
+
+/// Verifies `proofs`, returning one verdict per proof in order.
+///
+/// A proof with the wrong number of public inputs is reported invalid rather than failing
+/// the batch.
+pub fn verify_batch<R: RngCore + CryptoRng>(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    proofs: &[ProofWithInputs],
+    randomized: bool,
+    rng: &mut R,
+) -> Vec<bool> {
+    if randomized && proofs.len() > 1 && batch_holds(pvk, proofs, rng) {
+        return vec![true; proofs.len()];
+    }
+    proofs
+        .iter()
+        .map(|(proof, inputs)| Groth16::<Bn254>::verify_proof(pvk, proof, inputs).unwrap_or(false))
+        .collect()
+}
+
+fn batch_holds<R: RngCore + CryptoRng>(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    proofs: &[ProofWithInputs],
+    rng: &mut R,
+) -> bool {
+    let mut g1 = Vec::with_capacity(proofs.len() + 2);
+    let mut g2: Vec<<Bn254 as Pairing>::G2Prepared> = Vec::with_capacity(proofs.len() + 2);
+    let mut r_sum = Fr::from(0u64);
+    let mut inputs_acc = G1Projective::default();
+    let mut c_acc = G1Projective::default();
+
+    for (proof, inputs) in proofs {
+        let Ok(prepared) = Groth16::<Bn254>::prepare_inputs(pvk, inputs) else {
+            return false;
+        };
+        let r = Fr::from(((rng.next_u64() as u128) << 64) | rng.next_u64() as u128);
+        r_sum += r;
+        inputs_acc += prepared * r;
+        c_acc += proof.c * r;
+        g1.push((proof.a * r).into_affine());
+        g2.push(G2Affine::from(proof.b).into());
+    }
+    g1.push(inputs_acc.into_affine());
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g1.push(c_acc.into_affine());
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    let Some(lhs) = Bn254::final_exponentiation(Bn254::multi_miller_loop(g1, g2)) else {
+        return false;
+    };
+    lhs == PairingOutput(pvk.alpha_g1_beta_g2) * r_sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    /// Knowledge of `x` with `x * x = y`
+    #[derive(Clone)]
+    struct Square {
+        x: Fr,
+        y: Fr,
+    }
+
+    impl ConstraintSynthesizer<Fr> for Square {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let y = FpVar::new_input(cs.clone(), || Ok(self.y))?;
+            let x = FpVar::new_witness(cs, || Ok(self.x))?;
+            (&x * &x).enforce_equal(&y)
+        }
+    }
+
+    #[test]
+    fn test_batch_flags_only_the_invalid_proof() {
+        let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+        let setup = Square {
+            x: Fr::from(0u64),
+            y: Fr::from(0u64),
+        };
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(setup, &mut rng).unwrap();
+        let pvk = ark_groth16::prepare_verifying_key(&vk);
+
+        let mut proofs: Vec<ProofWithInputs> = (1..=4u64)
+            .map(|x| {
+                let circuit = Square {
+                    x: Fr::from(x),
+                    y: Fr::from(x * x),
+                };
+                let proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+                (proof, vec![Fr::from(x * x)])
+            })
+            .collect();
+
+        assert_eq!(verify_batch(&pvk, &proofs, true, &mut rng), vec![true; 4]);
+
+        proofs[2].1[0] += Fr::from(1u64);
+        proofs[3].1.push(Fr::from(1u64));
+        let expected = vec![true, true, false, false];
+        assert_eq!(verify_batch(&pvk, &proofs, true, &mut rng), expected);
+        assert_eq!(verify_batch(&pvk, &proofs, false, &mut rng), expected);
+    }
+}
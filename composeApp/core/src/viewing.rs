@@ -0,0 +1,796 @@
+//! Viewing keys and note encryption.
+//!
+//! Every key is derived from a 32-byte spend seed with HKDF-SHA256:
+//! - the spending key (`in_private_key`), whose `Poseidon1` is the note public key
+//! - the incoming viewing key, an X25519 secret; senders encrypt notes to its public key
+//! - the outgoing viewing key, a symmetric key the sender uses to keep a readable copy
+//!
+//! A `FullViewingKey` holds everything except the spending key. It decrypts incoming and
+//! outgoing notes and checks them against their on-chain commitments, but cannot derive
+//! nullifiers or sign, so it suits watch-only wallets and auditors. An `IncomingViewingKey`
+//! alone discloses only received notes.
+//!
+//! Encrypted output layout (`encrypted_output` in the pool events):
+//! `version || ephemeral_pk (32) || nonce (12) || incoming ciphertext (56) || outgoing ciphertext (88)`
+//!
+//! Output blindings can be derived from the same seed with `derive_output_blinding`, so a
+//! restored wallet recomputes them instead of backing them up. The derivation is frozen:
+//! `Fr::from_le_bytes_mod_order(HKDF-SHA256(ikm = seed, info = "vortex-output-blinding-v1"
+//! || recipient public key (32, LE) || output_index (4, LE) || tx_nonce (8, LE), 64 bytes))`.
+//! A change would get a new info tag rather than alter this one.
+//!
+//! Outputs proven with ciphertext binding commit to `Poseidon2(blinding, ciphertext_hash)`,
+//! which is the blinding the recipient later spends with.
+
+use crate::circuit::bind_ciphertext;
+use crate::field::{ct_eq, fr_from_bytes_le, fr_to_bytes_le, parse_fr, FieldParseMode};
+use crate::poseidon_opt::{hash1, hash4};
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Encrypted output format version, also the AEAD associated data
+pub const NOTE_ENCRYPTION_VERSION: u8 = 1;
+
+const SPENDING_KEY_INFO: &[u8] = b"vortex-spending-key-v1";
+const INCOMING_VIEWING_KEY_INFO: &[u8] = b"vortex-incoming-viewing-key-v1";
+const OUTGOING_VIEWING_KEY_INFO: &[u8] = b"vortex-outgoing-viewing-key-v1";
+const INCOMING_NOTE_KEY_INFO: &[u8] = b"vortex-note-incoming-v1";
+const OUTGOING_NOTE_KEY_INFO: &[u8] = b"vortex-note-outgoing-v1";
+const OUTPUT_BLINDING_INFO: &[u8] = b"vortex-output-blinding-v1";
+
+const NOTE_PLAINTEXT_LEN: usize = 40;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = 1 + 32 + 12;
+const INCOMING_CIPHERTEXT_LEN: usize = NOTE_PLAINTEXT_LEN + TAG_LEN;
+const OUTGOING_CIPHERTEXT_LEN: usize = 32 + NOTE_PLAINTEXT_LEN + TAG_LEN;
+
+/// Length of an encrypted output
+pub const ENCRYPTED_NOTE_LEN: usize =
+    HEADER_LEN + INCOMING_CIPHERTEXT_LEN + OUTGOING_CIPHERTEXT_LEN;
+
+/// The private part of a note; the owner's public key is implied by who can decrypt it
+#[derive(Clone, PartialEq, Eq)]
+pub struct NotePlaintext {
+    pub amount: u64,
+    pub blinding: Fr,
+}
+
+impl std::fmt::Debug for NotePlaintext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotePlaintext")
+            .field("amount", &self.amount)
+            .field("blinding", &"<redacted>")
+            .finish()
+    }
+}
+
+impl NotePlaintext {
+    /// `Poseidon4(amount, public_key, blinding, vortex)`, as computed by the circuit
+    pub fn commitment(&self, public_key: &Fr, vortex: &Fr) -> Fr {
+        hash4(&Fr::from(self.amount), public_key, &self.blinding, vortex)
+    }
+
+    fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(NOTE_PLAINTEXT_LEN));
+        bytes.extend_from_slice(&self.amount.to_le_bytes());
+        bytes.extend_from_slice(&fr_to_bytes_le(&self.blinding));
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != NOTE_PLAINTEXT_LEN {
+            return Err(anyhow!("Note plaintext has the wrong length"));
+        }
+        let amount = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let blinding = fr_from_bytes_le(&bytes[8..])
+            .context("Note blinding is not a canonical field element")?;
+        Ok(Self { amount, blinding })
+    }
+}
+
+/// Decrypts incoming notes only
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct IncomingViewingKey([u8; 32]);
+
+/// Decrypts incoming and outgoing notes and checks their commitments; cannot spend
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct FullViewingKey {
+    #[zeroize(skip)]
+    pub spend_public_key: Fr,
+    pub incoming: IncomingViewingKey,
+    outgoing: [u8; 32],
+}
+
+/// All keys of one spend seed
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SpendingKeys {
+    spending_key: Fr,
+    pub viewing: FullViewingKey,
+}
+
+impl std::fmt::Debug for IncomingViewingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IncomingViewingKey(<redacted>)")
+    }
+}
+
+impl std::fmt::Debug for FullViewingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FullViewingKey(<redacted>)")
+    }
+}
+
+impl std::fmt::Debug for SpendingKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SpendingKeys(<redacted>)")
+    }
+}
+
+impl SpendingKeys {
+    pub fn from_seed(seed: &[u8]) -> Result<Self> {
+        if seed.len() != 32 {
+            return Err(anyhow!("Spend seed must be 32 bytes, got {}", seed.len()));
+        }
+        let hkdf = Hkdf::<Sha256>::new(None, seed);
+        let expand = |info: &[u8], len: usize| {
+            let mut out = Zeroizing::new(vec![0u8; len]);
+            hkdf.expand(info, &mut out)
+                .map_err(|_| anyhow!("Failed to derive key"))?;
+            Ok::<_, anyhow::Error>(out)
+        };
+
+        // 64 bytes keep the reduction bias negligible
+        let spending_key = Fr::from_le_bytes_mod_order(&expand(SPENDING_KEY_INFO, 64)?);
+        let incoming = expand(INCOMING_VIEWING_KEY_INFO, 32)?;
+        let outgoing = expand(OUTGOING_VIEWING_KEY_INFO, 32)?;
+
+        Ok(Self {
+            spending_key,
+            viewing: FullViewingKey {
+                spend_public_key: hash1(&spending_key),
+                incoming: IncomingViewingKey(incoming[..].try_into().unwrap()),
+                outgoing: outgoing[..].try_into().unwrap(),
+            },
+        })
+    }
+
+    /// The `in_private_key` of notes owned by this seed
+    pub fn spending_key(&self) -> &Fr {
+        &self.spending_key
+    }
+}
+
+/// Derives the blinding of output `output_index` paying `recipient_public_key` in the
+/// transaction numbered `tx_nonce`, as documented in the module header.
+///
+/// The nonce must be unique per transaction built from `seed`; reusing one for the same
+/// recipient and slot repeats the blinding and links the two outputs.
+pub fn derive_output_blinding(
+    seed: &[u8],
+    recipient_public_key: &Fr,
+    output_index: u32,
+    tx_nonce: u64,
+) -> Result<Fr> {
+    if seed.len() != 32 {
+        return Err(anyhow!("Spend seed must be 32 bytes, got {}", seed.len()));
+    }
+    let mut info = OUTPUT_BLINDING_INFO.to_vec();
+    info.extend_from_slice(&fr_to_bytes_le(recipient_public_key));
+    info.extend_from_slice(&output_index.to_le_bytes());
+    info.extend_from_slice(&tx_nonce.to_le_bytes());
+
+    let mut okm = Zeroizing::new([0u8; 64]);
+    Hkdf::<Sha256>::new(None, seed)
+        .expand(&info, okm.as_mut())
+        .map_err(|_| anyhow!("Failed to derive output blinding"))?;
+    Ok(Fr::from_le_bytes_mod_order(okm.as_ref()))
+}
+
+impl IncomingViewingKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self(bytes.try_into().map_err(|_| {
+            anyhow!("Incoming viewing key must be 32 bytes, got {}", bytes.len())
+        })?))
+    }
+
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.0)
+    }
+
+    /// X25519 key senders encrypt notes to
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&StaticSecret::from(self.0)).to_bytes()
+    }
+
+    /// Decrypts a note sent to this key, or `None` if it was sent to someone else
+    pub fn decrypt(&self, encrypted_output: &[u8]) -> Option<NotePlaintext> {
+        self.decrypt_with(&self.public_key(), encrypted_output)
+    }
+
+    /// `decrypt` with this key's public key computed once by the caller, which halves the
+    /// scalar multiplications when scanning many outputs
+    fn decrypt_with(
+        &self,
+        public_key: &[u8; 32],
+        encrypted_output: &[u8],
+    ) -> Option<NotePlaintext> {
+        let parts = EncryptedParts::split(encrypted_output).ok()?;
+        let shared = StaticSecret::from(self.0).diffie_hellman(&PublicKey::from(parts.ephemeral));
+        let key = note_key(
+            shared.as_bytes(),
+            &parts.ephemeral,
+            public_key,
+            INCOMING_NOTE_KEY_INFO,
+        )
+        .ok()?;
+        let plaintext = open(&key, parts.nonce, parts.incoming).ok()?;
+        NotePlaintext::from_bytes(&plaintext).ok()
+    }
+}
+
+impl FullViewingKey {
+    /// Serialized as `spend_public_key (32, LE) || incoming (32) || outgoing (32)`
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(fr_to_bytes_le(&self.spend_public_key).to_vec());
+        bytes.extend_from_slice(&self.incoming.0);
+        bytes.extend_from_slice(&self.outgoing);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 96 {
+            return Err(anyhow!(
+                "Full viewing key must be 96 bytes, got {}",
+                bytes.len()
+            ));
+        }
+        let spend_public_key = fr_from_bytes_le(&bytes[..32])
+            .context("Spend public key is not a canonical field element")?;
+        Ok(Self {
+            spend_public_key,
+            incoming: IncomingViewingKey::from_bytes(&bytes[32..64])?,
+            outgoing: bytes[64..].try_into().unwrap(),
+        })
+    }
+
+    /// Decrypts a note received by this wallet and checks it opens `commitment`.
+    ///
+    /// If the commitment is bound to the ciphertext, the returned blinding is the bound one,
+    /// which is what the note must be spent with.
+    pub fn decrypt_incoming(
+        &self,
+        encrypted_output: &[u8],
+        commitment: &Fr,
+        vortex: &Fr,
+    ) -> Option<NotePlaintext> {
+        self.decrypt_incoming_with(
+            &self.incoming.public_key(),
+            encrypted_output,
+            commitment,
+            vortex,
+        )
+    }
+
+    fn decrypt_incoming_with(
+        &self,
+        incoming_public_key: &[u8; 32],
+        encrypted_output: &[u8],
+        commitment: &Fr,
+        vortex: &Fr,
+    ) -> Option<NotePlaintext> {
+        let note = self
+            .incoming
+            .decrypt_with(incoming_public_key, encrypted_output)?;
+        let bound = NotePlaintext {
+            amount: note.amount,
+            blinding: bind_ciphertext(&note.blinding, &ciphertext_hash(encrypted_output)),
+        };
+        // Both candidates are hashed and compared so timing does not reveal which one matched
+        let plain_matches = ct_eq(&note.commitment(&self.spend_public_key, vortex), commitment);
+        let bound_matches = ct_eq(
+            &bound.commitment(&self.spend_public_key, vortex),
+            commitment,
+        );
+        if bool::from(plain_matches) {
+            Some(note)
+        } else {
+            bool::from(bound_matches).then_some(bound)
+        }
+    }
+
+    /// Trial-decrypts a batch of pool outputs and returns the notes received by this wallet,
+    /// each checked against its commitment.
+    ///
+    /// # Errors
+    /// Returns error if an event's ciphertext hex or commitment is malformed.
+    pub fn scan_notes(&self, events: &[OutputEvent], vortex: &Fr) -> Result<Vec<ScannedNote>> {
+        let incoming_public_key = self.incoming.public_key();
+        let mut received = Vec::new();
+        for event in events {
+            let encrypted = hex::decode(event.encrypted_output.trim_start_matches("0x"))
+                .with_context(|| {
+                    format!("Invalid encrypted output hex at leaf {}", event.leaf_index)
+                })?;
+            let commitment = parse_fr(&event.commitment, FieldParseMode::Strict)
+                .with_context(|| format!("Invalid commitment at leaf {}", event.leaf_index))?;
+
+            if let Some(note) =
+                self.decrypt_incoming_with(&incoming_public_key, &encrypted, &commitment, vortex)
+            {
+                received.push(ScannedNote {
+                    leaf_index: event.leaf_index,
+                    commitment: event.commitment.clone(),
+                    note: DecryptedNote::new(&note, None),
+                });
+            }
+        }
+        Ok(received)
+    }
+
+    /// Decrypts a note this wallet sent, returning the recipient's incoming viewing public key
+    pub fn decrypt_outgoing(&self, encrypted_output: &[u8]) -> Option<([u8; 32], NotePlaintext)> {
+        let parts = EncryptedParts::split(encrypted_output).ok()?;
+        let key = note_key(
+            &self.outgoing,
+            &parts.ephemeral,
+            &[0u8; 32],
+            OUTGOING_NOTE_KEY_INFO,
+        )
+        .ok()?;
+        let plaintext = open(&key, parts.nonce, parts.outgoing).ok()?;
+        let recipient: [u8; 32] = plaintext[..32].try_into().ok()?;
+        Some((recipient, NotePlaintext::from_bytes(&plaintext[32..]).ok()?))
+    }
+}
+
+/// Encrypts `note` to `recipient_public_key`, with a copy readable by `sender`'s outgoing key
+pub fn encrypt_note<R: RngCore + CryptoRng>(
+    note: &NotePlaintext,
+    recipient_public_key: &[u8],
+    sender: &FullViewingKey,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let recipient: [u8; 32] = recipient_public_key.try_into().map_err(|_| {
+        anyhow!(
+            "Recipient viewing key must be 32 bytes, got {}",
+            recipient_public_key.len()
+        )
+    })?;
+
+    let mut ephemeral_bytes = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(ephemeral_bytes.as_mut());
+    let ephemeral = StaticSecret::from(*ephemeral_bytes);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient));
+    if !shared.was_contributory() {
+        return Err(anyhow!("Recipient viewing key is a low-order point"));
+    }
+
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut nonce);
+
+    let plaintext = note.to_bytes();
+    let incoming_key = note_key(
+        shared.as_bytes(),
+        &ephemeral_public,
+        &recipient,
+        INCOMING_NOTE_KEY_INFO,
+    )?;
+    let incoming = seal(&incoming_key, &nonce, &plaintext)?;
+
+    let mut outgoing_plaintext = Zeroizing::new(recipient.to_vec());
+    outgoing_plaintext.extend_from_slice(&plaintext);
+    let outgoing_key = note_key(
+        &sender.outgoing,
+        &ephemeral_public,
+        &[0u8; 32],
+        OUTGOING_NOTE_KEY_INFO,
+    )?;
+    let outgoing = seal(&outgoing_key, &nonce, &outgoing_plaintext)?;
+
+    let mut out = Vec::with_capacity(ENCRYPTED_NOTE_LEN);
+    out.push(NOTE_ENCRYPTION_VERSION);
+    out.extend_from_slice(&ephemeral_public);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&incoming);
+    out.extend_from_slice(&outgoing);
+    Ok(out)
+}
+
+/// `output_ciphertext_hash` public input of an encrypted output: Blake2b-256 reduced mod r,
+/// as the contract computes it over the bytes it emits
+pub fn ciphertext_hash(encrypted_output: &[u8]) -> Fr {
+    Fr::from_le_bytes_mod_order(&Blake2b::<U32>::digest(encrypted_output))
+}
+
+struct EncryptedParts<'a> {
+    ephemeral: [u8; 32],
+    nonce: &'a [u8],
+    incoming: &'a [u8],
+    outgoing: &'a [u8],
+}
+
+impl<'a> EncryptedParts<'a> {
+    fn split(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() != ENCRYPTED_NOTE_LEN {
+            return Err(anyhow!("Encrypted output has the wrong length"));
+        }
+        if bytes[0] != NOTE_ENCRYPTION_VERSION {
+            return Err(anyhow!("Unsupported note encryption version {}", bytes[0]));
+        }
+        Ok(Self {
+            ephemeral: bytes[1..33].try_into().unwrap(),
+            nonce: &bytes[33..HEADER_LEN],
+            incoming: &bytes[HEADER_LEN..HEADER_LEN + INCOMING_CIPHERTEXT_LEN],
+            outgoing: &bytes[HEADER_LEN + INCOMING_CIPHERTEXT_LEN..],
+        })
+    }
+}
+
+fn note_key(
+    secret: &[u8; 32],
+    ephemeral_public: &[u8; 32],
+    recipient: &[u8; 32],
+    info: &[u8],
+) -> Result<Zeroizing<[u8; 32]>> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public);
+    salt[32..].copy_from_slice(recipient);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), secret)
+        .expand(info, key.as_mut())
+        .map_err(|_| anyhow!("Failed to derive note key"))?;
+    Ok(key)
+}
+
+fn seal(key: &[u8; 32], nonce: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad: &[NOTE_ENCRYPTION_VERSION],
+            },
+        )
+        .map_err(|_| anyhow!("Failed to encrypt note"))
+}
+
+fn open(key: &[u8; 32], nonce: &[u8], ciphertext: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &[NOTE_ENCRYPTION_VERSION],
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow!("Note failed authentication"))
+}
+
+/// Decodes a hex key, accepting an optional `0x` prefix
+pub fn decode_key_hex(s: &str, what: &str) -> Result<Zeroizing<Vec<u8>>> {
+    hex::decode(s.trim().trim_start_matches("0x"))
+        .map(Zeroizing::new)
+        .with_context(|| format!("Invalid {} hex", what))
+}
+
+/// Viewing keys exported for a watch-only wallet or auditor, hex-encoded
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ViewingKeyExport {
+    /// Decimal note public key (`Poseidon1(spending_key)`)
+    pub spend_public_key: String,
+    /// Public key senders encrypt notes to
+    pub incoming_viewing_public_key: String,
+    /// Discloses received notes only
+    pub incoming_viewing_key: String,
+    /// Discloses received and sent notes
+    pub full_viewing_key: String,
+}
+
+impl std::fmt::Debug for ViewingKeyExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ViewingKeyExport")
+            .field("spend_public_key", &self.spend_public_key)
+            .field(
+                "incoming_viewing_public_key",
+                &self.incoming_viewing_public_key,
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl ViewingKeyExport {
+    pub fn new(keys: &FullViewingKey) -> Self {
+        Self {
+            spend_public_key: keys.spend_public_key.into_bigint().to_string(),
+            incoming_viewing_public_key: hex::encode(keys.incoming.public_key()),
+            incoming_viewing_key: hex::encode(*keys.incoming.to_bytes()),
+            full_viewing_key: hex::encode(&*keys.to_bytes()),
+        }
+    }
+}
+
+/// A decrypted note as returned to hosts
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct DecryptedNote {
+    pub amount: u64,
+    /// Decimal blinding
+    pub blinding: String,
+    /// Hex incoming viewing public key of the recipient; only set for outgoing notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient_viewing_public_key: Option<String>,
+}
+
+impl std::fmt::Debug for DecryptedNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecryptedNote")
+            .field("amount", &self.amount)
+            .field("blinding", &"<redacted>")
+            .field(
+                "recipient_viewing_public_key",
+                &self.recipient_viewing_public_key,
+            )
+            .finish()
+    }
+}
+
+impl DecryptedNote {
+    pub fn new(note: &NotePlaintext, recipient: Option<[u8; 32]>) -> Self {
+        Self {
+            amount: note.amount,
+            blinding: note.blinding.into_bigint().to_string(),
+            recipient_viewing_public_key: recipient.map(hex::encode),
+        }
+    }
+}
+
+/// A pool output to trial-decrypt, as emitted with its commitment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEvent {
+    pub leaf_index: u64,
+    /// Decimal commitment
+    pub commitment: String,
+    /// Hex `encrypted_output`
+    pub encrypted_output: String,
+}
+
+/// A note found by `scan_notes`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedNote {
+    pub leaf_index: u64,
+    /// Decimal commitment
+    pub commitment: String,
+    pub note: DecryptedNote,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_incoming_and_outgoing_decryption() {
+        let mut rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let sender = SpendingKeys::from_seed(&[10u8; 32]).unwrap();
+        let recipient = SpendingKeys::from_seed(&[20u8; 32]).unwrap();
+        let vortex = Fr::from(77u64);
+
+        let note = NotePlaintext {
+            amount: 1_000,
+            blinding: Fr::from(123456u64),
+        };
+        let commitment = note.commitment(&recipient.viewing.spend_public_key, &vortex);
+        let encrypted = encrypt_note(
+            &note,
+            &recipient.viewing.incoming.public_key(),
+            &sender.viewing,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(encrypted.len(), ENCRYPTED_NOTE_LEN);
+
+        let watch_only = FullViewingKey::from_bytes(&recipient.viewing.to_bytes()).unwrap();
+        assert_eq!(
+            watch_only.decrypt_incoming(&encrypted, &commitment, &vortex),
+            Some(note.clone())
+        );
+        assert_eq!(
+            watch_only.decrypt_incoming(&encrypted, &Fr::from(1u64), &vortex),
+            None
+        );
+        assert_eq!(sender.viewing.incoming.decrypt(&encrypted), None);
+
+        let bound_blinding = bind_ciphertext(&note.blinding, &ciphertext_hash(&encrypted));
+        let bound_commitment = NotePlaintext {
+            amount: note.amount,
+            blinding: bound_blinding,
+        }
+        .commitment(&recipient.viewing.spend_public_key, &vortex);
+        assert_eq!(
+            watch_only
+                .decrypt_incoming(&encrypted, &bound_commitment, &vortex)
+                .map(|n| n.blinding),
+            Some(bound_blinding)
+        );
+
+        let (to, sent) = sender.viewing.decrypt_outgoing(&encrypted).unwrap();
+        assert_eq!(to, recipient.viewing.incoming.public_key());
+        assert_eq!(sent, note);
+        assert!(recipient.viewing.decrypt_outgoing(&encrypted).is_none());
+    }
+
+    #[test]
+    fn test_scan_finds_only_received_notes() {
+        let mut rng = ChaCha20Rng::from_seed([5u8; 32]);
+        let sender = SpendingKeys::from_seed(&[10u8; 32]).unwrap();
+        let recipient = SpendingKeys::from_seed(&[20u8; 32]).unwrap();
+        let vortex = Fr::from(77u64);
+
+        let events: Vec<OutputEvent> = [(&recipient, 5u64), (&sender, 6), (&recipient, 7)]
+            .iter()
+            .enumerate()
+            .map(|(i, (owner, amount))| {
+                let note = NotePlaintext {
+                    amount: *amount,
+                    blinding: Fr::from(i as u64 + 1),
+                };
+                let encrypted = encrypt_note(
+                    &note,
+                    &owner.viewing.incoming.public_key(),
+                    &sender.viewing,
+                    &mut rng,
+                )
+                .unwrap();
+                OutputEvent {
+                    leaf_index: i as u64,
+                    commitment: note
+                        .commitment(&owner.viewing.spend_public_key, &vortex)
+                        .into_bigint()
+                        .to_string(),
+                    encrypted_output: hex::encode(encrypted),
+                }
+            })
+            .collect();
+
+        let received = recipient.viewing.scan_notes(&events, &vortex).unwrap();
+        assert_eq!(
+            received
+                .iter()
+                .map(|scanned| (scanned.leaf_index, scanned.note.amount))
+                .collect::<Vec<_>>(),
+            vec![(0, 5), (2, 7)]
+        );
+
+        let mut malformed = events;
+        malformed[1].encrypted_output = "zz".to_string();
+        assert!(recipient.viewing.scan_notes(&malformed, &vortex).is_err());
+    }
+
+    #[test]
+    fn test_scan_matches_plain_and_ciphertext_bound_commitments() {
+        let mut rng = ChaCha20Rng::from_seed([6u8; 32]);
+        let sender = SpendingKeys::from_seed(&[10u8; 32]).unwrap();
+        let recipient = SpendingKeys::from_seed(&[20u8; 32]).unwrap();
+        let vortex = Fr::from(77u64);
+
+        let mut bound_blindings = Vec::new();
+        let events: Vec<OutputEvent> = [false, true, false]
+            .iter()
+            .enumerate()
+            .map(|(i, bound)| {
+                let note = NotePlaintext {
+                    amount: i as u64 + 10,
+                    blinding: Fr::from(i as u64 + 100),
+                };
+                let encrypted = encrypt_note(
+                    &note,
+                    &recipient.viewing.incoming.public_key(),
+                    &sender.viewing,
+                    &mut rng,
+                )
+                .unwrap();
+                let committed = if *bound {
+                    let blinding = bind_ciphertext(&note.blinding, &ciphertext_hash(&encrypted));
+                    bound_blindings.push(blinding);
+                    NotePlaintext {
+                        amount: note.amount,
+                        blinding,
+                    }
+                } else {
+                    note
+                };
+                OutputEvent {
+                    leaf_index: i as u64,
+                    commitment: committed
+                        .commitment(&recipient.viewing.spend_public_key, &vortex)
+                        .into_bigint()
+                        .to_string(),
+                    encrypted_output: hex::encode(encrypted),
+                }
+            })
+            .collect();
+
+        let received = recipient.viewing.scan_notes(&events, &vortex).unwrap();
+        assert_eq!(
+            received
+                .iter()
+                .map(|scanned| (scanned.leaf_index, scanned.note.blinding.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "100".to_string()),
+                (1, bound_blindings[0].into_bigint().to_string()),
+                (2, "102".to_string()),
+            ]
+        );
+
+        // A commitment matching neither candidate is not a received note
+        let mut foreign = events;
+        foreign[1].commitment = "1".to_string();
+        assert_eq!(
+            recipient
+                .viewing
+                .scan_notes(&foreign, &vortex)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_key_derivation_is_deterministic_and_separated() {
+        let a = SpendingKeys::from_seed(&[3u8; 32]).unwrap();
+        let b = SpendingKeys::from_seed(&[3u8; 32]).unwrap();
+        assert_eq!(a.spending_key(), b.spending_key());
+        assert_eq!(a.viewing.spend_public_key, hash1(a.spending_key()));
+        assert_ne!(*a.viewing.incoming.to_bytes(), a.viewing.outgoing);
+        assert!(SpendingKeys::from_seed(&[3u8; 31]).is_err());
+    }
+
+    #[test]
+    fn test_output_blinding_derivation_is_stable() {
+        let seed = [4u8; 32];
+        let recipient = Fr::from(77u64);
+        let blinding = derive_output_blinding(&seed, &recipient, 0, 1).unwrap();
+
+        // Pinned so the derivation cannot drift between releases
+        assert_eq!(
+            blinding.into_bigint().to_string(),
+            "21273588924179069696248087541911684981623485881596002408828875012754866400892"
+        );
+        assert_ne!(
+            blinding,
+            derive_output_blinding(&seed, &recipient, 1, 1).unwrap()
+        );
+        assert_ne!(
+            blinding,
+            derive_output_blinding(&seed, &recipient, 0, 2).unwrap()
+        );
+        assert_ne!(
+            blinding,
+            derive_output_blinding(&seed, &Fr::from(78u64), 0, 1).unwrap()
+        );
+        assert!(derive_output_blinding(&seed[..31], &recipient, 0, 1).is_err());
+    }
+}
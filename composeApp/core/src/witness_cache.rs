@@ -0,0 +1,200 @@
+//! Optional cache of Merkle path witnesses shared by the proofs of a session.
+//!
+//! Re-proving a transaction after a small change, e.g. a new relayer fee or a rebuilt output,
+//! synthesizes the same Merkle paths again: forty Poseidon permutations per input, most of
+//! the circuit's hashing. When the cache is enabled, `PathVar::root_hash` records every
+//! S-box witness and level output of a path and replays them the next time the same path is
+//! proven, skipping all of its field arithmetic. The constraints and witness are unchanged.
+//!
+//! Entries are keyed by a domain-separated SHA-256 digest of the leaf, the direction bits and
+//! the path, so no input is kept in the clear. The traces follow from public tree nodes and
+//! the note's position, which still links a proof to its note, so the cache is off unless the
+//! host enables it and entries are wiped when evicted or cleared. Its capacity counts paths;
+//! one takes about 150 KiB.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroize;
+
+/// Separates the digests of this cache from any other use of SHA-256 over field elements
+const MERKLE_PATH_DOMAIN: &[u8] = b"vortex/witness-cache/merkle-path/v1";
+
+/// Hit and size counters of the witness cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct WitnessCacheStats {
+    pub capacity: u32,
+    pub entries: u32,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Digest of one Merkle path proof: hasher width, leaf, direction bits and sibling pairs
+pub(crate) type PathKey = [u8; 32];
+
+#[derive(Default)]
+struct WitnessCache {
+    capacity: usize,
+    /// Oldest entry first
+    entries: VecDeque<(PathKey, Arc<[Fr]>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl WitnessCache {
+    fn evict_to(&mut self, capacity: usize) {
+        while self.entries.len() > capacity {
+            if let Some((_, trace)) = self.entries.pop_front() {
+                wipe(trace);
+            }
+        }
+    }
+}
+
+/// Wipes a trace unless a path being synthesized still replays it
+fn wipe(mut trace: Arc<[Fr]>) {
+    if let Some(trace) = Arc::get_mut(&mut trace) {
+        trace.iter_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+static CACHE: Mutex<Option<WitnessCache>> = Mutex::new(None);
+
+/// Keeps the witnesses of up to `capacity` Merkle paths; 0 turns the cache off and wipes it
+pub fn set_capacity(capacity: usize) {
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(WitnessCache::default);
+    cache.capacity = capacity;
+    cache.evict_to(capacity);
+}
+
+/// Wipes every cached entry and resets the counters, keeping the capacity
+pub fn clear() {
+    if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+        cache.evict_to(0);
+        cache.hits = 0;
+        cache.misses = 0;
+    }
+}
+
+/// Current capacity, size and hit counters
+pub fn stats() -> WitnessCacheStats {
+    let cache = CACHE.lock().unwrap();
+    cache
+        .as_ref()
+        .map(|cache| WitnessCacheStats {
+            capacity: cache.capacity as u32,
+            entries: cache.entries.len() as u32,
+            hits: cache.hits,
+            misses: cache.misses,
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn is_enabled() -> bool {
+    CACHE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|cache| cache.capacity > 0)
+}
+
+/// The key of a path hashed with a `width`-element Poseidon
+pub(crate) fn path_key(width: usize, leaf: Fr, bits: &[bool], path: &[(Fr, Fr)]) -> PathKey {
+    let mut hasher = Sha256::new();
+    hasher.update(MERKLE_PATH_DOMAIN);
+    hasher.update((width as u32).to_le_bytes());
+    hasher.update((path.len() as u32).to_le_bytes());
+    hasher.update(leaf.into_bigint().to_bytes_le());
+    hasher.update(bits.iter().map(|&bit| bit as u8).collect::<Vec<_>>());
+    for (left, right) in path {
+        hasher.update(left.into_bigint().to_bytes_le());
+        hasher.update(right.into_bigint().to_bytes_le());
+    }
+    hasher.finalize().into()
+}
+
+/// The trace recorded for `key`, counting a hit or miss while enabled
+pub(crate) fn lookup(key: &PathKey) -> Option<Arc<[Fr]>> {
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.as_mut().filter(|cache| cache.capacity > 0)?;
+    let trace = cache
+        .entries
+        .iter()
+        .find(|(entry, _)| entry == key)
+        .map(|(_, trace)| trace.clone());
+    if trace.is_some() {
+        cache.hits += 1;
+    } else {
+        cache.misses += 1;
+    }
+    trace
+}
+
+pub(crate) fn insert(key: PathKey, mut trace: Vec<Fr>) {
+    let mut guard = CACHE.lock().unwrap();
+    let Some(cache) = guard.as_mut().filter(|cache| cache.capacity > 0) else {
+        trace.zeroize();
+        return;
+    };
+    if cache.entries.iter().any(|(entry, _)| *entry == key) {
+        trace.zeroize();
+        return;
+    }
+    cache.entries.push_back((key, Arc::from(trace)));
+    let capacity = cache.capacity;
+    cache.evict_to(capacity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::sample_zero_transfer;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+
+    #[test]
+    fn test_cached_witness_matches_computed_witness() {
+        let synthesize = || {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            sample_zero_transfer()
+                .generate_constraints(cs.clone())
+                .unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            let cs = cs.borrow().unwrap();
+            (
+                cs.instance_assignment.clone(),
+                cs.witness_assignment.clone(),
+            )
+        };
+
+        let computed = synthesize();
+        set_capacity(4);
+        let recorded = synthesize();
+        let before = stats();
+        let replayed = synthesize();
+        let after = stats();
+        set_capacity(0);
+
+        assert_eq!(recorded, computed);
+        assert_eq!(replayed, computed);
+        assert!(after.hits > before.hits);
+        assert_eq!(stats().entries, 0);
+
+        // One global cache, so the bound is checked in the same test
+        let key = |i: u8| [i; 32];
+        set_capacity(2);
+        for i in 0..3 {
+            insert(key(i), vec![Fr::from(i as u64)]);
+        }
+        assert_eq!(stats().entries, 2);
+        assert!(lookup(&key(0)).is_none());
+        assert!(lookup(&key(2)).is_some());
+        set_capacity(0);
+        assert_eq!(stats().entries, 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Account secrets derived from Sui zkLogin credentials.
+//!
+//! zkLogin users sign in with OAuth and have no seed phrase to derive a secret from. Their
+//! identity is the address seed, `Poseidon(claim name, sub, aud, Poseidon(user_salt))` as Sui
+//! computes it, which is public: every zkLogin signature carries it. The user salt is the
+//! only secret, so the account secret is
+//!
+//! `Poseidon3(DOMAIN_TAG, user_salt, address_seed)`
+//!
+//! over the same BN254 Poseidon as Sui's `poseidon_bn254` and the circuit. Signing in again on
+//! any device, with the salt service returning the same salt, gives the same shielded account.
+//! The circuit is unchanged: the derived secret is an ordinary `account_secret` witness.
+//!
+//! The address seed is taken from the host's zkLogin flow rather than recomputed here, since
+//! the `aud` claim hashes to five field elements and this crate's Poseidon stops at four. It
+//! is bound to the wallet instead through the zkLogin address it must produce.
+
+use crate::account::AccountSecret;
+use crate::field::{fr_to_bytes_be, parse_fr, FieldParseMode};
+use crate::poseidon_opt::hash3;
+use crate::relayer::normalize_address;
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use serde::{Deserialize, Serialize};
+
+/// Signature scheme flag Sui hashes in front of a zkLogin public identifier
+pub const ZKLOGIN_FLAG: u8 = 0x05;
+
+/// Domain tag for the derivation, so the secret is no other Poseidon3 of the salt and seed
+const DOMAIN_TAG: &[u8] = b"vortex-zklogin-account-secret-v1";
+
+/// The parts of a zkLogin session the account secret is derived from
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(rename_all = "camelCase")]
+pub struct ZkLoginCredentials {
+    /// `iss` claim of the JWT
+    pub iss: String,
+    /// Decimal address seed, as in the zkLogin proof inputs
+    pub address_seed: String,
+    /// Decimal user salt from the salt service
+    pub user_salt: String,
+}
+
+impl std::fmt::Debug for ZkLoginCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZkLoginCredentials")
+            .field("iss", &self.iss)
+            .field("address_seed", &self.address_seed)
+            .field("user_salt", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ZkLoginCredentials {
+    fn address_seed(&self) -> Result<Fr> {
+        let seed = parse_fr(&self.address_seed, FieldParseMode::Strict)
+            .context("Invalid zkLogin address seed")?;
+        if seed.is_zero() {
+            return Err(anyhow!("zkLogin address seed must be non-zero"));
+        }
+        Ok(seed)
+    }
+
+    fn user_salt(&self) -> Result<Fr> {
+        let salt =
+            parse_fr(&self.user_salt, FieldParseMode::Strict).context("Invalid zkLogin salt")?;
+        if salt.is_zero() {
+            return Err(anyhow!("zkLogin salt must be non-zero"));
+        }
+        Ok(salt)
+    }
+
+    /// The Sui address these credentials sign for
+    pub fn address(&self) -> Result<String> {
+        zklogin_address(&self.iss, &self.address_seed()?)
+    }
+
+    /// Derives the account secret, first checking the credentials belong to
+    /// `expected_address` when given.
+    ///
+    /// # Errors
+    /// Returns error if the seed or salt is malformed or zero, or the credentials produce a
+    /// different address, e.g. a seed from another OAuth account.
+    pub fn derive_account_secret(&self, expected_address: Option<&str>) -> Result<AccountSecret> {
+        let address_seed = self.address_seed()?;
+        if let Some(expected) = expected_address {
+            let expected = normalize_address(expected).context("Invalid zkLogin address")?;
+            let address = zklogin_address(&self.iss, &address_seed)?;
+            if address != expected {
+                return Err(anyhow!(
+                    "zkLogin credentials sign for {}, not {}",
+                    address,
+                    expected
+                ));
+            }
+        }
+        let domain = Fr::from_be_bytes_mod_order(DOMAIN_TAG);
+        AccountSecret::from_fr(hash3(&domain, &self.user_salt()?, &address_seed))
+    }
+}
+
+/// Sui's issuer normalization: Google tokens may omit the scheme
+fn normalize_iss(iss: &str) -> &str {
+    match iss {
+        "accounts.google.com" => "https://accounts.google.com",
+        iss => iss,
+    }
+}
+
+/// `Blake2b-256(0x05 || len(iss) || iss || address_seed)`, the seed as 32 big-endian bytes
+pub fn zklogin_address(iss: &str, address_seed: &Fr) -> Result<String> {
+    let iss = normalize_iss(iss);
+    let iss_len =
+        u8::try_from(iss.len()).map_err(|_| anyhow!("zkLogin issuer is over 255 bytes"))?;
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([ZKLOGIN_FLAG, iss_len]);
+    hasher.update(iss.as_bytes());
+    hasher.update(fr_to_bytes_be(address_seed));
+    Ok(format!("0x{}", hex::encode(hasher.finalize())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> ZkLoginCredentials {
+        ZkLoginCredentials {
+            iss: "https://accounts.google.com".to_string(),
+            address_seed: "17385927401239577218236150392817263740192837465019283746501928374650192837"
+                .to_string(),
+            user_salt: "129390038577185583942388216820280642146".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_derivation_is_stable_and_bound_to_the_address() {
+        let creds = credentials();
+        let address = creds.address().unwrap();
+        let secret = creds.derive_account_secret(Some(&address)).unwrap();
+        assert_eq!(creds.derive_account_secret(None).unwrap(), secret);
+        assert!(!format!("{:?}", creds).contains(&creds.user_salt));
+
+        let short_iss = ZkLoginCredentials {
+            iss: "accounts.google.com".to_string(),
+            ..creds.clone()
+        };
+        assert_eq!(short_iss.address().unwrap(), address);
+
+        let other_salt = ZkLoginCredentials {
+            user_salt: "7".to_string(),
+            ..creds.clone()
+        };
+        assert_ne!(other_salt.derive_account_secret(None).unwrap(), secret);
+
+        let other_seed = ZkLoginCredentials {
+            address_seed: "42".to_string(),
+            ..creds.clone()
+        };
+        assert!(other_seed.derive_account_secret(Some(&address)).is_err());
+        assert!(ZkLoginCredentials {
+            user_salt: "0".to_string(),
+            ..creds
+        }
+        .derive_account_secret(None)
+        .is_err());
+    }
+}
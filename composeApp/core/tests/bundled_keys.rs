@@ -0,0 +1,95 @@
+//! The keys shipped with the app against the current circuit.
+//!
+//! `composeResources/files` holds the proving and verifying key the app loads, with a copy in
+//! `src/commonMain/rust/keys`. Any change to the default constraint system leaves them
+//! unable to prove, and their verifying key no longer matches the one registered on chain.
+//! This fails until both copies are regenerated with `keygen` and the new verifying key is
+//! re-registered.
+
+#![cfg(feature = "std")]
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::AdditiveGroup;
+use ark_groth16::Groth16;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use vortex_core::circuit::{compute_commitment, compute_nullifier, TransactionCircuit};
+use vortex_core::constants::{N_INS, N_OUTS};
+use vortex_core::merkle_tree::Path;
+use vortex_core::poseidon_opt::hash1;
+use vortex_core::validation::{
+    deserialize_proving_key, deserialize_verifying_key, PointValidation,
+};
+
+const PROVING_KEY: &[u8] =
+    include_bytes!("../../src/commonMain/composeResources/files/proving_key.bin");
+const VERIFYING_KEY: &[u8] =
+    include_bytes!("../../src/commonMain/composeResources/files/verification_key.bin");
+const RUST_PROVING_KEY: &[u8] = include_bytes!("../../src/commonMain/rust/keys/proving_key.bin");
+const RUST_VERIFYING_KEY: &[u8] =
+    include_bytes!("../../src/commonMain/rust/keys/verification_key.bin");
+
+/// Spends two zero-amount notes into two zero-amount outputs, which needs no Merkle path
+fn zero_transfer() -> TransactionCircuit {
+    let vortex = Fr::ZERO;
+    let private_keys = [Fr::from(12345u64), Fr::from(67890u64)];
+    let blindings = [Fr::from(999u64), Fr::from(888u64)];
+    let path_indices = [Fr::from(0u64), Fr::from(1u64)];
+    let nullifiers: [Fr; N_INS] = std::array::from_fn(|i| {
+        let commitment =
+            compute_commitment(&Fr::ZERO, &hash1(&private_keys[i]), &blindings[i], &vortex);
+        compute_nullifier(&commitment, &path_indices[i], &private_keys[i])
+    });
+    let out_public_key = hash1(&private_keys[0]);
+    let out_blindings = [Fr::from(777u64), Fr::from(666u64)];
+
+    TransactionCircuit::new(
+        vortex,
+        Fr::ZERO,
+        Fr::ZERO,
+        nullifiers[0],
+        nullifiers[1],
+        compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[0], &vortex),
+        compute_commitment(&Fr::ZERO, &out_public_key, &out_blindings[1], &vortex),
+        Fr::ZERO,
+        Fr::ZERO,
+        private_keys,
+        [Fr::ZERO; N_INS],
+        blindings,
+        path_indices,
+        [Path::empty(), Path::empty()],
+        [out_public_key, out_public_key],
+        [Fr::ZERO; N_OUTS],
+        out_blindings,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_bundled_keys_prove_the_default_shape() {
+    assert!(
+        PROVING_KEY == RUST_PROVING_KEY && VERIFYING_KEY == RUST_VERIFYING_KEY,
+        "the two bundled key copies differ"
+    );
+
+    let vk = deserialize_verifying_key(VERIFYING_KEY, PointValidation::Checked).unwrap();
+    assert_eq!(
+        vk.gamma_abc_g1.len(),
+        TransactionCircuit::empty().get_public_inputs().len() + 1,
+        "bundled verifying key has a different public input count than the default circuit"
+    );
+
+    let pk = deserialize_proving_key(PROVING_KEY, PointValidation::Checked).unwrap();
+    let circuit = zero_transfer();
+    circuit.check_public_inputs().unwrap();
+    let public_inputs = circuit.get_public_inputs();
+    let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+    let proof =
+        Groth16::<Bn254>::create_random_proof_with_reduction(circuit, &pk, &mut rng).unwrap();
+
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    assert!(
+        Groth16::<Bn254>::verify_proof(&pvk, &proof, &public_inputs).unwrap(),
+        "a proof from the bundled proving key does not verify under the bundled verifying key"
+    );
+}
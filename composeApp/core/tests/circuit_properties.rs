@@ -0,0 +1,151 @@
+//! Property tests: native computation agrees with circuit evaluation.
+//!
+//! Random keys, amounts, blindings and tree states are generated and
+//! - every Poseidon arity gives the same hash in and out of the circuit
+//! - Merkle roots recomputed in the circuit match the native tree
+//! - a balanced transaction built from native commitments and nullifiers always satisfies
+//!   the constraints
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, R1CSVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use proptest::prelude::*;
+use vortex_core::circuit::{compute_commitment, compute_nullifier, TransactionCircuit};
+use vortex_core::constants::{MERKLE_TREE_LEVEL, N_OUTS, ZERO_VALUE};
+use vortex_core::merkle_tree::{PathVar, SparseMerkleTree};
+use vortex_core::poseidon_opt::{fr_from_str, hash1, PoseidonOptimized, PoseidonOptimizedVar};
+
+fn field() -> impl Strategy<Value = Fr> {
+    any::<[u8; 32]>().prop_map(|bytes| Fr::from_le_bytes_mod_order(&bytes))
+}
+
+/// Native and in-circuit hashers of each arity, 1 to 4
+fn hashers(arity: usize) -> (PoseidonOptimized, PoseidonOptimizedVar) {
+    match arity {
+        1 => (PoseidonOptimized::new_t2(), PoseidonOptimizedVar::new_t2()),
+        2 => (PoseidonOptimized::new_t3(), PoseidonOptimizedVar::new_t3()),
+        3 => (PoseidonOptimized::new_t4(), PoseidonOptimizedVar::new_t4()),
+        _ => (PoseidonOptimized::new_t5(), PoseidonOptimizedVar::new_t5()),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn poseidon_gadget_matches_native(inputs in proptest::collection::vec(field(), 1..=4)) {
+        let (native, gadget) = hashers(inputs.len());
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let hashed = gadget.hash(&vars).unwrap();
+        prop_assert_eq!(hashed.value().unwrap(), native.hash(&inputs));
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn merkle_root_gadget_matches_native_tree(
+        leaves in proptest::collection::vec(field(), 1..=5).prop_map(|mut leaves| {
+            if leaves.len() % 2 == 1 {
+                leaves.push(fr_from_str(ZERO_VALUE));
+            }
+            leaves
+        }),
+        pick in any::<prop::sample::Index>(),
+    ) {
+        let target = leaves[pick.index(leaves.len())];
+        let (root, index, path) = SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(
+            &leaves,
+            &target,
+            &fr_from_str(ZERO_VALUE),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let path_var = PathVar::<MERKLE_TREE_LEVEL>::new_witness(cs.clone(), || Ok(path)).unwrap();
+        let leaf = FpVar::new_witness(cs.clone(), || Ok(target)).unwrap();
+        let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+        let bits = PathVar::<MERKLE_TREE_LEVEL>::index_bits(&index).unwrap();
+        let computed = path_var
+            .root_hash(&leaf, &bits, &PoseidonOptimizedVar::new_t3())
+            .unwrap();
+
+        prop_assert_eq!(computed.value().unwrap(), root);
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn balanced_transactions_satisfy_the_circuit(
+        private_keys in [field(), field()],
+        in_blindings in [field(), field()],
+        out_blindings in [field(), field()],
+        in_amounts in [0u64..1 << 40, 0u64..1 << 40],
+        deposit in 0u64..1 << 40,
+        send_share in 0u64..=100,
+        filler_pairs in proptest::collection::vec((field(), field()), 0..3),
+        vortex in field(),
+    ) {
+        let public_keys = private_keys.map(|key| hash1(&key));
+        let in_amounts_fr = in_amounts.map(Fr::from);
+        let commitments = [0, 1].map(|i| {
+            compute_commitment(&in_amounts_fr[i], &public_keys[i], &in_blindings[i], &vortex)
+        });
+
+        let mut leaves: Vec<Fr> = filler_pairs.iter().flat_map(|(a, b)| [*a, *b]).collect();
+        leaves.extend_from_slice(&commitments);
+        let empty_leaf = fr_from_str(ZERO_VALUE);
+        let (root, index_0, path_0) =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(&leaves, &commitments[0], &empty_leaf)
+                .unwrap();
+        let (_, index_1, path_1) =
+            SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(&leaves, &commitments[1], &empty_leaf)
+                .unwrap();
+        let path_indices = [Fr::from(index_0 as u64), Fr::from(index_1 as u64)];
+        let nullifiers = [0, 1].map(|i| {
+            compute_nullifier(&commitments[i], &path_indices[i], &private_keys[i])
+        });
+
+        let total = in_amounts[0] as u128 + in_amounts[1] as u128 + deposit as u128;
+        let send = total * send_share as u128 / 100;
+        let out_amounts = [Fr::from(send), Fr::from(total - send)];
+        let out_public_keys: [Fr; N_OUTS] = [public_keys[1], public_keys[0]];
+        let out_commitments = [0, 1].map(|i| {
+            compute_commitment(&out_amounts[i], &out_public_keys[i], &out_blindings[i], &vortex)
+        });
+
+        let circuit = TransactionCircuit::new(
+            vortex,
+            root,
+            Fr::from(deposit),
+            nullifiers[0],
+            nullifiers[1],
+            out_commitments[0],
+            out_commitments[1],
+            Fr::from(0u64),
+            Fr::from(0u64),
+            private_keys,
+            in_amounts_fr,
+            in_blindings,
+            path_indices,
+            [path_0, path_1],
+            out_public_keys,
+            out_amounts,
+            out_blindings,
+        )
+        .unwrap();
+        prop_assert!(circuit.check_public_inputs().is_ok());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        prop_assert!(cs.is_satisfied().unwrap(), "unsatisfied: {:?}", cs.which_is_unsatisfied());
+    }
+}
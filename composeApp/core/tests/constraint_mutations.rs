@@ -0,0 +1,222 @@
+//! Mutation testing of the circuit's security constraints.
+//!
+//! Each group of security constraints gets a known malicious witness that only that group
+//! should stop. The witness is synthesized with region labels and every unsatisfied
+//! constraint must fall inside the group: the witness is rejected while the group is in
+//! place and accepted once the group is disabled. A group that never fails, or a witness
+//! that also fails elsewhere, means the constraint is not load-bearing as intended.
+//!
+//! A witness that cannot be assigned at all (equal nullifiers leave `enforce_not_equal`
+//! no inverse) counts as failing in the region where synthesis stopped.
+
+#![cfg(feature = "std")]
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, Field};
+use vortex_core::circuit::{compute_commitment, compute_nullifier, TransactionCircuit};
+use vortex_core::constants::{MAX_AMOUNT_BITS, MERKLE_TREE_LEVEL, N_INS, N_OUTS, ZERO_VALUE};
+use vortex_core::debug::unsatisfied_constraints;
+use vortex_core::merkle_tree::SparseMerkleTree;
+use vortex_core::poseidon_opt::{fr_from_str, hash1};
+
+#[derive(Debug, Clone, Copy)]
+enum ConstraintGroup {
+    RangeCheck,
+    NullifierUniqueness,
+    Conservation,
+    PublicAmountRange,
+    MerkleMembership,
+}
+
+impl ConstraintGroup {
+    const ALL: [ConstraintGroup; 5] = [
+        ConstraintGroup::RangeCheck,
+        ConstraintGroup::NullifierUniqueness,
+        ConstraintGroup::Conservation,
+        ConstraintGroup::PublicAmountRange,
+        ConstraintGroup::MerkleMembership,
+    ];
+
+    /// Whether the constraint at region `path` belongs to the group
+    fn contains(self, path: &str) -> bool {
+        match self {
+            ConstraintGroup::RangeCheck => path.ends_with("/range_check"),
+            ConstraintGroup::NullifierUniqueness => path == "nullifier_uniqueness",
+            ConstraintGroup::Conservation => path == "conservation",
+            ConstraintGroup::PublicAmountRange => path == "public_amount",
+            ConstraintGroup::MerkleMembership => path.ends_with("/merkle"),
+        }
+    }
+
+    /// A witness that breaks only what the group protects
+    fn malicious_witness(self) -> TransactionCircuit {
+        let amount = |x: u64| Fr::from(x);
+        match self {
+            // An output of -1 lets the other output take one more than was spent
+            ConstraintGroup::RangeCheck => Transfer {
+                out_amounts: [-Fr::ONE, amount(43)],
+                ..Transfer::default()
+            },
+            // The same note spent through both inputs
+            ConstraintGroup::NullifierUniqueness => Transfer {
+                spend_same_note: true,
+                in_amounts: [amount(30), amount(30)],
+                out_amounts: [amount(60), Fr::ZERO],
+                ..Transfer::default()
+            },
+            // Outputs worth more than the inputs
+            ConstraintGroup::Conservation => Transfer {
+                out_amounts: [amount(40), amount(10)],
+                ..Transfer::default()
+            },
+            // A deposit one past the largest amount the pool accepts
+            ConstraintGroup::PublicAmountRange => Transfer {
+                in_amounts: [Fr::ZERO, Fr::ZERO],
+                public_amount: Fr::from(2u64).pow([MAX_AMOUNT_BITS as u64]),
+                out_amounts: [
+                    Fr::from(2u64).pow([MAX_AMOUNT_BITS as u64]) - Fr::ONE,
+                    Fr::ONE,
+                ],
+                ..Transfer::default()
+            },
+            // A note that is in some tree, just not the pool's
+            ConstraintGroup::MerkleMembership => Transfer {
+                second_note_in_tree: false,
+                in_amounts: [amount(30), amount(1000)],
+                out_amounts: [amount(1030), Fr::ZERO],
+                ..Transfer::default()
+            },
+        }
+        .circuit()
+    }
+}
+
+/// Native description of a transaction; `circuit()` derives every public input from it
+struct Transfer {
+    in_amounts: [Fr; N_INS],
+    out_amounts: [Fr; N_OUTS],
+    public_amount: Fr,
+    spend_same_note: bool,
+    second_note_in_tree: bool,
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Self {
+            in_amounts: [Fr::from(30u64), Fr::from(12u64)],
+            out_amounts: [Fr::from(40u64), Fr::from(2u64)],
+            public_amount: Fr::ZERO,
+            spend_same_note: false,
+            second_note_in_tree: true,
+        }
+    }
+}
+
+impl Transfer {
+    fn circuit(&self) -> TransactionCircuit {
+        let vortex = Fr::from(7u64);
+        let private_key = Fr::from(4242u64);
+        let public_key = hash1(&private_key);
+        let blindings = if self.spend_same_note {
+            [Fr::from(501u64); N_INS]
+        } else {
+            [Fr::from(501u64), Fr::from(502u64)]
+        };
+        let commitments: [Fr; N_INS] = std::array::from_fn(|i| {
+            compute_commitment(&self.in_amounts[i], &public_key, &blindings[i], &vortex)
+        });
+
+        let empty_leaf = fr_from_str(ZERO_VALUE);
+        let pool_leaves = [
+            Fr::from(1u64),
+            commitments[0],
+            commitments[1],
+            Fr::from(2u64),
+        ];
+        let other_leaves = [commitments[1], Fr::from(3u64)];
+        let (root, index_0, path_0) = SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(
+            &pool_leaves,
+            &commitments[0],
+            &empty_leaf,
+        )
+        .unwrap();
+        let (_, index_1, path_1) = SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(
+            if self.second_note_in_tree {
+                &pool_leaves
+            } else {
+                &other_leaves
+            },
+            &commitments[1],
+            &empty_leaf,
+        )
+        .unwrap();
+        let (index_1, path_1) = if self.spend_same_note {
+            (index_0, path_0)
+        } else {
+            (index_1, path_1)
+        };
+
+        let path_indices = [Fr::from(index_0 as u64), Fr::from(index_1 as u64)];
+        let nullifiers: [Fr; N_INS] = std::array::from_fn(|i| {
+            compute_nullifier(&commitments[i], &path_indices[i], &private_key)
+        });
+        let out_blindings = [Fr::from(601u64), Fr::from(602u64)];
+        let out_commitments: [Fr; N_OUTS] = std::array::from_fn(|i| {
+            compute_commitment(
+                &self.out_amounts[i],
+                &public_key,
+                &out_blindings[i],
+                &vortex,
+            )
+        });
+
+        TransactionCircuit::new(
+            vortex,
+            root,
+            self.public_amount,
+            nullifiers[0],
+            nullifiers[1],
+            out_commitments[0],
+            out_commitments[1],
+            Fr::ZERO,
+            Fr::ZERO,
+            [private_key; N_INS],
+            self.in_amounts,
+            blindings,
+            path_indices,
+            [path_0, path_1],
+            [public_key; N_OUTS],
+            self.out_amounts,
+            out_blindings,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_honest_transfer_satisfies_every_group() {
+    let failing = unsatisfied_constraints(&Transfer::default().circuit()).unwrap();
+    assert!(failing.is_empty(), "honest transfer fails at {:?}", failing);
+}
+
+#[test]
+fn test_every_security_group_is_load_bearing() {
+    for group in ConstraintGroup::ALL {
+        let failing = unsatisfied_constraints(&group.malicious_witness()).unwrap();
+        assert!(
+            !failing.is_empty(),
+            "{:?}: malicious witness is accepted with the group in place",
+            group
+        );
+        let outside: Vec<&(u64, String)> = failing
+            .iter()
+            .filter(|(_, path)| !group.contains(path))
+            .collect();
+        assert!(
+            outside.is_empty(),
+            "{:?}: malicious witness would still be rejected with the group disabled, at {:?}",
+            group,
+            outside
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Golden vectors embedded in the test binary.
+//!
+//! `tests/golden` holds one transfer produced by the `e2e` binary: the tree it spends from,
+//! its notes, the verifying key from `keygen`'s seeded setup, and the proof with its public
+//! inputs in the bytes the Move verifier takes. The current build must reproduce every
+//! derived value and the verifying key, and still accept the proof, so any change to
+//! hashing, commitments, the circuit shape or serialization that would break deployed
+//! contracts or existing notes fails here. Regenerate with `e2e core/tests/golden` only for
+//! an intentional protocol change.
+
+#![cfg(feature = "std")]
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::Groth16;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use vortex_core::account::AccountSecret;
+use vortex_core::circuit::{compute_commitment, compute_nullifier, TransactionCircuit};
+use vortex_core::constants::{MERKLE_TREE_LEVEL, ZERO_VALUE};
+use vortex_core::field::{parse_fr, FieldParseMode};
+use vortex_core::merkle_tree::SparseMerkleTree;
+use vortex_core::poseidon_opt::{fr_from_str, PoseidonOptimized};
+use vortex_core::relayer::{ext_data_hash, ExtData};
+use vortex_core::validation::{deserialize_proof, deserialize_verifying_key, PointValidation};
+
+const VECTORS: &[u8] = include_bytes!("golden/vectors.json");
+const VERIFYING_KEY: &[u8] = include_bytes!("golden/verifying_key.bin");
+const PROOF_POINTS: &[u8] = include_bytes!("golden/proof_points.bin");
+const PUBLIC_INPUTS: &[u8] = include_bytes!("golden/public_inputs.bin");
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vectors {
+    vortex: String,
+    leaves: Vec<String>,
+    root: String,
+    spent: Vec<SpentNote>,
+    outputs: Vec<OutputNote>,
+    account_secret: String,
+    ext_data: GoldenExtData,
+    public_inputs: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpentNote {
+    amount: String,
+    public_key: String,
+    blinding: String,
+    leaf_index: u64,
+    private_key: String,
+    commitment: String,
+    nullifier: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputNote {
+    amount: String,
+    public_key: String,
+    blinding: String,
+    commitment: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoldenExtData {
+    value: u64,
+    is_deposit: bool,
+    relayer: String,
+    relayer_fee: u64,
+    encrypted_output_0: String,
+    encrypted_output_1: String,
+}
+
+fn vectors() -> Vectors {
+    serde_json::from_slice(VECTORS).unwrap()
+}
+
+fn fr(s: &str) -> Fr {
+    parse_fr(s, FieldParseMode::Strict).unwrap()
+}
+
+/// Public inputs in circuit order, from the names recorded next to them
+fn public_inputs(vectors: &Vectors) -> Vec<Fr> {
+    TransactionCircuit::empty()
+        .public_input_names()
+        .into_iter()
+        .map(|name| {
+            let value = vectors
+                .public_inputs
+                .get(name)
+                .unwrap_or_else(|| panic!("golden vectors have no public input {}", name));
+            fr(value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_golden_tree_and_notes_reproduce() {
+    let vectors = vectors();
+    let vortex = fr(&vectors.vortex);
+
+    let hasher = PoseidonOptimized::new_t3();
+    let mut tree =
+        SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+    let leaves: Vec<Fr> = vectors.leaves.iter().map(|leaf| fr(leaf)).collect();
+    tree.bulk_insert(&leaves, &hasher).unwrap();
+    assert_eq!(tree.root(), fr(&vectors.root), "tree root");
+
+    for note in &vectors.spent {
+        let commitment = compute_commitment(
+            &fr(&note.amount),
+            &fr(&note.public_key),
+            &fr(&note.blinding),
+            &vortex,
+        );
+        assert_eq!(commitment, fr(&note.commitment), "spent note commitment");
+        assert_eq!(
+            commitment, leaves[note.leaf_index as usize],
+            "spent note leaf"
+        );
+        let nullifier = compute_nullifier(
+            &commitment,
+            &Fr::from(note.leaf_index),
+            &fr(&note.private_key),
+        );
+        assert_eq!(nullifier, fr(&note.nullifier), "nullifier");
+    }
+    for note in &vectors.outputs {
+        let commitment = compute_commitment(
+            &fr(&note.amount),
+            &fr(&note.public_key),
+            &fr(&note.blinding),
+            &vortex,
+        );
+        assert_eq!(commitment, fr(&note.commitment), "output commitment");
+    }
+
+    let ext_data = ExtData {
+        value: vectors.ext_data.value,
+        is_deposit: vectors.ext_data.is_deposit,
+        relayer: vectors.ext_data.relayer.clone(),
+        relayer_fee: vectors.ext_data.relayer_fee,
+        encrypted_output_0: hex::decode(&vectors.ext_data.encrypted_output_0).unwrap(),
+        encrypted_output_1: hex::decode(&vectors.ext_data.encrypted_output_1).unwrap(),
+    };
+    let expected = [
+        ("vortex", vortex),
+        ("root", tree.root()),
+        ("inputNullifier0", fr(&vectors.spent[0].nullifier)),
+        ("inputNullifier1", fr(&vectors.spent[1].nullifier)),
+        ("outputCommitment0", fr(&vectors.outputs[0].commitment)),
+        ("outputCommitment1", fr(&vectors.outputs[1].commitment)),
+        (
+            "hashedAccountSecret",
+            AccountSecret::parse(&vectors.account_secret)
+                .unwrap()
+                .hashed(),
+        ),
+        ("extDataHash", ext_data_hash(&ext_data).unwrap()),
+    ];
+    for (name, value) in expected {
+        assert_eq!(fr(&vectors.public_inputs[name]), value, "{}", name);
+    }
+}
+
+#[test]
+fn test_golden_proof_verifies() {
+    let vectors = vectors();
+    let inputs = public_inputs(&vectors);
+
+    let serialized: Vec<Fr> = PUBLIC_INPUTS
+        .chunks(32)
+        .map(|chunk| Fr::deserialize_compressed(chunk).unwrap())
+        .collect();
+    assert_eq!(serialized, inputs, "serialized public inputs");
+
+    let vk = deserialize_verifying_key(VERIFYING_KEY, PointValidation::Checked).unwrap();
+    let proof = deserialize_proof(PROOF_POINTS, PointValidation::Checked).unwrap();
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &inputs).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered[1] += Fr::from(1u64);
+    assert!(!Groth16::<Bn254>::verify_proof(&pvk, &proof, &tampered).unwrap());
+}
+
+#[test]
+fn test_golden_verifying_key_reproduces() {
+    // `keygen`'s default seed and circuit
+    let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+        TransactionCircuit::empty(),
+        &mut ChaCha20Rng::from_seed([0u8; 32]),
+    )
+    .unwrap();
+    let mut vk_bytes = Vec::new();
+    pk.vk.serialize_compressed(&mut vk_bytes).unwrap();
+
+    assert!(
+        vk_bytes == VERIFYING_KEY,
+        "verifying key changed: the circuit shape or setup no longer matches deployed contracts"
+    );
+}
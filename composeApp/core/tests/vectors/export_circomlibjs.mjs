@@ -0,0 +1,105 @@
+// Exports differential test vectors from circomlibjs for tests/circomlibjs_vectors.rs.
+//
+//   npm install circomlibjs
+//   node tests/vectors/export_circomlibjs.mjs > tests/vectors/circomlibjs_generated.json
+//
+// Trees follow the circom circuit: a fixed-depth binary tree (26 levels) and a 4-ary tree
+// (13 levels), empty positions filled with the hash of an empty subtree of that height.
+import { buildPoseidon } from "circomlibjs";
+import { createRequire } from "module";
+
+const ZERO_VALUE =
+  "18688842432741139442778047327644092677418528270738216181718229581494125774932";
+const FIELD_MODULUS =
+  21888242871839275222246405745257275088548364400416034343698204186575808495617n;
+
+const poseidon = await buildPoseidon();
+const hash = (...inputs) => poseidon.F.toString(poseidon(inputs.map(BigInt)));
+
+// Deterministic field elements, so reruns produce the same file
+let state = 0x9e3779b97f4a7c15n;
+const next = () => {
+  let value = 0n;
+  for (let i = 0; i < 4; i++) {
+    state = (state * 6364136223846793005n + 1442695040888963407n) & ((1n << 64n) - 1n);
+    value = (value << 64n) | state;
+  }
+  return (value % FIELD_MODULUS).toString();
+};
+
+function zeros(arity, levels) {
+  const out = [ZERO_VALUE];
+  for (let i = 0; i < levels; i++) out.push(hash(...Array(arity).fill(out[i])));
+  return out;
+}
+
+function root(arity, levels, leaves) {
+  const empty = zeros(arity, levels);
+  let layer = leaves;
+  for (let level = 0; level < levels; level++) {
+    const parents = [];
+    for (let i = 0; i < Math.max(layer.length, 1); i += arity) {
+      const children = [];
+      for (let j = 0; j < arity; j++) children.push(layer[i + j] ?? empty[level]);
+      parents.push(hash(...children));
+    }
+    layer = parents;
+  }
+  return layer[0];
+}
+
+const poseidonVectors = [];
+for (let arity = 1; arity <= 4; arity++) {
+  const cases = [
+    Array(arity).fill("0"),
+    Array(arity).fill((FIELD_MODULUS - 1n).toString()),
+    ...Array.from({ length: 8 }, () => Array.from({ length: arity }, next)),
+  ];
+  for (const inputs of cases) poseidonVectors.push({ inputs, output: hash(...inputs) });
+}
+
+const merkleTrees = [];
+for (const count of [2, 4, 6, 10]) {
+  const leaves = Array.from({ length: count }, next);
+  merkleTrees.push({ shape: "binary", leaves, root: root(2, 26, leaves) });
+}
+for (const count of [1, 3, 5, 17]) {
+  const leaves = Array.from({ length: count }, next);
+  merkleTrees.push({ shape: "quaternary", leaves, root: root(4, 13, leaves) });
+}
+
+const commitments = [];
+const nullifiers = [];
+for (let i = 0; i < 8; i++) {
+  const privateKey = next();
+  const amount = (BigInt(next()) % (1n << 64n)).toString();
+  const publicKey = hash(privateKey);
+  const blinding = next();
+  const vortex = next();
+  const commitment = hash(amount, publicKey, blinding, vortex);
+  commitments.push({ amount, publicKey, blinding, vortex, commitment });
+
+  const pathIndex = (BigInt(next()) % (1n << 26n)).toString();
+  const signature = hash(privateKey, commitment, pathIndex);
+  nullifiers.push({
+    commitment,
+    pathIndex,
+    privateKey,
+    nullifier: hash(commitment, pathIndex, signature),
+  });
+}
+
+const version = createRequire(import.meta.url)("circomlibjs/package.json").version;
+console.log(
+  JSON.stringify(
+    {
+      source: `circomlibjs ${version}`,
+      poseidon: poseidonVectors,
+      merkleTrees,
+      commitments,
+      nullifiers,
+    },
+    null,
+    2,
+  ),
+);
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vortex::field::{parse_fr, FieldParseMode};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let strict = parse_fr(s, FieldParseMode::Strict);
+    let reduced = parse_fr(s, FieldParseMode::Reduce);
+
+    // Strict parsing never accepts anything reducing parsing rejects, and agrees when it accepts
+    match (&strict, &reduced) {
+        (Ok(a), Ok(b)) => assert_eq!(a, b),
+        (Ok(_), Err(_)) => panic!("strict parse accepted '{}' but reduce rejected it", s),
+        _ => {}
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vortex::validation::{deserialize_proving_key, deserialize_verifying_key, PointValidation};
+
+// Hex-encoded keys as downloaded at runtime; both policies must fail cleanly, never panic
+fuzz_target!(|data: &[u8]| {
+    let Ok(bytes) = hex::decode(data) else {
+        return;
+    };
+    for validation in [PointValidation::Checked, PointValidation::Unchecked] {
+        let _ = deserialize_verifying_key(&bytes, validation);
+        let _ = deserialize_proving_key(&bytes, validation);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vortex::bindings::parse_merkle_path_binding;
+use vortex::field::FieldParseMode;
+
+// Merkle paths arrive as JSON arrays of [left, right] decimal/hex strings
+fuzz_target!(|data: &[u8]| {
+    let Ok(path) = serde_json::from_slice::<Vec<[String; 2]>>(data) else {
+        return;
+    };
+    let _ = parse_merkle_path_binding(&path, FieldParseMode::Strict);
+    let _ = parse_merkle_path_binding(&path, FieldParseMode::Reduce);
+});
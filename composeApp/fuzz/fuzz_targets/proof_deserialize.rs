@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vortex::bindings::verify;
+use vortex::validation::{deserialize_proof, PointValidation};
+
+const VERIFYING_KEY: &[u8] = include_bytes!("../../src/commonMain/rust/keys/verification_key.bin");
+
+fuzz_target!(|data: &[u8]| {
+    let _ = deserialize_proof(data, PointValidation::Checked);
+    let _ = deserialize_proof(data, PointValidation::Unchecked);
+
+    // Full ProofOutput JSON path used by relayers and verifiers
+    if let Ok(json) = std::str::from_utf8(data) {
+        let _ = verify(json.to_string(), VERIFYING_KEY.to_vec());
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vortex::bindings::create_circuit_from_input;
+use vortex::interop::ProofInput;
+
+// ProofInput JSON as received from the app layer or a QR-encoded payment flow
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(input) = serde_json::from_str::<ProofInput>(json) {
+        let _ = create_circuit_from_input(&input);
+    }
+});
@@ -0,0 +1,545 @@
+//! Generates Kotlin and Swift conformance tests for the uniffi surface from the Rust vectors.
+//!
+//! Usage: `conformance [out_dir] [--proving-key <key>] [--device-key-path <path>]
+//! [--swift-module <name>]`
+//!
+//! The circomlibjs Poseidon vectors and the golden transfer under `core/tests/golden` are
+//! first checked against the bindings on this machine, then written into
+//! `kotlin/VortexConformanceTest.kt` (kotlin.test, package `xyz.mcxross.vortex`) and
+//! `swift/VortexConformanceTests.swift` (XCTest) under `out_dir` (default
+//! `files/conformance`). The tests hash, rebuild the golden tree, derive its root from a
+//! Merkle path, and prove the golden input with the key below. Proofs are randomized, so
+//! the device proof is not compared with the one made here: it must verify against the
+//! golden verifying key and carry the same public inputs.
+//!
+//! The proving key comes from the same seeded setup as `keygen` and `e2e`, or from
+//! `--proving-key`, and is written to `out_dir/vortex_conformance.pk`. The tests load it by
+//! path, which is this file unless `--device-key-path` names where the device sees it, e.g.
+//! after `adb push`.
+
+use ark_bn254::Bn254;
+use ark_groth16::Groth16;
+use ark_serialize::CanonicalSerialize;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::fs;
+use std::path::Path;
+use vortex::bindings;
+use vortex::circuit::TransactionCircuit;
+use vortex::constants::{MERKLE_TREE_LEVEL, ZERO_VALUE};
+use vortex::interop::{ProofInput, ProofOutput};
+use vortex::merkle_tree::{RootStatus, SparseMerkleTree};
+use vortex::note::Note;
+use vortex::poseidon_opt::{fr_from_str, PoseidonOptimized};
+use vortex::validation::PointValidation;
+
+const CIRCOMLIBJS_VECTORS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/core/tests/vectors/circomlibjs_reference.json"
+));
+const GOLDEN_VECTORS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/core/tests/golden/vectors.json"
+));
+const GOLDEN_PROOF_INPUT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/core/tests/golden/proof_input.json"
+));
+const GOLDEN_VERIFYING_KEY: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/core/tests/golden/verifying_key.bin"
+));
+
+const CIRCUIT_ID: &str = "conformance";
+
+#[derive(serde::Deserialize)]
+struct PoseidonVector {
+    inputs: Vec<String>,
+    output: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CircomlibjsVectors {
+    poseidon: Vec<PoseidonVector>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpentNote {
+    amount: String,
+    blinding: String,
+    commitment: String,
+    leaf_index: u64,
+    nullifier: String,
+    private_key: String,
+    public_key: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GoldenVectors {
+    vortex: String,
+    root: String,
+    leaves: Vec<String>,
+    spent: Vec<SpentNote>,
+}
+
+/// Everything the generated tests assert, computed through the bindings
+struct Vectors {
+    poseidon: Vec<PoseidonVector>,
+    vortex: String,
+    root: String,
+    leaves: Vec<String>,
+    note: Note,
+    leaf_index: u64,
+    commitment: String,
+    nullifier: String,
+    private_key: String,
+    merkle_path: Vec<String>,
+    proof_input: String,
+    /// The `"publicInputs":[...]` member of the proof made here
+    public_inputs_json: String,
+    tampered_proof: String,
+    verifying_key_hex: String,
+    proving_key_path: String,
+}
+
+/// Removes `--flag <value>` from `args` and returns the value
+fn take_flag(args: &mut Vec<String>, flag: &str) -> anyhow::Result<Option<String>> {
+    let Some(pos) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    let value = args
+        .get(pos + 1)
+        .ok_or_else(|| anyhow::anyhow!("{} requires a value", flag))?
+        .clone();
+    args.drain(pos..pos + 2);
+    Ok(Some(value))
+}
+
+fn check_poseidon(vector: &PoseidonVector) -> anyhow::Result<()> {
+    let output = match vector.inputs.as_slice() {
+        [input] => bindings::poseidon1(input.clone())?,
+        [_, _] => bindings::poseidon2(vector.inputs.clone())?,
+        [_, _, _] => bindings::poseidon3(vector.inputs.clone())?,
+        [_, _, _, _] => bindings::poseidon4(vector.inputs.clone())?,
+        _ => anyhow::bail!("No Poseidon binding takes {} inputs", vector.inputs.len()),
+    };
+    if output != vector.output {
+        anyhow::bail!(
+            "poseidon{}({:?}) is {}, the vector says {}",
+            vector.inputs.len(),
+            vector.inputs,
+            output,
+            vector.output
+        );
+    }
+    Ok(())
+}
+
+fn collect_vectors(proving_key: &Path, device_key_path: String) -> anyhow::Result<Vectors> {
+    let circomlibjs: CircomlibjsVectors = serde_json::from_str(CIRCOMLIBJS_VECTORS)?;
+    for vector in &circomlibjs.poseidon {
+        check_poseidon(vector)?;
+    }
+
+    let golden: GoldenVectors = serde_json::from_str(GOLDEN_VECTORS)?;
+    let consistency = bindings::check_root_consistency(
+        golden.leaves.clone(),
+        golden.root.clone(),
+        golden.leaves.len() as u64,
+        Vec::new(),
+    )?;
+    if consistency.status != RootStatus::Consistent {
+        anyhow::bail!("Golden leaves do not build the golden root");
+    }
+
+    let spent = golden
+        .spent
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Golden vectors spend no note"))?;
+    let note = Note {
+        amount: spent.amount.parse()?,
+        public_key: spent.public_key.clone(),
+        blinding: spent.blinding.clone(),
+        legacy_commitment: false,
+    };
+    let utxo = bindings::create_utxo(note.clone(), spent.leaf_index, golden.vortex.clone())?;
+    if utxo.commitment != spent.commitment {
+        anyhow::bail!("Golden note commits to {}", utxo.commitment);
+    }
+    let nullifier = bindings::compute_utxo_nullifier(utxo.clone(), spent.private_key.clone())?;
+    if nullifier != spent.nullifier {
+        anyhow::bail!("Golden note's nullifier is {}", nullifier);
+    }
+
+    let hasher = PoseidonOptimized::new_t3();
+    let mut tree =
+        SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+    let leaves: Vec<_> = golden.leaves.iter().map(|leaf| fr_from_str(leaf)).collect();
+    tree.bulk_insert(&leaves, &hasher)?;
+    let merkle_path: Vec<String> = tree
+        .generate_membership_proof(spent.leaf_index as usize)?
+        .path
+        .iter()
+        .flat_map(|(left, right)| [left.to_string(), right.to_string()])
+        .collect();
+    let builder = bindings::ProofInputBuilder::new();
+    builder.set_vortex(golden.vortex.clone())?;
+    builder.add_input(utxo, spent.private_key.clone(), merkle_path.clone())?;
+    let input: ProofInput = serde_json::from_str(&builder.build()?)?;
+    if input.root != golden.root {
+        anyhow::bail!("Golden Merkle path derives root {}", input.root);
+    }
+
+    bindings::init_prover_cache_from_file(
+        CIRCUIT_ID.to_string(),
+        proving_key.display().to_string(),
+        PointValidation::Checked,
+    )?;
+    let proof = bindings::prove_cached(CIRCUIT_ID.to_string(), GOLDEN_PROOF_INPUT.to_string());
+    bindings::evict_prover_cache(CIRCUIT_ID.to_string());
+    let proof = proof?;
+    if !bindings::verify(proof.clone(), GOLDEN_VERIFYING_KEY.to_vec())? {
+        anyhow::bail!("The proving key does not match the golden verifying key");
+    }
+    let mut tampered: ProofOutput = serde_json::from_str(&proof)?;
+    let public_inputs_json = format!(
+        "\"publicInputs\":{}",
+        serde_json::to_string(&tampered.public_inputs)?
+    );
+    if !proof.contains(&public_inputs_json) {
+        anyhow::bail!("Proof JSON does not carry its public inputs as expected");
+    }
+    tampered.public_inputs.swap(0, 1);
+    let tampered_proof = serde_json::to_string(&tampered)?;
+    if bindings::verify(tampered_proof.clone(), GOLDEN_VERIFYING_KEY.to_vec())? {
+        anyhow::bail!("A proof with swapped public inputs verifies");
+    }
+
+    Ok(Vectors {
+        poseidon: circomlibjs.poseidon,
+        vortex: golden.vortex,
+        root: golden.root,
+        leaves: golden.leaves,
+        note,
+        leaf_index: spent.leaf_index,
+        commitment: spent.commitment.clone(),
+        nullifier,
+        private_key: spent.private_key.clone(),
+        merkle_path,
+        proof_input: GOLDEN_PROOF_INPUT.trim().to_string(),
+        public_inputs_json,
+        tampered_proof,
+        verifying_key_hex: hex::encode(GOLDEN_VERIFYING_KEY),
+        proving_key_path: device_key_path,
+    })
+}
+
+fn escape(s: &str, kotlin: bool) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => escaped.push('\\'),
+            '$' if kotlin => escaped.push('\\'),
+            '\n' => {
+                escaped.push_str("\\n");
+                continue;
+            }
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn kotlin(s: &str) -> String {
+    escape(s, true)
+}
+
+fn swift(s: &str) -> String {
+    escape(s, false)
+}
+
+fn list(items: &[String], quote: fn(&str) -> String, open: &str, close: &str) -> String {
+    let items: Vec<String> = items.iter().map(|item| quote(item)).collect();
+    format!("{}{}{}", open, items.join(", "), close)
+}
+
+fn render_kotlin(v: &Vectors) -> String {
+    let poseidon: String = v
+        .poseidon
+        .iter()
+        .map(|vector| {
+            let call = match vector.inputs.as_slice() {
+                [input] => format!("poseidon1({})", kotlin(input)),
+                inputs => format!(
+                    "poseidon{}({})",
+                    inputs.len(),
+                    list(inputs, kotlin, "listOf(", ")")
+                ),
+            };
+            format!("    assertEquals({}, {})\n", kotlin(&vector.output), call)
+        })
+        .collect();
+    format!(
+        r#"// Generated by `cargo run --bin conformance` from the Rust test vectors. Do not edit;
+// regenerate after changing the vectors or the bindings.
+package xyz.mcxross.vortex
+
+import kotlin.test.Test
+import kotlin.test.assertEquals
+import kotlin.test.assertFalse
+import kotlin.test.assertTrue
+import uniffi.vortex.Note
+import uniffi.vortex.PointValidation
+import uniffi.vortex.ProofInputBuilder
+import uniffi.vortex.RootStatus
+import uniffi.vortex.checkRootConsistency
+import uniffi.vortex.computeUtxoNullifier
+import uniffi.vortex.createUtxo
+import uniffi.vortex.evictProverCache
+import uniffi.vortex.initProverCacheFromFile
+import uniffi.vortex.poseidon1
+import uniffi.vortex.poseidon2
+import uniffi.vortex.poseidon3
+import uniffi.vortex.poseidon4
+import uniffi.vortex.proveCached
+import uniffi.vortex.verify
+
+class VortexConformanceTest {{
+
+  @Test
+  fun poseidonMatchesRust() {{
+{poseidon}  }}
+
+  @Test
+  fun commitmentTreeMatchesRust() {{
+    val consistency = checkRootConsistency(LEAVES, ROOT, LEAVES.size.toULong(), emptyList())
+    assertEquals(RootStatus.CONSISTENT, consistency.status)
+  }}
+
+  @Test
+  fun merklePathDerivesTheRustRoot() {{
+    val utxo = createUtxo(Note({amount}uL, {public_key}, {blinding}, false), {leaf_index}uL, VORTEX)
+    assertEquals({commitment}, utxo.commitment)
+    assertEquals({nullifier}, computeUtxoNullifier(utxo, PRIVATE_KEY))
+    val builder = ProofInputBuilder()
+    builder.setVortex(VORTEX)
+    builder.addInput(utxo, PRIVATE_KEY, MERKLE_PATH)
+    assertTrue(builder.build().contains("\"root\":\"$ROOT\""))
+  }}
+
+  @Test
+  fun proofVerifiesWithTheRustPublicInputs() {{
+    initProverCacheFromFile(CIRCUIT_ID, PROVING_KEY_PATH, PointValidation.CHECKED)
+    try {{
+      val proof = proveCached(CIRCUIT_ID, PROOF_INPUT)
+      assertTrue(verify(proof, hex(VERIFYING_KEY_HEX)))
+      assertTrue(proof.contains(EXPECTED_PUBLIC_INPUTS))
+    }} finally {{
+      evictProverCache(CIRCUIT_ID)
+    }}
+  }}
+
+  @Test
+  fun tamperedProofIsRejected() {{
+    assertFalse(verify(TAMPERED_PROOF, hex(VERIFYING_KEY_HEX)))
+  }}
+
+  private fun hex(s: String): ByteArray =
+    ByteArray(s.length / 2) {{ s.substring(2 * it, 2 * it + 2).toInt(16).toByte() }}
+
+  private companion object {{
+    const val CIRCUIT_ID = {circuit_id}
+    const val PROVING_KEY_PATH = {proving_key_path}
+    const val VORTEX = {vortex}
+    const val ROOT = {root}
+    const val PRIVATE_KEY = {private_key}
+    const val PROOF_INPUT = {proof_input}
+    const val EXPECTED_PUBLIC_INPUTS = {public_inputs}
+    const val TAMPERED_PROOF = {tampered_proof}
+    const val VERIFYING_KEY_HEX = {verifying_key_hex}
+    val LEAVES = {leaves}
+    val MERKLE_PATH = {merkle_path}
+  }}
+}}
+"#,
+        poseidon = poseidon,
+        amount = v.note.amount,
+        public_key = kotlin(&v.note.public_key),
+        blinding = kotlin(&v.note.blinding),
+        leaf_index = v.leaf_index,
+        commitment = kotlin(&v.commitment),
+        nullifier = kotlin(&v.nullifier),
+        circuit_id = kotlin(CIRCUIT_ID),
+        proving_key_path = kotlin(&v.proving_key_path),
+        vortex = kotlin(&v.vortex),
+        root = kotlin(&v.root),
+        private_key = kotlin(&v.private_key),
+        proof_input = kotlin(&v.proof_input),
+        public_inputs = kotlin(&v.public_inputs_json),
+        tampered_proof = kotlin(&v.tampered_proof),
+        verifying_key_hex = kotlin(&v.verifying_key_hex),
+        leaves = list(&v.leaves, kotlin, "listOf(", ")"),
+        merkle_path = list(&v.merkle_path, kotlin, "listOf(", ")"),
+    )
+}
+
+fn render_swift(v: &Vectors, module: &str) -> String {
+    let poseidon: String = v
+        .poseidon
+        .iter()
+        .map(|vector| {
+            let call = match vector.inputs.as_slice() {
+                [input] => format!("poseidon1(input: {})", swift(input)),
+                inputs => format!(
+                    "poseidon{}(inputs: {})",
+                    inputs.len(),
+                    list(inputs, swift, "[", "]")
+                ),
+            };
+            format!(
+                "        XCTAssertEqual(try {}, {})\n",
+                call,
+                swift(&vector.output)
+            )
+        })
+        .collect();
+    format!(
+        r#"// Generated by `cargo run --bin conformance` from the Rust test vectors. Do not edit;
+// regenerate after changing the vectors or the bindings.
+import XCTest
+import {module}
+
+final class VortexConformanceTests: XCTestCase {{
+    func testPoseidonMatchesRust() throws {{
+{poseidon}    }}
+
+    func testCommitmentTreeMatchesRust() throws {{
+        let consistency = try checkRootConsistency(
+            localLeaves: leaves, chainRoot: root, chainLeafCount: UInt64(leaves.count), chainLeaves: [])
+        XCTAssertEqual(consistency.status, .consistent)
+    }}
+
+    func testMerklePathDerivesTheRustRoot() throws {{
+        let note = Note(amount: {amount}, publicKey: {public_key}, blinding: {blinding}, legacyCommitment: false)
+        let utxo = try createUtxo(note: note, leafIndex: {leaf_index}, vortex: vortex)
+        XCTAssertEqual(utxo.commitment, {commitment})
+        XCTAssertEqual(try computeUtxoNullifier(utxo: utxo, privateKey: privateKey), {nullifier})
+        let builder = ProofInputBuilder()
+        try builder.setVortex(vortex: vortex)
+        try builder.addInput(utxo: utxo, privateKey: privateKey, merklePath: merklePath)
+        XCTAssertTrue(try builder.build().contains("\"root\":\"\(root)\""))
+    }}
+
+    func testProofVerifiesWithTheRustPublicInputs() throws {{
+        _ = try initProverCacheFromFile(circuitId: circuitId, path: provingKeyPath, validation: .checked)
+        defer {{ _ = evictProverCache(circuitId: circuitId) }}
+        let proof = try proveCached(circuitId: circuitId, inputJson: proofInput)
+        XCTAssertTrue(try verify(proofJson: proof, verifyingKey: hex(verifyingKeyHex)))
+        XCTAssertTrue(proof.contains(expectedPublicInputs))
+    }}
+
+    func testTamperedProofIsRejected() throws {{
+        XCTAssertFalse(try verify(proofJson: tamperedProof, verifyingKey: hex(verifyingKeyHex)))
+    }}
+}}
+
+private func hex(_ string: String) -> Data {{
+    var data = Data()
+    var index = string.startIndex
+    while index < string.endIndex {{
+        let next = string.index(index, offsetBy: 2)
+        data.append(UInt8(string[index..<next], radix: 16)!)
+        index = next
+    }}
+    return data
+}}
+
+private let circuitId = {circuit_id}
+private let provingKeyPath = {proving_key_path}
+private let vortex = {vortex}
+private let root = {root}
+private let privateKey = {private_key}
+private let proofInput = {proof_input}
+private let expectedPublicInputs = {public_inputs}
+private let tamperedProof = {tampered_proof}
+private let verifyingKeyHex = {verifying_key_hex}
+private let leaves = {leaves}
+private let merklePath = {merkle_path}
+"#,
+        module = module,
+        poseidon = poseidon,
+        amount = v.note.amount,
+        public_key = swift(&v.note.public_key),
+        blinding = swift(&v.note.blinding),
+        leaf_index = v.leaf_index,
+        commitment = swift(&v.commitment),
+        nullifier = swift(&v.nullifier),
+        circuit_id = swift(CIRCUIT_ID),
+        proving_key_path = swift(&v.proving_key_path),
+        vortex = swift(&v.vortex),
+        root = swift(&v.root),
+        private_key = swift(&v.private_key),
+        proof_input = swift(&v.proof_input),
+        public_inputs = swift(&v.public_inputs_json),
+        tampered_proof = swift(&v.tampered_proof),
+        verifying_key_hex = swift(&v.verifying_key_hex),
+        leaves = list(&v.leaves, swift, "[", "]"),
+        merkle_path = list(&v.merkle_path, swift, "[", "]"),
+    )
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let proving_key = take_flag(&mut args, "--proving-key")?;
+    let device_key_path = take_flag(&mut args, "--device-key-path")?;
+    let swift_module = take_flag(&mut args, "--swift-module")?.unwrap_or_else(|| "vortex".into());
+    let out_dir = args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "files/conformance".to_string());
+    let out_dir = Path::new(&out_dir);
+    fs::create_dir_all(out_dir.join("kotlin"))?;
+    fs::create_dir_all(out_dir.join("swift"))?;
+
+    let key_copy = out_dir.join("vortex_conformance.pk");
+    match proving_key {
+        Some(path) => {
+            fs::copy(path, &key_copy)?;
+        }
+        None => {
+            println!("Running setup...");
+            // Same seed as `keygen`, so the key matches the golden verifying key
+            let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+                TransactionCircuit::empty(),
+                &mut ChaCha20Rng::from_seed([0u8; 32]),
+            )?;
+            let mut pk_bytes = Vec::new();
+            pk.serialize_compressed(&mut pk_bytes)?;
+            fs::write(&key_copy, pk_bytes)?;
+        }
+    }
+    let device_key_path = match device_key_path {
+        Some(path) => path,
+        None => fs::canonicalize(&key_copy)?.display().to_string(),
+    };
+
+    println!("Checking the vectors against the bindings...");
+    let vectors = collect_vectors(&key_copy, device_key_path)?;
+
+    let kotlin_path = out_dir.join("kotlin/VortexConformanceTest.kt");
+    fs::write(&kotlin_path, render_kotlin(&vectors))?;
+    let swift_path = out_dir.join("swift/VortexConformanceTests.swift");
+    fs::write(&swift_path, render_swift(&vectors, &swift_module))?;
+    println!("{}", kotlin_path.display());
+    println!("{}", swift_path.display());
+    println!(
+        "{} (loaded from {})",
+        key_copy.display(),
+        vectors.proving_key_path
+    );
+    Ok(())
+}
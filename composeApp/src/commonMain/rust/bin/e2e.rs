@@ -0,0 +1,279 @@
+//! End-to-end smoke test of the proving pipeline.
+//!
+//! Usage: `e2e [out_dir]`
+//!
+//! Runs a fresh setup, fills a commitment tree with other users' notes and a sender's
+//! deposit, builds a transfer paying a recipient with change back to the sender, proves it
+//! through the same JSON entry point as the apps, verifies it and checks the recipient can
+//! decrypt their note. The bytes the Move verifier takes are written to `out_dir` (default
+//! `files/e2e`): `verifying_key.bin`, `proof_points.bin` and `public_inputs.bin`, with the
+//! tree, notes and public inputs behind them in `vectors.json` and the prover input in
+//! `proof_input.json`. Any failing step exits non-zero.
+//!
+//! The verifying key comes from the same seeded setup as `keygen`, and the output is the
+//! golden set under `core/tests/golden`: after an intentional protocol change, regenerate
+//! it with `e2e core/tests/golden`.
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::Groth16;
+use ark_serialize::CanonicalSerialize;
+use ark_std::UniformRand;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use vortex::account::AccountSecret;
+use vortex::address::ShieldedAddress;
+use vortex::bindings;
+use vortex::circuit::TransactionCircuit;
+use vortex::constants::{MERKLE_TREE_LEVEL, ZERO_VALUE};
+use vortex::field::{parse_fr, FieldParseMode};
+use vortex::interop::ProofOutput;
+use vortex::merkle_tree::SparseMerkleTree;
+use vortex::note::{create_output_for, Utxo};
+use vortex::poseidon_opt::{fr_from_str, PoseidonOptimized};
+use vortex::relayer::{ext_data_hash, ExtData};
+use vortex::viewing::SpendingKeys;
+
+const MIST_PER_SUI: u64 = 1_000_000_000;
+
+pub fn main() -> anyhow::Result<()> {
+    let out_dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "files/e2e".to_string());
+    let out_dir = Path::new(&out_dir);
+    fs::create_dir_all(out_dir)?;
+    let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+    let vortex = Fr::rand(&mut rng);
+
+    println!("[1/6] Running setup...");
+    let started = Instant::now();
+    // Same seed as `keygen`, so the key is the default `files/verification_key.bin`
+    let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+        TransactionCircuit::empty(),
+        &mut ChaCha20Rng::from_seed([0u8; 32]),
+    )?;
+    let mut pk_bytes = Vec::new();
+    pk.serialize_compressed(&mut pk_bytes)?;
+    let mut vk_bytes = Vec::new();
+    pk.vk.serialize_compressed(&mut vk_bytes)?;
+    println!("      done in {:.1?}", started.elapsed());
+
+    println!("[2/6] Building the commitment tree...");
+    let sender = SpendingKeys::from_seed(&[1u8; 32])?;
+    let recipient = SpendingKeys::from_seed(&[2u8; 32])?;
+    let sender_address = ShieldedAddress::from_viewing_key(&sender.viewing).encode();
+    let recipient_address = ShieldedAddress::from_viewing_key(&recipient.viewing).encode();
+
+    let mut deposit = |amount: u64| {
+        create_output_for(
+            &sender_address,
+            amount * MIST_PER_SUI,
+            &vortex,
+            &sender.viewing,
+            None,
+            &mut rng,
+        )
+    };
+    let deposit = [deposit(30)?, deposit(12)?];
+
+    // The sender's deposit lands as one output pair between other users' transactions
+    let mut leaves: Vec<Fr> = (0..6).map(|_| Fr::rand(&mut rng)).collect();
+    let first_leaf = leaves.len() as u64;
+    let utxos = [
+        Utxo::new(deposit[0].note.clone(), first_leaf, &vortex)?,
+        Utxo::new(deposit[1].note.clone(), first_leaf + 1, &vortex)?,
+    ];
+    for utxo in &utxos {
+        leaves.push(utxo.note.commitment(&vortex)?);
+    }
+    leaves.extend((0..4).map(|_| Fr::rand(&mut rng)));
+
+    let hasher = PoseidonOptimized::new_t3();
+    let mut tree =
+        SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &fr_from_str(ZERO_VALUE));
+    tree.bulk_insert(&leaves, &hasher)?;
+    println!("      {} leaves, root {}", tree.len(), tree.root());
+
+    println!("[3/6] Building the transfer...");
+    let spends = utxos
+        .iter()
+        .map(|utxo| {
+            let path = tree.generate_membership_proof(utxo.metadata.leaf_index as usize)?;
+            utxo.spend_input(sender.spending_key(), path)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let nullifiers = utxos
+        .iter()
+        .map(|utxo| utxo.nullifier(sender.spending_key()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let payment = create_output_for(
+        &recipient_address,
+        25 * MIST_PER_SUI,
+        &vortex,
+        &sender.viewing,
+        None,
+        &mut rng,
+    )?;
+    let change = create_output_for(
+        &sender_address,
+        17 * MIST_PER_SUI,
+        &vortex,
+        &sender.viewing,
+        None,
+        &mut rng,
+    )?;
+    let ext_data = ExtData {
+        value: 0,
+        is_deposit: false,
+        relayer: "0x0".to_string(),
+        relayer_fee: 0,
+        encrypted_output_0: hex::decode(&payment.encrypted_output)?,
+        encrypted_output_1: hex::decode(&change.encrypted_output)?,
+    };
+    let account_secret = AccountSecret::generate(&mut rng);
+
+    let s = |x: &Fr| x.into_bigint().to_string();
+    let path = |i: usize| -> Vec<[String; 2]> {
+        spends[i]
+            .merkle_path
+            .path
+            .iter()
+            .map(|(left, right)| [s(left), s(right)])
+            .collect()
+    };
+    let input = serde_json::json!({
+        "vortex": s(&vortex),
+        "root": s(&tree.root()),
+        "publicAmount": "0",
+        "inputNullifier0": s(&nullifiers[0]),
+        "inputNullifier1": s(&nullifiers[1]),
+        "outputCommitment0": payment.commitment,
+        "outputCommitment1": change.commitment,
+        "hashedAccountSecret": s(&account_secret.hashed()),
+        "extDataHash": s(&ext_data_hash(&ext_data)?),
+        "accountSecret": *account_secret.to_decimal(),
+        "inPrivateKey0": s(&spends[0].private_key),
+        "inPrivateKey1": s(&spends[1].private_key),
+        "inAmount0": s(&spends[0].amount),
+        "inAmount1": s(&spends[1].amount),
+        "inBlinding0": s(&spends[0].blinding),
+        "inBlinding1": s(&spends[1].blinding),
+        "inPathIndex0": s(&spends[0].path_index),
+        "inPathIndex1": s(&spends[1].path_index),
+        "merklePath0": path(0),
+        "merklePath1": path(1),
+        "outPublicKey0": payment.note.public_key,
+        "outPublicKey1": change.note.public_key,
+        "outAmount0": payment.note.amount.to_string(),
+        "outAmount1": change.note.amount.to_string(),
+        "outBlinding0": payment.note.blinding,
+        "outBlinding1": change.note.blinding,
+    })
+    .to_string();
+
+    println!("[4/6] Proving...");
+    let started = Instant::now();
+    let proof_json = bindings::prove(input.clone(), pk_bytes)?;
+    println!("      done in {:.1?}", started.elapsed());
+
+    println!("[5/6] Verifying...");
+    if !bindings::verify(proof_json.clone(), vk_bytes.clone())? {
+        anyhow::bail!("Proof does not verify");
+    }
+    let received = recipient
+        .viewing
+        .decrypt_incoming(
+            &ext_data.encrypted_output_0,
+            &parse_fr(&payment.commitment, FieldParseMode::Strict)?,
+            &vortex,
+        )
+        .ok_or_else(|| anyhow::anyhow!("Recipient cannot open the payment note"))?;
+    println!(
+        "      proof verifies, recipient received {} MIST",
+        received.amount
+    );
+
+    println!("[6/6] Writing Move verifier inputs...");
+    let proof: ProofOutput = serde_json::from_str(&proof_json)?;
+    let spent: Vec<serde_json::Value> = utxos
+        .iter()
+        .zip(&nullifiers)
+        .map(|(utxo, nullifier)| {
+            serde_json::json!({
+                "amount": utxo.note.amount.to_string(),
+                "publicKey": utxo.note.public_key,
+                "blinding": utxo.note.blinding,
+                "leafIndex": utxo.metadata.leaf_index,
+                "privateKey": s(sender.spending_key()),
+                "commitment": utxo.commitment,
+                "nullifier": s(nullifier),
+            })
+        })
+        .collect();
+    let outputs: Vec<serde_json::Value> = [&payment, &change]
+        .iter()
+        .map(|output| {
+            serde_json::json!({
+                "amount": output.note.amount.to_string(),
+                "publicKey": output.note.public_key,
+                "blinding": output.note.blinding,
+                "commitment": output.commitment,
+            })
+        })
+        .collect();
+    let public_inputs: serde_json::Map<String, serde_json::Value> = TransactionCircuit::empty()
+        .public_input_names()
+        .into_iter()
+        .zip(&proof.public_inputs)
+        .map(|(name, value)| (name.to_string(), value.clone().into()))
+        .collect();
+    let vectors = serde_json::json!({
+        "vortex": s(&vortex),
+        "leaves": leaves.iter().map(s).collect::<Vec<_>>(),
+        "root": s(&tree.root()),
+        "spent": spent,
+        "outputs": outputs,
+        "accountSecret": *account_secret.to_decimal(),
+        "extData": {
+            "value": ext_data.value,
+            "isDeposit": ext_data.is_deposit,
+            "relayer": ext_data.relayer,
+            "relayerFee": ext_data.relayer_fee,
+            "encryptedOutput0": payment.encrypted_output,
+            "encryptedOutput1": change.encrypted_output,
+        },
+        "publicInputs": public_inputs,
+    });
+    fs::write(
+        out_dir.join("vectors.json"),
+        serde_json::to_string_pretty(&vectors)?,
+    )?;
+    fs::write(out_dir.join("proof_input.json"), &input)?;
+    let files = [
+        ("verifying_key.bin", vk_bytes),
+        (
+            "proof_points.bin",
+            hex::decode(&proof.proof_serialized_hex)?,
+        ),
+        (
+            "public_inputs.bin",
+            hex::decode(&proof.public_inputs_serialized_hex)?,
+        ),
+    ];
+    for (name, bytes) in &files {
+        fs::write(out_dir.join(name), bytes)?;
+        println!(
+            "      {} ({} bytes): 0x{}",
+            name,
+            bytes.len(),
+            hex::encode(bytes)
+        );
+    }
+
+    println!("✅ End-to-end pipeline passed");
+    Ok(())
+}
@@ -1,18 +1,118 @@
-use ark_bn254::Bn254;
+use ark_bn254::{Bn254, Fr};
+use ark_ff::AdditiveGroup;
 use ark_groth16::Groth16;
 
 use ark_serialize::CanonicalSerialize;
 use rand_chacha::ChaCha20Rng;
-use rand_core::SeedableRng;
+use rand_core::{OsRng, SeedableRng};
 
 use std::fs;
 use std::path::Path;
+use vortex::aggregation::AggregationSrs;
 use vortex::circuit::TransactionCircuit;
+use vortex::constants::{N_INS, N_OUTS};
+use vortex::debug::dump_constraint_system;
+use vortex::key_shards::{shard_proving_key, DEFAULT_POINTS_PER_SHARD};
+use vortex::validation::{compress_proving_key, serialize_proving_key};
+
+/// Parses `--dummy-inputs 0,1` into per-slot flags; no flag means the default circuit.
+fn parse_dummy_inputs() -> anyhow::Result<[bool; N_INS]> {
+    let mut dummy_inputs = [false; N_INS];
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(pos) = args.iter().position(|a| a == "--dummy-inputs") {
+        let slots = args.get(pos + 1).ok_or_else(|| {
+            anyhow::anyhow!("--dummy-inputs requires a comma-separated slot list")
+        })?;
+        for slot in slots.split(',') {
+            let slot: usize = slot.trim().parse()?;
+            if slot >= N_INS {
+                anyhow::bail!("Dummy input slot {} out of range (0..{})", slot, N_INS);
+            }
+            dummy_inputs[slot] = true;
+        }
+    }
+
+    Ok(dummy_inputs)
+}
+
+/// Parses `--aggregation-srs 64` into the most proofs one aggregate may hold.
+fn parse_aggregation_srs() -> anyhow::Result<Option<usize>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let Some(pos) = args.iter().position(|a| a == "--aggregation-srs") else {
+        return Ok(None);
+    };
+    let max_proofs: usize = args
+        .get(pos + 1)
+        .ok_or_else(|| anyhow::anyhow!("--aggregation-srs requires a proof count"))?
+        .parse()?;
+    if max_proofs == 0 {
+        anyhow::bail!("--aggregation-srs needs at least one proof");
+    }
+    Ok(Some(max_proofs))
+}
 
 pub fn main() -> anyhow::Result<()> {
     println!("Generating Groth16 proving and verifying files...");
 
-    let circuit = TransactionCircuit::empty();
+    let dummy_inputs = parse_dummy_inputs()?;
+    // `--no-account-secret` builds the variant without the hashed_account_secret input
+    let without_account_secret = std::env::args().any(|a| a == "--no-account-secret");
+    // `--ciphertext-binding` builds the variant with output ciphertext hash inputs
+    let ciphertext_binding = std::env::args().any(|a| a == "--ciphertext-binding");
+    // `--key-rotation` builds the variant with the authority_root input and authority paths
+    let key_rotation = std::env::args().any(|a| a == "--key-rotation");
+    // `--mixed-era` builds the variant that can also spend legacy commitments
+    let mixed_era_inputs = std::env::args().any(|a| a == "--mixed-era");
+    // `--dump-constraints` also writes the R1CS of the same variant for auditors
+    let dump_constraints = std::env::args().any(|a| a == "--dump-constraints");
+    // `--shards` also writes the proving key split for `prove_sharded`
+    let shards = std::env::args().any(|a| a == "--shards");
+    // `--zstd` also writes the proving key zstd-compressed
+    let zstd = std::env::args().any(|a| a == "--zstd");
+    // `--aggregation-srs <max>` also writes a SnarkPack SRS for up to `max` proofs
+    let aggregation_srs = parse_aggregation_srs()?;
+    let mut suffix: String = dummy_inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, is_dummy)| **is_dummy)
+        .map(|(i, _)| format!("_dummy{}", i))
+        .collect();
+
+    if !suffix.is_empty() {
+        println!(
+            "⚠️  Dummy-slot keys reveal which inputs are dummies. Register them as a pool's \
+             only verifying key, never next to the default one."
+        );
+    }
+
+    let mut circuit = TransactionCircuit::empty().with_dummy_inputs(dummy_inputs)?;
+    if without_account_secret {
+        circuit = circuit.without_account_secret();
+        suffix.push_str("_noaccount");
+    }
+    if ciphertext_binding {
+        circuit = circuit.with_ciphertext_binding([Fr::ZERO; N_OUTS]);
+        suffix.push_str("_ciphertext");
+    }
+    if key_rotation {
+        circuit = circuit.with_key_rotation(
+            Fr::ZERO,
+            [None; N_INS],
+            [Fr::ZERO; N_INS],
+            [vortex::merkle_tree::Path::empty(); N_INS],
+        );
+        suffix.push_str("_rotation");
+    }
+    if mixed_era_inputs {
+        circuit = circuit.with_mixed_era_inputs();
+        suffix.push_str("_mixed_era");
+    }
+
+    let dump = dump_constraints
+        .then(|| dump_constraint_system(&circuit))
+        .transpose()?;
 
     let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
 
@@ -29,22 +129,72 @@ pub fn main() -> anyhow::Result<()> {
     let mut vk_bytes = Vec::new();
     vk.serialize_compressed(&mut vk_bytes)?;
 
-    let mut pk_bytes = Vec::new();
-    pk.serialize_compressed(&mut pk_bytes)?;
+    let pk_bytes = serialize_proving_key(&pk)?;
 
-    fs::write(keys_dir.join("verification_key.bin"), &vk_bytes)?;
     fs::write(
-        keys_dir.join("verification_key.hex"),
+        keys_dir.join(format!("verification_key{}.bin", suffix)),
+        &vk_bytes,
+    )?;
+    fs::write(
+        keys_dir.join(format!("verification_key{}.hex", suffix)),
         hex::encode(&vk_bytes),
     )?;
 
-    fs::write(keys_dir.join("proving_key.bin"), &pk_bytes)?;
-    fs::write(keys_dir.join("proving_key.hex"), hex::encode(&pk_bytes))?;
+    fs::write(
+        keys_dir.join(format!("proving_key{}.bin", suffix)),
+        &pk_bytes,
+    )?;
+    fs::write(
+        keys_dir.join(format!("proving_key{}.hex", suffix)),
+        hex::encode(&pk_bytes),
+    )?;
+
+    if let Some(dump) = &dump {
+        dump.write_json(&keys_dir.join(format!("constraint_system{}.json", suffix)))?;
+    }
+    if zstd {
+        fs::write(
+            keys_dir.join(format!("proving_key{}_zstd.bin", suffix)),
+            compress_proving_key(&pk_bytes)?,
+        )?;
+    }
+    if shards {
+        let shards_dir = keys_dir.join(format!("proving_key{}_shards", suffix));
+        fs::create_dir_all(&shards_dir)?;
+        for shard in shard_proving_key(&pk, DEFAULT_POINTS_PER_SHARD)? {
+            fs::write(shards_dir.join(&shard.name), &shard.bytes)?;
+        }
+    }
+    if let Some(max_proofs) = aggregation_srs {
+        // Unlike the seeded Groth16 setup, its secrets must not be reproducible
+        let srs = AggregationSrs::setup(max_proofs, &mut OsRng);
+        let mut srs_bytes = Vec::new();
+        srs.serialize_compressed(&mut srs_bytes)?;
+        fs::write(keys_dir.join("aggregation_srs.bin"), &srs_bytes)?;
+        let mut verifier_key_bytes = Vec::new();
+        srs.verifier_key().serialize_compressed(&mut verifier_key_bytes)?;
+        fs::write(
+            keys_dir.join("aggregation_verifier_key.bin"),
+            &verifier_key_bytes,
+        )?;
+    }
 
     println!("✅ Keys generated successfully!");
     println!("  Keys written to ./files/");
-    println!("    - proving_key.bin / .hex");
-    println!("    - verification_key.bin / .hex");
+    println!("    - proving_key{}.bin / .hex", suffix);
+    println!("    - verification_key{}.bin / .hex", suffix);
+    if dump.is_some() {
+        println!("    - constraint_system{}.json", suffix);
+    }
+    if zstd {
+        println!("    - proving_key{}_zstd.bin", suffix);
+    }
+    if shards {
+        println!("    - proving_key{}_shards/", suffix);
+    }
+    if aggregation_srs.is_some() {
+        println!("    - aggregation_srs.bin / aggregation_verifier_key.bin");
+    }
 
     Ok(())
 }
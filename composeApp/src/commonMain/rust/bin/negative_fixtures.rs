@@ -0,0 +1,44 @@
+//! Writes invalid-transaction fixtures for the Move verifier tests.
+//!
+//! Usage: `negative_fixtures <proof.json> <out_dir> [variant]`
+//!
+//! `proof.json` is the output of `prove` for a transaction the contract accepts; `variant`
+//! is the circuit it was proven with (`transaction`, `noAccountSecret`,
+//! `ciphertextBinding`, `keyRotation` or `mixedEraInputs`, default `transaction`). One
+//! `<case>.json` is written per fixture, holding the proof and the failure the contract
+//! should report.
+
+use std::fs;
+use std::path::Path;
+use vortex::estimate::CircuitVariant;
+use vortex::fixtures::negative_fixtures;
+use vortex::interop::ProofOutput;
+
+pub fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [proof_path, out_dir, rest @ ..] = args.as_slice() else {
+        anyhow::bail!("Usage: negative_fixtures <proof.json> <out_dir> [variant]");
+    };
+    let variant: CircuitVariant = match rest.first() {
+        Some(name) => serde_json::from_value(serde_json::Value::String(name.clone()))
+            .map_err(|_| anyhow::anyhow!("Unknown circuit variant '{}'", name))?,
+        None => CircuitVariant::Transaction,
+    };
+
+    let valid: ProofOutput = serde_json::from_str(&fs::read_to_string(proof_path)?)?;
+    let layout = variant.empty_circuit().public_input_names();
+    let fixtures = negative_fixtures(&valid, &layout)?;
+
+    let out_dir = Path::new(out_dir);
+    fs::create_dir_all(out_dir)?;
+    for fixture in &fixtures {
+        let name = serde_json::to_value(fixture.case)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let path = out_dir.join(format!("{}.json", name));
+        fs::write(&path, serde_json::to_string_pretty(fixture)?)?;
+        println!("{}: {}", path.display(), fixture.expected_failure);
+    }
+    Ok(())
+}
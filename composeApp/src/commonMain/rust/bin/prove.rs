@@ -0,0 +1,80 @@
+//! Proves a `ProofInput` off-device through the same entry point as the apps.
+//!
+//! Usage: `prove <proof_input.json> <proving_key> [out_proof.json] [--verifying-key <key>]`
+//!
+//! Key files are read as raw bytes, or as hex when the name ends in `.hex`, so both files
+//! `keygen` writes work. The input goes through the uniffi `prove`, and the `ProofOutput`
+//! JSON is written to `out_proof.json` or printed. A failure prints the error the app would
+//! have reported and, when the witness can be built, the first unsatisfied constraint; the
+//! exit status is then non-zero. With `--verifying-key` the proof is also verified.
+
+use std::fs;
+use std::path::Path;
+use vortex::bindings;
+
+/// Reads a key file, hex-decoding it when its name ends in `.hex`
+fn read_key(path: &str) -> anyhow::Result<Vec<u8>> {
+    if Path::new(path).extension().is_some_and(|ext| ext == "hex") {
+        let hex = fs::read_to_string(path)?;
+        Ok(hex::decode(hex.trim().trim_start_matches("0x"))?)
+    } else {
+        Ok(fs::read(path)?)
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let verifying_key = match args.iter().position(|a| a == "--verifying-key") {
+        Some(pos) => {
+            let path = args
+                .get(pos + 1)
+                .ok_or_else(|| anyhow::anyhow!("--verifying-key requires a key file"))?
+                .clone();
+            args.drain(pos..pos + 2);
+            Some(read_key(&path)?)
+        }
+        None => None,
+    };
+    let [input_path, proving_key_path, rest @ ..] = args.as_slice() else {
+        anyhow::bail!(
+            "Usage: prove <proof_input.json> <proving_key> [out_proof.json] [--verifying-key <key>]"
+        );
+    };
+
+    let input = fs::read_to_string(input_path)?;
+    let proving_key = read_key(proving_key_path)?;
+
+    let proof_json = match bindings::prove(input.clone(), proving_key) {
+        Ok(proof_json) => proof_json,
+        Err(e) => {
+            eprintln!("Proving failed: {}", e);
+            // Parse, key and public input errors leave no witness to locate a constraint in
+            if let Ok(Some(report)) = bindings::find_unsatisfied_constraint(input) {
+                eprintln!(
+                    "First unsatisfied constraint: #{} in {}",
+                    report.constraint_index, report.path
+                );
+                for value in &report.values {
+                    eprintln!("  {} = {}", value.name, value.value);
+                }
+            }
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(verifying_key) = verifying_key {
+        if !bindings::verify(proof_json.clone(), verifying_key)? {
+            anyhow::bail!("Proof does not verify with the given verifying key");
+        }
+        eprintln!("Proof verifies");
+    }
+
+    match rest.first() {
+        Some(out_path) => {
+            fs::write(out_path, &proof_json)?;
+            eprintln!("Proof written to {}", out_path);
+        }
+        None => println!("{}", proof_json),
+    }
+    Ok(())
+}
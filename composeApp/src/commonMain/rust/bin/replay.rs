@@ -0,0 +1,54 @@
+//! Reproduces a failed proof from a bug report.
+//!
+//! Usage: `replay <bug_report.json> [out_input.json]`
+//!
+//! `bug_report.json` is the output of `take_bug_report`. The placeholders are filled with
+//! fresh secrets, amounts and leaf indices, the public input checks and the constraint system are run, and the
+//! result is printed next to the recorded error. With `out_input.json`, the replayed
+//! `ProofInput` is written there so it can be fed to `prove` directly.
+
+use std::fs;
+use vortex::debug::find_unsatisfied;
+use vortex::replay::{circuit_from_input, replay_input, BugReport};
+
+pub fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [report_path, rest @ ..] = args.as_slice() else {
+        anyhow::bail!("Usage: replay <bug_report.json> [out_input.json]");
+    };
+
+    let report: BugReport = serde_json::from_str(&fs::read_to_string(report_path)?)?;
+    let env = &report.environment;
+    println!(
+        "Recorded on vortex {} ({}-{}, {}), features: [{}]",
+        env.crate_version,
+        env.target_arch,
+        env.target_os,
+        if env.debug_build { "debug" } else { "release" },
+        env.features.join(", ")
+    );
+    println!("Recorded error: {}", report.error);
+    if !report.full_detail {
+        println!("Amounts and leaf indices were redacted; replaying same-width substitutes");
+    }
+
+    let input = replay_input(&report)?;
+    if let Some(out_path) = rest.first() {
+        fs::write(out_path, serde_json::to_string_pretty(&input)?)?;
+        println!("Replayed input written to {}", out_path);
+    }
+
+    let circuit = circuit_from_input(&input)?;
+    match circuit.check_public_inputs() {
+        Ok(()) => println!("Public inputs: consistent"),
+        Err(e) => println!("Public inputs: {}", e),
+    }
+    match find_unsatisfied(&circuit)? {
+        Some(report) => println!(
+            "Constraints: #{} in {} is unsatisfied",
+            report.constraint_index, report.path
+        ),
+        None => println!("Constraints: all satisfied"),
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+//! Long-running soak test of the FFI proving, verification and scanning paths.
+//!
+//! Usage: `soak <proving_key.bin> <proof_input.json> [--minutes M] [--sample-secs S]
+//! [--max-growth-mb G]`
+//!
+//! Loops prove (through the cached-key path), verify and a trial-decryption scan for `M`
+//! minutes (default 60), re-caching the proving key every few iterations as apps do on
+//! memory pressure. Every `S` seconds (default 30) it prints resident set size and the live
+//! heap counted by this binary's allocator. The first sample is the baseline once caches
+//! are warm; the run fails if the live heap at the end has grown past it by more than `G`
+//! MiB (default 16), which is how leaks in the caching layers show up long before a mobile
+//! OOM. RSS growing while the live heap stays flat points at fragmentation instead.
+//!
+//! `files/proving_key.bin` from `keygen` and `core/tests/golden/proof_input.json` from
+//! `e2e` belong together.
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ark_std::UniformRand;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use vortex::address::ShieldedAddress;
+use vortex::bindings::{self, DEFAULT_CIRCUIT_ID};
+use vortex::note::create_output_for;
+use vortex::validation::{deserialize_proving_key, PointValidation};
+use vortex::viewing::{OutputEvent, SpendingKeys};
+
+/// Passes through to the system allocator, counting live bytes and allocation calls
+struct CountingAllocator;
+
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            LIVE_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The proving key is dropped from the cache and reloaded every this many iterations
+const RECACHE_EVERY: u64 = 10;
+/// Outputs trial-decrypted per scan, one in eight of them ours
+const SCAN_EVENTS: usize = 64;
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Resident set size in bytes, where `/proc` is available (Linux, Android)
+fn resident_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+struct Sample {
+    iterations: u64,
+    rss: Option<u64>,
+    live: u64,
+    allocations: u64,
+}
+
+impl Sample {
+    fn take(iterations: u64) -> Self {
+        Self {
+            iterations,
+            rss: resident_bytes(),
+            live: LIVE_BYTES.load(Ordering::Relaxed),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        }
+    }
+
+    fn print(&self, elapsed: Duration) {
+        let rss = self
+            .rss
+            .map(|rss| format!("{:.1} MiB", rss as f64 / MIB))
+            .unwrap_or_else(|| "n/a".to_string());
+        println!(
+            "[{:>6.0}s] iterations {:>5}  rss {:>10}  live heap {:>8.1} MiB  allocations {}",
+            elapsed.as_secs_f64(),
+            self.iterations,
+            rss,
+            self.live as f64 / MIB,
+            self.allocations
+        );
+    }
+}
+
+/// Value following `flag`, parsed, or `default` when the flag is absent
+fn flag_value(args: &[String], flag: &str, default: u64) -> anyhow::Result<u64> {
+    match args.iter().position(|a| a == flag) {
+        Some(pos) => {
+            let value = args
+                .get(pos + 1)
+                .ok_or_else(|| anyhow::anyhow!("{} requires a value", flag))?;
+            Ok(value.parse()?)
+        }
+        None => Ok(default),
+    }
+}
+
+/// Pool outputs for the scan, a fraction of them paying the scanning wallet
+fn scan_events(vortex: &Fr) -> anyhow::Result<(String, Vec<OutputEvent>)> {
+    let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+    let wallet = SpendingKeys::from_seed(&[3u8; 32])?;
+    let other = SpendingKeys::from_seed(&[4u8; 32])?;
+    let wallet_address = ShieldedAddress::from_viewing_key(&wallet.viewing).encode();
+    let other_address = ShieldedAddress::from_viewing_key(&other.viewing).encode();
+
+    let events = (0..SCAN_EVENTS)
+        .map(|i| {
+            let address = if i % 8 == 0 {
+                &wallet_address
+            } else {
+                &other_address
+            };
+            let output = create_output_for(
+                address,
+                1_000 + i as u64,
+                vortex,
+                &other.viewing,
+                None,
+                &mut rng,
+            )?;
+            Ok(OutputEvent {
+                leaf_index: i as u64,
+                commitment: output.commitment,
+                encrypted_output: output.encrypted_output,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((hex::encode(wallet.viewing.to_bytes()), events))
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [proving_key_path, input_path, ..] = args.as_slice() else {
+        anyhow::bail!(
+            "Usage: soak <proving_key.bin> <proof_input.json> [--minutes M] [--sample-secs S] [--max-growth-mb G]"
+        );
+    };
+    let duration = Duration::from_secs(flag_value(&args, "--minutes", 60)? * 60);
+    let sample_every = Duration::from_secs(flag_value(&args, "--sample-secs", 30)?);
+    let max_growth = flag_value(&args, "--max-growth-mb", 16)? * 1024 * 1024;
+
+    let pk_bytes = fs::read(proving_key_path)?;
+    let input = fs::read_to_string(input_path)?;
+    let mut vk_bytes = Vec::new();
+    deserialize_proving_key(&pk_bytes, PointValidation::Checked)?
+        .vk
+        .serialize_compressed(&mut vk_bytes)?;
+
+    let vortex = Fr::rand(&mut ChaCha20Rng::from_seed([8u8; 32]));
+    let (full_viewing_key, events) = scan_events(&vortex)?;
+    let vortex = vortex.into_bigint().to_string();
+    let expected_notes = events.len().div_ceil(8);
+
+    println!(
+        "Soaking for {} minutes, sampling every {}s...",
+        duration.as_secs() / 60,
+        sample_every.as_secs()
+    );
+    let started = Instant::now();
+    let mut last_sample = started;
+    let mut baseline: Option<Sample> = None;
+    let mut iterations = 0u64;
+
+    while started.elapsed() < duration {
+        if iterations.is_multiple_of(RECACHE_EVERY) {
+            bindings::clear_prover_cache();
+            bindings::init_prover_cache(pk_bytes.clone())?;
+        }
+
+        let proof_json = bindings::prove_cached(DEFAULT_CIRCUIT_ID.to_string(), input.clone())?;
+        if !bindings::verify(proof_json, vk_bytes.clone())? {
+            anyhow::bail!("Proof does not verify at iteration {}", iterations);
+        }
+        let notes = bindings::scan_notes(full_viewing_key.clone(), events.clone(), vortex.clone())?;
+        if notes.len() != expected_notes {
+            anyhow::bail!(
+                "Scan found {} notes at iteration {}, expected {}",
+                notes.len(),
+                iterations,
+                expected_notes
+            );
+        }
+        iterations += 1;
+
+        if last_sample.elapsed() >= sample_every {
+            last_sample = Instant::now();
+            let sample = Sample::take(iterations);
+            sample.print(started.elapsed());
+            baseline.get_or_insert(sample);
+        }
+    }
+
+    let end = Sample::take(iterations);
+    end.print(started.elapsed());
+    let Some(baseline) = baseline else {
+        println!("Run too short for a baseline sample; nothing to compare");
+        return Ok(());
+    };
+
+    let growth = end.live.saturating_sub(baseline.live);
+    let per_iteration = growth as f64 / (end.iterations - baseline.iterations).max(1) as f64;
+    println!(
+        "Live heap grew {:.2} MiB over {} iterations since the baseline ({:.0} bytes per iteration)",
+        growth as f64 / MIB,
+        end.iterations - baseline.iterations,
+        per_iteration
+    );
+    if let (Some(start), Some(end)) = (baseline.rss, end.rss) {
+        println!(
+            "RSS grew {:.2} MiB since the baseline",
+            end.saturating_sub(start) as f64 / MIB
+        );
+    }
+    if growth > max_growth {
+        anyhow::bail!(
+            "Live heap grew {:.2} MiB, more than the {} MiB allowed",
+            growth as f64 / MIB,
+            max_growth / (1024 * 1024)
+        );
+    }
+
+    println!("✅ Soak passed");
+    Ok(())
+}
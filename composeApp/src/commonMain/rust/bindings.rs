@@ -1,22 +1,98 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
-use std::str::FromStr;
-use num_bigint::BigUint;
 use ark_bn254::{Bn254, Fr};
 use ark_ff::PrimeField;
-use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
+use ark_groth16::{Groth16, Proof, ProvingKey};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_crypto_primitives::snark::SNARK;
 use rand_chacha::ChaCha20Rng;
-use rand_core::SeedableRng;
-use crate::poseidon_opt::{hash1, hash2, hash3, hash4};
-use crate::wasm::{ProofOutput, ProofInput};
+use rand_core::{OsRng, RngCore, SeedableRng};
+use crate::account::{self, AccountRegistration, AccountRotation, AccountSecret};
+use crate::address::ShieldedAddress;
+use crate::aggregation::{self, AggregateProof, AggregationSrs, AggregationVerifierKey};
+use crate::amount::{self, AmountPlan, TransferIntent};
+use crate::backup::{self, WalletSnapshot};
+use crate::dry_run::{self, DryRunReport, TransactionIntent};
+use crate::error_code::{ErrorCategory, VortexErrorCode};
+use crate::estimate::{self, DeviceCalibration, ProvingTimeEstimate};
+use crate::poseidon_opt::{self, hash1, hash2, hash3, hash4};
+use crate::interop::{validate_session_nonce, ProofInput, ProofOutput};
 use crate::circuit::TransactionCircuit;
-use crate::constants::MERKLE_TREE_LEVEL;
-use crate::merkle_tree::Path;
+use crate::config::{self, ProverConfig};
+use crate::constants::{AUTHORITY_TREE_LEVEL, MERKLE_TREE_LEVEL, ZERO_VALUE};
+use crate::cross_pool::{CrossPoolAction, CrossPoolEvent, CrossPoolTransfer, PoolRef};
+use crate::debug::{self, ConstraintReport};
+use crate::delegate;
+use crate::deposit_note::{DepositNote, DepositNoteParts};
+use crate::field::{self, FieldParseMode};
+use crate::mul_kernel::{self, MulKernel, MulKernelReport};
+use crate::health::{self, HealthReport};
+use crate::history::{self, HistoryEntry, SignedHistoryExport};
+use crate::input_builder;
+#[cfg(feature = "key-download")]
+use crate::key_download::{KeyDownload, KeyDownloader};
+use crate::key_shards::{self, DirectoryShards, KeyShard, ShardedProvingKey};
+use crate::memlock::{self, MemoryLock};
+use crate::merkle_tree::{self, Path, RootConsistency, SparseMerkleTree};
+use crate::metrics;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsSnapshot;
+use crate::consolidation::{self, ConsolidationPlan, ConsolidationPolicy};
+use crate::multi_send::{self, Recipient, SendPlan};
+use crate::note::{self, Note, NoteStatusChange, PreparedOutput, Utxo};
+use crate::onchain_vk::{self, OnChainVerifyingKey, VerifyingKeyCheck};
+use crate::padding::{self, DummyInput};
+use crate::payment_request::{self, PaymentRequest, ScannedPaymentRequest};
+use crate::payment_uri;
+use crate::prover::{clear_circuit_structures, finalize_proof_with_domain, prepare_circuit, PreparedWitness, ProvingDomain};
+use crate::queue::{ProofExecutor, ProofJobListener, ProofJobStatus, ProofQueue, PROOF_MEMORY_ESTIMATE_BYTES};
+use crate::receipt::{self, PaymentReceipt};
+use crate::relayer::{self, ExtData, RelayReceipt, RelayerFeeQuote};
+use crate::relayer_fee::{self, FeeSplit};
+use crate::replay;
+use crate::rotation::{self, KeyRotation};
+use crate::selftest::{self, KeyManifest, SelfTestReport};
+use crate::sui_tx::{self, TransactCall};
+use crate::telemetry::{self, Phase, PhaseTimer, ProofStats};
+use crate::trace_span;
+use crate::validation::{self, PointValidation};
+use crate::viewing::{self, DecryptedNote, FullViewingKey, IncomingViewingKey, NotePlaintext, OutputEvent, ScannedNote, SpendingKeys, ViewingKeyExport};
+use crate::witness_cache::{self, WitnessCacheStats};
+use crate::zklogin::ZkLoginCredentials;
+use zeroize::Zeroizing;
+
+/// Circuit id of the key used by calls that do not name one
+pub const DEFAULT_CIRCUIT_ID: &str = "default";
+
+/// A proving key with its evaluation domain, computed once and shared by its proofs
+struct ProverKey {
+    pk: ProvingKey<Bn254>,
+    domain: ProvingDomain,
+}
+
+impl ProverKey {
+    fn new(pk: ProvingKey<Bn254>) -> Result<Arc<Self>, BindingError> {
+        let domain = ProvingDomain::for_key(&pk).map_err(|e| BindingError::KeyError(format!("Invalid proving key: {}", e)))?;
+        Ok(Arc::new(Self { pk, domain }))
+    }
+}
+
+/// A resident proving key and the page locks keeping it in RAM
+struct CachedProvingKey {
+    key: Arc<ProverKey>,
+    /// SHA-256 of the bytes the key was loaded from, for `self_test`
+    digest: [u8; 32],
+    _locks: Vec<MemoryLock>,
+    /// Load order, for evicting the least recently loaded key
+    loaded_seq: u64,
+}
+
+static PROVING_KEY_LOAD_SEQ: AtomicU64 = AtomicU64::new(0);
 
 lazy_static! {
-    static ref PROVING_KEY_CACHE: Arc<Mutex<Option<ProvingKey<Bn254>>>> = Arc::new(Mutex::new(None));
+    /// Proving keys by circuit id (e.g. "deposit-v1", "2x2-v3"), so several variants stay resident
+    static ref PROVING_KEY_CACHE: Mutex<HashMap<String, CachedProvingKey>> = Mutex::new(HashMap::new());
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -33,10 +109,28 @@ pub enum BindingError {
     SerializationError(String),
     #[error("Invalid input: {0}")]
     InputError(String),
+    #[error("Unsatisfied constraint: {0}")]
+    ConstraintError(String),
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+impl BindingError {
+    /// The stable code of this error; each variant has the code of the same name
+    pub fn code(&self) -> VortexErrorCode {
+        match self {
+            BindingError::ParseError(_) => VortexErrorCode::Parse,
+            BindingError::KeyError(_) => VortexErrorCode::Key,
+            BindingError::ProofError(_) => VortexErrorCode::Proof,
+            BindingError::VerifyError(_) => VortexErrorCode::Verify,
+            BindingError::SerializationError(_) => VortexErrorCode::Serialization,
+            BindingError::InputError(_) => VortexErrorCode::Input,
+            BindingError::ConstraintError(_) => VortexErrorCode::Constraint,
+            BindingError::InternalError(_) => VortexErrorCode::Internal,
+        }
+    }
+}
+
 impl From<anyhow::Error> for BindingError {
     fn from(e: anyhow::Error) -> Self {
         BindingError::InternalError(e.to_string())
@@ -44,9 +138,11 @@ impl From<anyhow::Error> for BindingError {
 }
 
 fn parse_fr(s: &str) -> Result<Fr, BindingError> {
-    BigUint::from_str(s)
-        .map(Fr::from)
-        .map_err(|e| BindingError::ParseError(format!("Failed to parse '{}': {}", s, e)))
+    parse_fr_with(s, FieldParseMode::Strict)
+}
+
+fn parse_fr_with(s: &str, mode: FieldParseMode) -> Result<Fr, BindingError> {
+    field::parse_fr(s, mode).map_err(|e| BindingError::ParseError(e.to_string()))
 }
 
 fn fr_to_string(f: &Fr) -> String {
@@ -93,20 +189,290 @@ pub fn poseidon4(inputs: Vec<String>) -> Result<String, BindingError> {
     Ok(fr_to_string(&hash))
 }
 
+/// Canonical 32-byte little-endian encoding of a field element, as arkworks serializes it
+#[uniffi::export]
+pub fn field_to_bytes_le(value: String) -> Result<Vec<u8>, BindingError> {
+    Ok(field::fr_to_bytes_le(&parse_fr(&value)?).to_vec())
+}
+
+/// Canonical 32-byte big-endian encoding of a field element
+#[uniffi::export]
+pub fn field_to_bytes_be(value: String) -> Result<Vec<u8>, BindingError> {
+    Ok(field::fr_to_bytes_be(&parse_fr(&value)?).to_vec())
+}
+
+/// Decimal field element from 32 little-endian bytes; values at or above the modulus are
+/// rejected rather than reduced
+#[uniffi::export]
+pub fn field_from_bytes_le(bytes: Vec<u8>) -> Result<String, BindingError> {
+    field::fr_from_bytes_le(&bytes).map(|f| fr_to_string(&f)).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// Decimal field element from 32 big-endian bytes; values at or above the modulus are
+/// rejected rather than reduced
+#[uniffi::export]
+pub fn field_from_bytes_be(bytes: Vec<u8>) -> Result<String, BindingError> {
+    field::fr_from_bytes_be(&bytes).map(|f| fr_to_string(&f)).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// Caches the proving key of the default circuit
 #[uniffi::export]
 pub fn init_prover_cache(proving_key: Vec<u8>) -> Result<bool, BindingError> {
-    let pk = ProvingKey::<Bn254>::deserialize_compressed(&proving_key[..])
-        .map_err(|e| BindingError::KeyError(format!("Failed to deserialize proving key: {}", e)))?;
+    init_prover_cache_with(proving_key, PointValidation::Checked)
+}
+
+/// Caches the proving key using the given point-validation policy.
+///
+/// `PointValidation::Unchecked` skips the curve/subgroup checks and should only be used for
+/// the key bundled with the app, never for one downloaded at runtime.
+#[uniffi::export]
+pub fn init_prover_cache_with(
+    proving_key: Vec<u8>,
+    validation: PointValidation,
+) -> Result<bool, BindingError> {
+    init_prover_cache_for(DEFAULT_CIRCUIT_ID.to_string(), proving_key, validation)
+}
+
+/// Caches the proving key of circuit variant `circuit_id`, replacing any key cached under it.
+///
+/// Keys of other variants stay resident, so e.g. deposit and 2x2 keys can both be loaded
+/// and picked per call with `prove_cached`.
+#[uniffi::export]
+pub fn init_prover_cache_for(
+    circuit_id: String,
+    proving_key: Vec<u8>,
+    validation: PointValidation,
+) -> Result<bool, BindingError> {
+    let key = ProverKey::new(
+        validation::deserialize_proving_key(&proving_key, validation)
+            .map_err(|e| BindingError::KeyError(e.to_string()))?,
+    )?;
+    let locks = memlock::lock_proving_key(&key.pk);
+    let digest = selftest::proving_key_digest(&proving_key);
+
+    let loaded_seq = PROVING_KEY_LOAD_SEQ.fetch_add(1, Ordering::Relaxed);
 
     let mut cache = PROVING_KEY_CACHE.lock().unwrap();
-    *cache = Some(pk);
+    cache.insert(circuit_id, CachedProvingKey { key, digest, _locks: locks, loaded_seq });
+    evict_over_limit(&mut cache, config::prover_config().max_resident_keys());
     Ok(true)
 }
 
+/// Caches the proving key stored at `path` for `circuit_id`, streaming it from disk.
+///
+/// Accepts the same formats as `init_prover_cache_for`; zstd-compressed keys are decompressed
+/// while they are deserialized, without a temp file or the whole file in memory.
 #[uniffi::export]
-pub fn clear_prover_cache() -> bool {
+pub fn init_prover_cache_from_file(
+    circuit_id: String,
+    path: String,
+    validation: PointValidation,
+) -> Result<bool, BindingError> {
+    let file = std::fs::File::open(&path).map_err(|e| BindingError::KeyError(format!("Failed to open {}: {}", path, e)))?;
+    let mut reader = selftest::DigestReader::new(std::io::BufReader::new(file));
+    let key = ProverKey::new(
+        validation::read_proving_key(&mut reader, validation).map_err(|e| BindingError::KeyError(e.to_string()))?,
+    )?;
+    let digest = reader.finish().map_err(|e| BindingError::KeyError(format!("Failed to read {}: {}", path, e)))?;
+    let locks = memlock::lock_proving_key(&key.pk);
+
+    let loaded_seq = PROVING_KEY_LOAD_SEQ.fetch_add(1, Ordering::Relaxed);
+
     let mut cache = PROVING_KEY_CACHE.lock().unwrap();
-    *cache = None;
+    cache.insert(circuit_id, CachedProvingKey { key, digest, _locks: locks, loaded_seq });
+    evict_over_limit(&mut cache, config::prover_config().max_resident_keys());
+    Ok(true)
+}
+
+/// Receives key download progress, on the thread running `download_keys`
+#[cfg(feature = "key-download")]
+#[uniffi::export(with_foreign)]
+pub trait KeyDownloadListener: Send + Sync {
+    /// Bytes of `name` stored so far and its size if the server sent one; return `false` to
+    /// stop, keeping the partial file so the next call resumes it
+    fn on_progress(&self, name: String, stored_bytes: u64, total_bytes: Option<u64>) -> bool;
+}
+
+/// Downloads `keys` into `dir` over HTTPS, resuming partial files and checking each against
+/// its pinned SHA-256, and returns their paths in order. Keys already stored with the pinned
+/// hash are not fetched again. Load them with `init_prover_cache_from_file`.
+#[cfg(feature = "key-download")]
+#[uniffi::export]
+pub fn download_keys(
+    dir: String,
+    keys: Vec<KeyDownload>,
+    listener: Option<Arc<dyn KeyDownloadListener>>,
+) -> Result<Vec<String>, BindingError> {
+    let downloader = KeyDownloader::new(dir).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let paths = downloader
+        .download_all(&keys, &mut |key, stored, total| {
+            listener.as_ref().is_none_or(|listener| listener.on_progress(key.name.clone(), stored, total))
+        })
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(paths.into_iter().map(|path| path.to_string_lossy().into_owned()).collect())
+}
+
+/// Recompresses a serialized proving key with zstd, for storing or bundling it
+#[uniffi::export]
+pub fn compress_proving_key(proving_key: Vec<u8>) -> Result<Vec<u8>, BindingError> {
+    validation::compress_proving_key(&proving_key).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Drops the least recently loaded keys until at most `max_keys` remain
+fn evict_over_limit(cache: &mut HashMap<String, CachedProvingKey>, max_keys: usize) {
+    while cache.len() > max_keys {
+        let Some(oldest) = cache.iter().min_by_key(|(_, cached)| cached.loaded_seq).map(|(id, _)| id.clone()) else {
+            break;
+        };
+        cache.remove(&oldest);
+    }
+}
+
+/// Selects the memory profile: proof parallelism, batch chunk size and how many proving keys
+/// stay cached. Keys beyond the new limit are evicted right away.
+#[uniffi::export]
+pub fn set_prover_config(config: ProverConfig) -> bool {
+    config::set_prover_config(config);
+    evict_over_limit(&mut PROVING_KEY_CACHE.lock().unwrap(), config.max_resident_keys());
+    true
+}
+
+#[uniffi::export]
+pub fn get_prover_config() -> ProverConfig {
+    config::prover_config()
+}
+
+/// Drops every cached proving key, the constraint matrices kept for each circuit shape and
+/// the cached Merkle path witnesses
+#[uniffi::export]
+pub fn clear_prover_cache() -> bool {
+    PROVING_KEY_CACHE.lock().unwrap().clear();
+    clear_circuit_structures();
+    witness_cache::clear();
+    true
+}
+
+/// Caches the Merkle path witnesses of up to `capacity` paths between proofs, so re-proving
+/// the same notes (e.g. after a fee change) skips their hashing; 0 turns the cache off and
+/// wipes it. Off by default since the witnesses reveal the notes' positions.
+#[uniffi::export]
+pub fn set_witness_cache_capacity(capacity: u32) -> bool {
+    witness_cache::set_capacity(capacity as usize);
+    true
+}
+
+/// Capacity, size and hit counters of the witness cache
+#[uniffi::export]
+pub fn witness_cache_stats() -> WitnessCacheStats {
+    witness_cache::stats()
+}
+
+/// The witness-map multiplication kernel in use and the per-kernel timings it was picked by.
+/// The first call benchmarks the kernels, which takes about a millisecond.
+#[uniffi::export]
+pub fn mul_kernel_report() -> MulKernelReport {
+    mul_kernel::report().clone()
+}
+
+/// Forces a witness-map multiplication kernel; `None` returns to the fastest benchmarked one
+#[uniffi::export]
+pub fn set_mul_kernel(kernel: Option<MulKernel>) -> bool {
+    mul_kernel::force(kernel);
+    true
+}
+
+/// Drops the proving key cached for `circuit_id`; `false` if none was cached
+#[uniffi::export]
+pub fn evict_prover_cache(circuit_id: String) -> bool {
+    PROVING_KEY_CACHE.lock().unwrap().remove(&circuit_id).is_some()
+}
+
+/// Ids of the circuits whose proving keys are cached, sorted
+#[uniffi::export]
+pub fn cached_circuit_ids() -> Vec<String> {
+    let mut ids: Vec<String> = PROVING_KEY_CACHE.lock().unwrap().keys().cloned().collect();
+    ids.sort();
+    ids
+}
+
+/// Proves and verifies a canned transfer with the key cached for `manifest.circuit_id`,
+/// after checking the key against the manifest. Run at app startup to catch mismatched
+/// keys, circuit or binary before a real payment.
+#[uniffi::export]
+pub fn self_test(verifying_key: Vec<u8>, manifest: KeyManifest) -> Result<SelfTestReport, BindingError> {
+    let (key, digest) = PROVING_KEY_CACHE.lock().unwrap().get(&manifest.circuit_id)
+        .map(|cached| (cached.key.clone(), cached.digest))
+        .ok_or_else(|| BindingError::KeyError(format!("No proving key cached for circuit '{}'", manifest.circuit_id)))?;
+    selftest::check_manifest(&digest, &manifest).map_err(|e| BindingError::KeyError(e.to_string()))?;
+
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    selftest::self_test(&key.pk, &vk).map_err(|e| BindingError::VerifyError(e.to_string()))
+}
+
+/// Checks the install without proving: the hashing constants, every cached proving key
+/// (against its entry in `manifests`, if any), that each manifest's key is cached, that
+/// `storage_dir` can be written when given, and the memory available for a proof.
+///
+/// Takes milliseconds, so it can run on every app start; `self_test` is the slower,
+/// end-to-end check.
+#[uniffi::export]
+pub fn health_check(manifests: Vec<KeyManifest>, storage_dir: Option<String>) -> HealthReport {
+    let mut checks = vec![health::check_constants()];
+    {
+        let cache = PROVING_KEY_CACHE.lock().unwrap();
+        let mut circuit_ids: Vec<&String> = cache.keys().collect();
+        circuit_ids.sort();
+        for circuit_id in circuit_ids {
+            let cached = &cache[circuit_id];
+            let manifest = manifests.iter().find(|manifest| &manifest.circuit_id == circuit_id);
+            checks.push(health::check_proving_key(circuit_id, &cached.key.pk, &cached.digest, manifest));
+        }
+        for manifest in manifests.iter().filter(|manifest| !cache.contains_key(&manifest.circuit_id)) {
+            checks.push(health::missing_proving_key(&manifest.circuit_id));
+        }
+    }
+    if let Some(storage_dir) = storage_dir {
+        checks.push(health::check_storage(std::path::Path::new(&storage_dir)));
+    }
+    let available_memory = health::available_memory();
+    checks.push(health::check_memory(available_memory, PROOF_MEMORY_ESTIMATE_BYTES));
+    HealthReport::new(checks, available_memory)
+}
+
+/// Absorbs cold-start costs in the background, e.g. at app start.
+///
+/// Parses the Poseidon constants, starts the global thread pool `prove` runs its parallel
+/// arithmetic on (`parallel` builds), benchmarks the witness-map multiplication kernels and,
+/// when `proving_key` is given and no key is cached yet, deserializes and caches it as
+/// `init_prover_cache` would, evaluation domain included. Returns immediately; a proof
+/// started before warm-up finishes simply does the remaining work itself.
+#[uniffi::export]
+pub fn warm_up(proving_key: Option<Vec<u8>>) -> bool {
+    std::thread::spawn(move || {
+        trace_span!("warm_up");
+        poseidon_opt::warm_up();
+        #[cfg(feature = "parallel")]
+        rayon::broadcast(|_| ());
+        mul_kernel::report();
+        let key_cached = cached_proving_key(DEFAULT_CIRCUIT_ID).is_some();
+        if let Some(proving_key) = proving_key
+            && !key_cached
+            && let Err(e) = init_prover_cache(proving_key)
+        {
+            log::warn!("Proving key warm-up failed: {}", e);
+        }
+    });
+    true
+}
+
+/// Turns best-effort `mlock`/`VirtualLock` of secrets and proving key pages on or off.
+///
+/// Takes effect for keys cached and proofs started after the call. Locking silently does
+/// nothing where the OS refuses it, so this never fails.
+#[uniffi::export]
+pub fn set_memory_locking_enabled(enabled: bool) -> bool {
+    memlock::set_enabled(enabled);
     true
 }
 
@@ -124,7 +490,7 @@ pub fn init_logger() -> bool {
     {
         // Simple logger for non-android environments (like tests)
          let _ = log::set_boxed_logger(Box::new(SimpleLogger));
-         let _ = log::set_max_level(log::LevelFilter::Debug);
+         log::set_max_level(log::LevelFilter::Debug);
          true
     }
 }
@@ -145,36 +511,228 @@ impl log::Log for SimpleLogger {
 
 #[uniffi::export]
 pub fn prove(input_json: String, proving_key: Vec<u8>) -> Result<String, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let _input_lock = memlock::lock(input_json.as_bytes());
+    let (key, _pk_locks) = load_proving_key(&proving_key)?;
+    let mut rng = proof_rng()?;
 
-    let cached_pk = {
-        let cache = PROVING_KEY_CACHE.lock().unwrap();
-        cache.clone()
-    };
+    prove_with_key(&key, &input_json, &mut rng)
+}
 
-    let pk = if let Some(pk) = cached_pk {
-        pk
-    } else {
-        ProvingKey::<Bn254>::deserialize_compressed(&proving_key[..])
-             .map_err(|e| BindingError::KeyError(format!("Failed to deserialize proving key: {}", e)))?
-    };
+/// Proves with the key cached for `circuit_id` by `init_prover_cache_for`.
+///
+/// Fails with `KeyError` if no key is cached for that circuit.
+#[uniffi::export]
+pub fn prove_cached(circuit_id: String, input_json: String) -> Result<String, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let _input_lock = memlock::lock(input_json.as_bytes());
+    let key = cached_proving_key(&circuit_id)
+        .ok_or_else(|| BindingError::KeyError(format!("No proving key cached for circuit '{}'", circuit_id)))?;
+    let mut rng = proof_rng()?;
+
+    prove_with_key(&key, &input_json, &mut rng)
+}
+
+/// Proves several transactions with the proving key loaded once, as many at a time as the
+/// prover config allows.
+///
+/// Each entry of `inputs_json` is a `ProofInput` JSON string; the returned vector holds the
+/// matching `ProofOutput` JSON strings in the same order. Proving stops after the chunk
+/// holding the first failing input and the error names its position in the batch.
+#[uniffi::export]
+pub fn prove_batch(inputs_json: Vec<String>, proving_key: Vec<u8>) -> Result<Vec<String>, BindingError> {
+    if inputs_json.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    trace_span!("prove_batch", batch_size = inputs_json.len());
+    let inputs_json = Zeroizing::new(inputs_json);
+    let _input_locks: Vec<MemoryLock> = inputs_json
+        .iter()
+        .filter_map(|input_json| memlock::lock(input_json.as_bytes()))
+        .collect();
+    let (key, _pk_locks) = load_proving_key(&proving_key)?;
+    let mut rng = proof_rng()?;
 
+    // Chunks of `max_parallel_proofs` are proven concurrently, each on its own thread and rng
+    let chunk_size = config::prover_config().max_parallel_proofs();
+    let mut outputs = Vec::with_capacity(inputs_json.len());
+    for (chunk_index, chunk) in inputs_json.chunks(chunk_size).enumerate() {
+        let seeds: Vec<[u8; 32]> = chunk.iter().map(|_| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seed
+        }).collect();
+        let results: Vec<Result<String, BindingError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk.iter().zip(seeds).map(|(input_json, seed)| {
+                let key = &key;
+                scope.spawn(move || prove_with_key(key, input_json, &mut ChaCha20Rng::from_seed(seed)))
+            }).collect();
+            handles.into_iter().map(|handle| {
+                handle.join().unwrap_or_else(|_| Err(BindingError::InternalError("Proving thread panicked".to_string())))
+            }).collect()
+        });
+        for (offset, result) in results.into_iter().enumerate() {
+            outputs.push(result.map_err(|e| prefix_batch_error(chunk_index * chunk_size + offset, e))?);
+        }
+    }
+    Ok(outputs)
+}
 
+/// Debug-prove mode: checks every constraint with region labels before proving.
+///
+/// Slower than `prove`, but instead of an opaque proving failure an unsatisfiable input
+/// yields `BindingError::ConstraintError` carrying the JSON `ConstraintReport` (region path
+/// of the first failing constraint and redacted values).
+#[uniffi::export]
+pub fn prove_debug(input_json: String, proving_key: Vec<u8>) -> Result<String, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let _input_lock = memlock::lock(input_json.as_bytes());
     let input: ProofInput = serde_json::from_str(&input_json)
         .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
+    let circuit = create_circuit_from_input(&input)?;
+
+    if let Some(report) = debug::find_unsatisfied(&circuit)? {
+        let report_json = serde_json::to_string(&report)
+            .map_err(|e| BindingError::SerializationError(format!("Failed to serialize report: {}", e)))?;
+        return Err(BindingError::ConstraintError(report_json));
+    }
+
+    let (key, _pk_locks) = load_proving_key(&proving_key)?;
+    let mut rng = proof_rng()?;
 
+    prove_with_key(&key, &input_json, &mut rng)
+}
+
+/// Returns the first unsatisfied constraint for `input_json`, or `None` if it would prove
+#[uniffi::export]
+pub fn find_unsatisfied_constraint(input_json: String) -> Result<Option<ConstraintReport>, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let input: ProofInput = serde_json::from_str(&input_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
     let circuit = create_circuit_from_input(&input)?;
 
-    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    Ok(debug::find_unsatisfied(&circuit)?)
+}
+
+/// Groth16 blinding randomness, freshly seeded from the OS for every proof
+fn proof_rng() -> Result<ChaCha20Rng, BindingError> {
+    ChaCha20Rng::from_rng(OsRng).map_err(|e| BindingError::InternalError(format!("Failed to seed proof randomness: {}", e)))
+}
+
+fn cached_proving_key(circuit_id: &str) -> Option<Arc<ProverKey>> {
+    PROVING_KEY_CACHE.lock().unwrap().get(circuit_id).map(|cached| cached.key.clone())
+}
+
+/// The cached default key, or `proving_key` deserialized when none is cached.
+///
+/// Only a freshly deserialized key comes with locks. A cached key is already locked by its
+/// cache entry, and since page locks do not nest, a second lock would unlock it on drop.
+fn load_proving_key(proving_key: &[u8]) -> Result<(Arc<ProverKey>, Option<Vec<MemoryLock>>), BindingError> {
+    trace_span!("load_proving_key");
+    if let Some(key) = cached_proving_key(DEFAULT_CIRCUIT_ID) {
+        Ok((key, None))
+    } else {
+        let key = ProverKey::new(
+            validation::deserialize_proving_key(proving_key, PointValidation::Checked)
+                .map_err(|e| BindingError::KeyError(e.to_string()))?,
+        )?;
+        let locks = memlock::lock_proving_key(&key.pk);
+        Ok((key, Some(locks)))
+    }
+}
+
+fn prefix_batch_error(index: usize, e: BindingError) -> BindingError {
+    let prefix = |msg: String| format!("batch input {}: {}", index, msg);
+    match e {
+        BindingError::ParseError(m) => BindingError::ParseError(prefix(m)),
+        BindingError::KeyError(m) => BindingError::KeyError(prefix(m)),
+        BindingError::ProofError(m) => BindingError::ProofError(prefix(m)),
+        BindingError::VerifyError(m) => BindingError::VerifyError(prefix(m)),
+        BindingError::SerializationError(m) => BindingError::SerializationError(prefix(m)),
+        BindingError::InputError(m) => BindingError::InputError(prefix(m)),
+        BindingError::ConstraintError(m) => BindingError::ConstraintError(prefix(m)),
+        BindingError::InternalError(m) => BindingError::InternalError(prefix(m)),
+    }
+}
+
+fn prove_with_key(
+    key: &ProverKey,
+    input_json: &str,
+    rng: &mut ChaCha20Rng,
+) -> Result<String, BindingError> {
+    trace_span!("prove");
+    let result = prepare_with_input(input_json).and_then(|prepared| finalize_prepared(key, prepared, rng));
+    if let Err(e) = &result {
+        metrics::proof_failed();
+        replay::record_failure(input_json, &e.to_string());
+    }
+    result
+}
+
+/// A parsed input whose witness is synthesized, waiting for its MSMs
+struct PreparedProof {
+    witness: PreparedWitness,
+    public_inputs_serialized: Vec<u8>,
+    session_nonce: Option<String>,
+    timer: PhaseTimer,
+}
+
+fn prepare_with_input(input_json: &str) -> Result<PreparedProof, BindingError> {
+    let mut timer = PhaseTimer::start();
+
+    let input: ProofInput = {
+        trace_span!("parse_input", len = input_json.len());
+        serde_json::from_str(input_json)
+            .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?
+    };
+    let _secret_lock = memlock::lock(input.account_secret.as_bytes());
 
-    let proof = Groth16::<Bn254>::prove(&pk, circuit.clone(), &mut rng)
-        .map_err(|e| BindingError::ProofError(format!("Failed to generate proof: {}", e)))?;
+    if let Some(nonce) = &input.session_nonce {
+        validate_session_nonce(nonce).map_err(|e| BindingError::InputError(e.to_string()))?;
+    }
 
-    let public_inputs_field = circuit.get_public_inputs();
+    let circuit = {
+        trace_span!("build_circuit");
+        create_circuit_from_input(&input)?
+    };
     let public_inputs_serialized = circuit
         .get_public_inputs_serialized()
         .map_err(|e| BindingError::SerializationError(format!("Failed to serialize public inputs: {}", e)))?;
+    timer.end_phase(Phase::Parse);
+
+    let witness = prepare_circuit(circuit, &mut timer)
+        .map_err(|e| BindingError::ProofError(format!("Failed to synthesize witness: {}", e)))?;
 
-     let mut proof_a_bytes = Vec::new();
+    Ok(PreparedProof {
+        witness,
+        public_inputs_serialized,
+        session_nonce: input.session_nonce.clone(),
+        timer,
+    })
+}
+
+fn finalize_prepared(
+    key: &ProverKey,
+    prepared: PreparedProof,
+    rng: &mut ChaCha20Rng,
+) -> Result<String, BindingError> {
+    finalize_prepared_with(prepared, |witness, timer| {
+        finalize_proof_with_domain(&key.pk, &key.domain, witness, rng, timer)
+            .map_err(|e| BindingError::ProofError(format!("Failed to generate proof: {}", e)))
+    })
+}
+
+/// Runs `prove` on the prepared witness and serializes the `ProofOutput`
+fn finalize_prepared_with(
+    prepared: PreparedProof,
+    prove: impl FnOnce(&PreparedWitness, &mut PhaseTimer) -> Result<Proof<Bn254>, BindingError>,
+) -> Result<String, BindingError> {
+    let PreparedProof { witness, public_inputs_serialized, session_nonce, mut timer } = prepared;
+
+    let proof = prove(&witness, &mut timer)?;
+
+    let mut proof_a_bytes = Vec::new();
     proof.a.serialize_compressed(&mut proof_a_bytes)
         .map_err(|e| BindingError::SerializationError(format!("Failed to serialize proof.a: {}", e)))?;
 
@@ -189,7 +747,8 @@ pub fn prove(input_json: String, proving_key: Vec<u8>) -> Result<String, Binding
     let mut proof_serialized = Vec::new();
     proof.serialize_compressed(&mut proof_serialized).unwrap();
 
-    let public_inputs: Vec<String> = public_inputs_field
+    let public_inputs: Vec<String> = witness
+        .public_inputs()
         .iter()
         .map(|input| input.into_bigint().to_string())
         .collect();
@@ -201,27 +760,324 @@ pub fn prove(input_json: String, proving_key: Vec<u8>) -> Result<String, Binding
         public_inputs,
         proof_serialized_hex: hex::encode(proof_serialized),
         public_inputs_serialized_hex: hex::encode(public_inputs_serialized),
+        session_nonce,
+    };
+
+    let output_json = {
+        trace_span!("serialize_output");
+        serde_json::to_string(&output)
+            .map_err(|e| BindingError::SerializationError(format!("Failed to serialize output: {}", e)))?
     };
+    timer.end_phase(Phase::Serialize);
+    timer.finish();
+
+    Ok(output_json)
+}
+
+/// Splits a proving key into shards for `prove_sharded`; write each shard to its `name` in
+/// one directory
+#[uniffi::export]
+pub fn shard_proving_key(proving_key: Vec<u8>, points_per_shard: u32) -> Result<Vec<KeyShard>, BindingError> {
+    let pk = validation::deserialize_proving_key(&proving_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    key_shards::shard_proving_key(&pk, points_per_shard).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Proves with a key sharded into `key_dir`, loading one shard at a time.
+///
+/// Use `PointValidation::Unchecked` only for shards the app wrote itself from a trusted key.
+#[uniffi::export]
+pub fn prove_sharded(key_dir: String, input_json: String, validation: PointValidation) -> Result<String, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let _input_lock = memlock::lock(input_json.as_bytes());
+    let key = ShardedProvingKey::open(DirectoryShards(key_dir.into()), validation)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let mut rng = proof_rng()?;
+
+    trace_span!("prove");
+    let result = prepare_with_input(&input_json).and_then(|prepared| {
+        finalize_prepared_with(prepared, |witness, timer| {
+            key_shards::finalize_proof_sharded(&key, witness, &mut rng, timer)
+                .map_err(|e| BindingError::ProofError(format!("Failed to generate proof: {}", e)))
+        })
+    });
+    if let Err(e) = &result {
+        metrics::proof_failed();
+        replay::record_failure(&input_json, &e.to_string());
+    }
+    result
+}
+
+/// Synthesizes the witness for `input_json` without running the MSMs.
+///
+/// Parsing, witness generation and constraint synthesis happen here, so this can run
+/// speculatively while the user reviews the transaction. `WitnessHandle::finalize` then
+/// only pays for the MSMs. The handle holds the witness until it is finalized or discarded.
+#[uniffi::export]
+pub fn prepare_proof(input_json: String) -> Result<Arc<WitnessHandle>, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let _input_lock = memlock::lock(input_json.as_bytes());
+    let prepared = prepare_with_input(&input_json)?;
+    Ok(Arc::new(WitnessHandle { prepared: Mutex::new(Some(prepared)) }))
+}
+
+/// Witness synthesized by `prepare_proof`
+#[derive(uniffi::Object)]
+pub struct WitnessHandle {
+    prepared: Mutex<Option<PreparedProof>>,
+}
+
+#[uniffi::export]
+impl WitnessHandle {
+    /// Runs the MSMs and returns the `ProofOutput` JSON, as `prove` would.
+    ///
+    /// A handle finalizes once; later calls fail with `InputError`.
+    pub fn finalize(&self, proving_key: Vec<u8>) -> Result<String, BindingError> {
+        let prepared = self.prepared.lock().unwrap().take()
+            .ok_or_else(|| BindingError::InputError("Witness was already finalized or discarded".to_string()))?;
+        let (key, _pk_locks) = load_proving_key(&proving_key)?;
+        let mut rng = proof_rng()?;
+
+        finalize_prepared(&key, prepared, &mut rng)
+    }
+
+    /// Wipes the witness without proving, e.g. when the user cancels
+    pub fn discard(&self) {
+        self.prepared.lock().unwrap().take();
+    }
+}
+
+/// A prover owning its proving keys and thread pool, independent of the process-wide key
+/// cache the free functions use.
+///
+/// Several provers can coexist, e.g. one per wallet or per circuit family, each with its own
+/// memory profile. `close` releases the keys (and their page locks) and stops the pool's
+/// threads at a known point instead of whenever the foreign object is garbage collected;
+/// proofs already running finish first and keep what they use until then.
+#[derive(uniffi::Object)]
+pub struct Prover {
+    config: ProverConfig,
+    state: Mutex<Option<ProverState>>,
+}
+
+struct ProverState {
+    keys: HashMap<String, CachedProvingKey>,
+    pool: Arc<ProverPool>,
+}
+
+#[uniffi::export]
+impl Prover {
+    /// Creates a prover with no keys loaded and a thread pool with one thread per core
+    #[uniffi::constructor]
+    pub fn new(config: ProverConfig) -> Result<Arc<Self>, BindingError> {
+        let pool = ProverPool::new()?;
+        Ok(Arc::new(Self { config, state: Mutex::new(Some(ProverState { keys: HashMap::new(), pool: Arc::new(pool) })) }))
+    }
+
+    /// Deserializes `keys` (circuit id to proving key bytes) and makes them available to
+    /// `prove`, replacing keys loaded earlier under the same ids.
+    ///
+    /// Every key is deserialized before any is added, so a malformed key leaves the prover
+    /// unchanged. Beyond the memory profile's resident-key limit the least recently loaded
+    /// keys are dropped.
+    pub fn load_keys(&self, keys: HashMap<String, Vec<u8>>, validation: PointValidation) -> Result<(), BindingError> {
+        let mut loaded = Vec::with_capacity(keys.len());
+        for (circuit_id, proving_key) in keys {
+            let key = ProverKey::new(
+                validation::deserialize_proving_key(&proving_key, validation)
+                    .map_err(|e| BindingError::KeyError(format!("Proving key for circuit '{}': {}", circuit_id, e)))?,
+            )?;
+            let locks = memlock::lock_proving_key(&key.pk);
+            let digest = selftest::proving_key_digest(&proving_key);
+            let loaded_seq = PROVING_KEY_LOAD_SEQ.fetch_add(1, Ordering::Relaxed);
+            loaded.push((circuit_id, CachedProvingKey { key, digest, _locks: locks, loaded_seq }));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let state = state.as_mut().ok_or_else(closed)?;
+        state.keys.extend(loaded);
+        evict_over_limit(&mut state.keys, self.config.max_resident_keys());
+        Ok(())
+    }
+
+    /// Proves `input_json` with the key loaded for `circuit_id`, running the MSMs and FFTs on
+    /// this prover's threads, and returns the `ProofOutput` JSON.
+    pub fn prove(&self, circuit_id: String, input_json: String) -> Result<String, BindingError> {
+        let input_json = Zeroizing::new(input_json);
+        let _input_lock = memlock::lock(input_json.as_bytes());
+        let (key, pool) = {
+            let state = self.state.lock().unwrap();
+            let state = state.as_ref().ok_or_else(closed)?;
+            let key = state.keys.get(&circuit_id).map(|cached| cached.key.clone())
+                .ok_or_else(|| BindingError::KeyError(format!("No proving key loaded for circuit '{}'", circuit_id)))?;
+            (key, state.pool.clone())
+        };
+        let mut rng = proof_rng()?;
+
+        pool.install(|| prove_with_key(&key, &input_json, &mut rng))
+    }
+
+    /// Ids of the circuits with a loaded key, sorted; empty once closed
+    pub fn circuit_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.state.lock().unwrap().iter().flat_map(|state| state.keys.keys().cloned()).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Drops the keys and the thread pool. Later calls fail with `InputError`; closing twice
+    /// does nothing.
+    pub fn close(&self) {
+        self.state.lock().unwrap().take();
+    }
+}
+
+fn closed() -> BindingError {
+    BindingError::InputError("Prover is closed".to_string())
+}
+
+/// Threads a `Prover` runs its proofs' parallel arithmetic on; proofs run on the calling
+/// thread in builds without the `parallel` feature
+#[cfg(feature = "parallel")]
+struct ProverPool(rayon::ThreadPool);
+
+#[cfg(feature = "parallel")]
+impl ProverPool {
+    fn new() -> Result<Self, BindingError> {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|index| format!("vortex-prover-{}", index))
+            .build()
+            .map(Self)
+            .map_err(|e| BindingError::InternalError(format!("Failed to start prover threads: {}", e)))
+    }
+
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        self.0.install(op)
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+struct ProverPool;
+
+#[cfg(not(feature = "parallel"))]
+impl ProverPool {
+    fn new() -> Result<Self, BindingError> {
+        Ok(Self)
+    }
+
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        op()
+    }
+}
+
+/// Enables or disables per-phase timing of proofs (disabled by default)
+#[uniffi::export]
+pub fn set_proof_stats_enabled(enabled: bool) -> bool {
+    telemetry::set_enabled(enabled);
+    true
+}
+
+/// Returns the phase timings of the most recent proof, or `None` if stats collection was
+/// disabled when it ran
+#[uniffi::export]
+pub fn get_last_proof_stats() -> Option<ProofStats> {
+    telemetry::last_proof_stats()
+}
+
+/// Receives the metrics drained by `drain_metrics`
+#[cfg(feature = "metrics")]
+#[uniffi::export(with_foreign)]
+pub trait MetricsSink: Send + Sync {
+    fn on_metrics(&self, snapshot: MetricsSnapshot);
+}
 
-    serde_json::to_string(&output)
-        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize output: {}", e)))
+/// Hands the proof counters and histograms recorded since the last drain to `sink`, on the
+/// calling thread, and starts over. Returns `false` without calling `sink` if no proof ran.
+#[cfg(feature = "metrics")]
+#[uniffi::export]
+pub fn drain_metrics(sink: Arc<dyn MetricsSink>) -> bool {
+    let snapshot = metrics::drain();
+    if snapshot.counters.is_empty() {
+        return false;
+    }
+    sink.on_metrics(snapshot);
+    true
+}
+
+/// Enables or disables keeping a redacted report of failed proofs (disabled by default)
+#[uniffi::export]
+pub fn set_bug_report_recording(enabled: bool) -> bool {
+    replay::set_enabled(enabled);
+    true
+}
+
+/// Keeps amounts and leaf indices exact in later bug reports instead of bucketing amounts by
+/// bit length and replacing indices (disabled by default). Only for reports the user agreed
+/// to share with their amounts.
+#[uniffi::export]
+pub fn set_bug_report_full_detail(enabled: bool) -> bool {
+    replay::set_full_detail(enabled);
+    true
+}
+
+/// Returns the `BugReport` JSON of the most recent failed proof and forgets it, or `None`
+/// if none failed while recording was enabled. Secrets in it are replaced by placeholders,
+/// amounts and leaf indices too unless `set_bug_report_full_detail` is on.
+#[uniffi::export]
+pub fn take_bug_report() -> Result<Option<String>, BindingError> {
+    replay::take_report()
+        .map(|report| serde_json::to_string(&report))
+        .transpose()
+        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize bug report: {}", e)))
+}
+
+/// The stable numeric code of `code`, e.g. 2001 for `Key`
+#[uniffi::export]
+pub fn error_code_value(code: VortexErrorCode) -> u32 {
+    code.code()
+}
+
+/// The category `code` belongs to, for deciding between retrying, re-fetching keys and
+/// reporting a bug
+#[uniffi::export]
+pub fn error_code_category(code: VortexErrorCode) -> ErrorCategory {
+    code.category()
+}
+
+/// Measures this device with a short MSM micro-benchmark; store the result for `estimate_proving_time`
+#[uniffi::export]
+pub fn calibrate_prover() -> DeviceCalibration {
+    estimate::calibrate()
+}
+
+/// Calibration measured from the stats of a real proof, more accurate than `calibrate_prover`
+#[uniffi::export]
+pub fn calibration_from_proof_stats(stats: ProofStats) -> Result<DeviceCalibration, BindingError> {
+    estimate::calibration_from_stats(&stats).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Expected proving time of each circuit variant, calibrating first when no stored calibration is given
+#[uniffi::export]
+pub fn estimate_proving_time(calibration: Option<DeviceCalibration>) -> Result<Vec<ProvingTimeEstimate>, BindingError> {
+    estimate::estimate_proving_time(calibration.as_ref()).map_err(|e| BindingError::ConstraintError(e.to_string()))
 }
 
 #[uniffi::export]
 pub fn verify(proof_json: String, verifying_key: Vec<u8>) -> Result<bool, BindingError> {
+    trace_span!("verify");
     let proof_output: ProofOutput = serde_json::from_str(&proof_json)
         .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
 
-    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&verifying_key[..])
-        .map_err(|e| BindingError::KeyError(format!("Failed to deserialize verifying key: {}", e)))?;
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
 
     let pvk = ark_groth16::prepare_verifying_key(&vk);
 
     let proof_bytes = hex::decode(&proof_output.proof_serialized_hex)
         .map_err(|e| BindingError::ParseError(format!("Failed to decode proof hex: {}", e)))?;
 
-    let proof = ark_groth16::Proof::<Bn254>::deserialize_compressed(&proof_bytes[..])
-        .map_err(|e| BindingError::ParseError(format!("Failed to deserialize proof: {}", e)))?;
+    // Proofs come from untrusted parties, so points are always fully validated
+    let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)
+        .map_err(|e| BindingError::ParseError(e.to_string()))?;
 
     let public_inputs: Result<Vec<Fr>, _> = proof_output.public_inputs.iter()
         .map(|s| parse_fr(s))
@@ -234,8 +1090,945 @@ pub fn verify(proof_json: String, verifying_key: Vec<u8>) -> Result<bool, Bindin
     Ok(is_valid)
 }
 
+/// Verifies a proof and checks it carries the session nonce of the originating request.
+///
+/// Fails with `VerifyError` when the nonce is missing or different, which is how replayed
+/// or mismatched relayer responses are detected.
+#[uniffi::export]
+pub fn verify_with_nonce(
+    proof_json: String,
+    verifying_key: Vec<u8>,
+    expected_nonce: String,
+) -> Result<bool, BindingError> {
+    let proof_output: ProofOutput = serde_json::from_str(&proof_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
+    proof_output
+        .check_session_nonce(&expected_nonce)
+        .map_err(|e| BindingError::VerifyError(e.to_string()))?;
+
+    verify(proof_json, verifying_key)
+}
+
+/// Compares the verifying key a pool registered on chain with the bundled `verifying_key`.
+///
+/// Call before proving against a pool: a mismatch means its proofs would be rejected.
+#[uniffi::export]
+pub fn check_onchain_verifying_key(onchain: OnChainVerifyingKey, verifying_key: Vec<u8>) -> Result<VerifyingKeyCheck, BindingError> {
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    onchain_vk::check_verifying_key(&onchain, &vk).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Hex fingerprint of a verifying key in the prepared form the Move contract stores
+#[uniffi::export]
+pub fn verifying_key_fingerprint(verifying_key: Vec<u8>) -> Result<String, BindingError> {
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    onchain_vk::fingerprint(&vk).map_err(|e| BindingError::SerializationError(e.to_string()))
+}
+
+/// Aggregates proofs made with one verifying key into a single argument (SnarkPack).
+///
+/// `srs` is a serialized `AggregationSrs` from `keygen --aggregation-srs`. Returns the
+/// serialized aggregate, which is sent with the public inputs of every proof in order.
+#[uniffi::export]
+pub fn aggregate_proofs(proofs_json: Vec<String>, verifying_key: Vec<u8>, srs: Vec<u8>) -> Result<Vec<u8>, BindingError> {
+    trace_span!("aggregate_proofs");
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let srs = AggregationSrs::deserialize_compressed(srs.as_slice())
+        .map_err(|e| BindingError::KeyError(format!("Failed to deserialize aggregation SRS: {}", e)))?;
+
+    let proofs = proofs_json.iter().enumerate().map(|(i, proof_json)| {
+        let output: ProofOutput = serde_json::from_str(proof_json)
+            .map_err(|e| BindingError::ParseError(format!("Proof {}: failed to parse proof JSON: {}", i, e)))?;
+        let proof_bytes = hex::decode(&output.proof_serialized_hex)
+            .map_err(|e| BindingError::ParseError(format!("Proof {}: failed to decode proof hex: {}", i, e)))?;
+        let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)
+            .map_err(|e| BindingError::ParseError(format!("Proof {}: {}", i, e)))?;
+        let public_inputs = output.public_inputs.iter().map(|s| parse_fr(s)).collect::<Result<Vec<Fr>, _>>()?;
+        Ok((proof, public_inputs))
+    }).collect::<Result<Vec<_>, BindingError>>()?;
+
+    let aggregate = aggregation::aggregate_proofs(&srs, &vk, &proofs)
+        .map_err(|e| BindingError::ProofError(e.to_string()))?;
+    let mut bytes = Vec::new();
+    aggregate.serialize_compressed(&mut bytes)
+        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize aggregate proof: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Checks an aggregate from `aggregate_proofs` against the public inputs of every proof, in
+/// the order they were aggregated; `verifier_key` is the serialized `AggregationVerifierKey`
+#[uniffi::export]
+pub fn verify_aggregate_proof(
+    aggregate: Vec<u8>,
+    public_inputs: Vec<Vec<String>>,
+    verifying_key: Vec<u8>,
+    verifier_key: Vec<u8>,
+) -> Result<bool, BindingError> {
+    trace_span!("verify_aggregate_proof");
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let verifier_key = AggregationVerifierKey::deserialize_compressed(verifier_key.as_slice())
+        .map_err(|e| BindingError::KeyError(format!("Failed to deserialize aggregation verifier key: {}", e)))?;
+    // Aggregates come from untrusted parties, so points are always fully validated
+    let aggregate = AggregateProof::deserialize_compressed(aggregate.as_slice())
+        .map_err(|e| BindingError::ParseError(format!("Failed to deserialize aggregate proof: {}", e)))?;
+    let public_inputs = public_inputs.iter()
+        .map(|inputs| inputs.iter().map(|s| parse_fr(s)).collect::<Result<Vec<Fr>, _>>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(aggregation::verify_aggregate(&verifier_key, &vk, &public_inputs, &aggregate))
+}
+
+/// Encrypts a `ProofInput` to a remote prover's X25519 public key.
+///
+/// Returns the `EncryptedWitness` JSON to send to the prover.
+#[uniffi::export]
+pub fn encrypt_witness_for_prover(input_json: String, prover_public_key: Vec<u8>) -> Result<String, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let input: ProofInput = serde_json::from_str(&input_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
+    let envelope = delegate::encrypt_witness(&input, &prover_public_key, &mut OsRng)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    serde_json::to_string(&envelope)
+        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize envelope: {}", e)))
+}
+
+/// Checks a proof produced by a remote prover against the input that was delegated.
+///
+/// Fails with `VerifyError` if the prover dropped the session nonce, changed any public input,
+/// or the proof is invalid.
+#[uniffi::export]
+pub fn verify_delegated_proof(
+    proof_json: String,
+    input_json: String,
+    verifying_key: Vec<u8>,
+) -> Result<bool, BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let input: ProofInput = serde_json::from_str(&input_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
+    let output: ProofOutput = serde_json::from_str(&proof_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
+    let vk = validation::deserialize_verifying_key(&verifying_key, PointValidation::Checked)
+        .map_err(|e| BindingError::KeyError(e.to_string()))?;
+
+    delegate::verify_delegated_proof(&output, &input, &vk).map_err(|e| BindingError::VerifyError(e.to_string()))?;
+    Ok(true)
+}
+
+/// Parses and validates a relayer fee quote; `now_ms` is the current Unix time in milliseconds
+#[uniffi::export]
+pub fn parse_relayer_fee_quote(quote_json: String, now_ms: u64) -> Result<RelayerFeeQuote, BindingError> {
+    relayer::parse_fee_quote(&quote_json, now_ms).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// The `ext_data_hash` public input binding `ext_data` to a proof, in decimal
+#[uniffi::export]
+pub fn compute_ext_data_hash(ext_data: ExtData) -> Result<String, BindingError> {
+    let hash = relayer::ext_data_hash(&ext_data).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(hash.into_bigint().to_string())
+}
+
+/// Builds the JSON body to POST to a relayer for a withdrawal or transfer proof
+#[uniffi::export]
+pub fn build_relay_request(
+    proof_json: String,
+    ext_data: ExtData,
+    quote: RelayerFeeQuote,
+    pool_id: String,
+    recipient: String,
+    now_ms: u64,
+) -> Result<String, BindingError> {
+    let proof: ProofOutput = serde_json::from_str(&proof_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
+    let request = relayer::build_relay_request(&proof, &ext_data, &quote, &pool_id, &recipient, now_ms)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    serde_json::to_string(&request)
+        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize relay request: {}", e)))
+}
+
+/// Splits a relayed withdrawal of `withdraw` from notes holding `input_amounts` into the ext
+/// data value, the relayer fee, what the recipient receives and the change
+#[uniffi::export]
+pub fn split_relayer_fee(input_amounts: Vec<u64>, withdraw: u64, quote: RelayerFeeQuote) -> Result<FeeSplit, BindingError> {
+    relayer_fee::split_relayer_fee(&input_amounts, withdraw, &quote).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Checks a relayed withdrawal's `ProofInput` against its ext data and the relayer's quote
+/// before proving: fee, relayer, the signed `public_amount`, amount conservation and
+/// `ext_data_hash`
+#[uniffi::export]
+pub fn check_relayed_input(input_json: String, ext_data: ExtData, quote: RelayerFeeQuote) -> Result<(), BindingError> {
+    let input_json = Zeroizing::new(input_json);
+    let input: ProofInput = serde_json::from_str(&input_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
+    relayer_fee::check_relayed_input(&input, &ext_data, &quote).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Ordered BCS arguments of the Move calls submitting a proof to the pool; a `None`
+/// `coin_type` is SUI
+#[uniffi::export]
+pub fn build_transact_call(
+    proof_json: String,
+    ext_data: ExtData,
+    package_id: String,
+    pool_id: String,
+    coin_type: Option<String>,
+) -> Result<TransactCall, BindingError> {
+    let proof: ProofOutput = serde_json::from_str(&proof_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
+    let coin_type = coin_type.as_deref().unwrap_or(sui_tx::SUI_COIN_TYPE);
+    sui_tx::build_transact_call(&proof, &ext_data, &package_id, &pool_id, coin_type)
+        .map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Checks the local commitment tree against the pool's root and leaf count before proving.
+///
+/// `local_leaves` are the wallet's leaves in index order; `chain_leaves` is as much of the
+/// chain's commitment history as the host has, possibly none, to locate a fork.
+#[uniffi::export]
+pub fn check_root_consistency(
+    local_leaves: Vec<String>,
+    chain_root: String,
+    chain_leaf_count: u64,
+    chain_leaves: Vec<String>,
+) -> Result<RootConsistency, BindingError> {
+    let parse_all = |leaves: &[String]| leaves.iter().map(|leaf| parse_fr(leaf)).collect::<Result<Vec<_>, _>>();
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &parse_fr(ZERO_VALUE)?);
+    tree.bulk_insert(&parse_all(&local_leaves)?, &hasher)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(merkle_tree::check_root_consistency(&tree, &parse_fr(&chain_root)?, chain_leaf_count, &parse_all(&chain_leaves)?, &hasher))
+}
+
+/// Issues a receipt disclosing `note`, paid to `recipient` at `leaf_index` of the tree of
+/// `leaves`, signed with the login key of `private_key` when given.
+///
+/// Returns `PaymentReceipt` JSON.
+#[uniffi::export]
+pub fn create_payment_receipt(
+    note: Note,
+    recipient: String,
+    leaf_index: u64,
+    leaves: Vec<String>,
+    vortex: String,
+    tx_digest: Option<String>,
+    private_key: Option<String>,
+) -> Result<String, BindingError> {
+    let private_key = private_key.as_deref().map(parse_fr).transpose()?.map(Zeroizing::new);
+    let leaves = leaves.iter().map(|leaf| parse_fr(leaf)).collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &parse_fr(ZERO_VALUE)?);
+    tree.bulk_insert(&leaves, &hasher).map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    let receipt = receipt::create_payment_receipt(&note, &recipient, leaf_index, &tree, &parse_fr(&vortex)?, tx_digest, private_key.as_deref())
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    serde_json::to_string(&receipt).map_err(|e| BindingError::SerializationError(e.to_string()))
+}
+
+/// Verifies a payment receipt's opening, Merkle path and signature; the caller still checks
+/// its `root` against the pool's root history and its `recipient`
+#[uniffi::export]
+pub fn verify_payment_receipt(receipt_json: String) -> Result<(), BindingError> {
+    let receipt: PaymentReceipt = serde_json::from_str(&receipt_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse receipt JSON: {}", e)))?;
+    receipt::verify_payment_receipt(&receipt).map_err(|e| BindingError::VerifyError(e.to_string()))
+}
+
+/// Plans the chain of 2-in-2-out transactions paying every recipient from the unspent notes
+#[uniffi::export]
+pub fn plan_multi_send(notes: Vec<Utxo>, recipients: Vec<Recipient>) -> Result<SendPlan, BindingError> {
+    multi_send::plan_multi_send(&notes, &recipients).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Builds the proof inputs of every step of `plan`, spending with the keys of `spend_seed`.
+///
+/// `leaves` are the synced tree's decimal leaves. Returns a JSON array of
+/// `{ proofInput, extData, outputs, change }` in submission order.
+#[uniffi::export]
+pub fn build_multi_send(
+    plan: SendPlan,
+    notes: Vec<Utxo>,
+    recipients: Vec<Recipient>,
+    spend_seed: Vec<u8>,
+    leaves: Vec<String>,
+    vortex: String,
+    account_secret: Option<String>,
+) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let keys = SpendingKeys::from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let account_secret = account_secret
+        .map(|secret| AccountSecret::parse(&secret))
+        .transpose()
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    let leaves = leaves.iter().map(|leaf| parse_fr(leaf)).collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &parse_fr(ZERO_VALUE)?);
+    tree.bulk_insert(&leaves, &hasher).map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    let transactions = multi_send::build_multi_send(&plan, &notes, &recipients, &keys, &tree, &parse_fr(&vortex)?, account_secret.as_ref(), &mut OsRng)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    serde_json::to_string(&transactions).map_err(|e| BindingError::SerializationError(e.to_string()))
+}
+
+/// Plans the merge of the wallet's unspent dust notes under `policy`; `None` when there is
+/// too little dust or no step is worth its fee. Cheap enough to run after every sync.
+#[uniffi::export]
+pub fn plan_consolidation(notes: Vec<Utxo>, policy: ConsolidationPolicy) -> Result<Option<ConsolidationPlan>, BindingError> {
+    consolidation::plan_consolidation(&notes, &policy).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Builds the proof inputs of every step of a consolidation plan, spending with the keys of
+/// `spend_seed` and paying each step's fee to `relayer`.
+///
+/// `leaves` are the synced tree's decimal leaves. Returns a JSON array of
+/// `{ proofInput, extData, outputs, change }` in submission order.
+#[uniffi::export]
+pub fn build_consolidation(
+    plan: ConsolidationPlan,
+    notes: Vec<Utxo>,
+    spend_seed: Vec<u8>,
+    leaves: Vec<String>,
+    vortex: String,
+    relayer: Option<RelayerFeeQuote>,
+    account_secret: Option<String>,
+) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let keys = SpendingKeys::from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let account_secret = account_secret
+        .map(|secret| AccountSecret::parse(&secret))
+        .transpose()
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    let leaves = leaves.iter().map(|leaf| parse_fr(leaf)).collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(&hasher, &parse_fr(ZERO_VALUE)?);
+    tree.bulk_insert(&leaves, &hasher).map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    let transactions = consolidation::build_consolidation(&plan, &notes, &keys, &tree, &parse_fr(&vortex)?, relayer.as_ref(), account_secret.as_ref(), &mut OsRng)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+    serde_json::to_string(&transactions).map_err(|e| BindingError::SerializationError(e.to_string()))
+}
+
+/// Starts a transfer of `amount` from `source` into `target` through `intermediate_address`
+#[uniffi::export]
+pub fn start_cross_pool_transfer(id: String, source: PoolRef, target: PoolRef, amount: u64, intermediate_address: String) -> Result<CrossPoolTransfer, BindingError> {
+    CrossPoolTransfer::new(id, source, target, amount, intermediate_address).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// What to do next for a persisted cross-pool transfer
+#[uniffi::export]
+pub fn cross_pool_next_action(transfer: CrossPoolTransfer) -> CrossPoolAction {
+    transfer.next_action()
+}
+
+/// Records the outcome of the last action and returns the transfer to persist
+#[uniffi::export]
+pub fn apply_cross_pool_event(transfer: CrossPoolTransfer, event: CrossPoolEvent) -> Result<CrossPoolTransfer, BindingError> {
+    let mut transfer = transfer;
+    transfer.apply(event).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(transfer)
+}
+
+/// Verifies a relayer's submission response against the proof it was sent
+#[uniffi::export]
+pub fn verify_relay_response(response_json: String, proof_json: String) -> Result<RelayReceipt, BindingError> {
+    let proof: ProofOutput = serde_json::from_str(&proof_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse proof JSON: {}", e)))?;
+
+    relayer::verify_relay_response(&response_json, &proof).map_err(|e| BindingError::VerifyError(e.to_string()))
+}
+
+/// Generates a fresh non-zero account secret, returned in decimal
+#[uniffi::export]
+pub fn generate_account_secret() -> String {
+    AccountSecret::generate(&mut OsRng).to_decimal().to_string()
+}
+
+/// `Poseidon1(account_secret)` in decimal, for `ProofInput::hashed_account_secret`
+#[uniffi::export]
+pub fn hash_account_secret(account_secret: String) -> Result<String, BindingError> {
+    let secret = AccountSecret::parse(&account_secret).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(secret.hashed().into_bigint().to_string())
+}
+
+/// Builds the Move call that registers an account for `account_secret`
+#[uniffi::export]
+pub fn account_registration_payload(package_id: String, account_secret: String) -> Result<AccountRegistration, BindingError> {
+    let secret = AccountSecret::parse(&account_secret).map_err(|e| BindingError::InputError(e.to_string()))?;
+    account::registration_payload(&package_id, &secret).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Generates a replacement for `account_secret` together with its registration call
+#[uniffi::export]
+pub fn rotate_account_secret(package_id: String, account_secret: String) -> Result<AccountRotation, BindingError> {
+    let previous = AccountSecret::parse(&account_secret).map_err(|e| BindingError::InputError(e.to_string()))?;
+    let (_, rotation) = account::rotate(&previous, &package_id, &mut OsRng).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(rotation)
+}
+
+/// Derives the account secret of a zkLogin user, returned in decimal; `expected_address`
+/// is checked against the credentials when given
+#[uniffi::export]
+pub fn derive_zklogin_account_secret(credentials: ZkLoginCredentials, expected_address: Option<String>) -> Result<String, BindingError> {
+    let secret = credentials.derive_account_secret(expected_address.as_deref()).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(secret.to_decimal().to_string())
+}
+
+/// The Sui address a zkLogin session signs for
+#[uniffi::export]
+pub fn zklogin_address(credentials: ZkLoginCredentials) -> Result<String, BindingError> {
+    credentials.address().map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Encrypts `account_secret` under a 32-byte host key for storage; returns hex
+#[uniffi::export]
+pub fn seal_account_secret(account_secret: String, key: Vec<u8>) -> Result<String, BindingError> {
+    let account_secret = Zeroizing::new(account_secret);
+    let secret = AccountSecret::parse(&account_secret).map_err(|e| BindingError::InputError(e.to_string()))?;
+    secret.seal(&key, &mut OsRng).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Decrypts a secret produced by `seal_account_secret`, returned in decimal
+#[uniffi::export]
+pub fn unseal_account_secret(sealed_hex: String, key: Vec<u8>) -> Result<String, BindingError> {
+    let secret = AccountSecret::unseal(&sealed_hex, &key).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(secret.to_decimal().to_string())
+}
+
+/// Encrypts the wallet's notes and sync checkpoint under a password, for restoring on another device
+#[uniffi::export]
+pub fn export_backup(snapshot: WalletSnapshot, password: String) -> Result<Vec<u8>, BindingError> {
+    let password = Zeroizing::new(password);
+    backup::export_backup(&snapshot, &password, &mut OsRng).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Decrypts a backup produced by `export_backup`
+#[uniffi::export]
+pub fn import_backup(backup: Vec<u8>, password: String) -> Result<WalletSnapshot, BindingError> {
+    let password = Zeroizing::new(password);
+    backup::import_backup(&backup, &password).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Derives the viewing keys of a 32-byte spend seed for export to a watch-only wallet
+#[uniffi::export]
+pub fn derive_viewing_keys(spend_seed: Vec<u8>) -> Result<ViewingKeyExport, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let keys = SpendingKeys::from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(ViewingKeyExport::new(&keys.viewing))
+}
+
+/// Derives the spending key (`in_private_key`) of a 32-byte spend seed, in decimal
+#[uniffi::export]
+pub fn derive_spending_key(spend_seed: Vec<u8>) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let keys = SpendingKeys::from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(keys.spending_key().into_bigint().to_string())
+}
+
+/// Derives the blinding of an output from the spend seed, in decimal.
+///
+/// Reproducible from the seed, recipient note public key, output slot and per-transaction
+/// nonce, so blindings need no separate backup. See `viewing::derive_output_blinding`.
+#[uniffi::export]
+pub fn derive_output_blinding(spend_seed: Vec<u8>, recipient_public_key: String, output_index: u32, tx_nonce: u64) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let blinding = viewing::derive_output_blinding(&spend_seed, &parse_fr(&recipient_public_key)?, output_index, tx_nonce).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(blinding.into_bigint().to_string())
+}
+
+/// Encrypts an output note to the recipient, keeping a copy for the sender's outgoing key.
+///
+/// Returns the bytes to publish as `encrypted_output`.
+#[uniffi::export]
+pub fn encrypt_note_output(
+    amount: u64,
+    blinding: String,
+    recipient_viewing_public_key: Vec<u8>,
+    sender_full_viewing_key: String,
+) -> Result<Vec<u8>, BindingError> {
+    let note = NotePlaintext { amount, blinding: parse_fr(&blinding)? };
+    let sender = parse_full_viewing_key(&sender_full_viewing_key)?;
+    viewing::encrypt_note(&note, &recipient_viewing_public_key, &sender, &mut OsRng)
+        .map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Builds an output of `amount` paying a shielded address, with its commitment and encrypted
+/// note. The blinding is random unless one is given.
+#[uniffi::export]
+pub fn create_output_for(
+    address: String,
+    amount: u64,
+    vortex: String,
+    sender_full_viewing_key: String,
+    blinding: Option<String>,
+) -> Result<PreparedOutput, BindingError> {
+    let sender = parse_full_viewing_key(&sender_full_viewing_key)?;
+    let blinding = blinding.as_deref().map(parse_fr).transpose()?;
+    note::create_output_for(&address, amount, &parse_fr(&vortex)?, &sender, blinding, &mut OsRng).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// The `output_ciphertext_hash` public input of an encrypted output, in decimal
+#[uniffi::export]
+pub fn compute_ciphertext_hash(encrypted_output: Vec<u8>) -> String {
+    viewing::ciphertext_hash(&encrypted_output).into_bigint().to_string()
+}
+
+/// Builds the record to register when rotating `previous_private_key` out for `new_private_key`
+#[uniffi::export]
+pub fn create_key_rotation(previous_private_key: String, new_private_key: String, now_ms: u64, grace_period_ms: u64) -> Result<KeyRotation, BindingError> {
+    let previous_private_key = Zeroizing::new(parse_fr(&previous_private_key)?);
+    let new_private_key = Zeroizing::new(parse_fr(&new_private_key)?);
+    rotation::rotate_key(&previous_private_key, &new_private_key, now_ms, grace_period_ms).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Whether `key_rotation` still accepts spends authorized by the previous key alone at `now_ms`
+#[uniffi::export]
+pub fn key_rotation_accepts_previous_key(key_rotation: KeyRotation, now_ms: u64) -> bool {
+    key_rotation.accepts_previous_key(now_ms)
+}
+
+/// The authority tree leaf notes of `private_key` are spent against, in decimal, authorized by
+/// `rotated_private_key` when given. The app looks up its index and path in the pool's
+/// authority tree; the leaf itself is never a public input.
+#[uniffi::export]
+pub fn compute_authority_leaf(private_key: String, rotated_private_key: Option<String>) -> Result<String, BindingError> {
+    let private_key = Zeroizing::new(parse_fr(&private_key)?);
+    let rotated_private_key = rotated_private_key.as_deref().map(parse_fr).transpose()?.map(Zeroizing::new);
+    Ok(rotation::authority_leaf(&private_key, rotated_private_key.as_deref()).into_bigint().to_string())
+}
+
+/// Decrypts a received note and checks it opens `commitment`; `None` if it is not ours.
+///
+/// For ciphertext-bound outputs the returned blinding is the bound one to spend with.
+#[uniffi::export]
+pub fn decrypt_incoming_note(
+    full_viewing_key: String,
+    encrypted_output: Vec<u8>,
+    commitment: String,
+    vortex: String,
+) -> Result<Option<DecryptedNote>, BindingError> {
+    let keys = parse_full_viewing_key(&full_viewing_key)?;
+    let note = keys.decrypt_incoming(&encrypted_output, &parse_fr(&commitment)?, &parse_fr(&vortex)?);
+    Ok(note.map(|note| DecryptedNote::new(&note, None)))
+}
+
+/// Trial-decrypts a batch of pool outputs and returns the notes received by this wallet
+#[uniffi::export]
+pub fn scan_notes(full_viewing_key: String, events: Vec<OutputEvent>, vortex: String) -> Result<Vec<ScannedNote>, BindingError> {
+    let keys = parse_full_viewing_key(&full_viewing_key)?;
+    keys.scan_notes(&events, &parse_fr(&vortex)?).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Decrypts a received note with an incoming viewing key alone, without a commitment check
+#[uniffi::export]
+pub fn decrypt_note_with_incoming_key(
+    incoming_viewing_key: String,
+    encrypted_output: Vec<u8>,
+) -> Result<Option<DecryptedNote>, BindingError> {
+    let key_bytes = viewing::decode_key_hex(&incoming_viewing_key, "incoming viewing key").map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let key = IncomingViewingKey::from_bytes(&key_bytes).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(key.decrypt(&encrypted_output).map(|note| DecryptedNote::new(&note, None)))
+}
+
+/// Decrypts a note this wallet sent; `None` if it was not sent by this key
+#[uniffi::export]
+pub fn decrypt_outgoing_note(
+    full_viewing_key: String,
+    encrypted_output: Vec<u8>,
+) -> Result<Option<DecryptedNote>, BindingError> {
+    let keys = parse_full_viewing_key(&full_viewing_key)?;
+    Ok(keys
+        .decrypt_outgoing(&encrypted_output)
+        .map(|(recipient, note)| DecryptedNote::new(&note, Some(recipient))))
+}
+
+/// The note a decrypted output opens to under the wallet's note public key
+#[uniffi::export]
+pub fn note_from_decrypted(decrypted: DecryptedNote, public_key: String) -> Result<Note, BindingError> {
+    Ok(Note::from_decrypted(&decrypted, &parse_fr(&public_key)?))
+}
+
+/// An unspent UTXO for `note` at `leaf_index`, with its commitment computed
+#[uniffi::export]
+pub fn create_utxo(note: Note, leaf_index: u64, vortex: String) -> Result<Utxo, BindingError> {
+    Utxo::new(note, leaf_index, &parse_fr(&vortex)?).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// The nullifier `utxo` publishes when spent with `private_key`, in decimal
+#[uniffi::export]
+pub fn compute_utxo_nullifier(utxo: Utxo, private_key: String) -> Result<String, BindingError> {
+    let private_key = Zeroizing::new(parse_fr(&private_key)?);
+    let nullifier = utxo.nullifier(&private_key).map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(nullifier.into_bigint().to_string())
+}
+
+/// Reconciles the wallet's notes with the pool's nullifiers after a sync: derives each
+/// note's nullifier with `private_key` and returns the notes whose `spent` flag is wrong.
+///
+/// `on_chain_nullifiers` (decimal) must be the pool's complete nullifier set, since a note
+/// flagged spent whose nullifier is missing is reported as unspent.
+#[uniffi::export]
+pub fn mark_spent_notes(owned_notes: Vec<Utxo>, on_chain_nullifiers: Vec<String>, private_key: String) -> Result<Vec<NoteStatusChange>, BindingError> {
+    let private_key = Zeroizing::new(parse_fr(&private_key)?);
+    let on_chain_nullifiers = on_chain_nullifiers.iter().map(|nullifier| parse_fr(nullifier)).collect::<Result<HashSet<Fr>, _>>()?;
+    note::mark_spent_notes(&owned_notes, &on_chain_nullifiers, &private_key).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// A zero-amount input at leaf `index` with a fresh random key, for filling an unused
+/// `ProofInput` slot; every derived value in it is consistent with the others
+#[uniffi::export]
+pub fn make_dummy_input(index: u64, vortex: String) -> Result<DummyInput, BindingError> {
+    padding::make_dummy_input(index, &parse_fr(&vortex)?, &mut OsRng).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+fn parse_full_viewing_key(full_viewing_key: &str) -> Result<FullViewingKey, BindingError> {
+    let bytes = viewing::decode_key_hex(full_viewing_key, "full viewing key").map_err(|e| BindingError::KeyError(e.to_string()))?;
+    FullViewingKey::from_bytes(&bytes).map_err(|e| BindingError::KeyError(e.to_string()))
+}
+
+/// Encodes a note and its private key as a `vortex-sui-v1-<asset>-<data>` deposit note
+/// string; anyone holding the string can spend the note
+#[uniffi::export]
+pub fn encode_deposit_note(parts: DepositNoteParts) -> Result<String, BindingError> {
+    let deposit = DepositNote::from_parts(&parts).map_err(|e| BindingError::InputError(e.to_string()))?;
+    let encoded = deposit.encode().map_err(|e| BindingError::InputError(e.to_string()))?;
+    Ok(encoded.to_string())
+}
+
+/// Decodes a deposit note string, checking its version and checksum
+#[uniffi::export]
+pub fn decode_deposit_note(note: String) -> Result<DepositNoteParts, BindingError> {
+    let note = Zeroizing::new(note);
+    let deposit = DepositNote::decode(&note).map_err(|e| BindingError::ParseError(e.to_string()))?;
+    Ok(deposit.to_parts())
+}
+
+/// Parts of a shielded address
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct ShieldedAddressParts {
+    /// Decimal note public key, the `out_public_key` of notes paid to the address
+    pub note_public_key: String,
+    /// Incoming viewing public key notes are encrypted to
+    pub viewing_public_key: Vec<u8>,
+}
+
+/// Encodes a note public key and viewing public key as a bech32m shielded address
+#[uniffi::export]
+pub fn encode_shielded_address(note_public_key: String, viewing_public_key: Vec<u8>) -> Result<String, BindingError> {
+    let viewing_public_key = viewing_public_key.try_into().map_err(|v: Vec<u8>| {
+        BindingError::InputError(format!("Viewing public key must be 32 bytes, got {}", v.len()))
+    })?;
+    let address = ShieldedAddress { note_public_key: parse_fr(&note_public_key)?, viewing_public_key };
+    Ok(address.encode())
+}
+
+/// Decodes a shielded address into its keys
+#[uniffi::export]
+pub fn decode_shielded_address(address: String) -> Result<ShieldedAddressParts, BindingError> {
+    let address = ShieldedAddress::decode(&address).map_err(|e| BindingError::ParseError(e.to_string()))?;
+    Ok(ShieldedAddressParts {
+        note_public_key: address.note_public_key.into_bigint().to_string(),
+        viewing_public_key: address.viewing_public_key.to_vec(),
+    })
+}
+
+#[uniffi::export]
+pub fn validate_shielded_address(address: String) -> bool {
+    ShieldedAddress::is_valid(&address)
+}
+
+/// Encodes a payment request as an uppercase QR payload, signed with the login key of
+/// `private_key` when given
+#[uniffi::export]
+pub fn encode_payment_request(request: PaymentRequest, private_key: Option<String>) -> Result<String, BindingError> {
+    let private_key = private_key.as_deref().map(parse_fr).transpose()?.map(Zeroizing::new);
+    payment_request::encode_payment_request(&request, private_key.as_deref()).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Decodes a scanned payment request, verifying its signature and expiry at `now_ms`
+#[uniffi::export]
+pub fn decode_payment_request(payload: String, now_ms: u64) -> Result<ScannedPaymentRequest, BindingError> {
+    payment_request::decode_payment_request(&payload, now_ms).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// Parses a `vortex:` deep link, rejecting it if it expired at `now_ms`
+#[uniffi::export]
+pub fn parse_payment_uri(uri: String, now_ms: u64) -> Result<PaymentRequest, BindingError> {
+    payment_uri::parse_payment_uri(&uri, now_ms).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// Formats a payment request as a canonical `vortex:` URI
+#[uniffi::export]
+pub fn format_payment_uri(request: PaymentRequest) -> Result<String, BindingError> {
+    payment_uri::format_payment_uri(&request).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Canonical form of a `vortex:` URI, for comparing and logging deep links
+#[uniffi::export]
+pub fn canonicalize_payment_uri(uri: String) -> Result<String, BindingError> {
+    payment_uri::canonicalize_payment_uri(&uri).map_err(|e| BindingError::ParseError(e.to_string()))
+}
+
+/// Shielded address of a 32-byte spend seed
+#[uniffi::export]
+pub fn derive_shielded_address(spend_seed: Vec<u8>) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let keys = SpendingKeys::from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(ShieldedAddress::from_viewing_key(&keys.viewing).encode())
+}
+
+/// Exports the entries inside `[from_ms, to_ms)`, signed with the history key of `spend_seed`.
+///
+/// Returns `SignedHistoryExport` JSON.
+#[uniffi::export]
+pub fn export_transaction_history(
+    entries: Vec<HistoryEntry>,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+    spend_seed: Vec<u8>,
+) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let signing_key = history::signing_key_from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    let export = history::export_history(&entries, from_ms, to_ms, &signing_key)
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    serde_json::to_string(&export)
+        .map_err(|e| BindingError::SerializationError(format!("Failed to serialize history export: {}", e)))
+}
+
+/// Hex verifying key that history exports of `spend_seed` are signed with
+#[uniffi::export]
+pub fn history_signer_public_key(spend_seed: Vec<u8>) -> Result<String, BindingError> {
+    let spend_seed = Zeroizing::new(spend_seed);
+    let signing_key = history::signing_key_from_seed(&spend_seed).map_err(|e| BindingError::KeyError(e.to_string()))?;
+    Ok(hex::encode(signing_key.verifying_key().as_bytes()))
+}
+
+/// Checks the signature of a history export; fails with `VerifyError` if it was modified
+#[uniffi::export]
+pub fn verify_transaction_history(export_json: String) -> Result<bool, BindingError> {
+    let export: SignedHistoryExport = serde_json::from_str(&export_json)
+        .map_err(|e| BindingError::ParseError(format!("Failed to parse history export JSON: {}", e)))?;
+    history::verify_history(&export).map_err(|e| BindingError::VerifyError(e.to_string()))?;
+    Ok(true)
+}
+
+/// Computes `public_amount`, the change output and the ext data value for a payment intent
+#[uniffi::export]
+pub fn plan_transaction_amounts(intent: TransferIntent) -> Result<AmountPlan, BindingError> {
+    amount::plan_amounts(&intent).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Decimal field encoding of an amount, as the `ProofInput` amount fields take it
+#[uniffi::export]
+pub fn amount_to_field(amount: u64) -> String {
+    fr_to_string(&amount::amount_to_fr(amount))
+}
+
+/// Amount encoded by a field element, failing when it is out of the circuit's range or does
+/// not fit a u64
+#[uniffi::export]
+pub fn field_to_amount(value: String) -> Result<u64, BindingError> {
+    amount::fr_to_amount(&parse_fr(&value)?).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Decimal `public_amount` of a signed change to the pool's balance: positive for deposits,
+/// negative for withdrawals including their fee
+#[uniffi::export]
+pub fn signed_amount_to_public_amount(amount: i64) -> String {
+    fr_to_string(&amount::signed_to_public_amount(amount.into()))
+}
+
+/// Signed change to the pool's balance encoded by a `public_amount`
+#[uniffi::export]
+pub fn public_amount_to_signed_amount(public_amount: String) -> Result<i64, BindingError> {
+    let amount = amount::public_amount_to_signed(&parse_fr(&public_amount)?).map_err(|e| BindingError::InputError(e.to_string()))?;
+    i64::try_from(amount).map_err(|_| BindingError::InputError(format!("Public amount {} does not fit an i64", amount)))
+}
+
+/// Selects notes for an intent and derives its nullifiers, output commitments, change and
+/// fee without proving
+#[uniffi::export]
+pub fn dry_run(intent: TransactionIntent, private_key: String, vortex: String) -> Result<DryRunReport, BindingError> {
+    let private_key = Zeroizing::new(parse_fr(&private_key)?);
+    dry_run::dry_run(&intent, &private_key, &parse_fr(&vortex)?).map_err(|e| BindingError::InputError(e.to_string()))
+}
+
+/// Builds a `ProofInput` JSON from an all-dummy transaction; see `input_builder`.
+///
+/// Set only what the transaction is about: unused slots become zero-amount notes and the
+/// nullifiers, commitments, root and public amount are derived on `build`.
+#[derive(uniffi::Object)]
+pub struct ProofInputBuilder {
+    builder: Mutex<input_builder::ProofInputBuilder>,
+}
+
+impl ProofInputBuilder {
+    fn update(&self, f: impl FnOnce(input_builder::ProofInputBuilder) -> input_builder::ProofInputBuilder) {
+        let mut builder = self.builder.lock().unwrap();
+        *builder = f(std::mem::take(&mut *builder));
+    }
+}
+
+#[uniffi::export]
+impl ProofInputBuilder {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self { builder: Mutex::new(input_builder::ProofInputBuilder::new()) })
+    }
+
+    pub fn set_vortex(&self, vortex: String) -> Result<(), BindingError> {
+        let vortex = parse_fr(&vortex)?;
+        self.update(|builder| builder.with_vortex(vortex));
+        Ok(())
+    }
+
+    /// Overrides the root derived from the inputs' paths
+    pub fn set_root(&self, root: String) -> Result<(), BindingError> {
+        let root = parse_fr(&root)?;
+        self.update(|builder| builder.with_root(root));
+        Ok(())
+    }
+
+    /// Overrides the public amount derived as outputs minus inputs
+    pub fn set_public_amount(&self, public_amount: String) -> Result<(), BindingError> {
+        let public_amount = parse_fr(&public_amount)?;
+        self.update(|builder| builder.with_public_amount(public_amount));
+        Ok(())
+    }
+
+    /// Spends `utxo` with `private_key`; `merkle_path` is the `[left, right]` pairs of every
+    /// level flattened, `2 * MERKLE_TREE_LEVEL` decimals in all
+    pub fn add_input(&self, utxo: Utxo, private_key: String, merkle_path: Vec<String>) -> Result<(), BindingError> {
+        if !merkle_path.len().is_multiple_of(2) {
+            return Err(BindingError::InputError(format!("Merkle path has an odd number of elements: {}", merkle_path.len())));
+        }
+        let pairs: Vec<[String; 2]> = merkle_path.chunks_exact(2).map(|pair| [pair[0].clone(), pair[1].clone()]).collect();
+        let path = parse_merkle_path_binding(&pairs, FieldParseMode::Strict)?;
+        let private_key = Zeroizing::new(parse_fr(&private_key)?);
+        let input = utxo.spend_input(&private_key, path).map_err(|e| BindingError::InputError(e.to_string()))?;
+        self.update(|builder| builder.with_input(input));
+        Ok(())
+    }
+
+    /// Creates `note` as an output
+    pub fn add_output(&self, note: Note) -> Result<(), BindingError> {
+        if note.legacy_commitment {
+            return Err(BindingError::InputError("Outputs cannot use the legacy commitment".to_string()));
+        }
+        let public_key = parse_fr(&note.public_key)?;
+        let blinding = Zeroizing::new(parse_fr(&note.blinding)?);
+        self.update(|builder| builder.with_output(public_key, note.amount, *blinding));
+        Ok(())
+    }
+
+    pub fn set_account_secret(&self, account_secret: String) -> Result<(), BindingError> {
+        let account_secret = AccountSecret::parse(&account_secret).map_err(|e| BindingError::ParseError(e.to_string()))?;
+        self.update(|builder| builder.with_account_secret(account_secret));
+        Ok(())
+    }
+
+    pub fn set_ext_data_hash(&self, ext_data_hash: String) -> Result<(), BindingError> {
+        let ext_data_hash = parse_fr(&ext_data_hash)?;
+        self.update(|builder| builder.with_ext_data_hash(ext_data_hash));
+        Ok(())
+    }
+
+    pub fn set_session_nonce(&self, session_nonce: String) -> Result<(), BindingError> {
+        validate_session_nonce(&session_nonce).map_err(|e| BindingError::InputError(e.to_string()))?;
+        self.update(|builder| builder.with_session_nonce(session_nonce));
+        Ok(())
+    }
+
+    /// The `ProofInput` JSON; each call draws fresh dummies and shuffles the inputs again
+    pub fn build(&self) -> Result<String, BindingError> {
+        let input = self.builder.lock().unwrap().build(&mut OsRng)
+            .map_err(|e| BindingError::InputError(e.to_string()))?;
+        serde_json::to_string(&input)
+            .map_err(|e| BindingError::SerializationError(format!("Failed to serialize proof input: {}", e)))
+    }
+}
+
+/// Persistent proof job queue; see `queue::ProofQueue`
+#[derive(uniffi::Object)]
+pub struct ProofJobQueue {
+    queue: ProofQueue,
+}
+
+#[uniffi::export]
+impl ProofJobQueue {
+    /// Opens the queue stored in `storage_dir` (app-private storage; job files hold secrets).
+    ///
+    /// `memory_budget_bytes` and the prover config bound how many proofs run at once; the
+    /// proving key is loaded once and shared by all jobs.
+    #[uniffi::constructor]
+    pub fn new(
+        storage_dir: String,
+        proving_key: Vec<u8>,
+        memory_budget_bytes: u64,
+        listener: Option<Arc<dyn ProofJobListener>>,
+    ) -> Result<Arc<Self>, BindingError> {
+        let (key, _) = load_proving_key(&proving_key)?;
+        let executor: ProofExecutor = Arc::new(move |input_json: &str| {
+            let input_json = Zeroizing::new(input_json.to_string());
+            let mut rng = proof_rng().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            prove_with_key(&key, &input_json, &mut rng).map_err(|e| anyhow::anyhow!(e.to_string()))
+        });
+
+        let profile_budget = config::prover_config().max_parallel_proofs() as u64 * PROOF_MEMORY_ESTIMATE_BYTES;
+        let queue = ProofQueue::open(&storage_dir, executor, memory_budget_bytes.min(profile_budget), listener)
+            .map_err(|e| BindingError::InternalError(e.to_string()))?;
+        Ok(Arc::new(Self { queue }))
+    }
+
+    /// Persists a `ProofInput` JSON job and returns its id
+    pub fn enqueue(&self, input_json: String) -> Result<String, BindingError> {
+        serde_json::from_str::<ProofInput>(&input_json)
+            .map_err(|e| BindingError::ParseError(format!("Failed to parse input JSON: {}", e)))?;
+        Ok(self.queue.enqueue(input_json)?)
+    }
+
+    pub fn status(&self, job_id: String) -> Option<ProofJobStatus> {
+        self.queue.status(&job_id)
+    }
+
+    /// `ProofOutput` JSON of a completed job
+    pub fn output(&self, job_id: String) -> Option<String> {
+        self.queue.output(&job_id)
+    }
+
+    /// Error message of a failed job
+    pub fn error(&self, job_id: String) -> Option<String> {
+        self.queue.error(&job_id)
+    }
+
+    /// Ids of queued jobs in execution order
+    pub fn queued(&self) -> Vec<String> {
+        self.queue.queued()
+    }
+
+    /// Deletes a finished or queued job and its stored input
+    pub fn remove(&self, job_id: String) -> Result<bool, BindingError> {
+        Ok(self.queue.remove(&job_id)?)
+    }
+
+    /// Runs queued jobs until none are left and returns how many ran; blocks the caller
+    pub fn run(&self) -> Result<u32, BindingError> {
+        Ok(self.queue.run()?)
+    }
+}
+
+/// Builds and validates a circuit from a parsed `ProofInput`.
+///
+/// Public so that the fuzz targets can exercise the same parsing path as `prove`.
+pub fn create_circuit_from_input(input: &ProofInput) -> Result<TransactionCircuit, BindingError> {
+    let mode = FieldParseMode::from_reduce(input.reduce_field_elements);
+    let parse_fr = |s: &str| parse_fr_with(s, mode);
 
-fn create_circuit_from_input(input: &ProofInput) -> Result<TransactionCircuit, BindingError> {
     let vortex = parse_fr(&input.vortex)?;
     let root = parse_fr(&input.root)?;
     let public_amount = parse_fr(&input.public_amount)?;
@@ -244,44 +2037,60 @@ fn create_circuit_from_input(input: &ProofInput) -> Result<TransactionCircuit, B
     let output_commitment_0 = parse_fr(&input.output_commitment_0)?;
     let output_commitment_1 = parse_fr(&input.output_commitment_1)?;
     let hashed_account_secret = parse_fr(&input.hashed_account_secret)?;
-    let account_secret = parse_fr(&input.account_secret)?;
+    let output_ciphertext_hashes = [parse_fr(&input.output_ciphertext_hash_0)?, parse_fr(&input.output_ciphertext_hash_1)?];
+    let authority_root = parse_fr(&input.authority_root)?;
+    let ext_data_hash = parse_fr(&input.ext_data_hash)?;
+    // Private values are wiped on every return path, including parse errors
+    let account_secret = Zeroizing::new(parse_fr(&input.account_secret)?);
 
-    let in_private_keys = [
+    let in_private_keys = Zeroizing::new([
         parse_fr(&input.in_private_key_0)?,
         parse_fr(&input.in_private_key_1)?,
-    ];
-    let in_amounts = [
+    ]);
+    let in_amounts = Zeroizing::new([
         parse_fr(&input.in_amount_0)?,
         parse_fr(&input.in_amount_1)?,
-    ];
-    let in_blindings = [
+    ]);
+    let in_blindings = Zeroizing::new([
         parse_fr(&input.in_blinding_0)?,
         parse_fr(&input.in_blinding_1)?,
-    ];
-    let in_path_indices = [
+    ]);
+    let in_path_indices = Zeroizing::new([
         parse_fr(&input.in_path_index_0)?,
         parse_fr(&input.in_path_index_1)?,
-    ];
+    ]);
+    let in_rotated_private_keys = Zeroizing::new([
+        input.in_rotated_private_key_0.as_deref().map(parse_fr).transpose()?,
+        input.in_rotated_private_key_1.as_deref().map(parse_fr).transpose()?,
+    ]);
 
-    let merkle_paths = [
-        parse_merkle_path_binding(&input.merkle_path_0)?,
-        parse_merkle_path_binding(&input.merkle_path_1)?,
+    let merkle_paths = Zeroizing::new([
+        parse_merkle_path_binding(&input.merkle_path_0, mode)?,
+        parse_merkle_path_binding(&input.merkle_path_1, mode)?,
+    ]);
+    let in_authority_indices = [
+        parse_fr(&input.in_authority_index_0)?,
+        parse_fr(&input.in_authority_index_1)?,
     ];
+    let authority_paths = Zeroizing::new([
+        parse_authority_path_binding(&input.authority_path_0, mode)?,
+        parse_authority_path_binding(&input.authority_path_1, mode)?,
+    ]);
 
-    let out_public_keys = [
+    let out_public_keys = Zeroizing::new([
         parse_fr(&input.out_public_key_0)?,
         parse_fr(&input.out_public_key_1)?,
-    ];
-    let out_amounts = [
+    ]);
+    let out_amounts = Zeroizing::new([
         parse_fr(&input.out_amount_0)?,
         parse_fr(&input.out_amount_1)?,
-    ];
-    let out_blindings = [
+    ]);
+    let out_blindings = Zeroizing::new([
         parse_fr(&input.out_blinding_0)?,
         parse_fr(&input.out_blinding_1)?,
-    ];
+    ]);
 
-    TransactionCircuit::new(
+    let circuit = TransactionCircuit::new(
         vortex,
         root,
         public_amount,
@@ -290,32 +2099,69 @@ fn create_circuit_from_input(input: &ProofInput) -> Result<TransactionCircuit, B
         output_commitment_0,
         output_commitment_1,
         hashed_account_secret,
-        account_secret,
-        in_private_keys,
-        in_amounts,
-        in_blindings,
-        in_path_indices,
-        merkle_paths,
-        out_public_keys,
-        out_amounts,
-        out_blindings,
-    ).map_err(|e| BindingError::InternalError(e.to_string()))
-}
-
-fn parse_merkle_path_binding(path_data: &[[String; 2]]) -> Result<Path<MERKLE_TREE_LEVEL>, BindingError> {
-    if path_data.len() != MERKLE_TREE_LEVEL {
+        *account_secret,
+        *in_private_keys,
+        *in_amounts,
+        *in_blindings,
+        *in_path_indices,
+        *merkle_paths,
+        *out_public_keys,
+        *out_amounts,
+        *out_blindings,
+    )
+    .and_then(|circuit| circuit.with_dummy_inputs([input.dummy_input_0, input.dummy_input_1]))
+    .map_err(|e| BindingError::InternalError(e.to_string()))?
+    .with_ext_data_hash(ext_data_hash)
+    .with_legacy_commitments([input.in_legacy_commitment_0, input.in_legacy_commitment_1]);
+    let circuit = if input.without_account_secret { circuit.without_account_secret() } else { circuit };
+    let circuit = if input.bind_output_ciphertexts { circuit.with_ciphertext_binding(output_ciphertext_hashes) } else { circuit };
+    let circuit = if input.key_rotation { circuit.with_key_rotation(authority_root, *in_rotated_private_keys, in_authority_indices, *authority_paths) } else { circuit };
+    let circuit = if input.mixed_era_inputs { circuit.with_mixed_era_inputs() } else { circuit };
+
+    circuit
+        .check_public_inputs()
+        .map_err(|e| BindingError::InputError(e.to_string()))?;
+
+    Ok(circuit)
+}
+
+/// Parses `[left, right]` string pairs into a Merkle path of `MERKLE_TREE_LEVEL` levels
+pub fn parse_merkle_path_binding(
+    path_data: &[[String; 2]],
+    mode: FieldParseMode,
+) -> Result<Path<MERKLE_TREE_LEVEL>, BindingError> {
+    parse_path_binding(path_data, mode)
+}
+
+/// Parses an authority path of `AUTHORITY_TREE_LEVEL` levels; empty when the circuit has no
+/// key rotation
+fn parse_authority_path_binding(
+    path_data: &[[String; 2]],
+    mode: FieldParseMode,
+) -> Result<Path<AUTHORITY_TREE_LEVEL>, BindingError> {
+    if path_data.is_empty() {
+        return Ok(Path::empty());
+    }
+    parse_path_binding(path_data, mode)
+}
+
+fn parse_path_binding<const N: usize>(
+    path_data: &[[String; 2]],
+    mode: FieldParseMode,
+) -> Result<Path<N>, BindingError> {
+    if path_data.len() != N {
         return Err(BindingError::InputError(format!(
             "Invalid Merkle path length: expected {}, got {}",
-            MERKLE_TREE_LEVEL,
+            N,
             path_data.len()
         )));
     }
 
-    let mut path = [(Fr::from(0u64), Fr::from(0u64)); MERKLE_TREE_LEVEL];
+    let mut path = [(Fr::from(0u64), Fr::from(0u64)); N];
 
     for (i, pair) in path_data.iter().enumerate() {
-        let left = parse_fr(&pair[0])?;
-        let right = parse_fr(&pair[1])?;
+        let left = parse_fr_with(&pair[0], mode)?;
+        let right = parse_fr_with(&pair[1], mode)?;
         path[i] = (left, right);
     }
 
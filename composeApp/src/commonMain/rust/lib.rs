@@ -1,8 +1,12 @@
-pub mod circuit;
-pub mod constants;
-pub mod merkle_tree;
-pub mod poseidon_opt;
-pub mod wasm;
+//! uniffi bindings for the Android and iOS apps.
+//!
+//! A thin layer over `vortex-core`, which is re-exported whole so the CLI tools and
+//! integration tests reach the cryptography through `vortex::circuit`, `vortex::note` and
+//! so on, as before the split.
+
+pub use vortex_core::*;
+
 pub mod bindings;
+pub mod queue;
 
 uniffi::setup_scaffolding!();
@@ -0,0 +1,370 @@
+//! Persistent proof job queue.
+//!
+//! Jobs are stored one JSON file per job under the queue directory, so a queue reopened after
+//! an app restart picks up where it stopped; jobs that were running when the process died are
+//! queued again. `run` executes queued jobs in FIFO order, as many at a time as the memory
+//! budget allows, and reports every status change to the listener.
+//!
+//! Job files contain the raw `ProofInput` JSON, secrets included, until the job is removed.
+//! The directory must be app-private storage.
+
+use anyhow::{anyhow, Context, Result};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Rough peak memory of one proof of the transaction circuit, proving key included
+pub const PROOF_MEMORY_ESTIMATE_BYTES: u64 = 256 * 1024 * 1024;
+
+const JOB_FILE_EXTENSION: &str = "job.json";
+
+/// Turns a `ProofInput` JSON string into a `ProofOutput` JSON string
+pub type ProofExecutor = Arc<dyn Fn(&str) -> Result<String> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum ProofJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Receives job status changes; called from the thread executing the job
+#[uniffi::export(with_foreign)]
+pub trait ProofJobListener: Send + Sync {
+    fn on_status_changed(&self, job_id: String, status: ProofJobStatus);
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredJob {
+    id: String,
+    seq: u64,
+    input_json: String,
+    status: ProofJobStatus,
+    output_json: Option<String>,
+    error: Option<String>,
+}
+
+/// The input JSON carries the spend credentials, so it is left out
+impl std::fmt::Debug for StoredJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredJob")
+            .field("id", &self.id)
+            .field("seq", &self.seq)
+            .field("input_json", &"<redacted>")
+            .field("status", &self.status)
+            .field("output_json", &self.output_json)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+pub struct ProofQueue {
+    dir: PathBuf,
+    jobs: Mutex<HashMap<String, StoredJob>>,
+    executor: ProofExecutor,
+    max_parallel: usize,
+    listener: Option<Arc<dyn ProofJobListener>>,
+    /// Serializes `run` calls so a job is never picked twice
+    run_lock: Mutex<()>,
+}
+
+impl ProofQueue {
+    /// Opens the queue stored in `dir`, creating the directory if needed.
+    ///
+    /// `memory_budget_bytes` bounds how many proofs `run` executes at once (at least one).
+    pub fn open(
+        dir: impl AsRef<Path>,
+        executor: ProofExecutor,
+        memory_budget_bytes: u64,
+        listener: Option<Arc<dyn ProofJobListener>>,
+    ) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create queue directory {}", dir.display()))?;
+
+        let mut jobs = HashMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if !path.to_string_lossy().ends_with(JOB_FILE_EXTENSION) {
+                continue;
+            }
+            let bytes = fs::read(&path)?;
+            let mut job: StoredJob = serde_json::from_slice(&bytes)
+                .with_context(|| format!("Corrupt job file {}", path.display()))?;
+            if job.status == ProofJobStatus::Running {
+                job.status = ProofJobStatus::Queued;
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+
+        Ok(Self {
+            dir,
+            jobs: Mutex::new(jobs),
+            executor,
+            max_parallel: (memory_budget_bytes / PROOF_MEMORY_ESTIMATE_BYTES).max(1) as usize,
+            listener,
+            run_lock: Mutex::new(()),
+        })
+    }
+
+    /// Number of proofs `run` executes concurrently
+    pub fn max_parallel(&self) -> usize {
+        self.max_parallel
+    }
+
+    /// Persists a new job and returns its id
+    pub fn enqueue(&self, input_json: String) -> Result<String> {
+        let mut id_bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut id_bytes);
+
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = StoredJob {
+            id: hex::encode(id_bytes),
+            seq: jobs.values().map(|job| job.seq + 1).max().unwrap_or(0),
+            input_json,
+            status: ProofJobStatus::Queued,
+            output_json: None,
+            error: None,
+        };
+        self.persist(&job)?;
+        let id = job.id.clone();
+        jobs.insert(id.clone(), job);
+        drop(jobs);
+
+        self.notify(&id, ProofJobStatus::Queued);
+        Ok(id)
+    }
+
+    pub fn status(&self, job_id: &str) -> Option<ProofJobStatus> {
+        self.jobs.lock().unwrap().get(job_id).map(|job| job.status)
+    }
+
+    /// `ProofOutput` JSON of a completed job
+    pub fn output(&self, job_id: &str) -> Option<String> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .and_then(|job| job.output_json.clone())
+    }
+
+    /// Error message of a failed job
+    pub fn error(&self, job_id: &str) -> Option<String> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .and_then(|job| job.error.clone())
+    }
+
+    /// Ids of queued jobs in execution order
+    pub fn queued(&self) -> Vec<String> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut queued: Vec<&StoredJob> = jobs
+            .values()
+            .filter(|job| job.status == ProofJobStatus::Queued)
+            .collect();
+        queued.sort_by_key(|job| job.seq);
+        queued.iter().map(|job| job.id.clone()).collect()
+    }
+
+    /// Deletes a job that is not running, returning whether it existed
+    pub fn remove(&self, job_id: &str) -> Result<bool> {
+        let mut jobs = self.jobs.lock().unwrap();
+        match jobs.get(job_id).map(|job| job.status) {
+            None => Ok(false),
+            Some(ProofJobStatus::Running) => Err(anyhow!("Job {} is running", job_id)),
+            Some(_) => {
+                let path = self.job_path(job_id);
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+                jobs.remove(job_id);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Executes queued jobs until none are left, returning how many ran.
+    ///
+    /// Blocks the calling thread; hosts call it from a background worker.
+    pub fn run(&self) -> Result<u32> {
+        let _running = self.run_lock.lock().unwrap();
+        let mut processed = 0u32;
+
+        loop {
+            let batch: Vec<String> = self.queued().into_iter().take(self.max_parallel).collect();
+            if batch.is_empty() {
+                return Ok(processed);
+            }
+
+            for id in &batch {
+                self.update(id, ProofJobStatus::Running, None, None)?;
+            }
+
+            let results: Vec<Result<()>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|id| scope.spawn(move || self.execute(id)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|_| Err(anyhow!("Proof worker panicked")))
+                    })
+                    .collect()
+            });
+            for result in results {
+                result?;
+            }
+
+            processed += batch.len() as u32;
+        }
+    }
+
+    fn execute(&self, id: &str) -> Result<()> {
+        let input_json = match self.jobs.lock().unwrap().get(id) {
+            Some(job) => job.input_json.clone(),
+            None => return Ok(()),
+        };
+
+        match (self.executor)(&input_json) {
+            Ok(output) => self.update(id, ProofJobStatus::Completed, Some(output), None),
+            Err(e) => self.update(id, ProofJobStatus::Failed, None, Some(e.to_string())),
+        }
+    }
+
+    fn update(
+        &self,
+        id: &str,
+        status: ProofJobStatus,
+        output_json: Option<String>,
+        error: Option<String>,
+    ) -> Result<()> {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs
+                .get_mut(id)
+                .ok_or_else(|| anyhow!("Unknown job {}", id))?;
+            job.status = status;
+            job.output_json = output_json;
+            job.error = error;
+            self.persist(job)?;
+        }
+
+        self.notify(id, status);
+        Ok(())
+    }
+
+    fn notify(&self, id: &str, status: ProofJobStatus) {
+        if let Some(listener) = &self.listener {
+            listener.on_status_changed(id.to_string(), status);
+        }
+    }
+
+    /// Writes to a temporary file first so a crash never leaves a truncated job
+    fn persist(&self, job: &StoredJob) -> Result<()> {
+        let path = self.job_path(&job.id);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(job)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    fn job_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, JOB_FILE_EXTENSION))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recorder(Mutex<Vec<(String, ProofJobStatus)>>);
+
+    impl ProofJobListener for Recorder {
+        fn on_status_changed(&self, job_id: String, status: ProofJobStatus) {
+            self.0.lock().unwrap().push((job_id, status));
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("vortex-queue-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn echo_executor() -> ProofExecutor {
+        Arc::new(|input: &str| {
+            if input == "bad" {
+                Err(anyhow!("invalid input"))
+            } else {
+                Ok(format!("proof of {}", input))
+            }
+        })
+    }
+
+    #[test]
+    fn test_run_executes_jobs_and_reports_status() {
+        let dir = temp_dir("run");
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        let queue = ProofQueue::open(
+            &dir,
+            echo_executor(),
+            2 * PROOF_MEMORY_ESTIMATE_BYTES,
+            Some(recorder.clone()),
+        )
+        .unwrap();
+        assert_eq!(queue.max_parallel(), 2);
+
+        let ok = queue.enqueue("a".to_string()).unwrap();
+        let bad = queue.enqueue("bad".to_string()).unwrap();
+        let later = queue.enqueue("c".to_string()).unwrap();
+        assert_eq!(queue.queued(), vec![ok.clone(), bad.clone(), later.clone()]);
+
+        assert_eq!(queue.run().unwrap(), 3);
+        assert_eq!(queue.output(&ok).as_deref(), Some("proof of a"));
+        assert_eq!(queue.status(&bad), Some(ProofJobStatus::Failed));
+        assert_eq!(queue.error(&bad).as_deref(), Some("invalid input"));
+        assert_eq!(queue.status(&later), Some(ProofJobStatus::Completed));
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 9);
+        assert!(events.contains(&(later, ProofJobStatus::Running)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reopened_queue_requeues_interrupted_jobs() {
+        let dir = temp_dir("reopen");
+        let queue = ProofQueue::open(&dir, echo_executor(), 0, None).unwrap();
+        let interrupted = queue.enqueue("a".to_string()).unwrap();
+        queue
+            .update(&interrupted, ProofJobStatus::Running, None, None)
+            .unwrap();
+        let removed = queue.enqueue("b".to_string()).unwrap();
+        assert!(queue.remove(&removed).unwrap());
+        drop(queue);
+
+        let reopened = ProofQueue::open(&dir, echo_executor(), 0, None).unwrap();
+        assert_eq!(reopened.queued(), vec![interrupted.clone()]);
+        assert_eq!(reopened.status(&removed), None);
+        assert_eq!(reopened.run().unwrap(), 1);
+        assert_eq!(
+            reopened.status(&interrupted),
+            Some(ProofJobStatus::Completed)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
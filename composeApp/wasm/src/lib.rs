@@ -0,0 +1,3062 @@
+//! wasm-bindgen exports for the web wallet.
+//!
+//! A thin layer over `vortex-core`: every export parses its JSON or hex arguments, calls
+//! into the core and maps failures to a `VortexError` carrying the same code as the uniffi
+//! bindings.
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, ProvingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand_core::OsRng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use vortex_core::{
+    account::{self, AccountSecret},
+    address::ShieldedAddress,
+    aggregation::{self, AggregateProof, AggregationSrs, AggregationVerifierKey},
+    amount::{self, TransferIntent},
+    auth,
+    backup::{self, WalletSnapshot},
+    circuit::TransactionCircuit,
+    config::{self, ProverConfig},
+    consolidation::{self, ConsolidationPlan, ConsolidationPolicy},
+    constants::{
+        AUTHORITY_TREE_LEVEL, MAX_AMOUNT_BITS, MERKLE_TREE_LEVEL, N_INS, N_OUTS, ZERO_VALUE,
+    },
+    cross_pool::{CrossPoolEvent, CrossPoolTransfer, PoolRef},
+    delegate,
+    deposit_note::{DepositNote, DepositNoteParts},
+    dry_run::{self, TransactionIntent},
+    error_code::VortexErrorCode,
+    estimate::{self, CircuitVariant, DeviceCalibration},
+    field::{self, FieldParseMode},
+    history::{self, HistoryEntry, SignedHistoryExport},
+    interop::{validate_session_nonce, ProofInput, ProofOutput},
+    merkle_tree::{self, Path, SparseMerkleTree},
+    metrics, mul_kernel,
+    multi_send::{self, Recipient, SendPlan},
+    note::{self, Note, Utxo},
+    onchain_vk::{self, OnChainVerifyingKey},
+    padding, payment_request, payment_uri, poseidon_opt,
+    prover::{finalize_proof_with_domain, prepare_circuit, ProvingDomain},
+    receipt::{self, PaymentReceipt},
+    relayer::{self, ExtData, RelayerFeeQuote},
+    relayer_fee, replay, rotation,
+    selftest::{self, KeyManifest},
+    sui_tx,
+    telemetry::{self, Phase, PhaseTimer},
+    trace_span,
+    validation::{self, PointValidation},
+    verifier,
+    viewing::{
+        self, DecryptedNote, FullViewingKey, NotePlaintext, OutputEvent, SpendingKeys,
+        ViewingKeyExport,
+    },
+    zklogin::ZkLoginCredentials,
+};
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+/// Message of the last panic, kept so the host can report it after the instance traps
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Error thrown by every fallible export.
+///
+/// Carries the same stable code as the uniffi `BindingError`, so web and mobile clients map
+/// failures to UX the same way. `toString()` returns the message alone, as the plain string
+/// errors thrown before did.
+///
+/// ```javascript
+/// try {
+///   prove(inputJson, provingKeyHex);
+/// } catch (e) {
+///   if (e.category === "key") refetchKeys();
+///   report(e.code, e.message);
+/// }
+/// ```
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VortexError {
+    code: VortexErrorCode,
+    message: String,
+}
+
+impl VortexError {
+    pub fn new(code: VortexErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn error_code(&self) -> VortexErrorCode {
+        self.code
+    }
+}
+
+#[wasm_bindgen]
+impl VortexError {
+    /// Stable numeric code, e.g. 2001 for a bad key
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> u32 {
+        self.code.code()
+    }
+
+    /// `input`, `key`, `proving`, `verification` or `internal`
+    #[wasm_bindgen(getter)]
+    pub fn category(&self) -> String {
+        serde_json::to_value(self.code.category())
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.message.clone()
+    }
+}
+
+// Set panic hook for better error messages in browser
+#[wasm_bindgen(start)]
+pub fn main() {
+    std::panic::set_hook(Box::new(|info| {
+        *LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()) = Some(info.to_string());
+        console_error_panic_hook::hook(info);
+    }));
+}
+
+/// Returns and clears the message of the last panic.
+///
+/// A panic aborts the wasm instance, so every later call on it fails with a bare
+/// `RuntimeError: unreachable`. A service worker can call this on the trapped instance to
+/// log why before replacing it with a fresh one.
+#[wasm_bindgen(js_name = takeLastPanic)]
+pub fn take_last_panic() -> Option<String> {
+    LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// Absorbs cold-start costs ahead of the first hash or proof
+///
+/// Parses the Poseidon constants, benchmarks the witness-map multiplication kernels and,
+/// when `proving_key_hex` is given, deserializes and validates it and computes its
+/// evaluation domain, kept for the next `prove` with the same key unless the memory profile
+/// is `Low`.
+///
+/// # Errors
+/// Returns error if the proving key fails to decode or validate
+#[wasm_bindgen(js_name = warmUp)]
+pub fn warm_up(proving_key_hex: Option<String>) -> Result<(), VortexError> {
+    poseidon_opt::warm_up();
+    mul_kernel::report();
+    if let Some(proving_key_hex) = proving_key_hex {
+        load_passed_key(&proving_key_hex, PointValidation::Checked)?;
+    }
+    Ok(())
+}
+
+/// Protocol constants, so clients validate inputs against these values rather than copies
+///
+/// # Returns
+/// JSON `{ merkleTreeLevel, nIns, nOuts, maxAmountBits, zeroValue, fieldModulus,
+/// publicInputLayout }`. `fieldModulus` is the decimal BN254 scalar field modulus and
+/// `publicInputLayout` maps each circuit variant to its public input names, in proof order
+#[wasm_bindgen(js_name = getConstants)]
+pub fn get_constants() -> String {
+    let layout: serde_json::Map<String, serde_json::Value> = CircuitVariant::ALL
+        .iter()
+        .map(|variant| {
+            let name = serde_json::to_value(variant)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            (name, variant.empty_circuit().public_input_names().into())
+        })
+        .collect();
+    serde_json::json!({
+        "merkleTreeLevel": MERKLE_TREE_LEVEL,
+        "nIns": N_INS,
+        "nOuts": N_OUTS,
+        "maxAmountBits": MAX_AMOUNT_BITS,
+        "zeroValue": ZERO_VALUE,
+        "fieldModulus": Fr::MODULUS.to_string(),
+        "publicInputLayout": layout,
+    })
+    .to_string()
+}
+
+/// Generates a zero-knowledge proof for a privacy-preserving transaction
+///
+/// # Arguments
+/// * `input_json` - JSON string containing all circuit inputs
+/// * `proving_key_hex` - Hex-encoded proving key (generated during setup)
+///
+/// # Returns
+/// JSON string containing the proof and public inputs
+///
+/// # Example
+/// ```javascript
+/// const input = {
+///   root: "12345...",
+///   publicAmount: "1000",
+///   // ... other inputs
+/// };
+/// const proof = prove(JSON.stringify(input), provingKeyHex);
+/// const { proofA, proofB, proofC, publicInputs } = JSON.parse(proof);
+/// ```
+#[wasm_bindgen]
+pub fn prove(input_json: &str, proving_key_hex: &str) -> Result<String, VortexError> {
+    prove_with_key_validation(input_json, proving_key_hex, true)
+}
+
+/// Same as `prove`, with control over proving-key point validation
+///
+/// # Arguments
+/// * `validate_key` - `false` skips curve/subgroup checks on the proving key; only do this
+///   for a key bundled with the app, never for one fetched at runtime
+#[wasm_bindgen(js_name = proveWithKeyValidation)]
+pub fn prove_with_key_validation(
+    input_json: &str,
+    proving_key_hex: &str,
+    validate_key: bool,
+) -> Result<String, VortexError> {
+    // Turns a panic into an error where unwinding is available; on wasm32 a panic aborts
+    // and is recorded by the hook installed in `main` instead
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        prove_unchecked(input_json, proving_key_hex, validate_key)
+    }))
+    .unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(VortexError::new(
+            VortexErrorCode::Internal,
+            format!("Prover panicked: {}", message),
+        ))
+    });
+    if let Err(e) = &result {
+        metrics::proof_failed();
+        replay::record_failure(input_json, &e.message);
+    }
+    result
+}
+
+fn prove_unchecked(
+    input_json: &str,
+    proving_key_hex: &str,
+    validate_key: bool,
+) -> Result<String, VortexError> {
+    trace_span!("prove");
+    let mut timer = PhaseTimer::start();
+
+    // Parse input
+    let input: ProofInput = {
+        trace_span!("parse_input", len = input_json.len());
+        serde_json::from_str(input_json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse input JSON: {}", e),
+            )
+        })?
+    };
+
+    if let Some(nonce) = &input.session_nonce {
+        validate_session_nonce(nonce)
+            .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    }
+
+    // Parse proving key
+    let key_validation = if validate_key {
+        PointValidation::Checked
+    } else {
+        PointValidation::Unchecked
+    };
+    let (pk, domain) = {
+        trace_span!("load_proving_key", validate_key);
+        load_passed_key(proving_key_hex, key_validation)?
+    };
+
+    // Convert input strings to field elements
+    let mode = FieldParseMode::from_reduce(input.reduce_field_elements);
+    let parse_field_element = |s: &str| parse_field_element_with(s, mode);
+
+    let vortex = parse_field_element(&input.vortex)?;
+    let root = parse_field_element(&input.root)?;
+    let public_amount = parse_field_element(&input.public_amount)?;
+    let input_nullifier_0 = parse_field_element(&input.input_nullifier_0)?;
+    let input_nullifier_1 = parse_field_element(&input.input_nullifier_1)?;
+    let output_commitment_0 = parse_field_element(&input.output_commitment_0)?;
+    let output_commitment_1 = parse_field_element(&input.output_commitment_1)?;
+    let hashed_account_secret = parse_field_element(&input.hashed_account_secret)?;
+    let output_ciphertext_hashes = [
+        parse_field_element(&input.output_ciphertext_hash_0)?,
+        parse_field_element(&input.output_ciphertext_hash_1)?,
+    ];
+    let authority_root = parse_field_element(&input.authority_root)?;
+    let ext_data_hash = parse_field_element(&input.ext_data_hash)?;
+
+    // Private values are wrapped so they are wiped on every return path
+    let account_secret = Zeroizing::new(parse_field_element(&input.account_secret)?);
+
+    let in_private_keys = Zeroizing::new([
+        parse_field_element(&input.in_private_key_0)?,
+        parse_field_element(&input.in_private_key_1)?,
+    ]);
+
+    let in_amounts = Zeroizing::new([
+        parse_field_element(&input.in_amount_0)?,
+        parse_field_element(&input.in_amount_1)?,
+    ]);
+
+    let in_blindings = Zeroizing::new([
+        parse_field_element(&input.in_blinding_0)?,
+        parse_field_element(&input.in_blinding_1)?,
+    ]);
+
+    let in_path_indices = Zeroizing::new([
+        parse_field_element(&input.in_path_index_0)?,
+        parse_field_element(&input.in_path_index_1)?,
+    ]);
+
+    let in_rotated_private_keys = Zeroizing::new([
+        input
+            .in_rotated_private_key_0
+            .as_deref()
+            .map(parse_field_element)
+            .transpose()?,
+        input
+            .in_rotated_private_key_1
+            .as_deref()
+            .map(parse_field_element)
+            .transpose()?,
+    ]);
+
+    // Parse Merkle paths
+    let merkle_paths = Zeroizing::new([
+        parse_merkle_path(&input.merkle_path_0, mode)?,
+        parse_merkle_path(&input.merkle_path_1, mode)?,
+    ]);
+    let in_authority_indices = [
+        parse_field_element(&input.in_authority_index_0)?,
+        parse_field_element(&input.in_authority_index_1)?,
+    ];
+    let authority_paths = Zeroizing::new([
+        parse_authority_path(&input.authority_path_0, mode)?,
+        parse_authority_path(&input.authority_path_1, mode)?,
+    ]);
+
+    let out_public_keys = Zeroizing::new([
+        parse_field_element(&input.out_public_key_0)?,
+        parse_field_element(&input.out_public_key_1)?,
+    ]);
+
+    let out_amounts = Zeroizing::new([
+        parse_field_element(&input.out_amount_0)?,
+        parse_field_element(&input.out_amount_1)?,
+    ]);
+
+    let out_blindings = Zeroizing::new([
+        parse_field_element(&input.out_blinding_0)?,
+        parse_field_element(&input.out_blinding_1)?,
+    ]);
+
+    // Create circuit
+    let circuit = TransactionCircuit::new(
+        vortex,
+        root,
+        public_amount,
+        input_nullifier_0,
+        input_nullifier_1,
+        output_commitment_0,
+        output_commitment_1,
+        hashed_account_secret,
+        *account_secret,
+        *in_private_keys,
+        *in_amounts,
+        *in_blindings,
+        *in_path_indices,
+        *merkle_paths,
+        *out_public_keys,
+        *out_amounts,
+        *out_blindings,
+    )
+    .and_then(|circuit| circuit.with_dummy_inputs([input.dummy_input_0, input.dummy_input_1]))
+    .map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Input,
+            format!("Failed to create circuit: {}", e),
+        )
+    })?
+    .with_ext_data_hash(ext_data_hash)
+    .with_legacy_commitments([input.in_legacy_commitment_0, input.in_legacy_commitment_1]);
+    let circuit = if input.without_account_secret {
+        circuit.without_account_secret()
+    } else {
+        circuit
+    };
+    let circuit = if input.bind_output_ciphertexts {
+        circuit.with_ciphertext_binding(output_ciphertext_hashes)
+    } else {
+        circuit
+    };
+    let circuit = if input.key_rotation {
+        circuit.with_key_rotation(
+            authority_root,
+            *in_rotated_private_keys,
+            in_authority_indices,
+            *authority_paths,
+        )
+    } else {
+        circuit
+    };
+    let circuit = if input.mixed_era_inputs {
+        circuit.with_mixed_era_inputs()
+    } else {
+        circuit
+    };
+
+    // Catch inconsistent public inputs before paying for synthesis and proving
+    circuit
+        .check_public_inputs()
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, format!("Invalid input: {}", e)))?;
+
+    timer.end_phase(Phase::Parse);
+
+    // Groth16 blinding randomness must be fresh for every proof
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    let mut rng = ChaCha20Rng::from_rng(OsRng).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Internal,
+            format!("Failed to seed proof randomness: {}", e),
+        )
+    })?;
+
+    // Extract public inputs BEFORE proving (circuit is consumed by prove())
+    // The order MUST match the order in which FpVar::new_input() is called in generate_constraints()
+    // This is: vortex, root, public_amount, input_nullifier_0, input_nullifier_1,
+    //          output_commitment_0, output_commitment_1, hashed_account_secret,
+    //          [output_ciphertext_hash_0, output_ciphertext_hash_1,] [authority_root,]
+    //          ext_data_hash
+    let public_inputs_field = circuit.get_public_inputs();
+    let public_inputs_serialized = circuit.get_public_inputs_serialized().map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize public inputs: {}", e),
+        )
+    })?;
+
+    timer.end_phase(Phase::Witness);
+
+    // Generate proof - Groth16 will internally call generate_constraints() and extract public inputs
+    // It uses the same public inputs we extracted above (in the same order)
+    // Note: Groth16's prove() function extracts public inputs from the constraint system
+    // in the order they were allocated via FpVar::new_input(). Our get_public_inputs()
+    // should match this order exactly.
+    //
+    // IMPORTANT: Groth16 extracts public inputs from the constraint system during prove().
+    // The public inputs are stored in the constraint system in the order they were allocated.
+    // We extract them manually using get_public_inputs() which should match exactly.
+    let proof = prepare_circuit(circuit, &mut timer)
+        .and_then(|prepared| {
+            finalize_proof_with_domain(&pk, &domain, &prepared, &mut rng, &mut timer)
+        })
+        .map_err(|e| match e {
+            SynthesisError::Unsatisfiable => VortexError::new(
+                VortexErrorCode::Constraint,
+                "Constraints are not satisfied by the input".to_string(),
+            ),
+            e => VortexError::new(
+                VortexErrorCode::Proof,
+                format!("Failed to generate proof: {}", e),
+            ),
+        })?;
+
+    // Serialize proof components (compressed format)
+    let mut proof_a_bytes = Vec::new();
+    proof
+        .a
+        .serialize_compressed(&mut proof_a_bytes)
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize proof.a: {}", e),
+            )
+        })?;
+
+    let mut proof_b_bytes = Vec::new();
+    proof
+        .b
+        .serialize_compressed(&mut proof_b_bytes)
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize proof.b: {}", e),
+            )
+        })?;
+
+    let mut proof_c_bytes = Vec::new();
+    proof
+        .c
+        .serialize_compressed(&mut proof_c_bytes)
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize proof.c: {}", e),
+            )
+        })?;
+
+    // Serialize proof
+    let mut proof_serialized = Vec::new();
+    proof
+        .serialize_compressed(&mut proof_serialized)
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize proof: {}", e),
+            )
+        })?;
+
+    // Convert public inputs to strings for JSON output
+    // Use the field's underlying representation for reliable serialization/deserialization
+    // This ensures the string can be parsed back correctly by parse_field_element()
+    let public_inputs: Vec<String> = public_inputs_field
+        .iter()
+        .map(|input| {
+            // Convert Fr to BigInt representation, then to string
+            // This ensures reliable round-trip conversion
+            input.into_bigint().to_string()
+        })
+        .collect();
+
+    let output = ProofOutput {
+        proof_a: proof_a_bytes,
+        proof_b: proof_b_bytes,
+        proof_c: proof_c_bytes,
+        public_inputs,
+        proof_serialized_hex: hex::encode(proof_serialized),
+        public_inputs_serialized_hex: hex::encode(public_inputs_serialized),
+        session_nonce: input.session_nonce.clone(),
+    };
+
+    let output_json = serde_json::to_string(&output).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize output: {}", e),
+        )
+    })?;
+    timer.end_phase(Phase::Serialize);
+    timer.finish();
+
+    Ok(output_json)
+}
+
+/// Key passed to the last `prove` call, kept when the prover config allows it
+struct PassedKey {
+    /// SHA-256 of the hex the key was passed as
+    digest: [u8; 32],
+    validation: PointValidation,
+    pk: Arc<ProvingKey<Bn254>>,
+    domain: Arc<ProvingDomain>,
+}
+
+static PASSED_KEY: Mutex<Option<PassedKey>> = Mutex::new(None);
+
+/// Deserializes `proving_key_hex` and computes its domain, reusing the key kept from the
+/// previous call when the bytes and validation mode match
+fn load_passed_key(
+    proving_key_hex: &str,
+    validation: PointValidation,
+) -> Result<(Arc<ProvingKey<Bn254>>, Arc<ProvingDomain>), VortexError> {
+    let digest: [u8; 32] = Sha256::digest(proving_key_hex.as_bytes()).into();
+    let keep = config::prover_config().keeps_passed_key();
+
+    let mut passed = PASSED_KEY.lock().unwrap_or_else(|e| e.into_inner());
+    if !keep {
+        *passed = None;
+    }
+    if let Some(kept) = passed.as_ref()
+        && kept.digest == digest
+        && kept.validation == validation
+    {
+        return Ok((kept.pk.clone(), kept.domain.clone()));
+    }
+
+    let pk_bytes = hex::decode(proving_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode proving key hex: {}", e),
+        )
+    })?;
+    let pk = Arc::new(
+        validation::deserialize_proving_key(&pk_bytes, validation)
+            .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?,
+    );
+    let domain = Arc::new(ProvingDomain::for_key(&pk).map_err(|e| {
+        VortexError::new(VortexErrorCode::Key, format!("Invalid proving key: {}", e))
+    })?);
+    if keep {
+        *passed = Some(PassedKey {
+            digest,
+            validation,
+            pk: pk.clone(),
+            domain: domain.clone(),
+        });
+    }
+    Ok((pk, domain))
+}
+
+/// Selects the prover memory profile
+///
+/// # Arguments
+/// * `config_json` - `ProverConfig` JSON, e.g. `{"memoryProfile":"low"}`. `low` stops
+///   keeping the last proving key deserialized between `prove` calls
+#[wasm_bindgen(js_name = setProverConfig)]
+pub fn set_prover_config(config_json: &str) -> Result<(), VortexError> {
+    let prover_config: ProverConfig = serde_json::from_str(config_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse prover config JSON: {}", e),
+        )
+    })?;
+    config::set_prover_config(prover_config);
+    if !prover_config.keeps_passed_key() {
+        *PASSED_KEY.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+    Ok(())
+}
+
+/// Returns the current `ProverConfig` as JSON
+#[wasm_bindgen(js_name = getProverConfig)]
+pub fn get_prover_config() -> Result<String, VortexError> {
+    serde_json::to_string(&config::prover_config()).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize prover config: {}", e),
+        )
+    })
+}
+
+/// Enables or disables per-phase timing of proofs (disabled by default)
+#[wasm_bindgen(js_name = setProofStatsEnabled)]
+pub fn set_proof_stats_enabled(enabled: bool) {
+    telemetry::set_enabled(enabled);
+}
+
+/// Returns the phase timings of the most recent proof as JSON
+///
+/// # Returns
+/// JSON string with `parseUs`, `witnessUs`, `constraintsUs`, `proveUs`, `serializeUs`,
+/// `totalUs` and `numConstraints`, or `undefined` if stats collection was disabled
+#[wasm_bindgen(js_name = getLastProofStats)]
+pub fn get_last_proof_stats() -> Result<Option<String>, VortexError> {
+    telemetry::last_proof_stats()
+        .map(|stats| serde_json::to_string(&stats))
+        .transpose()
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize proof stats: {}", e),
+            )
+        })
+}
+
+/// Returns the proof counters and histograms recorded since the last call as JSON, and
+/// starts over
+///
+/// # Returns
+/// `MetricsSnapshot` JSON: `counters` of `{ name, value }` and `histograms` of
+/// `{ name, count, sum, min, max, buckets }`
+#[cfg(feature = "metrics")]
+#[wasm_bindgen(js_name = drainMetrics)]
+pub fn drain_metrics() -> Result<String, VortexError> {
+    serde_json::to_string(&metrics::drain()).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize metrics: {}", e),
+        )
+    })
+}
+
+/// Enables or disables keeping a redacted report of failed proofs (disabled by default)
+#[wasm_bindgen(js_name = setBugReportRecording)]
+pub fn set_bug_report_recording(enabled: bool) {
+    replay::set_enabled(enabled);
+}
+
+/// Keeps amounts and leaf indices exact in later bug reports instead of bucketing amounts by
+/// bit length and replacing indices (disabled by default)
+#[wasm_bindgen(js_name = setBugReportFullDetail)]
+pub fn set_bug_report_full_detail(enabled: bool) {
+    replay::set_full_detail(enabled);
+}
+
+/// Returns the report of the most recent failed proof and forgets it
+///
+/// # Returns
+/// `BugReport` JSON with `version`, `error`, `environment`, `fullDetail`, the `input` with
+/// secrets replaced by `redacted:<n>` placeholders, amounts by `bits:<n>` buckets and leaf
+/// indices by `index:<n>` unless full detail is on, and `hints` for the `replay` tool, or
+/// `undefined` if no proof failed while recording was enabled
+#[wasm_bindgen(js_name = takeBugReport)]
+pub fn take_bug_report() -> Result<Option<String>, VortexError> {
+    replay::take_report()
+        .map(|report| serde_json::to_string(&report))
+        .transpose()
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Serialization,
+                format!("Failed to serialize bug report: {}", e),
+            )
+        })
+}
+
+/// Measures this device with a short MSM micro-benchmark
+///
+/// # Returns
+/// `DeviceCalibration` JSON to store and pass to `estimateProvingTime`
+#[wasm_bindgen(js_name = calibrateProver)]
+pub fn calibrate_prover() -> Result<String, VortexError> {
+    serde_json::to_string(&estimate::calibrate()).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize calibration: {}", e),
+        )
+    })
+}
+
+/// Derives a calibration from the stats of a real proof
+///
+/// # Arguments
+/// * `stats_json` - JSON from `getLastProofStats()`
+///
+/// # Returns
+/// `DeviceCalibration` JSON, more accurate than `calibrateProver()`
+#[wasm_bindgen(js_name = calibrationFromProofStats)]
+pub fn calibration_from_proof_stats(stats_json: &str) -> Result<String, VortexError> {
+    let stats = serde_json::from_str(stats_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof stats JSON: {}", e),
+        )
+    })?;
+    let calibration = estimate::calibration_from_stats(&stats)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&calibration).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize calibration: {}", e),
+        )
+    })
+}
+
+/// Estimates the proving time of each circuit variant on this device
+///
+/// # Arguments
+/// * `calibration_json` - Stored `DeviceCalibration` JSON; calibrates first if omitted
+///
+/// # Returns
+/// JSON array of `{ variant, numConstraints, estimatedMs }`
+#[wasm_bindgen(js_name = estimateProvingTime)]
+pub fn estimate_proving_time(calibration_json: Option<String>) -> Result<String, VortexError> {
+    let calibration: Option<DeviceCalibration> = calibration_json
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse calibration JSON: {}", e),
+            )
+        })?;
+    let estimates = estimate::estimate_proving_time(calibration.as_ref())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&estimates).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize estimates: {}", e),
+        )
+    })
+}
+
+/// Verifies a proof (useful for testing before submitting to chain)
+///
+/// # Arguments
+/// * `proof_json` - JSON string containing proof output from `prove()`
+/// * `verifying_key_hex` - Hex-encoded verifying key
+///
+/// # Returns
+/// "true" if proof is valid, "false" otherwise
+#[wasm_bindgen]
+pub fn verify(proof_json: &str, verifying_key_hex: &str) -> Result<bool, VortexError> {
+    let proof_output: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Step 1 - Failed to parse proof JSON: {}", e),
+        )
+    })?;
+
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Step 2 - Failed to decode VK hex: {}", e),
+        )
+    })?;
+
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, format!("Step 3 - {}", e)))?;
+
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+
+    let proof_bytes = hex::decode(&proof_output.proof_serialized_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Step 4 - Failed to decode proof hex: {}", e),
+        )
+    })?;
+
+    let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, format!("Step 5 - {}", e)))?;
+
+    let public_inputs: Result<Vec<Fr>, VortexError> = proof_output
+        .public_inputs
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            parse_field_element(s).map_err(|e| {
+                VortexError::new(
+                    VortexErrorCode::Parse,
+                    format!("Step 6 - Failed to parse public input {}: {:?}", i, e),
+                )
+            })
+        })
+        .collect();
+    let public_inputs = public_inputs?;
+
+    let is_valid = Groth16::<Bn254>::verify_proof(&pvk, &proof, &public_inputs).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Verify,
+            format!(
+                "Step 7 - Verify failed (inputs={}): {}",
+                public_inputs.len(),
+                e
+            ),
+        )
+    })?;
+
+    Ok(is_valid)
+}
+
+/// Proves and verifies a canned transfer to check the shipped keys at startup
+///
+/// # Arguments
+/// * `verifying_key_hex` - Hex-encoded verifying key
+/// * `proving_key_hex` - Hex-encoded proving key, as later passed to `prove()`
+/// * `manifest_json` - `KeyManifest` JSON, e.g. `{"circuitId":"default","provingKeySha256":"..."}`
+///
+/// # Returns
+/// `SelfTestReport` JSON with the prove and verify times; an error naming the mismatch otherwise
+#[wasm_bindgen(js_name = selfTest)]
+pub fn self_test(
+    verifying_key_hex: &str,
+    proving_key_hex: &str,
+    manifest_json: &str,
+) -> Result<String, VortexError> {
+    let manifest: KeyManifest = serde_json::from_str(manifest_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse manifest JSON: {}", e),
+        )
+    })?;
+    let pk_bytes = hex::decode(proving_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode proving key hex: {}", e),
+        )
+    })?;
+    selftest::check_manifest(&selftest::proving_key_digest(&pk_bytes), &manifest)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    drop(pk_bytes);
+
+    let (pk, _) = load_passed_key(proving_key_hex, PointValidation::Checked)?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode VK hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+
+    let report = selftest::self_test(&pk, &vk)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))?;
+    serde_json::to_string(&report).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize self-test report: {}", e),
+        )
+    })
+}
+
+/// Verifies many proofs against one verifying key, preparing the key once
+///
+/// # Arguments
+/// * `proofs_json` - JSON array of proof outputs from `prove()`
+/// * `verifying_key_hex` - Hex-encoded verifying key
+/// * `randomized` - Check the whole set with one randomized multi-pairing, falling back to
+///   one-by-one checks only if it fails
+///
+/// # Returns
+/// JSON array with one boolean per proof, in input order
+#[wasm_bindgen(js_name = verifyBatch)]
+pub fn verify_batch(
+    proofs_json: &str,
+    verifying_key_hex: &str,
+    randomized: bool,
+) -> Result<String, VortexError> {
+    let outputs: Vec<ProofOutput> = serde_json::from_str(proofs_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proofs JSON: {}", e),
+        )
+    })?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode VK hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+
+    let proofs = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| {
+            let proof_bytes = hex::decode(&output.proof_serialized_hex).map_err(|e| {
+                VortexError::new(
+                    VortexErrorCode::Parse,
+                    format!("Proof {}: failed to decode proof hex: {}", i, e),
+                )
+            })?;
+            let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)
+                .map_err(|e| {
+                    VortexError::new(VortexErrorCode::Parse, format!("Proof {}: {}", i, e))
+                })?;
+            let public_inputs = output
+                .public_inputs
+                .iter()
+                .map(|s| parse_field_element(s))
+                .collect::<Result<Vec<Fr>, VortexError>>()?;
+            Ok((proof, public_inputs))
+        })
+        .collect::<Result<Vec<_>, VortexError>>()?;
+
+    let verdicts = verifier::verify_batch(&pvk, &proofs, randomized, &mut OsRng);
+    serde_json::to_string(&verdicts).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize verdicts: {}", e),
+        )
+    })
+}
+
+/// Compares the verifying key a pool registered on chain with the bundled one
+///
+/// # Arguments
+/// * `onchain_json` - `OnChainVerifyingKey` JSON: `{"format": "raw", "bytes": [...]}` or the
+///   four `PreparedVerifyingKey` byte vectors with `"format": "prepared"`
+/// * `verifying_key_hex` - Hex-encoded bundled verifying key
+///
+/// # Returns
+/// `VerifyingKeyCheck` JSON with both fingerprints and the parts that differ
+#[wasm_bindgen(js_name = checkOnchainVerifyingKey)]
+pub fn check_onchain_verifying_key(
+    onchain_json: &str,
+    verifying_key_hex: &str,
+) -> Result<String, VortexError> {
+    let onchain: OnChainVerifyingKey = serde_json::from_str(onchain_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse on-chain verifying key JSON: {}", e),
+        )
+    })?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode VK hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+
+    let check = onchain_vk::check_verifying_key(&onchain, &vk)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    serde_json::to_string(&check).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize verifying key check: {}", e),
+        )
+    })
+}
+
+/// Aggregates proofs made with one verifying key into a single argument (SnarkPack)
+///
+/// # Arguments
+/// * `proofs_json` - JSON array of proof outputs from `prove()`
+/// * `verifying_key_hex` - Hex-encoded verifying key
+/// * `srs_hex` - Hex-encoded aggregation SRS from `keygen --aggregation-srs`
+///
+/// # Returns
+/// Hex-encoded aggregate, sent with the public inputs of every proof in order
+#[wasm_bindgen(js_name = aggregateProofs)]
+pub fn aggregate_proofs(
+    proofs_json: &str,
+    verifying_key_hex: &str,
+    srs_hex: &str,
+) -> Result<String, VortexError> {
+    trace_span!("aggregate_proofs");
+    let outputs: Vec<ProofOutput> = serde_json::from_str(proofs_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proofs JSON: {}", e),
+        )
+    })?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode VK hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let srs_bytes = hex::decode(srs_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode SRS hex: {}", e),
+        )
+    })?;
+    let srs = AggregationSrs::deserialize_compressed(srs_bytes.as_slice()).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Key,
+            format!("Failed to deserialize aggregation SRS: {}", e),
+        )
+    })?;
+
+    let proofs = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| {
+            let proof_bytes = hex::decode(&output.proof_serialized_hex).map_err(|e| {
+                VortexError::new(
+                    VortexErrorCode::Parse,
+                    format!("Proof {}: failed to decode proof hex: {}", i, e),
+                )
+            })?;
+            let proof = validation::deserialize_proof(&proof_bytes, PointValidation::Checked)
+                .map_err(|e| {
+                    VortexError::new(VortexErrorCode::Parse, format!("Proof {}: {}", i, e))
+                })?;
+            let public_inputs = output
+                .public_inputs
+                .iter()
+                .map(|s| parse_field_element(s))
+                .collect::<Result<Vec<Fr>, VortexError>>()?;
+            Ok((proof, public_inputs))
+        })
+        .collect::<Result<Vec<_>, VortexError>>()?;
+
+    let aggregate = aggregation::aggregate_proofs(&srs, &vk, &proofs)
+        .map_err(|e| VortexError::new(VortexErrorCode::Proof, e.to_string()))?;
+    let mut bytes = Vec::new();
+    aggregate.serialize_compressed(&mut bytes).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize aggregate proof: {}", e),
+        )
+    })?;
+    Ok(hex::encode(bytes))
+}
+
+/// Checks an aggregate from `aggregateProofs()`
+///
+/// # Arguments
+/// * `aggregate_hex` - Hex-encoded aggregate
+/// * `public_inputs_json` - JSON array holding each proof's public inputs, in aggregation order
+/// * `verifying_key_hex` - Hex-encoded verifying key
+/// * `verifier_key_hex` - Hex-encoded aggregation verifier key
+///
+/// # Returns
+/// `true` if every aggregated proof is valid
+#[wasm_bindgen(js_name = verifyAggregateProof)]
+pub fn verify_aggregate_proof(
+    aggregate_hex: &str,
+    public_inputs_json: &str,
+    verifying_key_hex: &str,
+    verifier_key_hex: &str,
+) -> Result<bool, VortexError> {
+    trace_span!("verify_aggregate_proof");
+    let public_inputs: Vec<Vec<String>> =
+        serde_json::from_str(public_inputs_json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse public inputs JSON: {}", e),
+            )
+        })?;
+    let public_inputs = public_inputs
+        .iter()
+        .map(|inputs| {
+            inputs
+                .iter()
+                .map(|s| parse_field_element(s))
+                .collect::<Result<Vec<Fr>, VortexError>>()
+        })
+        .collect::<Result<Vec<_>, VortexError>>()?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode VK hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let key_bytes = hex::decode(verifier_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode verifier key hex: {}", e),
+        )
+    })?;
+    let verifier_key = AggregationVerifierKey::deserialize_compressed(key_bytes.as_slice())
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Key,
+                format!("Failed to deserialize aggregation verifier key: {}", e),
+            )
+        })?;
+    let aggregate_bytes = hex::decode(aggregate_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode aggregate hex: {}", e),
+        )
+    })?;
+    // Aggregates come from untrusted parties, so points are always fully validated
+    let aggregate =
+        AggregateProof::deserialize_compressed(aggregate_bytes.as_slice()).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to deserialize aggregate proof: {}", e),
+            )
+        })?;
+
+    Ok(aggregation::verify_aggregate(
+        &verifier_key,
+        &vk,
+        &public_inputs,
+        &aggregate,
+    ))
+}
+
+/// Verifies a proof and checks it answers the request identified by `expected_nonce`
+///
+/// # Returns
+/// `true` if the proof is valid and its `sessionNonce` equals `expected_nonce`; an error if
+/// the nonce is missing or different (e.g. a replayed relayer response)
+#[wasm_bindgen(js_name = verifyWithNonce)]
+pub fn verify_with_nonce(
+    proof_json: &str,
+    verifying_key_hex: &str,
+    expected_nonce: &str,
+) -> Result<bool, VortexError> {
+    let proof_output: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })?;
+    proof_output
+        .check_session_nonce(expected_nonce)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))?;
+
+    verify(proof_json, verifying_key_hex)
+}
+
+/// Encrypts a proof input to a remote prover's X25519 public key
+///
+/// # Returns
+/// `EncryptedWitness` JSON to send to the prover
+#[wasm_bindgen(js_name = encryptWitnessForProver)]
+pub fn encrypt_witness_for_prover(
+    input_json: &str,
+    prover_public_key_hex: &str,
+) -> Result<String, VortexError> {
+    let input: ProofInput = serde_json::from_str(input_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse input JSON: {}", e),
+        )
+    })?;
+    let prover_public_key = hex::decode(prover_public_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode prover key hex: {}", e),
+        )
+    })?;
+    let envelope = delegate::encrypt_witness(&input, &prover_public_key, &mut OsRng)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    serde_json::to_string(&envelope).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize envelope: {}", e),
+        )
+    })
+}
+
+/// Checks a proof produced by a remote prover against the input that was delegated
+///
+/// # Returns
+/// `true` if the proof verifies for the locally derived public inputs; an error otherwise
+#[wasm_bindgen(js_name = verifyDelegatedProof)]
+pub fn verify_delegated_proof(
+    proof_json: &str,
+    input_json: &str,
+    verifying_key_hex: &str,
+) -> Result<bool, VortexError> {
+    let input: ProofInput = serde_json::from_str(input_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse input JSON: {}", e),
+        )
+    })?;
+    let output: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })?;
+    let vk_bytes = hex::decode(verifying_key_hex).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode verifying key hex: {}", e),
+        )
+    })?;
+    let vk = validation::deserialize_verifying_key(&vk_bytes, PointValidation::Checked)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+
+    delegate::verify_delegated_proof(&output, &input, &vk)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))?;
+    Ok(true)
+}
+
+/// Parses and validates a relayer fee quote
+///
+/// # Arguments
+/// * `quote_json` - Quote returned by the relayer
+/// * `now_ms` - Current time, e.g. `Date.now()`
+#[wasm_bindgen(js_name = parseRelayerFeeQuote)]
+pub fn parse_relayer_fee_quote(quote_json: &str, now_ms: f64) -> Result<String, VortexError> {
+    let quote = relayer::parse_fee_quote(quote_json, now_ms as u64)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))?;
+    serde_json::to_string(&quote).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize quote: {}", e),
+        )
+    })
+}
+
+/// Computes the `ext_data_hash` public input binding ext data to a proof
+///
+/// # Returns
+/// Decimal field element to pass as `extDataHash` in the proof input
+#[wasm_bindgen(js_name = computeExtDataHash)]
+pub fn compute_ext_data_hash(ext_data_json: &str) -> Result<String, VortexError> {
+    let ext_data: ExtData = serde_json::from_str(ext_data_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse ext data JSON: {}", e),
+        )
+    })?;
+    let hash = relayer::ext_data_hash(&ext_data)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    Ok(hash.into_bigint().to_string())
+}
+
+/// Computes the `outputCiphertextHash` public input of an encrypted output
+///
+/// # Arguments
+/// * `encrypted_output_hex` - Hex-encoded `encrypted_output` as published on chain
+///
+/// # Returns
+/// Decimal field element; the output commits to `Poseidon2(blinding, hash)` when bound
+#[wasm_bindgen(js_name = computeCiphertextHash)]
+pub fn compute_ciphertext_hash(encrypted_output_hex: &str) -> Result<String, VortexError> {
+    let encrypted_output =
+        hex::decode(encrypted_output_hex.trim_start_matches("0x")).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to decode encrypted output hex: {}", e),
+            )
+        })?;
+    Ok(viewing::ciphertext_hash(&encrypted_output)
+        .into_bigint()
+        .to_string())
+}
+
+/// Canonical 32-byte encoding of a field element
+///
+/// # Arguments
+/// * `value` - Decimal or `0x`-prefixed hex field element
+/// * `big_endian` - Big-endian instead of the little-endian form arkworks serializes
+///
+/// # Returns
+/// Hex-encoded bytes
+#[wasm_bindgen(js_name = fieldToBytes)]
+pub fn field_to_bytes(value: &str, big_endian: bool) -> Result<String, VortexError> {
+    let value = parse_field_element(value)?;
+    Ok(hex::encode(if big_endian {
+        field::fr_to_bytes_be(&value)
+    } else {
+        field::fr_to_bytes_le(&value)
+    }))
+}
+
+/// Field element from its canonical 32-byte encoding
+///
+/// # Arguments
+/// * `bytes_hex` - Hex-encoded 32 bytes; values at or above the modulus are rejected
+/// * `big_endian` - Whether the bytes are big-endian
+///
+/// # Returns
+/// Decimal field element
+#[wasm_bindgen(js_name = fieldFromBytes)]
+pub fn field_from_bytes(bytes_hex: &str, big_endian: bool) -> Result<String, VortexError> {
+    let bytes = hex::decode(bytes_hex.trim_start_matches("0x")).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode field element hex: {}", e),
+        )
+    })?;
+    let value = if big_endian {
+        field::fr_from_bytes_be(&bytes)
+    } else {
+        field::fr_from_bytes_le(&bytes)
+    };
+    value
+        .map(|value| value.into_bigint().to_string())
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))
+}
+
+/// Builds the JSON body to POST to a relayer for a withdrawal or transfer proof
+#[wasm_bindgen(js_name = buildRelayRequest)]
+pub fn build_relay_request(
+    proof_json: &str,
+    ext_data_json: &str,
+    quote_json: &str,
+    pool_id: &str,
+    recipient: &str,
+    now_ms: f64,
+) -> Result<String, VortexError> {
+    let proof: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })?;
+    let ext_data: ExtData = serde_json::from_str(ext_data_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse ext data JSON: {}", e),
+        )
+    })?;
+    let quote: RelayerFeeQuote = serde_json::from_str(quote_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse quote JSON: {}", e),
+        )
+    })?;
+
+    let request =
+        relayer::build_relay_request(&proof, &ext_data, &quote, pool_id, recipient, now_ms as u64)
+            .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&request).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize relay request: {}", e),
+        )
+    })
+}
+
+/// Splits a relayed withdrawal into the ext data value, the relayer fee, what the recipient
+/// receives and the change
+///
+/// # Arguments
+/// * `input_amounts_json` - JSON array of the amounts of the notes being spent
+/// * `withdraw` - Amount the recipient's address should receive
+/// * `quote_json` - Quote from `parseRelayerFeeQuote`
+///
+/// # Returns
+/// JSON `FeeSplit`
+#[wasm_bindgen(js_name = splitRelayerFee)]
+pub fn split_relayer_fee(
+    input_amounts_json: &str,
+    withdraw: f64,
+    quote_json: &str,
+) -> Result<String, VortexError> {
+    let input_amounts: Vec<u64> = serde_json::from_str(input_amounts_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse input amounts JSON: {}", e),
+        )
+    })?;
+    let quote: RelayerFeeQuote = serde_json::from_str(quote_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse quote JSON: {}", e),
+        )
+    })?;
+    let split = relayer_fee::split_relayer_fee(&input_amounts, safe_amount(withdraw)?, &quote)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&split).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize fee split: {}", e),
+        )
+    })
+}
+
+/// Checks a relayed withdrawal's `ProofInput` against its ext data and the relayer's quote
+/// before proving: fee, relayer, the signed `publicAmount`, amount conservation and
+/// `extDataHash`
+#[wasm_bindgen(js_name = checkRelayedInput)]
+pub fn check_relayed_input(
+    input_json: &str,
+    ext_data_json: &str,
+    quote_json: &str,
+) -> Result<(), VortexError> {
+    let input: ProofInput = serde_json::from_str(input_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse input JSON: {}", e),
+        )
+    })?;
+    let ext_data: ExtData = serde_json::from_str(ext_data_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse ext data JSON: {}", e),
+        )
+    })?;
+    let quote: RelayerFeeQuote = serde_json::from_str(quote_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse quote JSON: {}", e),
+        )
+    })?;
+    relayer_fee::check_relayed_input(&input, &ext_data, &quote)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// Builds the ordered BCS arguments of the Move calls submitting a proof to the pool
+///
+/// # Arguments
+/// * `proof_json` - `ProofOutput` JSON from `prove`
+/// * `ext_data_json` - Ext data the proof was generated for
+/// * `package_id` - Vortex package address
+/// * `pool_id` - Pool object id
+/// * `coin_type` - Pool coin type; SUI when omitted
+///
+/// # Returns
+/// JSON `TransactCall`, with each argument as an array of bytes
+#[wasm_bindgen(js_name = buildTransactCall)]
+pub fn build_transact_call(
+    proof_json: &str,
+    ext_data_json: &str,
+    package_id: &str,
+    pool_id: &str,
+    coin_type: Option<String>,
+) -> Result<String, VortexError> {
+    let proof: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })?;
+    let ext_data: ExtData = serde_json::from_str(ext_data_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse ext data JSON: {}", e),
+        )
+    })?;
+    let coin_type = coin_type.as_deref().unwrap_or(sui_tx::SUI_COIN_TYPE);
+    let call = sui_tx::build_transact_call(&proof, &ext_data, package_id, pool_id, coin_type)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&call).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize transact call: {}", e),
+        )
+    })
+}
+
+/// Verifies a relayer's submission response against the proof it was sent
+///
+/// # Returns
+/// JSON `RelayReceipt` with the recomputed transaction digest
+#[wasm_bindgen(js_name = verifyRelayResponse)]
+pub fn verify_relay_response(response_json: &str, proof_json: &str) -> Result<String, VortexError> {
+    let proof: ProofOutput = serde_json::from_str(proof_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })?;
+    let receipt = relayer::verify_relay_response(response_json, &proof)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))?;
+    serde_json::to_string(&receipt).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize receipt: {}", e),
+        )
+    })
+}
+
+/// Generates a fresh non-zero account secret, returned in decimal
+#[wasm_bindgen(js_name = generateAccountSecret)]
+pub fn generate_account_secret() -> String {
+    AccountSecret::generate(&mut OsRng).to_decimal().to_string()
+}
+
+/// Builds the Move call that registers an account for `account_secret`
+///
+/// # Returns
+/// JSON `AccountRegistration`
+#[wasm_bindgen(js_name = accountRegistrationPayload)]
+pub fn account_registration_payload(
+    package_id: &str,
+    account_secret: &str,
+) -> Result<String, VortexError> {
+    let secret = AccountSecret::parse(account_secret)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    let registration = account::registration_payload(package_id, &secret)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&registration).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize registration: {}", e),
+        )
+    })
+}
+
+/// Generates a replacement for `account_secret`
+///
+/// # Returns
+/// JSON `AccountRotation` with the new secret and its registration call
+#[wasm_bindgen(js_name = rotateAccountSecret)]
+pub fn rotate_account_secret(
+    package_id: &str,
+    account_secret: &str,
+) -> Result<String, VortexError> {
+    let previous = AccountSecret::parse(account_secret)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    let (_, rotation) = account::rotate(&previous, package_id, &mut OsRng)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&rotation).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize rotation: {}", e),
+        )
+    })
+}
+
+/// Derives the account secret of a zkLogin user from their OAuth session
+///
+/// # Arguments
+/// * `credentials_json` - `ZkLoginCredentials` JSON: `iss`, `addressSeed` and `userSalt`
+/// * `expected_address` - The wallet's zkLogin address; checked against the credentials when given
+///
+/// # Returns
+/// Decimal account secret, the same on every device the user signs in on
+#[wasm_bindgen(js_name = deriveZkLoginAccountSecret)]
+pub fn derive_zklogin_account_secret(
+    credentials_json: &str,
+    expected_address: Option<String>,
+) -> Result<String, VortexError> {
+    let credentials: ZkLoginCredentials = serde_json::from_str(credentials_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse zkLogin credentials JSON: {}", e),
+        )
+    })?;
+    let secret = credentials
+        .derive_account_secret(expected_address.as_deref())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    Ok(secret.to_decimal().to_string())
+}
+
+/// Derives the blinding of an output from the spend seed
+///
+/// # Arguments
+/// * `spend_seed_hex` - 32-byte spend seed
+/// * `recipient_public_key` - Recipient's note public key
+/// * `output_index` - Output slot of the transaction
+/// * `tx_nonce` - Number unique to each transaction built from the seed
+///
+/// # Returns
+/// Decimal blinding
+#[wasm_bindgen(js_name = deriveOutputBlinding)]
+pub fn derive_output_blinding(
+    spend_seed_hex: &str,
+    recipient_public_key: &str,
+    output_index: u32,
+    tx_nonce: f64,
+) -> Result<String, VortexError> {
+    let seed = viewing::decode_key_hex(spend_seed_hex, "spend seed")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let blinding = viewing::derive_output_blinding(
+        &seed,
+        &parse_field_element(recipient_public_key)?,
+        output_index,
+        tx_nonce as u64,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    Ok(blinding.into_bigint().to_string())
+}
+
+/// Derives the viewing keys of a spend seed
+///
+/// # Arguments
+/// * `spend_seed_hex` - 32-byte spend seed
+///
+/// # Returns
+/// JSON `ViewingKeyExport`
+#[wasm_bindgen(js_name = deriveViewingKeys)]
+pub fn derive_viewing_keys(spend_seed_hex: &str) -> Result<String, VortexError> {
+    let seed = viewing::decode_key_hex(spend_seed_hex, "spend seed")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let keys = SpendingKeys::from_seed(&seed)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    serde_json::to_string(&ViewingKeyExport::new(&keys.viewing)).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize viewing keys: {}", e),
+        )
+    })
+}
+
+/// Encrypts an output note to the recipient with a copy for the sender's outgoing key
+///
+/// # Arguments
+/// * `amount` - Decimal note amount
+/// * `blinding` - Decimal or hex note blinding
+///
+/// # Returns
+/// Hex-encoded `encrypted_output`
+#[wasm_bindgen(js_name = encryptNoteOutput)]
+pub fn encrypt_note_output(
+    amount: &str,
+    blinding: &str,
+    recipient_viewing_public_key_hex: &str,
+    sender_full_viewing_key_hex: &str,
+) -> Result<String, VortexError> {
+    let note = NotePlaintext {
+        amount: amount.trim().parse().map_err(|e| {
+            VortexError::new(VortexErrorCode::Parse, format!("Invalid amount: {}", e))
+        })?,
+        blinding: parse_field_element(blinding)?,
+    };
+    let recipient = hex::decode(recipient_viewing_public_key_hex.trim_start_matches("0x"))
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to decode recipient key hex: {}", e),
+            )
+        })?;
+    let sender = parse_full_viewing_key(sender_full_viewing_key_hex)?;
+
+    viewing::encrypt_note(&note, &recipient, &sender, &mut OsRng)
+        .map(hex::encode)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// Decrypts a received note and checks it opens `commitment`
+///
+/// # Returns
+/// JSON `DecryptedNote`, or `null` if the note is not ours
+#[wasm_bindgen(js_name = decryptIncomingNote)]
+pub fn decrypt_incoming_note(
+    full_viewing_key_hex: &str,
+    encrypted_output_hex: &str,
+    commitment: &str,
+    vortex: &str,
+) -> Result<String, VortexError> {
+    let keys = parse_full_viewing_key(full_viewing_key_hex)?;
+    let encrypted = hex::decode(encrypted_output_hex.trim_start_matches("0x")).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode encrypted output hex: {}", e),
+        )
+    })?;
+    let note = keys
+        .decrypt_incoming(
+            &encrypted,
+            &parse_field_element(commitment)?,
+            &parse_field_element(vortex)?,
+        )
+        .map(|note| DecryptedNote::new(&note, None));
+    serde_json::to_string(&note).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize note: {}", e),
+        )
+    })
+}
+
+/// Trial-decrypts a batch of pool outputs and returns the notes received by this wallet
+///
+/// For parallel scanning, give each Web Worker its own module instance and the same event
+/// list with a different `shard`; together the workers cover every event once.
+///
+/// # Arguments
+/// * `full_viewing_key_hex` - The wallet's full viewing key
+/// * `events_json` - JSON array of `{ leafIndex, commitment, encryptedOutput }`
+/// * `vortex` - Pool the outputs belong to
+/// * `shard` / `shard_count` - Only scan events whose position modulo `shard_count` is
+///   `shard`; omit both to scan everything
+///
+/// # Returns
+/// JSON array of `{ leafIndex, commitment, note }` for the notes received
+#[wasm_bindgen(js_name = scanNotes)]
+pub fn scan_notes(
+    full_viewing_key_hex: &str,
+    events_json: &str,
+    vortex: &str,
+    shard: Option<u32>,
+    shard_count: Option<u32>,
+) -> Result<String, VortexError> {
+    let keys = parse_full_viewing_key(full_viewing_key_hex)?;
+    let mut events: Vec<OutputEvent> = serde_json::from_str(events_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse events JSON: {}", e),
+        )
+    })?;
+    match (shard, shard_count) {
+        (None, None) => {}
+        (Some(shard), Some(shard_count)) if shard < shard_count => {
+            let mut position = 0u32;
+            events.retain(|_| {
+                let keep = position % shard_count == shard;
+                position += 1;
+                keep
+            });
+        }
+        _ => {
+            return Err(VortexError::new(
+                VortexErrorCode::Input,
+                "shard and shard_count must be given together, with shard < shard_count",
+            ));
+        }
+    }
+
+    let received = keys
+        .scan_notes(&events, &parse_field_element(vortex)?)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&received).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize scanned notes: {}", e),
+        )
+    })
+}
+
+/// Decrypts a note this wallet sent
+///
+/// # Returns
+/// JSON `DecryptedNote` with the recipient key, or `null` if it was not sent by this key
+#[wasm_bindgen(js_name = decryptOutgoingNote)]
+pub fn decrypt_outgoing_note(
+    full_viewing_key_hex: &str,
+    encrypted_output_hex: &str,
+) -> Result<String, VortexError> {
+    let keys = parse_full_viewing_key(full_viewing_key_hex)?;
+    let encrypted = hex::decode(encrypted_output_hex.trim_start_matches("0x")).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode encrypted output hex: {}", e),
+        )
+    })?;
+    let note = keys
+        .decrypt_outgoing(&encrypted)
+        .map(|(recipient, note)| DecryptedNote::new(&note, Some(recipient)));
+    serde_json::to_string(&note).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize note: {}", e),
+        )
+    })
+}
+
+/// Builds an unspent UTXO and computes its commitment
+///
+/// # Arguments
+/// * `note_json` - `Note` JSON: `{ amount, publicKey, blinding, legacyCommitment }`
+/// * `leaf_index` - Leaf index of the commitment in the tree
+/// * `vortex` - Pool address the commitment is bound to
+///
+/// # Returns
+/// `Utxo` JSON
+#[wasm_bindgen(js_name = createUtxo)]
+pub fn create_utxo(note_json: &str, leaf_index: f64, vortex: &str) -> Result<String, VortexError> {
+    let note: Note = serde_json::from_str(note_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse note JSON: {}", e),
+        )
+    })?;
+    let utxo = Utxo::new(note, leaf_index as u64, &parse_field_element(vortex)?)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&utxo).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize UTXO: {}", e),
+        )
+    })
+}
+
+/// Finds the owned notes whose spent flag disagrees with the pool's nullifiers
+///
+/// # Arguments
+/// * `owned_notes_json` - JSON array of `Utxo`
+/// * `on_chain_nullifiers_json` - JSON array of the pool's decimal nullifiers, all of them:
+///   a note flagged spent whose nullifier is missing is reported as unspent
+/// * `private_key` - Note private key the nullifiers are derived with
+///
+/// # Returns
+/// JSON array of `NoteStatusChange`: `{ commitment, leafIndex, spent, nullifier }`
+#[wasm_bindgen(js_name = markSpentNotes)]
+pub fn mark_spent_notes(
+    owned_notes_json: &str,
+    on_chain_nullifiers_json: &str,
+    private_key: &str,
+) -> Result<String, VortexError> {
+    let owned_notes: Vec<Utxo> = serde_json::from_str(owned_notes_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse notes JSON: {}", e),
+        )
+    })?;
+    let on_chain_nullifiers: Vec<String> =
+        serde_json::from_str(on_chain_nullifiers_json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse nullifiers JSON: {}", e),
+            )
+        })?;
+    let on_chain_nullifiers = on_chain_nullifiers
+        .iter()
+        .map(|nullifier| parse_field_element(nullifier))
+        .collect::<Result<HashSet<Fr>, _>>()?;
+    let private_key = Zeroizing::new(parse_field_element(private_key)?);
+    let changes = note::mark_spent_notes(&owned_notes, &on_chain_nullifiers, &private_key)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&changes).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize note changes: {}", e),
+        )
+    })
+}
+
+/// Builds an output paying a shielded address
+///
+/// # Arguments
+/// * `address` - Recipient's bech32m shielded address
+/// * `amount` - Amount in MIST
+/// * `vortex` - Pool address the commitment is bound to
+/// * `sender_full_viewing_key_hex` - Keeps a copy readable with the sender's outgoing key
+/// * `blinding` - Blinding to use, e.g. from `deriveOutputBlinding`; random if omitted
+///
+/// # Returns
+/// `PreparedOutput` JSON with the note, commitment and `encryptedOutput` hex
+#[wasm_bindgen(js_name = createOutputFor)]
+pub fn create_output_for(
+    address: &str,
+    amount: f64,
+    vortex: &str,
+    sender_full_viewing_key_hex: &str,
+    blinding: Option<String>,
+) -> Result<String, VortexError> {
+    let sender = parse_full_viewing_key(sender_full_viewing_key_hex)?;
+    let blinding = blinding.as_deref().map(parse_field_element).transpose()?;
+    let output = note::create_output_for(
+        address,
+        amount as u64,
+        &parse_field_element(vortex)?,
+        &sender,
+        blinding,
+        &mut OsRng,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&output).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize output: {}", e),
+        )
+    })
+}
+
+/// Computes the nullifier a UTXO publishes when spent
+///
+/// # Returns
+/// Decimal nullifier
+#[wasm_bindgen(js_name = computeUtxoNullifier)]
+pub fn compute_utxo_nullifier(utxo_json: &str, private_key: &str) -> Result<String, VortexError> {
+    let utxo: Utxo = serde_json::from_str(utxo_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse UTXO JSON: {}", e),
+        )
+    })?;
+    let private_key = Zeroizing::new(parse_field_element(private_key)?);
+    let nullifier = utxo
+        .nullifier(&private_key)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    Ok(nullifier.into_bigint().to_string())
+}
+
+/// Builds a zero-amount input for filling an unused `ProofInput` slot
+///
+/// # Arguments
+/// * `index` - Leaf index the dummy claims; any index in the tree is accepted
+/// * `vortex` - Pool address the commitment is bound to
+///
+/// # Returns
+/// `DummyInput` JSON: `{ privateKey, publicKey, amount, blinding, pathIndex, commitment,
+/// signature, nullifier, merklePath }`
+#[wasm_bindgen(js_name = makeDummyInput)]
+pub fn make_dummy_input(index: f64, vortex: &str) -> Result<String, VortexError> {
+    let dummy = padding::make_dummy_input(index as u64, &parse_field_element(vortex)?, &mut OsRng)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&dummy).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize dummy input: {}", e),
+        )
+    })
+}
+
+/// Encodes a note public key and viewing public key as a bech32m shielded address
+#[wasm_bindgen(js_name = encodeShieldedAddress)]
+pub fn encode_shielded_address(
+    note_public_key: &str,
+    viewing_public_key_hex: &str,
+) -> Result<String, VortexError> {
+    let viewing_public_key = hex::decode(viewing_public_key_hex.trim_start_matches("0x"))
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to decode viewing key hex: {}", e),
+            )
+        })?
+        .try_into()
+        .map_err(|v: Vec<u8>| {
+            VortexError::new(
+                VortexErrorCode::Input,
+                format!("Viewing public key must be 32 bytes, got {}", v.len()),
+            )
+        })?;
+    let address = ShieldedAddress {
+        note_public_key: parse_field_element(note_public_key)?,
+        viewing_public_key,
+    };
+    Ok(address.encode())
+}
+
+/// Decodes a shielded address
+///
+/// # Returns
+/// JSON `{ notePublicKey, viewingPublicKey }` with a decimal note key and hex viewing key
+#[wasm_bindgen(js_name = decodeShieldedAddress)]
+pub fn decode_shielded_address(address: &str) -> Result<String, VortexError> {
+    let address = ShieldedAddress::decode(address)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))?;
+    Ok(serde_json::json!({
+        "notePublicKey": address.note_public_key.into_bigint().to_string(),
+        "viewingPublicKey": hex::encode(address.viewing_public_key),
+    })
+    .to_string())
+}
+
+/// Encodes a note and its private key as a deposit note string
+///
+/// # Arguments
+/// * `parts_json` - `DepositNoteParts` JSON `{ asset, note, privateKey }`
+///
+/// # Returns
+/// `vortex-sui-v1-<asset>-<data>`; anyone holding it can spend the note
+#[wasm_bindgen(js_name = encodeDepositNote)]
+pub fn encode_deposit_note(parts_json: &str) -> Result<String, VortexError> {
+    let parts: DepositNoteParts = serde_json::from_str(parts_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse deposit note parts: {}", e),
+        )
+    })?;
+    let encoded = DepositNote::from_parts(&parts)
+        .and_then(|deposit| deposit.encode())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    Ok(encoded.to_string())
+}
+
+/// Decodes a deposit note string, checking its version and checksum
+///
+/// # Returns
+/// `DepositNoteParts` JSON
+#[wasm_bindgen(js_name = decodeDepositNote)]
+pub fn decode_deposit_note(note: &str) -> Result<String, VortexError> {
+    let deposit = DepositNote::decode(note)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))?;
+    serde_json::to_string(&deposit.to_parts()).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize deposit note: {}", e),
+        )
+    })
+}
+
+/// Encodes a payment request as an uppercase QR payload
+///
+/// # Arguments
+/// * `request_json` - `PaymentRequest` JSON `{ address, amount?, memo?, expiresAtMs? }`
+/// * `spending_key` - Signs the request with its login key when given
+///
+/// # Returns
+/// `VORTEXPAY1...` payload for a QR code in alphanumeric mode
+#[wasm_bindgen(js_name = encodePaymentRequest)]
+pub fn encode_payment_request(
+    request_json: &str,
+    spending_key: Option<String>,
+) -> Result<String, VortexError> {
+    let request: payment_request::PaymentRequest =
+        serde_json::from_str(request_json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse payment request: {}", e),
+            )
+        })?;
+    let spending_key = spending_key
+        .as_deref()
+        .map(parse_field_element)
+        .transpose()?
+        .map(Zeroizing::new);
+    payment_request::encode_payment_request(&request, spending_key.as_deref())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// Decodes a scanned payment request, verifying its signature and expiry
+///
+/// # Arguments
+/// * `payload` - Scanned `vortexpay1...` string, in either case
+/// * `now_ms` - Current Unix time in milliseconds
+///
+/// # Returns
+/// `ScannedPaymentRequest` JSON `{ request, signerPublicKey }`
+#[wasm_bindgen(js_name = decodePaymentRequest)]
+pub fn decode_payment_request(payload: &str, now_ms: f64) -> Result<String, VortexError> {
+    let scanned = payment_request::decode_payment_request(payload, now_ms as u64)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))?;
+    serde_json::to_string(&scanned).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize payment request: {}", e),
+        )
+    })
+}
+
+/// Parses a `vortex:` deep link
+///
+/// # Arguments
+/// * `uri` - `vortex:<address>?amount=..&memo=..&expires=..`
+/// * `now_ms` - Current Unix time in milliseconds; expired links are rejected
+///
+/// # Returns
+/// `PaymentRequest` JSON
+#[wasm_bindgen(js_name = parsePaymentUri)]
+pub fn parse_payment_uri(uri: &str, now_ms: f64) -> Result<String, VortexError> {
+    let request = payment_uri::parse_payment_uri(uri, now_ms as u64)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))?;
+    serde_json::to_string(&request).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize payment request: {}", e),
+        )
+    })
+}
+
+/// Formats a `PaymentRequest` JSON as a canonical `vortex:` URI
+#[wasm_bindgen(js_name = formatPaymentUri)]
+pub fn format_payment_uri(request_json: &str) -> Result<String, VortexError> {
+    let request: payment_request::PaymentRequest =
+        serde_json::from_str(request_json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse payment request: {}", e),
+            )
+        })?;
+    payment_uri::format_payment_uri(&request)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// Canonical form of a `vortex:` URI, for comparing and logging deep links
+#[wasm_bindgen(js_name = canonicalizePaymentUri)]
+pub fn canonicalize_payment_uri(uri: &str) -> Result<String, VortexError> {
+    payment_uri::canonicalize_payment_uri(uri)
+        .map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))
+}
+
+#[wasm_bindgen(js_name = validateShieldedAddress)]
+pub fn validate_shielded_address(address: &str) -> bool {
+    ShieldedAddress::is_valid(address)
+}
+
+/// Exports history entries inside `[from_ms, to_ms)`, signed with the spend seed's history key
+///
+/// # Arguments
+/// * `entries_json` - JSON array of `HistoryEntry`
+/// * `spend_seed_hex` - 32-byte spend seed
+///
+/// # Returns
+/// `SignedHistoryExport` JSON
+#[wasm_bindgen(js_name = exportTransactionHistory)]
+pub fn export_transaction_history(
+    entries_json: &str,
+    from_ms: Option<f64>,
+    to_ms: Option<f64>,
+    spend_seed_hex: &str,
+) -> Result<String, VortexError> {
+    let entries: Vec<HistoryEntry> = serde_json::from_str(entries_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse history entries JSON: {}", e),
+        )
+    })?;
+    let seed = viewing::decode_key_hex(spend_seed_hex, "spend seed")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let signing_key = history::signing_key_from_seed(&seed)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let export = history::export_history(
+        &entries,
+        from_ms.map(|ms| ms as u64),
+        to_ms.map(|ms| ms as u64),
+        &signing_key,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    serde_json::to_string(&export).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize history export: {}", e),
+        )
+    })
+}
+
+/// Checks the signature of a history export
+///
+/// # Returns
+/// `true` if the export is unmodified; an error otherwise
+#[wasm_bindgen(js_name = verifyTransactionHistory)]
+pub fn verify_transaction_history(export_json: &str) -> Result<bool, VortexError> {
+    let export: SignedHistoryExport = serde_json::from_str(export_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse history export JSON: {}", e),
+        )
+    })?;
+    history::verify_history(&export)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))?;
+    Ok(true)
+}
+
+/// Computes `public_amount`, the change output and the ext data value for a payment intent
+///
+/// # Arguments
+/// * `intent_json` - `TransferIntent` JSON: `{ inputAmounts, deposit, send, withdraw, fee }`
+///
+/// # Returns
+/// `AmountPlan` JSON
+#[wasm_bindgen(js_name = planTransactionAmounts)]
+pub fn plan_transaction_amounts(intent_json: &str) -> Result<String, VortexError> {
+    let intent: TransferIntent = serde_json::from_str(intent_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse intent JSON: {}", e),
+        )
+    })?;
+    let plan = amount::plan_amounts(&intent)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&plan).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize amount plan: {}", e),
+        )
+    })
+}
+
+/// Decimal field encoding of an amount, as the `ProofInput` amount fields take it
+///
+/// # Arguments
+/// * `amount` - Amount in MIST; must be a whole number below 2^53 to be exact in JavaScript
+#[wasm_bindgen(js_name = amountToField)]
+pub fn amount_to_field(amount: f64) -> Result<String, VortexError> {
+    Ok(amount::amount_to_fr(safe_amount(amount)?)
+        .into_bigint()
+        .to_string())
+}
+
+/// `amount` as a `u64`, if JavaScript represents it exactly
+fn safe_amount(amount: f64) -> Result<u64, VortexError> {
+    // `Number.MAX_SAFE_INTEGER`; larger numbers may already have been rounded
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+    if !(0.0..=MAX_SAFE_INTEGER).contains(&amount) || amount.fract() != 0.0 {
+        return Err(VortexError::new(
+            VortexErrorCode::Input,
+            format!("Amount {} is not a safe non-negative integer", amount),
+        ));
+    }
+    Ok(amount as u64)
+}
+
+/// Amount encoded by a field element
+///
+/// # Returns
+/// The amount as a decimal string, since it may exceed `Number.MAX_SAFE_INTEGER`
+#[wasm_bindgen(js_name = fieldToAmount)]
+pub fn field_to_amount(value: &str) -> Result<String, VortexError> {
+    amount::fr_to_amount(&parse_field_element(value)?)
+        .map(|amount| amount.to_string())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// `public_amount` of a signed change to the pool's balance
+///
+/// # Arguments
+/// * `amount` - Decimal MIST, positive for deposits and negative (`-850`) for withdrawals
+///   including their fee
+///
+/// # Returns
+/// Decimal field element, `p - |amount|` for withdrawals
+#[wasm_bindgen(js_name = signedAmountToPublicAmount)]
+pub fn signed_amount_to_public_amount(amount: &str) -> Result<String, VortexError> {
+    let amount: i128 = amount.trim().parse().map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Invalid signed amount '{}': {}", amount, e),
+        )
+    })?;
+    Ok(amount::signed_to_public_amount(amount)
+        .into_bigint()
+        .to_string())
+}
+
+/// Signed change to the pool's balance encoded by a `public_amount`
+///
+/// # Returns
+/// Decimal MIST, negative for withdrawals
+#[wasm_bindgen(js_name = publicAmountToSignedAmount)]
+pub fn public_amount_to_signed_amount(public_amount: &str) -> Result<String, VortexError> {
+    amount::public_amount_to_signed(&parse_field_element(public_amount)?)
+        .map(|amount| amount.to_string())
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))
+}
+
+/// Encrypts the wallet's notes and sync checkpoint under a password
+///
+/// # Arguments
+/// * `snapshot_json` - `WalletSnapshot` JSON: `notes` and the `syncCheckpoint` JSON string
+/// * `password` - Backup password, stretched with Argon2id
+///
+/// # Returns
+/// Hex-encoded backup
+#[wasm_bindgen(js_name = exportBackup)]
+pub fn export_backup(snapshot_json: &str, password: &str) -> Result<String, VortexError> {
+    let snapshot: WalletSnapshot = serde_json::from_str(snapshot_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse wallet snapshot JSON: {}", e),
+        )
+    })?;
+    let backup = backup::export_backup(&snapshot, password, &mut OsRng)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    Ok(hex::encode(backup))
+}
+
+/// Decrypts a backup produced by `exportBackup`
+///
+/// # Arguments
+/// * `backup_hex` - Hex-encoded backup
+/// * `password` - Password it was exported with
+///
+/// # Returns
+/// `WalletSnapshot` JSON
+#[wasm_bindgen(js_name = importBackup)]
+pub fn import_backup(backup_hex: &str, password: &str) -> Result<String, VortexError> {
+    let backup = hex::decode(backup_hex.trim()).map_err(|e| {
+        VortexError::new(VortexErrorCode::Parse, format!("Invalid backup hex: {}", e))
+    })?;
+    let snapshot = backup::import_backup(&backup, password)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    serde_json::to_string(&snapshot).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize wallet snapshot: {}", e),
+        )
+    })
+}
+
+/// Membership of a commitment, in `ProofInput` format
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitmentPath {
+    root: String,
+    /// `inPathIndexN`
+    path_index: String,
+    /// `merklePathN`
+    merkle_path: Vec<[String; 2]>,
+}
+
+/// Builds the Merkle path of a commitment from the pool's full commitment list
+///
+/// No tree object is kept between calls; the tree is rebuilt from the list each time.
+///
+/// # Arguments
+/// * `commitments_json` - JSON array of every decimal commitment in insertion order, e.g.
+///   fetched from an indexer
+/// * `commitment` - The note's commitment
+///
+/// # Returns
+/// JSON `{ root, pathIndex, merklePath }` ready for the matching `ProofInput` fields
+#[wasm_bindgen(js_name = merklePathFromCommitments)]
+pub fn merkle_path_from_commitments(
+    commitments_json: &str,
+    commitment: &str,
+) -> Result<String, VortexError> {
+    let commitments: Vec<String> = serde_json::from_str(commitments_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse commitments JSON: {}", e),
+        )
+    })?;
+    let leaves = commitments
+        .iter()
+        .map(|c| parse_field_element(c))
+        .collect::<Result<Vec<Fr>, VortexError>>()?;
+    let target = parse_field_element(commitment)?;
+    let empty_leaf = parse_field_element(ZERO_VALUE)?;
+
+    let (root, index, path) =
+        SparseMerkleTree::<MERKLE_TREE_LEVEL>::path_from_leaves(&leaves, &target, &empty_leaf)
+            .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    let to_string = |fr: &Fr| fr.into_bigint().to_string();
+    let output = CommitmentPath {
+        root: to_string(&root),
+        path_index: index.to_string(),
+        merkle_path: path
+            .path
+            .iter()
+            .map(|(left, right)| [to_string(left), to_string(right)])
+            .collect(),
+    };
+    serde_json::to_string(&output).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize Merkle path: {}", e),
+        )
+    })
+}
+
+/// Checks a local commitment tree against the pool's root and leaf count before proving
+///
+/// # Arguments
+/// * `local_commitments_json` - JSON array of the wallet's decimal leaves in insertion order
+/// * `chain_root` - Root the pool currently reports
+/// * `chain_leaf_count` - Leaves the pool has inserted
+/// * `chain_commitments_json` - JSON array of as much of the chain's commitment history as
+///   is at hand, from leaf 0; `[]` when none
+///
+/// # Returns
+/// JSON `RootConsistency`: `status` (`consistent`, `behind` or `diverged`), the first
+/// mismatching leaf when known and whether to resync
+#[wasm_bindgen(js_name = checkRootConsistency)]
+pub fn check_root_consistency(
+    local_commitments_json: &str,
+    chain_root: &str,
+    chain_leaf_count: f64,
+    chain_commitments_json: &str,
+) -> Result<String, VortexError> {
+    let parse_all = |json: &str| -> Result<Vec<Fr>, VortexError> {
+        let commitments: Vec<String> = serde_json::from_str(json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse commitments JSON: {}", e),
+            )
+        })?;
+        commitments.iter().map(|c| parse_field_element(c)).collect()
+    };
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(
+        &hasher,
+        &parse_field_element(ZERO_VALUE)?,
+    );
+    tree.bulk_insert(&parse_all(local_commitments_json)?, &hasher)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let report = merkle_tree::check_root_consistency(
+        &tree,
+        &parse_field_element(chain_root)?,
+        chain_leaf_count as u64,
+        &parse_all(chain_commitments_json)?,
+        &hasher,
+    );
+    serde_json::to_string(&report).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize root consistency report: {}", e),
+        )
+    })
+}
+
+/// Issues a receipt disclosing one paid output
+///
+/// # Arguments
+/// * `note_json` - The output's `Note`
+/// * `recipient` - Shielded address it paid
+/// * `leaf_index` - Leaf the output was inserted at
+/// * `commitments_json` - JSON array of the synced tree's decimal leaves
+/// * `vortex` - Pool id as a field element
+/// * `tx_digest` - Digest of the paying transaction, if known
+/// * `spending_key` - Signs the receipt with the payer's login key when given
+///
+/// # Returns
+/// `PaymentReceipt` JSON
+#[wasm_bindgen(js_name = createPaymentReceipt)]
+pub fn create_payment_receipt(
+    note_json: &str,
+    recipient: &str,
+    leaf_index: f64,
+    commitments_json: &str,
+    vortex: &str,
+    tx_digest: Option<String>,
+    spending_key: Option<String>,
+) -> Result<String, VortexError> {
+    let note: Note = serde_json::from_str(note_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse note JSON: {}", e),
+        )
+    })?;
+    let spending_key = spending_key
+        .as_deref()
+        .map(parse_field_element)
+        .transpose()?
+        .map(Zeroizing::new);
+    let commitments: Vec<String> = serde_json::from_str(commitments_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse commitments JSON: {}", e),
+        )
+    })?;
+    let leaves = commitments
+        .iter()
+        .map(|c| parse_field_element(c))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(
+        &hasher,
+        &parse_field_element(ZERO_VALUE)?,
+    );
+    tree.bulk_insert(&leaves, &hasher)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let receipt = receipt::create_payment_receipt(
+        &note,
+        recipient,
+        leaf_index as u64,
+        &tree,
+        &parse_field_element(vortex)?,
+        tx_digest,
+        spending_key.as_deref(),
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&receipt).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize receipt: {}", e),
+        )
+    })
+}
+
+/// Verifies a payment receipt's opening, Merkle path and signature
+///
+/// # Returns
+/// Nothing; throws naming the failed check. Callers still check the receipt's `root` against
+/// the pool's root history and its `recipient` against their own address
+#[wasm_bindgen(js_name = verifyPaymentReceipt)]
+pub fn verify_payment_receipt(receipt_json: &str) -> Result<(), VortexError> {
+    let receipt: PaymentReceipt = serde_json::from_str(receipt_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse receipt JSON: {}", e),
+        )
+    })?;
+    receipt::verify_payment_receipt(&receipt)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))
+}
+
+/// Plans the chain of 2-in-2-out transactions paying every recipient
+///
+/// # Arguments
+/// * `notes_json` - JSON array of the wallet's `Utxo`s
+/// * `recipients_json` - JSON array of `{ address, amount }`
+///
+/// # Returns
+/// `SendPlan` JSON: `steps` in submission order, the `total` paid and the final `change`
+#[wasm_bindgen(js_name = planMultiSend)]
+pub fn plan_multi_send(notes_json: &str, recipients_json: &str) -> Result<String, VortexError> {
+    let (notes, recipients) = parse_multi_send(notes_json, recipients_json)?;
+    let plan = multi_send::plan_multi_send(&notes, &recipients)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&plan).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize send plan: {}", e),
+        )
+    })
+}
+
+/// Builds the proof inputs of every step of a send plan
+///
+/// # Arguments
+/// * `plan_json` - `SendPlan` from `planMultiSend()`
+/// * `notes_json` - The `Utxo`s the plan was made from
+/// * `recipients_json` - The recipients the plan was made for
+/// * `spend_seed_hex` - 32-byte spend seed owning the notes
+/// * `commitments_json` - JSON array of the synced tree's decimal leaves
+/// * `vortex` - Pool id as a field element
+/// * `account_secret` - Proves the account secret when given
+///
+/// # Returns
+/// JSON array of `{ proofInput, extData, outputs, change }` in submission order; the inputs
+/// hold only if no other transaction lands between the steps
+#[wasm_bindgen(js_name = buildMultiSend)]
+pub fn build_multi_send(
+    plan_json: &str,
+    notes_json: &str,
+    recipients_json: &str,
+    spend_seed_hex: &str,
+    commitments_json: &str,
+    vortex: &str,
+    account_secret: Option<String>,
+) -> Result<String, VortexError> {
+    let plan: SendPlan = serde_json::from_str(plan_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse send plan: {}", e),
+        )
+    })?;
+    let (notes, recipients) = parse_multi_send(notes_json, recipients_json)?;
+    let seed = viewing::decode_key_hex(spend_seed_hex, "spend seed")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let keys = SpendingKeys::from_seed(&seed)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let account_secret = account_secret
+        .map(|secret| AccountSecret::parse(&secret))
+        .transpose()
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let commitments: Vec<String> = serde_json::from_str(commitments_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse commitments JSON: {}", e),
+        )
+    })?;
+    let leaves = commitments
+        .iter()
+        .map(|c| parse_field_element(c))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(
+        &hasher,
+        &parse_field_element(ZERO_VALUE)?,
+    );
+    tree.bulk_insert(&leaves, &hasher)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let transactions = multi_send::build_multi_send(
+        &plan,
+        &notes,
+        &recipients,
+        &keys,
+        &tree,
+        &parse_field_element(vortex)?,
+        account_secret.as_ref(),
+        &mut OsRng,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&transactions).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize send transactions: {}", e),
+        )
+    })
+}
+
+/// Plans the merge of the wallet's unspent dust notes
+///
+/// # Arguments
+/// * `notes_json` - JSON array of the wallet's `Utxo`s
+/// * `policy_json` - `{ dustThreshold, minDustNotes, feePerTransaction, maxFeeBps,
+///   maxTransactions }`
+///
+/// # Returns
+/// `ConsolidationPlan` JSON, or `null` when there is too little dust or no step is worth its
+/// fee
+#[wasm_bindgen(js_name = planConsolidation)]
+pub fn plan_consolidation(notes_json: &str, policy_json: &str) -> Result<String, VortexError> {
+    let notes: Vec<Utxo> = serde_json::from_str(notes_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse notes JSON: {}", e),
+        )
+    })?;
+    let policy: ConsolidationPolicy = serde_json::from_str(policy_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse consolidation policy: {}", e),
+        )
+    })?;
+    let plan = consolidation::plan_consolidation(&notes, &policy)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&plan).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize consolidation plan: {}", e),
+        )
+    })
+}
+
+/// Builds the proof inputs of every step of a consolidation plan
+///
+/// # Arguments
+/// * `plan_json` - `ConsolidationPlan` from `planConsolidation()`
+/// * `notes_json` - The `Utxo`s the plan was made from
+/// * `spend_seed_hex` - 32-byte spend seed owning the notes
+/// * `commitments_json` - JSON array of the synced tree's decimal leaves
+/// * `vortex` - Pool id as a field element
+/// * `quote_json` - `RelayerFeeQuote` paid by every step; required when the plan has a fee
+/// * `account_secret` - Proves the account secret when given
+///
+/// # Returns
+/// JSON array of `{ proofInput, extData, outputs, change }` in submission order; the inputs
+/// hold only if no other transaction lands between the steps
+#[wasm_bindgen(js_name = buildConsolidation)]
+pub fn build_consolidation(
+    plan_json: &str,
+    notes_json: &str,
+    spend_seed_hex: &str,
+    commitments_json: &str,
+    vortex: &str,
+    quote_json: Option<String>,
+    account_secret: Option<String>,
+) -> Result<String, VortexError> {
+    let plan: ConsolidationPlan = serde_json::from_str(plan_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse consolidation plan: {}", e),
+        )
+    })?;
+    let notes: Vec<Utxo> = serde_json::from_str(notes_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse notes JSON: {}", e),
+        )
+    })?;
+    let quote: Option<RelayerFeeQuote> = quote_json
+        .map(|quote| serde_json::from_str(&quote))
+        .transpose()
+        .map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse fee quote JSON: {}", e),
+            )
+        })?;
+    let seed = viewing::decode_key_hex(spend_seed_hex, "spend seed")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let keys = SpendingKeys::from_seed(&seed)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    let account_secret = account_secret
+        .map(|secret| AccountSecret::parse(&secret))
+        .transpose()
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let commitments: Vec<String> = serde_json::from_str(commitments_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse commitments JSON: {}", e),
+        )
+    })?;
+    let leaves = commitments
+        .iter()
+        .map(|c| parse_field_element(c))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hasher = poseidon_opt::PoseidonOptimized::new_t3();
+    let mut tree = SparseMerkleTree::<MERKLE_TREE_LEVEL>::new_empty(
+        &hasher,
+        &parse_field_element(ZERO_VALUE)?,
+    );
+    tree.bulk_insert(&leaves, &hasher)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+
+    let transactions = consolidation::build_consolidation(
+        &plan,
+        &notes,
+        &keys,
+        &tree,
+        &parse_field_element(vortex)?,
+        quote.as_ref(),
+        account_secret.as_ref(),
+        &mut OsRng,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&transactions).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize consolidation transactions: {}", e),
+        )
+    })
+}
+
+fn parse_multi_send(
+    notes_json: &str,
+    recipients_json: &str,
+) -> Result<(Vec<Utxo>, Vec<Recipient>), VortexError> {
+    let notes = serde_json::from_str(notes_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse notes JSON: {}", e),
+        )
+    })?;
+    let recipients = serde_json::from_str(recipients_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse recipients JSON: {}", e),
+        )
+    })?;
+    Ok((notes, recipients))
+}
+
+/// Starts a transfer from one pool into another
+///
+/// # Arguments
+/// * `id` - Host-chosen identifier to persist the transfer under
+/// * `source_json` / `target_json` - `{ poolId, coinType }` of each pool
+/// * `amount` - Amount to withdraw from the source pool
+/// * `intermediate_address` - Sui address receiving the withdrawal and paying the deposit
+///
+/// # Returns
+/// `CrossPoolTransfer` JSON to persist and pass to `crossPoolNextAction`
+#[wasm_bindgen(js_name = startCrossPoolTransfer)]
+pub fn start_cross_pool_transfer(
+    id: &str,
+    source_json: &str,
+    target_json: &str,
+    amount: f64,
+    intermediate_address: &str,
+) -> Result<String, VortexError> {
+    let pool = |json: &str| -> Result<PoolRef, VortexError> {
+        serde_json::from_str(json).map_err(|e| {
+            VortexError::new(
+                VortexErrorCode::Parse,
+                format!("Failed to parse pool JSON: {}", e),
+            )
+        })
+    };
+    let transfer = CrossPoolTransfer::new(
+        id.to_string(),
+        pool(source_json)?,
+        pool(target_json)?,
+        safe_amount(amount)?,
+        intermediate_address.to_string(),
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serialize_cross_pool(&transfer)
+}
+
+/// What to do next for a persisted cross-pool transfer
+///
+/// # Returns
+/// `CrossPoolAction` JSON tagged by `action`
+#[wasm_bindgen(js_name = crossPoolNextAction)]
+pub fn cross_pool_next_action(transfer_json: &str) -> Result<String, VortexError> {
+    serialize_cross_pool(&parse_cross_pool(transfer_json)?.next_action())
+}
+
+/// Records the outcome of a cross-pool transfer's last action
+///
+/// # Arguments
+/// * `transfer_json` - The persisted `CrossPoolTransfer`
+/// * `event_json` - `CrossPoolEvent` tagged by `event`
+///
+/// # Returns
+/// The updated `CrossPoolTransfer` JSON to persist; throws and leaves the stored transfer
+/// valid when the event does not fit its stage
+#[wasm_bindgen(js_name = applyCrossPoolEvent)]
+pub fn apply_cross_pool_event(
+    transfer_json: &str,
+    event_json: &str,
+) -> Result<String, VortexError> {
+    let mut transfer = parse_cross_pool(transfer_json)?;
+    let event: CrossPoolEvent = serde_json::from_str(event_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse cross-pool event JSON: {}", e),
+        )
+    })?;
+    transfer
+        .apply(event)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serialize_cross_pool(&transfer)
+}
+
+fn parse_cross_pool(transfer_json: &str) -> Result<CrossPoolTransfer, VortexError> {
+    serde_json::from_str(transfer_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse cross-pool transfer JSON: {}", e),
+        )
+    })
+}
+
+fn serialize_cross_pool<T: serde::Serialize>(value: &T) -> Result<String, VortexError> {
+    serde_json::to_string(value).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize cross-pool transfer: {}", e),
+        )
+    })
+}
+
+/// Public key for "log in with your shielded account", derived from the spending key
+///
+/// # Arguments
+/// * `spending_key` - The wallet's spending key (`inPrivateKey` of its notes)
+///
+/// # Returns
+/// Hex-encoded 32-byte public key to register with the dApp
+#[wasm_bindgen(js_name = authPublicKey)]
+pub fn auth_public_key(spending_key: &str) -> Result<String, VortexError> {
+    let spending_key = Zeroizing::new(parse_field_element(spending_key)?);
+    Ok(hex::encode(auth::auth_public_key(&spending_key)))
+}
+
+/// Signs a dApp login challenge with the key derived from the spending key
+///
+/// # Arguments
+/// * `spending_key` - The wallet's spending key
+/// * `challenge` - Challenge message sent by the dApp
+///
+/// # Returns
+/// Hex-encoded 64-byte signature
+#[wasm_bindgen(js_name = signChallenge)]
+pub fn sign_challenge(spending_key: &str, challenge: &str) -> Result<String, VortexError> {
+    let spending_key = Zeroizing::new(parse_field_element(spending_key)?);
+    auth::sign_challenge(&spending_key, challenge.as_bytes())
+        .map(hex::encode)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))
+}
+
+/// Verifies a login challenge signature
+///
+/// # Arguments
+/// * `public_key_hex` - Key from `authPublicKey()`
+/// * `challenge` - The challenge message that was signed
+/// * `signature_hex` - Signature from `signChallenge()`
+///
+/// # Returns
+/// `true` if the signature is valid for this key and challenge
+#[wasm_bindgen(js_name = verifyChallengeSignature)]
+pub fn verify_challenge_signature(
+    public_key_hex: &str,
+    challenge: &str,
+    signature_hex: &str,
+) -> Result<bool, VortexError> {
+    let public_key = hex::decode(public_key_hex.trim_start_matches("0x")).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode public key hex: {}", e),
+        )
+    })?;
+    let signature = hex::decode(signature_hex.trim_start_matches("0x")).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to decode signature hex: {}", e),
+        )
+    })?;
+    auth::verify_challenge(&public_key, challenge.as_bytes(), &signature)
+        .map_err(|e| VortexError::new(VortexErrorCode::Verify, e.to_string()))
+}
+
+/// Dry-runs a transaction: selects notes and derives what its proof will publish
+///
+/// # Arguments
+/// * `intent_json` - `TransactionIntent` JSON with the wallet's notes and output blindings
+/// * `private_key` - Key the notes were created under
+/// * `vortex` - Pool the transaction targets
+///
+/// # Returns
+/// `DryRunReport` JSON with the selected notes, nullifiers, output commitments and amounts
+#[wasm_bindgen(js_name = dryRun)]
+pub fn dry_run(intent_json: &str, private_key: &str, vortex: &str) -> Result<String, VortexError> {
+    let intent: TransactionIntent = serde_json::from_str(intent_json).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Parse,
+            format!("Failed to parse intent JSON: {}", e),
+        )
+    })?;
+    let private_key = Zeroizing::new(parse_field_element(private_key)?);
+    let report = dry_run::dry_run(&intent, &private_key, &parse_field_element(vortex)?)
+        .map_err(|e| VortexError::new(VortexErrorCode::Input, e.to_string()))?;
+    serde_json::to_string(&report).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize dry run report: {}", e),
+        )
+    })
+}
+
+/// Builds the record to register when rotating a note key to a new one
+///
+/// # Arguments
+/// * `previous_private_key` - Key being rotated out, e.g. of a compromised device
+/// * `new_private_key` - Successor key
+/// * `now_ms` - Current time, e.g. `Date.now()`
+/// * `grace_period_ms` - How long the previous key alone may still spend
+///
+/// # Returns
+/// `KeyRotation` JSON
+#[wasm_bindgen(js_name = createKeyRotation)]
+pub fn create_key_rotation(
+    previous_private_key: &str,
+    new_private_key: &str,
+    now_ms: f64,
+    grace_period_ms: f64,
+) -> Result<String, VortexError> {
+    let previous_private_key = Zeroizing::new(parse_field_element(previous_private_key)?);
+    let new_private_key = Zeroizing::new(parse_field_element(new_private_key)?);
+    let key_rotation = rotation::rotate_key(
+        &previous_private_key,
+        &new_private_key,
+        now_ms as u64,
+        grace_period_ms as u64,
+    )
+    .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    serde_json::to_string(&key_rotation).map_err(|e| {
+        VortexError::new(
+            VortexErrorCode::Serialization,
+            format!("Failed to serialize key rotation: {}", e),
+        )
+    })
+}
+
+/// Computes the authority tree leaf a key rotation circuit proves membership of
+///
+/// # Arguments
+/// * `private_key` - Key the spent notes were created under
+/// * `rotated_private_key` - Rotated-in key authorizing the spend, if any
+///
+/// # Returns
+/// Decimal field element to look up in the pool's authority tree for `inAuthorityIndex`
+/// and `authorityPath`
+#[wasm_bindgen(js_name = computeAuthorityLeaf)]
+pub fn compute_authority_leaf(
+    private_key: &str,
+    rotated_private_key: Option<String>,
+) -> Result<String, VortexError> {
+    let private_key = Zeroizing::new(parse_field_element(private_key)?);
+    let rotated_private_key = rotated_private_key
+        .as_deref()
+        .map(parse_field_element)
+        .transpose()?
+        .map(Zeroizing::new);
+    Ok(
+        rotation::authority_leaf(&private_key, rotated_private_key.as_deref())
+            .into_bigint()
+            .to_string(),
+    )
+}
+
+// Helper functions
+fn parse_field_element(s: &str) -> Result<Fr, VortexError> {
+    parse_field_element_with(s, FieldParseMode::Strict)
+}
+
+fn parse_field_element_with(s: &str, mode: FieldParseMode) -> Result<Fr, VortexError> {
+    // Handles both decimal and 0x-prefixed hex strings
+    field::parse_fr(s, mode).map_err(|e| VortexError::new(VortexErrorCode::Parse, e.to_string()))
+}
+
+fn parse_full_viewing_key(hex_str: &str) -> Result<FullViewingKey, VortexError> {
+    let bytes = viewing::decode_key_hex(hex_str, "full viewing key")
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))?;
+    FullViewingKey::from_bytes(&bytes)
+        .map_err(|e| VortexError::new(VortexErrorCode::Key, e.to_string()))
+}
+
+/// Parses an authority path; empty when the circuit has no key rotation
+fn parse_authority_path(
+    path_data: &[[String; 2]],
+    mode: FieldParseMode,
+) -> Result<Path<AUTHORITY_TREE_LEVEL>, VortexError> {
+    if path_data.is_empty() {
+        return Ok(Path::empty());
+    }
+    parse_merkle_path(path_data, mode)
+}
+
+fn parse_merkle_path<const N: usize>(
+    path_data: &[[String; 2]],
+    mode: FieldParseMode,
+) -> Result<Path<N>, VortexError> {
+    if path_data.len() != N {
+        return Err(VortexError::new(
+            VortexErrorCode::Input,
+            format!(
+                "Invalid Merkle path length: expected {}, got {}",
+                N,
+                path_data.len()
+            ),
+        ));
+    }
+
+    let mut path = [(Fr::from(0u64), Fr::from(0u64)); N];
+
+    for (i, pair) in path_data.iter().enumerate() {
+        let left = parse_field_element_with(&pair[0], mode)?;
+        let right = parse_field_element_with(&pair[1], mode)?;
+        path[i] = (left, right);
+    }
+
+    Ok(Path { path })
+}
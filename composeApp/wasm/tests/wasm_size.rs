@@ -0,0 +1,43 @@
+//! Size budget of the wasm bundle, which web wallets download before they can prove.
+//!
+//! Checks the artifact built as described in the README (`pkg/`), or the file named by
+//! `VORTEX_WASM_PATH`. Skipped when none has been built, so native-only runs are unaffected.
+
+use std::path::PathBuf;
+
+/// Raise deliberately, with the reason in the commit, when a feature needs it
+const WASM_SIZE_BUDGET_BYTES: u64 = 4 * 1024 * 1024;
+
+fn artifact() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("VORTEX_WASM_PATH") {
+        return Some(PathBuf::from(path));
+    }
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("pkg")
+        .join("vortex_bg.wasm");
+    path.exists().then_some(path)
+}
+
+#[test]
+fn test_wasm_bundle_fits_budget_and_excludes_native_code() {
+    let Some(path) = artifact() else {
+        eprintln!("no wasm artifact built, skipping");
+        return;
+    };
+    let bytes = std::fs::read(&path).unwrap();
+    let size = bytes.len() as u64;
+    eprintln!("{}: {} bytes", path.display(), size);
+    assert!(
+        size <= WASM_SIZE_BUDGET_BYTES,
+        "{} is {} bytes, over the {} byte budget",
+        path.display(),
+        size,
+        WASM_SIZE_BUDGET_BYTES
+    );
+    // uniffi scaffolding exports are named `uniffi_vortex_*`
+    assert!(
+        !bytes.windows(7).any(|w| w == b"uniffi_"),
+        "{} contains uniffi scaffolding; vortex-core's `uniffi` feature leaked into the build",
+        path.display()
+    );
+}